@@ -2,8 +2,10 @@
 
 use wasm_bindgen::prelude::*;
 
+use apriltag::{Detector, DetectorBuffers, DetectorBuilder, DetectorConfig};
 use apriltag_bench::distortion::{self, Distortion};
-use apriltag_bench::scene::{Background, SceneBuilder};
+use apriltag_bench::metrics;
+use apriltag_bench::scene::{Background, PlacedTag, Scene, SceneBuilder};
 use apriltag_bench::transform::Transform;
 
 /// Generate a scene with a single tag and return the image data + ground truth.
@@ -28,68 +30,267 @@ pub fn generate_scene(
     blur_sigma: f64,
     contrast: f64,
 ) -> Result<JsValue, JsError> {
-    let cx = width as f64 / 2.0;
-    let cy = height as f64 / 2.0;
+    let scene = build_scene(&SceneParams {
+        width,
+        height,
+        family: family.to_string(),
+        tag_id,
+        tag_size,
+        rotation: rotation_deg,
+        tilt_x: tilt_x_deg,
+        tilt_y: tilt_y_deg,
+        noise: noise_sigma,
+        blur: blur_sigma,
+        contrast,
+    })
+    .map_err(|e| JsError::new(&e))?;
 
-    let transform = if tilt_x_deg.abs() > 0.01 || tilt_y_deg.abs() > 0.01 {
+    // Build result object
+    let result = SceneResult {
+        width: scene.image.width,
+        height: scene.image.height,
+        stride: scene.image.stride,
+        ground_truth: ground_truth(&scene.ground_truth),
+        image_data: scene.image.buf,
+    };
+
+    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// A single-tag scene, as set up by the explorer's sliders. Angles are in
+/// degrees; missing fields take the explorer's defaults.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SceneParams {
+    width: u32,
+    height: u32,
+    family: String,
+    tag_id: u32,
+    tag_size: f64,
+    rotation: f64,
+    tilt_x: f64,
+    tilt_y: f64,
+    noise: f64,
+    blur: f64,
+    contrast: f64,
+}
+
+impl Default for SceneParams {
+    fn default() -> Self {
+        Self {
+            width: 500,
+            height: 500,
+            family: "tag36h11".to_string(),
+            tag_id: 0,
+            tag_size: 120.0,
+            rotation: 0.0,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            noise: 0.0,
+            blur: 0.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+/// Render the tag centered on a gray background and apply the distortions.
+fn build_scene(p: &SceneParams) -> Result<Scene, String> {
+    let cx = p.width as f64 / 2.0;
+    let cy = p.height as f64 / 2.0;
+
+    let transform = if p.tilt_x.abs() > 0.01 || p.tilt_y.abs() > 0.01 {
         Transform::FromPose {
             center: [cx, cy],
-            size: tag_size,
-            roll: rotation_deg.to_radians(),
-            tilt_x: tilt_x_deg.to_radians(),
-            tilt_y: tilt_y_deg.to_radians(),
+            size: p.tag_size,
+            roll: p.rotation.to_radians(),
+            tilt_x: p.tilt_x.to_radians(),
+            tilt_y: p.tilt_y.to_radians(),
         }
     } else {
         Transform::Similarity {
             cx,
             cy,
-            scale: tag_size / 2.0,
-            theta: rotation_deg.to_radians(),
+            scale: p.tag_size / 2.0,
+            theta: p.rotation.to_radians(),
         }
     };
 
-    let mut scene = SceneBuilder::new(width, height)
+    let mut scene = SceneBuilder::new(p.width, p.height)
         .background(Background::Solid(128))
-        .add_tag(family, tag_id, transform)
-        .build();
+        .add_tag(&p.family, p.tag_id, transform)
+        .try_build()
+        .map_err(|e| e.to_string())?;
 
     // Apply distortions
     let mut distortions = Vec::new();
-    if contrast != 1.0 {
-        distortions.push(Distortion::ContrastScale { factor: contrast });
+    if p.contrast != 1.0 {
+        distortions.push(Distortion::ContrastScale { factor: p.contrast });
     }
-    if blur_sigma > 0.0 {
-        distortions.push(Distortion::GaussianBlur { sigma: blur_sigma });
+    if p.blur > 0.0 {
+        distortions.push(Distortion::GaussianBlur { sigma: p.blur });
     }
-    if noise_sigma > 0.0 {
+    if p.noise > 0.0 {
         distortions.push(Distortion::GaussianNoise {
-            sigma: noise_sigma,
+            sigma: p.noise,
             seed: 42,
         });
     }
     if !distortions.is_empty() {
         distortion::apply(&mut scene.image, &distortions);
     }
+    Ok(scene)
+}
 
-    // Build result object
-    let result = SceneResult {
-        width: scene.image.width,
-        height: scene.image.height,
-        stride: scene.image.stride,
-        image_data: scene.image.buf,
-        ground_truth: scene
-            .ground_truth
+fn ground_truth(tags: &[PlacedTag]) -> Vec<GroundTruthTag> {
+    tags.iter()
+        .map(|gt| GroundTruthTag {
+            family_name: gt.family_name.clone(),
+            tag_id: gt.tag_id,
+            corners: gt.corners,
+            center: gt.center,
+        })
+        .collect()
+}
+
+/// Detector setup for a [`SceneRunner`].
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunnerConfig {
+    /// Families to detect.
+    families: Vec<String>,
+    /// Maximum Hamming distance for matching (default: 2).
+    #[serde(default)]
+    max_hamming: Option<u32>,
+    /// Detector configuration with the core field names (`quad_decimate`,
+    /// `quad_sigma`, `quad.min_cluster_pixels`, ...); missing fields keep
+    /// their defaults.
+    #[serde(default)]
+    detector: DetectorConfig,
+}
+
+/// Generates scenes, detects them and scores the detections against the
+/// ground truth, all in one call per frame, for tuning the detector
+/// interactively.
+///
+/// ```js
+/// const runner = new SceneRunner({
+///   families: ["tag36h11"],
+///   detector: { quad_decimate: 1.0, quad: { min_white_black_diff: 10 } },
+/// });
+/// const { detections, metrics } = runner.run({ tagSize: 60, blur: 1.5 });
+/// ```
+///
+/// Building a detector builds its families' lookup tables, so keep a runner
+/// for as long as its configuration holds and create a new one when it
+/// changes.
+#[wasm_bindgen]
+pub struct SceneRunner {
+    detector: Detector,
+    buffers: DetectorBuffers,
+}
+
+#[wasm_bindgen]
+impl SceneRunner {
+    /// Build a runner from `{ families, maxHamming?, detector? }`, where
+    /// `detector` is a partial `DetectorConfig`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: JsValue) -> Result<SceneRunner, JsError> {
+        let config: RunnerConfig =
+            serde_wasm_bindgen::from_value(config).map_err(|e| JsError::new(&e.to_string()))?;
+        let mut detector = config.detector;
+        // There is no thread pool to run on in the browser
+        detector.num_threads = Some(1);
+        let max_hamming = config.max_hamming.unwrap_or(2);
+        let detector = config
+            .families
             .iter()
-            .map(|gt| GroundTruthTag {
-                family_name: gt.family_name.clone(),
-                tag_id: gt.tag_id,
-                corners: gt.corners,
-                center: gt.center,
+            .fold(DetectorBuilder::with_config(detector), |b, family| {
+                b.family(family, max_hamming)
             })
-            .collect(),
-    };
+            .try_build()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Self {
+            detector,
+            buffers: DetectorBuffers::new(),
+        })
+    }
 
-    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+    /// Generate the scene described by `scene` (the fields of
+    /// `generateScene`, in camelCase), detect it, and return the image,
+    /// ground truth, detections with their corner errors, and metrics.
+    pub fn run(&mut self, scene: JsValue) -> Result<JsValue, JsError> {
+        let params: SceneParams =
+            serde_wasm_bindgen::from_value(scene).map_err(|e| JsError::new(&e.to_string()))?;
+
+        let start = now_ms();
+        let scene = build_scene(&params).map_err(|e| JsError::new(&e))?;
+        let gen_ms = now_ms() - start;
+
+        let start = now_ms();
+        let detections = self.detector.detect(&scene.image, &mut self.buffers);
+        let det_ms = now_ms() - start;
+
+        let result = metrics::evaluate(&scene.ground_truth, &detections, (det_ms * 1000.0) as u64);
+        let matched = result.matches.iter().filter_map(|m| {
+            let d = m.detection.as_ref()?;
+            Some(RunDetection::new(d, m.corner_errors))
+        });
+        let false_positives = result
+            .false_positives
+            .iter()
+            .map(|d| RunDetection::new(d, None));
+        let detections: Vec<RunDetection> = matched.chain(false_positives).collect();
+        let detected = result
+            .matches
+            .iter()
+            .filter(|m| m.detection.is_some())
+            .count();
+
+        let out = RunResult {
+            width: scene.image.width,
+            height: scene.image.height,
+            stride: scene.image.stride,
+            ground_truth: ground_truth(&scene.ground_truth),
+            image_data: scene.image.buf,
+            detections,
+            metrics: RunMetrics {
+                expected: scene.ground_truth.len(),
+                detected,
+                false_positives: result.false_positives.len(),
+                detection_rate: result.detection_rate,
+                corner_rmse: (detected > 0).then_some(result.corner_rmse),
+                max_corner_error: (detected > 0).then_some(result.max_corner_error),
+                mean_corner_error: (detected > 0).then_some(result.mean_corner_error),
+                gen_time_ms: gen_ms,
+                det_time_ms: det_ms,
+            },
+        };
+        serde_wasm_bindgen::to_value(&out).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+/// Milliseconds from an arbitrary fixed origin, for measuring durations.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .filter(|p| !p.is_undefined())
+        .and_then(|p| {
+            let now: js_sys::Function = js_sys::Reflect::get(&p, &"now".into())
+                .ok()?
+                .dyn_into()
+                .ok()?;
+            now.call0(&p).ok()?.as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
 }
 
 #[derive(serde::Serialize)]
@@ -112,3 +313,61 @@ struct GroundTruthTag {
     corners: [[f64; 2]; 4],
     center: [f64; 2],
 }
+
+/// A scene from [`SceneRunner::run`] with its detections and metrics.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunResult {
+    width: u32,
+    height: u32,
+    stride: u32,
+    image_data: Vec<u8>,
+    ground_truth: Vec<GroundTruthTag>,
+    /// Detections matched to a ground-truth tag first, then false positives.
+    detections: Vec<RunDetection>,
+    metrics: RunMetrics,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunDetection {
+    family: String,
+    id: i32,
+    hamming: i32,
+    decision_margin: f32,
+    center: [f64; 2],
+    corners: [[f64; 2]; 4],
+    /// Distance of each corner from its ground truth in pixels, or `None`
+    /// for a false positive.
+    corner_errors: Option<[f64; 4]>,
+}
+
+impl RunDetection {
+    fn new(d: &apriltag::Detection, corner_errors: Option<[f64; 4]>) -> Self {
+        Self {
+            family: d.family_id.to_string(),
+            id: d.id,
+            hamming: d.hamming,
+            decision_margin: d.decision_margin,
+            center: d.center.into(),
+            corners: d.corners.map(Into::into),
+            corner_errors,
+        }
+    }
+}
+
+/// Scores of one scene, as computed by [`metrics::evaluate`]. Corner errors
+/// are `None` when no tag was detected.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunMetrics {
+    expected: usize,
+    detected: usize,
+    false_positives: usize,
+    detection_rate: f64,
+    corner_rmse: Option<f64>,
+    max_corner_error: Option<f64>,
+    mean_corner_error: Option<f64>,
+    gen_time_ms: f64,
+    det_time_ms: f64,
+}
@@ -1,10 +1,23 @@
 fn main() {
+    // Recorded in benchmark JSON output; see `environment.rs`
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if let Some(version) = command_output(std::process::Command::new(rustc).arg("--version")) {
+        println!("cargo:rustc-env=APRILTAG_BENCH_RUSTC={version}");
+    }
+
     #[cfg(feature = "reference")]
     {
         build_reference();
     }
 }
 
+/// Trimmed stdout of a successful command.
+fn command_output(command: &mut std::process::Command) -> Option<String> {
+    let out = command.output().ok()?;
+    let text = String::from_utf8(out.stdout).ok()?.trim().to_string();
+    (out.status.success() && !text.is_empty()).then_some(text)
+}
+
 #[cfg(feature = "reference")]
 fn build_reference() {
     let ref_dir = std::path::Path::new("../docs/reference-detection");
@@ -75,6 +88,13 @@ fn build_reference() {
 
     build.compile("apriltag_reference");
 
+    let mut git = std::process::Command::new("git");
+    git.arg("-C").arg(ref_dir).args(["rev-parse", "HEAD"]);
+    if let Some(commit) = command_output(&mut git) {
+        println!("cargo:rustc-env=APRILTAG_REFERENCE_COMMIT={commit}");
+    }
+    println!("cargo:rerun-if-changed=../docs/reference-detection/.git/HEAD");
+
     println!("cargo:rustc-link-lib=static=apriltag_reference");
     println!("cargo:rustc-link-lib=pthread");
     println!("cargo:rustc-link-lib=m");
@@ -0,0 +1,290 @@
+/// Pinhole camera model for projecting pose-placed tags into a scene.
+///
+/// A [`Camera`] turns the ergonomic [`Transform::FromPose`](crate::transform::Transform::FromPose)
+/// description (image center, apparent size, roll, tilts) into a physically
+/// consistent projection: the tag is placed in 3D in front of the camera and
+/// projected through the intrinsics `K`, with optional radial lens distortion.
+/// The resulting ground-truth pose is exactly the one a pose estimator given
+/// the same intrinsics should recover.
+use apriltag::detect::geometry::{Mat3, Vec3};
+use apriltag::detect::pose::PoseParams;
+use serde::{Deserialize, Serialize};
+
+/// Radial lens distortion (Brown–Conrady, radial terms only).
+///
+/// Normalized coordinates are scaled by `1 + k1·r² + k2·r⁴`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LensDistortion {
+    pub k1: f64,
+    pub k2: f64,
+}
+
+/// Pinhole camera intrinsics with optional lens distortion.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    /// Focal length in pixels along x.
+    pub fx: f64,
+    /// Focal length in pixels along y.
+    pub fy: f64,
+    /// Principal point x in pixels.
+    pub cx: f64,
+    /// Principal point y in pixels.
+    pub cy: f64,
+    /// Optional radial distortion applied after the pinhole projection.
+    #[serde(default)]
+    pub distortion: Option<LensDistortion>,
+}
+
+/// A tag placed in 3D in front of a [`Camera`].
+#[derive(Debug, Clone)]
+pub struct CameraPlacement {
+    /// Undistorted homography mapping tag-space [-1, 1]² to pixels (row-major).
+    pub h: [f64; 9],
+    /// Ground-truth rotation in the pose estimator's tag frame (y-up).
+    pub rotation: Mat3,
+    /// Ground-truth translation in tag half-size units (tagsize = 2).
+    pub translation: Vec3,
+}
+
+impl Camera {
+    /// Create a distortion-free camera with the given intrinsics.
+    pub fn new(fx: f64, fy: f64, cx: f64, cy: f64) -> Self {
+        Self {
+            fx,
+            fy,
+            cx,
+            cy,
+            distortion: None,
+        }
+    }
+
+    /// Create a camera with square pixels and the principal point at the image center.
+    pub fn centered(width: u32, height: u32, focal_length: f64) -> Self {
+        Self::new(
+            focal_length,
+            focal_length,
+            width as f64 / 2.0,
+            height as f64 / 2.0,
+        )
+    }
+
+    /// Add radial lens distortion.
+    pub fn with_distortion(mut self, k1: f64, k2: f64) -> Self {
+        self.distortion = Some(LensDistortion { k1, k2 });
+        self
+    }
+
+    /// Pose estimation parameters matching this camera, in tag-space units.
+    pub fn pose_params(&self) -> PoseParams {
+        PoseParams {
+            tagsize: 2.0,
+            fx: self.fx,
+            fy: self.fy,
+            cx: self.cx,
+            cy: self.cy,
+            solver: Default::default(),
+        }
+    }
+
+    /// Place a tag so that its center projects to `center` and, when viewed
+    /// fronto-parallel, it spans `size` pixels horizontally.
+    ///
+    /// Rotation is `Rz(roll) · Ry(tilt_x) · Rx(tilt_y)`, matching
+    /// [`Transform::FromPose`](crate::transform::Transform::FromPose).
+    pub fn place(
+        &self,
+        center: [f64; 2],
+        size: f64,
+        roll: f64,
+        tilt_x: f64,
+        tilt_y: f64,
+    ) -> CameraPlacement {
+        let (r0, r1) = pose_rotation_columns(roll, tilt_x, tilt_y);
+
+        // Depth at which a 2-unit tag appears `size` pixels wide.
+        let z = 2.0 * self.fx / size;
+        let [ux, uy] = self.undistort(center);
+        let t = Vec3::new(
+            z * (ux - self.cx) / self.fx,
+            z * (uy - self.cy) / self.fy,
+            z,
+        );
+
+        // H = K · [r0 | r1 | t], normalized so that H[2][2] = 1.
+        let k = |v: Vec3| {
+            Vec3::new(
+                (self.fx * v[0] + self.cx * v[2]) / z,
+                (self.fy * v[1] + self.cy * v[2]) / z,
+                v[2] / z,
+            )
+        };
+        let (h0, h1, h2) = (k(r0), k(r1), k(t));
+        let h = [
+            h0[0], h1[0], h2[0], h0[1], h1[1], h2[1], h0[2], h1[2], h2[2],
+        ];
+
+        // Bench tag-space is y-down while the pose estimator's tag frame is
+        // y-up, so the estimator sees R · diag(1, -1, -1).
+        let r1_gt = -r1;
+        let r2_gt = r0.cross(r1_gt);
+        let rotation = Mat3([
+            [r0[0], r1_gt[0], r2_gt[0]],
+            [r0[1], r1_gt[1], r2_gt[1]],
+            [r0[2], r1_gt[2], r2_gt[2]],
+        ]);
+
+        CameraPlacement {
+            h,
+            rotation,
+            translation: t,
+        }
+    }
+
+    /// Map an undistorted pixel position to its distorted position.
+    pub fn distort(&self, p: [f64; 2]) -> [f64; 2] {
+        let Some(d) = self.distortion else {
+            return p;
+        };
+        let xn = (p[0] - self.cx) / self.fx;
+        let yn = (p[1] - self.cy) / self.fy;
+        let r2 = xn * xn + yn * yn;
+        let factor = 1.0 + d.k1 * r2 + d.k2 * r2 * r2;
+        [
+            self.cx + self.fx * xn * factor,
+            self.cy + self.fy * yn * factor,
+        ]
+    }
+
+    /// Map a distorted pixel position back to its undistorted position.
+    ///
+    /// Uses fixed-point iteration, which converges for the mild distortion
+    /// coefficients found in real lenses.
+    pub fn undistort(&self, p: [f64; 2]) -> [f64; 2] {
+        let Some(d) = self.distortion else {
+            return p;
+        };
+        let xd = (p[0] - self.cx) / self.fx;
+        let yd = (p[1] - self.cy) / self.fy;
+        let (mut xn, mut yn) = (xd, yd);
+        for _ in 0..20 {
+            let r2 = xn * xn + yn * yn;
+            let factor = 1.0 + d.k1 * r2 + d.k2 * r2 * r2;
+            xn = xd / factor;
+            yn = yd / factor;
+        }
+        [self.cx + self.fx * xn, self.cy + self.fy * yn]
+    }
+}
+
+/// First two columns of `Rz(roll) · Ry(tilt_x) · Rx(tilt_y)`.
+pub(crate) fn pose_rotation_columns(roll: f64, tilt_x: f64, tilt_y: f64) -> (Vec3, Vec3) {
+    let (sr, cr) = roll.sin_cos();
+    let (sx, cx) = tilt_x.sin_cos();
+    let (sy, cy) = tilt_y.sin_cos();
+    let r0 = Vec3::new(cr * cx, sr * cx, -sx);
+    let r1 = Vec3::new(cr * sx * sy - sr * cy, sr * sx * sy + cr * cy, cx * sy);
+    (r0, r1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_h(h: &[f64; 9], tx: f64, ty: f64) -> [f64; 2] {
+        let w = h[6] * tx + h[7] * ty + h[8];
+        [
+            (h[0] * tx + h[1] * ty + h[2]) / w,
+            (h[3] * tx + h[4] * ty + h[5]) / w,
+        ]
+    }
+
+    #[test]
+    fn frontal_placement_is_a_square_of_requested_size() {
+        let cam = Camera::centered(640, 480, 500.0);
+        let p = cam.place([320.0, 240.0], 100.0, 0.0, 0.0, 0.0);
+        let tl = apply_h(&p.h, -1.0, -1.0);
+        let br = apply_h(&p.h, 1.0, 1.0);
+        assert!((tl[0] - 270.0).abs() < 1e-9 && (tl[1] - 190.0).abs() < 1e-9);
+        assert!((br[0] - 370.0).abs() < 1e-9 && (br[1] - 290.0).abs() < 1e-9);
+        // t_z = 2 * fx / size = 10 tag half-units
+        assert!((p.translation[2] - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn off_center_placement_projects_center() {
+        let cam = Camera::new(600.0, 580.0, 300.0, 200.0);
+        let p = cam.place([450.0, 120.0], 80.0, 0.4, 0.3, -0.2);
+        let c = apply_h(&p.h, 0.0, 0.0);
+        assert!((c[0] - 450.0).abs() < 1e-9);
+        assert!((c[1] - 120.0).abs() < 1e-9);
+        assert!(p.translation[0] > 0.0);
+        assert!(p.translation[1] < 0.0);
+    }
+
+    #[test]
+    fn placement_rotation_is_orthonormal() {
+        let cam = Camera::centered(500, 500, 400.0);
+        let r = cam.place([250.0, 250.0], 100.0, 0.7, 0.5, 0.2).rotation;
+        let rrt = r * r.transpose();
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((rrt.0[i][j] - expected).abs() < 1e-9);
+            }
+        }
+        assert!((r.det() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn placement_homography_decomposes_to_requested_view() {
+        use apriltag::detect::homography::Homography;
+
+        let cam = Camera::new(600.0, 590.0, 310.0, 250.0);
+        for (center, size, roll, tilt_x, tilt_y) in [
+            ([310.0, 250.0], 100.0, 0.0, 0.0, 0.0),
+            ([450.0, 120.0], 80.0, 0.4, 0.3, -0.2),
+            ([200.0, 300.0], 60.0, -1.2, -0.9, 0.5),
+        ] {
+            let p = cam.place(center, size, roll, tilt_x, tilt_y);
+            let h = Homography {
+                data: Mat3(std::array::from_fn(|i| {
+                    [p.h[3 * i], p.h[3 * i + 1], p.h[3 * i + 2]]
+                })),
+            };
+            let view = h.view_geometry(cam.fx, cam.fy, cam.cx, cam.cy).unwrap();
+            assert!(
+                (view.roll - roll).abs() < 1e-9,
+                "roll {} != {roll}",
+                view.roll
+            );
+            assert!((view.tilt_x - tilt_x).abs() < 1e-9);
+            assert!((view.tilt_y - tilt_y).abs() < 1e-9);
+            assert!((view.size - size).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn distortion_round_trips() {
+        let cam = Camera::centered(640, 480, 500.0).with_distortion(-0.2, 0.05);
+        let p = [100.0, 400.0];
+        let d = cam.distort(p);
+        // Barrel distortion pulls points toward the principal point
+        assert!((d[0] - 320.0).abs() < (p[0] - 320.0).abs());
+        let u = cam.undistort(d);
+        assert!((u[0] - p[0]).abs() < 1e-6 && (u[1] - p[1]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn no_distortion_is_identity() {
+        let cam = Camera::new(500.0, 500.0, 10.0, 20.0);
+        assert_eq!(cam.distort([3.0, 4.0]), [3.0, 4.0]);
+        assert_eq!(cam.undistort([3.0, 4.0]), [3.0, 4.0]);
+    }
+
+    #[test]
+    fn pose_params_match_intrinsics() {
+        let pp = Camera::new(510.0, 505.0, 320.5, 240.5).pose_params();
+        assert_eq!(pp.tagsize, 2.0);
+        assert_eq!((pp.fx, pp.fy, pp.cx, pp.cy), (510.0, 505.0, 320.5, 240.5));
+    }
+}
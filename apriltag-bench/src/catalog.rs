@@ -1,7 +1,8 @@
-/// Pre-defined test scenarios for detection quality evaluation.
-use crate::distortion::Distortion;
+use crate::distortion::{Distortion, Rng};
 use crate::scene::{Background, Scene, SceneBuilder};
 use crate::transform::Transform;
+/// Pre-defined test scenarios for detection quality evaluation.
+use apriltag::DecimateMode;
 
 /// A category of test scenarios.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -17,6 +18,11 @@ pub enum Category {
     MultiTag,
     Occlusion,
     Decimation,
+    QuadSigma,
+    QuietZone,
+    Density,
+    FalsePositive,
+    Jitter,
 }
 
 impl Category {
@@ -33,6 +39,11 @@ impl Category {
             Category::MultiTag,
             Category::Occlusion,
             Category::Decimation,
+            Category::QuadSigma,
+            Category::QuietZone,
+            Category::Density,
+            Category::FalsePositive,
+            Category::Jitter,
         ]
     }
 
@@ -49,6 +60,11 @@ impl Category {
             Category::MultiTag => "multi-tag",
             Category::Occlusion => "occlusion",
             Category::Decimation => "decimation",
+            Category::QuadSigma => "quad-sigma",
+            Category::QuietZone => "quiet-zone",
+            Category::Density => "density",
+            Category::FalsePositive => "false-positive",
+            Category::Jitter => "jitter",
         }
     }
 
@@ -70,16 +86,53 @@ pub struct Scenario {
     pub max_rotation_error_deg: Option<f64>,
     /// Override detector config: quad_decimate value (None = use default).
     pub quad_decimate: Option<f32>,
-    /// Build the scene.
-    build_fn: Box<dyn Fn() -> Scene + Send + Sync>,
+    /// Override detector config: quad_sigma value (None = use default).
+    pub quad_sigma: Option<f32>,
+    /// Override detector config: decimate_mode value (None = use default).
+    pub decimate_mode: Option<DecimateMode>,
+    /// Maximum median single-threaded detection time in milliseconds
+    /// (None = no budget). Enforced by `regression`.
+    pub max_time_ms: Option<f64>,
+    /// Maximum number of false positives (None = not checked).
+    pub max_false_positives: Option<usize>,
+    /// Re-detect the scene over independently noised frames and bound the
+    /// spread of the detected corners (None = single frame only).
+    pub jitter: Option<JitterSpec>,
+    /// Build the scene, randomizing its layout when given a seed.
+    build_fn: Box<dyn Fn(Option<u64>) -> Scene + Send + Sync>,
 }
 
 impl Scenario {
     pub fn build(&self) -> Scene {
-        (self.build_fn)()
+        (self.build_fn)(None)
+    }
+
+    /// Build the scene with its whole layout moved to a random position and
+    /// rotation drawn from `seed`; see [`SceneBuilder::layout_seed`].
+    pub fn build_trial(&self, seed: u64) -> Scene {
+        (self.build_fn)(Some(seed))
     }
 }
 
+/// Repeated-frame settings for a jitter scenario.
+///
+/// Each frame is the scenario's scene plus Gaussian noise with its own seed,
+/// as a static camera would see it. Jitter is the standard deviation of each
+/// detected corner across frames, which single-frame RMSE does not capture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterSpec {
+    pub frames: usize,
+    pub noise_sigma: f64,
+    /// Maximum acceptable RMS corner jitter in pixels.
+    pub max_jitter_px: f64,
+}
+
+/// Families whose data region sits inside a reversed (white-inside) border.
+///
+/// These exercise the `reversed_border` gating in quad fitting and the
+/// inverted polarity check in decoding.
+const REVERSED_BORDER_FAMILIES: [&str; 2] = ["tagCircle21h7", "tagStandard41h12"];
+
 /// Build the full catalog of test scenarios.
 pub fn all_scenarios() -> Vec<Scenario> {
     let mut scenarios = Vec::new();
@@ -87,6 +140,7 @@ pub fn all_scenarios() -> Vec<Scenario> {
     scenarios.extend(rotation_scenarios());
     scenarios.extend(perspective_scenarios());
     scenarios.extend(scale_scenarios());
+    scenarios.extend(decimate_mode_scenarios());
     scenarios.extend(noise_scenarios());
     scenarios.extend(contrast_scenarios());
     scenarios.extend(lighting_scenarios());
@@ -94,6 +148,11 @@ pub fn all_scenarios() -> Vec<Scenario> {
     scenarios.extend(multi_tag_scenarios());
     scenarios.extend(occlusion_scenarios());
     scenarios.extend(decimation_scenarios());
+    scenarios.extend(quad_sigma_scenarios());
+    scenarios.extend(quiet_zone_scenarios());
+    scenarios.extend(density_scenarios());
+    scenarios.extend(false_positive_scenarios());
+    scenarios.extend(jitter_scenarios());
     scenarios
 }
 
@@ -105,8 +164,17 @@ pub fn scenarios_for_category(category: Category) -> Vec<Scenario> {
         .collect()
 }
 
+/// Single-threaded time budget for the 300x300 single-tag baseline scenes.
+const BASELINE_MAX_TIME_MS: f64 = 10.0;
+
 fn baseline_scenarios() -> Vec<Scenario> {
-    let families = ["tag36h11", "tag16h5", "tag25h9"];
+    let families = [
+        "tag36h11",
+        "tag16h5",
+        "tag25h9",
+        REVERSED_BORDER_FAMILIES[0],
+        REVERSED_BORDER_FAMILIES[1],
+    ];
     families
         .iter()
         .map(|&fam| {
@@ -119,7 +187,12 @@ fn baseline_scenarios() -> Vec<Scenario> {
                 max_corner_rmse: 2.0,
                 max_rotation_error_deg: None,
                 quad_decimate: None,
-                build_fn: Box::new(move || {
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: Some(BASELINE_MAX_TIME_MS),
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
                     SceneBuilder::new(300, 300)
                         .background(Background::Solid(128))
                         .add_tag(
@@ -132,6 +205,7 @@ fn baseline_scenarios() -> Vec<Scenario> {
                                 theta: 0.0,
                             },
                         )
+                        .layout_seed(seed)
                         .build()
                 }),
             }
@@ -141,7 +215,7 @@ fn baseline_scenarios() -> Vec<Scenario> {
 
 fn rotation_scenarios() -> Vec<Scenario> {
     let angles_deg = [0, 10, 15, 20, 25, 30, 45, 60, 70, 75, 80, 90];
-    angles_deg
+    let mut scenarios: Vec<Scenario> = angles_deg
         .iter()
         .map(|&deg| {
             let theta = (deg as f64).to_radians();
@@ -153,7 +227,12 @@ fn rotation_scenarios() -> Vec<Scenario> {
                 max_corner_rmse: 3.0,
                 max_rotation_error_deg: None,
                 quad_decimate: None,
-                build_fn: Box::new(move || {
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
                     SceneBuilder::new(500, 500)
                         .background(Background::Solid(128))
                         .add_tag(
@@ -166,11 +245,51 @@ fn rotation_scenarios() -> Vec<Scenario> {
                                 theta,
                             },
                         )
+                        .layout_seed(seed)
                         .build()
                 }),
             }
         })
-        .collect()
+        .collect();
+
+    // Reversed-border families at a subset of angles
+    for &fam in &REVERSED_BORDER_FAMILIES {
+        for deg in [30, 45, 80] {
+            let theta = (deg as f64).to_radians();
+            scenarios.push(Scenario {
+                name: format!("rotation-{deg}deg-{fam}"),
+                description: format!("{fam} tag rotated {deg} degrees"),
+                category: Category::Rotation,
+                expect_ids: vec![(fam.to_string(), 0)],
+                max_corner_rmse: 3.0,
+                max_rotation_error_deg: None,
+                quad_decimate: None,
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    SceneBuilder::new(500, 500)
+                        .background(Background::Solid(128))
+                        .add_tag(
+                            fam,
+                            0,
+                            Transform::Similarity {
+                                cx: 250.0,
+                                cy: 250.0,
+                                scale: 80.0,
+                                theta,
+                            },
+                        )
+                        .layout_seed(seed)
+                        .build()
+                }),
+            });
+        }
+    }
+
+    scenarios
 }
 
 fn perspective_scenarios() -> Vec<Scenario> {
@@ -188,7 +307,12 @@ fn perspective_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 5.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 SceneBuilder::new(500, 500)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -202,6 +326,7 @@ fn perspective_scenarios() -> Vec<Scenario> {
                             tilt_y: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build()
             }),
         });
@@ -220,7 +345,12 @@ fn perspective_scenarios() -> Vec<Scenario> {
             max_corner_rmse: max_rmse,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 SceneBuilder::new(500, 500)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -234,6 +364,7 @@ fn perspective_scenarios() -> Vec<Scenario> {
                             tilt_y: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build()
             }),
         });
@@ -252,7 +383,12 @@ fn perspective_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 5.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 SceneBuilder::new(500, 500)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -266,6 +402,7 @@ fn perspective_scenarios() -> Vec<Scenario> {
                             tilt_y,
                         },
                     )
+                    .layout_seed(seed)
                     .build()
             }),
         });
@@ -290,7 +427,12 @@ fn scale_scenarios() -> Vec<Scenario> {
                 max_corner_rmse: 3.0,
                 max_rotation_error_deg: None,
                 quad_decimate: if size <= 32 { Some(1.0) } else { None },
-                build_fn: Box::new(move || {
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
                     SceneBuilder::new(img_size, img_size)
                         .background(Background::Solid(128))
                         .add_tag(
@@ -303,6 +445,7 @@ fn scale_scenarios() -> Vec<Scenario> {
                                 theta: 0.0,
                             },
                         )
+                        .layout_seed(seed)
                         .build()
                 }),
             }
@@ -310,6 +453,55 @@ fn scale_scenarios() -> Vec<Scenario> {
         .collect()
 }
 
+/// The smallest tags detected at 3x and 4x decimation, once per
+/// [`DecimateMode`]. Both modes find the nominal layout; `trials` shows how
+/// much more often averaging finds them as the layout moves off the grid.
+fn decimate_mode_scenarios() -> Vec<Scenario> {
+    let modes = [
+        ("point", DecimateMode::Point),
+        ("average", DecimateMode::Average),
+    ];
+    let mut scenarios = Vec::new();
+    for (decimate, size) in [(3.0_f32, 30), (4.0, 40)] {
+        for (mode_name, mode) in modes {
+            let scale = size as f64 / 2.0;
+            scenarios.push(Scenario {
+                name: format!("scale-{size}px-{decimate:.0}x-{mode_name}"),
+                description: format!(
+                    "Tag at {size}px size, quad_decimate={decimate} with {mode_name} decimation"
+                ),
+                category: Category::Scale,
+                expect_ids: vec![("tag36h11".to_string(), 0)],
+                max_corner_rmse: 3.0,
+                max_rotation_error_deg: None,
+                quad_decimate: Some(decimate),
+                quad_sigma: None,
+                decimate_mode: Some(mode),
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    SceneBuilder::new(200, 200)
+                        .background(Background::Solid(128))
+                        .add_tag(
+                            "tag36h11",
+                            0,
+                            Transform::Similarity {
+                                cx: 100.37,
+                                cy: 100.37,
+                                scale,
+                                theta: 0.3,
+                            },
+                        )
+                        .layout_seed(seed)
+                        .build()
+                }),
+            });
+        }
+    }
+    scenarios
+}
+
 fn noise_scenarios() -> Vec<Scenario> {
     let sigmas = [5, 10, 20, 40];
     let mut scenarios: Vec<Scenario> = sigmas
@@ -322,7 +514,12 @@ fn noise_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 5.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 let mut scene = SceneBuilder::new(300, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -335,6 +532,7 @@ fn noise_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build();
                 crate::distortion::apply(
                     &mut scene.image,
@@ -360,7 +558,12 @@ fn noise_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 5.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 let mut scene = SceneBuilder::new(300, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -373,6 +576,7 @@ fn noise_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build();
                 crate::distortion::apply(
                     &mut scene.image,
@@ -383,12 +587,56 @@ fn noise_scenarios() -> Vec<Scenario> {
         });
     }
 
+    // Gaussian noise on reversed-border families
+    for &fam in &REVERSED_BORDER_FAMILIES {
+        for sigma in [10, 20] {
+            scenarios.push(Scenario {
+                name: format!("noise-sigma{sigma}-{fam}"),
+                description: format!("{fam} tag with Gaussian noise sigma={sigma}"),
+                category: Category::Noise,
+                expect_ids: vec![(fam.to_string(), 0)],
+                max_corner_rmse: 5.0,
+                max_rotation_error_deg: None,
+                quad_decimate: None,
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    let mut scene = SceneBuilder::new(300, 300)
+                        .background(Background::Solid(128))
+                        .add_tag(
+                            fam,
+                            0,
+                            Transform::Similarity {
+                                cx: 150.0,
+                                cy: 150.0,
+                                scale: 50.0,
+                                theta: 0.0,
+                            },
+                        )
+                        .layout_seed(seed)
+                        .build();
+                    crate::distortion::apply(
+                        &mut scene.image,
+                        &[Distortion::GaussianNoise {
+                            sigma: sigma as f64,
+                            seed: 42,
+                        }],
+                    );
+                    scene
+                }),
+            });
+        }
+    }
+
     scenarios
 }
 
 fn contrast_scenarios() -> Vec<Scenario> {
     let factors = [0.5, 0.25, 0.1];
-    factors
+    let mut scenarios: Vec<Scenario> = factors
         .iter()
         .map(|&factor| {
             let label = format!("{:.0}pct", factor * 100.0);
@@ -400,7 +648,12 @@ fn contrast_scenarios() -> Vec<Scenario> {
                 max_corner_rmse: 3.0,
                 max_rotation_error_deg: None,
                 quad_decimate: None,
-                build_fn: Box::new(move || {
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
                     let mut scene = SceneBuilder::new(300, 300)
                         .background(Background::Solid(128))
                         .add_tag(
@@ -413,6 +666,7 @@ fn contrast_scenarios() -> Vec<Scenario> {
                                 theta: 0.0,
                             },
                         )
+                        .layout_seed(seed)
                         .build();
                     crate::distortion::apply(
                         &mut scene.image,
@@ -422,7 +676,59 @@ fn contrast_scenarios() -> Vec<Scenario> {
                 }),
             }
         })
-        .collect()
+        .collect();
+
+    // Exposure clipping: (label, black_level, white_level, gamma)
+    let exposures = [
+        ("underexposed", 40u8, 255u8, 2.2),
+        ("overexposed", 0, 200, 0.45),
+        ("crushed", 60, 180, 1.0),
+    ];
+    for (label, black_level, white_level, gamma) in exposures {
+        scenarios.push(Scenario {
+            name: format!("contrast-clip-{label}"),
+            description: format!(
+                "Exposure clipping: black floor {black_level}, white clip {white_level}, gamma {gamma}"
+            ),
+            category: Category::Contrast,
+            expect_ids: vec![("tag36h11".to_string(), 0)],
+            max_corner_rmse: 3.0,
+            max_rotation_error_deg: None,
+            quad_decimate: None,
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
+                let mut scene = SceneBuilder::new(300, 300)
+                    .background(Background::Solid(128))
+                    .add_tag(
+                        "tag36h11",
+                        0,
+                        Transform::Similarity {
+                            cx: 150.0,
+                            cy: 150.0,
+                            scale: 50.0,
+                            theta: 0.0,
+                        },
+                    )
+                    .layout_seed(seed)
+                    .build();
+                crate::distortion::apply(
+                    &mut scene.image,
+                    &[Distortion::Clip {
+                        black_level,
+                        white_level,
+                        gamma,
+                    }],
+                );
+                scene
+            }),
+        });
+    }
+
+    scenarios
 }
 
 fn lighting_scenarios() -> Vec<Scenario> {
@@ -435,7 +741,12 @@ fn lighting_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 let mut scene = SceneBuilder::new(300, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -448,6 +759,7 @@ fn lighting_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build();
                 crate::distortion::apply(
                     &mut scene.image,
@@ -468,7 +780,12 @@ fn lighting_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 let mut scene = SceneBuilder::new(300, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -481,6 +798,7 @@ fn lighting_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build();
                 crate::distortion::apply(
                     &mut scene.image,
@@ -501,7 +819,12 @@ fn lighting_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(move || {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
                 let mut scene = SceneBuilder::new(300, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -514,6 +837,7 @@ fn lighting_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build();
                 crate::distortion::apply(
                     &mut scene.image,
@@ -529,7 +853,7 @@ fn lighting_scenarios() -> Vec<Scenario> {
 
 fn blur_scenarios() -> Vec<Scenario> {
     let sigmas = [1.0, 2.0, 4.0];
-    sigmas
+    let mut scenarios: Vec<Scenario> = sigmas
         .iter()
         .map(|&sigma| {
             let label = format!("{sigma:.0}");
@@ -541,7 +865,12 @@ fn blur_scenarios() -> Vec<Scenario> {
                 max_corner_rmse: 5.0,
                 max_rotation_error_deg: None,
                 quad_decimate: None,
-                build_fn: Box::new(move || {
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
                     let mut scene = SceneBuilder::new(300, 300)
                         .background(Background::Solid(128))
                         .add_tag(
@@ -554,6 +883,7 @@ fn blur_scenarios() -> Vec<Scenario> {
                                 theta: 0.0,
                             },
                         )
+                        .layout_seed(seed)
                         .build();
                     crate::distortion::apply(
                         &mut scene.image,
@@ -563,7 +893,48 @@ fn blur_scenarios() -> Vec<Scenario> {
                 }),
             }
         })
-        .collect()
+        .collect();
+
+    // Interlacing: odd field displaced horizontally by motion between fields
+    for dx in [1.0, 2.0, 4.0] {
+        scenarios.push(Scenario {
+            name: format!("blur-interlace-{dx:.0}px"),
+            description: format!("Interlaced capture, odd field shifted {dx}px horizontally"),
+            category: Category::Blur,
+            expect_ids: vec![("tag36h11".to_string(), 0)],
+            max_corner_rmse: 5.0,
+            max_rotation_error_deg: None,
+            quad_decimate: None,
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(move |seed| {
+                let mut scene = SceneBuilder::new(300, 300)
+                    .background(Background::Solid(128))
+                    .add_tag(
+                        "tag36h11",
+                        0,
+                        Transform::Similarity {
+                            cx: 150.0,
+                            cy: 150.0,
+                            scale: 50.0,
+                            theta: 0.0,
+                        },
+                    )
+                    .layout_seed(seed)
+                    .build();
+                crate::distortion::apply(
+                    &mut scene.image,
+                    &[Distortion::Interlace { dx, dy: 0.0 }],
+                );
+                scene
+            }),
+        });
+    }
+
+    scenarios
 }
 
 fn multi_tag_scenarios() -> Vec<Scenario> {
@@ -576,7 +947,12 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 SceneBuilder::new(500, 300)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -599,6 +975,7 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build()
             }),
         },
@@ -610,7 +987,12 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 let positions = [
                     (100.0, 100.0),
                     (300.0, 100.0),
@@ -631,7 +1013,7 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
                         },
                     );
                 }
-                builder.build()
+                builder.layout_seed(seed).build()
             }),
         },
         Scenario {
@@ -645,7 +1027,12 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 3.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 SceneBuilder::new(600, 400)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -668,6 +1055,7 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
                     .build()
             }),
         },
@@ -689,7 +1077,12 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
             max_corner_rmse: 5.0,
             max_rotation_error_deg: None,
             quad_decimate: None,
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 let positions = [
                     (150.0, 150.0),
                     (400.0, 150.0),
@@ -722,7 +1115,7 @@ fn multi_tag_scenarios() -> Vec<Scenario> {
                         },
                     );
                 }
-                builder.build()
+                builder.layout_seed(seed).build()
             }),
         },
     ]
@@ -737,7 +1130,12 @@ fn occlusion_scenarios() -> Vec<Scenario> {
         max_corner_rmse: 5.0,
         max_rotation_error_deg: None,
         quad_decimate: None,
-        build_fn: Box::new(|| {
+        quad_sigma: None,
+        decimate_mode: None,
+        max_time_ms: None,
+        max_false_positives: None,
+        jitter: None,
+        build_fn: Box::new(|seed| {
             let mut scene = SceneBuilder::new(300, 300)
                 .background(Background::Solid(128))
                 .add_tag(
@@ -750,32 +1148,41 @@ fn occlusion_scenarios() -> Vec<Scenario> {
                         theta: 0.0,
                     },
                 )
+                .layout_seed(seed)
                 .build();
-            // Occlude a small strip at the top of the tag
-            crate::distortion::apply(
-                &mut scene.image,
-                &[Distortion::Occlude {
-                    rect: [100, 100, 200, 115],
-                }],
-            );
+            // Occlude a strip across the top 15% of the tag's bounding box
+            let corners = scene.ground_truth[0].corners;
+            let (x0, x1) = corners.iter().fold((f64::MAX, f64::MIN), |(lo, hi), c| {
+                (lo.min(c[0]), hi.max(c[0]))
+            });
+            let (y0, y1) = corners.iter().fold((f64::MAX, f64::MIN), |(lo, hi), c| {
+                (lo.min(c[1]), hi.max(c[1]))
+            });
+            let rect = [x0, y0, x1, y0 + 0.15 * (y1 - y0)].map(|v| v.round().max(0.0) as u32);
+            crate::distortion::apply(&mut scene.image, &[Distortion::Occlude { rect }]);
             scene
         }),
     }]
 }
 
 fn decimation_scenarios() -> Vec<Scenario> {
-    let decimations = [1.0_f32, 2.0, 4.0];
+    let decimations = [1.0_f32, 1.5, 2.0, 4.0];
     decimations
         .iter()
         .map(|&decimate| Scenario {
-            name: format!("decimation-{decimate:.0}x"),
+            name: format!("decimation-{decimate}x"),
             description: format!("Detection with quad_decimate={decimate}"),
             category: Category::Decimation,
             expect_ids: vec![("tag36h11".to_string(), 0)],
             max_corner_rmse: if decimate >= 4.0 { 5.0 } else { 3.0 },
             max_rotation_error_deg: None,
             quad_decimate: Some(decimate),
-            build_fn: Box::new(|| {
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: None,
+            build_fn: Box::new(|seed| {
                 SceneBuilder::new(400, 400)
                     .background(Background::Solid(128))
                     .add_tag(
@@ -788,6 +1195,293 @@ fn decimation_scenarios() -> Vec<Scenario> {
                             theta: 0.0,
                         },
                     )
+                    .layout_seed(seed)
+                    .build()
+            }),
+        })
+        .collect()
+}
+
+/// `quad_sigma` values swept: none, light and strong blur, and sharpening.
+const QUAD_SIGMAS: [f32; 4] = [0.0, 0.8, 1.5, -0.8];
+
+fn quad_sigma_scenarios() -> Vec<Scenario> {
+    let degradations = [
+        ("blurred", Distortion::GaussianBlur { sigma: 2.0 }),
+        (
+            "noisy",
+            Distortion::GaussianNoise {
+                sigma: 20.0,
+                seed: 42,
+            },
+        ),
+    ];
+    let mut scenarios = Vec::new();
+    for (scene_name, distortion) in degradations {
+        for decimate in [1.0_f32, 2.0] {
+            for quad_sigma in QUAD_SIGMAS {
+                let distortion = distortion.clone();
+                scenarios.push(Scenario {
+                    name: format!("quad-sigma{quad_sigma}-{decimate:.0}x-{scene_name}"),
+                    description: format!(
+                        "{scene_name} scene with quad_sigma={quad_sigma}, quad_decimate={decimate}"
+                    ),
+                    category: Category::QuadSigma,
+                    expect_ids: vec![("tag36h11".to_string(), 0)],
+                    max_corner_rmse: 5.0,
+                    max_rotation_error_deg: None,
+                    quad_decimate: Some(decimate),
+                    quad_sigma: Some(quad_sigma),
+                    decimate_mode: None,
+                    max_time_ms: None,
+                    max_false_positives: None,
+                    jitter: None,
+                    build_fn: Box::new(move |seed| {
+                        let mut scene = SceneBuilder::new(300, 300)
+                            .background(Background::Solid(128))
+                            .add_tag(
+                                "tag36h11",
+                                0,
+                                Transform::Similarity {
+                                    cx: 150.0,
+                                    cy: 150.0,
+                                    scale: 50.0,
+                                    theta: 0.0,
+                                },
+                            )
+                            .layout_seed(seed)
+                            .build();
+                        crate::distortion::apply(
+                            &mut scene.image,
+                            std::slice::from_ref(&distortion),
+                        );
+                        scene
+                    }),
+                });
+            }
+        }
+    }
+    scenarios
+}
+
+/// Quiet-zone widths swept, in modules of white outside the black border.
+const QUIET_ZONES: [f64; 4] = [1.0, 0.5, 0.25, 0.0];
+
+fn quiet_zone_scenarios() -> Vec<Scenario> {
+    let backgrounds = [
+        ("gray", Background::Solid(128)),
+        (
+            "clutter",
+            Background::Checkerboard {
+                cell_size: 7,
+                light: 70,
+                dark: 20,
+            },
+        ),
+    ];
+    let mut scenarios = Vec::new();
+    for (bg_name, bg) in backgrounds {
+        for qz in QUIET_ZONES {
+            let bg = bg.clone();
+            scenarios.push(Scenario {
+                name: format!("quiet-zone-{qz}-{bg_name}"),
+                description: format!(
+                    "Tag with {qz} module(s) of white quiet zone on a {bg_name} background"
+                ),
+                category: Category::QuietZone,
+                expect_ids: vec![("tag36h11".to_string(), 0)],
+                max_corner_rmse: 3.0,
+                max_rotation_error_deg: None,
+                quad_decimate: None,
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    SceneBuilder::new(300, 300)
+                        .background(bg.clone())
+                        .quiet_zone(qz)
+                        .add_tag(
+                            "tag36h11",
+                            0,
+                            Transform::Similarity {
+                                cx: 150.0,
+                                cy: 150.0,
+                                scale: 50.0,
+                                theta: 0.0,
+                            },
+                        )
+                        .layout_seed(seed)
+                        .build()
+                }),
+            });
+        }
+    }
+    scenarios
+}
+
+/// Dense tag arrays: (tag count, columns).
+const DENSITY_GRIDS: [(u32, u32); 3] = [(50, 10), (100, 10), (200, 20)];
+
+fn density_scenarios() -> Vec<Scenario> {
+    // Calibration-board-like spacing: 30px tags (3px modules) on a 40px pitch.
+    const SCALE: f64 = 12.0;
+    const PITCH: f64 = 40.0;
+    DENSITY_GRIDS
+        .iter()
+        .map(|&(count, cols)| {
+            let rows = count.div_ceil(cols);
+            let width = (cols as f64 * PITCH) as u32 + 40;
+            let height = (rows as f64 * PITCH) as u32 + 40;
+            Scenario {
+                name: format!("density-{count}tags"),
+                description: format!("{count} small tag36h11 tags in a {cols}x{rows} array"),
+                category: Category::Density,
+                expect_ids: (0..count).map(|i| ("tag36h11".to_string(), i)).collect(),
+                max_corner_rmse: 3.0,
+                max_rotation_error_deg: None,
+                quad_decimate: None,
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: None,
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    let mut builder =
+                        SceneBuilder::new(width, height).background(Background::Solid(128));
+                    for id in 0..count {
+                        builder = builder.add_tag(
+                            "tag36h11",
+                            id,
+                            Transform::Similarity {
+                                cx: 40.0 + (id % cols) as f64 * PITCH,
+                                cy: 40.0 + (id / cols) as f64 * PITCH,
+                                scale: SCALE,
+                                theta: 0.0,
+                            },
+                        );
+                    }
+                    builder.layout_seed(seed).build()
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Families checked against their theoretical false-positive rate.
+const FALSE_POSITIVE_FAMILIES: [&str; 3] = ["tag16h5", "tag25h9", "tag36h11"];
+
+/// Decoys per false-positive scene; one more grid cell holds a real tag.
+const DECOY_COUNT: u32 = 35;
+const DECOY_SEED: u64 = 0xdec0;
+
+fn false_positive_scenarios() -> Vec<Scenario> {
+    const COLS: u32 = 6;
+    const PITCH: f64 = 110.0;
+    let size = (COLS as f64 * PITCH) as u32 + 40;
+
+    FALSE_POSITIVE_FAMILIES
+        .iter()
+        .map(|&fam| {
+            let family = apriltag_gen::family::builtin_family(fam)
+                .unwrap_or_else(|| panic!("unknown tag family: {fam}"));
+            // Decoys are rendered cleanly, so each is read exactly: it decodes
+            // iff its random data bits are within the detector's Hamming limit
+            // of some code, with the probability computed by apriltag-gen.
+            let rate =
+                apriltag_gen::robustness::analyze(&family, [2]).levels[0].false_positive_rate;
+            let n = DECOY_COUNT as f64;
+            let expected = n * rate;
+            let budget = (expected + 3.0 * (n * rate * (1.0 - rate)).sqrt()).floor() as usize;
+            let nbits = family.layout.nbits as u32;
+
+            Scenario {
+                name: format!("false-positive-decoys-{fam}"),
+                description: format!(
+                    "{DECOY_COUNT} random-data {fam} decoys and one tag; \
+                     {expected:.2} false positives expected at Hamming 2"
+                ),
+                category: Category::FalsePositive,
+                expect_ids: vec![(fam.to_string(), 0)],
+                max_corner_rmse: 1.0,
+                max_rotation_error_deg: None,
+                quad_decimate: None,
+                quad_sigma: None,
+                decimate_mode: None,
+                max_time_ms: None,
+                max_false_positives: Some(budget),
+                jitter: None,
+                build_fn: Box::new(move |seed| {
+                    let cell = |i: u32| Transform::Similarity {
+                        cx: 20.0 + PITCH / 2.0 + (i % COLS) as f64 * PITCH,
+                        cy: 20.0 + PITCH / 2.0 + (i / COLS) as f64 * PITCH,
+                        scale: 30.0,
+                        theta: 0.0,
+                    };
+                    let mut rng = Rng::new(DECOY_SEED);
+                    let mut builder = SceneBuilder::new(size, size)
+                        .background(Background::Solid(128))
+                        .add_tag(fam, 0, cell(0));
+                    for i in 1..=DECOY_COUNT {
+                        let code = rng.next_u64() >> (64 - nbits);
+                        builder = builder.add_decoy(fam, code, cell(i));
+                    }
+                    builder.layout_seed(seed).build()
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Frames per jitter scenario.
+const JITTER_FRAMES: usize = 30;
+
+fn jitter_scenarios() -> Vec<Scenario> {
+    // (name, tag scale, noise sigma, max RMS jitter). Tags sit off the pixel
+    // grid so that corners are not pinned to pixel boundaries.
+    let cases = [
+        ("large-sigma2", 50.0, 2.0, 0.02),
+        ("large-sigma5", 50.0, 5.0, 0.04),
+        ("large-sigma10", 50.0, 10.0, 0.08),
+        ("small-sigma10", 10.0, 10.0, 0.12),
+    ];
+    cases
+        .iter()
+        .map(|&(label, scale, sigma, max_jitter_px)| Scenario {
+            name: format!("jitter-{label}"),
+            description: format!(
+                "Static {:.0}px tag36h11 tag over {JITTER_FRAMES} frames of noise sigma={sigma}",
+                2.0 * scale
+            ),
+            category: Category::Jitter,
+            expect_ids: vec![("tag36h11".to_string(), 0)],
+            max_corner_rmse: 2.0,
+            max_rotation_error_deg: None,
+            quad_decimate: None,
+            quad_sigma: None,
+            decimate_mode: None,
+            max_time_ms: None,
+            max_false_positives: None,
+            jitter: Some(JitterSpec {
+                frames: JITTER_FRAMES,
+                noise_sigma: sigma,
+                max_jitter_px,
+            }),
+            build_fn: Box::new(move |seed| {
+                SceneBuilder::new(300, 300)
+                    .background(Background::Solid(128))
+                    .add_tag(
+                        "tag36h11",
+                        0,
+                        Transform::Similarity {
+                            cx: 150.37,
+                            cy: 149.71,
+                            scale,
+                            theta: 0.1,
+                        },
+                    )
+                    .layout_seed(seed)
                     .build()
             }),
         })
@@ -849,6 +1543,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn quad_sigma_sweep_covers_sharpening_and_decimation() {
+        let scenarios = scenarios_for_category(Category::QuadSigma);
+        assert_eq!(scenarios.len(), 2 * 2 * QUAD_SIGMAS.len());
+        assert!(scenarios.iter().any(|s| s.quad_sigma < Some(0.0)));
+        assert!(scenarios
+            .iter()
+            .any(|s| s.quad_decimate == Some(1.0) && s.quad_sigma == Some(1.5)));
+    }
+
+    #[test]
+    fn decimate_mode_scenarios_pair_each_mode() {
+        let scenarios = decimate_mode_scenarios();
+        for pair in scenarios.chunks(2) {
+            assert_eq!(pair[0].quad_decimate, pair[1].quad_decimate);
+            assert_eq!(pair[0].decimate_mode, Some(DecimateMode::Point));
+            assert_eq!(pair[1].decimate_mode, Some(DecimateMode::Average));
+        }
+        assert!(scenarios.iter().any(|s| s.quad_decimate == Some(4.0)));
+    }
+
+    #[test]
+    fn decoys_are_detected_iff_within_hamming_limit() {
+        let scenario = scenarios_for_category(Category::FalsePositive)
+            .into_iter()
+            .find(|s| s.name == "false-positive-decoys-tag16h5")
+            .unwrap();
+        let family = apriltag::family::tag16h5();
+        let rotations: Vec<u64> = family
+            .codes
+            .iter()
+            .flat_map(|&c| {
+                std::iter::successors(Some(c), |&r| Some(apriltag::hamming::rotate90(r, 16)))
+                    .take(4)
+            })
+            .collect();
+
+        let mut rng = Rng::new(DECOY_SEED);
+        let decodable = (0..DECOY_COUNT)
+            .filter(|_| {
+                let code = rng.next_u64() >> 48;
+                rotations.iter().any(|&c| (c ^ code).count_ones() <= 2)
+            })
+            .count();
+
+        let (result, _) = crate::runner::run_scenario(&scenario);
+        assert_eq!(result.detection_rate, 1.0);
+        assert_eq!(result.false_positives.len(), decodable);
+        assert!(decodable <= scenario.max_false_positives.unwrap());
+    }
+
+    #[test]
+    fn quiet_zone_sweep_reaches_zero_on_clutter() {
+        let scenarios = scenarios_for_category(Category::QuietZone);
+        assert_eq!(scenarios.len(), 2 * QUIET_ZONES.len());
+        assert!(scenarios.iter().any(|s| s.name == "quiet-zone-0-clutter"));
+    }
+
     #[test]
     fn combined_tilt_scenarios_exist() {
         let scenarios = scenarios_for_category(Category::Perspective);
@@ -863,6 +1615,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn only_jitter_scenarios_repeat_frames() {
+        for s in all_scenarios() {
+            assert_eq!(
+                s.jitter.is_some(),
+                s.category == Category::Jitter,
+                "{}",
+                s.name
+            );
+            if let Some(spec) = s.jitter {
+                assert!(spec.frames >= 2 && spec.noise_sigma > 0.0, "{}", s.name);
+            }
+        }
+    }
+
     #[test]
     fn baseline_scenarios_cover_families() {
         let scenarios = scenarios_for_category(Category::Baseline);
@@ -874,4 +1641,16 @@ mod tests {
         assert!(families.contains(&"tag16h5".to_string()));
         assert!(families.contains(&"tag25h9".to_string()));
     }
+
+    #[test]
+    fn reversed_border_families_covered_beyond_baseline() {
+        for fam in REVERSED_BORDER_FAMILIES {
+            for cat in [Category::Baseline, Category::Rotation, Category::Noise] {
+                let covered = scenarios_for_category(cat)
+                    .iter()
+                    .any(|s| s.expect_ids.iter().any(|(f, _)| f == fam));
+                assert!(covered, "{fam} missing from {cat:?} scenarios");
+            }
+        }
+    }
 }
@@ -0,0 +1,492 @@
+/// Robustness curves: detection rate and corner error as a function of one
+/// distortion parameter, for the `curve` command.
+///
+/// Each point renders a single centered tag with the parameter at its value
+/// and everything else clean, over several trials that differ in sub-pixel
+/// tag position and in the seeds of random distortions.
+use std::fmt::Write;
+
+use apriltag::{Detector, DetectorBuffers};
+use serde::Serialize;
+
+use crate::distortion::{self, Distortion, Rng};
+use crate::metrics;
+use crate::plot::{Chart, Series};
+use crate::scene::{Background, Scene, SceneBuilder};
+use crate::transform::Transform;
+
+/// Side of the square test image, in pixels.
+const IMAGE_SIZE: u32 = 400;
+
+/// Tag size (border region width) in pixels, unless swept.
+const TAG_SIZE: f64 = 100.0;
+
+/// A parameter a curve can sweep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveParam {
+    /// Gaussian noise sigma.
+    Noise,
+    /// Gaussian blur sigma, in pixels.
+    Blur,
+    /// Contrast scale factor.
+    Contrast,
+    /// Salt-and-pepper noise density.
+    SaltPepper,
+    /// Brightness offset.
+    Brightness,
+    /// In-plane rotation, in degrees.
+    Rotation,
+    /// Perspective tilt about the vertical axis, in degrees.
+    Tilt,
+    /// Tag size (border region width), in pixels.
+    TagSize,
+}
+
+impl CurveParam {
+    pub const ALL: [CurveParam; 8] = [
+        CurveParam::Noise,
+        CurveParam::Blur,
+        CurveParam::Contrast,
+        CurveParam::SaltPepper,
+        CurveParam::Brightness,
+        CurveParam::Rotation,
+        CurveParam::Tilt,
+        CurveParam::TagSize,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CurveParam::Noise => "noise",
+            CurveParam::Blur => "blur",
+            CurveParam::Contrast => "contrast",
+            CurveParam::SaltPepper => "salt-pepper",
+            CurveParam::Brightness => "brightness",
+            CurveParam::Rotation => "rotation",
+            CurveParam::Tilt => "tilt",
+            CurveParam::TagSize => "tag-size",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    /// Axis label, with units.
+    fn label(self) -> &'static str {
+        match self {
+            CurveParam::Noise => "noise sigma",
+            CurveParam::Blur => "blur sigma (px)",
+            CurveParam::Contrast => "contrast factor",
+            CurveParam::SaltPepper => "salt-and-pepper density",
+            CurveParam::Brightness => "brightness offset",
+            CurveParam::Rotation => "rotation (deg)",
+            CurveParam::Tilt => "tilt (deg)",
+            CurveParam::TagSize => "tag size (px)",
+        }
+    }
+
+    /// The test scene with this parameter at `value`. `rng` places the tag
+    /// and seeds random distortions.
+    fn scene(self, value: f64, family: &str, rng: &mut Rng) -> Scene {
+        let half = IMAGE_SIZE as f64 / 2.0;
+        let center = [half + rng.next_f64() - 0.5, half + rng.next_f64() - 0.5];
+        let (mut size, mut roll, mut tilt) = (TAG_SIZE, 0.0, 0.0);
+        let mut distortions = Vec::new();
+        match self {
+            CurveParam::Noise => distortions.push(Distortion::GaussianNoise {
+                sigma: value,
+                seed: rng.next_u64(),
+            }),
+            CurveParam::Blur => distortions.push(Distortion::GaussianBlur { sigma: value }),
+            CurveParam::Contrast => distortions.push(Distortion::ContrastScale { factor: value }),
+            CurveParam::SaltPepper => distortions.push(Distortion::SaltPepper {
+                density: value,
+                seed: rng.next_u64(),
+            }),
+            CurveParam::Brightness => distortions.push(Distortion::BrightnessShift {
+                offset: value.round() as i16,
+            }),
+            CurveParam::Rotation => roll = value.to_radians(),
+            CurveParam::Tilt => tilt = value.to_radians(),
+            CurveParam::TagSize => size = value,
+        }
+
+        let transform = Transform::FromPose {
+            center,
+            size,
+            roll,
+            tilt_x: tilt,
+            tilt_y: 0.0,
+        };
+        let mut scene = SceneBuilder::new(IMAGE_SIZE, IMAGE_SIZE)
+            .background(Background::Solid(128))
+            .add_tag(family, 0, transform)
+            .build();
+        distortion::apply(&mut scene.image, &distortions);
+        scene
+    }
+}
+
+/// Parameter values from `start` to `end` inclusive in steps of `step`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurveRange {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl CurveRange {
+    /// Parse `start:end:step`, e.g. `0:60:5`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let [start, end, step] = parts[..] else {
+            return Err(format!("range '{spec}' is not start:end:step"));
+        };
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("invalid number '{s}' in range '{spec}'"))
+        };
+        let range = Self {
+            start: parse(start)?,
+            end: parse(end)?,
+            step: parse(step)?,
+        };
+        if range.step <= 0.0 {
+            return Err(format!("step of range '{spec}' must be positive"));
+        }
+        if range.end < range.start {
+            return Err(format!("range '{spec}' ends before it starts"));
+        }
+        Ok(range)
+    }
+
+    /// The values of the range. The end is included when a whole number of
+    /// steps (up to rounding) reaches it.
+    pub fn values(&self) -> Vec<f64> {
+        let steps = ((self.end - self.start) / self.step + 1e-9).floor() as usize;
+        (0..=steps)
+            .map(|i| self.start + i as f64 * self.step)
+            .collect()
+    }
+}
+
+/// Detection results at one parameter value.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurvePoint {
+    pub value: f64,
+    pub trials: usize,
+    pub detected: usize,
+    /// Fraction of trials in which the tag was detected.
+    pub detection_rate: f64,
+    /// 95% Wilson interval of the detection rate.
+    pub detection_rate_ci: (f64, f64),
+    /// Root mean square corner error over all detected tags (pixels), or
+    /// `None` if none was detected.
+    pub corner_rmse: Option<f64>,
+    /// Detections of anything but the rendered tag, over all trials.
+    pub false_positives: usize,
+    /// Corner RMSE of the compared detector on the same scenes, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compared_corner_rmse: Option<f64>,
+}
+
+/// A swept parameter and the detection results at each of its values.
+#[derive(Debug, Clone, Serialize)]
+pub struct Curve {
+    pub param: &'static str,
+    pub family: String,
+    pub seed: u64,
+    /// Name of the detector compared against, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compared: Option<String>,
+    pub points: Vec<CurvePoint>,
+}
+
+/// Sum of squared corner errors and number of corners of `detector`'s
+/// matches in `scene`.
+fn corner_errors(
+    detector: &Detector,
+    scene: &Scene,
+    buffers: &mut DetectorBuffers,
+) -> (f64, usize) {
+    let detections = detector.detect(&scene.image, buffers);
+    metrics::evaluate(&scene.ground_truth, &detections, 0)
+        .matches
+        .iter()
+        .filter_map(|m| m.corner_errors)
+        .fold((0.0, 0), |(sum, n), errors| {
+            (
+                sum + errors.iter().map(|e| e * e).sum::<f64>(),
+                n + errors.len(),
+            )
+        })
+}
+
+/// Detect the rendered tag `trials` times at each of `values` of `param`.
+/// Trial seeds are drawn from `seed`, so the same arguments reproduce the
+/// same curve.
+///
+/// With `compare`, a named second detector also runs on every scene, and
+/// its corner RMSE is reported next to the first's, e.g. to measure what an
+/// optional refinement stage gains.
+pub fn run_curve(
+    detector: &Detector,
+    compare: Option<(&str, &Detector)>,
+    param: CurveParam,
+    values: &[f64],
+    family: &str,
+    trials: usize,
+    seed: u64,
+) -> Curve {
+    let mut buffers = DetectorBuffers::new();
+    let points = values
+        .iter()
+        .map(|&value| {
+            // The same seeds at every value, so neighbouring points differ
+            // only by the parameter
+            let mut rng = Rng::new(seed);
+            let (mut detected, mut false_positives) = (0, 0);
+            let (mut squared_error, mut corners) = (0.0, 0usize);
+            let (mut compared_error, mut compared_corners) = (0.0, 0usize);
+            for _ in 0..trials {
+                let scene = param.scene(value, family, &mut rng);
+                let detections = detector.detect(&scene.image, &mut buffers);
+                let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+                false_positives += result.false_positives.len();
+                for errors in result.matches.iter().filter_map(|m| m.corner_errors) {
+                    detected += 1;
+                    squared_error += errors.iter().map(|e| e * e).sum::<f64>();
+                    corners += errors.len();
+                }
+                if let Some((_, other)) = compare {
+                    let (sum, n) = corner_errors(other, &scene, &mut buffers);
+                    compared_error += sum;
+                    compared_corners += n;
+                }
+            }
+            CurvePoint {
+                value,
+                trials,
+                detected,
+                detection_rate: if trials > 0 {
+                    detected as f64 / trials as f64
+                } else {
+                    0.0
+                },
+                detection_rate_ci: metrics::wilson_interval(detected, trials),
+                corner_rmse: (corners > 0).then(|| (squared_error / corners as f64).sqrt()),
+                false_positives,
+                compared_corner_rmse: (compared_corners > 0)
+                    .then(|| (compared_error / compared_corners as f64).sqrt()),
+            }
+        })
+        .collect();
+
+    Curve {
+        param: param.name(),
+        family: family.to_string(),
+        seed,
+        compared: compare.map(|(name, _)| name.to_string()),
+        points,
+    }
+}
+
+impl Curve {
+    /// One CSV row per parameter value; an undefined RMSE is left empty.
+    /// A compared detector adds a `<name>_corner_rmse` column.
+    pub fn to_csv(&self) -> String {
+        let mut out = format!(
+            "{},trials,detected,detection_rate,detection_rate_lo,detection_rate_hi,corner_rmse,false_positives",
+            self.param
+        );
+        if let Some(name) = &self.compared {
+            let _ = write!(out, ",{name}_corner_rmse");
+        }
+        out.push('\n');
+        let rmse = |r: Option<f64>| r.map_or(String::new(), |r| r.to_string());
+        for p in &self.points {
+            let _ = write!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                p.value,
+                p.trials,
+                p.detected,
+                p.detection_rate,
+                p.detection_rate_ci.0,
+                p.detection_rate_ci.1,
+                rmse(p.corner_rmse),
+                p.false_positives
+            );
+            if self.compared.is_some() {
+                let _ = write!(out, ",{}", rmse(p.compared_corner_rmse));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A table for the terminal.
+    pub fn to_table(&self) -> String {
+        let mut out = format!(
+            "{:>12} {:>8} {:>17} {:>10} {:>4}",
+            self.param, "Detected", "Rate (95% CI)", "RMSE (px)", "FP"
+        );
+        if let Some(name) = &self.compared {
+            let _ = write!(out, " {:>16}", format!("{name} (px)"));
+        }
+        out.push('\n');
+        let rmse = |r: Option<f64>| r.map_or("-".to_string(), |r| format!("{r:.3}"));
+        for p in &self.points {
+            let _ = write!(
+                out,
+                "{:>12} {:>8} {:>5.1}% ({:>3.0}-{:>3.0}%) {:>10} {:>4}",
+                p.value,
+                format!("{}/{}", p.detected, p.trials),
+                p.detection_rate * 100.0,
+                p.detection_rate_ci.0 * 100.0,
+                p.detection_rate_ci.1 * 100.0,
+                rmse(p.corner_rmse),
+                p.false_positives
+            );
+            if self.compared.is_some() {
+                let _ = write!(out, " {:>16}", rmse(p.compared_corner_rmse));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Detection rate and corner RMSE against the parameter, for
+    /// [`render_svg`](crate::plot::render_svg). A compared detector's RMSE
+    /// is drawn dashed on the same chart.
+    pub fn charts(&self) -> Vec<Chart> {
+        let x_label = CurveParam::from_name(self.param)
+            .map_or(self.param, CurveParam::label)
+            .to_string();
+        let rate: Vec<(f64, f64)> = self
+            .points
+            .iter()
+            .map(|p| (p.value, p.detection_rate * 100.0))
+            .collect();
+        let rmse: Vec<(f64, f64)> = self
+            .points
+            .iter()
+            .filter_map(|p| Some((p.value, p.corner_rmse?)))
+            .collect();
+        let mut rmse_series = vec![Series::mean_by_x(&self.family, &rmse, false)];
+        if let Some(name) = &self.compared {
+            let compared: Vec<(f64, f64)> = self
+                .points
+                .iter()
+                .filter_map(|p| Some((p.value, p.compared_corner_rmse?)))
+                .collect();
+            rmse_series.push(Series::mean_by_x(name, &compared, true));
+        }
+        vec![
+            Chart {
+                title: format!("{}: detection rate", self.family),
+                x_label: x_label.clone(),
+                y_label: "detection rate (%)".to_string(),
+                series: vec![Series::mean_by_x(&self.family, &rate, false)],
+            },
+            Chart {
+                title: format!("{}: corner RMSE", self.family),
+                x_label,
+                y_label: "corner RMSE (px)".to_string(),
+                series: rmse_series,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_includes_the_end() {
+        let range = CurveRange::parse("0:60:5").unwrap();
+        let values = range.values();
+        assert_eq!(values.len(), 13);
+        assert_eq!(values[12], 60.0);
+        // Rounding does not drop the last step
+        assert_eq!(CurveRange::parse("0:0.3:0.1").unwrap().values().len(), 4);
+        // A partial last step is left out
+        assert_eq!(
+            CurveRange::parse("1:2:0.4").unwrap().values(),
+            [1.0, 1.4, 1.8]
+        );
+    }
+
+    #[test]
+    fn range_rejects_malformed_specs() {
+        for spec in ["0:60", "0:60:0", "60:0:5", "0:x:5", "0:inf:5"] {
+            assert!(CurveRange::parse(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn noise_curve_degrades_from_a_clean_detection() {
+        let detector = Detector::builder().family("tag36h11", 2).build();
+        let curve = run_curve(
+            &detector,
+            None,
+            CurveParam::Noise,
+            &[0.0, 200.0],
+            "tag36h11",
+            4,
+            1,
+        );
+        let [clean, noisy] = &curve.points[..] else {
+            panic!("expected two points");
+        };
+        assert_eq!((clean.detected, clean.trials), (4, 4));
+        assert!(clean.corner_rmse.unwrap() < 0.5);
+        assert!(noisy.detection_rate < clean.detection_rate);
+
+        let csv = curve.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.starts_with("noise,trials,"));
+    }
+
+    #[test]
+    fn compared_detector_reports_its_own_rmse() {
+        let detector = Detector::builder().family("tag36h11", 2).build();
+        let refined = Detector::builder()
+            .refine_decode(true)
+            .family("tag36h11", 2)
+            .build();
+        let curve = run_curve(
+            &detector,
+            Some(("refine_decode", &refined)),
+            CurveParam::Blur,
+            &[1.0],
+            "tag36h11",
+            4,
+            1,
+        );
+        let point = &curve.points[0];
+        assert!(point.compared_corner_rmse.unwrap() < point.corner_rmse.unwrap());
+
+        let csv = curve.to_csv();
+        assert!(csv
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with(",refine_decode_corner_rmse"));
+        assert_eq!(csv.lines().nth(1).unwrap().split(',').count(), 9);
+        assert!(curve.to_table().contains("refine_decode (px)"));
+        assert_eq!(curve.charts()[1].series.len(), 2);
+    }
+
+    #[test]
+    fn params_round_trip_through_their_names() {
+        for param in CurveParam::ALL {
+            assert_eq!(CurveParam::from_name(param.name()), Some(param));
+        }
+    }
+}
@@ -28,6 +28,17 @@ pub enum Distortion {
     Vignette { strength: f64 },
     /// Black rectangle occlusion.
     Occlude { rect: [u32; 4] },
+    /// Exposure clipping: apply a gamma curve, then crush blacks up to
+    /// `black_level` and clip whites down to `white_level`.
+    Clip {
+        black_level: u8,
+        white_level: u8,
+        /// Gamma exponent on normalized intensity (>1 darkens midtones, <1 brightens).
+        gamma: f64,
+    },
+    /// Interlacing / line skip: odd rows come from a second field displaced
+    /// by `(dx, dy)` pixels, producing comb artifacts on edges.
+    Interlace { dx: f64, dy: f64 },
 }
 
 /// Apply a sequence of distortions to an image in-place.
@@ -51,22 +62,28 @@ fn apply_one(img: &mut ImageU8, d: &Distortion) {
         } => apply_gradient_lighting(img, *direction, *min_factor, *max_factor),
         Distortion::Vignette { strength } => apply_vignette(img, *strength),
         Distortion::Occlude { rect } => apply_occlude(img, rect),
+        Distortion::Clip {
+            black_level,
+            white_level,
+            gamma,
+        } => apply_clip(img, *black_level, *white_level, *gamma),
+        Distortion::Interlace { dx, dy } => apply_interlace(img, *dx, *dy),
     }
 }
 
 /// Simple LCG pseudo-random number generator (deterministic, no_std compatible).
-struct Rng {
+pub struct Rng {
     state: u64,
 }
 
 impl Rng {
-    fn new(seed: u64) -> Self {
+    pub fn new(seed: u64) -> Self {
         Self {
             state: seed.wrapping_add(1),
         }
     }
 
-    fn next_u64(&mut self) -> u64 {
+    pub fn next_u64(&mut self) -> u64 {
         // LCG with Knuth's constants
         self.state = self
             .state
@@ -76,7 +93,7 @@ impl Rng {
     }
 
     /// Generate a uniform f64 in [0, 1).
-    fn next_f64(&mut self) -> f64 {
+    pub fn next_f64(&mut self) -> f64 {
         (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
     }
 
@@ -237,6 +254,49 @@ fn apply_occlude(img: &mut ImageU8, rect: &[u32; 4]) {
     }
 }
 
+fn apply_clip(img: &mut ImageU8, black_level: u8, white_level: u8, gamma: f64) {
+    // Precompute the tone curve once; it only depends on the input value.
+    let lut: Vec<u8> = (0..=255u8)
+        .map(|v| {
+            let val = 255.0 * (v as f64 / 255.0).powf(gamma);
+            val.round()
+                .clamp(black_level as f64, white_level.max(black_level) as f64) as u8
+        })
+        .collect();
+    for y in 0..img.height {
+        for x in 0..img.width {
+            img.set(x, y, lut[img.get(x, y) as usize]);
+        }
+    }
+}
+
+fn apply_interlace(img: &mut ImageU8, dx: f64, dy: f64) {
+    if img.width == 0 || img.height == 0 {
+        return;
+    }
+    let src = img.clone();
+    let max_x = (img.width - 1) as f64;
+    let max_y = (img.height - 1) as f64;
+
+    for y in (1..img.height).step_by(2) {
+        for x in 0..img.width {
+            // Bilinear sample of the source at the displaced position
+            let sx = (x as f64 - dx).clamp(0.0, max_x);
+            let sy = (y as f64 - dy).clamp(0.0, max_y);
+            let x0 = sx.floor() as u32;
+            let y0 = sy.floor() as u32;
+            let x1 = (x0 + 1).min(img.width - 1);
+            let y1 = (y0 + 1).min(img.height - 1);
+            let fx = sx - x0 as f64;
+            let fy = sy - y0 as f64;
+            let top = src.get(x0, y0) as f64 * (1.0 - fx) + src.get(x1, y0) as f64 * fx;
+            let bottom = src.get(x0, y1) as f64 * (1.0 - fx) + src.get(x1, y1) as f64 * fx;
+            let val = top * (1.0 - fy) + bottom * fy;
+            img.set(x, y, val.round().clamp(0.0, 255.0) as u8);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,6 +443,63 @@ mod tests {
         assert_eq!(img.get(5, 5), 200); // outside rect
     }
 
+    #[test]
+    fn clip_crushes_blacks_and_clips_whites() {
+        let mut img = ImageU8::new(3, 1);
+        img.set(1, 0, 128);
+        img.set(2, 0, 255);
+        apply(
+            &mut img,
+            &[Distortion::Clip {
+                black_level: 40,
+                white_level: 200,
+                gamma: 1.0,
+            }],
+        );
+        assert_eq!(img.get(0, 0), 40);
+        assert_eq!(img.get(1, 0), 128);
+        assert_eq!(img.get(2, 0), 200);
+    }
+
+    #[test]
+    fn clip_gamma_shifts_midtones() {
+        let mut dark = uniform_image(4, 4, 128);
+        let mut bright = uniform_image(4, 4, 128);
+        apply_clip(&mut dark, 0, 255, 2.0);
+        apply_clip(&mut bright, 0, 255, 0.5);
+        // 255 * 0.502^2 ≈ 64, 255 * 0.502^0.5 ≈ 181
+        assert_eq!(dark.get(0, 0), 64);
+        assert_eq!(bright.get(0, 0), 181);
+    }
+
+    #[test]
+    fn interlace_shifts_odd_rows_only() {
+        // Vertical edge at x = 10
+        let mut img = ImageU8::new(20, 4);
+        for y in 0..4 {
+            for x in 10..20 {
+                img.set(x, y, 255);
+            }
+        }
+        apply(&mut img, &[Distortion::Interlace { dx: 3.0, dy: 0.0 }]);
+
+        // Even rows are untouched
+        assert_eq!(img.get(10, 0), 255);
+        assert_eq!(img.get(9, 2), 0);
+        // Odd rows have the edge moved right by 3 pixels
+        assert_eq!(img.get(12, 1), 0);
+        assert_eq!(img.get(13, 1), 255);
+    }
+
+    #[test]
+    fn interlace_ignores_empty_images() {
+        for (w, h) in [(0, 4), (5, 0), (0, 0)] {
+            let mut img = ImageU8::new(w, h);
+            apply(&mut img, &[Distortion::Interlace { dx: 2.0, dy: 1.0 }]);
+            assert_eq!((img.width, img.height), (w, h));
+        }
+    }
+
     #[test]
     fn apply_salt_pepper_via_apply() {
         // Exercise the SaltPepper arm of apply_one through the public apply() fn
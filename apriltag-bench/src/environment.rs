@@ -0,0 +1,122 @@
+/// Host and build metadata recorded alongside benchmark results, so that
+/// archived results from different machines and builds can be told apart.
+use serde::{Deserialize, Serialize};
+
+/// Where and how a benchmark ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Environment {
+    /// CPU model name, if the platform reports one.
+    pub cpu_model: Option<String>,
+    /// Logical cores available to the process.
+    pub cores: usize,
+    pub os: String,
+    pub arch: String,
+    /// `rustc --version` of the compiler that built the bench.
+    pub rustc: Option<String>,
+    /// `release` or `debug`.
+    pub profile: String,
+    /// Enabled bench features that affect timings.
+    pub features: Vec<String>,
+    /// Commit of the C reference library linked in, if any.
+    pub reference_commit: Option<String>,
+}
+
+impl Environment {
+    /// Describe the current host and build.
+    pub fn capture() -> Self {
+        let features = [
+            ("reference", cfg!(feature = "reference")),
+            ("f32", cfg!(feature = "f32")),
+            ("simd", cfg!(feature = "simd")),
+        ]
+        .into_iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+        Self {
+            cpu_model: cpu_model(),
+            cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            rustc: option_env!("APRILTAG_BENCH_RUSTC").map(str::to_string),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+            features,
+            reference_commit: option_env!("APRILTAG_REFERENCE_COMMIT").map(str::to_string),
+        }
+    }
+}
+
+/// Benchmark results together with the environment they were measured in.
+#[derive(Debug, Serialize)]
+pub struct WithEnvironment<T> {
+    pub environment: Environment,
+    pub results: T,
+}
+
+impl<T> WithEnvironment<T> {
+    /// Attach the current environment to `results`.
+    pub fn new(results: T) -> Self {
+        Self {
+            environment: Environment::capture(),
+            results,
+        }
+    }
+}
+
+fn cpu_model() -> Option<String> {
+    if let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") {
+        return parse_cpuinfo(&cpuinfo);
+    }
+    let out = std::process::Command::new("sysctl")
+        .args(["-n", "machdep.cpu.brand_string"])
+        .output()
+        .ok()?;
+    let model = String::from_utf8(out.stdout).ok()?.trim().to_string();
+    (out.status.success() && !model.is_empty()).then_some(model)
+}
+
+/// The first `model name` (x86) or `Model` (some ARM boards) entry.
+fn parse_cpuinfo(cpuinfo: &str) -> Option<String> {
+    cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        matches!(key.trim(), "model name" | "Model")
+            .then(|| value.trim().to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpuinfo_model_name() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Xeon(R) CPU\nflags\t\t: fpu\n\nprocessor\t: 1\nmodel name\t: Intel(R) Xeon(R) CPU\n";
+        assert_eq!(
+            parse_cpuinfo(cpuinfo).as_deref(),
+            Some("Intel(R) Xeon(R) CPU")
+        );
+        assert_eq!(
+            parse_cpuinfo("processor\t: 0\nModel\t\t: Raspberry Pi 4 Model B\n").as_deref(),
+            Some("Raspberry Pi 4 Model B")
+        );
+        assert_eq!(parse_cpuinfo("processor\t: 0\n"), None);
+    }
+
+    #[test]
+    fn capture_serializes_with_results() {
+        let env = Environment::capture();
+        assert!(env.cores >= 1);
+        assert_eq!(env.os, std::env::consts::OS);
+
+        let json = serde_json::to_value(WithEnvironment::new([1, 2])).unwrap();
+        assert_eq!(json["results"], serde_json::json!([1, 2]));
+        assert!(json["environment"]["features"].is_array());
+    }
+}
@@ -0,0 +1,333 @@
+/// Training-data export: scenes and ground truth as COCO or YOLO annotations.
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::catalog::Scenario;
+use crate::distortion::Rng;
+use crate::scene::{PlacedTag, Scene};
+
+/// Names of the four corner keypoints, in ground-truth order.
+pub const KEYPOINTS: [&str; 4] = ["top_left", "top_right", "bottom_right", "bottom_left"];
+
+/// Annotation file layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationFormat {
+    /// One COCO keypoint-detection JSON file for the whole dataset.
+    Coco,
+    /// One YOLO pose label file per image, plus a `data.yaml`.
+    Yolo,
+}
+
+impl AnnotationFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "coco" => Some(Self::Coco),
+            "yolo" => Some(Self::Yolo),
+            _ => None,
+        }
+    }
+}
+
+/// One exported image and the tags placed in it.
+#[derive(Debug, Clone)]
+pub struct ExportImage<'a> {
+    /// File name relative to the dataset directory.
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+    pub tags: &'a [PlacedTag],
+}
+
+/// Build each scenario's catalog layout, or `trials` random layouts of it
+/// drawn from `seed`, named after the scenario (and trial number).
+pub fn build_scenes(scenarios: &[Scenario], trials: usize, seed: u64) -> Vec<(String, Scene)> {
+    let mut seeds = Rng::new(seed);
+    let mut scenes = Vec::new();
+    for s in scenarios {
+        if trials == 0 {
+            scenes.push((s.name.clone(), s.build()));
+        }
+        for trial in 0..trials {
+            scenes.push((
+                format!("{}-{trial:04}", s.name),
+                s.build_trial(seeds.next_u64()),
+            ));
+        }
+    }
+    scenes
+}
+
+/// Class names, one per tag family, sorted so that class indices do not
+/// depend on scene order.
+pub fn class_names(images: &[ExportImage]) -> Vec<String> {
+    let names: BTreeSet<&str> = images
+        .iter()
+        .flat_map(|img| img.tags.iter().map(|t| t.family_name.as_str()))
+        .collect();
+    names.into_iter().map(str::to_string).collect()
+}
+
+/// Axis-aligned bounding box of the corners clipped to the image, as
+/// `[x, y, width, height]`.
+fn bbox(tag: &PlacedTag, width: u32, height: u32) -> [f64; 4] {
+    let clip = |v: f64, max: u32| v.clamp(0.0, max as f64);
+    let min = |v: [f64; 4]| v.into_iter().fold(f64::INFINITY, f64::min);
+    let max = |v: [f64; 4]| v.into_iter().fold(f64::NEG_INFINITY, f64::max);
+    let xs = tag.corners.map(|c| clip(c[0], width));
+    let ys = tag.corners.map(|c| clip(c[1], height));
+    let (x0, x1, y0, y1) = (min(xs), max(xs), min(ys), max(ys));
+    [x0, y0, x1 - x0, y1 - y0]
+}
+
+/// COCO keypoint visibility: 2 for corners inside the image, 1 for corners
+/// that are labeled but fall outside it.
+fn visibility(corner: [f64; 2], width: u32, height: u32) -> u8 {
+    let inside =
+        (0.0..width as f64).contains(&corner[0]) && (0.0..height as f64).contains(&corner[1]);
+    if inside {
+        2
+    } else {
+        1
+    }
+}
+
+/// A COCO keypoint-detection dataset.
+#[derive(Debug, Serialize)]
+pub struct CocoDataset {
+    pub images: Vec<CocoImage>,
+    pub annotations: Vec<CocoAnnotation>,
+    pub categories: Vec<CocoCategory>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CocoImage {
+    pub id: usize,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CocoAnnotation {
+    pub id: usize,
+    pub image_id: usize,
+    pub category_id: usize,
+    pub bbox: [f64; 4],
+    pub area: f64,
+    pub iscrowd: u8,
+    /// `[x, y, visibility]` for each of [`KEYPOINTS`].
+    pub keypoints: [f64; 12],
+    pub num_keypoints: usize,
+    /// Not part of the COCO schema; ignored by standard loaders.
+    pub tag_id: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CocoCategory {
+    pub id: usize,
+    pub name: String,
+    pub supercategory: &'static str,
+    pub keypoints: [&'static str; 4],
+    /// Keypoint edges (1-based), tracing the tag outline.
+    pub skeleton: [[usize; 2]; 4],
+}
+
+/// Build a COCO dataset. Image, annotation and category IDs start at 1.
+pub fn coco(images: &[ExportImage]) -> CocoDataset {
+    let classes = class_names(images);
+    let categories = classes
+        .iter()
+        .enumerate()
+        .map(|(i, name)| CocoCategory {
+            id: i + 1,
+            name: name.clone(),
+            supercategory: "apriltag",
+            keypoints: KEYPOINTS,
+            skeleton: [[1, 2], [2, 3], [3, 4], [4, 1]],
+        })
+        .collect();
+
+    let mut annotations = Vec::new();
+    for (i, img) in images.iter().enumerate() {
+        for tag in img.tags {
+            let bbox = bbox(tag, img.width, img.height);
+            let mut keypoints = [0.0; 12];
+            for (k, c) in tag.corners.iter().enumerate() {
+                keypoints[3 * k] = c[0];
+                keypoints[3 * k + 1] = c[1];
+                keypoints[3 * k + 2] = visibility(*c, img.width, img.height) as f64;
+            }
+            annotations.push(CocoAnnotation {
+                id: annotations.len() + 1,
+                image_id: i + 1,
+                category_id: class_index(&classes, tag) + 1,
+                bbox,
+                area: bbox[2] * bbox[3],
+                iscrowd: 0,
+                keypoints,
+                num_keypoints: 4,
+                tag_id: tag.tag_id,
+            });
+        }
+    }
+
+    CocoDataset {
+        images: images
+            .iter()
+            .enumerate()
+            .map(|(i, img)| CocoImage {
+                id: i + 1,
+                file_name: img.file_name.clone(),
+                width: img.width,
+                height: img.height,
+            })
+            .collect(),
+        annotations,
+        categories,
+    }
+}
+
+fn class_index(classes: &[String], tag: &PlacedTag) -> usize {
+    classes
+        .iter()
+        .position(|c| *c == tag.family_name)
+        .unwrap_or(classes.len())
+}
+
+/// YOLO pose labels for one image: per tag, the class, the normalized
+/// bounding box center and size, then each corner's normalized position and
+/// visibility.
+pub fn yolo_labels(img: &ExportImage, classes: &[String]) -> String {
+    let (w, h) = (img.width as f64, img.height as f64);
+    let mut out = String::new();
+    for tag in img.tags {
+        let [x, y, bw, bh] = bbox(tag, img.width, img.height);
+        out.push_str(&format!(
+            "{} {:.6} {:.6} {:.6} {:.6}",
+            class_index(classes, tag),
+            (x + bw / 2.0) / w,
+            (y + bh / 2.0) / h,
+            bw / w,
+            bh / h
+        ));
+        for c in tag.corners {
+            out.push_str(&format!(
+                " {:.6} {:.6} {}",
+                c[0] / w,
+                c[1] / h,
+                visibility(c, img.width, img.height)
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The `data.yaml` describing a YOLO pose dataset whose images and labels
+/// sit side by side in one directory.
+pub fn yolo_data_yaml(classes: &[String]) -> String {
+    let mut out = String::from("path: .\ntrain: .\nval: .\nkpt_shape: [4, 3]\nnames:\n");
+    for (i, name) in classes.iter().enumerate() {
+        out.push_str(&format!("  {i}: {name}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(family: &str, corners: [[f64; 2]; 4]) -> PlacedTag {
+        PlacedTag {
+            family_name: family.to_string(),
+            tag_id: 7,
+            corners,
+            center: [0.0, 0.0],
+            gt_rotation: None,
+            gt_translation: None,
+            gt_pose_params: None,
+        }
+    }
+
+    fn square(x: f64, y: f64, s: f64) -> [[f64; 2]; 4] {
+        [[x, y], [x + s, y], [x + s, y + s], [x, y + s]]
+    }
+
+    #[test]
+    fn coco_ids_boxes_and_keypoints() {
+        let a = [tag("tag36h11", square(10.0, 20.0, 30.0))];
+        let b = [
+            tag("tag16h5", square(0.0, 0.0, 10.0)),
+            tag("tag36h11", square(90.0, 90.0, 20.0)),
+        ];
+        let images = [
+            ExportImage {
+                file_name: "a.png".into(),
+                width: 100,
+                height: 100,
+                tags: &a,
+            },
+            ExportImage {
+                file_name: "b.png".into(),
+                width: 100,
+                height: 100,
+                tags: &b,
+            },
+        ];
+        let ds = coco(&images);
+
+        let names: Vec<&str> = ds.categories.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["tag16h5", "tag36h11"]);
+        let ids: Vec<(usize, usize, usize)> = ds
+            .annotations
+            .iter()
+            .map(|a| (a.id, a.image_id, a.category_id))
+            .collect();
+        assert_eq!(ids, [(1, 1, 2), (2, 2, 1), (3, 2, 2)]);
+
+        let first = &ds.annotations[0];
+        assert_eq!(first.bbox, [10.0, 20.0, 30.0, 30.0]);
+        assert_eq!(first.area, 900.0);
+        assert_eq!(&first.keypoints[..3], &[10.0, 20.0, 2.0]);
+
+        // Clipped to the image; corners past the edge are labeled but not visible
+        let clipped = &ds.annotations[2];
+        assert_eq!(clipped.bbox, [90.0, 90.0, 10.0, 10.0]);
+        assert_eq!(clipped.keypoints[2], 2.0);
+        assert_eq!(clipped.keypoints[5], 1.0);
+    }
+
+    #[test]
+    fn yolo_labels_are_normalized() {
+        let tags = [tag("tag36h11", square(10.0, 20.0, 40.0))];
+        let img = ExportImage {
+            file_name: "a.png".into(),
+            width: 200,
+            height: 100,
+            tags: &tags,
+        };
+        let classes = class_names(std::slice::from_ref(&img));
+        let labels = yolo_labels(&img, &classes);
+        let fields: Vec<&str> = labels.split_whitespace().collect();
+        assert_eq!(fields.len(), 5 + 4 * 3);
+        assert_eq!(
+            &fields[..8],
+            ["0", "0.150000", "0.400000", "0.200000", "0.400000", "0.050000", "0.200000", "2"]
+        );
+        assert!(yolo_data_yaml(&classes).ends_with("names:\n  0: tag36h11\n"));
+    }
+
+    #[test]
+    fn format_names() {
+        assert_eq!(
+            AnnotationFormat::from_name("coco"),
+            Some(AnnotationFormat::Coco)
+        );
+        assert_eq!(
+            AnnotationFormat::from_name("yolo"),
+            Some(AnnotationFormat::Yolo)
+        );
+        assert_eq!(AnnotationFormat::from_name("voc"), None);
+    }
+}
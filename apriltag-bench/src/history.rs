@@ -0,0 +1,480 @@
+/// Persistent results history: `run` and `benchmark` append one JSON line per
+/// invocation to a file, and `history` renders per-scenario trends from it.
+///
+/// JSON Lines keeps the store appendable from concurrent CI jobs and readable
+/// with standard tools, without a database dependency.
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::environment::Environment;
+use crate::plot::{Chart, Series};
+use crate::report::ScenarioReport;
+
+/// One `run` or `benchmark` invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Abbreviated commit hash of the working tree, with a `-dirty` suffix
+    /// if it had uncommitted changes; `None` outside a git checkout.
+    pub commit: Option<String>,
+    /// The bench command that produced the record.
+    pub command: String,
+    pub environment: Environment,
+    pub scenarios: Vec<HistoryEntry>,
+}
+
+/// Results of one scenario within a record. Metrics the command does not
+/// measure are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub name: String,
+    pub passed: Option<bool>,
+    pub detection_rate: Option<f64>,
+    pub corner_rmse: Option<f64>,
+    /// Detection time in microseconds: a single run for `run`, the median
+    /// for `benchmark`.
+    pub time_us: Option<u64>,
+    /// Median time of the C reference detector in microseconds.
+    pub ref_time_us: Option<u64>,
+}
+
+impl HistoryEntry {
+    /// The quality metrics of a `run` report.
+    pub fn from_report(report: &ScenarioReport) -> Self {
+        Self {
+            name: report.name.clone(),
+            passed: Some(report.passed),
+            detection_rate: Some(report.detection_rate),
+            corner_rmse: Some(report.corner_rmse),
+            time_us: Some(report.detection_time_us),
+            ref_time_us: None,
+        }
+    }
+
+    /// The timings of a `benchmark` row.
+    pub fn from_timing(name: &str, time_us: u64, ref_time_us: Option<u64>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: None,
+            detection_rate: None,
+            corner_rmse: None,
+            time_us: Some(time_us),
+            ref_time_us,
+        }
+    }
+}
+
+impl HistoryRecord {
+    /// A record of `scenarios` for `command`, stamped with the current time,
+    /// commit and environment.
+    pub fn capture(command: &str, scenarios: Vec<HistoryEntry>) -> Self {
+        Self {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            commit: current_commit(),
+            command: command.to_string(),
+            environment: Environment::capture(),
+            scenarios,
+        }
+    }
+}
+
+/// A per-scenario value that `history` can chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    DetectionRate,
+    CornerRmse,
+    Time,
+    RefTime,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [
+        Metric::DetectionRate,
+        Metric::CornerRmse,
+        Metric::Time,
+        Metric::RefTime,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::DetectionRate => "detection-rate",
+            Metric::CornerRmse => "corner-rmse",
+            Metric::Time => "time",
+            Metric::RefTime => "ref-time",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// The metric's value in display units: percent, pixels or milliseconds.
+    pub fn value(self, entry: &HistoryEntry) -> Option<f64> {
+        match self {
+            Metric::DetectionRate => entry.detection_rate.map(|r| r * 100.0),
+            Metric::CornerRmse => entry.corner_rmse,
+            Metric::Time => entry.time_us.map(|us| us as f64 / 1000.0),
+            Metric::RefTime => entry.ref_time_us.map(|us| us as f64 / 1000.0),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Metric::DetectionRate => "detection rate (%)",
+            Metric::CornerRmse => "corner RMSE (px)",
+            Metric::Time => "time (ms)",
+            Metric::RefTime => "reference time (ms)",
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            Metric::DetectionRate => format!("{value:.0}%"),
+            Metric::CornerRmse => format!("{value:.3}"),
+            Metric::Time | Metric::RefTime => format!("{value:.1}"),
+        }
+    }
+}
+
+/// Append `record` to the history file at `path` as one JSON line, creating
+/// the file if needed.
+pub fn append(path: &Path, record: &HistoryRecord) -> std::io::Result<()> {
+    let line = serde_json::to_string(record)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Read every record of the history file at `path`, oldest first.
+pub fn load(path: &Path) -> std::io::Result<Vec<HistoryRecord>> {
+    parse(&std::fs::read_to_string(path)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Parse history file contents, skipping blank lines.
+pub fn parse(contents: &str) -> Result<Vec<HistoryRecord>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {e}", i + 1)))
+        .collect()
+}
+
+/// The records with a value of `metric` for any scenario matching
+/// `scenario` (substring match), most recent `last` only.
+pub fn select<'a>(
+    records: &'a [HistoryRecord],
+    metric: Metric,
+    scenario: Option<&str>,
+    last: usize,
+) -> Vec<&'a HistoryRecord> {
+    let selected: Vec<_> = records
+        .iter()
+        .filter(|r| {
+            r.scenarios
+                .iter()
+                .any(|e| matches(e, scenario) && metric.value(e).is_some())
+        })
+        .collect();
+    selected[selected.len().saturating_sub(last)..].to_vec()
+}
+
+/// Names of the scenarios matching `scenario` across `records`, in order of
+/// first appearance.
+pub fn scenario_names(records: &[&HistoryRecord], scenario: Option<&str>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in records.iter().flat_map(|r| &r.scenarios) {
+        if matches(entry, scenario) && !names.contains(&entry.name) {
+            names.push(entry.name.clone());
+        }
+    }
+    names
+}
+
+/// A table of `metric` with one row per scenario and one column per record,
+/// oldest on the left, headed by commit and date.
+pub fn trend_table(records: &[&HistoryRecord], metric: Metric, scenario: Option<&str>) -> String {
+    let names = scenario_names(records, scenario);
+    let name_width = names
+        .iter()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max(metric.label().len());
+    let mut out = String::new();
+
+    let _ = write!(out, "{:<name_width$}", metric.label());
+    for r in records {
+        let _ = write!(out, " {:>13}", short_commit(r));
+    }
+    let _ = write!(out, "\n{:<name_width$}", "");
+    for r in records {
+        let _ = write!(out, " {:>13}", format_date(r.timestamp));
+    }
+    let _ = writeln!(out, "\n{}", "-".repeat(name_width + 14 * records.len()));
+
+    for name in &names {
+        let _ = write!(out, "{name:<name_width$}");
+        for r in records {
+            let cell = r
+                .scenarios
+                .iter()
+                .find(|e| &e.name == name)
+                .and_then(|e| metric.value(e))
+                .map_or_else(|| "-".to_string(), |v| metric.format(v));
+            let _ = write!(out, " {cell:>13}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Every metric of the matching scenarios as CSV, one row per scenario per
+/// record.
+pub fn to_csv(records: &[&HistoryRecord], scenario: Option<&str>) -> String {
+    let mut out = String::from("timestamp,commit,command,scenario,passed");
+    for m in Metric::ALL {
+        let _ = write!(out, ",{}", m.name());
+    }
+    out.push('\n');
+    for r in records {
+        for e in r.scenarios.iter().filter(|e| matches(e, scenario)) {
+            let _ = write!(
+                out,
+                "{},{},{},{},{}",
+                r.timestamp,
+                r.commit.as_deref().unwrap_or(""),
+                r.command,
+                e.name,
+                e.passed.map_or(String::new(), |p| p.to_string())
+            );
+            for m in Metric::ALL {
+                let _ = write!(
+                    out,
+                    ",{}",
+                    m.value(e).map_or(String::new(), |v| v.to_string())
+                );
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// One chart per matching scenario of `metric` against record index, for
+/// [`render_svg`](crate::plot::render_svg).
+pub fn trend_charts(
+    records: &[&HistoryRecord],
+    metric: Metric,
+    scenario: Option<&str>,
+) -> Vec<Chart> {
+    scenario_names(records, scenario)
+        .into_iter()
+        .map(|name| {
+            let samples: Vec<(f64, f64)> = records
+                .iter()
+                .enumerate()
+                .filter_map(|(i, r)| {
+                    let entry = r.scenarios.iter().find(|e| e.name == name)?;
+                    Some(((i + 1) as f64, metric.value(entry)?))
+                })
+                .collect();
+            Chart {
+                series: vec![Series::mean_by_x(metric.name(), &samples, false)],
+                title: name,
+                x_label: "record".to_string(),
+                y_label: metric.label().to_string(),
+            }
+        })
+        .collect()
+}
+
+fn matches(entry: &HistoryEntry, scenario: Option<&str>) -> bool {
+    scenario.is_none_or(|pat| entry.name.contains(pat))
+}
+
+fn short_commit(record: &HistoryRecord) -> &str {
+    record.commit.as_deref().unwrap_or("?")
+}
+
+/// Abbreviated hash of `HEAD`, marked `-dirty` if tracked files have
+/// changed, or `None` if git is unavailable or this is not a checkout.
+fn current_commit() -> Option<String> {
+    let git = |args: &[&str]| {
+        let out = std::process::Command::new("git").args(args).output().ok()?;
+        out.status
+            .success()
+            .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    };
+    let hash = git(&["rev-parse", "--short", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+    Some(if dirty { format!("{hash}-dirty") } else { hash })
+}
+
+/// UTC calendar date of a Unix timestamp, as `YYYY-MM-DD`.
+fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = (timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        commit: &str,
+        timestamp: u64,
+        command: &str,
+        entries: Vec<HistoryEntry>,
+    ) -> HistoryRecord {
+        HistoryRecord {
+            timestamp,
+            commit: Some(commit.to_string()),
+            command: command.to_string(),
+            environment: Environment::capture(),
+            scenarios: entries,
+        }
+    }
+
+    fn run_entry(name: &str, rate: f64, time_us: u64) -> HistoryEntry {
+        HistoryEntry {
+            name: name.to_string(),
+            passed: Some(rate >= 1.0),
+            detection_rate: Some(rate),
+            corner_rmse: Some(0.25),
+            time_us: Some(time_us),
+            ref_time_us: None,
+        }
+    }
+
+    fn sample() -> Vec<HistoryRecord> {
+        vec![
+            record("aaaaaaa", 0, "run", vec![run_entry("noise-a", 1.0, 1500)]),
+            record(
+                "bbbbbbb",
+                86_400 * 366,
+                "run",
+                vec![
+                    run_entry("noise-a", 0.5, 2500),
+                    run_entry("blur-b", 1.0, 800),
+                ],
+            ),
+            record(
+                "ccccccc",
+                1_791_331_200,
+                "benchmark",
+                vec![HistoryEntry::from_timing("noise-a", 900, Some(1000))],
+            ),
+        ]
+    }
+
+    #[test]
+    fn records_round_trip_through_lines() {
+        let records = sample();
+        let mut contents = String::new();
+        for r in &records {
+            contents += &serde_json::to_string(r).unwrap();
+            contents += "\n\n";
+        }
+        assert_eq!(parse(&contents).unwrap(), records);
+        let err = parse("{}\n").unwrap_err();
+        assert!(err.starts_with("line 1:"), "{err}");
+    }
+
+    #[test]
+    fn select_skips_records_without_the_metric() {
+        let records = sample();
+        let commits = |sel: Vec<&HistoryRecord>| -> Vec<String> {
+            sel.iter().map(|r| short_commit(r).to_string()).collect()
+        };
+        assert_eq!(
+            commits(select(&records, Metric::Time, None, 10)),
+            ["aaaaaaa", "bbbbbbb", "ccccccc"]
+        );
+        assert_eq!(
+            commits(select(&records, Metric::DetectionRate, None, 10)),
+            ["aaaaaaa", "bbbbbbb"]
+        );
+        assert_eq!(
+            commits(select(&records, Metric::RefTime, None, 10)),
+            ["ccccccc"]
+        );
+        assert_eq!(
+            commits(select(&records, Metric::Time, Some("blur"), 10)),
+            ["bbbbbbb"]
+        );
+        assert_eq!(
+            commits(select(&records, Metric::Time, None, 1)),
+            ["ccccccc"]
+        );
+    }
+
+    #[test]
+    fn trend_table_has_a_column_per_record() {
+        let records = sample();
+        let selected = select(&records, Metric::DetectionRate, None, 10);
+        let table = trend_table(&selected, Metric::DetectionRate, None);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].contains("aaaaaaa") && lines[0].contains("bbbbbbb"));
+        assert!(lines[1].contains("1970-01-01") && lines[1].contains("1971-01-02"));
+        assert!(lines[3].starts_with("noise-a"));
+        assert!(lines[3].contains("100%") && lines[3].contains("50%"));
+        assert!(lines[4].starts_with("blur-b") && lines[4].contains(" -"));
+    }
+
+    #[test]
+    fn csv_has_a_row_per_scenario_per_record() {
+        let records = sample();
+        let all: Vec<&HistoryRecord> = records.iter().collect();
+        let csv = to_csv(&all, Some("noise"));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,commit,command,scenario,passed,detection-rate,corner-rmse,time,ref-time"
+        );
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "0,aaaaaaa,run,noise-a,true,100,0.25,1.5,");
+        assert_eq!(lines[3], "1791331200,ccccccc,benchmark,noise-a,,,,0.9,1");
+    }
+
+    #[test]
+    fn charts_plot_each_scenario_by_record() {
+        let records = sample();
+        let all: Vec<&HistoryRecord> = records.iter().collect();
+        let charts = trend_charts(&all, Metric::Time, None);
+        assert_eq!(charts.len(), 2);
+        assert_eq!(charts[0].title, "noise-a");
+        assert_eq!(
+            charts[0].series[0].points,
+            [(1.0, 1.5), (2.0, 2.5), (3.0, 0.9)]
+        );
+        assert_eq!(charts[1].series[0].points, [(2.0, 0.8)]);
+    }
+
+    #[test]
+    fn dates_are_utc_calendar_days() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_791_331_199), "2026-10-06");
+        assert_eq!(format_date(1_791_331_200), "2026-10-07");
+    }
+}
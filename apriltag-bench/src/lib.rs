@@ -1,10 +1,18 @@
 #![deny(unsafe_code)]
 
+pub mod camera;
 pub mod catalog;
+pub mod curve;
 pub mod distortion;
+pub mod environment;
+pub mod export;
+pub mod history;
 pub mod metrics;
+pub mod plot;
 #[cfg(feature = "reference")]
 pub mod reference;
 pub mod report;
+pub mod runner;
 pub mod scene;
+pub mod sweep;
 pub mod transform;
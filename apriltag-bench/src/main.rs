@@ -3,13 +3,19 @@
 use std::time::Instant;
 
 use apriltag::family;
-use apriltag::{Detector, DetectorBuffers, DetectorConfig};
+use apriltag::{Ablation, Detector, DetectorBuffers, DetectorBuilder, DetectorConfig};
 use clap::{Parser, Subcommand};
 
-use apriltag_bench::catalog::{self, Category, Scenario};
+use apriltag_bench::catalog::{Category, Scenario};
+use apriltag_bench::curve::{self, CurveParam, CurveRange};
 use apriltag_bench::distortion::{self, Distortion};
+#[cfg(feature = "reference")]
+use apriltag_bench::environment::WithEnvironment;
+use apriltag_bench::export::{self, AnnotationFormat, ExportImage};
+use apriltag_bench::history::{self, HistoryEntry, HistoryRecord, Metric};
 use apriltag_bench::metrics;
 use apriltag_bench::report::{self, FullReport};
+use apriltag_bench::runner;
 use apriltag_bench::scene::{Background, SceneBuilder};
 use apriltag_bench::transform::Transform;
 
@@ -39,6 +45,55 @@ enum Command {
         /// Only show failures.
         #[arg(long)]
         quiet: bool,
+        /// After the table, list the N scenarios with the highest corner
+        /// RMSE (terminal format only).
+        #[arg(long)]
+        worst: Option<usize>,
+        /// Append the results, with commit and timestamp, to this history
+        /// file (JSON Lines).
+        #[arg(long)]
+        history: Option<String>,
+    },
+    /// Re-run scenarios over randomized layouts and report detection-rate
+    /// confidence intervals.
+    Trials {
+        /// Filter by category name.
+        #[arg(long)]
+        category: Option<String>,
+        /// Filter by scenario name pattern (substring match).
+        #[arg(long)]
+        scenario: Option<String>,
+        /// Random layouts per scenario.
+        #[arg(long, default_value_t = 50)]
+        trials: usize,
+        /// Seed for the layouts; the same seed reproduces them.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Output format: terminal, json.
+        #[arg(long, default_value = "terminal")]
+        format: String,
+    },
+    /// Re-run scenarios with single pipeline stages skipped or swapped and
+    /// report each change's effect on quality and per-stage time.
+    Ablation {
+        /// Filter by category name.
+        #[arg(long)]
+        category: Option<String>,
+        /// Filter by scenario name pattern (substring match).
+        #[arg(long)]
+        scenario: Option<String>,
+        /// Ablations to run, comma-separated (default: all but no-blur, which
+        /// only matters for scenarios that blur): no-decimation,
+        /// average-decimation, no-blur, deglitch, banded, no-refine-edges,
+        /// no-sharpening, refine-homography, refine-decode.
+        #[arg(long, value_delimiter = ',')]
+        ablation: Vec<String>,
+        /// Detections per scene and configuration; times are the fastest.
+        #[arg(long, default_value_t = 3)]
+        iterations: usize,
+        /// Output format: terminal, json.
+        #[arg(long, default_value = "terminal")]
+        format: String,
     },
     /// List available scenarios.
     List {
@@ -46,7 +101,8 @@ enum Command {
         #[arg(long)]
         category: Option<String>,
     },
-    /// Run all scenarios and exit with code 1 on any failure.
+    /// Run all scenarios and exit with code 1 on any failure, including
+    /// exceeded per-scenario time budgets.
     Regression {
         /// Filter by category.
         #[arg(long)]
@@ -69,6 +125,26 @@ enum Command {
         /// Number of threads (1 = single-threaded, 0 = all cores).
         #[arg(long, default_value_t = 1)]
         threads: usize,
+        /// After the table, list the N scenarios slowest relative to the
+        /// reference (terminal format only).
+        #[arg(long)]
+        worst: Option<usize>,
+        /// Append the timings, with commit and timestamp, to this history
+        /// file (JSON Lines).
+        #[arg(long)]
+        history: Option<String>,
+    },
+    /// Measure detector construction time and memory: Rust vs C reference (requires --features reference).
+    BenchmarkInit {
+        /// Comma-separated tag families (default: all built-in families).
+        #[arg(long)]
+        family: Option<String>,
+        /// Number of constructions per detector (median shown).
+        #[arg(long, default_value_t = 5)]
+        iterations: usize,
+        /// Output format: terminal, json.
+        #[arg(long, default_value = "terminal")]
+        format: String,
     },
     /// Run a comprehensive benchmark sweep: many tags × distortion conditions (requires --features reference).
     BenchmarkSweep {
@@ -84,6 +160,9 @@ enum Command {
         /// Run the complete sweep (all tag configs, conditions, families, decimate levels).
         #[arg(long)]
         full: bool,
+        /// Write SVG charts of time vs tag count per condition to this path.
+        #[arg(long)]
+        plot: Option<String>,
     },
     /// Compare Rust detector vs C reference (requires --features reference).
     Compare {
@@ -97,6 +176,57 @@ enum Command {
         #[arg(long, default_value = "terminal")]
         format: String,
     },
+    /// Sweep one distortion parameter over a single tag and report detection
+    /// rate and corner RMSE at each value.
+    Curve {
+        /// Parameter to sweep: noise, blur, contrast, salt-pepper,
+        /// brightness, rotation, tilt, tag-size.
+        #[arg(long)]
+        param: String,
+        /// Values as start:end:step, e.g. 0:60:5.
+        #[arg(long)]
+        range: String,
+        /// Tag family.
+        #[arg(long, default_value = "tag36h11")]
+        family: String,
+        /// Trials per value.
+        #[arg(long, default_value_t = 20)]
+        trials: usize,
+        /// Seed for tag placement and random distortions.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Output format: terminal, csv, json.
+        #[arg(long, default_value = "terminal")]
+        format: String,
+        /// Write SVG charts of detection rate and corner RMSE to this path.
+        #[arg(long)]
+        plot: Option<String>,
+        /// Also detect each scene with the `refine_decode` stage enabled and
+        /// report its corner RMSE alongside.
+        #[arg(long)]
+        refine_decode: bool,
+    },
+    /// Show per-scenario trends from a history file written by `run` or
+    /// `benchmark --history`.
+    History {
+        /// History file (JSON Lines).
+        file: String,
+        /// Filter by scenario name pattern (substring match).
+        #[arg(long)]
+        scenario: Option<String>,
+        /// Metric to show: detection-rate, corner-rmse, time, ref-time.
+        #[arg(long, default_value = "time")]
+        metric: String,
+        /// Show the N most recent records that have the metric.
+        #[arg(long, default_value_t = 10)]
+        last: usize,
+        /// Output format: terminal, csv (every metric).
+        #[arg(long, default_value = "terminal")]
+        format: String,
+        /// Write SVG charts of the metric per scenario to this path.
+        #[arg(long)]
+        plot: Option<String>,
+    },
     /// Generate test images for all scenarios and save to output directory.
     GenerateImages {
         /// Filter by category name.
@@ -109,6 +239,27 @@ enum Command {
         #[arg(long, default_value = "output")]
         output: String,
     },
+    /// Export scenes and ground truth as a COCO or YOLO training dataset.
+    ExportDataset {
+        /// Filter by category name.
+        #[arg(long)]
+        category: Option<String>,
+        /// Filter by scenario name pattern (substring match).
+        #[arg(long)]
+        scenario: Option<String>,
+        /// Annotation format: coco, yolo.
+        #[arg(long, default_value = "coco")]
+        format: String,
+        /// Output directory for images and annotations.
+        #[arg(long, default_value = "dataset")]
+        output: String,
+        /// Random layouts per scenario (0 = the catalog layout only).
+        #[arg(long, default_value_t = 0)]
+        trials: usize,
+        /// Seed for the layouts; the same seed reproduces them.
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
     /// Start a local HTTP server for the web UI.
     Serve {
         /// Port to listen on.
@@ -156,6 +307,12 @@ enum Command {
         /// Number of detection iterations.
         #[arg(long, default_value_t = 1000)]
         iterations: usize,
+        /// Minimum intensity step across a cluster boundary (0 = disabled).
+        #[arg(long, default_value_t = 0)]
+        min_edge_gradient: u8,
+        /// Fit quads to at most this many clusters per frame.
+        #[arg(long)]
+        max_clusters: Option<usize>,
     },
     /// Generate and detect a single scene with custom parameters.
     Explore {
@@ -192,6 +349,12 @@ enum Command {
         /// Image height.
         #[arg(long, default_value_t = 500)]
         height: u32,
+        /// Minimum intensity step across a cluster boundary (0 = disabled).
+        #[arg(long, default_value_t = 0)]
+        min_edge_gradient: u8,
+        /// Fit quads to at most this many clusters per frame.
+        #[arg(long)]
+        max_clusters: Option<usize>,
         /// Output format: terminal, json.
         #[arg(long, default_value = "terminal")]
         format: String,
@@ -208,7 +371,31 @@ fn main() {
             format,
             threshold,
             quiet,
-        } => cmd_run(category, scenario, &format, threshold, quiet),
+            worst,
+            history,
+        } => cmd_run(
+            category,
+            scenario,
+            &format,
+            threshold,
+            quiet,
+            worst,
+            history.as_deref(),
+        ),
+        Command::Trials {
+            category,
+            scenario,
+            trials,
+            seed,
+            format,
+        } => cmd_trials(category, scenario, trials, seed, &format),
+        Command::Ablation {
+            category,
+            scenario,
+            ablation,
+            iterations,
+            format,
+        } => cmd_ablation(category, scenario, &ablation, iterations, &format),
         Command::List { category } => cmd_list(category),
         Command::Regression { category } => cmd_regression(category),
         Command::Benchmark {
@@ -217,23 +404,75 @@ fn main() {
             iterations,
             format,
             threads,
-        } => cmd_benchmark(category, scenario, iterations, &format, threads),
+            worst,
+            history,
+        } => cmd_benchmark(
+            category, scenario, iterations, &format, threads, worst, history,
+        ),
+        Command::BenchmarkInit {
+            family,
+            iterations,
+            format,
+        } => cmd_benchmark_init(family, iterations, &format),
         Command::BenchmarkSweep {
             iterations,
             format,
             threads,
             full,
-        } => cmd_benchmark_sweep(iterations, &format, threads, full),
+            plot,
+        } => cmd_benchmark_sweep(iterations, &format, threads, full, plot.as_deref()),
         Command::Compare {
             category,
             scenario,
             format,
         } => cmd_compare(category, scenario, &format),
+        Command::Curve {
+            param,
+            range,
+            family,
+            trials,
+            seed,
+            format,
+            plot,
+            refine_decode,
+        } => cmd_curve(
+            &param,
+            &range,
+            &family,
+            trials,
+            seed,
+            &format,
+            plot.as_deref(),
+            refine_decode,
+        ),
+        Command::History {
+            file,
+            scenario,
+            metric,
+            last,
+            format,
+            plot,
+        } => cmd_history(
+            &file,
+            scenario.as_deref(),
+            &metric,
+            last,
+            &format,
+            plot.as_deref(),
+        ),
         Command::GenerateImages {
             category,
             scenario,
             output,
         } => cmd_generate_images(category, scenario, &output),
+        Command::ExportDataset {
+            category,
+            scenario,
+            format,
+            output,
+            trials,
+            seed,
+        } => cmd_export_dataset(category, scenario, &format, &output, trials, seed),
         Command::Serve { port } => cmd_serve(port),
         Command::Profile {
             scenario,
@@ -249,9 +488,24 @@ fn main() {
             width,
             height,
             iterations,
+            min_edge_gradient,
+            max_clusters,
         } => cmd_profile(
-            scenario, &family, tag_id, tag_size, rotation, tilt_x, tilt_y, noise, blur, contrast,
-            width, height, iterations,
+            scenario,
+            &family,
+            tag_id,
+            tag_size,
+            rotation,
+            tilt_x,
+            tilt_y,
+            noise,
+            blur,
+            contrast,
+            width,
+            height,
+            iterations,
+            min_edge_gradient,
+            max_clusters,
         ),
         Command::Explore {
             family,
@@ -265,95 +519,202 @@ fn main() {
             contrast,
             width,
             height,
+            min_edge_gradient,
+            max_clusters,
             format,
         } => cmd_explore(
-            &family, tag_id, tag_size, rotation, tilt_x, tilt_y, noise, blur, contrast, width,
-            height, &format,
+            &family,
+            tag_id,
+            tag_size,
+            rotation,
+            tilt_x,
+            tilt_y,
+            noise,
+            blur,
+            contrast,
+            width,
+            height,
+            min_edge_gradient,
+            max_clusters,
+            &format,
         ),
     }
 }
 
 fn filter_scenarios(category: Option<String>, scenario: Option<String>) -> Vec<Scenario> {
-    let mut scenarios = if let Some(cat_name) = &category {
-        let cat =
-            Category::from_name(cat_name).unwrap_or_else(|| panic!("unknown category: {cat_name}"));
-        catalog::scenarios_for_category(cat)
-    } else {
-        catalog::all_scenarios()
-    };
-
-    if let Some(pattern) = &scenario {
-        scenarios.retain(|s| s.name.contains(pattern.as_str()));
-    }
-
-    scenarios
+    let category = category.map(|cat_name| {
+        Category::from_name(&cat_name).unwrap_or_else(|| panic!("unknown category: {cat_name}"))
+    });
+    runner::select_scenarios(category, scenario.as_deref())
 }
 
-fn run_scenario(scenario: &Scenario) -> (metrics::SceneResult, std::time::Duration) {
-    let scene = scenario.build();
+fn cmd_run(
+    category: Option<String>,
+    scenario: Option<String>,
+    format: &str,
+    threshold_override: f64,
+    quiet: bool,
+    worst: Option<usize>,
+    history_file: Option<&str>,
+) {
+    let scenarios = filter_scenarios(category, scenario);
 
-    let mut config = DetectorConfig::default();
-    if let Some(decimate) = scenario.quad_decimate {
-        config.quad_decimate = decimate;
+    let threshold = (threshold_override > 0.0).then_some(threshold_override);
+    let mut reports = runner::run_scenarios(&scenarios, threshold);
+    if let Some(path) = history_file {
+        let entries = reports.iter().map(HistoryEntry::from_report).collect();
+        append_history(path, &HistoryRecord::capture("run", entries));
+    }
+    if quiet {
+        reports.retain(|r| !r.passed);
     }
 
-    let mut detector = Detector::new(config);
-    let unique_families: Vec<&str> = scenario
-        .expect_ids
-        .iter()
-        .map(|(f, _)| f.as_str())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-    for fam_name in &unique_families {
-        if let Some(fam) = family::builtin_family(fam_name) {
-            detector.add_family(fam, 2);
+    let full = FullReport::from_scenarios(reports);
+
+    match format {
+        "json" => println!("{}", report::to_json(&full)),
+        _ => {
+            report::print_terminal(&full);
+            if let Some(n) = worst {
+                report::print_worst(&full, n);
+            }
         }
     }
+}
 
-    let start = Instant::now();
-    let detections = detector.detect(&scene.image, &mut DetectorBuffers::new());
-    let elapsed = start.elapsed();
+fn cmd_trials(
+    category: Option<String>,
+    scenario: Option<String>,
+    trials: usize,
+    seed: u64,
+    format: &str,
+) {
+    let scenarios = filter_scenarios(category, scenario);
+    let reports: Vec<_> = scenarios
+        .iter()
+        .map(|s| runner::run_trials(s, trials, seed))
+        .collect();
 
-    let result = metrics::evaluate(&scene.ground_truth, &detections, elapsed.as_micros() as u64);
-    (result, elapsed)
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        _ => report::print_trials_terminal(&reports),
+    }
 }
 
-fn cmd_run(
+fn cmd_ablation(
     category: Option<String>,
     scenario: Option<String>,
+    names: &[String],
+    iterations: usize,
     format: &str,
-    threshold_override: f64,
-    quiet: bool,
 ) {
     let scenarios = filter_scenarios(category, scenario);
+    let ablations: Vec<Ablation> = if names.is_empty() {
+        Ablation::DEFAULT.to_vec()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                Ablation::from_name(name).unwrap_or_else(|| panic!("unknown ablation: {name}"))
+            })
+            .collect()
+    };
+    let reports = runner::run_ablations(&scenarios, &ablations, iterations);
 
-    let mut reports = Vec::new();
-    for s in &scenarios {
-        let threshold = if threshold_override > 0.0 {
-            threshold_override
-        } else {
-            s.max_corner_rmse
-        };
-        let (result, _) = run_scenario(s);
-        let r = report::scenario_report(
-            &s.name,
-            s.category.name(),
-            &result,
-            s.expect_ids.len(),
-            threshold,
-            s.max_rotation_error_deg,
-        );
-        if !quiet || !r.passed {
-            reports.push(r);
-        }
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        _ => report::print_ablations_terminal(&reports),
     }
+}
 
-    let full = FullReport::from_scenarios(reports);
+fn append_history(path: &str, record: &HistoryRecord) {
+    history::append(std::path::Path::new(path), record)
+        .unwrap_or_else(|e| panic!("cannot append to {path}: {e}"));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_curve(
+    param: &str,
+    range: &str,
+    family_name: &str,
+    trials: usize,
+    seed: u64,
+    format: &str,
+    plot: Option<&str>,
+    refine_decode: bool,
+) {
+    let param = CurveParam::from_name(param).unwrap_or_else(|| {
+        let names: Vec<_> = CurveParam::ALL.iter().map(|p| p.name()).collect();
+        panic!("unknown parameter '{param}', use {}", names.join(", "))
+    });
+    let range = CurveRange::parse(range).unwrap_or_else(|e| panic!("{e}"));
+    let detector = single_family_detector(family_name, 0, None);
+    let refined = refine_decode.then(|| {
+        let mut config = detector.config.clone();
+        config.refine_decode = true;
+        DetectorBuilder::with_config(config)
+            .family(family_name, 2)
+            .build()
+    });
+    let curve = curve::run_curve(
+        &detector,
+        refined.as_ref().map(|d| ("refine_decode", d)),
+        param,
+        &range.values(),
+        family_name,
+        trials,
+        seed,
+    );
 
     match format {
-        "json" => println!("{}", report::to_json(&full)),
-        _ => report::print_terminal(&full),
+        "csv" => print!("{}", curve.to_csv()),
+        "json" => println!("{}", serde_json::to_string_pretty(&curve).unwrap()),
+        _ => print!("{}", curve.to_table()),
+    }
+
+    if let Some(path) = plot {
+        std::fs::write(path, apriltag_bench::plot::render_svg(&curve.charts(), 2))
+            .unwrap_or_else(|e| panic!("cannot write {path}: {e}"));
+        eprintln!("Wrote charts to {path}");
+    }
+}
+
+fn cmd_history(
+    file: &str,
+    scenario: Option<&str>,
+    metric: &str,
+    last: usize,
+    format: &str,
+    plot: Option<&str>,
+) {
+    let metric = Metric::from_name(metric).unwrap_or_else(|| {
+        panic!("unknown metric '{metric}', use detection-rate, corner-rmse, time or ref-time")
+    });
+    let records = history::load(std::path::Path::new(file))
+        .unwrap_or_else(|e| panic!("cannot read {file}: {e}"));
+    let selected = history::select(&records, metric, scenario, last);
+
+    match format {
+        "csv" => print!("{}", history::to_csv(&selected, scenario)),
+        _ => {
+            if selected.is_empty() {
+                println!("No records with {} in {file}", metric.name());
+            } else {
+                print!("{}", history::trend_table(&selected, metric, scenario));
+                println!(
+                    "\n{} of {} records shown, oldest first",
+                    selected.len(),
+                    records.len()
+                );
+            }
+        }
+    }
+
+    if let Some(path) = plot {
+        let charts = history::trend_charts(&selected, metric, scenario);
+        std::fs::write(path, apriltag_bench::plot::render_svg(&charts, 3))
+            .unwrap_or_else(|e| panic!("cannot write {path}: {e}"));
+        eprintln!("Wrote {} charts to {path}", charts.len());
     }
 }
 
@@ -369,19 +730,8 @@ fn cmd_list(category: Option<String>) {
 
 fn cmd_regression(category: Option<String>) {
     let scenarios = filter_scenarios(category, None);
-
-    let mut reports = Vec::new();
-    for s in &scenarios {
-        let (result, _) = run_scenario(s);
-        reports.push(report::scenario_report(
-            &s.name,
-            s.category.name(),
-            &result,
-            s.expect_ids.len(),
-            s.max_corner_rmse,
-            s.max_rotation_error_deg,
-        ));
-    }
+    let mut reports = runner::run_scenarios(&scenarios, None);
+    runner::enforce_time_budgets(&scenarios, &mut reports);
 
     let full = FullReport::from_scenarios(reports);
     report::print_terminal(&full);
@@ -429,6 +779,81 @@ fn cmd_generate_images(category: Option<String>, scenario: Option<String>, outpu
     println!("\nGenerated {} images in {output_dir}/", scenarios.len());
 }
 
+fn cmd_export_dataset(
+    category: Option<String>,
+    scenario: Option<String>,
+    format: &str,
+    output_dir: &str,
+    trials: usize,
+    seed: u64,
+) {
+    let format = AnnotationFormat::from_name(format)
+        .unwrap_or_else(|| panic!("unknown format '{format}', use 'coco' or 'yolo'"));
+    let scenarios = filter_scenarios(category, scenario);
+    let out = std::path::Path::new(output_dir);
+    std::fs::create_dir_all(out).unwrap_or_else(|e| panic!("cannot create {output_dir}: {e}"));
+
+    let scenes = export::build_scenes(&scenarios, trials, seed);
+
+    let images: Vec<ExportImage> = scenes
+        .iter()
+        .map(|(name, scene)| ExportImage {
+            file_name: format!("{name}.png"),
+            width: scene.image.width,
+            height: scene.image.height,
+            tags: &scene.ground_truth,
+        })
+        .collect();
+    for (img, (_, scene)) in images.iter().zip(&scenes) {
+        let path = out.join(&img.file_name);
+        write_png(&scene.image, &path)
+            .unwrap_or_else(|e| panic!("cannot write {}: {e}", path.display()));
+    }
+
+    let write = |name: &str, contents: String| {
+        let path = out.join(name);
+        std::fs::write(&path, contents)
+            .unwrap_or_else(|e| panic!("cannot write {}: {e}", path.display()));
+    };
+    match format {
+        AnnotationFormat::Coco => write(
+            "annotations.json",
+            serde_json::to_string(&export::coco(&images))
+                .unwrap_or_else(|e| panic!("cannot serialize annotations: {e}")),
+        ),
+        AnnotationFormat::Yolo => {
+            let classes = export::class_names(&images);
+            for img in &images {
+                write(
+                    &img.file_name.replace(".png", ".txt"),
+                    export::yolo_labels(img, &classes),
+                );
+            }
+            write("data.yaml", export::yolo_data_yaml(&classes));
+        }
+    }
+
+    let tags: usize = images.iter().map(|img| img.tags.len()).sum();
+    println!(
+        "Exported {} images with {tags} tags to {output_dir}/",
+        images.len()
+    );
+}
+
+/// Write an 8-bit grayscale PNG.
+fn write_png(img: &apriltag::ImageU8, path: &std::path::Path) -> Result<(), png::EncodingError> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, img.width, img.height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let mut data = Vec::with_capacity(img.width as usize * img.height as usize);
+    for y in 0..img.height {
+        data.extend_from_slice(img.row(y));
+    }
+    writer.write_image_data(&data)
+}
+
 fn cmd_serve(port: u16) {
     // Serve the web UI from the project root so that both ui/ and WASM pkg/ dirs are accessible
     let ui_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
@@ -483,10 +908,20 @@ fn cmd_benchmark(
     iterations: usize,
     format: &str,
     threads: usize,
+    worst: Option<usize>,
+    history_file: Option<String>,
 ) {
     #[cfg(not(feature = "reference"))]
     {
-        let _ = (category, scenario, iterations, format, threads);
+        let _ = (
+            category,
+            scenario,
+            iterations,
+            format,
+            threads,
+            worst,
+            history_file,
+        );
         eprintln!("Error: the 'benchmark' command requires the 'reference' feature.");
         eprintln!("Build with: cargo run -p apriltag-bench --features reference -- benchmark");
         eprintln!("Make sure to run scripts/fetch-references.sh first.");
@@ -501,7 +936,17 @@ fn cmd_benchmark(
             .build()
             .expect("failed to create thread pool");
 
-        pool.install(|| cmd_benchmark_inner(category, scenario, iterations, format, threads));
+        pool.install(|| {
+            cmd_benchmark_inner(
+                category,
+                scenario,
+                iterations,
+                format,
+                threads,
+                worst,
+                history_file.as_deref(),
+            )
+        });
     }
 }
 
@@ -512,6 +957,8 @@ fn cmd_benchmark_inner(
     iterations: usize,
     format: &str,
     threads: usize,
+    worst: Option<usize>,
+    history_file: Option<&str>,
 ) {
     use apriltag_bench::reference::{PersistentReferenceDetector, ReferenceConfig};
 
@@ -542,26 +989,10 @@ fn cmd_benchmark_inner(
         let img = &scene.image;
         let size = [img.width, img.height];
 
-        // Collect unique families for this scenario
-        let families: Vec<&str> = s
-            .expect_ids
-            .iter()
-            .map(|(f, _)| f.as_str())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
+        let families = runner::scenario_families(s);
 
         // Create Rust detector (persistent across iterations)
-        let mut rust_config = DetectorConfig::default();
-        if let Some(decimate) = s.quad_decimate {
-            rust_config.quad_decimate = decimate;
-        }
-        let mut rust_detector = Detector::new(rust_config);
-        for fam_name in &families {
-            if let Some(fam) = family::builtin_family(fam_name) {
-                rust_detector.add_family(fam, 2);
-            }
-        }
+        let rust_detector = runner::scenario_detector(s);
 
         // Create C reference detector (persistent across iterations)
         let ref_config = ReferenceConfig {
@@ -577,46 +1008,24 @@ fn cmd_benchmark_inner(
         // Reuse a single buffer across all iterations (matches C which reuses internal allocs)
         let mut buffers = DetectorBuffers::new();
 
-        // Warmup runs (3 iterations to stabilize caches and allocator)
-        for _ in 0..3 {
+        // Warmup runs to stabilize caches and allocator
+        for _ in 0..runner::WARMUP_ITERATIONS {
             let _ = rust_detector.detect(&scene.image, &mut buffers);
             let _ = ref_detector.detect(&scene.image);
         }
 
         // Adaptive iteration count: calibrate from a single timed run,
-        // then target at least 200ms total measurement time per detector.
+        // then target a minimum total measurement time per detector.
         let calib_start = Instant::now();
         let _ = rust_detector.detect(&scene.image, &mut buffers);
-        let calib_dur = calib_start.elapsed();
-        let min_total = std::time::Duration::from_millis(200);
-        let adaptive_iters = if calib_dur.is_zero() {
-            iterations
-        } else {
-            (min_total.as_nanos() / calib_dur.as_nanos()).max(1) as usize
-        }
-        .max(iterations);
+        let adaptive_iters = runner::adaptive_iterations(calib_start.elapsed(), iterations);
 
-        // Benchmark Rust detector
-        let mut rust_times = Vec::with_capacity(adaptive_iters);
-        for _ in 0..adaptive_iters {
-            let start = Instant::now();
+        let rust_median = runner::median_duration(adaptive_iters, || {
             let _ = rust_detector.detect(&scene.image, &mut buffers);
-            rust_times.push(start.elapsed());
-        }
-
-        // Benchmark C reference detector
-        let mut ref_times = Vec::with_capacity(adaptive_iters);
-        for _ in 0..adaptive_iters {
-            let start = Instant::now();
+        });
+        let ref_median = runner::median_duration(adaptive_iters, || {
             let _ = ref_detector.detect(&scene.image);
-            ref_times.push(start.elapsed());
-        }
-
-        rust_times.sort();
-        ref_times.sort();
-
-        let rust_median = rust_times[adaptive_iters / 2];
-        let ref_median = ref_times[adaptive_iters / 2];
+        });
 
         let rust_us = rust_median.as_micros() as u64;
         let ref_us = ref_median.as_micros() as u64;
@@ -649,8 +1058,19 @@ fn cmd_benchmark_inner(
         });
     }
 
+    if let Some(path) = history_file {
+        let entries = rows
+            .iter()
+            .map(|r| HistoryEntry::from_timing(&r.name, r.rust_median_us, Some(r.ref_median_us)))
+            .collect();
+        append_history(path, &HistoryRecord::capture("benchmark", entries));
+    }
+
     if format == "json" {
-        println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&WithEnvironment::new(&rows)).unwrap()
+        );
     } else {
         println!("{}", "-".repeat(85));
 
@@ -680,13 +1100,178 @@ fn cmd_benchmark_inner(
             threads,
             if threads == 1 { "" } else { "s" },
         );
+
+        if let Some(n) = worst {
+            let mut slowest: Vec<&BenchRow> = rows.iter().collect();
+            slowest.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+            slowest.truncate(n);
+            if !slowest.is_empty() {
+                println!("\nWorst {} by Rust/reference time ratio:", slowest.len());
+                for r in slowest {
+                    println!(
+                        "  {:<35} {:>9.2}x  (Rust {:.1} ms, Ref {:.1} ms)",
+                        &r.name,
+                        r.ratio,
+                        r.rust_median_us as f64 / 1000.0,
+                        r.ref_median_us as f64 / 1000.0,
+                    );
+                }
+            }
+        }
     }
 }
 
-fn cmd_benchmark_sweep(iterations: usize, format: &str, threads: usize, full: bool) {
+fn cmd_benchmark_init(family: Option<String>, iterations: usize, format: &str) {
+    let families: Vec<String> = match family {
+        Some(list) => list.split(',').map(|f| f.trim().to_string()).collect(),
+        None => family::BUILTIN_NAMES
+            .iter()
+            .map(|f| f.to_string())
+            .collect(),
+    };
+    for name in &families {
+        if family::builtin_family(name).is_none() {
+            eprintln!("Unknown family: {name}");
+            std::process::exit(1);
+        }
+    }
+
     #[cfg(not(feature = "reference"))]
     {
-        let _ = (iterations, format, threads, full);
+        let _ = (families, iterations, format);
+        eprintln!("Error: the 'benchmark-init' command requires the 'reference' feature.");
+        eprintln!("Build with: cargo run -p apriltag-bench --features reference -- benchmark-init");
+        eprintln!("Make sure to run scripts/fetch-references.sh first.");
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "reference")]
+    cmd_benchmark_init_inner(&families, iterations.max(1), format);
+}
+
+/// Construction cost of a single-family detector, Rust vs C.
+///
+/// Memory is measured in a first pass, before any timing loop has churned the
+/// allocator: each detector is kept alive while the RSS delta is taken. RSS
+/// deltas are page-granular and miss memory the allocator reuses, so the
+/// exact size of the Rust decode tables is reported alongside. The C
+/// `quick_decode` table is a hash of every code within the Hamming limit and
+/// usually dominates its RSS delta.
+#[cfg(feature = "reference")]
+fn cmd_benchmark_init_inner(families: &[String], iterations: usize, format: &str) {
+    use apriltag_bench::reference::{PersistentReferenceDetector, ReferenceConfig};
+
+    #[derive(serde::Serialize)]
+    struct InitRow {
+        family: String,
+        rust_build_us: u64,
+        ref_build_us: u64,
+        rust_rss_bytes: Option<u64>,
+        ref_rss_bytes: Option<u64>,
+        rust_table_bytes: usize,
+        iterations: usize,
+    }
+
+    let ref_config = ReferenceConfig {
+        quad_decimate: 2.0,
+        nthreads: 1,
+    };
+    let build_rust = |name: &str| {
+        let mut detector = Detector::new(DetectorConfig::default());
+        if let Some(fam) = family::builtin_family(name) {
+            detector.add_family(fam, 2);
+        }
+        detector
+    };
+    let rss_delta = |before: Option<u64>, after: Option<u64>| Some(after?.saturating_sub(before?));
+
+    // Pass 1: memory
+    let mut rows: Vec<InitRow> = families
+        .iter()
+        .map(|name| {
+            let before = runner::resident_memory_bytes();
+            let rust = build_rust(name);
+            let after_rust = runner::resident_memory_bytes();
+            let reference = PersistentReferenceDetector::new(name, &ref_config);
+            let after_ref = runner::resident_memory_bytes();
+            let row = InitRow {
+                family: name.clone(),
+                rust_build_us: 0,
+                ref_build_us: 0,
+                rust_rss_bytes: rss_delta(before, after_rust),
+                ref_rss_bytes: rss_delta(after_rust, after_ref),
+                rust_table_bytes: rust.decode_table_bytes(),
+                iterations,
+            };
+            drop(reference);
+            drop(rust);
+            row
+        })
+        .collect();
+
+    // Pass 2: construction time. Detectors are kept until the timing loop
+    // ends so that teardown is not measured.
+    for row in &mut rows {
+        let mut kept = Vec::with_capacity(iterations);
+        let rust_median =
+            runner::median_duration(iterations, || kept.push(build_rust(&row.family)));
+        drop(kept);
+
+        let mut kept = Vec::with_capacity(iterations);
+        let ref_median = runner::median_duration(iterations, || {
+            kept.push(PersistentReferenceDetector::new(&row.family, &ref_config));
+        });
+        drop(kept);
+
+        row.rust_build_us = rust_median.as_micros() as u64;
+        row.ref_build_us = ref_median.as_micros() as u64;
+    }
+
+    if format == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&WithEnvironment::new(&rows)).unwrap()
+        );
+        return;
+    }
+
+    let kib = |bytes: Option<u64>| match bytes {
+        Some(b) => format!("{:.1}", b as f64 / 1024.0),
+        None => "--".to_string(),
+    };
+    println!(
+        "{:<20} {:>10} {:>10} {:>12} {:>12} {:>12}",
+        "Family", "Rust(ms)", "Ref(ms)", "Rust RSS KiB", "Ref RSS KiB", "Table KiB"
+    );
+    println!("{}", "-".repeat(81));
+    for row in &rows {
+        println!(
+            "{:<20} {:>10.2} {:>10.2} {:>12} {:>12} {:>12.1}",
+            row.family,
+            row.rust_build_us as f64 / 1000.0,
+            row.ref_build_us as f64 / 1000.0,
+            kib(row.rust_rss_bytes),
+            kib(row.ref_rss_bytes),
+            row.rust_table_bytes as f64 / 1024.0,
+        );
+    }
+    println!("{}", "-".repeat(81));
+    println!(
+        "\nSingle-family detectors, max Hamming 2, {iterations} constructions each, median times shown.\n\
+         RSS deltas are page-granular; 'Table' is the exact size of the Rust decode tables."
+    );
+}
+
+fn cmd_benchmark_sweep(
+    iterations: usize,
+    format: &str,
+    threads: usize,
+    full: bool,
+    plot: Option<&str>,
+) {
+    #[cfg(not(feature = "reference"))]
+    {
+        let _ = (iterations, format, threads, full, plot);
         eprintln!("Error: the 'benchmark-sweep' command requires the 'reference' feature.");
         eprintln!(
             "Build with: cargo run -p apriltag-bench --features reference -- benchmark-sweep"
@@ -703,13 +1288,21 @@ fn cmd_benchmark_sweep(iterations: usize, format: &str, threads: usize, full: bo
             .build()
             .expect("failed to create thread pool");
 
-        pool.install(|| cmd_benchmark_sweep_inner(iterations, format, threads, full));
+        pool.install(|| cmd_benchmark_sweep_inner(iterations, format, threads, full, plot));
     }
 }
 
 #[cfg(feature = "reference")]
-fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, full: bool) {
+fn cmd_benchmark_sweep_inner(
+    iterations: usize,
+    format: &str,
+    threads: usize,
+    full: bool,
+    plot: Option<&str>,
+) {
+    use apriltag_bench::plot::{self as svg_plot, Chart, Series};
     use apriltag_bench::reference::{PersistentReferenceDetector, ReferenceConfig};
+    use apriltag_bench::sweep::{self, SweepPlan};
 
     #[derive(serde::Serialize)]
     struct BenchRow {
@@ -725,223 +1318,27 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
         iterations: usize,
     }
 
-    struct SweepScene {
-        name: String,
-        tags: usize,
-        condition: String,
-        families: String,
-        quad_decimate: f32,
-        scene: apriltag_bench::scene::Scene,
-    }
-
-    // Full tag configs: (n_tags, width, height, tag_scale)
-    let all_tag_configs: &[(usize, u32, u32, f64)] = &[
-        (1, 500, 500, 80.0),
-        (5, 800, 600, 50.0),
-        (10, 1000, 800, 45.0),
-        (25, 1280, 960, 35.0),
-        (25, 2000, 1500, 50.0),
-        (50, 4000, 3000, 45.0),
-        (70, 6000, 4000, 40.0),
-    ];
-    let quick_tag_configs: &[(usize, u32, u32, f64)] = &[
-        (1, 500, 500, 80.0),
-        (25, 2000, 1500, 50.0),
-        (70, 6000, 4000, 40.0),
-    ];
-    let tag_configs = if full {
-        all_tag_configs
-    } else {
-        quick_tag_configs
-    };
-
-    // Family modes
-    let all_family_modes: &[&str] = &["single", "mixed"];
-    let family_modes = all_family_modes; // both in quick and full
-
-    // Decimate levels
-    let all_decimates: &[f32] = &[1.0, 2.0, 4.0];
-    let quick_decimates: &[f32] = &[1.0, 2.0];
-    let decimates = if full { all_decimates } else { quick_decimates };
-
-    // Distortion conditions
-    struct Condition {
-        name: &'static str,
-        rotation_deg: f64,
-        tilt_x_deg: f64,
-        distortions: Vec<Distortion>,
-    }
-
-    let all_conditions = vec![
-        Condition {
-            name: "clean",
-            rotation_deg: 0.0,
-            tilt_x_deg: 0.0,
-            distortions: vec![],
-        },
-        Condition {
-            name: "rotation-30",
-            rotation_deg: 30.0,
-            tilt_x_deg: 0.0,
-            distortions: vec![],
-        },
-        Condition {
-            name: "tilt-20",
-            rotation_deg: 0.0,
-            tilt_x_deg: 20.0,
-            distortions: vec![],
-        },
-        Condition {
-            name: "noise-20",
-            rotation_deg: 0.0,
-            tilt_x_deg: 0.0,
-            distortions: vec![Distortion::GaussianNoise {
-                sigma: 20.0,
-                seed: 42,
-            }],
-        },
-        Condition {
-            name: "blur-2",
-            rotation_deg: 0.0,
-            tilt_x_deg: 0.0,
-            distortions: vec![Distortion::GaussianBlur { sigma: 2.0 }],
-        },
-        Condition {
-            name: "contrast-25",
-            rotation_deg: 0.0,
-            tilt_x_deg: 0.0,
-            distortions: vec![Distortion::ContrastScale { factor: 0.25 }],
-        },
-        Condition {
-            name: "combined",
-            rotation_deg: 15.0,
-            tilt_x_deg: 15.0,
-            distortions: vec![
-                Distortion::GaussianNoise {
-                    sigma: 10.0,
-                    seed: 42,
-                },
-                Distortion::GaussianBlur { sigma: 1.0 },
-            ],
-        },
-    ];
-
-    // In quick mode, only use clean + combined
-    let conditions: Vec<&Condition> = if full {
-        all_conditions.iter().collect()
+    let plan = if full {
+        SweepPlan::full()
     } else {
-        all_conditions
-            .iter()
-            .filter(|c| c.name == "clean" || c.name == "combined")
-            .collect()
+        SweepPlan::quick()
     };
 
-    let total_full =
-        all_tag_configs.len() * all_family_modes.len() * all_decimates.len() * all_conditions.len();
-    let total_scenarios =
-        tag_configs.len() * family_modes.len() * decimates.len() * conditions.len();
-
     if !full && format != "json" {
         println!(
             "Running {} / {} scenarios (pass --full for the complete sweep)\n",
-            total_scenarios, total_full
+            plan.len(),
+            SweepPlan::full().len()
         );
     }
 
-    // Generate all sweep scenes
-    let mut sweep_scenes = Vec::new();
+    let sweep_scenes = plan.scenes();
 
-    for &(n_tags, width, height, tag_scale) in tag_configs {
-        let positions = grid_positions(n_tags, width, height, tag_scale);
-
-        for &family_mode in family_modes {
-            for cond in &conditions {
-                for &decimate in decimates {
-                    let name = format!(
-                        "{}tags-{}-{}-dec{}",
-                        n_tags, family_mode, cond.name, decimate as u32
-                    );
-
-                    let mut builder =
-                        SceneBuilder::new(width, height).background(Background::Solid(128));
-
-                    for (id, &(cx, cy)) in positions.iter().enumerate() {
-                        let tag_family = if family_mode == "mixed" && id % 2 == 1 {
-                            "tagStandard52h13"
-                        } else {
-                            "tag36h11"
-                        };
-
-                        let transform =
-                            if cond.tilt_x_deg.abs() > 0.01 || cond.rotation_deg.abs() > 0.01 {
-                                if cond.tilt_x_deg.abs() > 0.01 {
-                                    Transform::FromPose {
-                                        center: [cx, cy],
-                                        size: tag_scale,
-                                        roll: cond.rotation_deg.to_radians(),
-                                        tilt_x: cond.tilt_x_deg.to_radians(),
-                                        tilt_y: 0.0,
-                                    }
-                                } else {
-                                    Transform::Similarity {
-                                        cx,
-                                        cy,
-                                        scale: tag_scale / 2.0,
-                                        theta: cond.rotation_deg.to_radians(),
-                                    }
-                                }
-                            } else {
-                                Transform::Similarity {
-                                    cx,
-                                    cy,
-                                    scale: tag_scale / 2.0,
-                                    theta: 0.0,
-                                }
-                            };
-
-                        builder = builder.add_tag(tag_family, id as u32, transform);
-                    }
-
-                    let mut scene = builder.build();
-
-                    if !cond.distortions.is_empty() {
-                        distortion::apply(&mut scene.image, &cond.distortions);
-                    }
-
-                    sweep_scenes.push(SweepScene {
-                        name,
-                        tags: n_tags,
-                        condition: cond.name.to_string(),
-                        families: family_mode.to_string(),
-                        quad_decimate: decimate,
-                        scene,
-                    });
-                }
-            }
-        }
-    }
-
-    // Create detectors for single-family and mixed-family modes
     let make_ref_config = |decimate: f32| ReferenceConfig {
         quad_decimate: decimate,
         nthreads: threads as i32,
     };
 
-    let make_rust_detector = |families: &str, decimate: f32| {
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = decimate;
-        let mut detector = Detector::new(config);
-        if let Some(fam) = family::builtin_family("tag36h11") {
-            detector.add_family(fam, 2);
-        }
-        if families == "mixed" {
-            if let Some(fam) = family::builtin_family("tagStandard52h13") {
-                detector.add_family(fam, 2);
-            }
-        }
-        detector
-    };
-
     if format != "json" {
         println!(
             "{:<40} {:>5} {:>8} {:>4} {:>10} {:>10} {:>10} {:>10} {:>6}",
@@ -956,7 +1353,7 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
     // Track current detector config to avoid re-creating unnecessarily
     let mut cur_family_mode = String::new();
     let mut cur_decimate: f32 = -1.0;
-    let mut rust_detector = make_rust_detector("single", 2.0); // placeholder
+    let mut rust_detector = sweep::sweep_detector("single", 2.0); // placeholder
     let mut ref_detector = PersistentReferenceDetector::new("tag36h11", &make_ref_config(2.0)); // placeholder
 
     for ss in &sweep_scenes {
@@ -964,7 +1361,7 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
         if ss.families != cur_family_mode || (ss.quad_decimate - cur_decimate).abs() > 0.001 {
             cur_family_mode = ss.families.clone();
             cur_decimate = ss.quad_decimate;
-            rust_detector = make_rust_detector(&cur_family_mode, cur_decimate);
+            rust_detector = sweep::sweep_detector(&cur_family_mode, cur_decimate);
             let ref_config = make_ref_config(cur_decimate);
             if cur_family_mode == "mixed" {
                 ref_detector = PersistentReferenceDetector::with_families(
@@ -980,7 +1377,7 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
         let size = [img.width, img.height];
 
         // Warmup runs
-        for _ in 0..3 {
+        for _ in 0..runner::WARMUP_ITERATIONS {
             let _ = rust_detector.detect(img, &mut buffers);
             let _ = ref_detector.detect(img);
         }
@@ -988,36 +1385,16 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
         // Adaptive iteration count
         let calib_start = Instant::now();
         let _ = rust_detector.detect(img, &mut buffers);
-        let calib_dur = calib_start.elapsed();
-        let min_total = std::time::Duration::from_millis(200);
-        let adaptive_iters = if calib_dur.is_zero() {
-            iterations
-        } else {
-            (min_total.as_nanos() / calib_dur.as_nanos()).max(1) as usize
-        }
-        .max(iterations);
+        let adaptive_iters = runner::adaptive_iterations(calib_start.elapsed(), iterations);
 
-        // Benchmark Rust
-        let mut rust_times = Vec::with_capacity(adaptive_iters);
-        for _ in 0..adaptive_iters {
-            let start = Instant::now();
+        let rust_us = runner::median_duration(adaptive_iters, || {
             let _ = rust_detector.detect(img, &mut buffers);
-            rust_times.push(start.elapsed());
-        }
-
-        // Benchmark C reference
-        let mut ref_times = Vec::with_capacity(adaptive_iters);
-        for _ in 0..adaptive_iters {
-            let start = Instant::now();
+        })
+        .as_micros() as u64;
+        let ref_us = runner::median_duration(adaptive_iters, || {
             let _ = ref_detector.detect(img);
-            ref_times.push(start.elapsed());
-        }
-
-        rust_times.sort();
-        ref_times.sort();
-
-        let rust_us = rust_times[adaptive_iters / 2].as_micros() as u64;
-        let ref_us = ref_times[adaptive_iters / 2].as_micros() as u64;
+        })
+        .as_micros() as u64;
         let ratio = if ref_us > 0 {
             rust_us as f64 / ref_us as f64
         } else {
@@ -1055,13 +1432,16 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
     }
 
     if format == "json" {
-        println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&WithEnvironment::new(&rows)).unwrap()
+        );
     } else {
         println!("{}", "-".repeat(109));
 
         // Per-condition summary
         println!("\nPer-condition averages:");
-        let cond_names: Vec<&str> = conditions.iter().map(|c| c.name).collect();
+        let cond_names: Vec<&str> = plan.conditions.iter().map(|c| c.name).collect();
         for cond_name in &cond_names {
             let cond_rows: Vec<_> = rows.iter().filter(|r| r.condition == *cond_name).collect();
             let total_rust: u64 = cond_rows.iter().map(|r| r.rust_median_us).sum();
@@ -1083,7 +1463,7 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
         // Per-tag-count summary
         println!("\nPer-tag-count averages:");
         let mut seen_tags = Vec::new();
-        for &(n_tags, _, _, _) in tag_configs {
+        for n_tags in plan.tag_grids.iter().map(|g| g.tags) {
             if seen_tags.contains(&n_tags) {
                 continue;
             }
@@ -1128,43 +1508,35 @@ fn cmd_benchmark_sweep_inner(iterations: usize, format: &str, threads: usize, fu
                 if threads == 1 { "" } else { "s" },
             );
     }
-}
-
-/// Compute grid positions for N tags within an image, with spacing for borders.
-#[cfg(feature = "reference")]
-fn grid_positions(n: usize, width: u32, height: u32, tag_scale: f64) -> Vec<(f64, f64)> {
-    if n == 1 {
-        return vec![(width as f64 / 2.0, height as f64 / 2.0)];
-    }
-
-    // Compute grid dimensions
-    let cols = (n as f64).sqrt().ceil() as usize;
-    let rows = n.div_ceil(cols);
-
-    let margin = tag_scale * 1.2;
-    let usable_w = width as f64 - 2.0 * margin;
-    let usable_h = height as f64 - 2.0 * margin;
 
-    let step_x = if cols > 1 {
-        usable_w / (cols - 1) as f64
-    } else {
-        0.0
-    };
-    let step_y = if rows > 1 {
-        usable_h / (rows - 1) as f64
-    } else {
-        0.0
-    };
-
-    let mut positions = Vec::with_capacity(n);
-    for i in 0..n {
-        let col = i % cols;
-        let row = i / cols;
-        let x = margin + col as f64 * step_x;
-        let y = margin + row as f64 * step_y;
-        positions.push((x, y));
+    if let Some(path) = plot {
+        // One chart per condition; family modes and decimate levels at the
+        // same tag count are averaged.
+        let charts: Vec<Chart> = plan
+            .conditions
+            .iter()
+            .map(|cond| {
+                let samples = |us: fn(&BenchRow) -> u64| -> Vec<(f64, f64)> {
+                    rows.iter()
+                        .filter(|r| r.condition == cond.name)
+                        .map(|r| (r.tags as f64, us(r) as f64 / 1000.0))
+                        .collect()
+                };
+                Chart {
+                    title: cond.name.to_string(),
+                    x_label: "tags".to_string(),
+                    y_label: "median time (ms)".to_string(),
+                    series: vec![
+                        Series::mean_by_x("Rust", &samples(|r| r.rust_median_us), false),
+                        Series::mean_by_x("Reference", &samples(|r| r.ref_median_us), true),
+                    ],
+                }
+            })
+            .collect();
+        std::fs::write(path, svg_plot::render_svg(&charts, 2))
+            .unwrap_or_else(|e| panic!("cannot write {path}: {e}"));
+        eprintln!("Wrote {} charts to {path}", charts.len());
     }
-    positions
 }
 
 fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str) {
@@ -1179,15 +1551,20 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
 
     #[cfg(feature = "reference")]
     {
+        use apriltag::PixelConvention;
         use apriltag_bench::reference::{self, ReferenceConfig};
 
         let scenarios = filter_scenarios(category, scenario);
 
+        // Ground truth and our default detections put integer coordinates at
+        // pixel corners; C detections are converted from
+        // reference::PIXEL_CONVENTION. The mean corner offsets from ground
+        // truth would show a half-pixel mismatch.
         println!(
-            "{:<35} {:>8} {:>8} {:>8} {:>8} {:>8}",
-            "Scenario", "Rust%", "Ref%", "RustRMS", "RefRMS", "Match"
+            "{:<35} {:>8} {:>8} {:>8} {:>8} {:>13} {:>13} {:>8}",
+            "Scenario", "Rust%", "Ref%", "RustRMS", "RefRMS", "RustOffset", "RefOffset", "Match"
         );
-        println!("{}", "-".repeat(85));
+        println!("{}", "-".repeat(113));
 
         #[derive(serde::Serialize)]
         struct CompareRow {
@@ -1196,6 +1573,8 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
             ref_detection_rate: f64,
             rust_corner_rmse: f64,
             ref_corner_rmse: f64,
+            rust_corner_offset: Option<[f64; 2]>,
+            ref_corner_offset: Option<[f64; 2]>,
             results_match: bool,
         }
 
@@ -1205,16 +1584,10 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
             let scene = s.build();
 
             // Run Rust detector
-            let (rust_result, _) = run_scenario(s);
+            let (rust_result, _) = runner::run_scenario(s);
 
             // Run C reference detector
-            let families: Vec<&str> = s
-                .expect_ids
-                .iter()
-                .map(|(f, _)| f.as_str())
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect();
+            let families = runner::scenario_families(s);
 
             let ref_config = ReferenceConfig {
                 quad_decimate: s.quad_decimate.unwrap_or(2.0),
@@ -1232,14 +1605,26 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
                     let c = d.corners;
                     let corners =
                         [c[3], c[2], c[1], c[0]].map(apriltag::detect::geometry::Vec2::from);
-                    all_ref_dets.push(apriltag::detect::detector::Detection {
+                    let Some(homography) =
+                        apriltag::detect::homography::Homography::from_quad_corners(&corners)
+                    else {
+                        continue;
+                    };
+                    let mut det = apriltag::detect::detector::Detection {
                         id: d.id,
                         hamming: d.hamming,
                         decision_margin: d.decision_margin,
                         center: apriltag::detect::geometry::Vec2::from(d.center),
                         corners,
+                        homography,
                         family_id: apriltag::family::FamilyId::from(&**fam),
-                    });
+                        mirrored: false,
+                        line_fit_mse: 0.0,
+                        refine_residual: None,
+                        corner_covariance: [[0.0; 3]; 4],
+                    };
+                    det.convert_pixels(reference::PIXEL_CONVENTION, PixelConvention::Corner);
+                    all_ref_dets.push(det);
                 }
             }
 
@@ -1254,18 +1639,25 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
                 ref_detection_rate: ref_result.detection_rate,
                 rust_corner_rmse: rust_result.corner_rmse,
                 ref_corner_rmse: ref_result.corner_rmse,
+                rust_corner_offset: metrics::mean_corner_offset(&rust_result),
+                ref_corner_offset: metrics::mean_corner_offset(&ref_result),
                 results_match,
             };
 
             if format != "json" {
                 let match_str = if results_match { "YES" } else { "NO" };
+                let offset = |o: Option<[f64; 2]>| {
+                    o.map_or("-".to_string(), |[x, y]| format!("{x:+.2},{y:+.2}"))
+                };
                 println!(
-                    "{:<35} {:>7.0}% {:>7.0}% {:>8.2} {:>8.2} {:>8}",
+                    "{:<35} {:>7.0}% {:>7.0}% {:>8.2} {:>8.2} {:>13} {:>13} {:>8}",
                     &s.name,
                     rust_result.detection_rate * 100.0,
                     ref_result.detection_rate * 100.0,
                     rust_result.corner_rmse,
                     ref_result.corner_rmse,
+                    offset(row.rust_corner_offset),
+                    offset(row.ref_corner_offset),
                     match_str,
                 );
             }
@@ -1276,13 +1668,35 @@ fn cmd_compare(category: Option<String>, scenario: Option<String>, format: &str)
         if format == "json" {
             println!("{}", serde_json::to_string_pretty(&rows).unwrap());
         } else {
-            println!("{}", "-".repeat(85));
+            println!("{}", "-".repeat(113));
             let matching = rows.iter().filter(|r| r.results_match).count();
             println!("Matching: {}/{} scenarios", matching, rows.len());
         }
     }
 }
 
+/// The detector `profile` and `explore` run: one built-in family with the
+/// given quad-fitting overrides. Exits on an unknown family.
+fn single_family_detector(
+    family_name: &str,
+    min_edge_gradient: u8,
+    max_clusters: Option<usize>,
+) -> Detector {
+    let mut config = DetectorConfig::default();
+    config.quad.min_edge_gradient = min_edge_gradient;
+    config.max_clusters = max_clusters;
+    match DetectorBuilder::with_config(config)
+        .family(family_name, 2)
+        .try_build()
+    {
+        Ok(detector) => detector,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn cmd_profile(
     scenario_name: Option<String>,
@@ -1298,6 +1712,8 @@ fn cmd_profile(
     width: u32,
     height: u32,
     iterations: usize,
+    min_edge_gradient: u8,
+    max_clusters: Option<usize>,
 ) {
     let (image, scene_desc) = if let Some(name) = &scenario_name {
         let scenarios = filter_scenarios(None, Some(name.clone()));
@@ -1363,10 +1779,7 @@ fn cmd_profile(
     eprintln!("Scene: {scene_desc}");
     eprintln!("Iterations: {iterations}");
 
-    let mut detector = Detector::new(DetectorConfig::default());
-    if let Some(fam) = family::builtin_family(family_name) {
-        detector.add_family(fam, 2);
-    }
+    let detector = single_family_detector(family_name, min_edge_gradient, max_clusters);
 
     let mut buffers = DetectorBuffers::new();
 
@@ -1391,6 +1804,8 @@ fn cmd_explore(
     contrast: f64,
     width: u32,
     height: u32,
+    min_edge_gradient: u8,
+    max_clusters: Option<usize>,
     format: &str,
 ) {
     let cx = width as f64 / 2.0;
@@ -1437,10 +1852,7 @@ fn cmd_explore(
     }
 
     // Detect
-    let mut detector = Detector::new(DetectorConfig::default());
-    if let Some(fam) = family::builtin_family(family_name) {
-        detector.add_family(fam, 2);
-    }
+    let detector = single_family_detector(family_name, min_edge_gradient, max_clusters);
 
     let start = Instant::now();
     let detections = detector.detect(&scene.image, &mut DetectorBuffers::new());
@@ -1,11 +1,15 @@
 /// Detection quality metrics: corner accuracy, detection rate, scoring.
 use apriltag::detect::geometry::{Mat3, Vec3};
 use apriltag::detect::pose::estimate_tag_pose;
+use apriltag::detect::profile::TimeProfile;
 use apriltag::Detection;
 use serde::{Deserialize, Serialize};
 
 use crate::scene::PlacedTag;
 
+/// Two-sided standard normal quantile for 95% confidence.
+const Z_95: f64 = 1.959_963_984_540_054;
+
 /// Per-detection pose error.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoseError {
@@ -15,13 +19,21 @@ pub struct PoseError {
     pub translation_error_frac: f64,
 }
 
+/// Wall-clock time of one detection pipeline stage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageTiming {
+    /// Stage name, as in [`TimeProfile`]: `threshold`, `decode`, ...
+    pub stage: String,
+    pub time_us: u64,
+}
+
 /// Result of evaluating detections against ground truth for a single scene.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SceneResult {
     /// Matched ground-truth tags with their corresponding detections.
     pub matches: Vec<DetectionMatch>,
     /// Detections that don't correspond to any ground-truth tag.
-    pub false_positives: Vec<DetectionSummary>,
+    pub false_positives: Vec<Detection>,
     /// Fraction of ground-truth tags that were detected (0.0–1.0).
     pub detection_rate: f64,
     /// Root mean square of all per-corner Euclidean distances across all matches.
@@ -38,6 +50,10 @@ pub struct SceneResult {
     pub mean_rotation_error_deg: Option<f64>,
     /// Mean translation error (normalized by t_z) across matches with pose data.
     pub mean_translation_error_frac: Option<f64>,
+    /// Detection time per pipeline stage, in pipeline order (None unless
+    /// attached with [`SceneResult::with_stage_times`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage_times: Option<Vec<StageTiming>>,
 }
 
 /// A ground-truth tag matched (or unmatched) with a detection.
@@ -46,35 +62,11 @@ pub struct DetectionMatch {
     /// The ground-truth tag.
     pub ground_truth: PlacedTag,
     /// The matched detection, if any.
-    pub detection: Option<DetectionSummary>,
+    pub detection: Option<Detection>,
     /// Per-corner Euclidean distance (pixels), if matched. [TL, TR, BR, BL].
     pub corner_errors: Option<[f64; 4]>,
 }
 
-/// Serializable summary of a detection (avoids needing Detection to be Serialize).
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DetectionSummary {
-    pub family_name: String,
-    pub id: i32,
-    pub hamming: i32,
-    pub decision_margin: f32,
-    pub corners: [[f64; 2]; 4],
-    pub center: [f64; 2],
-}
-
-impl From<&Detection> for DetectionSummary {
-    fn from(det: &Detection) -> Self {
-        DetectionSummary {
-            family_name: det.family_id.to_string(),
-            id: det.id,
-            hamming: det.hamming,
-            decision_margin: det.decision_margin,
-            corners: det.corners.map(Into::into),
-            center: det.center.into(),
-        }
-    }
-}
-
 /// Evaluate detections against ground truth.
 ///
 /// For each ground-truth tag, finds the detection with matching family+ID.
@@ -101,7 +93,7 @@ pub fn evaluate(
             let corner_errors = best_corner_errors(&gt.corners, &det_corners);
             matches.push(DetectionMatch {
                 ground_truth: gt.clone(),
-                detection: Some(det.into()),
+                detection: Some(det.clone()),
                 corner_errors: Some(corner_errors),
             });
             detections_for_pose.push(Some(det));
@@ -116,11 +108,11 @@ pub fn evaluate(
     }
 
     // False positives: detections not matched to any ground truth
-    let false_positives: Vec<DetectionSummary> = detections
+    let false_positives: Vec<Detection> = detections
         .iter()
         .enumerate()
         .filter(|(i, _)| !used[*i])
-        .map(|(_, det)| det.into())
+        .map(|(_, det)| det.clone())
         .collect();
 
     // Compute aggregate metrics
@@ -198,6 +190,24 @@ pub fn evaluate(
         pose_errors,
         mean_rotation_error_deg,
         mean_translation_error_frac,
+        stage_times: None,
+    }
+}
+
+impl SceneResult {
+    /// Attach the per-stage times of the detection that was evaluated.
+    pub fn with_stage_times(mut self, profile: &TimeProfile) -> Self {
+        self.stage_times = Some(
+            profile
+                .stages
+                .iter()
+                .map(|s| StageTiming {
+                    stage: s.name.to_string(),
+                    time_us: s.duration.as_micros() as u64,
+                })
+                .collect(),
+        );
+        self
     }
 }
 
@@ -252,10 +262,136 @@ fn best_corner_errors(gt: &[[f64; 2]; 4], det: &[[f64; 2]; 4]) -> [f64; 4] {
     best_errors
 }
 
+/// 95% Wilson score interval for a proportion of `successes` out of
+/// `trials`, or `(0.0, 1.0)` without trials.
+///
+/// Unlike the normal approximation it stays within `[0, 1]` and does not
+/// collapse to a single point at rates of 0% or 100%.
+pub fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let denom = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denom;
+    let half = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denom;
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
+
+/// Spread of a tag's detected corners over repeated frames of one scene.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CornerJitter {
+    /// Root mean square over the four corners of each corner's standard
+    /// deviation (pixels).
+    pub rms: f64,
+    /// Largest per-corner standard deviation (pixels).
+    pub max: f64,
+}
+
+/// Mean signed offset `[dx, dy]` of the matched detections' corner
+/// centroids from the ground truth's, in pixels; `None` without matches.
+///
+/// Unlike the corner errors this keeps the direction, so a pixel-convention
+/// mismatch shows up as an offset near `[±0.5, ±0.5]`. Ground truth uses
+/// [`PixelConvention::Corner`](apriltag::PixelConvention::Corner).
+pub fn mean_corner_offset(result: &SceneResult) -> Option<[f64; 2]> {
+    let centroid = |corners: [[f64; 2]; 4]| {
+        let [x, y] = corners
+            .iter()
+            .fold([0.0, 0.0], |[x, y], c| [x + c[0], y + c[1]]);
+        [x / 4.0, y / 4.0]
+    };
+    let offsets: Vec<[f64; 2]> = result
+        .matches
+        .iter()
+        .filter_map(|m| {
+            let det = centroid(m.detection.as_ref()?.corners.map(Into::into));
+            let gt = centroid(m.ground_truth.corners);
+            Some([det[0] - gt[0], det[1] - gt[1]])
+        })
+        .collect();
+    let n = offsets.len() as f64;
+    (!offsets.is_empty()).then(|| {
+        let [x, y] = offsets
+            .iter()
+            .fold([0.0, 0.0], |[x, y], o| [x + o[0], y + o[1]]);
+        [x / n, y / n]
+    })
+}
+
+/// Corner jitter of one tag from its detected corners in each frame.
+///
+/// A corner's standard deviation is the root mean square distance of its
+/// positions from their mean. `None` with fewer than two frames.
+pub fn corner_jitter(frames: &[[[f64; 2]; 4]]) -> Option<CornerJitter> {
+    if frames.len() < 2 {
+        return None;
+    }
+    let n = frames.len() as f64;
+    let mut sum_var = 0.0;
+    let mut max_var = 0.0f64;
+    for k in 0..4 {
+        let mean = frames.iter().fold([0.0; 2], |acc, f| {
+            [acc[0] + f[k][0] / n, acc[1] + f[k][1] / n]
+        });
+        let var = frames
+            .iter()
+            .map(|f| (f[k][0] - mean[0]).powi(2) + (f[k][1] - mean[1]).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        sum_var += var;
+        max_var = max_var.max(var);
+    }
+    Some(CornerJitter {
+        rms: (sum_var / 4.0).sqrt(),
+        max: max_var.sqrt(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn corner_jitter_spread() {
+        let square = |dx: f64| [[dx, 0.0], [10.0 + dx, 0.0], [10.0 + dx, 10.0], [dx, 10.0]];
+        assert_eq!(corner_jitter(&[square(0.0)]), None);
+        assert_eq!(corner_jitter(&[square(1.0); 5]).map(|j| j.rms), Some(0.0));
+
+        // Every corner alternates by ±0.5px in x: sample std dev sqrt(1/3)
+        let frames = [square(-0.5), square(0.5), square(-0.5), square(0.5)];
+        let j = corner_jitter(&frames).unwrap();
+        assert!((j.rms - (1.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((j.max - j.rms).abs() < 1e-12);
+
+        // One corner moving leaves the others still
+        let mut moved = frames;
+        for f in &mut moved {
+            f[1..].copy_from_slice(&square(0.0)[1..]);
+        }
+        let j = corner_jitter(&moved).unwrap();
+        assert!((j.rms - j.max / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wilson_interval_bounds() {
+        assert_eq!(wilson_interval(0, 0), (0.0, 1.0));
+
+        // Reference values for 8/10 at 95%
+        let (lo, hi) = wilson_interval(8, 10);
+        assert!((lo - 0.4902).abs() < 1e-4 && (hi - 0.9433).abs() < 1e-4);
+
+        // All successes still leave room below 100%
+        let (lo, hi) = wilson_interval(50, 50);
+        assert!(lo > 0.9 && lo < 1.0);
+        assert!((hi - 1.0).abs() < 1e-12);
+        let (lo, hi) = wilson_interval(0, 50);
+        assert!(lo.abs() < 1e-12);
+        assert!(hi > 0.0 && hi < 0.1);
+    }
+
     fn make_gt(family: &str, id: u32, corners: [[f64; 2]; 4]) -> PlacedTag {
         let cx = corners.iter().map(|c| c[0]).sum::<f64>() / 4.0;
         let cy = corners.iter().map(|c| c[1]).sum::<f64>() / 4.0;
@@ -273,13 +409,20 @@ mod tests {
     fn make_det(family: &str, id: i32, corners: [[f64; 2]; 4]) -> Detection {
         let cx = corners.iter().map(|c| c[0]).sum::<f64>() / 4.0;
         let cy = corners.iter().map(|c| c[1]).sum::<f64>() / 4.0;
+        let corners = corners.map(apriltag::detect::geometry::Vec2::from);
         Detection {
             family_id: apriltag::family::FamilyId::from(family),
             id,
             hamming: 0,
             decision_margin: 100.0,
-            corners: corners.map(apriltag::detect::geometry::Vec2::from),
+            corners,
             center: apriltag::detect::geometry::Vec2::new(cx, cy),
+            homography: apriltag::detect::homography::Homography::from_quad_corners(&corners)
+                .unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         }
     }
 
@@ -477,6 +620,88 @@ mod tests {
         assert!(result.mean_translation_error_frac.is_some());
     }
 
+    #[test]
+    fn stage_times_round_trip_through_json() {
+        use crate::scene::{Background, SceneBuilder};
+        use crate::transform::Transform;
+        use apriltag::{family, Detector, DetectorBuffers, DetectorConfig};
+
+        let scene = SceneBuilder::new(300, 300)
+            .background(Background::Solid(128))
+            .add_tag(
+                "tag36h11",
+                0,
+                Transform::Similarity {
+                    cx: 150.0,
+                    cy: 150.0,
+                    scale: 50.0,
+                    theta: 0.0,
+                },
+            )
+            .build();
+
+        let mut detector = Detector::new(DetectorConfig::default());
+        detector.add_family(family::builtin_family("tag36h11").unwrap(), 2);
+        let (detections, profile) =
+            detector.detect_with_profile(&scene.image, &mut DetectorBuffers::new());
+
+        let result = evaluate(&scene.ground_truth, &detections, 0);
+        assert!(!serde_json::to_string(&result)
+            .unwrap()
+            .contains("stage_times"));
+
+        let result = result.with_stage_times(&profile);
+        let stages = result.stage_times.as_ref().unwrap();
+        assert!(!stages.is_empty());
+        assert!(stages.iter().any(|s| s.stage == "decode"));
+
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: SceneResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.stage_times.as_ref(), Some(stages));
+    }
+
+    #[test]
+    fn detections_share_the_ground_truth_pixel_convention() {
+        use crate::scene::{Background, SceneBuilder};
+        use crate::transform::Transform;
+        use apriltag::{family, Detector, DetectorBuffers, DetectorConfig, PixelConvention};
+
+        let scene = SceneBuilder::new(300, 300)
+            .background(Background::Solid(128))
+            .add_tag(
+                "tag36h11",
+                0,
+                Transform::FromPose {
+                    center: [150.0, 150.0],
+                    size: 80.0,
+                    roll: 0.3,
+                    tilt_x: 0.0,
+                    tilt_y: 0.0,
+                },
+            )
+            .build();
+        let offset = |convention| {
+            let mut detector = Detector::new(DetectorConfig {
+                pixel_convention: convention,
+                ..DetectorConfig::default()
+            });
+            detector.add_family(family::builtin_family("tag36h11").unwrap(), 2);
+            let detections = detector.detect(&scene.image, &mut DetectorBuffers::new());
+            mean_corner_offset(&evaluate(&scene.ground_truth, &detections, 0)).unwrap()
+        };
+
+        let [dx, dy] = offset(PixelConvention::Corner);
+        assert!(dx.abs() < 0.1 && dy.abs() < 0.1, "offset {dx}, {dy}");
+        let [dx, dy] = offset(PixelConvention::Center);
+        assert!(
+            (dx + 0.5).abs() < 0.1 && (dy + 0.5).abs() < 0.1,
+            "offset {dx}, {dy}"
+        );
+
+        let unmatched = evaluate(&scene.ground_truth, &[], 0);
+        assert_eq!(mean_corner_offset(&unmatched), None);
+    }
+
     #[test]
     fn best_corner_errors_identity() {
         let corners = [[10.0, 10.0], [20.0, 10.0], [20.0, 20.0], [10.0, 20.0]];
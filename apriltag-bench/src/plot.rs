@@ -0,0 +1,309 @@
+/// Minimal SVG line charts for benchmark results.
+///
+/// Hand-written SVG keeps the harness free of a plotting dependency; the
+/// charts are meant for eyeballing trends across commits, not publication.
+use std::fmt::Write;
+
+/// Size of one chart panel in SVG user units.
+const PANEL_WIDTH: f64 = 480.0;
+const PANEL_HEIGHT: f64 = 300.0;
+
+/// Plot area margins inside a panel: left, right, top, bottom.
+const MARGIN: [f64; 4] = [60.0, 20.0, 36.0, 44.0];
+
+const COLORS: &[&str] = &["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd"];
+
+/// Number of intervals on the value axis.
+const Y_TICKS: usize = 5;
+
+/// One line on a chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    /// Points sorted by x.
+    pub points: Vec<(f64, f64)>,
+    pub dashed: bool,
+}
+
+impl Series {
+    /// Build a series from unordered samples, averaging samples that share
+    /// an x value.
+    pub fn mean_by_x(label: impl Into<String>, samples: &[(f64, f64)], dashed: bool) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let mut count = 0usize;
+        for (x, y) in sorted {
+            match points.last_mut() {
+                Some(last) if last.0 == x => {
+                    count += 1;
+                    last.1 += (y - last.1) / count as f64;
+                }
+                _ => {
+                    points.push((x, y));
+                    count = 1;
+                }
+            }
+        }
+
+        Self {
+            label: label.into(),
+            points,
+            dashed,
+        }
+    }
+}
+
+/// A titled chart with one or more series sharing its axes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub series: Vec<Series>,
+}
+
+/// Render charts as panels of a single SVG document, `columns` panels wide.
+///
+/// The value axis always starts at zero. X ticks are placed at every distinct
+/// x value of the chart's series.
+pub fn render_svg(charts: &[Chart], columns: usize) -> String {
+    let columns = columns.clamp(1, charts.len().max(1));
+    let rows = charts.len().div_ceil(columns).max(1);
+    let width = PANEL_WIDTH * columns as f64;
+    let height = PANEL_HEIGHT * rows as f64;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="11">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="white"/>"#
+    );
+    for (i, chart) in charts.iter().enumerate() {
+        let x0 = PANEL_WIDTH * (i % columns) as f64;
+        let y0 = PANEL_HEIGHT * (i / columns) as f64;
+        render_panel(&mut svg, chart, x0, y0);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn render_panel(svg: &mut String, chart: &Chart, x0: f64, y0: f64) {
+    let [ml, mr, mt, mb] = MARGIN;
+    let left = x0 + ml;
+    let right = x0 + PANEL_WIDTH - mr;
+    let top = y0 + mt;
+    let bottom = y0 + PANEL_HEIGHT - mb;
+
+    let mut xs: Vec<f64> = chart
+        .series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|p| p.0))
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    let (x_min, x_max) = match (xs.first(), xs.last()) {
+        (Some(&lo), Some(&hi)) if hi > lo => (lo, hi),
+        (Some(&lo), _) => (lo - 1.0, lo + 1.0),
+        _ => (0.0, 1.0),
+    };
+    let y_data_max = chart
+        .series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|p| p.1))
+        .fold(0.0f64, f64::max);
+    let y_step = nice_step(y_data_max / Y_TICKS as f64);
+    let y_max = y_step * Y_TICKS as f64;
+
+    let px = |x: f64| left + (x - x_min) / (x_max - x_min) * (right - left);
+    let py = |y: f64| bottom - y / y_max * (bottom - top);
+
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle" font-size="13" font-weight="bold">{}</text>"#,
+        (left + right) / 2.0,
+        y0 + mt / 2.0 + 4.0,
+        escape(&chart.title)
+    );
+
+    // Grid and value axis labels
+    for i in 0..=Y_TICKS {
+        let v = y_step * i as f64;
+        let y = py(v);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{left}" y1="{y}" x2="{right}" y2="{y}" stroke="#ddd"/>"##
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+            left - 6.0,
+            y + 4.0,
+            format_tick(v)
+        );
+    }
+    for &x in &xs {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            px(x),
+            bottom + 16.0,
+            format_tick(x)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r##"<polyline points="{left},{top} {left},{bottom} {right},{bottom}" fill="none" stroke="#333"/>"##
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+        (left + right) / 2.0,
+        bottom + 34.0,
+        escape(&chart.x_label)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{x}" y="{y}" text-anchor="middle" transform="rotate(-90 {x} {y})">{}</text>"#,
+        escape(&chart.y_label),
+        x = x0 + 16.0,
+        y = (top + bottom) / 2.0,
+    );
+
+    // Series and legend
+    for (i, series) in chart.series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let dash = if series.dashed {
+            r#" stroke-dasharray="6 4""#
+        } else {
+            ""
+        };
+        let points: Vec<String> = series
+            .points
+            .iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", px(x), py(y)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"{dash}/>"#,
+            points.join(" ")
+        );
+        for &(x, y) in &series.points {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{color}"/>"#,
+                px(x),
+                py(y)
+            );
+        }
+
+        let ly = top + 8.0 + 14.0 * i as f64;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{}" y1="{ly}" x2="{}" y2="{ly}" stroke="{color}" stroke-width="2"{dash}/>"#,
+            left + 8.0,
+            left + 28.0
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}">{}</text>"#,
+            left + 34.0,
+            ly + 4.0,
+            escape(&series.label)
+        );
+    }
+}
+
+/// Smallest 1/2/5 × 10^k step that is at least `raw`.
+fn nice_step(raw: f64) -> f64 {
+    if !raw.is_finite() || raw <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn format_tick(v: f64) -> String {
+    if v.fract() == 0.0 {
+        format!("{v:.0}")
+    } else {
+        let s = format!("{v:.3}");
+        s.trim_end_matches('0').to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_by_x_sorts_and_averages() {
+        let s = Series::mean_by_x(
+            "rust",
+            &[(25.0, 4.0), (1.0, 1.0), (25.0, 8.0), (25.0, 3.0)],
+            false,
+        );
+        assert_eq!(s.points, vec![(1.0, 1.0), (25.0, 5.0)]);
+    }
+
+    #[test]
+    fn nice_steps() {
+        assert_eq!(nice_step(0.0), 1.0);
+        assert_eq!(nice_step(0.3), 0.5);
+        assert_eq!(nice_step(1.0), 1.0);
+        assert_eq!(nice_step(1.1), 2.0);
+        assert_eq!(nice_step(42.0), 50.0);
+        assert_eq!(nice_step(60.0), 100.0);
+    }
+
+    #[test]
+    fn render_lays_out_panels_and_series() {
+        let chart = |title: &str| Chart {
+            title: title.to_string(),
+            x_label: "tags".to_string(),
+            y_label: "ms".to_string(),
+            series: vec![
+                Series::mean_by_x("Rust", &[(1.0, 2.0), (70.0, 40.0)], false),
+                Series::mean_by_x("C <ref>", &[(1.0, 3.0), (70.0, 50.0)], true),
+            ],
+        };
+        let svg = render_svg(&[chart("a"), chart("b"), chart("c")], 2);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(r#"width="960" height="600""#));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 6);
+        assert!(svg.contains("C &lt;ref&gt;"));
+        assert!(!svg.contains("NaN"));
+    }
+
+    #[test]
+    fn render_handles_empty_and_single_point_charts() {
+        let single = Chart {
+            title: "one".to_string(),
+            x_label: String::new(),
+            y_label: String::new(),
+            series: vec![Series::mean_by_x("x", &[(5.0, 0.0)], false)],
+        };
+        let svg = render_svg(&[single], 3);
+        assert!(svg.contains(r#"width="480""#));
+        assert!(!svg.contains("NaN"));
+
+        let svg = render_svg(&[], 2);
+        assert!(svg.starts_with("<svg"));
+    }
+}
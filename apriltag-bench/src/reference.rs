@@ -3,7 +3,13 @@
 ///
 /// This module is only available when the `reference` feature is enabled.
 /// Requires running `scripts/fetch-references.sh` to obtain the C source.
-use apriltag::ImageU8;
+use apriltag::{ImageU8, PixelConvention};
+
+/// Where the C library puts integer pixel coordinates: at pixel corners,
+/// like the bench's ground truth. Its quad fitting offsets the doubled
+/// boundary coordinates by half a pixel, and edge refinement samples pixel
+/// centers at `+0.5`.
+pub const PIXEL_CONVENTION: PixelConvention = PixelConvention::Corner;
 
 /// A detection result from the reference C implementation.
 #[derive(Debug, Clone)]
@@ -1,5 +1,5 @@
 /// Report generation: terminal, JSON output for scenario results.
-use crate::metrics::SceneResult;
+use crate::metrics::{SceneResult, StageTiming};
 
 /// Summary of a single scenario run.
 #[derive(Debug, serde::Serialize)]
@@ -19,6 +19,122 @@ pub struct ScenarioReport {
     pub mean_rotation_error_deg: Option<f64>,
     /// Mean translation error normalized by t_z (None if no pose data).
     pub mean_translation_error_frac: Option<f64>,
+    /// Scenario time budget in milliseconds (None if not checked).
+    pub max_time_ms: Option<f64>,
+    /// Median single-threaded detection time in milliseconds, measured when
+    /// checking the time budget.
+    pub median_time_ms: Option<f64>,
+    /// RMS corner jitter in pixels across noisy frames (None if not a
+    /// jitter scenario, or if a frame missed a tag).
+    pub jitter_px: Option<f64>,
+    /// Scenario jitter limit in pixels (None if not checked).
+    pub max_jitter_px: Option<f64>,
+    /// Detection time per pipeline stage, so that a slowdown can be traced
+    /// to a stage from the same report (None if not timed).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage_times: Option<Vec<StageTiming>>,
+}
+
+/// Detection statistics for a scenario re-run over randomized layouts.
+#[derive(Debug, serde::Serialize)]
+pub struct TrialReport {
+    pub name: String,
+    pub category: String,
+    pub trials: usize,
+    pub seed: u64,
+    /// Ground-truth tags summed over all trials.
+    pub expected: usize,
+    pub detected: usize,
+    pub detection_rate: f64,
+    /// 95% Wilson score interval for the per-tag detection rate.
+    pub detection_rate_ci: (f64, f64),
+    /// Mean of the per-trial corner RMSE over trials that detected a tag.
+    pub mean_corner_rmse: f64,
+    /// Largest per-trial corner RMSE.
+    pub max_corner_rmse: f64,
+    /// False positives summed over all trials.
+    pub false_positives: usize,
+}
+
+/// Quality and speed of a scenario suite in one pipeline configuration, from
+/// [`run_ablations`](crate::runner::run_ablations).
+#[derive(Debug, serde::Serialize)]
+pub struct AblationReport {
+    /// Ablation name, or `baseline` for the scenarios' own configuration.
+    pub ablation: String,
+    pub scenarios: usize,
+    /// Ground-truth tags summed over all scenarios.
+    pub expected: usize,
+    pub detected: usize,
+    pub detection_rate: f64,
+    pub false_positives: usize,
+    /// Scenarios in which at least one tag was detected.
+    pub scenarios_detected: usize,
+    /// Mean corner RMSE over the scenarios that detected a tag.
+    pub mean_corner_rmse: f64,
+    /// Fastest detection time of each scenario, summed.
+    pub detection_time_us: u64,
+    /// Fastest time of each stage in each scenario, summed, in the order
+    /// the stages first ran.
+    pub stage_times: Vec<StageTiming>,
+}
+
+impl AblationReport {
+    /// An empty report for the configuration named `ablation`.
+    pub fn new(ablation: &str) -> Self {
+        Self {
+            ablation: ablation.to_string(),
+            scenarios: 0,
+            expected: 0,
+            detected: 0,
+            detection_rate: 0.0,
+            false_positives: 0,
+            scenarios_detected: 0,
+            mean_corner_rmse: 0.0,
+            detection_time_us: 0,
+            stage_times: Vec::new(),
+        }
+    }
+
+    /// Add one scenario's detection quality.
+    pub fn add_result(&mut self, result: &SceneResult) {
+        let found = result
+            .matches
+            .iter()
+            .filter(|m| m.detection.is_some())
+            .count();
+        self.scenarios += 1;
+        self.expected += result.matches.len();
+        self.detected += found;
+        self.false_positives += result.false_positives.len();
+        if self.expected > 0 {
+            self.detection_rate = self.detected as f64 / self.expected as f64;
+        }
+        if found > 0 {
+            self.scenarios_detected += 1;
+            self.mean_corner_rmse +=
+                (result.corner_rmse - self.mean_corner_rmse) / self.scenarios_detected as f64;
+        }
+    }
+
+    /// Add one scenario's detection time and stage times.
+    pub fn add_times(&mut self, total_us: u64, stages: &[StageTiming]) {
+        self.detection_time_us += total_us;
+        for stage in stages {
+            match self.stage_times.iter_mut().find(|s| s.stage == stage.stage) {
+                Some(s) => s.time_us += stage.time_us,
+                None => self.stage_times.push(stage.clone()),
+            }
+        }
+    }
+
+    /// Time of stage `name` summed over the suite, or 0 if it never ran.
+    pub fn stage_time_us(&self, name: &str) -> u64 {
+        self.stage_times
+            .iter()
+            .find(|s| s.stage == name)
+            .map_or(0, |s| s.time_us)
+    }
 }
 
 /// Full report across all scenarios.
@@ -104,12 +220,193 @@ pub fn print_terminal(report: &FullReport) {
 
     let sep_width = if has_pose { 95 } else { 75 };
     println!("{}", "-".repeat(sep_width));
+
+    let timed: Vec<_> = report
+        .scenarios
+        .iter()
+        .filter_map(|s| Some((s, s.median_time_ms?, s.max_time_ms?)))
+        .collect();
+    if !timed.is_empty() {
+        println!(
+            "{:<35} {:>10} {:>10} {:>6}",
+            "Time budget (1 thread)", "Median", "Budget", "Status"
+        );
+        for (s, median, budget) in timed {
+            let status = if median <= budget { "OK" } else { "OVER" };
+            println!(
+                "{:<35} {:>8.2}ms {:>8.2}ms {:>6}",
+                truncate(&s.name, 35),
+                median,
+                budget,
+                status
+            );
+        }
+        println!("{}", "-".repeat(sep_width));
+    }
+
+    let jittered: Vec<_> = report
+        .scenarios
+        .iter()
+        .filter_map(|s| Some((s, s.max_jitter_px?)))
+        .collect();
+    if !jittered.is_empty() {
+        println!(
+            "{:<35} {:>10} {:>10} {:>6}",
+            "Corner jitter (RMS)", "Jitter", "Limit", "Status"
+        );
+        for (s, limit) in jittered {
+            let (jitter, status) = match s.jitter_px {
+                Some(j) if j <= limit => (format!("{j:.3}px"), "OK"),
+                Some(j) => (format!("{j:.3}px"), "OVER"),
+                None => ("-".to_string(), "MISS"),
+            };
+            println!(
+                "{:<35} {:>10} {:>8.3}px {:>6}",
+                truncate(&s.name, 35),
+                jitter,
+                limit,
+                status
+            );
+        }
+        println!("{}", "-".repeat(sep_width));
+    }
     println!(
         "Total: {} | Passed: {} | Failed: {}",
         report.total, report.passed, report.failed
     );
 }
 
+/// The `n` scenarios with the highest corner RMSE, worst first.
+///
+/// Scenarios that missed tags rank ahead of all others, lowest detection
+/// rate first, since their RMSE only covers the tags that were found.
+pub fn worst_by_rmse(report: &FullReport, n: usize) -> Vec<&ScenarioReport> {
+    let mut sorted: Vec<&ScenarioReport> = report.scenarios.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.detection_rate
+            .total_cmp(&b.detection_rate)
+            .then(b.corner_rmse.total_cmp(&a.corner_rmse))
+    });
+    sorted.truncate(n);
+    sorted
+}
+
+/// Print the `n` worst scenarios by corner RMSE; see [`worst_by_rmse`].
+pub fn print_worst(report: &FullReport, n: usize) {
+    let worst = worst_by_rmse(report, n);
+    if worst.is_empty() {
+        return;
+    }
+    println!("\nWorst {} by corner RMSE:", worst.len());
+    for s in worst {
+        println!(
+            "  {:<35} {:>4.0}% {:>8.2} {:>8.2}  {}",
+            truncate(&s.name, 35),
+            s.detection_rate * 100.0,
+            s.corner_rmse,
+            s.max_corner_error,
+            s.category,
+        );
+    }
+}
+
+/// Print a terminal table of randomized-layout trial results.
+pub fn print_trials_terminal(reports: &[TrialReport]) {
+    println!(
+        "{:<35} {:>6} {:>6} {:>13} {:>8} {:>8} {:>5}",
+        "Scenario", "Trials", "Det%", "95% CI", "RMSE", "MaxRMSE", "FP"
+    );
+    println!("{}", "-".repeat(88));
+    for r in reports {
+        let (lo, hi) = r.detection_rate_ci;
+        let ci = format!("{:.1}-{:.1}%", lo * 100.0, hi * 100.0);
+        println!(
+            "{:<35} {:>6} {:>5.1}% {:>13} {:>8.2} {:>8.2} {:>5}",
+            truncate(&r.name, 35),
+            r.trials,
+            r.detection_rate * 100.0,
+            ci,
+            r.mean_corner_rmse,
+            r.max_corner_rmse,
+            r.false_positives,
+        );
+    }
+    println!("{}", "-".repeat(88));
+    if let Some(r) = reports.first() {
+        println!(
+            "{} scenarios, {} randomized layouts each (seed {})",
+            reports.len(),
+            r.trials,
+            r.seed
+        );
+    }
+}
+
+/// Print a terminal table of ablation results against the baseline (the
+/// first report), then the stages whose time changed most under each.
+pub fn print_ablations_terminal(reports: &[AblationReport]) {
+    let Some(base) = reports.first() else {
+        return;
+    };
+    println!(
+        "{:<20} {:>6} {:>7} {:>8} {:>8} {:>5} {:>10} {:>7}",
+        "Ablation", "Det%", "ΔDet", "RMSE", "ΔRMSE", "FP", "Time ms", "ΔTime"
+    );
+    println!("{}", "-".repeat(78));
+    for r in reports {
+        let ms = r.detection_time_us as f64 / 1e3;
+        let time_change = if base.detection_time_us > 0 {
+            (r.detection_time_us as f64 / base.detection_time_us as f64 - 1.0) * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "{:<20} {:>5.1}% {:>+7} {:>8.3} {:>+8.3} {:>5} {:>10.2} {:>+6.1}%",
+            truncate(&r.ablation, 20),
+            r.detection_rate * 100.0,
+            r.detected as i64 - base.detected as i64,
+            r.mean_corner_rmse,
+            r.mean_corner_rmse - base.mean_corner_rmse,
+            r.false_positives,
+            ms,
+            time_change,
+        );
+    }
+    println!("{}", "-".repeat(78));
+    println!(
+        "{} scenarios, {} tags; deltas against {}",
+        base.scenarios, base.expected, base.ablation
+    );
+
+    println!();
+    println!("Largest stage time changes (ms):");
+    for r in &reports[1..] {
+        let mut stages: Vec<&str> = base
+            .stage_times
+            .iter()
+            .chain(&r.stage_times)
+            .map(|s| s.stage.as_str())
+            .collect();
+        stages.sort_unstable();
+        stages.dedup();
+        let mut changes: Vec<(&str, f64)> = stages
+            .into_iter()
+            .map(|s| {
+                let delta = r.stage_time_us(s) as f64 - base.stage_time_us(s) as f64;
+                (s, delta / 1e3)
+            })
+            .filter(|(_, delta)| delta.abs() >= 0.01)
+            .collect();
+        changes.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        let changes: Vec<String> = changes
+            .iter()
+            .take(3)
+            .map(|(stage, delta)| format!("{stage} {delta:+.2}"))
+            .collect();
+        println!("  {:<20} {}", r.ablation, changes.join(", "));
+    }
+}
+
 /// Render report as JSON.
 pub fn to_json(report: &FullReport) -> String {
     serde_json::to_string_pretty(report).unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"))
@@ -154,6 +451,11 @@ pub fn scenario_report(
         threshold,
         mean_rotation_error_deg: result.mean_rotation_error_deg,
         mean_translation_error_frac: result.mean_translation_error_frac,
+        max_time_ms: None,
+        median_time_ms: None,
+        jitter_px: None,
+        max_jitter_px: None,
+        stage_times: result.stage_times.clone(),
     }
 }
 
@@ -186,6 +488,11 @@ mod tests {
                 threshold: 2.0,
                 mean_rotation_error_deg: None,
                 mean_translation_error_frac: None,
+                max_time_ms: None,
+                median_time_ms: None,
+                jitter_px: None,
+                max_jitter_px: None,
+                stage_times: None,
             },
             ScenarioReport {
                 name: "b".into(),
@@ -201,6 +508,11 @@ mod tests {
                 threshold: 2.0,
                 mean_rotation_error_deg: None,
                 mean_translation_error_frac: None,
+                max_time_ms: None,
+                median_time_ms: None,
+                jitter_px: None,
+                max_jitter_px: None,
+                stage_times: None,
             },
         ];
         let full = FullReport::from_scenarios(reports);
@@ -210,6 +522,44 @@ mod tests {
         assert!(!full.all_passed());
     }
 
+    #[test]
+    fn worst_ranks_missed_tags_then_rmse() {
+        let report = |name: &str, detection_rate: f64, corner_rmse: f64| ScenarioReport {
+            name: name.into(),
+            category: "test".into(),
+            passed: true,
+            detected: 1,
+            expected: 1,
+            detection_rate,
+            corner_rmse,
+            max_corner_error: corner_rmse,
+            false_positives: 0,
+            detection_time_us: 100,
+            threshold: 2.0,
+            mean_rotation_error_deg: None,
+            mean_translation_error_frac: None,
+            max_time_ms: None,
+            median_time_ms: None,
+            jitter_px: None,
+            max_jitter_px: None,
+            stage_times: None,
+        };
+        let full = FullReport::from_scenarios(vec![
+            report("good", 1.0, 0.1),
+            report("missed", 0.0, 0.0),
+            report("bad", 1.0, 0.9),
+            report("partial", 0.5, 0.2),
+        ]);
+
+        let names: Vec<&str> = worst_by_rmse(&full, 3)
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["missed", "partial", "bad"]);
+        assert_eq!(worst_by_rmse(&full, 10).len(), 4);
+        assert!(worst_by_rmse(&full, 0).is_empty());
+    }
+
     #[test]
     fn json_output_parses() {
         let full = FullReport::from_scenarios(vec![]);
@@ -0,0 +1,526 @@
+/// Programmatic scenario execution: select, run, score and time scenarios.
+///
+/// These are the building blocks behind the `apriltag-bench` CLI commands,
+/// exposed so that other crates and tests can obtain [`SceneResult`]s
+/// directly instead of parsing the binary's output.
+use std::time::{Duration, Instant};
+
+use apriltag::{Ablation, Detector, DetectorBuffers, DetectorBuilder, DetectorConfig};
+
+use crate::catalog::{self, Category, JitterSpec, Scenario};
+use crate::distortion::{self, Distortion, Rng};
+use crate::metrics::{self, CornerJitter, SceneResult, StageTiming};
+use crate::report::{self, AblationReport, ScenarioReport, TrialReport};
+
+/// Untimed runs before measuring, to stabilize caches and the allocator.
+pub const WARMUP_ITERATIONS: usize = 3;
+
+/// Minimum total measurement time per detector when benchmarking.
+pub const MIN_MEASURE_TIME: Duration = Duration::from_millis(200);
+
+/// Base seed of the per-frame noise in jitter scenarios.
+const JITTER_SEED: u64 = 0x6a17;
+
+/// Median detection time over an adaptively chosen number of iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub median: Duration,
+    pub iterations: usize,
+}
+
+/// Select scenarios by category and/or name substring.
+pub fn select_scenarios(category: Option<Category>, pattern: Option<&str>) -> Vec<Scenario> {
+    let mut scenarios = match category {
+        Some(cat) => catalog::scenarios_for_category(cat),
+        None => catalog::all_scenarios(),
+    };
+
+    if let Some(pattern) = pattern {
+        scenarios.retain(|s| s.name.contains(pattern));
+    }
+
+    scenarios
+}
+
+/// Unique family names expected by a scenario, in first-appearance order.
+pub fn scenario_families(scenario: &Scenario) -> Vec<&str> {
+    let mut families: Vec<&str> = Vec::new();
+    for (fam, _) in &scenario.expect_ids {
+        if !families.contains(&fam.as_str()) {
+            families.push(fam);
+        }
+    }
+    families
+}
+
+/// Build a detector configured for a scenario: its families and config overrides.
+pub fn scenario_detector(scenario: &Scenario) -> Detector {
+    detector_with_config(scenario, scenario_config(scenario))
+}
+
+/// A scenario's detector configuration: the defaults with its overrides.
+pub fn scenario_config(scenario: &Scenario) -> DetectorConfig {
+    let mut config = DetectorConfig::default();
+    if let Some(decimate) = scenario.quad_decimate {
+        config.quad_decimate = decimate;
+    }
+    if let Some(sigma) = scenario.quad_sigma {
+        config.quad_sigma = sigma;
+    }
+    if let Some(mode) = scenario.decimate_mode {
+        config.decimate_mode = mode;
+    }
+    config
+}
+
+/// Build a detector for a scenario's families with `config`.
+pub fn detector_with_config(scenario: &Scenario, config: DetectorConfig) -> Detector {
+    let mut builder = DetectorBuilder::with_config(config);
+    for fam_name in scenario_families(scenario) {
+        builder = builder.family(fam_name, 2);
+    }
+    builder.build()
+}
+
+/// Build a scenario's scene, detect once, and evaluate against ground truth,
+/// with the time of each pipeline stage.
+pub fn run_scenario(scenario: &Scenario) -> (SceneResult, Duration) {
+    let scene = scenario.build();
+    let detector = scenario_detector(scenario);
+
+    let start = Instant::now();
+    let (detections, profile) =
+        detector.detect_with_profile(&scene.image, &mut DetectorBuffers::new());
+    let elapsed = start.elapsed();
+
+    let result = metrics::evaluate(&scene.ground_truth, &detections, elapsed.as_micros() as u64)
+        .with_stage_times(&profile);
+    (result, elapsed)
+}
+
+/// Run scenarios and score each against its thresholds.
+///
+/// `threshold_override` replaces every scenario's `max_corner_rmse` when set.
+pub fn run_scenarios(
+    scenarios: &[Scenario],
+    threshold_override: Option<f64>,
+) -> Vec<ScenarioReport> {
+    scenarios
+        .iter()
+        .map(|s| {
+            let (result, _) = run_scenario(s);
+            let mut report = report::scenario_report(
+                &s.name,
+                s.category.name(),
+                &result,
+                s.expect_ids.len(),
+                threshold_override.unwrap_or(s.max_corner_rmse),
+                s.max_rotation_error_deg,
+            );
+            if s.max_false_positives
+                .is_some_and(|max| report.false_positives > max)
+            {
+                report.passed = false;
+            }
+            if let Some(spec) = &s.jitter {
+                let jitter = measure_jitter(s, spec);
+                report.jitter_px = jitter.map(|j| j.rms);
+                report.max_jitter_px = Some(spec.max_jitter_px);
+                if !jitter.is_some_and(|j| j.rms <= spec.max_jitter_px) {
+                    report.passed = false;
+                }
+            }
+            report
+        })
+        .collect()
+}
+
+/// Detect a scenario's scene over `spec.frames` frames, each with its own
+/// Gaussian noise, and return the largest corner jitter of any expected tag.
+///
+/// Returns `None` if any frame misses an expected tag, since jitter is then
+/// not comparable.
+pub fn measure_jitter(scenario: &Scenario, spec: &JitterSpec) -> Option<CornerJitter> {
+    let scene = scenario.build();
+    let detector = scenario_detector(scenario);
+    let mut buffers = DetectorBuffers::new();
+
+    let mut tracks = vec![Vec::with_capacity(spec.frames); scene.ground_truth.len()];
+    for frame in 0..spec.frames {
+        let mut image = scene.image.clone();
+        distortion::apply(
+            &mut image,
+            &[Distortion::GaussianNoise {
+                sigma: spec.noise_sigma,
+                seed: JITTER_SEED + frame as u64,
+            }],
+        );
+        let detections = detector.detect(&image, &mut buffers);
+        let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+        for (track, m) in tracks.iter_mut().zip(&result.matches) {
+            track.push(m.detection.as_ref()?.corners.map(Into::into));
+        }
+    }
+
+    tracks
+        .iter()
+        .map(|track| metrics::corner_jitter(track))
+        .try_fold(None, |worst: Option<CornerJitter>, j| {
+            let j = j?;
+            Some(Some(match worst {
+                Some(w) if w.rms >= j.rms => w,
+                _ => j,
+            }))
+        })?
+}
+
+/// Re-run a scenario over `trials` random layouts and report its detection
+/// rate with a confidence interval.
+///
+/// Each trial moves the scenario's whole layout to a random position and
+/// in-plane rotation (see [`Scenario::build_trial`]). Trial seeds are drawn
+/// from `seed`, so the same arguments reproduce the same layouts.
+pub fn run_trials(scenario: &Scenario, trials: usize, seed: u64) -> TrialReport {
+    let detector = scenario_detector(scenario);
+    let mut buffers = DetectorBuffers::new();
+    let mut seeds = Rng::new(seed);
+
+    let (mut expected, mut detected, mut false_positives) = (0, 0, 0);
+    let mut rmses = Vec::with_capacity(trials);
+    for _ in 0..trials {
+        let scene = scenario.build_trial(seeds.next_u64());
+        let detections = detector.detect(&scene.image, &mut buffers);
+        let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+
+        expected += result.matches.len();
+        let found = result
+            .matches
+            .iter()
+            .filter(|m| m.detection.is_some())
+            .count();
+        detected += found;
+        false_positives += result.false_positives.len();
+        if found > 0 {
+            rmses.push(result.corner_rmse);
+        }
+    }
+
+    TrialReport {
+        name: scenario.name.clone(),
+        category: scenario.category.name().to_string(),
+        trials,
+        seed,
+        expected,
+        detected,
+        detection_rate: if expected > 0 {
+            detected as f64 / expected as f64
+        } else {
+            0.0
+        },
+        detection_rate_ci: metrics::wilson_interval(detected, expected),
+        mean_corner_rmse: if rmses.is_empty() {
+            0.0
+        } else {
+            rmses.iter().sum::<f64>() / rmses.len() as f64
+        },
+        max_corner_rmse: rmses.iter().copied().fold(0.0, f64::max),
+        false_positives,
+    }
+}
+
+/// Run `scenarios` in their own configuration and then with each of
+/// `ablations` applied, summing quality and per-stage time over the suite
+/// for each; the first report is the baseline.
+///
+/// Every scene is detected `iterations` times per configuration on the
+/// calling thread. Times are the fastest run of each stage, which keeps
+/// scheduling noise out of small differences; quality comes from the first
+/// run, as detection is deterministic.
+pub fn run_ablations(
+    scenarios: &[Scenario],
+    ablations: &[Ablation],
+    iterations: usize,
+) -> Vec<AblationReport> {
+    let names = std::iter::once("baseline").chain(ablations.iter().map(|a| a.name()));
+    let mut reports: Vec<AblationReport> = names.map(AblationReport::new).collect();
+
+    for scenario in scenarios {
+        let scene = scenario.build();
+        let configs = std::iter::once(None).chain(ablations.iter().map(Some));
+        for (report, ablation) in reports.iter_mut().zip(configs) {
+            let mut config = scenario_config(scenario);
+            if let Some(ablation) = ablation {
+                ablation.apply(&mut config);
+            }
+            config.num_threads = Some(1);
+            let detector = detector_with_config(scenario, config);
+            let mut buffers = DetectorBuffers::new();
+
+            let mut total = Duration::MAX;
+            let mut stages: Vec<StageTiming> = Vec::new();
+            for i in 0..iterations.max(1) {
+                let start = Instant::now();
+                let (detections, profile) =
+                    detector.detect_with_profile(&scene.image, &mut buffers);
+                total = total.min(start.elapsed());
+                for stage in &profile.stages {
+                    let time_us = stage.duration.as_micros() as u64;
+                    match stages.iter_mut().find(|s| s.stage == stage.name) {
+                        Some(s) => s.time_us = s.time_us.min(time_us),
+                        None => stages.push(StageTiming {
+                            stage: stage.name.to_string(),
+                            time_us,
+                        }),
+                    }
+                }
+                if i == 0 {
+                    let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+                    report.add_result(&result);
+                }
+            }
+            report.add_times(total.as_micros() as u64, &stages);
+        }
+    }
+    reports
+}
+
+/// Iteration count needed to spend [`MIN_MEASURE_TIME`] given one calibration
+/// run, but never fewer than `min_iterations`.
+pub fn adaptive_iterations(calibration: Duration, min_iterations: usize) -> usize {
+    if calibration.is_zero() {
+        min_iterations
+    } else {
+        ((MIN_MEASURE_TIME.as_nanos() / calibration.as_nanos()).max(1) as usize).max(min_iterations)
+    }
+}
+
+/// Time `iterations` calls of `f` and return the median.
+///
+/// # Panics
+///
+/// Panics if `iterations` is zero.
+pub fn median_duration(iterations: usize, mut f: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[iterations / 2]
+}
+
+/// Current resident set size of this process in bytes, from
+/// `/proc/self/status`. Returns `None` on platforms without procfs.
+///
+/// RSS moves in whole pages and only counts touched memory, so deltas around
+/// an allocation are approximate, and small allocations may be served from
+/// pages the allocator already holds.
+pub fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Benchmark the Rust detector on a scenario with warmup and adaptive iterations.
+pub fn benchmark_scenario(scenario: &Scenario, min_iterations: usize) -> Timing {
+    benchmark_detector(scenario, &scenario_detector(scenario), min_iterations)
+}
+
+fn benchmark_detector(scenario: &Scenario, detector: &Detector, min_iterations: usize) -> Timing {
+    let scene = scenario.build();
+    let mut buffers = DetectorBuffers::new();
+
+    for _ in 0..WARMUP_ITERATIONS {
+        let _ = detector.detect(&scene.image, &mut buffers);
+    }
+
+    let calib_start = Instant::now();
+    let _ = detector.detect(&scene.image, &mut buffers);
+    let iterations = adaptive_iterations(calib_start.elapsed(), min_iterations);
+
+    let median = median_duration(iterations, || {
+        let _ = detector.detect(&scene.image, &mut buffers);
+    });
+    Timing { median, iterations }
+}
+
+/// Minimum timed iterations when checking a scenario's time budget.
+pub const BUDGET_MIN_ITERATIONS: usize = 10;
+
+/// Check each scenario's `max_time_ms` budget against its median
+/// single-threaded detection time (`num_threads = Some(1)`), failing reports
+/// that exceed it.
+///
+/// `reports` must correspond to `scenarios` in order, as returned by
+/// [`run_scenarios`].
+pub fn enforce_time_budgets(scenarios: &[Scenario], reports: &mut [ScenarioReport]) {
+    for (scenario, report) in scenarios.iter().zip(reports) {
+        let Some(budget) = scenario.max_time_ms else {
+            continue;
+        };
+        let mut detector = scenario_detector(scenario);
+        detector.config.num_threads = Some(1);
+        let timing = benchmark_detector(scenario, &detector, BUDGET_MIN_ITERATIONS);
+        let median = timing.median.as_secs_f64() * 1e3;
+        report.max_time_ms = Some(budget);
+        report.median_time_ms = Some(median);
+        if median > budget {
+            report.passed = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_by_category_and_pattern() {
+        let blur = select_scenarios(Some(Category::Blur), None);
+        assert!(blur.iter().all(|s| s.category == Category::Blur));
+
+        let sigma = select_scenarios(Some(Category::Blur), Some("sigma"));
+        assert!(!sigma.is_empty() && sigma.len() < blur.len());
+        assert!(sigma.iter().all(|s| s.name.contains("sigma")));
+    }
+
+    #[test]
+    fn families_are_unique_and_ordered() {
+        let scenarios = select_scenarios(Some(Category::MultiTag), Some("multi-family-2tags"));
+        assert_eq!(
+            scenario_families(&scenarios[0]),
+            vec!["tag36h11", "tagStandard52h13"]
+        );
+    }
+
+    #[test]
+    fn run_scenario_detects_baseline() {
+        let scenarios = select_scenarios(Some(Category::Baseline), Some("baseline-tag36h11"));
+        let (result, _) = run_scenario(&scenarios[0]);
+        assert_eq!(result.detection_rate, 1.0);
+
+        let reports = run_scenarios(&scenarios, None);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].passed);
+
+        // Stage times travel with the quality metrics into the JSON report
+        let stages = reports[0].stage_times.as_ref().unwrap();
+        assert!(stages.iter().any(|s| s.stage == "threshold"));
+        assert_eq!(stages.last().unwrap().stage, "deduplicate");
+        let json = report::to_json(&report::FullReport::from_scenarios(reports));
+        assert!(json.contains("\"stage_times\""));
+    }
+
+    #[test]
+    fn ablations_attribute_stage_times() {
+        let scenarios = select_scenarios(Some(Category::Baseline), Some("baseline-tag36h11"));
+        let reports = run_ablations(&scenarios, &[Ablation::NoRefineEdges], 2);
+        assert_eq!(reports.len(), 2);
+        let (base, ablated) = (&reports[0], &reports[1]);
+        assert_eq!(base.ablation, "baseline");
+        assert_eq!(ablated.ablation, "no-refine-edges");
+        for r in &reports {
+            assert_eq!((r.scenarios, r.expected, r.detected), (1, 1, 1));
+            assert!(r.detection_time_us > 0);
+        }
+
+        // The skipped stage leaves the ablated run's profile
+        assert!(base.stage_times.iter().any(|s| s.stage == "refine_edges"));
+        assert!(!ablated
+            .stage_times
+            .iter()
+            .any(|s| s.stage == "refine_edges"));
+        assert_eq!(ablated.stage_time_us("refine_edges"), 0);
+    }
+
+    #[test]
+    fn threshold_override_applies() {
+        let scenarios = select_scenarios(Some(Category::Blur), Some("blur-interlace-2px"));
+        let reports = run_scenarios(&scenarios, Some(0.0));
+        assert!(!reports[0].passed);
+    }
+
+    #[test]
+    fn time_budgets_fail_slow_scenarios() {
+        let mut scenarios = select_scenarios(Some(Category::Baseline), Some("baseline-tag36h11"));
+        assert!(scenarios[0].max_time_ms.is_some());
+        let mut reports = run_scenarios(&scenarios, None);
+        enforce_time_budgets(&scenarios, &mut reports);
+        assert!(reports[0].median_time_ms.is_some());
+
+        scenarios[0].max_time_ms = Some(0.0);
+        let mut reports = run_scenarios(&scenarios, None);
+        enforce_time_budgets(&scenarios, &mut reports);
+        assert!(!reports[0].passed);
+        assert_eq!(reports[0].max_time_ms, Some(0.0));
+
+        // Scenarios without a budget are not timed
+        let unbudgeted = select_scenarios(Some(Category::Blur), Some("blur-sigma1"));
+        let mut reports = run_scenarios(&unbudgeted, None);
+        enforce_time_budgets(&unbudgeted, &mut reports);
+        assert!(reports[0].passed && reports[0].median_time_ms.is_none());
+    }
+
+    #[test]
+    fn jitter_grows_with_noise() {
+        let mut scenarios = select_scenarios(Some(Category::Jitter), Some("large-sigma2"));
+        let spec = scenarios[0].jitter.unwrap();
+        let low = measure_jitter(&scenarios[0], &spec).unwrap();
+        assert!(low.rms > 0.0 && low.rms <= low.max);
+        assert_eq!(measure_jitter(&scenarios[0], &spec), Some(low));
+
+        let noisy = JitterSpec {
+            noise_sigma: 10.0,
+            ..spec
+        };
+        assert!(measure_jitter(&scenarios[0], &noisy).unwrap().rms > low.rms);
+
+        scenarios[0].jitter = Some(JitterSpec {
+            max_jitter_px: 0.0,
+            ..spec
+        });
+        let reports = run_scenarios(&scenarios, None);
+        assert!(!reports[0].passed);
+        assert_eq!(reports[0].jitter_px, Some(low.rms));
+    }
+
+    #[test]
+    fn trials_are_reproducible_and_randomized() {
+        let scenarios = select_scenarios(Some(Category::Baseline), Some("baseline-tag36h11"));
+        let a = run_trials(&scenarios[0], 5, 7);
+        assert_eq!(a.expected, 5);
+        assert_eq!(a.detected, 5);
+        assert!(a.detection_rate_ci.0 > 0.0 && a.detection_rate_ci.1 == 1.0);
+
+        let b = run_trials(&scenarios[0], 5, 7);
+        assert_eq!(a.mean_corner_rmse, b.mean_corner_rmse);
+
+        // Different seeds move the tag elsewhere
+        let moved = scenarios[0].build_trial(1).ground_truth[0].center;
+        assert_ne!(moved, scenarios[0].build().ground_truth[0].center);
+        assert_ne!(moved, scenarios[0].build_trial(2).ground_truth[0].center);
+    }
+
+    #[test]
+    fn adaptive_iterations_bounds() {
+        assert_eq!(adaptive_iterations(Duration::ZERO, 5), 5);
+        assert_eq!(adaptive_iterations(Duration::from_millis(50), 1), 4);
+        assert_eq!(adaptive_iterations(Duration::from_secs(1), 3), 3);
+    }
+
+    #[test]
+    fn median_duration_runs_each_iteration() {
+        let mut calls = 0;
+        let _ = median_duration(7, || calls += 1);
+        assert_eq!(calls, 7);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn resident_memory_is_reported() {
+        assert!(resident_memory_bytes().unwrap() > 0);
+    }
+}
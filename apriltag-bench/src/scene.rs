@@ -1,14 +1,19 @@
 /// Scene composition: place rendered tags into an image with ground truth.
 use apriltag::detect::geometry::{Mat3, Vec3};
 use apriltag::detect::pose::PoseParams;
-use apriltag::family;
+use apriltag::family::{self, TagFamily};
 use apriltag::render::RenderedTag;
 use apriltag::types::Pixel;
 use apriltag::ImageU8;
 use serde::{Deserialize, Serialize};
 
+use crate::camera::{pose_rotation_columns, Camera};
+use crate::distortion::Rng;
 use crate::transform::Transform;
 
+/// Random rotations tried when randomizing a layout before giving up.
+const LAYOUT_ATTEMPTS: usize = 32;
+
 /// A tag placed in a scene with its ground-truth corner positions.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlacedTag {
@@ -34,8 +39,39 @@ pub struct PlacedTag {
 pub struct Scene {
     pub image: ImageU8,
     pub ground_truth: Vec<PlacedTag>,
+    /// Camera used for `FromPose` placements, if one was set.
+    pub camera: Option<Camera>,
+}
+
+/// Why a [`SceneBuilder`] could not build its scene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneError {
+    /// A placement named a family that is neither built in nor added with
+    /// [`SceneBuilder::add_tag_family`].
+    UnknownFamily(String),
+    /// A placement asked for a tag the family does not have.
+    TagOutOfRange {
+        family: String,
+        tag_id: u32,
+        codes: usize,
+    },
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownFamily(name) => write!(f, "unknown tag family: {name}"),
+            Self::TagOutOfRange {
+                family,
+                tag_id,
+                codes,
+            } => write!(f, "{family} has {codes} tags, no tag {tag_id}"),
+        }
+    }
 }
 
+impl std::error::Error for SceneError {}
+
 /// Background fill for the scene.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Background {
@@ -49,9 +85,12 @@ pub enum Background {
 
 /// A tag to be placed in the scene.
 struct TagPlacement {
-    family_name: String,
+    /// Index into [`SceneBuilder::families`].
+    family: usize,
     tag_id: u32,
     transform: Transform,
+    /// Data bits drawn instead of the tag's code; decoys have no ground truth.
+    decoy_code: Option<u64>,
 }
 
 /// Builder for constructing scenes.
@@ -59,7 +98,14 @@ pub struct SceneBuilder {
     width: u32,
     height: u32,
     background: Background,
+    camera: Option<Camera>,
+    quiet_zone: Option<f64>,
+    /// Families used by the placements, one entry per family name.
+    families: Vec<TagFamily>,
     tags: Vec<TagPlacement>,
+    layout_seed: Option<u64>,
+    /// The first unknown family name passed to a placement.
+    unknown_family: Option<String>,
 }
 
 impl SceneBuilder {
@@ -68,7 +114,12 @@ impl SceneBuilder {
             width,
             height,
             background: Background::Solid(128),
+            camera: None,
+            quiet_zone: None,
+            families: Vec::new(),
             tags: Vec::new(),
+            layout_seed: None,
+            unknown_family: None,
         }
     }
 
@@ -77,110 +128,446 @@ impl SceneBuilder {
         self
     }
 
+    /// Project [`Transform::FromPose`] placements through an explicit camera.
+    ///
+    /// Without a camera, `FromPose` uses a per-tag virtual focal length of
+    /// `2 × size`. Image-space transforms (`Similarity`, `Perspective`) are
+    /// unaffected either way.
+    pub fn camera(mut self, camera: Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// Limit the white margin drawn outside each tag's border to `modules`
+    /// cells, letting the background show right up to the border beyond it.
+    ///
+    /// Without a limit, the full rendered margin is drawn (one module for
+    /// classic families).
+    pub fn quiet_zone(mut self, modules: f64) -> Self {
+        self.quiet_zone = Some(modules);
+        self
+    }
+
+    /// Move the whole layout by a random rotation about its center and a
+    /// random translation drawn from `seed`, keeping every tag and one module
+    /// of margin inside the image. `None` keeps the layout as placed.
+    ///
+    /// Tags keep their size, tilt and arrangement relative to each other. If
+    /// no tried rotation fits the layout inside the image, it is left as is.
+    pub fn layout_seed(mut self, seed: Option<u64>) -> Self {
+        self.layout_seed = seed;
+        self
+    }
+
+    /// Place tag `tag_id` of the family named `family_name`.
+    ///
+    /// `family_name` must be a built-in family or the name of a family
+    /// already added with [`add_tag_family`](Self::add_tag_family); otherwise
+    /// [`try_build`](Self::try_build) fails.
     pub fn add_tag(mut self, family_name: &str, tag_id: u32, transform: Transform) -> Self {
+        let Some(family) = self.resolve_family(family_name) else {
+            return self;
+        };
         self.tags.push(TagPlacement {
-            family_name: family_name.to_string(),
+            family,
             tag_id,
             transform,
+            decoy_code: None,
         });
         self
     }
 
+    /// Place tag `tag_id` of any family, e.g. a custom or freshly generated
+    /// one. Ground truth refers to it by `family.config.name`; a later family
+    /// with the same name reuses the first one.
+    pub fn add_tag_family(mut self, family: &TagFamily, tag_id: u32, transform: Transform) -> Self {
+        let family = match self.family_index(&family.config.name) {
+            Some(index) => index,
+            None => {
+                self.families.push(family.clone());
+                self.families.len() - 1
+            }
+        };
+        self.tags.push(TagPlacement {
+            family,
+            tag_id,
+            transform,
+            decoy_code: None,
+        });
+        self
+    }
+
+    /// Draw a tag-shaped decoy: the family's border and layout with arbitrary
+    /// data bits `code`. Decoys are not part of the ground truth, so any
+    /// detection of one counts as a false positive.
+    ///
+    /// `family_name` is resolved like in [`add_tag`](Self::add_tag).
+    pub fn add_decoy(mut self, family_name: &str, code: u64, transform: Transform) -> Self {
+        let Some(family) = self.resolve_family(family_name) else {
+            return self;
+        };
+        self.tags.push(TagPlacement {
+            family,
+            tag_id: 0,
+            transform,
+            decoy_code: Some(code),
+        });
+        self
+    }
+
+    fn family_index(&self, name: &str) -> Option<usize> {
+        self.families.iter().position(|f| *f.config.name == *name)
+    }
+
+    /// Index of the family named `name`, adding it if built in. Unknown
+    /// names are remembered for [`try_build`](Self::try_build) to report.
+    fn resolve_family(&mut self, name: &str) -> Option<usize> {
+        if let Some(index) = self.family_index(name) {
+            return Some(index);
+        }
+        let Some(fam) = family::builtin_family(name) else {
+            self.unknown_family.get_or_insert_with(|| name.to_string());
+            return None;
+        };
+        self.families.push(fam);
+        Some(self.families.len() - 1)
+    }
+
     /// Build the scene: render tags, composite onto background, compute ground truth.
+    ///
+    /// # Panics
+    ///
+    /// Panics on any error [`try_build`](Self::try_build) would return.
     pub fn build(self) -> Scene {
+        self.try_build().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`build`](Self::build), but fails instead of panicking if a
+    /// placement names an unknown family or a tag its family lacks.
+    pub fn try_build(mut self) -> Result<Scene, SceneError> {
+        if let Some(name) = self.unknown_family.take() {
+            return Err(SceneError::UnknownFamily(name));
+        }
+        for placement in &self.tags {
+            let fam = &self.families[placement.family];
+            if placement.decoy_code.is_none() && fam.get_tag(placement.tag_id as usize).is_none() {
+                return Err(SceneError::TagOutOfRange {
+                    family: fam.config.name.to_string(),
+                    tag_id: placement.tag_id,
+                    codes: fam.codes.len(),
+                });
+            }
+        }
+        if let Some(seed) = self.layout_seed {
+            self.randomize_layout(seed);
+        }
         let mut image = fill_background(self.width, self.height, &self.background);
         let mut ground_truth = Vec::new();
 
         for placement in &self.tags {
-            let fam = family::builtin_family(&placement.family_name)
-                .unwrap_or_else(|| panic!("unknown tag family: {}", placement.family_name));
+            let fam = &self.families[placement.family];
 
-            let rendered = fam.tag(placement.tag_id as usize).render();
+            let rendered = match placement.decoy_code {
+                Some(code) => {
+                    let mut decoy = fam.clone();
+                    decoy.codes = vec![code];
+                    decoy.tag(0).render()
+                }
+                None => fam.tag(placement.tag_id as usize).render(),
+            };
+
+            let (projector, gt_pose) = placement_projector(&placement.transform, &self.camera);
 
             composite_tag(
                 &mut image,
                 &rendered,
-                &placement.transform,
+                &projector,
                 fam.layout.border_start,
                 fam.layout.border_width,
+                self.quiet_zone,
             );
+            if placement.decoy_code.is_some() {
+                continue;
+            }
 
-            let corners = placement.transform.ground_truth_corners();
-            let (cx, cy) = placement.transform.project(0.0, 0.0);
-
-            let (gt_rotation, gt_translation, gt_pose_params) = if let Transform::FromPose {
-                center,
-                size,
-                roll,
-                tilt_x,
-                tilt_y,
-            } = &placement.transform
-            {
-                let f = size * 2.0;
-
-                // Build rotation R_bench = Rz(roll) * Ry(tilt_x) * Rx(tilt_y)
-                let cr = roll.cos();
-                let sr = roll.sin();
-                let cxx = tilt_x.cos();
-                let sxx = tilt_x.sin();
-                let cyy = tilt_y.cos();
-                let syy = tilt_y.sin();
-
-                // R_bench columns
-                let r0 = Vec3::new(cr * cxx, sr * cxx, -sxx);
-                let r1_bench = Vec3::new(
-                    cr * sxx * syy - sr * cyy,
-                    sr * sxx * syy + cr * cyy,
-                    cxx * syy,
-                );
-
-                // Account for y-axis flip: pose estimator negates homography
-                // column 1 (r1 = -c1), so ground-truth R_gt = R_bench * diag(1,-1,1)
-                // then r2_gt = r0_gt × r1_gt to ensure proper rotation.
-                let r1 = -r1_bench;
-                let r2 = r0.cross(r1);
-
-                let rotation = Mat3([
-                    [r0[0], r1[0], r2[0]],
-                    [r0[1], r1[1], r2[1]],
-                    [r0[2], r1[2], r2[2]],
-                ]);
-                // The pose estimator works in tag-size units: t = c2 * (tagsize/2).
-                // Our virtual camera puts the tag at z=f pixels from center.
-                // The estimator sees columns scaled by half/f, so t_z = f/(half) * (tagsize/2).
-                // With tagsize=2, half=size/2: t_z = f/half = (size*2)/(size/2) = 4.
-                let tagsize = 2.0;
-                let half = size / 2.0;
-                let translation = Vec3::new(0.0, 0.0, f / half * (tagsize / 2.0));
-
-                let pose_params = PoseParams {
-                    tagsize: 2.0,
-                    fx: f,
-                    fy: f,
-                    cx: center[0],
-                    cy: center[1],
-                };
+            let tag_corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+            let corners = tag_corners.map(|[tx, ty]| projector.project(tx, ty));
+            let center = projector.project(0.0, 0.0);
 
-                (Some(rotation), Some(translation), Some(pose_params))
-            } else {
-                (None, None, None)
+            let (gt_rotation, gt_translation, gt_pose_params) = match gt_pose {
+                Some((r, t, pp)) => (Some(r), Some(t), Some(pp)),
+                None => (None, None, None),
             };
 
             ground_truth.push(PlacedTag {
-                family_name: placement.family_name.clone(),
+                family_name: fam.config.name.to_string(),
                 tag_id: placement.tag_id,
                 corners,
-                center: [cx, cy],
+                center,
                 gt_rotation,
                 gt_translation,
                 gt_pose_params,
             });
         }
 
-        Scene {
+        Ok(Scene {
             image,
             ground_truth,
+            camera: self.camera,
+        })
+    }
+
+    fn randomize_layout(&mut self, seed: u64) {
+        let original: Vec<Transform> = self.tags.iter().map(|t| t.transform.clone()).collect();
+        let Some([x0, y0, x1, y1]) = self.layout_bounds(&original) else {
+            return;
+        };
+        let pivot = [(x0 + x1) / 2.0, (y0 + y1) / 2.0];
+        let (width, height) = (self.width as f64, self.height as f64);
+
+        let mut rng = Rng::new(seed);
+        for _ in 0..LAYOUT_ATTEMPTS {
+            let theta = rng.next_f64() * std::f64::consts::TAU;
+            let moved = |offset: [f64; 2]| -> Vec<Transform> {
+                original
+                    .iter()
+                    .map(|t| move_transform(t, pivot, theta, offset))
+                    .collect()
+            };
+            let Some([x0, y0, x1, y1]) = self.layout_bounds(&moved([0.0, 0.0])) else {
+                continue;
+            };
+            if x1 - x0 > width || y1 - y0 > height {
+                continue;
+            }
+            let offset = [
+                rng.next_f64() * (width - (x1 - x0)) - x0,
+                rng.next_f64() * (height - (y1 - y0)) - y0,
+            ];
+
+            // Tilted tags do not move rigidly with their center, so check
+            // the final placement too
+            let placed = moved(offset);
+            if self.layout_bounds(&placed).is_some_and(|[x0, y0, x1, y1]| {
+                x0 >= 0.0 && y0 >= 0.0 && x1 <= width && y1 <= height
+            }) {
+                for (tag, transform) in self.tags.iter_mut().zip(placed) {
+                    tag.transform = transform;
+                }
+                return;
+            }
+        }
+    }
+
+    /// Image-space bounding box `[x0, y0, x1, y1]` of all tags with one
+    /// module of margin, or `None` if there are no tags or one projects
+    /// through the horizon.
+    fn layout_bounds(&self, transforms: &[Transform]) -> Option<[f64; 4]> {
+        let mut bounds: Option<[f64; 4]> = None;
+        for (placement, transform) in self.tags.iter().zip(transforms) {
+            let module = 2.0 / self.families[placement.family].layout.border_width as f64;
+            let (projector, _) = placement_projector(transform, &self.camera);
+            let e = 1.0 + module;
+            for [tx, ty] in [[-e, -e], [e, -e], [e, e], [-e, e]] {
+                let [x, y] = projector.project(tx, ty);
+                if !x.is_finite() || !y.is_finite() {
+                    return None;
+                }
+                let [bx0, by0, bx1, by1] = bounds.get_or_insert([x, y, x, y]);
+                *bx0 = bx0.min(x);
+                *by0 = by0.min(y);
+                *bx1 = bx1.max(x);
+                *by1 = by1.max(y);
+            }
+        }
+        bounds
+    }
+}
+
+/// Projector and ground-truth pose for a placement.
+fn placement_projector(
+    transform: &Transform,
+    camera: &Option<Camera>,
+) -> (Projector, Option<(Mat3, Vec3, PoseParams)>) {
+    match (transform, camera) {
+        (
+            Transform::FromPose {
+                center,
+                size,
+                roll,
+                tilt_x,
+                tilt_y,
+            },
+            Some(camera),
+        ) => {
+            let placed = camera.place(*center, *size, *roll, *tilt_x, *tilt_y);
+            let projector = Projector::new(placed.h, Some(*camera));
+            let pose = (placed.rotation, placed.translation, camera.pose_params());
+            (projector, Some(pose))
+        }
+        (transform, _) => (
+            Projector::new(transform_to_homography(transform), None),
+            legacy_pose_ground_truth(transform),
+        ),
+    }
+}
+
+/// Rotate a placement by `theta` about the image point `pivot`, then shift it
+/// by `offset`.
+fn move_transform(
+    transform: &Transform,
+    pivot: [f64; 2],
+    theta: f64,
+    offset: [f64; 2],
+) -> Transform {
+    let (sin, cos) = theta.sin_cos();
+    let move_point = |[x, y]: [f64; 2]| {
+        let (dx, dy) = (x - pivot[0], y - pivot[1]);
+        [
+            pivot[0] + cos * dx - sin * dy + offset[0],
+            pivot[1] + sin * dx + cos * dy + offset[1],
+        ]
+    };
+    match transform {
+        Transform::Similarity {
+            cx,
+            cy,
+            scale,
+            theta: t,
+        } => {
+            let [cx, cy] = move_point([*cx, *cy]);
+            Transform::Similarity {
+                cx,
+                cy,
+                scale: *scale,
+                theta: t + theta,
+            }
+        }
+        Transform::Perspective { h } => {
+            // Left-multiply by the rigid motion
+            let [tx, ty] = move_point([0.0, 0.0]);
+            let m = [[cos, -sin, tx], [sin, cos, ty], [0.0, 0.0, 1.0]];
+            let mut moved = [0.0; 9];
+            for r in 0..3 {
+                for c in 0..3 {
+                    moved[r * 3 + c] = (0..3).map(|k| m[r][k] * h[k * 3 + c]).sum();
+                }
+            }
+            Transform::Perspective { h: moved }
         }
+        Transform::FromPose {
+            center,
+            size,
+            roll,
+            tilt_x,
+            tilt_y,
+        } => Transform::FromPose {
+            center: move_point(*center),
+            size: *size,
+            roll: roll + theta,
+            tilt_x: *tilt_x,
+            tilt_y: *tilt_y,
+        },
+    }
+}
+
+/// Ground-truth pose for a `FromPose` placement rendered without an explicit camera.
+///
+/// The virtual camera has focal length `2 × size` and its principal point at
+/// the tag center.
+fn legacy_pose_ground_truth(transform: &Transform) -> Option<(Mat3, Vec3, PoseParams)> {
+    let Transform::FromPose {
+        center,
+        size,
+        roll,
+        tilt_x,
+        tilt_y,
+    } = transform
+    else {
+        return None;
+    };
+    let f = size * 2.0;
+
+    // R_bench columns of Rz(roll) * Ry(tilt_x) * Rx(tilt_y)
+    let (r0, r1_bench) = pose_rotation_columns(*roll, *tilt_x, *tilt_y);
+
+    // Account for y-axis flip: pose estimator negates homography
+    // column 1 (r1 = -c1), so ground-truth R_gt = R_bench * diag(1,-1,1)
+    // then r2_gt = r0_gt × r1_gt to ensure proper rotation.
+    let r1 = -r1_bench;
+    let r2 = r0.cross(r1);
+
+    let rotation = Mat3([
+        [r0[0], r1[0], r2[0]],
+        [r0[1], r1[1], r2[1]],
+        [r0[2], r1[2], r2[2]],
+    ]);
+    // The pose estimator works in tag-size units: t = c2 * (tagsize/2).
+    // Our virtual camera puts the tag at z=f pixels from center.
+    // The estimator sees columns scaled by half/f, so t_z = f/(half) * (tagsize/2).
+    // With tagsize=2, half=size/2: t_z = f/half = (size*2)/(size/2) = 4.
+    let tagsize = 2.0;
+    let half = size / 2.0;
+    let translation = Vec3::new(0.0, 0.0, f / half * (tagsize / 2.0));
+
+    let pose_params = PoseParams {
+        tagsize: 2.0,
+        fx: f,
+        fy: f,
+        cx: center[0],
+        cy: center[1],
+        solver: Default::default(),
+    };
+
+    Some((rotation, translation, pose_params))
+}
+
+/// Maps tag-space to image pixels for a single placement: a homography,
+/// optionally followed by lens distortion.
+struct Projector {
+    h: [f64; 9],
+    inv: [f64; 9],
+    camera: Option<Camera>,
+}
+
+impl Projector {
+    fn new(h: [f64; 9], camera: Option<Camera>) -> Self {
+        Self {
+            h,
+            inv: invert_3x3(&h),
+            camera,
+        }
+    }
+
+    /// Project a tag-space point to (distorted) image-space.
+    fn project(&self, tx: f64, ty: f64) -> [f64; 2] {
+        let h = &self.h;
+        let w = h[6] * tx + h[7] * ty + h[8];
+        let p = [
+            (h[0] * tx + h[1] * ty + h[2]) / w,
+            (h[3] * tx + h[4] * ty + h[5]) / w,
+        ];
+        match &self.camera {
+            Some(camera) => camera.distort(p),
+            None => p,
+        }
+    }
+
+    /// Map an image-space point back to tag-space, or `None` at the horizon.
+    fn unproject(&self, px: f64, py: f64) -> Option<(f64, f64)> {
+        let [px, py] = match &self.camera {
+            Some(camera) => camera.undistort([px, py]),
+            None => [px, py],
+        };
+        let inv = &self.inv;
+        let w = inv[6] * px + inv[7] * py + inv[8];
+        // COVERAGE: degenerate homography pixel — requires near-singular perspective
+        if w.abs() < 1e-12 {
+            return None;
+        }
+        let tx = (inv[0] * px + inv[1] * py + inv[2]) / w;
+        let ty = (inv[3] * px + inv[4] * py + inv[5]) / w;
+        Some((tx, ty))
     }
 }
 
@@ -230,47 +617,62 @@ fn fill_background(width: u32, height: u32, bg: &Background) -> ImageU8 {
     img
 }
 
-/// Composite a rendered tag onto an image using the given transform.
+/// Composite a rendered tag onto an image using the given projector.
 ///
-/// Uses inverse mapping: for each output pixel, compute the corresponding
-/// tag-space coordinate and sample the rendered tag.
+/// Uses inverse mapping: each output pixel's corners are mapped to grid
+/// space, and the rendered tag is box-filtered over that footprint. Pixels
+/// straddling a cell edge get the area-weighted mix of both cells, so edges
+/// stay free of stair-steps under strong tilt and minification. Transparent
+/// cells and the area outside the drawn range blend with the background by
+/// coverage.
 ///
 /// Tag-space convention: [-1, 1] maps to the border region
 /// [border_start, grid_size - border_start], matching the detector's homography.
-/// The white border extends beyond [-1, 1].
+/// The white border extends beyond [-1, 1], clipped to `quiet_zone` modules
+/// when given.
 fn composite_tag(
     img: &mut ImageU8,
     tag: &RenderedTag,
-    transform: &Transform,
+    projector: &Projector,
     border_start: usize,
     border_width: usize,
+    quiet_zone: Option<f64>,
 ) {
     let grid = tag.grid_size as f64;
     let bs = border_start as f64;
     let bw = border_width as f64;
 
+    // Grid-space range actually drawn; cells outside it leave the background.
+    let margin = quiet_zone.map_or(bs, |qz| qz.clamp(0.0, bs));
+    let g_min = bs - margin;
+    let g_max = grid - bs + margin;
+
     // The white border extends beyond tag-space [-1, 1].
     // Grid position 0 → tag-space = 2*(0-bs)/bw - 1 = -(2*bs/bw + 1)
     // Grid position grid_size → tag-space = 2*(grid_size-bs)/bw - 1 = (2*bs/bw + 1)
     let tag_extent = 2.0 * bs / bw + 1.0;
 
-    // Compute bounding box using the extended corners
-    let ext_corners = [
-        [-tag_extent, -tag_extent],
-        [tag_extent, -tag_extent],
-        [tag_extent, tag_extent],
-        [-tag_extent, tag_extent],
-    ];
+    // Compute bounding box by sampling the extended outline (edges may curve
+    // under lens distortion, so corners alone are not enough).
+    const SAMPLES: usize = 8;
     let mut min_x = f64::INFINITY;
     let mut max_x = f64::NEG_INFINITY;
     let mut min_y = f64::INFINITY;
     let mut max_y = f64::NEG_INFINITY;
-    for [tx, ty] in &ext_corners {
-        let (ix, iy) = transform.project(*tx, *ty);
-        min_x = min_x.min(ix);
-        max_x = max_x.max(ix);
-        min_y = min_y.min(iy);
-        max_y = max_y.max(iy);
+    for i in 0..=SAMPLES {
+        let s = -tag_extent + 2.0 * tag_extent * i as f64 / SAMPLES as f64;
+        for (tx, ty) in [
+            (s, -tag_extent),
+            (s, tag_extent),
+            (-tag_extent, s),
+            (tag_extent, s),
+        ] {
+            let [ix, iy] = projector.project(tx, ty);
+            min_x = min_x.min(ix);
+            max_x = max_x.max(ix);
+            min_y = min_y.min(iy);
+            max_y = max_y.max(iy);
+        }
     }
 
     let x0 = (min_x - 1.0).max(0.0) as u32;
@@ -278,41 +680,79 @@ fn composite_tag(
     let y0 = (min_y - 1.0).max(0.0) as u32;
     let y1 = ((max_y + 2.0) as u32).min(img.height);
 
-    let inv = inverse_homography(transform);
-
+    // Grid-space position of each pixel corner, one row at a time, so that
+    // neighboring pixels share unprojections.
+    let to_grid = |x: u32, y: u32| {
+        projector
+            .unproject(x as f64, y as f64)
+            .map(|(tx, ty)| [bs + (tx + 1.0) * 0.5 * bw, bs + (ty + 1.0) * 0.5 * bw])
+    };
+    let corner_row =
+        |y: u32| -> Vec<Option<[f64; 2]>> { (x0..=x1).map(|x| to_grid(x, y)).collect() };
+
+    let (mut wx, mut wy) = (Vec::new(), Vec::new());
+    let mut top = corner_row(y0);
     for iy in y0..y1 {
-        for ix in x0..x1 {
-            let px = ix as f64 + 0.5;
-            let py = iy as f64 + 0.5;
-
-            let w = inv[6] * px + inv[7] * py + inv[8];
-            // COVERAGE: degenerate homography pixel — requires near-singular perspective
-            if w.abs() < 1e-12 {
+        let bottom = corner_row(iy + 1);
+        for (i, ix) in (x0..x1).enumerate() {
+            let (Some(a), Some(b), Some(c), Some(d)) =
+                (top[i], top[i + 1], bottom[i], bottom[i + 1])
+            else {
                 continue;
-            }
-            let tx = (inv[0] * px + inv[1] * py + inv[2]) / w;
-            let ty = (inv[3] * px + inv[4] * py + inv[5]) / w;
-
-            // Tag-space → grid-space:
-            // tag-space [-1, 1] maps to grid [border_start, grid_size - border_start]
-            let gx = bs + (tx + 1.0) * 0.5 * bw;
-            let gy = bs + (ty + 1.0) * 0.5 * bw;
+            };
 
-            if gx < 0.0 || gx >= grid || gy < 0.0 || gy >= grid {
-                continue;
+            // Box-filter the tag over the pixel's footprint in grid space,
+            // approximated by the bounding box of its projected corners
+            let span = |k: usize| {
+                let v = [a[k], b[k], c[k], d[k]];
+                let lo = v.into_iter().fold(f64::INFINITY, f64::min);
+                let hi = v.into_iter().fold(f64::NEG_INFINITY, f64::max);
+                (lo, hi)
+            };
+            let (gx0, gx1) = span(0);
+            let (gy0, gy1) = span(1);
+            cell_coverage(gx0, gx1, g_min, g_max, &mut wx);
+            cell_coverage(gy0, gy1, g_min, g_max, &mut wy);
+
+            let (mut alpha, mut value) = (0.0, 0.0);
+            for &(cell_y, fy) in &wy {
+                for &(cell_x, fx) in &wx {
+                    let w = fx * fy;
+                    match tag.pixel(cell_x, cell_y) {
+                        Pixel::Black => alpha += w,
+                        Pixel::White => {
+                            alpha += w;
+                            value += w * 255.0;
+                        }
+                        // COVERAGE: only fires with custom families that have transparent cells
+                        Pixel::Transparent => {} // leave background
+                    }
+                }
             }
-
-            let cell_x = gx as usize;
-            let cell_y = gy as usize;
-            let pixel = tag.pixel(cell_x, cell_y);
-
-            match pixel {
-                Pixel::Black => img.set(ix, iy, 0),
-                Pixel::White => img.set(ix, iy, 255),
-                // COVERAGE: only fires with custom families that have transparent cells
-                Pixel::Transparent => {} // leave background
+            if alpha > 0.0 {
+                let background = img.get(ix, iy) as f64;
+                let v = background * (1.0 - alpha).max(0.0) + value;
+                img.set(ix, iy, v.round().clamp(0.0, 255.0) as u8);
             }
         }
+        top = bottom;
+    }
+}
+
+/// The fraction of the footprint `[a, b]` covered by each grid cell, with
+/// cells clipped to the drawn range `[lo, hi)`, as `(cell, fraction)` pairs.
+fn cell_coverage(a: f64, b: f64, lo: f64, hi: f64, out: &mut Vec<(usize, f64)>) {
+    out.clear();
+    // A vanishing footprint degenerates to point sampling
+    let width = (b - a).max(1e-6);
+    let b = a + width;
+    let first = a.max(lo).floor().max(0.0) as usize;
+    let last = b.min(hi).ceil().max(0.0) as usize;
+    for cell in first..last {
+        let overlap = b.min((cell + 1) as f64).min(hi) - a.max(cell as f64).max(lo);
+        if overlap > 0.0 {
+            out.push((cell, overlap / width));
+        }
     }
 }
 
@@ -377,12 +817,6 @@ fn transform_to_homography(transform: &Transform) -> [f64; 9] {
     }
 }
 
-/// Compute the inverse of a 3×3 homography matrix.
-fn inverse_homography(transform: &Transform) -> [f64; 9] {
-    let h = transform_to_homography(transform);
-    invert_3x3(&h)
-}
-
 /// Invert a 3×3 matrix given as a row-major array.
 fn invert_3x3(m: &[f64; 9]) -> [f64; 9] {
     let det = m[0] * (m[4] * m[8] - m[5] * m[7]) - m[1] * (m[3] * m[8] - m[5] * m[6])
@@ -407,6 +841,32 @@ fn invert_3x3(m: &[f64; 9]) -> [f64; 9] {
 mod tests {
     use super::*;
 
+    #[test]
+    fn try_build_reports_bad_placements() {
+        let at = Transform::Similarity {
+            cx: 50.0,
+            cy: 50.0,
+            scale: 20.0,
+            theta: 0.0,
+        };
+        let err = SceneBuilder::new(100, 100)
+            .add_tag("nonexistent", 0, at.clone())
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err, SceneError::UnknownFamily("nonexistent".to_string()));
+
+        let err = SceneBuilder::new(100, 100)
+            .add_tag("tag36h11", 587, at.clone())
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "tag36h11 has 587 tags, no tag 587");
+
+        assert!(SceneBuilder::new(100, 100)
+            .add_tag("tag36h11", 586, at)
+            .try_build()
+            .is_ok());
+    }
+
     #[test]
     fn solid_background_fills_image() {
         let img = fill_background(10, 10, &Background::Solid(200));
@@ -559,6 +1019,57 @@ mod tests {
         assert_eq!(scene.image.get(65, 65), 0);
     }
 
+    #[test]
+    fn tag_edges_are_antialiased() {
+        let build = |cx: f64, theta: f64| {
+            SceneBuilder::new(200, 200)
+                .background(Background::Solid(128))
+                .add_tag(
+                    "tag36h11",
+                    0,
+                    Transform::Similarity {
+                        cx,
+                        cy: 100.0,
+                        scale: 40.0,
+                        theta,
+                    },
+                )
+                .build()
+                .image
+        };
+
+        // Cell edges on pixel boundaries stay crisp
+        let aligned = build(100.0, 0.0);
+        assert!(aligned.buf.iter().all(|&v| [0, 128, 255].contains(&v)));
+
+        // Half a pixel off, the outer white edge covers half of column 49
+        let shifted = build(99.5, 0.0);
+        assert!(shifted.get(49, 100).abs_diff(192) <= 1);
+        assert_eq!(shifted.get(50, 100), 255);
+
+        // Rotated edges blend instead of stepping
+        let rotated = build(100.0, 0.3);
+        let mixed = rotated
+            .buf
+            .iter()
+            .filter(|&&v| ![0, 128, 255].contains(&v))
+            .count();
+        assert!(mixed > 100);
+    }
+
+    #[test]
+    fn cell_coverage_splits_footprint() {
+        let mut out = Vec::new();
+        cell_coverage(1.5, 2.5, 0.0, 10.0, &mut out);
+        assert_eq!(out, [(1, 0.5), (2, 0.5)]);
+        cell_coverage(1.5, 2.5, 0.0, 2.25, &mut out);
+        assert_eq!(out, [(1, 0.5), (2, 0.25)]);
+        cell_coverage(3.25, 3.25, 0.0, 10.0, &mut out);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, 3);
+        assert!((out[0].1 - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn scene_multiple_tags() {
         let scene = SceneBuilder::new(400, 200)
@@ -596,6 +1107,50 @@ mod tests {
         assert!(c1 == 0 || c1 == 255);
     }
 
+    #[test]
+    fn custom_family_is_rendered_and_detected() {
+        let mut custom = family::tag25h9();
+        custom.config.name = "myTag25h9".into();
+        custom.codes = custom.codes[10..20].to_vec();
+
+        let at = |cx: f64| Transform::Similarity {
+            cx,
+            cy: 100.0,
+            scale: 40.0,
+            theta: 0.0,
+        };
+        let scene = SceneBuilder::new(400, 200)
+            .add_tag_family(&custom, 0, at(100.0))
+            // Resolved by name once the family is known
+            .add_tag("myTag25h9", 3, at(300.0))
+            .build();
+
+        let names: Vec<&str> = scene
+            .ground_truth
+            .iter()
+            .map(|t| t.family_name.as_str())
+            .collect();
+        assert_eq!(names, ["myTag25h9", "myTag25h9"]);
+
+        let detector = apriltag::Detector::builder().add_family(custom, 0).build();
+        let mut buffers = apriltag::DetectorBuffers::new();
+        let mut detections: Vec<(String, i32, f64)> = detector
+            .detect(&scene.image, &mut buffers)
+            .iter()
+            .map(|d| (d.family_id.to_string(), d.id, d.center[0]))
+            .collect();
+        detections.sort_by(|a, b| a.2.total_cmp(&b.2));
+        assert_eq!(detections.len(), 2);
+        assert_eq!(
+            (detections[0].0.as_str(), detections[0].1),
+            ("myTag25h9", 0)
+        );
+        assert_eq!(
+            (detections[1].0.as_str(), detections[1].1),
+            ("myTag25h9", 3)
+        );
+    }
+
     #[test]
     fn gradient_background_height_1() {
         // Edge case: height=1 uses the t=0.0 branch
@@ -693,11 +1248,162 @@ mod tests {
         // Exercise the Transform::Perspective arm of transform_to_homography
         let h = [50.0, 0.0, 100.0, 0.0, 50.0, 100.0, 0.0, 0.0, 1.0];
         let t = Transform::Perspective { h };
-        let inv = inverse_homography(&t);
+        let inv = invert_3x3(&transform_to_homography(&t));
         // inv of a scale+translate: (100,100) → (0,0)
         let tx = inv[0] * 100.0 + inv[1] * 100.0 + inv[2];
         let ty = inv[3] * 100.0 + inv[4] * 100.0 + inv[5];
         assert!((tx).abs() < 1e-10);
         assert!((ty).abs() < 1e-10);
     }
+
+    #[test]
+    fn camera_ground_truth_matches_projection() {
+        let camera = Camera::centered(640, 480, 500.0);
+        let scene = SceneBuilder::new(640, 480)
+            .camera(camera)
+            .add_tag(
+                "tag36h11",
+                0,
+                Transform::FromPose {
+                    center: [400.0, 300.0],
+                    size: 100.0,
+                    roll: 0.2,
+                    tilt_x: 0.3,
+                    tilt_y: 0.1,
+                },
+            )
+            .build();
+
+        let gt = &scene.ground_truth[0];
+        assert!((gt.center[0] - 400.0).abs() < 1e-9);
+        assert!((gt.center[1] - 300.0).abs() < 1e-9);
+        assert_eq!(gt.gt_pose_params.as_ref().unwrap().cx, 320.0);
+        assert_eq!(scene.camera, Some(camera));
+
+        // Rotation ground truth differs from the legacy path only through
+        // the camera's principal point, not the tag orientation.
+        let legacy = legacy_pose_ground_truth(&Transform::FromPose {
+            center: [400.0, 300.0],
+            size: 100.0,
+            roll: 0.2,
+            tilt_x: 0.3,
+            tilt_y: 0.1,
+        })
+        .unwrap();
+        assert_eq!(gt.gt_rotation.unwrap(), legacy.0);
+    }
+
+    #[test]
+    fn camera_distortion_bends_tag_edges() {
+        let transform = Transform::FromPose {
+            center: [320.0, 240.0],
+            size: 300.0,
+            roll: 0.0,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+        };
+        let plain = SceneBuilder::new(640, 480)
+            .camera(Camera::centered(640, 480, 400.0))
+            .add_tag("tag36h11", 0, transform.clone())
+            .build();
+        let barrel = SceneBuilder::new(640, 480)
+            .camera(Camera::centered(640, 480, 400.0).with_distortion(-0.3, 0.0))
+            .add_tag("tag36h11", 0, transform)
+            .build();
+
+        // Barrel distortion pulls the corners toward the principal point.
+        let c_plain = plain.ground_truth[0].corners[0];
+        let c_barrel = barrel.ground_truth[0].corners[0];
+        assert!(c_barrel[0] > c_plain[0]);
+        assert!(c_barrel[1] > c_plain[1]);
+        assert_ne!(plain.image.buf, barrel.image.buf);
+    }
+
+    #[test]
+    fn quiet_zone_limits_white_margin() {
+        // tag36h11 at scale 40: one module is 10px, the black border spans
+        // [60, 140) and the full white margin spans [50, 60).
+        let build = |qz: Option<f64>| {
+            let mut builder = SceneBuilder::new(200, 200).background(Background::Solid(128));
+            if let Some(qz) = qz {
+                builder = builder.quiet_zone(qz);
+            }
+            builder
+                .add_tag(
+                    "tag36h11",
+                    0,
+                    Transform::Similarity {
+                        cx: 100.0,
+                        cy: 100.0,
+                        scale: 40.0,
+                        theta: 0.0,
+                    },
+                )
+                .build()
+        };
+
+        let full = build(None);
+        assert_eq!(full.image.get(52, 100), 255);
+        assert_eq!(full.image.get(57, 100), 255);
+
+        let half = build(Some(0.5));
+        assert_eq!(half.image.get(52, 100), 128);
+        assert_eq!(half.image.get(57, 100), 255);
+
+        let none = build(Some(0.0));
+        assert_eq!(none.image.get(57, 100), 128);
+        // The black border itself is untouched
+        assert_eq!(none.image.get(62, 100), 0);
+    }
+
+    #[test]
+    fn layout_seed_moves_layout_rigidly_inside_image() {
+        let build = |seed: Option<u64>| {
+            SceneBuilder::new(400, 300)
+                .add_tag(
+                    "tag16h5",
+                    0,
+                    Transform::Similarity {
+                        cx: 100.0,
+                        cy: 100.0,
+                        scale: 30.0,
+                        theta: 0.0,
+                    },
+                )
+                .add_tag(
+                    "tag16h5",
+                    1,
+                    Transform::FromPose {
+                        center: [200.0, 150.0],
+                        size: 60.0,
+                        roll: 0.0,
+                        tilt_x: 0.0,
+                        tilt_y: 0.0,
+                    },
+                )
+                .layout_seed(seed)
+                .build()
+        };
+        let dist = |scene: &Scene| {
+            let [a, b] = [scene.ground_truth[0].center, scene.ground_truth[1].center];
+            (a[0] - b[0]).hypot(a[1] - b[1])
+        };
+
+        let fixed = build(None);
+        for seed in 0..20 {
+            let moved = build(Some(seed));
+            assert!((dist(&moved) - dist(&fixed)).abs() < 1e-9);
+            for tag in &moved.ground_truth {
+                for [x, y] in tag.corners {
+                    assert!((0.0..=400.0).contains(&x) && (0.0..=300.0).contains(&y));
+                }
+            }
+            let first = build(Some(seed));
+            assert_eq!(first.ground_truth[0].corners, moved.ground_truth[0].corners);
+        }
+        assert_ne!(
+            build(Some(3)).ground_truth[0].corners,
+            fixed.ground_truth[0].corners
+        );
+    }
 }
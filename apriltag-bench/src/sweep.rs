@@ -0,0 +1,323 @@
+/// Multi-tag performance sweep: scene grids across tag count, family mix,
+/// decimation and distortion conditions.
+use apriltag::Detector;
+
+use crate::distortion::{self, Distortion};
+use crate::scene::{Background, Scene, SceneBuilder};
+use crate::transform::Transform;
+
+/// Tag count, image size and tag scale for one sweep grid.
+#[derive(Debug, Clone, Copy)]
+pub struct TagGrid {
+    pub tags: usize,
+    pub width: u32,
+    pub height: u32,
+    pub tag_scale: f64,
+}
+
+const fn grid(tags: usize, width: u32, height: u32, tag_scale: f64) -> TagGrid {
+    TagGrid {
+        tags,
+        width,
+        height,
+        tag_scale,
+    }
+}
+
+const FULL_TAG_GRIDS: &[TagGrid] = &[
+    grid(1, 500, 500, 80.0),
+    grid(5, 800, 600, 50.0),
+    grid(10, 1000, 800, 45.0),
+    grid(25, 1280, 960, 35.0),
+    grid(25, 2000, 1500, 50.0),
+    grid(50, 4000, 3000, 45.0),
+    grid(70, 6000, 4000, 40.0),
+];
+const QUICK_TAG_GRIDS: &[TagGrid] = &[
+    grid(1, 500, 500, 80.0),
+    grid(25, 2000, 1500, 50.0),
+    grid(70, 6000, 4000, 40.0),
+];
+
+/// Family modes: "single" is tag36h11 only, "mixed" alternates with tagStandard52h13.
+const FAMILY_MODES: &[&str] = &["single", "mixed"];
+
+const FULL_DECIMATES: &[f32] = &[1.0, 2.0, 4.0];
+const QUICK_DECIMATES: &[f32] = &[1.0, 2.0];
+
+/// A distortion condition applied uniformly to every tag in a sweep scene.
+#[derive(Debug, Clone)]
+pub struct SweepCondition {
+    pub name: &'static str,
+    pub rotation_deg: f64,
+    pub tilt_x_deg: f64,
+    pub distortions: Vec<Distortion>,
+}
+
+/// One generated sweep scene with its parameters.
+pub struct SweepScene {
+    pub name: String,
+    pub tags: usize,
+    pub condition: String,
+    pub families: String,
+    pub quad_decimate: f32,
+    pub scene: Scene,
+}
+
+/// The parameter grid for a sweep.
+pub struct SweepPlan {
+    pub tag_grids: &'static [TagGrid],
+    pub family_modes: &'static [&'static str],
+    pub decimates: &'static [f32],
+    pub conditions: Vec<SweepCondition>,
+}
+
+impl SweepPlan {
+    /// The complete sweep.
+    pub fn full() -> Self {
+        Self {
+            tag_grids: FULL_TAG_GRIDS,
+            family_modes: FAMILY_MODES,
+            decimates: FULL_DECIMATES,
+            conditions: all_conditions(),
+        }
+    }
+
+    /// A reduced sweep: fewer grids and decimations, clean + combined conditions only.
+    pub fn quick() -> Self {
+        let mut conditions = all_conditions();
+        conditions.retain(|c| c.name == "clean" || c.name == "combined");
+        Self {
+            tag_grids: QUICK_TAG_GRIDS,
+            family_modes: FAMILY_MODES,
+            decimates: QUICK_DECIMATES,
+            conditions,
+        }
+    }
+
+    /// Number of scenes this plan generates.
+    pub fn len(&self) -> usize {
+        self.tag_grids.len()
+            * self.family_modes.len()
+            * self.decimates.len()
+            * self.conditions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Generate every scene in the plan.
+    pub fn scenes(&self) -> Vec<SweepScene> {
+        let mut sweep_scenes = Vec::with_capacity(self.len());
+
+        for g in self.tag_grids {
+            let positions = grid_positions(g.tags, g.width, g.height, g.tag_scale);
+
+            for &family_mode in self.family_modes {
+                for cond in &self.conditions {
+                    for &decimate in self.decimates {
+                        let name = format!(
+                            "{}tags-{}-{}-dec{}",
+                            g.tags, family_mode, cond.name, decimate as u32
+                        );
+                        let scene = build_scene(g, &positions, family_mode == "mixed", cond);
+
+                        sweep_scenes.push(SweepScene {
+                            name,
+                            tags: g.tags,
+                            condition: cond.name.to_string(),
+                            families: family_mode.to_string(),
+                            quad_decimate: decimate,
+                            scene,
+                        });
+                    }
+                }
+            }
+        }
+
+        sweep_scenes
+    }
+}
+
+/// Build a Rust detector for a sweep family mode and decimation.
+pub fn sweep_detector(family_mode: &str, quad_decimate: f32) -> Detector {
+    let builder = Detector::builder()
+        .quad_decimate(quad_decimate)
+        .family("tag36h11", 2);
+    if family_mode == "mixed" {
+        builder.family("tagStandard52h13", 2).build()
+    } else {
+        builder.build()
+    }
+}
+
+fn all_conditions() -> Vec<SweepCondition> {
+    vec![
+        SweepCondition {
+            name: "clean",
+            rotation_deg: 0.0,
+            tilt_x_deg: 0.0,
+            distortions: vec![],
+        },
+        SweepCondition {
+            name: "rotation-30",
+            rotation_deg: 30.0,
+            tilt_x_deg: 0.0,
+            distortions: vec![],
+        },
+        SweepCondition {
+            name: "tilt-20",
+            rotation_deg: 0.0,
+            tilt_x_deg: 20.0,
+            distortions: vec![],
+        },
+        SweepCondition {
+            name: "noise-20",
+            rotation_deg: 0.0,
+            tilt_x_deg: 0.0,
+            distortions: vec![Distortion::GaussianNoise {
+                sigma: 20.0,
+                seed: 42,
+            }],
+        },
+        SweepCondition {
+            name: "blur-2",
+            rotation_deg: 0.0,
+            tilt_x_deg: 0.0,
+            distortions: vec![Distortion::GaussianBlur { sigma: 2.0 }],
+        },
+        SweepCondition {
+            name: "contrast-25",
+            rotation_deg: 0.0,
+            tilt_x_deg: 0.0,
+            distortions: vec![Distortion::ContrastScale { factor: 0.25 }],
+        },
+        SweepCondition {
+            name: "combined",
+            rotation_deg: 15.0,
+            tilt_x_deg: 15.0,
+            distortions: vec![
+                Distortion::GaussianNoise {
+                    sigma: 10.0,
+                    seed: 42,
+                },
+                Distortion::GaussianBlur { sigma: 1.0 },
+            ],
+        },
+    ]
+}
+
+fn build_scene(g: &TagGrid, positions: &[(f64, f64)], mixed: bool, cond: &SweepCondition) -> Scene {
+    let mut builder = SceneBuilder::new(g.width, g.height).background(Background::Solid(128));
+
+    for (id, &(cx, cy)) in positions.iter().enumerate() {
+        let tag_family = if mixed && id % 2 == 1 {
+            "tagStandard52h13"
+        } else {
+            "tag36h11"
+        };
+
+        let transform = if cond.tilt_x_deg.abs() > 0.01 {
+            Transform::FromPose {
+                center: [cx, cy],
+                size: g.tag_scale,
+                roll: cond.rotation_deg.to_radians(),
+                tilt_x: cond.tilt_x_deg.to_radians(),
+                tilt_y: 0.0,
+            }
+        } else {
+            Transform::Similarity {
+                cx,
+                cy,
+                scale: g.tag_scale / 2.0,
+                theta: cond.rotation_deg.to_radians(),
+            }
+        };
+
+        builder = builder.add_tag(tag_family, id as u32, transform);
+    }
+
+    let mut scene = builder.build();
+    if !cond.distortions.is_empty() {
+        distortion::apply(&mut scene.image, &cond.distortions);
+    }
+    scene
+}
+
+/// Compute grid positions for N tags within an image, with spacing for borders.
+pub fn grid_positions(n: usize, width: u32, height: u32, tag_scale: f64) -> Vec<(f64, f64)> {
+    if n == 1 {
+        return vec![(width as f64 / 2.0, height as f64 / 2.0)];
+    }
+
+    // Compute grid dimensions
+    let cols = (n as f64).sqrt().ceil() as usize;
+    let rows = n.div_ceil(cols);
+
+    let margin = tag_scale * 1.2;
+    let usable_w = width as f64 - 2.0 * margin;
+    let usable_h = height as f64 - 2.0 * margin;
+
+    let step_x = if cols > 1 {
+        usable_w / (cols - 1) as f64
+    } else {
+        0.0
+    };
+    let step_y = if rows > 1 {
+        usable_h / (rows - 1) as f64
+    } else {
+        0.0
+    };
+
+    let mut positions = Vec::with_capacity(n);
+    for i in 0..n {
+        let col = i % cols;
+        let row = i / cols;
+        let x = margin + col as f64 * step_x;
+        let y = margin + row as f64 * step_y;
+        positions.push((x, y));
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_sizes() {
+        assert_eq!(SweepPlan::full().len(), 7 * 2 * 3 * 7);
+        assert_eq!(SweepPlan::quick().len(), 3 * 2 * 2 * 2);
+    }
+
+    #[test]
+    fn grid_positions_single_is_centered() {
+        assert_eq!(grid_positions(1, 400, 200, 50.0), vec![(200.0, 100.0)]);
+    }
+
+    #[test]
+    fn grid_positions_stay_inside_margin() {
+        let positions = grid_positions(10, 1000, 800, 45.0);
+        assert_eq!(positions.len(), 10);
+        for (x, y) in positions {
+            assert!((54.0..=946.0).contains(&x));
+            assert!((54.0..=746.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn mixed_scene_alternates_families() {
+        let plan = SweepPlan::quick();
+        let cond = &plan.conditions[0];
+        let g = &grid(5, 800, 600, 50.0);
+        let scene = build_scene(g, &grid_positions(5, 800, 600, 50.0), true, cond);
+        let fams: Vec<_> = scene
+            .ground_truth
+            .iter()
+            .map(|t| t.family_name.as_str())
+            .collect();
+        assert_eq!(fams[0], "tag36h11");
+        assert_eq!(fams[1], "tagStandard52h13");
+    }
+}
@@ -5,6 +5,9 @@
 /// (-1,-1), (1,-1), (1,1), (-1,1) (top-left, top-right, bottom-right, bottom-left).
 use serde::{Deserialize, Serialize};
 
+use crate::camera::Camera;
+use crate::distortion::Rng;
+
 /// A geometric transform mapping tag-space → image-space.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Transform {
@@ -94,6 +97,93 @@ impl Transform {
         }
         corners_img
     }
+
+    /// Sample a physically plausible viewpoint of a tag within `bounds`.
+    ///
+    /// The camera distance is uniform over `bounds.distance` and sets the
+    /// apparent size through the pinhole model. The tag normal is uniform
+    /// over the cone of directions within `bounds.max_tilt` of the optical
+    /// axis, so steep views are as common as they are in real footage rather
+    /// than as common as head-on ones. Roll is uniform, and the center is
+    /// uniform over the positions that keep the whole tag plus
+    /// `bounds.margin` inside the image.
+    ///
+    /// Render with [`PoseBounds::camera`] set on the scene for ground-truth
+    /// poses at the sampled distance.
+    pub fn random_pose(rng: &mut Rng, bounds: &PoseBounds) -> Transform {
+        let [near, far] = bounds.distance;
+        let distance = near + rng.next_f64() * (far - near);
+        let size = size_at_distance(bounds.tag_size, distance, bounds.focal_length);
+
+        // Uniform on the spherical cap: cos(tilt) uniform in [cos(max), 1]
+        let cos_tilt = 1.0 - rng.next_f64() * (1.0 - bounds.max_tilt.cos());
+        let tilt = cos_tilt.clamp(-1.0, 1.0).acos();
+        let azimuth = rng.next_f64() * std::f64::consts::TAU;
+        let roll = (rng.next_f64() * 2.0 - 1.0) * std::f64::consts::PI;
+
+        // Ry(tilt_x) · Rx(tilt_y) turns the normal by `tilt` toward `azimuth`
+        let tilt_y = (tilt.sin() * azimuth.sin()).asin();
+        let tilt_x = (tilt.sin() * azimuth.cos()).atan2(tilt.cos());
+
+        // Perspective can push the near corners past the fronto-parallel
+        // half-diagonal, so keep a little extra room.
+        let reach = size * std::f64::consts::FRAC_1_SQRT_2 * 1.25 + bounds.margin;
+        let mut axis = |extent: u32| {
+            let extent = extent as f64;
+            if extent > 2.0 * reach {
+                reach + rng.next_f64() * (extent - 2.0 * reach)
+            } else {
+                extent / 2.0
+            }
+        };
+        let center = [axis(bounds.width), axis(bounds.height)];
+
+        Transform::FromPose {
+            center,
+            size,
+            roll,
+            tilt_x,
+            tilt_y,
+        }
+    }
+}
+
+/// Viewpoint ranges for [`Transform::random_pose`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoseBounds {
+    /// Image width in pixels.
+    pub width: u32,
+    /// Image height in pixels.
+    pub height: u32,
+    /// Focal length in pixels.
+    pub focal_length: f64,
+    /// Tag side length, in the units of `distance`.
+    pub tag_size: f64,
+    /// Nearest and farthest camera distance.
+    pub distance: [f64; 2],
+    /// Largest angle between the tag normal and the optical axis, radians.
+    pub max_tilt: f64,
+    /// Pixels to keep between the tag and the image edges.
+    pub margin: f64,
+}
+
+impl PoseBounds {
+    /// A centered camera with square pixels matching these bounds.
+    pub fn camera(&self) -> Camera {
+        Camera::centered(self.width, self.height, self.focal_length)
+    }
+}
+
+/// Apparent side length in pixels of a fronto-parallel tag `tag_size` wide,
+/// seen from `distance` (same units) through `focal_length` pixels.
+pub fn size_at_distance(tag_size: f64, distance: f64, focal_length: f64) -> f64 {
+    focal_length * tag_size / distance
+}
+
+/// Distance at which a tag `tag_size` wide appears `size` pixels wide; the
+/// inverse of [`size_at_distance`].
+pub fn distance_for_size(tag_size: f64, size: f64, focal_length: f64) -> f64 {
+    focal_length * tag_size / size
 }
 
 /// Build a 3×3 homography from an ergonomic pose specification.
@@ -321,6 +411,49 @@ mod tests {
         assert!(approx_eq(c1[0][0], c0[1][0]) && approx_eq(c1[0][1], c0[1][1]));
     }
 
+    #[test]
+    fn distance_sizing_round_trips() {
+        // A 16 cm tag at 2 m through an 800 px lens
+        assert!(approx_eq(size_at_distance(0.16, 2.0, 800.0), 64.0));
+        assert!(approx_eq(distance_for_size(0.16, 64.0, 800.0), 2.0));
+    }
+
+    #[test]
+    fn random_poses_stay_within_bounds() {
+        let bounds = PoseBounds {
+            width: 640,
+            height: 480,
+            focal_length: 600.0,
+            tag_size: 0.1,
+            distance: [0.5, 2.0],
+            max_tilt: 60f64.to_radians(),
+            margin: 4.0,
+        };
+        let mut rng = Rng::new(7);
+        for _ in 0..200 {
+            let Transform::FromPose {
+                center,
+                size,
+                roll,
+                tilt_x,
+                tilt_y,
+            } = Transform::random_pose(&mut rng, &bounds)
+            else {
+                panic!("random_pose places tags by pose");
+            };
+            assert!((30.0 - 1e-9..=120.0 + 1e-9).contains(&size), "{size}");
+            let tilt = (tilt_x.cos() * tilt_y.cos()).acos();
+            assert!(tilt <= bounds.max_tilt + 1e-9, "{tilt}");
+
+            // Rendered through the matching camera
+            let h = bounds.camera().place(center, size, roll, tilt_x, tilt_y).h;
+            let corners = Transform::Perspective { h }.ground_truth_corners();
+            for [x, y] in corners {
+                assert!((0.0..640.0).contains(&x) && (0.0..480.0).contains(&y));
+            }
+        }
+    }
+
     #[test]
     fn ground_truth_corners_matches_project() {
         let t = Transform::FromPose {
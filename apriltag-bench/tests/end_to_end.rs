@@ -1,6 +1,8 @@
 /// End-to-end integration tests: build scenes → detect → evaluate metrics.
 use apriltag::family;
 use apriltag::{Detector, DetectorBuffers, DetectorConfig};
+use apriltag_bench::camera::Camera;
+use apriltag_bench::catalog;
 use apriltag_bench::distortion::{self, Distortion};
 use apriltag_bench::metrics;
 use apriltag_bench::scene::{Background, SceneBuilder};
@@ -146,6 +148,23 @@ fn detect_with_noise() {
     assert_eq!(detections[0].id, 0);
 }
 
+#[test]
+fn dense_array_detects_each_tag_once() {
+    let scenario = catalog::all_scenarios()
+        .into_iter()
+        .find(|s| s.name == "density-200tags")
+        .unwrap();
+    let scene = scenario.build();
+
+    let detector = detector_with_family("tag36h11");
+    let detections = detector.detect(&scene.image, &mut DetectorBuffers::new());
+    let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+
+    assert_eq!(result.detection_rate, 1.0);
+    assert_eq!(detections.len(), 200, "duplicates survived deduplication");
+    assert!(result.false_positives.is_empty());
+}
+
 #[test]
 fn detect_with_perspective_tilt() {
     // Moderate tilt in a large image to ensure the tag fits comfortably
@@ -179,6 +198,43 @@ fn detect_with_perspective_tilt() {
     );
 }
 
+#[test]
+fn camera_pose_recovers_ground_truth_off_center() {
+    // Off-center, tilted tag seen through a camera whose principal point is
+    // the image center: the estimator should recover the rendered pose.
+    let scene = SceneBuilder::new(640, 480)
+        .background(Background::Solid(128))
+        .camera(Camera::centered(640, 480, 600.0))
+        .add_tag(
+            "tag36h11",
+            0,
+            Transform::FromPose {
+                center: [460.0, 150.0],
+                size: 120.0,
+                roll: 0.3,
+                tilt_x: 0.4,
+                tilt_y: -0.2,
+            },
+        )
+        .build();
+
+    let detector = detector_with_family("tag36h11");
+    let detections = detector.detect(&scene.image, &mut DetectorBuffers::new());
+    let result = metrics::evaluate(&scene.ground_truth, &detections, 0);
+
+    assert_eq!(result.detection_rate, 1.0);
+    let rot_err = result.mean_rotation_error_deg.unwrap();
+    assert!(
+        rot_err < 3.0,
+        "rotation error should be < 3°, got {rot_err}"
+    );
+    let t_err = result.mean_translation_error_frac.unwrap();
+    assert!(
+        t_err < 0.05,
+        "translation error should be < 5%, got {t_err}"
+    );
+}
+
 #[test]
 fn detect_with_gradient_lighting() {
     let mut scene = SceneBuilder::new(300, 300)
@@ -1,18 +1,18 @@
 /**
  * AprilTag Bench — interactive web UI.
  *
- * Loads two WASM modules:
- * - apriltag-bench-wasm: scene generation + distortion
- * - apriltag-wasm: tag detection
+ * Loads apriltag-bench-wasm, whose SceneRunner generates a scene, detects it
+ * with the detector configured by the controls, and scores the detections
+ * against the ground truth in one call.
  *
- * Flow: slider change → generate scene (bench WASM) → detect (apriltag WASM)
- *       → compute metrics → render canvas + overlays.
+ * Flow: control change → (rebuild runner if the detector config changed)
+ *       → run scene (bench WASM) → render canvas + overlays + metrics.
  */
 
-// WASM module references (set after init)
+// WASM module reference (set after init)
 let benchWasm = null;
-let detectorWasm = null;
-let detector = null;
+let runner = null;
+let lastRunnerKey = "";
 
 const statusEl = document.getElementById("status");
 
@@ -20,39 +20,17 @@ const statusEl = document.getElementById("status");
 
 async function initWasm() {
   try {
-    // Try loading WASM modules from expected paths
-    // wasm-pack builds to pkg/ directories
+    // wasm-pack builds to pkg/
     const benchPkg = "../../apriltag-bench-wasm/pkg/apriltag_bench_wasm.js";
-    const detectorPkg = "../../apriltag-wasm/pkg/apriltag_wasm.js";
-
-    const [benchMod, detectorMod] = await Promise.all([
-      import(benchPkg).catch(() => null),
-      import(detectorPkg).catch(() => null),
-    ]);
+    const benchMod = await import(benchPkg).catch(() => null);
 
     if (benchMod) {
       if (benchMod.default) await benchMod.default();
       benchWasm = benchMod;
-    }
-
-    if (detectorMod) {
-      if (detectorMod.default) await detectorMod.default();
-      detectorWasm = detectorMod;
-      // Create a detector instance with full config
-      const config = buildDetectorConfig(getFamily());
-      detector = new detectorMod.Detector(config);
-      lastDetectorKey = detectorConfigKey(getFamily());
-    }
-
-    if (benchWasm && detectorWasm) {
       statusEl.textContent = "Ready";
-    } else if (benchWasm) {
-      statusEl.textContent = "Scene gen ready (detector WASM not found)";
-    } else if (detectorWasm) {
-      statusEl.textContent = "Detector ready (bench WASM not found)";
     } else {
       statusEl.textContent =
-        "WASM not loaded — run wasm-pack build for both crates. Using demo mode.";
+        "WASM not loaded — run wasm-pack build apriltag-bench-wasm --target web. Using demo mode.";
     }
   } catch (e) {
     statusEl.textContent = `WASM load error: ${e.message}. Using demo mode.`;
@@ -86,6 +64,9 @@ const controls = {
   quadSigma:       { el: document.getElementById("quadSigma"),       valEl: document.getElementById("quadSigmaVal"),       decimals: 1 },
   refineEdges:     { el: document.getElementById("refineEdges") },
   decodeSharpening:{ el: document.getElementById("decodeSharpening"),valEl: document.getElementById("decodeSharpeningVal"),decimals: 2 },
+  refineHomography:{ el: document.getElementById("refineHomography") },
+  refineDecode:    { el: document.getElementById("refineDecode") },
+  minDecisionMargin:{ el: document.getElementById("minDecisionMargin"), valEl: document.getElementById("minDecisionMarginVal") },
   maxHamming:      { el: document.getElementById("maxHamming") },
   // Quad threshold params
   minClusterPixels:  { el: document.getElementById("minClusterPixels") },
@@ -108,6 +89,7 @@ const metricEls = {
   genTime: document.getElementById("metricGenTime"),
   detTime: document.getElementById("metricDetTime"),
   count:   document.getElementById("metricCount"),
+  falsePos: document.getElementById("metricFalsePos"),
 };
 
 const detectionsEl = document.getElementById("detections");
@@ -130,6 +112,9 @@ function getQuadDecimate()    { return parseFloat(controls.quadDecimate.el.value
 function getQuadSigma()       { return parseFloat(controls.quadSigma.el.value); }
 function getRefineEdges()     { return controls.refineEdges.el.checked; }
 function getDecodeSharpening(){ return parseFloat(controls.decodeSharpening.el.value); }
+function getRefineHomography(){ return controls.refineHomography.el.checked; }
+function getRefineDecode()    { return controls.refineDecode.el.checked; }
+function getMinDecisionMargin(){ return parseFloat(controls.minDecisionMargin.el.value); }
 function getMaxHamming()      { return parseInt(controls.maxHamming.el.value, 10); }
 function getMinClusterPixels(){ return parseInt(controls.minClusterPixels.el.value, 10); }
 function getMaxNmaxima()      { return parseInt(controls.maxNmaxima.el.value, 10); }
@@ -142,31 +127,36 @@ function showDet()     { return controls.showDet.el.checked; }
 function showErrors()  { return controls.showErrors.el.checked; }
 
 /**
- * Build a WasmDetectorConfig from current UI state.
- * Returns a plain object matching the WasmDetectorConfig shape.
+ * Build a SceneRunner config from current UI state. `detector` uses the
+ * core DetectorConfig field names; fields left out keep their defaults.
  */
-function buildDetectorConfig(family) {
-  const angleDeg = getCriticalAngle();
-  const angleRad = angleDeg * Math.PI / 180;
+function buildRunnerConfig(family) {
   return {
     families: [family],
-    quad_decimate: getQuadDecimate(),
-    quad_sigma: getQuadSigma(),
-    refine_edges: getRefineEdges(),
-    decode_sharpening: getDecodeSharpening(),
-    max_hamming: getMaxHamming(),
-    min_cluster_pixels: getMinClusterPixels(),
-    max_nmaxima: getMaxNmaxima(),
-    cos_critical_rad: Math.cos(angleRad),
-    max_line_fit_mse: getMaxLineFitMse(),
-    min_white_black_diff: getMinWhiteBlackDiff(),
-    deglitch: getDeglitch(),
+    maxHamming: getMaxHamming(),
+    detector: {
+      quad_decimate: getQuadDecimate(),
+      quad_sigma: getQuadSigma(),
+      refine_edges: getRefineEdges(),
+      decode_sharpening: getDecodeSharpening(),
+      refine_homography: getRefineHomography(),
+      refine_decode: getRefineDecode(),
+      min_decision_margin: getMinDecisionMargin(),
+      quad: {
+        min_cluster_pixels: getMinClusterPixels(),
+        max_nmaxima: getMaxNmaxima(),
+        critical_angle_deg: getCriticalAngle(),
+        max_line_fit_mse: getMaxLineFitMse(),
+        min_white_black_diff: getMinWhiteBlackDiff(),
+        deglitch: getDeglitch(),
+      },
+    },
   };
 }
 
-/** Serialize detector config to a string for change detection. */
-function detectorConfigKey(family) {
-  return JSON.stringify(buildDetectorConfig(family));
+/** Serialize runner config to a string for change detection. */
+function runnerConfigKey(family) {
+  return JSON.stringify(buildRunnerConfig(family));
 }
 
 // ── Event Binding ────────────────────────────────────────────────────
@@ -194,102 +184,65 @@ for (const [, ctrl] of Object.entries(controls)) {
 
 // ── Core Update Loop ─────────────────────────────────────────────────
 
-let lastScene = null;
-let lastDetections = [];
-let lastDetectorKey = "";
-
-async function update() {
+function update() {
   const width = getWidth();
   const height = getHeight();
   const family = getFamily();
-  const tagId = getTagId();
-  const tagSize = getTagSize();
-  const rotation = getRotation();
-  const tiltX = getTiltX();
-  const tiltY = getTiltY();
-  const noise = getNoise();
-  const blur = getBlur();
-  const contrast = getContrast();
 
   canvas.width = width;
   canvas.height = height;
 
-  let imageData;
-  let groundTruth = [];
-  let genTimeMs = 0;
-
-  if (benchWasm && benchWasm.generateScene) {
-    // Use WASM scene generation
-    const t0 = performance.now();
-    try {
-      const scene = benchWasm.generateScene(
-        width, height, family, tagId, tagSize,
-        rotation, tiltX, tiltY, noise, blur, contrast
-      );
-      genTimeMs = performance.now() - t0;
-      lastScene = scene;
-
-      // Convert grayscale to RGBA for canvas
-      const gray = scene.imageData;
-      imageData = new ImageData(width, height);
-      for (let i = 0; i < gray.length; i++) {
-        const v = gray[i];
-        imageData.data[i * 4]     = v;
-        imageData.data[i * 4 + 1] = v;
-        imageData.data[i * 4 + 2] = v;
-        imageData.data[i * 4 + 3] = 255;
-      }
-      groundTruth = scene.groundTruth || [];
-    } catch (e) {
-      console.error("Scene generation error:", e);
-      statusEl.textContent = `Error: ${e.message}`;
-      return;
-    }
-  } else {
+  if (!benchWasm) {
     // Demo mode: draw a placeholder
-    imageData = new ImageData(width, height);
-    for (let i = 0; i < width * height; i++) {
-      const v = 128;
-      imageData.data[i * 4]     = v;
-      imageData.data[i * 4 + 1] = v;
-      imageData.data[i * 4 + 2] = v;
-      imageData.data[i * 4 + 3] = 255;
-    }
-    genTimeMs = 0;
+    const imageData = new ImageData(width, height);
+    imageData.data.fill(128);
+    for (let i = 3; i < imageData.data.length; i += 4) imageData.data[i] = 255;
+    ctx.putImageData(imageData, 0, 0);
+    return;
   }
 
-  // Render image to canvas
-  ctx.putImageData(imageData, 0, 0);
-
-  // Detect
-  let detections = [];
-  let detTimeMs = 0;
-
-  if (detector && lastScene) {
-    try {
-      // Recreate detector if config changed (family, thresholds, etc.)
-      const configKey = detectorConfigKey(family);
-      if (configKey !== lastDetectorKey) {
-        detector = new detectorWasm.Detector(buildDetectorConfig(family));
-        lastDetectorKey = configKey;
-      }
-      const grayData = lastScene.imageData;
-      const t0 = performance.now();
-      const rawDets = detector.detect(grayData, width, height);
-      detTimeMs = performance.now() - t0;
-      detections = rawDets || [];
-    } catch (e) {
-      console.error("Detection error:", e);
+  let result;
+  try {
+    // Recreate the runner if the detector config changed (family, thresholds, etc.)
+    const configKey = runnerConfigKey(family);
+    if (!runner || configKey !== lastRunnerKey) {
+      if (runner) runner.free();
+      runner = null;
+      runner = new benchWasm.SceneRunner(buildRunnerConfig(family));
+      lastRunnerKey = configKey;
     }
+    result = runner.run({
+      width, height, family,
+      tagId: getTagId(),
+      tagSize: getTagSize(),
+      rotation: getRotation(),
+      tiltX: getTiltX(),
+      tiltY: getTiltY(),
+      noise: getNoise(),
+      blur: getBlur(),
+      contrast: getContrast(),
+    });
+  } catch (e) {
+    console.error("Scene run error:", e);
+    statusEl.textContent = `Error: ${e.message}`;
+    return;
   }
+  statusEl.textContent = "Ready";
+
+  // Convert grayscale to RGBA for canvas
+  const gray = result.imageData;
+  const imageData = new ImageData(width, height);
+  for (let i = 0; i < gray.length; i++) {
+    const v = gray[i];
+    imageData.data[i * 4]     = v;
+    imageData.data[i * 4 + 1] = v;
+    imageData.data[i * 4 + 2] = v;
+    imageData.data[i * 4 + 3] = 255;
+  }
+  ctx.putImageData(imageData, 0, 0);
 
-  lastDetections = detections;
-
-  // Draw overlays
-  drawOverlays(groundTruth, detections);
-
-  // Update metrics
-  updateMetrics(groundTruth, detections, genTimeMs, detTimeMs);
+  drawOverlays(result.groundTruth, result.detections);
+  updateMetrics(result.metrics, result.detections);
 }
 
 // ── Overlay Drawing ──────────────────────────────────────────────────
@@ -306,11 +259,8 @@ function drawOverlays(groundTruth, detections) {
   // Draw detected corners (red)
   if (showDet() && detections.length > 0) {
     for (const det of detections) {
-      const corners = det.corners || formatDetCorners(det);
-      if (corners) {
-        drawQuad(corners, "#f44336", 2);
-        drawCornerDots(corners, "#f44336", 3);
-      }
+      drawQuad(det.corners, "#f44336", 2);
+      drawCornerDots(det.corners, "#f44336", 3);
     }
   }
 
@@ -318,27 +268,13 @@ function drawOverlays(groundTruth, detections) {
   if (showErrors() && groundTruth.length > 0 && detections.length > 0) {
     for (const gt of groundTruth) {
       const matched = findMatchingDetection(gt, detections);
-      if (matched) {
-        const detCorners = matched.corners || formatDetCorners(matched);
-        if (detCorners) {
-          drawErrorLines(gt.corners, detCorners);
-        }
-      }
+      if (matched) drawErrorLines(gt.corners, matched.corners);
     }
   }
 }
 
-function formatDetCorners(det) {
-  // apriltag-wasm may return corners in different formats
-  if (det.corners && Array.isArray(det.corners)) return det.corners;
-  if (det.corner0 !== undefined) {
-    return [det.corner0, det.corner1, det.corner2, det.corner3];
-  }
-  return null;
-}
-
 function findMatchingDetection(gt, detections) {
-  return detections.find(d => d.id === gt.tagId || d.tagId === gt.tagId);
+  return detections.find(d => d.cornerErrors && d.id === gt.tagId && d.family === gt.familyName);
 }
 
 function drawQuad(corners, color, lineWidth) {
@@ -375,78 +311,50 @@ function drawErrorLines(gtCorners, detCorners) {
   ctx.setLineDash([]);
 }
 
-// ── Metrics Computation ──────────────────────────────────────────────
-
-function updateMetrics(groundTruth, detections, genTimeMs, detTimeMs) {
-  const nExpected = groundTruth.length;
-  const nDetected = detections.length;
-
-  // Detection rate
-  let rate = 0;
-  if (nExpected > 0) {
-    let matched = 0;
-    for (const gt of groundTruth) {
-      if (findMatchingDetection(gt, detections)) matched++;
-    }
-    rate = matched / nExpected;
-  }
+// ── Metrics Display ──────────────────────────────────────────────────
 
-  // Corner RMSE
-  let totalSqErr = 0;
-  let nCorners = 0;
-  let maxErr = 0;
-
-  for (const gt of groundTruth) {
-    const det = findMatchingDetection(gt, detections);
-    if (!det) continue;
-    const detCorners = det.corners || formatDetCorners(det);
-    if (!detCorners) continue;
-
-    for (let i = 0; i < 4; i++) {
-      const dx = gt.corners[i][0] - detCorners[i][0];
-      const dy = gt.corners[i][1] - detCorners[i][1];
-      const err = Math.sqrt(dx * dx + dy * dy);
-      totalSqErr += dx * dx + dy * dy;
-      nCorners++;
-      if (err > maxErr) maxErr = err;
-    }
-  }
-
-  const rmse = nCorners > 0 ? Math.sqrt(totalSqErr / nCorners) : 0;
+function updateMetrics(metrics, detections) {
+  const { expected, detected, falsePositives, detectionRate: rate } = metrics;
 
-  // Update display
   const rateEl = metricEls.rate;
   rateEl.textContent = `${(rate * 100).toFixed(0)}%`;
   rateEl.className = `value ${rate >= 1 ? "good" : rate > 0 ? "warn" : "bad"}`;
 
+  // Corner errors are null when nothing was detected
+  const rmse = metrics.cornerRmse;
   const rmseEl = metricEls.rmse;
-  rmseEl.textContent = `${rmse.toFixed(2)} px`;
-  rmseEl.className = `value ${rmse < 1 ? "good" : rmse < 3 ? "warn" : "bad"}`;
+  rmseEl.textContent = rmse == null ? "-" : `${rmse.toFixed(2)} px`;
+  rmseEl.className = `value ${rmse == null ? "neutral" : rmse < 1 ? "good" : rmse < 3 ? "warn" : "bad"}`;
 
+  const maxErr = metrics.maxCornerError;
   const maxErrEl = metricEls.maxErr;
-  maxErrEl.textContent = `${maxErr.toFixed(2)} px`;
-  maxErrEl.className = `value ${maxErr < 1 ? "good" : maxErr < 5 ? "warn" : "bad"}`;
+  maxErrEl.textContent = maxErr == null ? "-" : `${maxErr.toFixed(2)} px`;
+  maxErrEl.className = `value ${maxErr == null ? "neutral" : maxErr < 1 ? "good" : maxErr < 5 ? "warn" : "bad"}`;
 
-  metricEls.genTime.textContent = `${genTimeMs.toFixed(1)} ms`;
+  metricEls.genTime.textContent = `${metrics.genTimeMs.toFixed(1)} ms`;
   metricEls.genTime.className = "value neutral";
 
-  metricEls.detTime.textContent = `${detTimeMs.toFixed(1)} ms`;
+  metricEls.detTime.textContent = `${metrics.detTimeMs.toFixed(1)} ms`;
   metricEls.detTime.className = "value neutral";
 
-  metricEls.count.textContent = nDetected.toString();
-  metricEls.count.className = `value ${nDetected === nExpected && nExpected > 0 ? "good" : nDetected > 0 ? "warn" : nExpected === 0 ? "neutral" : "bad"}`;
+  metricEls.count.textContent = `${detected} / ${expected}`;
+  metricEls.count.className = `value ${detected === expected && expected > 0 ? "good" : detected > 0 ? "warn" : expected === 0 ? "neutral" : "bad"}`;
+
+  metricEls.falsePos.textContent = falsePositives.toString();
+  metricEls.falsePos.className = `value ${falsePositives === 0 ? "good" : "bad"}`;
 
   // Detection list
   detectionsEl.innerHTML = "";
   for (const det of detections) {
     const row = document.createElement("div");
     row.className = "det-row";
-    const id = det.id ?? det.tagId ?? "?";
-    const ham = det.hamming ?? "?";
-    const margin = (det.decisionMargin ?? det.decision_margin ?? 0).toFixed(1);
-    const cx = (det.center?.[0] ?? 0).toFixed(1);
-    const cy = (det.center?.[1] ?? 0).toFixed(1);
-    row.innerHTML = `Tag <span class="tag-id">${id}</span>: hamming=${ham}, margin=${margin}, center=(${cx}, ${cy})`;
+    const margin = det.decisionMargin.toFixed(1);
+    const cx = det.center[0].toFixed(1);
+    const cy = det.center[1].toFixed(1);
+    const err = det.cornerErrors
+      ? `max error=${Math.max(...det.cornerErrors).toFixed(2)} px`
+      : "false positive";
+    row.innerHTML = `Tag <span class="tag-id">${det.id}</span>: hamming=${det.hamming}, margin=${margin}, center=(${cx}, ${cy}), ${err}`;
     detectionsEl.appendChild(row);
   }
 }
@@ -0,0 +1,110 @@
+//! Per-tag pose averages over bursts of images of a static scene.
+
+use std::collections::BTreeMap;
+
+use apriltag::detect::pose::{average_poses, Pose};
+use serde::Serialize;
+
+/// Mean pose and spread of one tag over a burst.
+#[derive(Serialize)]
+pub struct TagAverage {
+    pub family: String,
+    pub id: i32,
+    /// Number of images in the burst the tag's pose was estimated in.
+    pub frames: usize,
+    /// Mean rotation (quaternion mean), row-major.
+    pub rotation: Vec<f64>,
+    /// Mean translation.
+    pub translation: [f64; 3],
+    /// RMS angle in degrees between each frame's rotation and the mean.
+    pub rotation_std_deg: f64,
+    /// Sample standard deviation of the translation per axis.
+    pub translation_std: [f64; 3],
+}
+
+/// The averages of one burst of images.
+#[derive(Serialize)]
+pub struct BurstAverage {
+    pub files: Vec<String>,
+    pub tags: Vec<TagAverage>,
+}
+
+/// Collects the poses of each tag over a burst of images.
+#[derive(Default)]
+pub struct PoseAverager {
+    files: Vec<String>,
+    /// Poses per family and tag ID.
+    poses: BTreeMap<(String, i32), Vec<Pose>>,
+}
+
+impl PoseAverager {
+    /// Number of images added since the last [`finish`](Self::finish).
+    pub fn images(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Start recording the tags of another image.
+    pub fn add_image(&mut self, file: &str) {
+        self.files.push(file.to_string());
+    }
+
+    /// Record one tag's pose in the current image, with `rotation` row-major.
+    pub fn add_pose(&mut self, family: &str, id: i32, rotation: &[f64], translation: [f64; 3]) {
+        let r = [
+            [rotation[0], rotation[1], rotation[2]],
+            [rotation[3], rotation[4], rotation[5]],
+            [rotation[6], rotation[7], rotation[8]],
+        ];
+        self.poses
+            .entry((family.to_string(), id))
+            .or_default()
+            .push(Pose { r, t: translation });
+    }
+
+    /// Average the recorded poses and start a new burst.
+    pub fn finish(&mut self) -> BurstAverage {
+        let files = std::mem::take(&mut self.files);
+        let tags = std::mem::take(&mut self.poses)
+            .into_iter()
+            .filter_map(|((family, id), poses)| average(family, id, &poses))
+            .collect();
+        BurstAverage { files, tags }
+    }
+}
+
+impl BurstAverage {
+    /// Print a human-readable table of the averages to stderr.
+    pub fn print(&self) {
+        eprintln!("average over {} images:", self.files.len());
+        for tag in &self.tags {
+            let t = tag.translation;
+            let s = tag.translation_std;
+            eprintln!(
+                "  {} {:>4}: {:>3} frames, t = ({:.4}, {:.4}, {:.4}) ± ({:.4}, {:.4}, {:.4}), R ± {:.3}°",
+                tag.family,
+                tag.id,
+                tag.frames,
+                t[0],
+                t[1],
+                t[2],
+                s[0],
+                s[1],
+                s[2],
+                tag.rotation_std_deg
+            );
+        }
+    }
+}
+
+fn average(family: String, id: i32, poses: &[Pose]) -> Option<TagAverage> {
+    let average = average_poses(poses)?;
+    Some(TagAverage {
+        family,
+        id,
+        frames: poses.len(),
+        rotation: average.pose.r.concat(),
+        translation: average.pose.t,
+        rotation_std_deg: average.rotation_std.to_degrees(),
+        translation_std: average.translation_std,
+    })
+}
@@ -1,23 +1,50 @@
 #![forbid(unsafe_code)]
 
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::Serialize;
 
-use apriltag::detect::pose::{estimate_tag_pose, Pose, PoseParams};
-use apriltag::detect::quad::QuadThreshParams;
-use apriltag::family;
-use apriltag::{Detector, DetectorBuffers, DetectorConfig, ImageU8};
+use apriltag::detect::debug::DebugImages;
+use apriltag::detect::image::{max_dimension_size, resize, ResizeFilter, ToneMap};
+use apriltag::detect::pose::{estimate_tag_pose, refine_tag_pose, Pose, PoseParams, PoseSolver};
+use apriltag::detect::quad::QuadParams;
+use apriltag::family::{self, TagFamily};
+use apriltag::{
+    DecimateMode, Detection, DetectorBuffers, DetectorBuilder, DetectorConfig, IdFilter, ImageU16,
+    ImageU8, InterlaceField, PixelConvention, Polarity,
+};
+
+mod average;
+mod selftest;
+mod summary;
+
+use average::PoseAverager;
+use summary::Summary;
 
 /// AprilTag detection CLI — detect tags in PNG/JPEG images
 #[derive(Parser)]
-#[command(name = "apriltag-detect", version)]
+#[command(
+    name = "apriltag-detect",
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input image files (PNG or JPEG)
     #[arg(required = true)]
     images: Vec<String>,
 
-    /// Tag family to detect (comma-separated for multiple)
+    /// Tag family to detect (comma-separated for multiple), each optionally
+    /// with its own maximum Hamming distance, e.g. "tag36h11:2,tag16h5:0".
+    /// A family may also be a `.toml` file from `apriltag-gen generate`,
+    /// with its codes in the `.bin` file next to it
     #[arg(short, long, default_value = "tag36h11")]
     family: String,
 
@@ -25,6 +52,11 @@ struct Args {
     #[arg(short = 'd', long, default_value = "2.0")]
     decimate: f32,
 
+    /// Average each block when decimating by an integer factor, instead of
+    /// keeping its top-left pixel; helps small tags at factors of 3 and above
+    #[arg(long)]
+    decimate_average: bool,
+
     /// Gaussian blur sigma (0 = no blur, negative = sharpen)
     #[arg(short = 'b', long, default_value = "0.0")]
     blur: f32,
@@ -33,22 +65,123 @@ struct Args {
     #[arg(short = 's', long, default_value = "0.25")]
     sharpening: f64,
 
-    /// Maximum Hamming distance for tag matching
+    /// Drop detections whose decision margin is below this
+    #[arg(long, default_value = "0.0")]
+    min_decision_margin: f32,
+
+    /// Maximum Hamming distance for tag matching, for families that do not
+    /// set their own
     #[arg(long, default_value = "2")]
     max_hamming: u32,
 
+    /// Only report these tag IDs, e.g. "0-10" or "0,3,7-9"; other IDs are
+    /// not decoded at all
+    #[arg(long)]
+    ids: Option<String>,
+
     /// Disable edge refinement
     #[arg(long)]
     no_refine: bool,
 
+    /// Re-estimate each tag's homography from its cell edges for more
+    /// accurate corners
+    #[arg(long)]
+    refine_homography: bool,
+
+    /// Align each decoded tag's bit pattern with the image for more
+    /// accurate corners
+    #[arg(long)]
+    refine_decode: bool,
+
+    /// Downscale images whose longer side exceeds this many pixels before
+    /// detection (area-averaged; coordinates are reported at full size)
+    #[arg(long)]
+    max_dimension: Option<u32>,
+
+    /// Also detect mirrored tags (seen via a mirror or from behind)
+    #[arg(long)]
+    mirrored: bool,
+
+    /// Convert 16-bit images linearly from this many significant bits,
+    /// instead of stretching each image's 0.5–99.5 percentile range
+    #[arg(long)]
+    bit_depth: Option<u32>,
+
+    /// Side of the adaptive-threshold tiles, in pixels (default: 4 pixels
+    /// of the decimated image); larger tiles help tags with large uniform
+    /// areas
+    #[arg(long)]
+    tile_size: Option<u32>,
+
+    /// Minimum local contrast for the threshold to classify a tile as black
+    /// and white
+    #[arg(long, default_value = "5")]
+    min_white_black_diff: u8,
+
+    /// Remove isolated pixels from the thresholded image before segmenting
+    #[arg(long)]
+    deglitch: bool,
+
+    /// Pixel width divided by pixel height, for anamorphic lenses or
+    /// unevenly binned sensors
+    #[arg(long, default_value = "1.0")]
+    pixel_aspect: f32,
+
+    /// Detect on one field of interlaced frames only: "even" or "odd" rows
+    /// (for analog capture, whose fields comb moving edges). Not with
+    /// --max-dimension, whose resize would blend the two fields first
+    #[arg(long, conflicts_with = "max_dimension")]
+    field: Option<String>,
+
+    /// Report coordinates with integer values at pixel centers (OpenCV's
+    /// convention) instead of pixel corners; pose intrinsics (--fx, --cx,
+    /// ...) must then use the same convention
+    #[arg(long)]
+    pixel_centers: bool,
+
+    /// Which prints to detect: "normal" (black on white), "inverted" (white
+    /// on black, e.g. engraved on dark material) or "either"
+    #[arg(long)]
+    polarity: Option<String>,
+
+    /// Grayscale image the size of the inputs; only search where it is
+    /// bright (128 or above), e.g. to leave out a robot's own chassis or a
+    /// timestamp overlay
+    #[arg(long)]
+    mask: Option<String>,
+
+    /// Fit quads to at most this many clusters per image, dropping the
+    /// smallest first; bounds the time spent on very noisy images
+    #[arg(long)]
+    max_clusters: Option<usize>,
+
+    /// Threshold and segment the image this many rows at a time, using less
+    /// memory on large images (default: the whole image at once)
+    #[arg(long)]
+    band_rows: Option<u32>,
+
+    /// Number of detection threads (default: all cores; 1 = single-threaded)
+    #[arg(short = 't', long)]
+    threads: Option<usize>,
+
+    /// Write images of intermediate pipeline stages (PGM/PPM) to this
+    /// directory, named after each input image and stage
+    #[arg(long)]
+    debug_dir: Option<PathBuf>,
+
     /// Pretty-print JSON output
     #[arg(long)]
     pretty: bool,
 
-    /// Suppress non-JSON output
+    /// Suppress non-JSON output, including the end-of-run summary
     #[arg(short, long)]
     quiet: bool,
 
+    /// Also write the end-of-run summary (per-image and aggregate statistics)
+    /// as JSON to this file
+    #[arg(long)]
+    summary_json: Option<String>,
+
     /// Enable pose estimation (requires camera parameters)
     #[arg(long)]
     pose: bool,
@@ -61,7 +194,7 @@ struct Args {
     #[arg(long)]
     fx: Option<f64>,
 
-    /// Camera focal length y in pixels
+    /// Camera focal length y in pixels (default: fx × --pixel-aspect)
     #[arg(long)]
     fy: Option<f64>,
 
@@ -72,24 +205,43 @@ struct Args {
     /// Camera principal point y in pixels
     #[arg(long)]
     cy: Option<f64>,
+
+    /// Pose solver: "oi" (orthogonal iteration, as the C library) or "ippe"
+    /// (closed form, as OpenCV's SOLVEPNP_IPPE)
+    #[arg(long)]
+    pose_solver: Option<String>,
+
+    /// Refine each pose over the centers of all bit cells, minimizing the
+    /// reprojection error in pixels (with --pose)
+    #[arg(long)]
+    refine_pose: bool,
+
+    /// Treat every N consecutive images as a burst of a static scene and
+    /// print each tag's mean pose and its standard deviation per burst,
+    /// instead of the per-image results (with --pose)
+    #[arg(long, value_name = "N")]
+    average: Option<usize>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render tags with this build, detect them and check that each decodes
+    /// to its own ID; exits with an error if any does not
+    SelfTest(selftest::SelfTestArgs),
 }
 
 #[derive(Serialize)]
-struct OutputResult {
+struct OutputResult<'a> {
     file: String,
     image_width: u32,
     image_height: u32,
-    detections: Vec<OutputDetection>,
+    detections: Vec<OutputDetection<'a>>,
 }
 
 #[derive(Serialize)]
-struct OutputDetection {
-    family: String,
-    id: i32,
-    hamming: i32,
-    decision_margin: f32,
-    center: [f64; 2],
-    corners: [[f64; 2]; 4],
+struct OutputDetection<'a> {
+    #[serde(flatten)]
+    detection: &'a Detection,
     #[serde(skip_serializing_if = "Option::is_none")]
     pose: Option<OutputPose>,
 }
@@ -99,12 +251,23 @@ struct OutputPose {
     rotation: Vec<f64>,
     translation: [f64; 3],
     error: f64,
+    /// RMS reprojection error in pixels of the refined pose (with
+    /// --refine-pose).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reprojection_error: Option<f64>,
+    /// Pixel coordinates of the tag origin and its X, Y, Z axis tips
+    /// (half a tag size long).
+    axes: [[f64; 2]; 4],
 }
 
-fn load_image(path: &str) -> Result<ImageU8> {
-    let img = image::open(path)
-        .with_context(|| format!("failed to open image: {path}"))?
-        .into_luma8();
+fn load_image(path: &str, tone_map: ToneMap) -> Result<ImageU8> {
+    let img = image::open(path).with_context(|| format!("failed to open image: {path}"))?;
+    if img.color().bytes_per_pixel() > img.color().channel_count() {
+        let img = img.into_luma16();
+        let (width, height) = img.dimensions();
+        return Ok(ImageU16::from_pixels(width, height, img.into_raw()).to_u8(tone_map));
+    }
+    let img = img.into_luma8();
 
     let width = img.width();
     let height = img.height();
@@ -113,7 +276,43 @@ fn load_image(path: &str) -> Result<ImageU8> {
     Ok(ImageU8::from_pixels(width, height, pixels))
 }
 
-fn pose_from_result(pose: &Pose, error: f64) -> OutputPose {
+/// A built-in family by name, or a family from a `.toml` file and its `.bin`.
+fn load_family(name_or_path: &str) -> Result<TagFamily> {
+    if let Some(family) = family::builtin_family(name_or_path) {
+        return Ok(family);
+    }
+    let path = Path::new(name_or_path);
+    anyhow::ensure!(
+        path.extension().is_some_and(|ext| ext == "toml"),
+        "unknown tag family: {name_or_path}"
+    );
+    TagFamily::from_toml_file(path)
+        .with_context(|| format!("loading family from {}", path.display()))
+}
+
+/// Parse an ID list like "0-10" or "0,3,7-9".
+fn parse_ids(spec: &str) -> Result<Vec<u32>> {
+    let mut ids = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u32>()
+                .with_context(|| format!("invalid tag ID '{s}' in --ids"))
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                anyhow::ensure!(start <= end, "empty ID range '{part}' in --ids");
+                ids.extend(start..=end);
+            }
+            None => ids.push(parse(part)?),
+        }
+    }
+    Ok(ids)
+}
+
+fn pose_from_result(pose: &Pose, error: f64, params: &PoseParams) -> OutputPose {
     let rotation = vec![
         pose.r[0][0],
         pose.r[0][1],
@@ -129,11 +328,38 @@ fn pose_from_result(pose: &Pose, error: f64) -> OutputPose {
         rotation,
         translation: pose.t,
         error,
+        reprojection_error: None,
+        axes: pose.axes_endpoints(params.tagsize / 2.0, params),
     }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    if let Some(Command::SelfTest(test_args)) = &args.command {
+        return selftest::run(test_args);
+    }
+    let interlace_field = match args.field.as_deref() {
+        None => None,
+        Some("even") => Some(InterlaceField::Even),
+        Some("odd") => Some(InterlaceField::Odd),
+        Some(other) => anyhow::bail!("--field must be \"even\" or \"odd\", not {other:?}"),
+    };
+    let polarity = match args.polarity.as_deref() {
+        None | Some("normal") => Polarity::Normal,
+        Some("inverted") => Polarity::Inverted,
+        Some("either") => Polarity::Either,
+        Some(other) => {
+            anyhow::bail!(
+                "--polarity must be \"normal\", \"inverted\" or \"either\", not {other:?}"
+            )
+        }
+    };
+
+    let solver = match args.pose_solver.as_deref() {
+        None | Some("oi") => PoseSolver::OrthogonalIteration,
+        Some("ippe") => PoseSolver::Ippe,
+        Some(other) => anyhow::bail!("--pose-solver must be \"oi\" or \"ippe\", not {other:?}"),
+    };
 
     // Validate pose parameters
     let pose_params = if args.pose {
@@ -141,7 +367,7 @@ fn main() -> Result<()> {
             .tag_size
             .context("--tag-size is required when --pose is set")?;
         let fx = args.fx.context("--fx is required when --pose is set")?;
-        let fy = args.fy.context("--fy is required when --pose is set")?;
+        let fy = args.fy.unwrap_or(fx * args.pixel_aspect as f64);
         let cx = args.cx.context("--cx is required when --pose is set")?;
         let cy = args.cy.context("--cy is required when --pose is set")?;
         Some(PoseParams {
@@ -150,38 +376,162 @@ fn main() -> Result<()> {
             fy,
             cx,
             cy,
+            solver,
         })
     } else {
         None
     };
+    anyhow::ensure!(
+        !args.refine_pose || args.pose,
+        "--refine-pose requires --pose"
+    );
+    anyhow::ensure!(
+        args.pose_solver.is_none() || args.pose,
+        "--pose-solver requires --pose"
+    );
+    anyhow::ensure!(
+        args.average.is_none() || args.pose,
+        "--average requires --pose"
+    );
+    anyhow::ensure!(args.average != Some(0), "--average must be at least 1");
 
     // Build detector
     let config = DetectorConfig {
         quad_decimate: args.decimate,
+        decimate_mode: if args.decimate_average {
+            DecimateMode::Average
+        } else {
+            DecimateMode::Point
+        },
         quad_sigma: args.blur,
         refine_edges: !args.no_refine,
         decode_sharpening: args.sharpening,
-        qtp: QuadThreshParams::default(),
+        min_decision_margin: args.min_decision_margin,
+        decode_mirrored: args.mirrored,
+        quad: QuadParams {
+            min_white_black_diff: args.min_white_black_diff,
+            deglitch: args.deglitch,
+            tile_size: args.tile_size,
+            pixel_aspect_ratio: args.pixel_aspect,
+            ..QuadParams::default()
+        },
+        max_image_dimension: None,
+        max_clusters: args.max_clusters,
+        refine_homography: args.refine_homography,
+        refine_decode: args.refine_decode,
+        num_threads: args.threads,
+        band_rows: args.band_rows,
+        interlace_field,
+        pixel_convention: if args.pixel_centers {
+            PixelConvention::Center
+        } else {
+            PixelConvention::Corner
+        },
+        polarity,
     };
-    let mut detector = Detector::new(config);
+    let mut builder = DetectorBuilder::with_config(config);
 
     // Add families
-    for family_name in args.family.split(',') {
-        let family_name = family_name.trim();
-        let fam = family::builtin_family(family_name)
-            .with_context(|| format!("unknown tag family: {family_name}"))?;
-        detector.add_family(fam, args.max_hamming);
+    let ids = match &args.ids {
+        Some(spec) => IdFilter::Only(parse_ids(spec)?),
+        None => IdFilter::All,
+    };
+    for spec in args.family.split(',') {
+        let (family_name, max_hamming) = match spec.rsplit_once(':') {
+            // A colon in a path (e.g. C:\tags.toml) is not a Hamming distance
+            Some((name, h)) if !h.contains(['/', '\\']) => (
+                name.trim(),
+                h.trim()
+                    .parse::<u32>()
+                    .with_context(|| format!("invalid Hamming distance in --family '{spec}'"))?,
+            ),
+            _ => (spec.trim(), args.max_hamming),
+        };
+        let fam = load_family(family_name)?;
+        builder = builder.add_family_with_ids(fam, max_hamming, ids.clone());
     }
+    let mut detector = builder.try_build()?;
+
+    let debug_images = match &args.debug_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create {}", dir.display()))?;
+            let images = Arc::new(DebugImages::default());
+            detector.set_debug_output(Some(images.clone()));
+            Some((dir, images))
+        }
+        None => None,
+    };
+
+    let tone_map = args.bit_depth.map_or(ToneMap::default(), ToneMap::BitDepth);
+    let mask = args
+        .mask
+        .as_deref()
+        .map(|path| load_image(path, ToneMap::default()))
+        .transpose()?;
 
     // Process each image
-    for image_path in &args.images {
-        let img = load_image(image_path)?;
+    let mut summary = Summary::default();
+    let mut averager = PoseAverager::default();
+    for (i, image_path) in args.images.iter().enumerate() {
+        let img = load_image(image_path, tone_map)?;
 
         if !args.quiet {
             eprintln!("detecting in {} ({}x{})", image_path, img.width, img.height);
         }
 
-        let detections = detector.detect(&img, &mut DetectorBuffers::new());
+        if let Some(mask) = &mask {
+            anyhow::ensure!(
+                (mask.width, mask.height) == (img.width, img.height),
+                "--mask is {}x{} but {image_path} is {}x{}",
+                mask.width,
+                mask.height,
+                img.width,
+                img.height
+            );
+        }
+
+        let mut buffers = DetectorBuffers::new();
+        let start = Instant::now();
+        let (w, h) = args.max_dimension.map_or((img.width, img.height), |m| {
+            max_dimension_size(img.width, img.height, m)
+        });
+        let mut detect = |img: &ImageU8, mask: Option<&ImageU8>| match mask {
+            Some(mask) => detector.try_detect_masked(img, mask, &mut buffers),
+            None => detector.try_detect(img, &mut buffers),
+        };
+        let detections = if (w, h) == (img.width, img.height) {
+            detect(&img, mask.as_ref())
+                .with_context(|| format!("cannot process {image_path}; try --max-dimension"))?
+        } else {
+            if !args.quiet {
+                eprintln!("  resized to {w}x{h}");
+            }
+            let small = resize(&img, w, h, ResizeFilter::Area);
+            let small_mask = mask
+                .as_ref()
+                .map(|mask| resize(mask, w, h, ResizeFilter::Area));
+            let (sx, sy) = (img.width as f64 / w as f64, img.height as f64 / h as f64);
+            let mut dets = detect(&small, small_mask.as_ref())
+                .with_context(|| format!("cannot process {image_path}"))?;
+            for det in &mut dets {
+                det.scale(sx, sy, detector.config.pixel_convention);
+            }
+            dets
+        };
+        let elapsed = start.elapsed();
+        summary.add_image(image_path, &detections, elapsed);
+
+        if let Some((dir, images)) = &debug_images {
+            let stem = Path::new(image_path)
+                .file_stem()
+                .map_or("image".into(), |s| s.to_string_lossy());
+            for (stage, image) in images.take() {
+                let path = dir.join(format!("{stem}-{}.pnm", stage.name()));
+                std::fs::write(&path, image.to_pnm())
+                    .with_context(|| format!("cannot write {}", path.display()))?;
+            }
+        }
 
         let output_detections: Vec<OutputDetection> = detections
             .iter()
@@ -189,28 +539,61 @@ fn main() -> Result<()> {
                 let pose = pose_params.as_ref().map(|params| {
                     let (pose1, err1, pose2, err2) = estimate_tag_pose(det, params);
                     // Pick the better pose
-                    if let Some(p2) = pose2 {
-                        if err2 < err1 {
-                            return pose_from_result(&p2, err2);
-                        }
+                    let (pose, err) = match pose2 {
+                        Some(p2) if err2 < err1 => (p2, err2),
+                        _ => (pose1, err1),
+                    };
+                    let refined = args
+                        .refine_pose
+                        .then(|| detector.families().find(|f| f.config.name == det.family_id))
+                        .flatten()
+                        .and_then(|family| refine_tag_pose(det, family, params, &pose));
+                    match refined {
+                        Some((refined, rms)) => OutputPose {
+                            reprojection_error: Some(rms),
+                            ..pose_from_result(&refined, err, params)
+                        },
+                        None => pose_from_result(&pose, err, params),
                     }
-                    pose_from_result(&pose1, err1)
                 });
 
                 OutputDetection {
-                    family: det.family_id.to_string(),
-                    id: det.id,
-                    hamming: det.hamming,
-                    decision_margin: det.decision_margin,
-                    center: det.center.into(),
-                    corners: det.corners.map(Into::into),
+                    detection: det,
                     pose,
                 }
             })
             .collect();
 
         if !args.quiet {
-            eprintln!("  found {} tags", output_detections.len());
+            eprintln!(
+                "  found {} tags in {:.2} ms",
+                output_detections.len(),
+                elapsed.as_secs_f64() * 1000.0
+            );
+        }
+
+        if let Some(burst) = args.average {
+            averager.add_image(image_path);
+            for det in &output_detections {
+                if let Some(pose) = &det.pose {
+                    let d = det.detection;
+                    averager.add_pose(&d.family_id, d.id, &pose.rotation, pose.translation);
+                }
+            }
+            // A short last burst is averaged too
+            if averager.images() == burst || i + 1 == args.images.len() {
+                let average = averager.finish();
+                if !args.quiet {
+                    average.print();
+                }
+                let json = if args.pretty {
+                    serde_json::to_string_pretty(&average)?
+                } else {
+                    serde_json::to_string(&average)?
+                };
+                println!("{json}");
+            }
+            continue;
         }
 
         let result = OutputResult {
@@ -228,5 +611,42 @@ fn main() -> Result<()> {
         println!("{json}");
     }
 
+    if !args.quiet {
+        summary.print();
+    }
+    if let Some(path) = &args.summary_json {
+        let json = if args.pretty {
+            serde_json::to_string_pretty(&summary)?
+        } else {
+            serde_json::to_string(&summary)?
+        };
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("failed to write summary: {path}"))?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn field_conflicts_with_max_dimension() {
+        let err = Args::try_parse_from([
+            "apriltag-detect",
+            "--field",
+            "even",
+            "--max-dimension",
+            "640",
+            "frame.png",
+        ])
+        .err()
+        .map(|e| e.kind());
+        assert_eq!(err, Some(ErrorKind::ArgumentConflict));
+
+        assert!(Args::try_parse_from(["apriltag-detect", "--field", "odd", "frame.png"]).is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+//! `apriltag-detect self-test`: render tags, detect them and check the IDs.
+
+use anyhow::{bail, Context, Result};
+use apriltag::family::{self, TagFamily};
+use apriltag::types::Pixel;
+use apriltag::{Detection, Detector, DetectorBuffers, ImageU8};
+
+/// White quiet zone around each rendered tag, in cells.
+const MARGIN_CELLS: usize = 2;
+
+/// Options of the `self-test` subcommand.
+#[derive(clap::Args)]
+pub struct SelfTestArgs {
+    /// Tag families to test, comma-separated (default: every family built
+    /// into this binary)
+    #[arg(short, long)]
+    family: Option<String>,
+
+    /// Tag IDs to test in each family, comma-separated (default: the first,
+    /// middle and last ID)
+    #[arg(long, conflicts_with = "all_ids")]
+    ids: Option<String>,
+
+    /// Test every ID of each family
+    #[arg(long)]
+    all_ids: bool,
+
+    /// Rendered pixels per tag cell
+    #[arg(long, default_value = "8")]
+    cell_size: usize,
+}
+
+/// Render and detect each requested tag, printing one line per family.
+/// Fails if any tag is missed or decodes to anything but itself.
+pub fn run(args: &SelfTestArgs) -> Result<()> {
+    anyhow::ensure!(args.cell_size > 0, "--cell-size must be positive");
+    let names: Vec<&str> = match &args.family {
+        Some(list) => list.split(',').map(str::trim).collect(),
+        None => family::BUILTIN_NAMES.to_vec(),
+    };
+    let requested_ids = args
+        .ids
+        .as_deref()
+        .map(|list| {
+            list.split(',')
+                .map(|id| {
+                    id.trim()
+                        .parse::<usize>()
+                        .with_context(|| format!("invalid tag ID: {id}"))
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let (mut tested, mut failed) = (0, 0);
+    for name in names {
+        let fam =
+            family::builtin_family(name).with_context(|| format!("unknown tag family: {name}"))?;
+        let count = fam.codes.len();
+        let ids = match &requested_ids {
+            Some(ids) => {
+                if let Some(&id) = ids.iter().find(|&&id| id >= count) {
+                    bail!("{name} has no tag {id} (IDs 0-{})", count - 1);
+                }
+                ids.clone()
+            }
+            None if args.all_ids => (0..count).collect(),
+            None => {
+                let mut ids = vec![0, count / 2, count - 1];
+                ids.dedup();
+                ids
+            }
+        };
+
+        let detector = Detector::builder().add_family(fam.clone(), 2).build();
+        let mut buffers = DetectorBuffers::new();
+        let mut family_failed = 0;
+        for &id in &ids {
+            let img = render_tag(&fam, id, args.cell_size);
+            let detections = detector.detect(&img, &mut buffers);
+            if let Some(problem) = check(&detections, id) {
+                println!("  {name} {id}: {problem}");
+                family_failed += 1;
+            }
+        }
+        let status = if family_failed == 0 { "ok" } else { "FAILED" };
+        println!(
+            "{name}: {}/{} tags decoded {status}",
+            ids.len() - family_failed,
+            ids.len()
+        );
+        tested += ids.len();
+        failed += family_failed;
+    }
+
+    if failed > 0 {
+        bail!("{failed} of {tested} tags did not round-trip");
+    }
+    Ok(())
+}
+
+/// Draw tag `id` of `family` at `cell_size` pixels per cell, surrounded by
+/// a white quiet zone. Transparent cells are left white.
+fn render_tag(family: &TagFamily, id: usize, cell_size: usize) -> ImageU8 {
+    let tag = family.tag(id).render();
+    let cells = tag.grid_size + 2 * MARGIN_CELLS;
+    let side = cells * cell_size;
+    let mut pixels = vec![255u8; side * side];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (cx, cy) = (i % side / cell_size, i / side / cell_size);
+        let inside = MARGIN_CELLS..MARGIN_CELLS + tag.grid_size;
+        if inside.contains(&cx)
+            && inside.contains(&cy)
+            && tag.pixel(cx - MARGIN_CELLS, cy - MARGIN_CELLS) == Pixel::Black
+        {
+            *pixel = 0;
+        }
+    }
+    ImageU8::from_pixels(side as u32, side as u32, pixels)
+}
+
+/// Why `detections` lacks tag `id` with no bit errors. Other detections
+/// are ignored: small families such as tag16h5 can decode a patch of a
+/// tag's own data bits as another code.
+fn check(detections: &[Detection], id: usize) -> Option<String> {
+    if detections
+        .iter()
+        .any(|det| det.id as usize == id && det.hamming == 0)
+    {
+        return None;
+    }
+    match detections.first() {
+        Some(det) => Some(format!(
+            "decoded as {} with {} bit errors",
+            det.id, det.hamming
+        )),
+        None => Some("not detected".to_string()),
+    }
+}
@@ -0,0 +1,137 @@
+//! End-of-run statistics across all processed images.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use apriltag::Detection;
+use serde::Serialize;
+
+/// Width of one decision-margin histogram bin.
+const MARGIN_BIN_WIDTH: f32 = 25.0;
+
+/// Number of margin bins; the last one is open-ended.
+const MARGIN_BINS: usize = 10;
+
+/// Line width at which per-ID counts wrap.
+const LINE_WIDTH: usize = 80;
+
+/// Statistics for one processed image.
+#[derive(Serialize)]
+pub struct ImageSummary {
+    pub file: String,
+    pub detections: usize,
+    pub time_ms: f64,
+}
+
+/// One decision-margin histogram bin, covering `[min, max)`.
+#[derive(Serialize)]
+pub struct MarginBin {
+    pub min: f32,
+    /// `None` for the open-ended last bin.
+    pub max: Option<f32>,
+    pub count: usize,
+}
+
+/// Aggregate statistics for a run.
+#[derive(Serialize)]
+pub struct Summary {
+    pub total_images: usize,
+    pub total_detections: usize,
+    pub mean_time_ms: f64,
+    /// Detection counts per family, then per tag ID.
+    pub id_counts: BTreeMap<String, BTreeMap<i32, usize>>,
+    pub margin_histogram: Vec<MarginBin>,
+    pub images: Vec<ImageSummary>,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        let margin_histogram = (0..MARGIN_BINS)
+            .map(|i| MarginBin {
+                min: i as f32 * MARGIN_BIN_WIDTH,
+                max: (i + 1 < MARGIN_BINS).then_some((i + 1) as f32 * MARGIN_BIN_WIDTH),
+                count: 0,
+            })
+            .collect();
+        Self {
+            total_images: 0,
+            total_detections: 0,
+            mean_time_ms: 0.0,
+            id_counts: BTreeMap::new(),
+            margin_histogram,
+            images: Vec::new(),
+        }
+    }
+}
+
+impl Summary {
+    /// Record one image's detections and detection time.
+    pub fn add_image(&mut self, file: &str, detections: &[Detection], time: Duration) {
+        let time_ms = time.as_secs_f64() * 1000.0;
+        self.total_images += 1;
+        self.total_detections += detections.len();
+        self.mean_time_ms += (time_ms - self.mean_time_ms) / self.total_images as f64;
+
+        for det in detections {
+            *self
+                .id_counts
+                .entry(det.family_id.to_string())
+                .or_default()
+                .entry(det.id)
+                .or_default() += 1;
+            let bin = (det.decision_margin.max(0.0) / MARGIN_BIN_WIDTH) as usize;
+            self.margin_histogram[bin.min(MARGIN_BINS - 1)].count += 1;
+        }
+
+        self.images.push(ImageSummary {
+            file: file.to_string(),
+            detections: detections.len(),
+            time_ms,
+        });
+    }
+
+    /// Print a human-readable summary to stderr.
+    pub fn print(&self) {
+        eprintln!("summary:");
+        eprintln!("  images:      {}", self.total_images);
+        eprintln!("  detections:  {}", self.total_detections);
+        eprintln!("  mean time:   {:.2} ms/image", self.mean_time_ms);
+
+        for (family, ids) in &self.id_counts {
+            let total: usize = ids.values().sum();
+            eprintln!(
+                "  {family}: {} IDs, {total} detections (ID×count)",
+                ids.len()
+            );
+            let mut line = String::from("   ");
+            for (id, n) in ids {
+                let entry = format!(" {id}×{n}");
+                if line.chars().count() + entry.chars().count() > LINE_WIDTH {
+                    eprintln!("{line}");
+                    line = String::from("   ");
+                }
+                line.push_str(&entry);
+            }
+            eprintln!("{line}");
+        }
+
+        if self.total_detections > 0 {
+            eprintln!("  decision margin:");
+            let peak = self
+                .margin_histogram
+                .iter()
+                .map(|b| b.count)
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            for bin in &self.margin_histogram {
+                let range = match bin.max {
+                    Some(max) => format!("{:>3}-{:<3}", bin.min, max),
+                    None => format!("{:>3}+   ", bin.min),
+                };
+                let bar = "#".repeat((bin.count * 40).div_ceil(peak));
+                eprintln!("    {range} {:>6} {bar}", bin.count);
+            }
+        }
+    }
+}
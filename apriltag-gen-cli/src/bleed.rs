@@ -0,0 +1,90 @@
+//! Bleed and kerf compensation: black regions grown or shrunk by a fraction
+//! of a cell for vector output.
+//!
+//! Ink spreads beyond the printed edge and a laser burns a kerf along the
+//! cut, so a printed or cut tag's black cells come out larger or smaller
+//! than drawn. The detector measures the edges, so that difference shows up
+//! directly as corner error and pose bias; drawing the black regions offset
+//! the other way cancels it.
+
+use apriltag_gen::render::RenderedTag;
+use apriltag_gen::types::Pixel;
+
+/// Largest offset magnitude in cells: at half a cell, a lone black or white
+/// cell vanishes.
+pub const MAX_OFFSET: f64 = 0.5;
+
+/// The black regions of `tag` as polygons in cell units, with the origin at
+/// the outer corner of a `border`-cell white margin and y down, each black
+/// region offset by `offset` cells: outward (grown) if positive, inward
+/// (shrunk) if negative. Corners stay square. White, transparent and
+/// outside cells all count as background.
+///
+/// Each black cell becomes one polygon; together they cover the offset
+/// region exactly, overlapping where grown. `offset` must be less than
+/// [`MAX_OFFSET`] in magnitude.
+pub fn black_polygons(tag: &RenderedTag, border: usize, offset: f64) -> Vec<Vec<[f64; 2]>> {
+    let size = (tag.grid_size + 2 * border) as isize;
+    let black = |x: isize, y: isize| {
+        let inside =
+            |v: isize| (border as isize..border as isize + tag.grid_size as isize).contains(&v);
+        inside(x)
+            && inside(y)
+            && tag.pixel(x as usize - border, y as usize - border) == Pixel::Black
+    };
+
+    let mut polygons = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            if !black(x, y) {
+                continue;
+            }
+            let (x0, y0) = (x as f64, y as f64);
+            if offset >= 0.0 {
+                // The union of grown cells is the grown region
+                let (x1, y1) = (x0 + 1.0 + offset, y0 + 1.0 + offset);
+                let (x0, y0) = (x0 - offset, y0 - offset);
+                polygons.push(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]);
+                continue;
+            }
+
+            // Shrinking keeps the points whose surrounding square of half-width
+            // `e` is all black: pull in each side that borders background, and
+            // notch each corner whose diagonal neighbor alone is background.
+            let e = -offset;
+            let inset = |dx: isize, dy: isize| if black(x + dx, y + dy) { 0.0 } else { e };
+            let (left, right) = (inset(-1, 0), inset(1, 0));
+            let (top, bottom) = (inset(0, -1), inset(0, 1));
+            let notch = |dx: isize, dy: isize, a: f64, b: f64| {
+                a == 0.0 && b == 0.0 && !black(x + dx, y + dy)
+            };
+            let (xl, xr) = (x0 + left, x0 + 1.0 - right);
+            let (yt, yb) = (y0 + top, y0 + 1.0 - bottom);
+
+            // Clockwise (on screen) from the top-left corner
+            let mut polygon = Vec::with_capacity(12);
+            if notch(-1, -1, left, top) {
+                polygon.extend([[xl, yt + e], [xl + e, yt + e], [xl + e, yt]]);
+            } else {
+                polygon.push([xl, yt]);
+            }
+            if notch(1, -1, right, top) {
+                polygon.extend([[xr - e, yt], [xr - e, yt + e], [xr, yt + e]]);
+            } else {
+                polygon.push([xr, yt]);
+            }
+            if notch(1, 1, right, bottom) {
+                polygon.extend([[xr, yb - e], [xr - e, yb - e], [xr - e, yb]]);
+            } else {
+                polygon.push([xr, yb]);
+            }
+            if notch(-1, 1, left, bottom) {
+                polygon.extend([[xl + e, yb], [xl + e, yb - e], [xl, yb - e]]);
+            } else {
+                polygon.push([xl, yb]);
+            }
+            polygons.push(polygon);
+        }
+    }
+    polygons
+}
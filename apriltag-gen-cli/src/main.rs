@@ -3,8 +3,19 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 
+mod bleed;
 mod render_pdf;
 mod render_png;
+mod render_svg;
+
+/// Image pixels per tag cell below which decoding becomes unreliable; a rule
+/// of thumb for the minimum tag size in `compare-families`.
+const MIN_PIXELS_PER_CELL: f64 = 3.0;
+
+/// Printer dots per tag cell needed for crisp cell edges, at
+/// [`PRINT_DPI`]; a rule of thumb for the minimum printed tag size.
+const MIN_DOTS_PER_CELL: f64 = 4.0;
+const PRINT_DPI: f64 = 300.0;
 
 /// AprilTag generation and rendering CLI
 #[derive(Parser)]
@@ -23,6 +34,9 @@ enum Command {
         /// Family name (built-in) or path to .toml config
         #[arg(long)]
         family: String,
+        /// List every code with its visual complexity, most complex first
+        #[arg(long)]
+        complexity: bool,
     },
     /// Render individual tags as PNG or PDF
     Render {
@@ -41,6 +55,11 @@ enum Command {
         /// White border width in cells around the tag
         #[arg(long, default_value = "1")]
         border: usize,
+        /// Grow black regions by this many cells (negative shrinks them)
+        /// to compensate for ink bleed or laser kerf, e.g. -0.05 where ink
+        /// spreads by 5% of a cell; vector output only
+        #[arg(long, default_value = "0", allow_negative_numbers = true)]
+        black_offset: f64,
         /// Output directory
         #[arg(short, long, default_value = ".")]
         output: String,
@@ -62,10 +81,39 @@ enum Command {
         /// Number of columns in the grid
         #[arg(long, default_value = "10")]
         columns: usize,
+        /// Grow black regions by this many cells (negative shrinks them)
+        /// to compensate for ink bleed or laser kerf, e.g. -0.05 where ink
+        /// spreads by 5% of a cell; vector output only
+        #[arg(long, default_value = "0", allow_negative_numbers = true)]
+        black_offset: f64,
         /// Output file path
         #[arg(short, long, default_value = "mosaic.png")]
         output: String,
     },
+    /// Export tags as an SVG sprite sheet of reusable symbols, or their
+    /// path data as JSON, for web front-ends
+    Sprites {
+        /// Family name (built-in) or path to .toml config
+        #[arg(long)]
+        family: String,
+        /// Tag IDs to export (e.g. "0-9", "0,3,5"; default: all)
+        #[arg(long)]
+        ids: Option<String>,
+        /// Output format: 'svg' or 'json'
+        #[arg(long, default_value = "svg")]
+        format: String,
+        /// White border width in cells around each tag
+        #[arg(long, default_value = "1")]
+        border: usize,
+        /// Grow black regions by this many cells (negative shrinks them)
+        /// to compensate for ink bleed or laser kerf, e.g. -0.05 where ink
+        /// spreads by 5% of a cell; vector output only
+        #[arg(long, default_value = "0", allow_negative_numbers = true)]
+        black_offset: f64,
+        /// Output file path (default: <family>-sprites.<format>)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
     /// Generate codes for a tag family config
     Generate {
         /// Family name (built-in) or path to .toml config
@@ -78,6 +126,35 @@ enum Command {
         #[arg(long)]
         family: String,
     },
+    /// Report false-positive and ID-confusion robustness per Hamming limit
+    Stats {
+        /// Family name (built-in) or path to .toml config (default: all built-in families)
+        #[arg(long)]
+        family: Option<String>,
+        /// Largest decoder Hamming limit to report
+        #[arg(long, default_value = "3")]
+        max_hamming: u32,
+    },
+    /// Compare families side by side to help choose one
+    CompareFamilies {
+        /// Family names (built-in) or paths to .toml configs (default: all built-in families)
+        families: Vec<String>,
+        /// Decoder Hamming limit for the false-positive and confusion columns
+        #[arg(long, default_value = "2")]
+        max_hamming: u32,
+    },
+    /// Pick a subset of IDs for a deployment that needs only a few tags
+    Select {
+        /// Family name (built-in) or path to .toml config
+        #[arg(long)]
+        family: String,
+        /// Number of tags to select
+        #[arg(long)]
+        count: usize,
+        /// Selection criterion: 'max-min-distance' or 'complexity'
+        #[arg(long, default_value = "max-min-distance")]
+        criterion: String,
+    },
 }
 
 fn main() -> Result<()> {
@@ -85,25 +162,63 @@ fn main() -> Result<()> {
 
     match cli.command {
         Command::List => cmd_list(),
-        Command::Info { family } => cmd_info(&family),
+        Command::Info { family, complexity } => cmd_info(&family, complexity),
         Command::Render {
             family,
             ids,
             format,
             scale,
             border,
+            black_offset,
             output,
-        } => cmd_render(&family, &ids, &format, scale, border, &output),
+        } => cmd_render(&family, &ids, &format, scale, border, black_offset, &output),
         Command::Mosaic {
             family,
             format,
             scale,
             spacing,
             columns,
+            black_offset,
             output,
-        } => cmd_mosaic(&family, &format, scale, spacing, columns, &output),
+        } => cmd_mosaic(
+            &family,
+            &format,
+            scale,
+            spacing,
+            columns,
+            black_offset,
+            &output,
+        ),
+        Command::Sprites {
+            family,
+            ids,
+            format,
+            border,
+            black_offset,
+            output,
+        } => cmd_sprites(
+            &family,
+            ids.as_deref(),
+            &format,
+            border,
+            black_offset,
+            output.as_deref(),
+        ),
         Command::Generate { family } => cmd_generate(&family),
         Command::Verify { family } => cmd_verify(&family),
+        Command::Stats {
+            family,
+            max_hamming,
+        } => cmd_stats(family.as_deref(), max_hamming),
+        Command::CompareFamilies {
+            families,
+            max_hamming,
+        } => cmd_compare_families(&families, max_hamming),
+        Command::Select {
+            family,
+            count,
+            criterion,
+        } => cmd_select(&family, count, &criterion),
     }
 }
 
@@ -116,16 +231,8 @@ fn load_family(name_or_path: &str) -> Result<apriltag_gen::family::TagFamily> {
     // Try loading as a TOML file path
     let toml_path = std::path::Path::new(name_or_path);
     if toml_path.exists() {
-        let toml_str = std::fs::read_to_string(toml_path)
-            .with_context(|| format!("reading {}", toml_path.display()))?;
-        let bin_path = toml_path.with_extension("bin");
-        let bin_data = if bin_path.exists() {
-            std::fs::read(&bin_path).with_context(|| format!("reading {}", bin_path.display()))?
-        } else {
-            Vec::new()
-        };
-        let family = apriltag_gen::family::TagFamily::from_toml_and_bin(&toml_str, &bin_data)
-            .with_context(|| format!("parsing family from {}", toml_path.display()))?;
+        let family = apriltag_gen::family::TagFamily::from_toml_file(toml_path)
+            .with_context(|| format!("loading family from {}", toml_path.display()))?;
         return Ok(family);
     }
 
@@ -183,7 +290,7 @@ fn cmd_list() -> Result<()> {
     Ok(())
 }
 
-fn cmd_info(name: &str) -> Result<()> {
+fn cmd_info(name: &str, list_complexity: bool) -> Result<()> {
     let family = load_family(name)?;
     println!("Family:        {}", family.config.name);
     println!("Data bits:     {}", family.layout.nbits);
@@ -198,6 +305,10 @@ fn cmd_info(name: &str) -> Result<()> {
     println!("Border width:  {}", family.layout.border_width);
     println!("Reversed:      {}", family.layout.reversed_border);
     println!("Code count:    {}", family.codes.len());
+    let scores = complexities(&family);
+    if let Some(summary) = complexity_summary(&scores) {
+        println!("Complexity:    {summary}");
+    }
     println!();
 
     // Show layout visualization
@@ -212,17 +323,203 @@ fn cmd_info(name: &str) -> Result<()> {
         }
         println!();
     }
+
+    if list_complexity {
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().enumerate().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        println!();
+        println!("  {:>5}  {:>14}  {:>10}", "ID", "Code", "Complexity");
+        for (id, score) in ranked {
+            println!("  {:>5}  {:>#14x}  {:>10.4}", id, family.codes[id], score);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_stats(name: Option<&str>, max_hamming: u32) -> Result<()> {
+    let families = match name {
+        Some(name) => vec![load_family(name)?],
+        None => apriltag_gen::family::BUILTIN_NAMES
+            .iter()
+            .map(|name| load_family(name))
+            .collect::<Result<_>>()?,
+    };
+
+    for (i, family) in families.iter().enumerate() {
+        let r = apriltag_gen::robustness::analyze(family, 0..=max_hamming);
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "{} ({} bits, {} rotated codes, min distance {})",
+            family.config.name, r.nbits, r.rotated_codes, r.min_distance
+        );
+        if let Some(summary) = complexity_summary(&complexities(family)) {
+            println!("  Complexity: {summary}");
+        }
+        println!(
+            "  {:>7} {:>14} {:>16} {:>10}",
+            "Hamming", "P(random)", "1 in", "Confusion"
+        );
+        for level in &r.levels {
+            let one_in = if level.false_positive_rate > 0.0 {
+                format!("{:.0}", 1.0 / level.false_positive_rate)
+            } else {
+                "-".to_string()
+            };
+            println!(
+                "  {:>7} {:>13.3e}{} {:>16} {:>10}",
+                level.max_hamming,
+                level.false_positive_rate,
+                if level.exact { ' ' } else { '*' },
+                one_in,
+                level.min_confusion_errors,
+            );
+        }
+    }
+
+    println!();
+    println!("P(random):  probability that a random bit pattern decodes as a tag");
+    println!("Confusion:  fewest bit errors that turn one tag into another ID or rotation");
+    println!("*           union upper bound (Hamming balls overlap)");
+    println!("Complexity: normalized Ising energy per code; see `info --complexity`");
     Ok(())
 }
 
+fn cmd_compare_families(names: &[String], max_hamming: u32) -> Result<()> {
+    let families: Vec<_> = if names.is_empty() {
+        apriltag_gen::family::BUILTIN_NAMES
+            .iter()
+            .map(|name| load_family(name))
+            .collect::<Result<_>>()?
+    } else {
+        names
+            .iter()
+            .map(|name| load_family(name))
+            .collect::<Result<_>>()?
+    };
+
+    println!(
+        "{:<22} {:>4} {:>7} {:>6} {:>5} {:>7} {:>6} {:>8} {:>11} {:>9}",
+        "Family",
+        "Bits",
+        "Hamming",
+        "Codes",
+        "Grid",
+        "Density",
+        "Min px",
+        "Print mm",
+        "P(random)",
+        "Confusion"
+    );
+    println!("{}", "-".repeat(94));
+    for family in &families {
+        let r = apriltag_gen::robustness::analyze(family, [max_hamming]);
+        let level = r.levels[0];
+        let grid = family.layout.grid_size;
+        let density = family.layout.nbits as f64 / (grid * grid) as f64;
+        let min_px = grid as f64 * MIN_PIXELS_PER_CELL;
+        let print_mm = grid as f64 * MIN_DOTS_PER_CELL / PRINT_DPI * 25.4;
+        println!(
+            "{:<22} {:>4} {:>7} {:>6} {:>5} {:>7.2} {:>6.0} {:>8.1} {:>10.2e}{} {:>9}",
+            family.config.name,
+            family.layout.nbits,
+            family.config.min_hamming,
+            family.codes.len(),
+            format!("{grid}x{grid}"),
+            density,
+            min_px,
+            print_mm,
+            level.false_positive_rate,
+            if level.exact { ' ' } else { '*' },
+            level.min_confusion_errors,
+        );
+    }
+
+    println!();
+    println!("Grid:       cells across, including the white border");
+    println!("Density:    data bits per grid cell");
+    println!(
+        "Min px:     tag width in image pixels at {MIN_PIXELS_PER_CELL} px per cell (rule of thumb)"
+    );
+    println!(
+        "Print mm:   printed tag width at {MIN_DOTS_PER_CELL} dots per cell at {PRINT_DPI} DPI"
+    );
+    println!(
+        "P(random):  probability that a random bit pattern decodes as a tag, at Hamming {max_hamming}"
+    );
+    println!("Confusion:  fewest bit errors that turn one tag into another ID or rotation");
+    println!("*           union upper bound (Hamming balls overlap)");
+    Ok(())
+}
+
+fn cmd_select(name: &str, count: usize, criterion: &str) -> Result<()> {
+    use apriltag_gen::select::{select, Criterion};
+
+    let family = load_family(name)?;
+    let Some(criterion) = Criterion::from_name(criterion) else {
+        anyhow::bail!(
+            "unknown criterion '{}', use 'max-min-distance' or 'complexity'",
+            criterion
+        );
+    };
+    let picked = select(&family, count, criterion);
+
+    println!(
+        "  {:>5}  {:>14}  {:>8}  {:>10}",
+        "ID", "Code", "Distance", "Complexity"
+    );
+    for s in &picked {
+        let distance = s.distance.map_or("-".to_string(), |d| d.to_string());
+        println!(
+            "  {:>5}  {:>#14x}  {:>8}  {:>10.4}",
+            s.id, family.codes[s.id], distance, s.complexity
+        );
+    }
+
+    let mut ids: Vec<usize> = picked.iter().map(|s| s.id).collect();
+    ids.sort_unstable();
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    println!();
+    if let Some(min) = picked.iter().filter_map(|s| s.distance).min() {
+        println!(
+            "Minimum distance: {} (family guarantees {})",
+            min, family.config.min_hamming
+        );
+    }
+    println!("IDs: {}", ids.join(","));
+    Ok(())
+}
+
+/// Visual complexity of each code, indexed by tag ID.
+fn complexities(family: &apriltag_gen::family::TagFamily) -> Vec<f64> {
+    family
+        .codes
+        .iter()
+        .map(|&code| apriltag_gen::codegen::complexity(&family.layout, code))
+        .collect()
+}
+
+fn complexity_summary(scores: &[f64]) -> Option<String> {
+    if scores.is_empty() {
+        return None;
+    }
+    let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+    Some(format!("min {min:.4}, mean {mean:.4}, max {max:.4}"))
+}
+
 fn cmd_render(
     name: &str,
     id_spec: &str,
     format: &str,
     scale: usize,
     border: usize,
+    black_offset: f64,
     output_dir: &str,
 ) -> Result<()> {
+    check_black_offset(black_offset, format)?;
     let family = load_family(name)?;
     let ids = parse_ids(id_spec, family.codes.len())?;
 
@@ -240,7 +537,7 @@ fn cmd_render(
                 println!("wrote {}", path.display());
             }
             "pdf" => {
-                render_pdf::write_tag_pdf(&tag, border, &path.to_string_lossy())?;
+                render_pdf::write_tag_pdf(&tag, border, black_offset, &path.to_string_lossy())?;
                 println!("wrote {}", path.display());
             }
             _ => anyhow::bail!("unknown format '{}', use 'png' or 'pdf'", format),
@@ -255,8 +552,10 @@ fn cmd_mosaic(
     scale: usize,
     spacing: usize,
     columns: usize,
+    black_offset: f64,
     output_path: &str,
 ) -> Result<()> {
+    check_black_offset(black_offset, format)?;
     let family = load_family(name)?;
 
     match format {
@@ -265,7 +564,7 @@ fn cmd_mosaic(
             println!("wrote {}", output_path);
         }
         "pdf" => {
-            render_pdf::write_mosaic_pdf(&family, spacing, columns, output_path)?;
+            render_pdf::write_mosaic_pdf(&family, spacing, columns, black_offset, output_path)?;
             println!("wrote {}", output_path);
         }
         _ => anyhow::bail!("unknown format '{}', use 'png' or 'pdf'", format),
@@ -273,8 +572,52 @@ fn cmd_mosaic(
     Ok(())
 }
 
-fn cmd_generate(name: &str) -> Result<()> {
+fn cmd_sprites(
+    name: &str,
+    id_spec: Option<&str>,
+    format: &str,
+    border: usize,
+    black_offset: f64,
+    output_path: Option<&str>,
+) -> Result<()> {
+    check_black_offset(black_offset, format)?;
     let family = load_family(name)?;
+    let ids = match id_spec {
+        Some(spec) => parse_ids(spec, family.codes.len())?,
+        None => (0..family.codes.len()).collect(),
+    };
+
+    let contents = match format {
+        "svg" => render_svg::sprite_svg(&family, &ids, border, black_offset),
+        "json" => render_svg::sprite_json(&family, &ids, border, black_offset),
+        _ => anyhow::bail!("unknown format '{}', use 'svg' or 'json'", format),
+    };
+    let output_path = output_path.map_or_else(
+        || format!("{}-sprites.{}", family.config.name, format),
+        str::to_string,
+    );
+    std::fs::write(&output_path, contents).with_context(|| format!("writing {output_path}"))?;
+    println!("wrote {} tags to {}", ids.len(), output_path);
+    Ok(())
+}
+
+/// Check a `--black-offset` against `format`: PNG cells are whole pixels, so
+/// only vector output can be offset.
+fn check_black_offset(offset: f64, format: &str) -> Result<()> {
+    anyhow::ensure!(
+        offset.abs() < bleed::MAX_OFFSET,
+        "--black-offset must be between -{max} and {max} cells, not {offset}",
+        max = bleed::MAX_OFFSET
+    );
+    anyhow::ensure!(
+        offset == 0.0 || format != "png",
+        "--black-offset needs vector output; use --format pdf"
+    );
+    Ok(())
+}
+
+fn cmd_generate(name: &str) -> Result<()> {
+    let mut family = load_family(name)?;
 
     let codes = if matches!(
         family.config.layout,
@@ -287,14 +630,11 @@ fn cmd_generate(name: &str) -> Result<()> {
 
     println!("Generated {} codes.", codes.len());
 
-    // Write .bin file
+    // Write .bin file, with a header the TOML config is checked against
     let bin_path = format!("{}.bin", family.config.name);
-    let mut bin_data = Vec::with_capacity(codes.len() * 8);
-    for &code in &codes {
-        bin_data.extend_from_slice(&code.to_le_bytes());
-    }
-    std::fs::write(&bin_path, &bin_data).with_context(|| format!("writing {}", bin_path))?;
-    println!("Wrote {} codes to {}", codes.len(), bin_path);
+    family.codes = codes;
+    std::fs::write(&bin_path, family.to_bin()).with_context(|| format!("writing {}", bin_path))?;
+    println!("Wrote {} codes to {}", family.codes.len(), bin_path);
 
     Ok(())
 }
@@ -336,45 +676,63 @@ fn cmd_verify(name: &str) -> Result<()> {
         )
     })?;
 
-    let codes = if matches!(
+    let mismatch = if matches!(
         family.config.layout,
         apriltag_gen::family::LayoutConfig::Classic { .. }
     ) {
-        generate_classic(&family)?
+        let codes = generate_classic(&family)?;
+        apriltag_gen::codegen::Mismatch::first(&family.codes, &codes)
     } else {
-        generate_era2(&family)?
+        // Compare as codes are found, so a mismatch fails without waiting
+        // for the rest of the search
+        let min_complexity = era2_min_complexity(&family)?;
+        let result = apriltag_gen::codegen::verify_with_progress(
+            &family.layout,
+            family.config.min_hamming,
+            min_complexity,
+            &family.codes,
+            search_progress(),
+        );
+        eprintln!();
+        result.err()
     };
 
-    if codes == family.codes {
-        println!(
-            "PASS: {} — regenerated {} codes match built-in data",
-            family.config.name,
-            codes.len()
-        );
-        Ok(())
-    } else {
-        // Find first mismatch for diagnostics
-        let mut msg = format!(
-            "FAIL: {} — regenerated codes differ from built-in data\n  expected {} codes, got {}",
+    match mismatch {
+        None => {
+            println!(
+                "PASS: {} — regenerated {} codes match built-in data",
+                family.config.name,
+                family.codes.len()
+            );
+            Ok(())
+        }
+        Some(mismatch) => anyhow::bail!(
+            "FAIL: {} — regenerated codes differ from built-in data\n  \
+             {} of {} expected codes matched; {}",
             family.config.name,
+            mismatch.index,
             family.codes.len(),
-            codes.len()
-        );
-        for (i, (expected, got)) in family.codes.iter().zip(codes.iter()).enumerate() {
-            if expected != got {
-                msg.push_str(&format!(
-                    "\n  first mismatch at index {}: expected {:#x}, got {:#x}",
-                    i, expected, got
-                ));
-                break;
-            }
-        }
-        anyhow::bail!(msg);
+            mismatch
+        ),
     }
 }
 
 /// Generate codes for an Era 2 family using the lexicode algorithm.
 fn generate_era2(family: &apriltag_gen::family::TagFamily) -> Result<Vec<u64>> {
+    let min_complexity = era2_min_complexity(family)?;
+    let codes = apriltag_gen::codegen::generate_with_progress(
+        &family.layout,
+        family.config.min_hamming,
+        min_complexity,
+        search_progress(),
+    );
+    eprintln!();
+
+    Ok(codes)
+}
+
+/// The minimum complexity of an Era 2 family, announcing its search.
+fn era2_min_complexity(family: &apriltag_gen::family::TagFamily) -> Result<u32> {
     let min_complexity = family
         .config
         .min_complexity
@@ -384,33 +742,28 @@ fn generate_era2(family: &apriltag_gen::family::TagFamily) -> Result<Vec<u64>> {
         "Generating codes for {} (nbits={}, min_hamming={}, min_complexity={})...",
         family.config.name, family.layout.nbits, family.config.min_hamming, min_complexity
     );
+    Ok(min_complexity)
+}
 
-    let codes = apriltag_gen::codegen::generate_with_progress(
-        &family.layout,
-        family.config.min_hamming,
-        min_complexity,
-        {
-            let mut last_print = std::time::Instant::now();
-            let mut decimals = None;
-            move |iter, total, codes_found| {
-                let d = *decimals
-                    .get_or_insert_with(|| ((total as f64).log10() - 8.0).ceil().max(1.0) as usize);
-                let now = std::time::Instant::now();
-                if iter == 0 || now.duration_since(last_print).as_millis() >= 100 {
-                    let pct = iter as f64 / total as f64 * 100.0;
-                    eprint!(
-                        "\r  {:>width$.prec$}% searched, {} codes found",
-                        pct,
-                        codes_found,
-                        width = d + 4,
-                        prec = d
-                    );
-                    last_print = now;
-                }
-            }
-        },
-    );
-    eprintln!();
-
-    Ok(codes)
+/// Progress callback for the lexicode search, redrawing one stderr line at
+/// most every 100 ms.
+fn search_progress() -> impl FnMut(u64, u64, usize) {
+    let mut last_print = std::time::Instant::now();
+    let mut decimals = None;
+    move |iter, total, codes_found| {
+        let d = *decimals
+            .get_or_insert_with(|| ((total as f64).log10() - 8.0).ceil().max(1.0) as usize);
+        let now = std::time::Instant::now();
+        if iter == 0 || now.duration_since(last_print).as_millis() >= 100 {
+            let pct = iter as f64 / total as f64 * 100.0;
+            eprint!(
+                "\r  {:>width$.prec$}% searched, {} codes found",
+                pct,
+                codes_found,
+                width = d + 4,
+                prec = d
+            );
+            last_print = now;
+        }
+    }
 }
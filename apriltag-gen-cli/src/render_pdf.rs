@@ -4,13 +4,22 @@ use anyhow::{Context, Result};
 use apriltag_gen::family::TagFamily;
 use apriltag_gen::render::RenderedTag;
 use apriltag_gen::types::Pixel;
+use printpdf::path::{PaintMode, WindingOrder};
 use printpdf::*;
 
+use crate::bleed;
+
 /// Default tag cell size in mm
 const CELL_SIZE_MM: f32 = 2.0;
 
-/// Write a single tag as a PDF file.
-pub fn write_tag_pdf(tag: &RenderedTag, border: usize, path: &str) -> Result<()> {
+/// Write a single tag as a PDF file, with black regions offset by
+/// `black_offset` cells.
+pub fn write_tag_pdf(
+    tag: &RenderedTag,
+    border: usize,
+    black_offset: f64,
+    path: &str,
+) -> Result<()> {
     let total_cells = tag.grid_size + 2 * border;
     let page_size_mm = total_cells as f32 * CELL_SIZE_MM + 20.0; // 10mm margin each side
 
@@ -19,7 +28,15 @@ pub fn write_tag_pdf(tag: &RenderedTag, border: usize, path: &str) -> Result<()>
     let layer = doc.get_page(page1).get_layer(layer1);
 
     let margin_mm = (page_size_mm - total_cells as f32 * CELL_SIZE_MM) / 2.0;
-    draw_tag(&layer, tag, border, margin_mm, margin_mm, CELL_SIZE_MM);
+    draw_tag(
+        &layer,
+        tag,
+        border,
+        margin_mm,
+        margin_mm,
+        CELL_SIZE_MM,
+        black_offset,
+    );
 
     doc.save(&mut std::io::BufWriter::new(
         std::fs::File::create(path).with_context(|| format!("creating {path}"))?,
@@ -29,11 +46,13 @@ pub fn write_tag_pdf(tag: &RenderedTag, border: usize, path: &str) -> Result<()>
     Ok(())
 }
 
-/// Write a mosaic of all tags in a family as a PDF (A4 pages).
+/// Write a mosaic of all tags in a family as a PDF (A4 pages), with black
+/// regions offset by `black_offset` cells.
 pub fn write_mosaic_pdf(
     family: &TagFamily,
     spacing: usize,
     columns: usize,
+    black_offset: f64,
     path: &str,
 ) -> Result<()> {
     let ncodes = family.codes.len();
@@ -92,7 +111,7 @@ pub fn write_mosaic_pdf(
                     - (local_row + 1) as f32 * tag_mm
                     - local_row as f32 * spacing_mm;
 
-                draw_tag(&layer, &tag, 1, x_mm, y_mm, cell_mm);
+                draw_tag(&layer, &tag, 1, x_mm, y_mm, cell_mm, black_offset);
             }
         }
     }
@@ -113,6 +132,7 @@ fn draw_tag(
     x_mm: f32,
     y_mm: f32,
     cell_mm: f32,
+    black_offset: f64,
 ) {
     let size = tag.grid_size;
 
@@ -128,6 +148,31 @@ fn draw_tag(
     );
     layer.add_rect(rect);
 
+    if black_offset != 0.0 {
+        // White cells are the background; draw the offset black regions
+        layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+        let top = y_mm + total as f32 * cell_mm;
+        let rings = bleed::black_polygons(tag, border, black_offset)
+            .into_iter()
+            .map(|polygon| {
+                polygon
+                    .into_iter()
+                    .map(|[x, y]| {
+                        let point =
+                            Point::new(Mm(x_mm + x as f32 * cell_mm), Mm(top - y as f32 * cell_mm));
+                        (point, false)
+                    })
+                    .collect()
+            })
+            .collect();
+        layer.add_polygon(Polygon {
+            rings,
+            mode: PaintMode::Fill,
+            winding_order: WindingOrder::NonZero,
+        });
+        return;
+    }
+
     // Draw each cell
     for cy in 0..size {
         for cx in 0..size {
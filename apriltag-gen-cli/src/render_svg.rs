@@ -0,0 +1,132 @@
+//! SVG sprite sheets: tags as reusable symbols for web front-ends.
+
+use std::fmt::Write;
+
+use apriltag_gen::family::TagFamily;
+use apriltag_gen::render::RenderedTag;
+use apriltag_gen::types::Pixel;
+
+use crate::bleed;
+
+/// Path data of a tag's white and black cells, in cell units with the origin
+/// at the outer corner of a `border`-cell white margin. Transparent cells
+/// are in neither path. With a black offset, the white path covers all
+/// opaque cells and the black path, drawn over it, the offset black regions.
+pub struct TagPaths {
+    pub white: String,
+    pub black: String,
+}
+
+/// Trace `tag` as one path per color, each horizontal run of cells a
+/// rectangle, with black regions offset by `black_offset` cells (see
+/// [`bleed::black_polygons`]).
+pub fn tag_paths(tag: &RenderedTag, border: usize, black_offset: f64) -> TagPaths {
+    let size = tag.grid_size + 2 * border;
+    let pixel = |x: usize, y: usize| {
+        let inside = |v: usize| (border..border + tag.grid_size).contains(&v);
+        if inside(x) && inside(y) {
+            tag.pixel(x - border, y - border)
+        } else {
+            Pixel::White
+        }
+    };
+
+    let mut paths = TagPaths {
+        white: String::new(),
+        black: String::new(),
+    };
+    for y in 0..size {
+        let mut x = 0;
+        while x < size {
+            let p = pixel(x, y);
+            let start = x;
+            while x < size && pixel(x, y) == p {
+                x += 1;
+            }
+            let out = match p {
+                Pixel::Transparent => continue,
+                _ if black_offset != 0.0 => &mut paths.white,
+                Pixel::White => &mut paths.white,
+                Pixel::Black => &mut paths.black,
+            };
+            let len = x - start;
+            let _ = write!(out, "M{start} {y}h{len}v1h-{len}z");
+        }
+    }
+    if black_offset != 0.0 {
+        for polygon in bleed::black_polygons(tag, border, black_offset) {
+            for (i, [x, y]) in polygon.iter().enumerate() {
+                let op = if i == 0 { 'M' } else { 'L' };
+                let _ = write!(paths.black, "{op}{} {}", number(*x), number(*y));
+            }
+            paths.black.push('z');
+        }
+    }
+    paths
+}
+
+/// `v` with at most four decimals and no trailing zeros.
+fn number(v: f64) -> String {
+    let s = format!("{v:.4}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// The symbol id of tag `id` in a sprite sheet.
+fn symbol_id(family: &TagFamily, id: usize) -> String {
+    format!("{}-{}", family.config.name, id)
+}
+
+/// An SVG document defining each tag in `ids` as a `<symbol>` with id
+/// `<family>-<id>` and a viewBox of one unit per cell, for
+/// `<use href="sheet.svg#tag36h11-0">` at any size.
+pub fn sprite_svg(family: &TagFamily, ids: &[usize], border: usize, black_offset: f64) -> String {
+    let size = family.layout.grid_size + 2 * border;
+    let mut out = String::from("<svg xmlns=\"http://www.w3.org/2000/svg\">\n");
+    for &id in ids {
+        let paths = tag_paths(&family.tag(id).render(), border, black_offset);
+        let _ = writeln!(
+            out,
+            "  <symbol id=\"{}\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\">",
+            symbol_id(family, id)
+        );
+        for (fill, d) in [("#fff", &paths.white), ("#000", &paths.black)] {
+            if !d.is_empty() {
+                let _ = writeln!(out, "    <path fill=\"{fill}\" d=\"{d}\"/>");
+            }
+        }
+        out.push_str("  </symbol>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// The same paths as [`sprite_svg`] as JSON, for drawing on a canvas (e.g.
+/// with `Path2D`): the family, the side of each tag in cells, and per tag
+/// its id and white and black path data, the black drawn over the white.
+pub fn sprite_json(family: &TagFamily, ids: &[usize], border: usize, black_offset: f64) -> String {
+    let tags: Vec<_> = ids
+        .iter()
+        .map(|&id| {
+            let paths = tag_paths(&family.tag(id).render(), border, black_offset);
+            serde_json::json!({
+                "id": id,
+                "symbol": symbol_id(family, id),
+                "white": paths.white,
+                "black": paths.black,
+            })
+        })
+        .collect();
+    let sheet = serde_json::json!({
+        "family": family.config.name.to_string(),
+        "size": family.layout.grid_size + 2 * border,
+        "border": border,
+        "black_offset": black_offset,
+        "tags": tags,
+    });
+    format!("{sheet:#}\n")
+}
@@ -6,12 +6,17 @@
 //! This implements Era 2 code generation (AprilTag 3) for Standard, Circle,
 //! and Custom tag families. Classic families use `upgrade.rs` instead.
 
+use std::fmt;
+use std::ops::ControlFlow;
+
 use apriltag::bits;
 #[cfg(test)]
 use apriltag::hamming::hamming_distance;
 use apriltag::hamming::{hamming_distance_at_least, rotate90};
 use apriltag::layout::Layout;
 use apriltag::types::CellType;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use smallvec::SmallVec;
 
 /// Flat code set: sequential scan with XOR + popcount.
@@ -126,10 +131,8 @@ impl ComplexityGrid {
 
                 for b in neighbors.into_iter().flatten() {
                     match (a, b) {
-                        (CellKind::Fixed(va), CellKind::Fixed(vb)) => {
-                            if va != vb {
-                                base_energy += 1;
-                            }
+                        (CellKind::Fixed(va), CellKind::Fixed(vb)) if va != vb => {
+                            base_energy += 1;
                         }
                         (CellKind::Fixed(v), CellKind::Data(i))
                         | (CellKind::Data(i), CellKind::Fixed(v)) => {
@@ -174,6 +177,16 @@ impl ComplexityGrid {
             threshold: 2 * area,
         }
     }
+
+    /// Whether the Ising energy of a code equals that of its 90° rotation.
+    ///
+    /// The energy is a quadratic function of the bits and rotation permutes
+    /// them, so it suffices to compare all codes with at most two bits set.
+    fn is_rotation_invariant(&self, nbits: u32) -> bool {
+        let invariant =
+            |code: u64| ising_energy(self, code) == ising_energy(self, rotate90(code, nbits));
+        invariant(0) && (0..nbits).all(|i| (i..nbits).all(|j| invariant((1u64 << i) | (1u64 << j))))
+    }
 }
 
 /// Generate tag family codes using the greedy lexicode search.
@@ -192,7 +205,169 @@ pub fn generate_with_progress(
     layout: &Layout,
     min_hamming: u32,
     min_complexity: u32,
+    on_progress: impl FnMut(u64, u64, usize),
+) -> Vec<u64> {
+    let total = 1u64 << layout.nbits;
+    let prune_orbits =
+        ComplexityGrid::from_layout(layout).is_rotation_invariant(layout.nbits as u32);
+    search_with(
+        layout,
+        min_hamming,
+        min_complexity,
+        total,
+        prune_orbits,
+        on_progress,
+        |_, _| ControlFlow::Continue(()),
+    )
+}
+
+/// Where regenerated codes first differ from the expected ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Index of the first differing code.
+    pub index: usize,
+    /// The expected code at `index`, or `None` if the search found more
+    /// codes than expected.
+    pub expected: Option<u64>,
+    /// The regenerated code at `index`, or `None` if the search ended with
+    /// fewer codes than expected.
+    pub found: Option<u64>,
+}
+
+impl Mismatch {
+    /// The first difference between `expected` and `found`, if any.
+    pub fn first(expected: &[u64], found: &[u64]) -> Option<Self> {
+        let index = expected
+            .iter()
+            .zip(found)
+            .position(|(e, f)| e != f)
+            .unwrap_or(expected.len().min(found.len()));
+        (index < expected.len().max(found.len())).then(|| Self {
+            index,
+            expected: expected.get(index).copied(),
+            found: found.get(index).copied(),
+        })
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.expected, self.found) {
+            (Some(expected), Some(found)) => write!(
+                f,
+                "code {} is {found:#x}, expected {expected:#x}",
+                self.index
+            ),
+            (Some(expected), None) => write!(
+                f,
+                "search ended after {} codes, expected code {} to be {expected:#x}",
+                self.index, self.index
+            ),
+            (None, Some(found)) => write!(
+                f,
+                "search found more than the {} expected codes, next {found:#x}",
+                self.index
+            ),
+            (None, None) => write!(f, "code {} differs", self.index),
+        }
+    }
+}
+
+/// Regenerate a family's codes like [`generate_with_progress`], comparing
+/// each with `expected` as it is found and stopping at the first
+/// difference, so a wrong family fails in seconds instead of after the
+/// whole search.
+///
+/// Matching codes still take the full search, since only its end shows
+/// that no code follows the last expected one.
+pub fn verify_with_progress(
+    layout: &Layout,
+    min_hamming: u32,
+    min_complexity: u32,
+    expected: &[u64],
+    on_progress: impl FnMut(u64, u64, usize),
+) -> Result<(), Mismatch> {
+    let total = 1u64 << layout.nbits;
+    let prune_orbits =
+        ComplexityGrid::from_layout(layout).is_rotation_invariant(layout.nbits as u32);
+    let mut mismatch = None;
+    let found = search_with(
+        layout,
+        min_hamming,
+        min_complexity,
+        total,
+        prune_orbits,
+        on_progress,
+        |index, code| {
+            if expected.get(index) == Some(&code) {
+                return ControlFlow::Continue(());
+            }
+            mismatch = Some(Mismatch {
+                index,
+                expected: expected.get(index).copied(),
+                found: Some(code),
+            });
+            ControlFlow::Break(())
+        },
+    );
+    match mismatch.or_else(|| Mismatch::first(expected, &found)) {
+        Some(mismatch) => Err(mismatch),
+        None => Ok(()),
+    }
+}
+
+/// Run the first `iterations` steps of the greedy search.
+///
+/// The accepted codes are always a prefix of the full family, since later
+/// candidates never affect earlier decisions.
+///
+/// # Orbit pruning
+///
+/// Every rejection test is invariant under rotating the candidate: the
+/// complexity check when the layout's energy is rotation invariant (checked
+/// by the caller, which passes `prune_orbits = false` otherwise), the self-rotation
+/// check always, and the distance check because `rotcodes` holds all four
+/// rotations of each accepted code. `rotcodes` only grows, so once any
+/// rotation of a code has been visited (and accepted or rejected), every
+/// later rotation of it is rejected. Candidates are therefore skipped
+/// outright unless they are the first of their rotation orbit in visit
+/// order, which is computed in O(1) by inverting the `PRIME` stride.
+#[cfg(test)]
+fn search(
+    layout: &Layout,
+    min_hamming: u32,
+    min_complexity: u32,
+    iterations: u64,
+    prune_orbits: bool,
+    on_progress: impl FnMut(u64, u64, usize),
+) -> Vec<u64> {
+    search_with(
+        layout,
+        min_hamming,
+        min_complexity,
+        iterations,
+        prune_orbits,
+        on_progress,
+        |_, _| ControlFlow::Continue(()),
+    )
+}
+
+/// [`search`], calling `on_accept(index, code)` for each accepted code and
+/// stopping early when it breaks.
+///
+/// Candidates are taken a block at a time. The checks that do not depend on
+/// the accepted codes (orbit, complexity, self-rotation) run over the whole
+/// block first, in parallel with the `parallel` feature; the survivors then
+/// go through the distance check in visit order, so the result is the same
+/// either way.
+fn search_with(
+    layout: &Layout,
+    min_hamming: u32,
+    min_complexity: u32,
+    iterations: u64,
+    prune_orbits: bool,
     mut on_progress: impl FnMut(u64, u64, usize),
+    mut on_accept: impl FnMut(usize, u64) -> ControlFlow<()>,
 ) -> Vec<u64> {
     let nbits = layout.nbits as u32;
     let mask = (1u64 << nbits) - 1;
@@ -208,51 +383,120 @@ pub fn generate_with_progress(
     // Pre-build grid once — avoids allocating a pixel grid per candidate
     let grid = ComplexityGrid::from_layout(layout);
 
-    // Report every 1M candidates (or every candidate for tiny families).
+    // Report every 1M candidates (or every candidate for tiny families),
+    // which is also the block size.
     let report_interval = 1_000_000u64.min(total).max(1);
 
-    let mut v = v0;
-    for iter in 0..total {
-        if iter % report_interval == 0 {
-            on_progress(iter, total, codelist.len());
-        }
-
-        v = v.wrapping_add(PRIME) & mask;
-
-        if !is_complex_enough(&grid, v) {
-            continue;
-        }
+    // Whether the candidate visited at `iter` passes the checks that do not
+    // depend on the accepted codes
+    let precheck = |iter: u64| -> Option<u64> {
+        let v = v0.wrapping_add((iter + 1).wrapping_mul(PRIME)) & mask;
 
         let rv1 = rotate90(v, nbits);
         let rv2 = rotate90(rv1, nbits);
         let rv3 = rotate90(rv2, nbits);
 
-        // Self-rotation distance check
+        if prune_orbits
+            && [rv1, rv2, rv3]
+                .iter()
+                .any(|&r| visit_index(r, v0, mask) < iter)
+        {
+            return None;
+        }
+
+        if !is_complex_enough(&grid, v) {
+            return None;
+        }
+
+        // Self-rotation distance check. Rotating both words preserves their
+        // distance, so d(rv1, rv2) = d(rv2, rv3) = d(rv3, v) = d(v, rv1) and
+        // d(rv1, rv3) = d(v, rv2).
         if !hamming_distance_at_least(v, rv1, min_hamming)
             || !hamming_distance_at_least(v, rv2, min_hamming)
-            || !hamming_distance_at_least(v, rv3, min_hamming)
-            || !hamming_distance_at_least(rv1, rv2, min_hamming)
-            || !hamming_distance_at_least(rv1, rv3, min_hamming)
-            || !hamming_distance_at_least(rv2, rv3, min_hamming)
         {
-            continue;
+            return None;
         }
+        Some(v)
+    };
+
+    let end = iterations.min(total);
+    for start in (0..end).step_by(report_interval as usize) {
+        on_progress(start, total, codelist.len());
+
+        let block = start..(start + report_interval).min(end);
+        #[cfg(feature = "parallel")]
+        let candidates: Vec<u64> = block.into_par_iter().filter_map(precheck).collect();
+        #[cfg(not(feature = "parallel"))]
+        let candidates: Vec<u64> = block.filter_map(precheck).collect();
+
+        for v in candidates {
+            // Distance from all previously accepted codes (and their rotations)
+            if rotcodes.has_any_closer_than(v, min_hamming) {
+                continue;
+            }
 
-        // Distance from all previously accepted codes (and their rotations)
-        if rotcodes.has_any_closer_than(v, min_hamming) {
-            continue;
+            let rv1 = rotate90(v, nbits);
+            let rv2 = rotate90(rv1, nbits);
+            let rv3 = rotate90(rv2, nbits);
+            codelist.push(v);
+            rotcodes.insert(v);
+            rotcodes.insert(rv1);
+            rotcodes.insert(rv2);
+            rotcodes.insert(rv3);
+            if on_accept(codelist.len() - 1, v).is_break() {
+                return codelist;
+            }
         }
-
-        codelist.push(v);
-        rotcodes.insert(v);
-        rotcodes.insert(rv1);
-        rotcodes.insert(rv2);
-        rotcodes.insert(rv3);
     }
 
     codelist
 }
 
+/// Multiplicative inverse of `PRIME` modulo 2^64.
+const PRIME_INVERSE: u64 = {
+    // Newton's iteration doubles the number of correct low bits each step;
+    // an odd number is its own inverse modulo 8.
+    let mut x = PRIME;
+    let mut i = 0;
+    while i < 5 {
+        x = x.wrapping_mul(2u64.wrapping_sub(PRIME.wrapping_mul(x)));
+        i += 1;
+    }
+    x
+};
+
+/// Iteration at which the search visits `code`.
+///
+/// Iteration `i` visits `v0 + (i + 1) * PRIME`, and `PRIME` is odd, so the
+/// stride is invertible modulo any power of two.
+fn visit_index(code: u64, v0: u64, mask: u64) -> u64 {
+    code.wrapping_sub(v0)
+        .wrapping_mul(PRIME_INVERSE)
+        .wrapping_sub(1)
+        & mask
+}
+
+/// Visual complexity of a code: its Ising energy normalized to `[0, 1]`.
+///
+/// Counts 4-connected black/white transitions across the whole tag
+/// (including fixed border cells) and divides by `2 * area`, roughly the
+/// number of adjacent cell pairs. [`generate`] only accepts codes scoring at
+/// least 0.3333 (classic families predate this check and may score lower).
+/// Higher scores mean busier patterns, which are less likely to be mimicked
+/// by background texture.
+///
+/// ```
+/// use apriltag_gen::codegen::complexity;
+///
+/// let family = apriltag_gen::family::tag_standard41h12();
+/// let c = complexity(&family.layout, family.codes[0]);
+/// assert!((0.3333..=1.0).contains(&c));
+/// ```
+pub fn complexity(layout: &Layout, code: u64) -> f64 {
+    let grid = ComplexityGrid::from_layout(layout);
+    ising_energy(&grid, code) as f64 / grid.threshold as f64
+}
+
 /// Check if a code has enough visual complexity (Ising energy).
 ///
 /// Counts 4-connected black/white transitions and requires
@@ -262,6 +506,10 @@ pub fn generate_with_progress(
 /// instead of per-shift loops. Each distinct net coefficient
 /// `(black_adj - white_adj)` gets one popcount call, typically 1-2 total.
 fn is_complex_enough(grid: &ComplexityGrid, code: u64) -> bool {
+    3 * ising_energy(grid, code) >= grid.threshold
+}
+
+fn ising_energy(grid: &ComplexityGrid, code: u64) -> i32 {
     let mut energy = grid.constant_energy;
 
     // Fixed ↔ data: popcount per net-coefficient group
@@ -276,7 +524,7 @@ fn is_complex_enough(grid: &ComplexityGrid, code: u64) -> bool {
         }
     }
 
-    3 * energy >= grid.threshold
+    energy
 }
 
 /// Reproduce Java's `new Random(seed).nextLong()`.
@@ -300,6 +548,7 @@ fn java_random_next_long(seed: i64) -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use apriltag::family::TagFamily;
     use apriltag::layout::Layout;
 
     #[test]
@@ -392,4 +641,225 @@ mod tests {
         let family = crate::family::tag_circle21h7();
         assert_eq!(codes, family.codes);
     }
+
+    /// The search without orbit pruning or the reduced self-rotation check.
+    fn search_unpruned(
+        layout: &Layout,
+        min_hamming: u32,
+        min_complexity: u32,
+        iterations: u64,
+    ) -> Vec<u64> {
+        let nbits = layout.nbits as u32;
+        let mask = (1u64 << nbits) - 1;
+        let seed = nbits as i64 * 10000 + min_hamming as i64 * 100 + min_complexity as i64;
+        let grid = ComplexityGrid::from_layout(layout);
+
+        let mut codelist = Vec::new();
+        let mut rotcodes = CodeSet::new();
+        let mut v = java_random_next_long(seed) as u64 & mask;
+        for _ in 0..iterations {
+            v = v.wrapping_add(PRIME) & mask;
+            if !is_complex_enough(&grid, v) {
+                continue;
+            }
+            let rots = [
+                v,
+                rotate90(v, nbits),
+                rotate90(rotate90(v, nbits), nbits),
+                rotate90(rotate90(rotate90(v, nbits), nbits), nbits),
+            ];
+            let self_ok = (0..4)
+                .all(|i| (i + 1..4).all(|j| hamming_distance(rots[i], rots[j]) >= min_hamming));
+            if !self_ok || rotcodes.has_any_closer_than(v, min_hamming) {
+                continue;
+            }
+            codelist.push(v);
+            for r in rots {
+                rotcodes.insert(r);
+            }
+        }
+        codelist
+    }
+
+    #[test]
+    fn visit_index_inverts_iteration_order() {
+        assert_eq!(PRIME.wrapping_mul(PRIME_INVERSE), 1);
+
+        let mask = (1u64 << 41) - 1;
+        let v0 = 0x123_4567_89ab & mask;
+        let mut v = v0;
+        for iter in 0..1000 {
+            v = v.wrapping_add(PRIME) & mask;
+            assert_eq!(visit_index(v, v0, mask), iter);
+        }
+        // v0 itself is the last code visited
+        assert_eq!(visit_index(v0, v0, mask), mask);
+    }
+
+    #[test]
+    fn builtin_layouts_are_rotation_invariant() {
+        for family in [
+            crate::family::tag_circle21h7(),
+            crate::family::tag_standard41h12(),
+            crate::family::tag36h11(),
+        ] {
+            let grid = ComplexityGrid::from_layout(&family.layout);
+            assert!(grid.is_rotation_invariant(family.layout.nbits as u32));
+        }
+    }
+
+    #[test]
+    fn asymmetric_layout_falls_back_to_unpruned_search() {
+        // A white cell in one transparent corner only, next to a data bit,
+        // makes the complexity check depend on rotation. A low minimum
+        // distance accepts enough codes for orbit pruning to go wrong.
+        let family = crate::family::tag_circle21h7();
+        let min_complexity = family.config.min_complexity.unwrap();
+        let mut layout = family.layout;
+        layout.cells[2] = CellType::White;
+        let grid = ComplexityGrid::from_layout(&layout);
+        assert!(!grid.is_rotation_invariant(layout.nbits as u32));
+
+        let codes = generate(&layout, 5, min_complexity);
+        assert_eq!(codes, search_unpruned(&layout, 5, min_complexity, 1 << 21));
+        let pruned = search(&layout, 5, min_complexity, 1 << 21, true, |_, _, _| {});
+        assert_ne!(codes, pruned);
+    }
+
+    #[test]
+    fn pruned_search_matches_unpruned() {
+        let family = crate::family::tag_circle21h7();
+        let config = &family.config;
+        let pruned = generate(
+            &family.layout,
+            config.min_hamming,
+            config.min_complexity.unwrap(),
+        );
+        let unpruned = search_unpruned(
+            &family.layout,
+            config.min_hamming,
+            config.min_complexity.unwrap(),
+            1 << 21,
+        );
+        assert_eq!(pruned, unpruned);
+    }
+
+    /// Run the first `iterations` steps of `family`'s search and check that
+    /// they reproduce a prefix of its built-in codes, with and without orbit
+    /// pruning. Returns the number of codes found.
+    fn assert_prefix_matches(family: &TagFamily, iterations: u64) -> usize {
+        let config = &family.config;
+        let min_complexity = config.min_complexity.unwrap();
+        let codes = search(
+            &family.layout,
+            config.min_hamming,
+            min_complexity,
+            iterations,
+            true,
+            |_, _, _| {},
+        );
+        assert_eq!(codes, family.codes[..codes.len()], "{}", config.name);
+        assert_eq!(
+            codes,
+            search_unpruned(
+                &family.layout,
+                config.min_hamming,
+                min_complexity,
+                iterations
+            ),
+            "{}",
+            config.name
+        );
+        codes.len()
+    }
+
+    #[test]
+    fn standard41h12_prefix_matches_reference() {
+        // The full 2^41 search takes hours; any prefix of it must reproduce
+        // a prefix of the built-in codes.
+        assert!(assert_prefix_matches(&crate::family::tag_standard41h12(), 1 << 18) > 10);
+    }
+
+    #[test]
+    fn circle49h12_prefix_matches_reference() {
+        // 49 bits exercises masks, seeds and rotations well past the 41-bit
+        // families, where nearly every early candidate is accepted.
+        assert!(assert_prefix_matches(&crate::family::tag_circle49h12(), 1 << 14) > 1000);
+    }
+
+    #[test]
+    #[ignore = "slow; run with --ignored --release"]
+    fn standard41h12_long_prefix_matches_reference() {
+        assert!(assert_prefix_matches(&crate::family::tag_standard41h12(), 1 << 26) > 1000);
+    }
+
+    #[test]
+    #[ignore = "slow; run with --ignored --release"]
+    fn circle49h12_long_prefix_matches_reference() {
+        assert!(assert_prefix_matches(&crate::family::tag_circle49h12(), 1 << 20) > 10000);
+    }
+
+    #[test]
+    fn complexity_is_normalized_energy() {
+        let family = crate::family::tag_circle21h7();
+        let grid = ComplexityGrid::from_layout(&family.layout);
+        for &code in &family.codes {
+            let c = complexity(&family.layout, code);
+            assert_eq!(c, ising_energy(&grid, code) as f64 / grid.threshold as f64);
+            assert!((0.3333..=1.0).contains(&c));
+            assert_eq!(c >= 1.0 / 3.0, is_complex_enough(&grid, code));
+        }
+    }
+
+    #[test]
+    fn verify_stops_at_first_mismatch() {
+        let family = crate::family::tag_circle21h7();
+        let config = &family.config;
+        let min_complexity = config.min_complexity.unwrap();
+        let verify = |expected: &[u64]| {
+            let mut searched = 0;
+            let result = verify_with_progress(
+                &family.layout,
+                config.min_hamming,
+                min_complexity,
+                expected,
+                |iter, _, _| searched = iter,
+            );
+            (result, searched)
+        };
+
+        assert_eq!(verify(&family.codes).0, Ok(()));
+
+        let mut corrupt = family.codes.clone();
+        corrupt[5] ^= 1;
+        let (result, searched) = verify(&corrupt);
+        let mismatch = result.unwrap_err();
+        assert_eq!(
+            mismatch,
+            Mismatch {
+                index: 5,
+                expected: Some(corrupt[5]),
+                found: Some(family.codes[5]),
+            }
+        );
+        // Stopped within the first progress block
+        assert_eq!(searched, 0);
+
+        let n = family.codes.len();
+        let extra = verify(&family.codes[..n - 1]).0.unwrap_err();
+        assert_eq!((extra.index, extra.expected), (n - 1, None));
+        let mut longer = family.codes.clone();
+        longer.push(0);
+        let missing = verify(&longer).0.unwrap_err();
+        assert_eq!((missing.index, missing.found), (n, None));
+    }
+
+    #[test]
+    fn first_mismatch_of_lists() {
+        assert_eq!(Mismatch::first(&[1, 2], &[1, 2]), None);
+        let m = Mismatch::first(&[1, 2, 3], &[1, 4]).unwrap();
+        assert_eq!((m.index, m.expected, m.found), (1, Some(2), Some(4)));
+        let m = Mismatch::first(&[1], &[1, 4]).unwrap();
+        assert_eq!((m.index, m.expected, m.found), (1, None, Some(4)));
+    }
 }
@@ -3,4 +3,6 @@
 pub use apriltag::*;
 
 pub mod codegen;
+pub mod robustness;
+pub mod select;
 pub mod upgrade;
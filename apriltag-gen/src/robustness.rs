@@ -0,0 +1,214 @@
+//! Bit-level robustness of a tag family's code set.
+//!
+//! The decoder accepts a sampled bit pattern if it lies within `max_hamming`
+//! bits of some code in some rotation. This module quantifies the two sides
+//! of that trade-off: how likely a random pattern (e.g. a textured quad in the
+//! background) is accepted, and how many bit errors it takes for a real tag
+//! to be read as a different ID or rotation.
+
+use std::collections::HashSet;
+
+use apriltag::family::TagFamily;
+use apriltag::hamming::{hamming_distance, rotate90};
+
+/// Largest number of Hamming-ball members enumerated to compute an exact
+/// false-positive rate when balls overlap. Beyond this the union bound is
+/// reported instead.
+const MAX_EXACT_WORDS: u64 = 1 << 24;
+
+/// Robustness of a family at one decoder Hamming limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HammingRobustness {
+    pub max_hamming: u32,
+    /// Probability that a uniformly random data pattern decodes as some tag
+    /// in some rotation.
+    pub false_positive_rate: f64,
+    /// Whether `false_positive_rate` is exact. Otherwise it is the union
+    /// bound, an upper bound.
+    pub exact: bool,
+    /// Fewest bit errors that can make a tag decode as a different ID or
+    /// rotation. Zero means some patterns are ambiguous without any errors.
+    pub min_confusion_errors: u32,
+}
+
+/// Robustness of a family across decoder Hamming limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyRobustness {
+    pub nbits: u32,
+    /// Number of distinct codewords, counting each rotation of a code.
+    pub rotated_codes: usize,
+    /// Minimum Hamming distance between any two rotated codewords.
+    pub min_distance: u32,
+    pub levels: Vec<HammingRobustness>,
+}
+
+/// Analyze a family at each of the given `max_hamming` settings.
+///
+/// The minimum distance search stops at the first pair of codewords that
+/// reaches the family's nominal `min_hamming`, which the generator guarantees
+/// as a lower bound.
+///
+/// ```
+/// use apriltag_gen::robustness::analyze;
+///
+/// let r = analyze(&apriltag_gen::family::tag36h11(), 0..=2);
+/// assert_eq!(r.min_distance, 11);
+/// assert_eq!(r.levels[2].min_confusion_errors, 9);
+/// assert!(r.levels[2].false_positive_rate < 1e-4);
+/// ```
+pub fn analyze(family: &TagFamily, max_hamming: impl IntoIterator<Item = u32>) -> FamilyRobustness {
+    let nbits = family.layout.nbits as u32;
+    let codes = rotated_codes(&family.codes, nbits);
+    let min_distance = min_distance(&codes, family.config.min_hamming);
+
+    let levels = max_hamming
+        .into_iter()
+        .map(|h| HammingRobustness {
+            max_hamming: h,
+            min_confusion_errors: min_distance.saturating_sub(h),
+            ..false_positive_rate(&codes, nbits, h, min_distance)
+        })
+        .collect();
+
+    FamilyRobustness {
+        nbits,
+        rotated_codes: codes.len(),
+        min_distance,
+        levels,
+    }
+}
+
+/// All four rotations of every code, deduplicated.
+fn rotated_codes(codes: &[u64], nbits: u32) -> Vec<u64> {
+    let mut out = Vec::with_capacity(codes.len() * 4);
+    for &code in codes {
+        let mut c = code;
+        for _ in 0..4 {
+            out.push(c);
+            c = rotate90(c, nbits);
+        }
+    }
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Minimum pairwise distance, stopping early once `lower_bound` is reached.
+fn min_distance(codes: &[u64], lower_bound: u32) -> u32 {
+    let mut best = u32::MAX;
+    for (i, &a) in codes.iter().enumerate() {
+        for &b in &codes[i + 1..] {
+            best = best.min(hamming_distance(a, b));
+        }
+        if best <= lower_bound {
+            break;
+        }
+    }
+    best
+}
+
+/// Number of words within `radius` bits of a fixed `nbits`-bit word.
+fn ball_volume(nbits: u32, radius: u32) -> u64 {
+    let mut binom = 1u64;
+    let mut total = 0u64;
+    for k in 0..=radius.min(nbits) {
+        total += binom;
+        binom = binom * (nbits - k) as u64 / (k + 1) as u64;
+    }
+    total
+}
+
+fn false_positive_rate(
+    codes: &[u64],
+    nbits: u32,
+    max_hamming: u32,
+    min_distance: u32,
+) -> HammingRobustness {
+    let space = 2f64.powi(nbits as i32);
+    let words = codes.len() as u64 * ball_volume(nbits, max_hamming);
+
+    let (accepted, exact) = if 2 * max_hamming < min_distance {
+        // Balls are disjoint: the union bound is exact.
+        (words as f64, true)
+    } else if words <= MAX_EXACT_WORDS {
+        let mut accepted = HashSet::with_capacity(words as usize);
+        for &code in codes {
+            for_each_within(code, nbits, max_hamming, &mut |w| {
+                accepted.insert(w);
+            });
+        }
+        (accepted.len() as f64, true)
+    } else {
+        (words as f64, false)
+    };
+
+    HammingRobustness {
+        max_hamming,
+        false_positive_rate: (accepted / space).min(1.0),
+        exact,
+        min_confusion_errors: 0,
+    }
+}
+
+/// Call `f` with every word within `radius` bits of `code`.
+fn for_each_within(code: u64, nbits: u32, radius: u32, f: &mut impl FnMut(u64)) {
+    fn flip(word: u64, from: u32, nbits: u32, radius: u32, f: &mut impl FnMut(u64)) {
+        f(word);
+        if radius == 0 {
+            return;
+        }
+        for bit in from..nbits {
+            flip(word ^ (1 << bit), bit + 1, nbits, radius - 1, f);
+        }
+    }
+    flip(code, 0, nbits, radius, f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ball_volumes() {
+        assert_eq!(ball_volume(16, 0), 1);
+        assert_eq!(ball_volume(16, 2), 1 + 16 + 120);
+        assert_eq!(ball_volume(4, 9), 16);
+
+        let mut n = 0;
+        for_each_within(0, 16, 2, &mut |_| n += 1);
+        assert_eq!(n, ball_volume(16, 2));
+    }
+
+    #[test]
+    fn tag16h5_disjoint_and_overlapping_balls() {
+        let family = apriltag::family::tag16h5();
+        let r = analyze(&family, 0..=3);
+        assert_eq!(r.rotated_codes, 4 * family.codes.len());
+        assert_eq!(r.min_distance, 5);
+
+        // Disjoint balls: exact closed form
+        let space = 65536.0;
+        assert_eq!(r.levels[0].false_positive_rate, 120.0 / space);
+        assert_eq!(r.levels[2].false_positive_rate, 120.0 * 137.0 / space);
+        assert!(r.levels.iter().all(|l| l.exact));
+
+        // Overlapping balls at h=3 (2h >= 5): strictly below the union bound
+        let union = 120.0 * ball_volume(16, 3) as f64 / space;
+        assert!(r.levels[3].false_positive_rate < union);
+        assert!(r.levels[3].false_positive_rate > r.levels[2].false_positive_rate);
+
+        let confusion: Vec<u32> = r.levels.iter().map(|l| l.min_confusion_errors).collect();
+        assert_eq!(confusion, [5, 4, 3, 2]);
+    }
+
+    #[test]
+    fn falls_back_to_union_bound_for_large_overlaps() {
+        let family = apriltag::family::tag36h11();
+        let r = analyze(&family, [6]);
+        let level = r.levels[0];
+        assert!(!level.exact);
+        assert_eq!(level.min_confusion_errors, 5);
+        let union = r.rotated_codes as f64 * ball_volume(36, 6) as f64 / 2f64.powi(36);
+        assert_eq!(level.false_positive_rate, union);
+    }
+}
@@ -0,0 +1,223 @@
+//! Choosing a subset of a family's tags for a deployment.
+//!
+//! A deployment that only needs a few tags can do better than IDs `0..n`:
+//! picking codes that are far apart from each other raises the number of bit
+//! errors needed to confuse one deployed tag with another, and picking
+//! complex codes makes them less likely to be mimicked by background texture.
+
+use apriltag::family::TagFamily;
+use apriltag::hamming::{hamming_distance, rotate90};
+
+use crate::codegen::complexity;
+
+/// What to optimize when selecting tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    /// Greedily maximize the minimum distance between selected tags, breaking
+    /// ties by complexity.
+    MaxMinDistance,
+    /// The most visually complex codes.
+    Complexity,
+}
+
+impl Criterion {
+    /// Parse a kebab-case criterion name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "max-min-distance" => Some(Self::MaxMinDistance),
+            "complexity" => Some(Self::Complexity),
+            _ => None,
+        }
+    }
+}
+
+/// One selected tag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Selected {
+    pub id: usize,
+    /// Minimum rotation-aware distance to the tags selected before it, or
+    /// `None` for the first tag.
+    pub distance: Option<u32>,
+    pub complexity: f64,
+}
+
+/// Select up to `count` tags of `family`, in selection order.
+///
+/// Distances are rotation-aware: the distance between two tags is the
+/// smallest Hamming distance between any rotations of their codes, which is
+/// what the decoder needs to tell them apart. The minimum distance of the
+/// selection is the last entry's `distance` for
+/// [`Criterion::MaxMinDistance`].
+///
+/// ```
+/// use apriltag_gen::select::{select, Criterion};
+///
+/// let family = apriltag_gen::family::tag16h5();
+/// let picked = select(&family, 4, Criterion::MaxMinDistance);
+/// assert_eq!(picked.len(), 4);
+/// assert!(picked[3].distance.unwrap() > family.config.min_hamming);
+/// ```
+pub fn select(family: &TagFamily, count: usize, criterion: Criterion) -> Vec<Selected> {
+    let nbits = family.layout.nbits as u32;
+    let scores: Vec<f64> = family
+        .codes
+        .iter()
+        .map(|&code| complexity(&family.layout, code))
+        .collect();
+
+    // Candidates ordered by decreasing complexity, then ID
+    let mut order: Vec<usize> = (0..family.codes.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    order.truncate(match criterion {
+        Criterion::Complexity => count,
+        Criterion::MaxMinDistance => order.len(),
+    });
+
+    let mut selected: Vec<Selected> = Vec::with_capacity(count.min(order.len()));
+    // Minimum distance from each remaining candidate to the selection
+    let mut nearest: Vec<u32> = vec![u32::MAX; order.len()];
+    let mut taken = vec![false; order.len()];
+
+    while selected.len() < count.min(order.len()) {
+        // First maximum wins, so ties go to the more complex code
+        let mut best: Option<usize> = None;
+        for i in 0..order.len() {
+            if !taken[i] && best.is_none_or(|b| nearest[i] > nearest[b]) {
+                best = Some(i);
+            }
+        }
+        let Some(best) = best else {
+            break;
+        };
+        taken[best] = true;
+        let id = order[best];
+        selected.push(Selected {
+            id,
+            distance: (!selected.is_empty()).then_some(nearest[best]),
+            complexity: scores[id],
+        });
+
+        if criterion == Criterion::MaxMinDistance {
+            let rotations = rotations(family.codes[id], nbits);
+            for (i, &other) in order.iter().enumerate() {
+                if !taken[i] {
+                    let d = rotations
+                        .iter()
+                        .map(|&r| hamming_distance(r, family.codes[other]))
+                        .min()
+                        .unwrap_or(u32::MAX);
+                    nearest[i] = nearest[i].min(d);
+                }
+            }
+        }
+    }
+
+    if criterion == Criterion::Complexity {
+        fill_distances(&mut selected, &family.codes, nbits);
+    }
+    selected
+}
+
+fn rotations(code: u64, nbits: u32) -> [u64; 4] {
+    let r1 = rotate90(code, nbits);
+    let r2 = rotate90(r1, nbits);
+    [code, r1, r2, rotate90(r2, nbits)]
+}
+
+/// Compute each entry's distance to the entries before it.
+fn fill_distances(selected: &mut [Selected], codes: &[u64], nbits: u32) {
+    for i in 1..selected.len() {
+        let rotations = rotations(codes[selected[i].id], nbits);
+        selected[i].distance = selected[..i]
+            .iter()
+            .flat_map(|s| rotations.iter().map(|&r| hamming_distance(r, codes[s.id])))
+            .min();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Brute-force minimum rotation-aware distance among `ids`.
+    fn min_distance(family: &TagFamily, ids: &[usize]) -> u32 {
+        let nbits = family.layout.nbits as u32;
+        let mut best = u32::MAX;
+        for (i, &a) in ids.iter().enumerate() {
+            for &b in &ids[i + 1..] {
+                for r in rotations(family.codes[a], nbits) {
+                    best = best.min(hamming_distance(r, family.codes[b]));
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn max_min_distance_beats_first_ids() {
+        let family = apriltag::family::tag36h11();
+        let picked = select(&family, 20, Criterion::MaxMinDistance);
+        let ids: Vec<usize> = picked.iter().map(|s| s.id).collect();
+
+        let mut unique = ids.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 20);
+
+        let achieved = min_distance(&family, &ids);
+        assert_eq!(
+            Some(achieved),
+            picked.iter().filter_map(|s| s.distance).min()
+        );
+        assert!(achieved > min_distance(&family, &(0..20).collect::<Vec<_>>()));
+
+        // Distances to the selection can only shrink as it grows
+        let distances: Vec<u32> = picked.iter().filter_map(|s| s.distance).collect();
+        assert!(distances.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(picked[0].distance, None);
+    }
+
+    #[test]
+    fn complexity_picks_most_complex_codes() {
+        let family = apriltag::family::tag16h5();
+        let picked = select(&family, 5, Criterion::Complexity);
+        assert_eq!(picked.len(), 5);
+        assert!(picked
+            .windows(2)
+            .all(|w| w[0].complexity >= w[1].complexity));
+
+        let threshold = picked[4].complexity;
+        let rest = (0..family.codes.len())
+            .filter(|id| picked.iter().all(|s| s.id != *id))
+            .map(|id| complexity(&family.layout, family.codes[id]));
+        assert!(rest.into_iter().all(|c| c <= threshold));
+
+        let ids: Vec<usize> = picked.iter().map(|s| s.id).collect();
+        assert_eq!(
+            Some(min_distance(&family, &ids)),
+            picked.iter().filter_map(|s| s.distance).min()
+        );
+    }
+
+    #[test]
+    fn count_is_capped_by_family_size() {
+        let family = apriltag::family::tag16h5();
+        for criterion in [Criterion::MaxMinDistance, Criterion::Complexity] {
+            assert_eq!(select(&family, 100, criterion).len(), family.codes.len());
+            assert!(select(&family, 0, criterion).is_empty());
+        }
+    }
+
+    #[test]
+    fn criterion_names() {
+        assert_eq!(
+            Criterion::from_name("max-min-distance"),
+            Some(Criterion::MaxMinDistance)
+        );
+        assert_eq!(
+            Criterion::from_name("complexity"),
+            Some(Criterion::Complexity)
+        );
+        assert_eq!(Criterion::from_name("random"), None);
+    }
+}
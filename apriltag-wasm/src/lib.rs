@@ -4,19 +4,32 @@ use serde::{Deserialize, Serialize};
 use tsify_next::Tsify;
 use wasm_bindgen::prelude::*;
 
+use apriltag::detect::homography::Homography;
+use apriltag::detect::image::{max_dimension_size, resize_into, ResizeFilter};
 use apriltag::detect::pose::{estimate_tag_pose, PoseParams};
 use apriltag::family;
 use apriltag::{
-    Detection as CoreDetection, Detector as CoreDetector, DetectorBuffers, DetectorConfig, ImageRef,
+    Detection as CoreDetection, Detector as CoreDetector, DetectorBuffers, DetectorBuilder,
+    DetectorConfig, GrayImage, ImageRef, ImageU8,
+};
+
+mod scan;
+mod worker;
+
+pub use scan::{ScanController, WasmScanConfig, WasmScanResult, WasmTagKey};
+pub use worker::{
+    init_worker, worker_detect_request, WasmFrameFormat, WasmWorkerRequest, WasmWorkerResponse,
 };
 
 // ── Tsify types for TypeScript interface generation ──
 
 /// Detector configuration passed from JavaScript.
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Default)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmDetectorConfig {
-    /// Tag family names to detect (e.g. ["tag36h11"]).
+    /// Tag family names to detect (e.g. ["tag36h11"]). A name may carry its
+    /// own maximum Hamming distance, overriding `max_hamming`
+    /// (e.g. ["tag36h11", "tag16h5:0"]).
     pub families: Vec<String>,
     /// Decimation factor (default: 2.0).
     #[serde(default = "default_decimate")]
@@ -30,29 +43,85 @@ pub struct WasmDetectorConfig {
     /// Decode sharpening factor (default: 0.25).
     #[serde(default)]
     pub decode_sharpening: Option<f64>,
+    /// Drop detections whose decision margin is below this (default: 0.0).
+    #[serde(default)]
+    pub min_decision_margin: Option<f32>,
+    /// Also detect mirrored tags (default: false).
+    #[serde(default)]
+    pub decode_mirrored: Option<bool>,
+    /// Refine each decoded tag's homography from its cell edges
+    /// (default: false).
+    #[serde(default)]
+    pub refine_homography: Option<bool>,
+    /// Align each decoded tag's bit pattern with the image (default: false).
+    #[serde(default)]
+    pub refine_decode: Option<bool>,
     /// Maximum Hamming distance for matching (default: 2).
     #[serde(default)]
     pub max_hamming: Option<u32>,
 
-    // ── QuadThreshParams overrides ──
-    /// Minimum pixels in a cluster (default: 5).
+    // ── QuadParams overrides ──
+    /// Minimum cluster size in full-resolution pixels (default: 5 at the
+    /// working resolution).
     #[serde(default)]
-    pub min_cluster_pixels: Option<i32>,
+    pub min_cluster_pixels: Option<u32>,
     /// Maximum number of local maxima (default: 10).
     #[serde(default)]
-    pub max_nmaxima: Option<i32>,
-    /// Cosine of critical angle for quad detection (default: cos(10°)).
+    pub max_nmaxima: Option<u32>,
+    /// Critical corner angle in degrees for quad detection (default: 10).
     #[serde(default)]
-    pub cos_critical_rad: Option<f32>,
-    /// Maximum line-fit mean-squared error (default: 10.0).
+    pub critical_angle_deg: Option<f32>,
+    /// Maximum line-fit mean-squared error in full-resolution pixels²
+    /// (default: 10.0 at the working resolution).
     #[serde(default)]
     pub max_line_fit_mse: Option<f32>,
     /// Minimum white-black pixel difference (default: 5).
     #[serde(default)]
-    pub min_white_black_diff: Option<i32>,
+    pub min_white_black_diff: Option<u8>,
+    /// Adaptive-threshold tile side in full-resolution pixels (default: 4
+    /// at the working resolution).
+    #[serde(default)]
+    pub tile_size: Option<u32>,
+    /// Minimum intensity step across a cluster boundary (default: 0, disabled).
+    #[serde(default)]
+    pub min_edge_gradient: Option<u8>,
     /// Enable deglitching (default: false).
     #[serde(default)]
     pub deglitch: Option<bool>,
+    /// Pixel width divided by pixel height, for anamorphic or unevenly
+    /// binned sensors (default: 1.0).
+    #[serde(default)]
+    pub pixel_aspect_ratio: Option<f32>,
+
+    /// Downscale frames whose longer side exceeds this many pixels before
+    /// detection (default: none). Detections are reported in input coordinates.
+    #[serde(default)]
+    pub max_dimension: Option<u32>,
+    /// Per-frame detection time budget in milliseconds. Frames that take
+    /// longer are counted in [`WasmDiagnostics::over_budget_frames`]
+    /// (default: none).
+    #[serde(default)]
+    pub frame_budget_ms: Option<f64>,
+    /// Families generated with `apriltag-gen`, detected alongside
+    /// `families` (requires the `custom-families` feature).
+    #[cfg(feature = "custom-families")]
+    #[serde(default)]
+    pub custom_families: Vec<WasmCustomFamily>,
+}
+
+/// A tag family loaded from the files `apriltag-gen generate` writes.
+#[cfg(feature = "custom-families")]
+#[derive(Tsify, Serialize, Deserialize, Default)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCustomFamily {
+    /// Contents of the family's `.toml` config.
+    pub toml: String,
+    /// Contents of its `.bin` code file (an array or `Uint8Array`).
+    pub bin: Vec<u8>,
+    /// Maximum Hamming distance for matching (default: the config's
+    /// `max_hamming`).
+    #[serde(default)]
+    pub max_hamming: Option<u32>,
 }
 
 fn default_decimate() -> Option<f32> {
@@ -60,7 +129,7 @@ fn default_decimate() -> Option<f32> {
 }
 
 /// A detected AprilTag returned to JavaScript.
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmDetection {
     pub family: String,
@@ -69,6 +138,15 @@ pub struct WasmDetection {
     pub decision_margin: f32,
     pub center: [f64; 2],
     pub corners: [[f64; 2]; 4],
+    /// Whether the tag was seen mirrored.
+    #[serde(default)]
+    pub mirrored: bool,
+    /// Largest per-edge line-fit mean squared error, in pixels².
+    #[serde(default)]
+    pub line_fit_mse: f32,
+    /// Largest per-edge residual after edge refinement, in pixels².
+    #[serde(default)]
+    pub refine_residual: Option<f32>,
 }
 
 /// A 3D pose estimate returned to JavaScript.
@@ -81,6 +159,235 @@ pub struct WasmPose {
     pub translation: [f64; 3],
     /// Reprojection error.
     pub error: f64,
+    /// Pixel coordinates of the tag origin and its X, Y, Z axis tips
+    /// (half a tag size long), for drawing overlays.
+    pub axes: [[f64; 2]; 4],
+}
+
+/// Metadata for a built-in tag family returned to JavaScript.
+#[derive(Tsify, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi)]
+pub struct WasmFamilyInfo {
+    pub name: String,
+    /// Number of data bits per tag.
+    pub bits: u32,
+    /// Minimum Hamming distance between any two codes.
+    pub min_hamming: u32,
+    /// Number of codes; valid IDs are `0..code_count`.
+    pub code_count: u32,
+    /// Width of the full tag grid in cells, including the outer border.
+    pub grid_size: u32,
+    /// Width of the black border square in cells.
+    pub border_width: u32,
+    /// Whether the data region sits inside a white (reversed) border.
+    pub reversed_border: bool,
+}
+
+/// What was compiled into this WASM module, returned to JavaScript.
+#[derive(Tsify, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi)]
+pub struct WasmBuildInfo {
+    /// Version of the `apriltag-wasm` crate.
+    pub version: String,
+    /// Names of the tag families compiled into this build.
+    pub families: Vec<String>,
+    /// Enabled cargo features of `apriltag-wasm`.
+    pub features: Vec<String>,
+    /// Whether the module was compiled with WebAssembly SIMD (`simd128`).
+    pub simd128: bool,
+}
+
+/// Memory and timing diagnostics for a [`Detector`].
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[tsify(into_wasm_abi)]
+pub struct WasmDiagnostics {
+    /// Current size of the module's linear memory in bytes.
+    pub memory_bytes: f64,
+    /// Heap bytes held by the detector's decode tables.
+    pub decode_table_bytes: f64,
+    /// Number of frames detected since construction.
+    pub frames: u32,
+    /// Duration of the most recent detection in milliseconds.
+    pub last_detect_ms: Option<f64>,
+    /// Mean detection duration in milliseconds.
+    pub mean_detect_ms: Option<f64>,
+    /// Longest detection duration in milliseconds.
+    pub max_detect_ms: Option<f64>,
+    /// The configured frame budget, if any.
+    pub frame_budget_ms: Option<f64>,
+    /// Number of frames whose detection exceeded the frame budget.
+    pub over_budget_frames: u32,
+}
+
+// ── Diagnostics ──
+
+/// Size of the module's linear memory in bytes.
+///
+/// WebAssembly memory only ever grows, so a value that keeps rising across
+/// frames (e.g. from repeatedly constructing detectors) will not come back
+/// down; reuse detectors and lower `max_dimension` or `quad_decimate` before
+/// it gets large. Always 0 outside WebAssembly.
+#[wasm_bindgen(js_name = getMemoryUsage)]
+pub fn get_memory_usage() -> f64 {
+    memory_bytes() as f64
+}
+
+#[cfg(target_arch = "wasm32")]
+fn memory_bytes() -> usize {
+    core::arch::wasm32::memory_size::<0>() * 65536
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn memory_bytes() -> usize {
+    0
+}
+
+/// Milliseconds from an arbitrary fixed origin, for measuring durations.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    // `performance` exists on both windows and worker scopes, but not in
+    // every embedding; fall back to the coarser wall clock.
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .filter(|p| !p.is_undefined())
+        .and_then(|p| {
+            let now: js_sys::Function = js_sys::Reflect::get(&p, &"now".into())
+                .ok()?
+                .dyn_into()
+                .ok()?;
+            now.call0(&p).ok()?.as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Running detection-time statistics.
+#[derive(Default)]
+struct FrameStats {
+    budget_ms: Option<f64>,
+    frames: u32,
+    last_ms: Option<f64>,
+    total_ms: f64,
+    max_ms: Option<f64>,
+    over_budget: u32,
+}
+
+impl FrameStats {
+    fn new(budget_ms: Option<f64>) -> Self {
+        Self {
+            budget_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Run `f`, recording how long it took.
+    fn time<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let start = now_ms();
+        let result = f();
+        self.record(now_ms() - start);
+        result
+    }
+
+    fn record(&mut self, ms: f64) {
+        self.frames = self.frames.saturating_add(1);
+        self.last_ms = Some(ms);
+        self.total_ms += ms;
+        self.max_ms = Some(self.max_ms.map_or(ms, |m| m.max(ms)));
+        if self.budget_ms.is_some_and(|b| ms > b) {
+            self.over_budget = self.over_budget.saturating_add(1);
+        }
+    }
+
+    fn diagnostics(&self, decode_table_bytes: usize) -> WasmDiagnostics {
+        WasmDiagnostics {
+            memory_bytes: memory_bytes() as f64,
+            decode_table_bytes: decode_table_bytes as f64,
+            frames: self.frames,
+            last_detect_ms: self.last_ms,
+            mean_detect_ms: (self.frames > 0).then(|| self.total_ms / self.frames as f64),
+            max_detect_ms: self.max_ms,
+            frame_budget_ms: self.budget_ms,
+            over_budget_frames: self.over_budget,
+        }
+    }
+}
+
+// ── Family metadata ──
+
+/// Names of the tag families compiled into this build.
+#[wasm_bindgen(js_name = listFamilies)]
+pub fn list_families() -> Vec<String> {
+    family::BUILTIN_NAMES
+        .iter()
+        .map(|n| n.to_string())
+        .collect()
+}
+
+/// Families, features and target options compiled into this build.
+#[wasm_bindgen(js_name = getBuildInfo)]
+pub fn get_build_info() -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(&build_info()).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Cargo features of this crate, with whether each is enabled.
+const FEATURES: &[(&str, bool)] = &[
+    ("all-families", cfg!(feature = "all-families")),
+    ("custom-families", cfg!(feature = "custom-families")),
+    ("family-tag16h5", cfg!(feature = "family-tag16h5")),
+    ("family-tag25h9", cfg!(feature = "family-tag25h9")),
+    ("family-tag36h11", cfg!(feature = "family-tag36h11")),
+    ("family-circle21h7", cfg!(feature = "family-circle21h7")),
+    ("family-circle49h12", cfg!(feature = "family-circle49h12")),
+    ("family-custom48h12", cfg!(feature = "family-custom48h12")),
+    (
+        "family-standard41h12",
+        cfg!(feature = "family-standard41h12"),
+    ),
+    (
+        "family-standard52h13",
+        cfg!(feature = "family-standard52h13"),
+    ),
+    ("simd", cfg!(feature = "simd")),
+];
+
+fn build_info() -> WasmBuildInfo {
+    WasmBuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        families: list_families(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        simd128: cfg!(target_feature = "simd128"),
+    }
+}
+
+/// Metadata for a built-in tag family.
+#[wasm_bindgen(js_name = familyInfo)]
+pub fn family_info(name: &str) -> Result<JsValue, JsError> {
+    let fam = family::builtin_family(name)
+        .ok_or_else(|| JsError::new(&format!("unknown tag family: {name}")))?;
+    serde_wasm_bindgen::to_value(&family_to_info(&fam)).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn family_to_info(fam: &family::TagFamily) -> WasmFamilyInfo {
+    WasmFamilyInfo {
+        name: fam.config.name.to_string(),
+        bits: fam.layout.nbits as u32,
+        min_hamming: fam.config.min_hamming,
+        code_count: fam.codes.len() as u32,
+        grid_size: fam.layout.grid_size as u32,
+        border_width: fam.layout.border_width as u32,
+        reversed_border: fam.layout.reversed_border,
+    }
 }
 
 // ── Detector wrapper ──
@@ -91,6 +398,8 @@ pub struct Detector {
     inner: CoreDetector,
     buffers: DetectorBuffers,
     gray_buf: Vec<u8>,
+    downscale: Downscale,
+    stats: FrameStats,
 }
 
 #[wasm_bindgen]
@@ -98,74 +407,24 @@ impl Detector {
     /// Create a new detector with the given configuration.
     #[wasm_bindgen(constructor)]
     pub fn new(config: WasmDetectorConfig) -> Result<Detector, JsError> {
-        let mut det_config = DetectorConfig::default();
-
-        if let Some(d) = config.quad_decimate {
-            det_config.quad_decimate = d;
-        }
-        if let Some(s) = config.quad_sigma {
-            det_config.quad_sigma = s;
-        }
-        if let Some(r) = config.refine_edges {
-            det_config.refine_edges = r;
-        }
-        if let Some(s) = config.decode_sharpening {
-            det_config.decode_sharpening = s;
-        }
-
-        // QuadThreshParams overrides
-        if let Some(v) = config.min_cluster_pixels {
-            det_config.qtp.min_cluster_pixels = v;
-        }
-        if let Some(v) = config.max_nmaxima {
-            det_config.qtp.max_nmaxima = v;
-        }
-        if let Some(v) = config.cos_critical_rad {
-            det_config.qtp.cos_critical_rad = v;
-        }
-        if let Some(v) = config.max_line_fit_mse {
-            det_config.qtp.max_line_fit_mse = v;
-        }
-        if let Some(v) = config.min_white_black_diff {
-            det_config.qtp.min_white_black_diff = v;
-        }
-        if let Some(v) = config.deglitch {
-            det_config.qtp.deglitch = v;
-        }
-
-        let max_hamming = config.max_hamming.unwrap_or(2);
-        let mut inner = CoreDetector::new(det_config);
-
-        for family_name in &config.families {
-            let fam = family::builtin_family(family_name)
-                .ok_or_else(|| JsError::new(&format!("unknown tag family: {family_name}")))?;
-            inner.add_family(fam, max_hamming);
-        }
+        let inner = build_core_detector(&config).map_err(|e| JsError::new(&e))?;
 
         Ok(Detector {
             inner,
             buffers: DetectorBuffers::new(),
             gray_buf: Vec::new(),
+            downscale: Downscale::new(config.max_dimension),
+            stats: FrameStats::new(config.frame_budget_ms),
         })
     }
 
     /// Detect tags in a grayscale image (one byte per pixel).
     pub fn detect(&mut self, data: &[u8], width: u32, height: u32) -> Result<JsValue, JsError> {
-        let expected = (width * height) as usize;
-        if data.len() != expected {
-            return Err(JsError::new(&format!(
-                "data length {} does not match {}x{} = {}",
-                data.len(),
-                width,
-                height,
-                expected,
-            )));
-        }
+        check_gray_len(data, width, height).map_err(|e| JsError::new(&e))?;
 
         let img = ImageRef::new(width, height, width, data);
-        let detections = self.inner.detect(&img, &mut self.buffers);
-
-        let wasm_dets: Vec<WasmDetection> = detections.iter().map(detection_to_wasm).collect();
+        let (inner, buffers, downscale) = (&mut self.inner, &mut self.buffers, &mut self.downscale);
+        let wasm_dets = self.stats.time(|| downscale.detect(inner, &img, buffers));
 
         serde_wasm_bindgen::to_value(&wasm_dets).map_err(|e| JsError::new(&e.to_string()))
     }
@@ -177,30 +436,32 @@ impl Detector {
         width: u32,
         height: u32,
     ) -> Result<JsValue, JsError> {
-        let expected = (width * height * 4) as usize;
-        if data.len() != expected {
-            return Err(JsError::new(&format!(
-                "RGBA data length {} does not match {}x{}x4 = {}",
-                data.len(),
-                width,
-                height,
-                expected,
-            )));
-        }
-
-        self.gray_buf.clear();
-        self.gray_buf.extend(data.chunks_exact(4).map(|px| {
-            ((77u32 * px[0] as u32 + 150u32 * px[1] as u32 + 29u32 * px[2] as u32) >> 8) as u8
-        }));
+        check_rgba_len(data, width, height).map_err(|e| JsError::new(&e))?;
+        rgba_to_gray(data, &mut self.gray_buf);
 
         let img = ImageRef::new(width, height, width, &self.gray_buf);
-        let detections = self.inner.detect(&img, &mut self.buffers);
-
-        let wasm_dets: Vec<WasmDetection> = detections.iter().map(detection_to_wasm).collect();
+        let (inner, buffers, downscale) = (&mut self.inner, &mut self.buffers, &mut self.downscale);
+        let wasm_dets = self.stats.time(|| downscale.detect(inner, &img, buffers));
 
         serde_wasm_bindgen::to_value(&wasm_dets).map_err(|e| JsError::new(&e.to_string()))
     }
 
+    /// Change the maximum Hamming distance of the family named `family`.
+    #[wasm_bindgen(js_name = setMaxHamming)]
+    pub fn set_max_hamming(&mut self, family: &str, max_hamming: u32) -> Result<(), JsError> {
+        if self.inner.set_max_hamming(family, max_hamming) {
+            Ok(())
+        } else {
+            Err(JsError::new(&format!("family not in detector: {family}")))
+        }
+    }
+
+    /// Memory usage and detection timing since this detector was created.
+    #[wasm_bindgen(js_name = getDiagnostics)]
+    pub fn get_diagnostics(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.diagnostics()).map_err(|e| JsError::new(&e.to_string()))
+    }
+
     /// Estimate the pose of a detected tag.
     ///
     /// Returns the best pose (lowest reprojection error) from up to two candidates.
@@ -213,13 +474,21 @@ impl Detector {
         cx: f64,
         cy: f64,
     ) -> Result<JsValue, JsError> {
+        let corners = detection.corners.map(Into::into);
+        let homography = Homography::from_quad_corners(&corners)
+            .ok_or_else(|| JsError::new("detection corners are degenerate"))?;
         let core_det = CoreDetection {
             family_id: detection.family.as_str().into(),
             id: detection.id,
             hamming: detection.hamming,
             decision_margin: detection.decision_margin,
-            corners: detection.corners.map(Into::into),
+            corners,
             center: detection.center.into(),
+            homography,
+            mirrored: detection.mirrored,
+            line_fit_mse: detection.line_fit_mse,
+            refine_residual: detection.refine_residual,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         let params = PoseParams {
@@ -228,24 +497,203 @@ impl Detector {
             fy,
             cx,
             cy,
+            solver: Default::default(),
         };
 
         let (pose1, err1, pose2, err2) = estimate_tag_pose(&core_det, &params);
 
         let best_pose = if let Some(p2) = pose2 {
             if err2 < err1 {
-                pose_to_wasm(&p2, err2)
+                pose_to_wasm(&p2, err2, &params)
             } else {
-                pose_to_wasm(&pose1, err1)
+                pose_to_wasm(&pose1, err1, &params)
             }
         } else {
-            pose_to_wasm(&pose1, err1)
+            pose_to_wasm(&pose1, err1, &params)
         };
 
         serde_wasm_bindgen::to_value(&best_pose).map_err(|e| JsError::new(&e.to_string()))
     }
 }
 
+impl Detector {
+    fn diagnostics(&self) -> WasmDiagnostics {
+        self.stats.diagnostics(self.inner.decode_table_bytes())
+    }
+}
+
+/// Build a core detector from a JavaScript configuration.
+fn build_core_detector(config: &WasmDetectorConfig) -> Result<CoreDetector, String> {
+    let mut det_config = DetectorConfig::default();
+
+    if let Some(d) = config.quad_decimate {
+        det_config.quad_decimate = d;
+    }
+    if let Some(s) = config.quad_sigma {
+        det_config.quad_sigma = s;
+    }
+    if let Some(r) = config.refine_edges {
+        det_config.refine_edges = r;
+    }
+    if let Some(s) = config.decode_sharpening {
+        det_config.decode_sharpening = s;
+    }
+    if let Some(m) = config.min_decision_margin {
+        det_config.min_decision_margin = m;
+    }
+    if let Some(m) = config.decode_mirrored {
+        det_config.decode_mirrored = m;
+    }
+    if let Some(r) = config.refine_homography {
+        det_config.refine_homography = r;
+    }
+    if let Some(r) = config.refine_decode {
+        det_config.refine_decode = r;
+    }
+
+    // QuadParams overrides
+    if let Some(v) = config.min_cluster_pixels {
+        det_config.quad.min_cluster_pixels = Some(v);
+    }
+    if let Some(v) = config.max_nmaxima {
+        det_config.quad.max_nmaxima = v;
+    }
+    if let Some(v) = config.critical_angle_deg {
+        det_config.quad.critical_angle_deg = v;
+    }
+    if let Some(v) = config.max_line_fit_mse {
+        det_config.quad.max_line_fit_mse = Some(v);
+    }
+    if let Some(v) = config.min_white_black_diff {
+        det_config.quad.min_white_black_diff = v;
+    }
+    if let Some(v) = config.tile_size {
+        det_config.quad.tile_size = Some(v);
+    }
+    if let Some(v) = config.min_edge_gradient {
+        det_config.quad.min_edge_gradient = v;
+    }
+    if let Some(v) = config.deglitch {
+        det_config.quad.deglitch = v;
+    }
+    if let Some(v) = config.pixel_aspect_ratio {
+        det_config.quad.pixel_aspect_ratio = v;
+    }
+
+    let max_hamming = config.max_hamming.unwrap_or(2);
+    let mut builder = DetectorBuilder::with_config(det_config);
+
+    for spec in &config.families {
+        let (family_name, max_hamming) = match spec.split_once(':') {
+            Some((name, h)) => (
+                name,
+                h.parse::<u32>()
+                    .map_err(|_| format!("invalid Hamming distance in family '{spec}'"))?,
+            ),
+            None => (spec.as_str(), max_hamming),
+        };
+        builder = builder.family(family_name, max_hamming);
+    }
+    #[cfg(feature = "custom-families")]
+    for custom in &config.custom_families {
+        let fam = family::TagFamily::from_toml_and_bin(&custom.toml, &custom.bin)
+            .map_err(|e| format!("custom family: {e}"))?;
+        builder = builder.add_family(fam, custom.max_hamming.unwrap_or(max_hamming));
+    }
+
+    builder.try_build().map_err(|e| e.to_string())
+}
+
+/// Optional pre-detection downscale to a maximum frame dimension.
+struct Downscale {
+    max_dimension: Option<u32>,
+    buf: ImageU8,
+}
+
+impl Downscale {
+    fn new(max_dimension: Option<u32>) -> Self {
+        Self {
+            max_dimension,
+            buf: ImageU8::new(0, 0),
+        }
+    }
+
+    /// Run detection, resizing first if needed, and map the results back to
+    /// `img` coordinates.
+    fn detect(
+        &mut self,
+        detector: &mut CoreDetector,
+        img: &ImageRef,
+        buffers: &mut DetectorBuffers,
+    ) -> Vec<WasmDetection> {
+        let Some(max_dimension) = self.max_dimension else {
+            return detector
+                .detect(img, buffers)
+                .iter()
+                .map(detection_to_wasm)
+                .collect();
+        };
+        let (w, h) = max_dimension_size(img.width(), img.height(), max_dimension);
+        if (w, h) == (img.width(), img.height()) {
+            return detector
+                .detect(img, buffers)
+                .iter()
+                .map(detection_to_wasm)
+                .collect();
+        }
+
+        resize_into(img, w, h, ResizeFilter::Area, &mut self.buf);
+        let (sx, sy) = (
+            img.width() as f64 / w as f64,
+            img.height() as f64 / h as f64,
+        );
+        let mut detections = detector.detect(&self.buf, buffers);
+        detections
+            .iter_mut()
+            .map(|det| {
+                det.scale(sx, sy, detector.config.pixel_convention);
+                detection_to_wasm(det)
+            })
+            .collect()
+    }
+}
+
+fn check_gray_len(data: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let expected = (width * height) as usize;
+    if data.len() != expected {
+        return Err(format!(
+            "data length {} does not match {}x{} = {}",
+            data.len(),
+            width,
+            height,
+            expected,
+        ));
+    }
+    Ok(())
+}
+
+fn check_rgba_len(data: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let expected = (width * height * 4) as usize;
+    if data.len() != expected {
+        return Err(format!(
+            "RGBA data length {} does not match {}x{}x4 = {}",
+            data.len(),
+            width,
+            height,
+            expected,
+        ));
+    }
+    Ok(())
+}
+
+/// Convert RGBA pixels to grayscale (BT.601 luma, integer weights) into `out`.
+fn rgba_to_gray(data: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    out.extend(data.chunks_exact(4).map(|px| {
+        ((77u32 * px[0] as u32 + 150u32 * px[1] as u32 + 29u32 * px[2] as u32) >> 8) as u8
+    }));
+}
+
 fn detection_to_wasm(det: &CoreDetection) -> WasmDetection {
     WasmDetection {
         family: det.family_id.to_string(),
@@ -254,10 +702,13 @@ fn detection_to_wasm(det: &CoreDetection) -> WasmDetection {
         decision_margin: det.decision_margin,
         center: det.center.into(),
         corners: det.corners.map(Into::into),
+        mirrored: det.mirrored,
+        line_fit_mse: det.line_fit_mse,
+        refine_residual: det.refine_residual,
     }
 }
 
-fn pose_to_wasm(pose: &apriltag::detect::pose::Pose, error: f64) -> WasmPose {
+fn pose_to_wasm(pose: &apriltag::detect::pose::Pose, error: f64, params: &PoseParams) -> WasmPose {
     WasmPose {
         rotation: vec![
             pose.r[0][0],
@@ -272,5 +723,116 @@ fn pose_to_wasm(pose: &apriltag::detect::pose::Pose, error: f64) -> WasmPose {
         ],
         translation: pose.t,
         error,
+        axes: pose.axes_endpoints(params.tagsize / 2.0, params),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_family_has_info() {
+        let names = list_families();
+        #[cfg(feature = "family-tag36h11")]
+        assert!(names.iter().any(|n| n == "tag36h11"));
+        for name in &names {
+            let info = family_to_info(&family::builtin_family(name).unwrap());
+            assert_eq!(&info.name, name);
+            assert!(info.code_count > 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn tag36h11_info() {
+        let info = family_to_info(&family::builtin_family("tag36h11").unwrap());
+        assert_eq!(info.bits, 36);
+        assert_eq!(info.min_hamming, 11);
+        assert_eq!(info.code_count, 587);
+        assert_eq!(info.grid_size, 10);
+        assert_eq!(info.border_width, 8);
+        assert!(!info.reversed_border);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn families_take_their_own_max_hamming() {
+        let config = |families: &[&str]| WasmDetectorConfig {
+            families: families.iter().map(|f| f.to_string()).collect(),
+            ..WasmDetectorConfig::default()
+        };
+        let mut inner = build_core_detector(&config(&["tag36h11:0"])).unwrap();
+        assert!(inner.set_max_hamming("tag36h11", 1));
+        assert!(build_core_detector(&config(&["tag36h11:x"])).is_err());
+        assert!(build_core_detector(&config(&["tag99h1:0"])).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "custom-families", feature = "family-tag36h11"))]
+    fn custom_families_load_from_their_files() {
+        let families = concat!(env!("CARGO_MANIFEST_DIR"), "/../apriltag/families/");
+        let custom = |name: &str, max_hamming| WasmCustomFamily {
+            toml: std::fs::read_to_string(format!("{families}{name}.toml")).unwrap(),
+            bin: std::fs::read(format!("{families}{name}.bin")).unwrap(),
+            max_hamming,
+        };
+        let config = |custom_families| WasmDetectorConfig {
+            custom_families,
+            ..WasmDetectorConfig::default()
+        };
+        let mut inner = build_core_detector(&config(vec![custom("tag36h11", Some(0))])).unwrap();
+        assert!(inner.set_max_hamming("tag36h11", 1));
+
+        let no_codes = WasmCustomFamily {
+            bin: Vec::new(),
+            ..custom("tag36h11", None)
+        };
+        assert!(build_core_detector(&config(vec![no_codes])).is_err());
+    }
+
+    #[test]
+    fn frame_stats_track_timing_and_budget() {
+        let mut stats = FrameStats::new(Some(10.0));
+        let empty = stats.diagnostics(0);
+        assert_eq!(empty.frames, 0);
+        assert_eq!(empty.mean_detect_ms, None);
+
+        for ms in [4.0, 12.0, 8.0] {
+            stats.record(ms);
+        }
+        let diag = stats.diagnostics(1024);
+        assert_eq!(diag.frames, 3);
+        assert_eq!(diag.last_detect_ms, Some(8.0));
+        assert_eq!(diag.mean_detect_ms, Some(8.0));
+        assert_eq!(diag.max_detect_ms, Some(12.0));
+        assert_eq!(diag.frame_budget_ms, Some(10.0));
+        assert_eq!(diag.over_budget_frames, 1);
+        assert_eq!(diag.decode_table_bytes, 1024.0);
+    }
+
+    #[test]
+    fn frame_stats_time_closures() {
+        let mut stats = FrameStats::new(None);
+        assert_eq!(stats.time(|| 7), 7);
+        let diag = stats.diagnostics(0);
+        assert_eq!(diag.frames, 1);
+        assert!(diag.last_detect_ms.unwrap() >= 0.0);
+        assert_eq!(diag.over_budget_frames, 0);
+    }
+
+    #[test]
+    fn build_info_matches_features() {
+        let info = build_info();
+        assert_eq!(info.families, list_families());
+        assert_eq!(
+            info.families.is_empty(),
+            info.features.iter().all(|f| f == "custom-families")
+        );
+        assert_eq!(
+            info.features.iter().any(|f| f == "family-tag36h11"),
+            cfg!(feature = "family-tag36h11")
+        );
     }
 }
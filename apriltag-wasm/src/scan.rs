@@ -0,0 +1,474 @@
+//! Rate-limited scanning for camera streams.
+//!
+//! [`ScanController`] accepts every camera frame but only runs detection at a
+//! configurable maximum rate. Between detection runs it carries the last
+//! results forward (optionally extrapolating tag motion), and reports tags
+//! that appeared or were lost.
+
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::*;
+
+use apriltag::{Detector as CoreDetector, DetectorBuffers, ImageRef};
+
+use crate::{
+    build_core_detector, check_gray_len, check_rgba_len, rgba_to_gray, Downscale, WasmDetection,
+    WasmDetectorConfig,
+};
+
+/// Scan pacing configuration passed from JavaScript.
+#[derive(Tsify, Serialize, Deserialize, Default)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmScanConfig {
+    /// Maximum detection runs per second (default: 10).
+    #[serde(default)]
+    pub max_rate_hz: Option<f64>,
+    /// How long a tag may go undetected before it is reported lost (default:
+    /// 500 ms). Must be positive and finite.
+    #[serde(default)]
+    pub lost_timeout_ms: Option<f64>,
+    /// Extrapolate tag motion between detection runs (default: true).
+    #[serde(default)]
+    pub predict: Option<bool>,
+}
+
+/// Identifies a tag in appeared/lost events.
+#[derive(Tsify, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmTagKey {
+    pub family: String,
+    pub id: i32,
+}
+
+/// Per-frame scan output returned to JavaScript.
+#[derive(Tsify, Serialize, Deserialize, Debug)]
+#[tsify(into_wasm_abi)]
+pub struct WasmScanResult {
+    /// Current estimate of every tracked tag, fresh or carried over.
+    pub detections: Vec<WasmDetection>,
+    /// Whether detection actually ran on this frame.
+    pub detected: bool,
+    /// Tags first seen on this frame.
+    pub appeared: Vec<WasmTagKey>,
+    /// Tags dropped on this frame after exceeding the lost timeout.
+    pub lost: Vec<WasmTagKey>,
+}
+
+/// Camera-rate frame sink that runs detection at a bounded rate.
+#[wasm_bindgen]
+pub struct ScanController {
+    detector: CoreDetector,
+    buffers: DetectorBuffers,
+    gray_buf: Vec<u8>,
+    downscale: Downscale,
+    tracker: Tracker,
+}
+
+#[wasm_bindgen]
+impl ScanController {
+    /// Create a scan controller with the given detector and pacing configuration.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        config: WasmDetectorConfig,
+        scan: Option<WasmScanConfig>,
+    ) -> Result<ScanController, JsError> {
+        let tracker =
+            Tracker::from_config(&scan.unwrap_or_default()).map_err(|e| JsError::new(&e))?;
+
+        Ok(ScanController {
+            detector: build_core_detector(&config).map_err(|e| JsError::new(&e))?,
+            buffers: DetectorBuffers::new(),
+            gray_buf: Vec::new(),
+            downscale: Downscale::new(config.max_dimension),
+            tracker,
+        })
+    }
+
+    /// Submit a grayscale frame (one byte per pixel) captured at `timestamp_ms`.
+    pub fn push_frame(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        timestamp_ms: f64,
+    ) -> Result<JsValue, JsError> {
+        check_gray_len(data, width, height).map_err(|e| JsError::new(&e))?;
+
+        if !self.tracker.due(timestamp_ms) {
+            return to_js(&self.tracker.carry(timestamp_ms));
+        }
+
+        let img = ImageRef::new(width, height, width, data);
+        let wasm_dets = self
+            .downscale
+            .detect(&mut self.detector, &img, &mut self.buffers);
+        to_js(&self.tracker.update(timestamp_ms, wasm_dets))
+    }
+
+    /// Submit an RGBA frame (4 bytes per pixel) captured at `timestamp_ms`.
+    ///
+    /// Skipped frames are not converted to grayscale.
+    pub fn push_frame_rgba(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        timestamp_ms: f64,
+    ) -> Result<JsValue, JsError> {
+        check_rgba_len(data, width, height).map_err(|e| JsError::new(&e))?;
+
+        if !self.tracker.due(timestamp_ms) {
+            return to_js(&self.tracker.carry(timestamp_ms));
+        }
+
+        rgba_to_gray(data, &mut self.gray_buf);
+        let img = ImageRef::new(width, height, width, &self.gray_buf);
+        let wasm_dets = self
+            .downscale
+            .detect(&mut self.detector, &img, &mut self.buffers);
+        to_js(&self.tracker.update(timestamp_ms, wasm_dets))
+    }
+
+    /// Forget all tracked tags; the next frame runs detection immediately.
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+    }
+}
+
+fn to_js(result: &WasmScanResult) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(result).map_err(|e| JsError::new(&e.to_string()))
+}
+
+// ── Tracking state (independent of wasm-bindgen) ──
+
+struct Track {
+    /// Most recent detection of this tag.
+    det: WasmDetection,
+    seen_ms: f64,
+    /// Corner velocity in pixels per millisecond, from the last two sightings.
+    velocity: Option<[[f64; 2]; 4]>,
+}
+
+impl Track {
+    fn key(&self) -> WasmTagKey {
+        WasmTagKey {
+            family: self.det.family.clone(),
+            id: self.det.id,
+        }
+    }
+
+    fn matches(&self, det: &WasmDetection) -> bool {
+        self.det.id == det.id && self.det.family == det.family
+    }
+
+    /// Squared distance between the track's last center and `det`'s.
+    fn distance_sq(&self, det: &WasmDetection) -> f64 {
+        let [dx, dy] = [0, 1].map(|k| self.det.center[k] - det.center[k]);
+        dx * dx + dy * dy
+    }
+
+    /// Position estimate at `now_ms`, extrapolating for at most `horizon_ms`.
+    fn predict(&self, now_ms: f64, horizon_ms: f64) -> WasmDetection {
+        let Some(v) = self.velocity else {
+            return self.det.clone();
+        };
+        let dt = (now_ms - self.seen_ms).clamp(0.0, horizon_ms);
+        let mut det = self.det.clone();
+        for (c, vc) in det.corners.iter_mut().zip(v) {
+            c[0] += vc[0] * dt;
+            c[1] += vc[1] * dt;
+        }
+        let n = det.corners.len() as f64;
+        det.center = [
+            det.corners.iter().map(|c| c[0]).sum::<f64>() / n,
+            det.corners.iter().map(|c| c[1]).sum::<f64>() / n,
+        ];
+        det
+    }
+}
+
+/// Frame pacing and appeared/lost bookkeeping.
+struct Tracker {
+    interval_ms: f64,
+    lost_timeout_ms: f64,
+    predict: bool,
+    last_run_ms: Option<f64>,
+    tracks: Vec<Track>,
+}
+
+impl Tracker {
+    fn new(interval_ms: f64, lost_timeout_ms: f64, predict: bool) -> Self {
+        Self {
+            interval_ms,
+            lost_timeout_ms,
+            predict,
+            last_run_ms: None,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// A tracker paced by `scan`, with its defaults for unset fields.
+    fn from_config(scan: &WasmScanConfig) -> Result<Self, String> {
+        let max_rate_hz = scan.max_rate_hz.unwrap_or(10.0);
+        if max_rate_hz.is_nan() || max_rate_hz <= 0.0 {
+            return Err(format!("max_rate_hz must be positive, got {max_rate_hz}"));
+        }
+        let lost_timeout_ms = scan.lost_timeout_ms.unwrap_or(500.0);
+        if !lost_timeout_ms.is_finite() || lost_timeout_ms <= 0.0 {
+            return Err(format!(
+                "lost_timeout_ms must be positive and finite, got {lost_timeout_ms}"
+            ));
+        }
+        Ok(Self::new(
+            1000.0 / max_rate_hz,
+            lost_timeout_ms,
+            scan.predict.unwrap_or(true),
+        ))
+    }
+
+    fn reset(&mut self) {
+        self.last_run_ms = None;
+        self.tracks.clear();
+    }
+
+    /// Whether detection should run on a frame at `now_ms`.
+    ///
+    /// A timestamp earlier than the last run (e.g. a restarted clock) also
+    /// triggers detection.
+    fn due(&self, now_ms: f64) -> bool {
+        match self.last_run_ms {
+            None => true,
+            Some(last) => now_ms - last >= self.interval_ms || now_ms < last,
+        }
+    }
+
+    /// Result for a skipped frame: carry tracked tags forward.
+    fn carry(&self, now_ms: f64) -> WasmScanResult {
+        WasmScanResult {
+            detections: self.estimates(now_ms),
+            detected: false,
+            appeared: Vec::new(),
+            lost: Vec::new(),
+        }
+    }
+
+    /// Fold a fresh detection run into the tracks.
+    fn update(&mut self, now_ms: f64, detections: Vec<WasmDetection>) -> WasmScanResult {
+        self.last_run_ms = Some(now_ms);
+
+        let mut appeared = Vec::new();
+        let mut seen = vec![false; self.tracks.len()];
+
+        for det in detections {
+            // Several prints of one tag each keep their own track: match the
+            // nearest track not yet updated this run.
+            let nearest = self
+                .tracks
+                .iter()
+                .enumerate()
+                .filter(|&(i, t)| !seen[i] && t.matches(&det))
+                .min_by(|(_, a), (_, b)| a.distance_sq(&det).total_cmp(&b.distance_sq(&det)))
+                .map(|(i, _)| i);
+            match nearest {
+                Some(i) => {
+                    let track = &mut self.tracks[i];
+                    let dt = now_ms - track.seen_ms;
+                    track.velocity = (dt > 0.0).then(|| {
+                        let mut v = [[0.0; 2]; 4];
+                        for ((vc, new), old) in
+                            v.iter_mut().zip(&det.corners).zip(&track.det.corners)
+                        {
+                            *vc = [(new[0] - old[0]) / dt, (new[1] - old[1]) / dt];
+                        }
+                        v
+                    });
+                    track.det = det;
+                    track.seen_ms = now_ms;
+                    seen[i] = true;
+                }
+                None => {
+                    let track = Track {
+                        det,
+                        seen_ms: now_ms,
+                        velocity: None,
+                    };
+                    appeared.push(track.key());
+                    self.tracks.push(track);
+                    seen.push(true);
+                }
+            }
+        }
+
+        // Tracks not seen this run keep coasting until the lost timeout.
+        let mut lost = Vec::new();
+        let timeout = self.lost_timeout_ms;
+        let mut idx = 0;
+        self.tracks.retain(|t| {
+            let keep = seen[idx] || now_ms - t.seen_ms <= timeout;
+            idx += 1;
+            if !keep {
+                lost.push(t.key());
+            }
+            keep
+        });
+
+        WasmScanResult {
+            detections: self.estimates(now_ms),
+            detected: true,
+            appeared,
+            lost,
+        }
+    }
+
+    fn estimates(&self, now_ms: f64) -> Vec<WasmDetection> {
+        self.tracks
+            .iter()
+            .map(|t| {
+                if self.predict {
+                    t.predict(now_ms, self.interval_ms)
+                } else {
+                    t.det.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn det(id: i32, x: f64) -> WasmDetection {
+        WasmDetection {
+            family: "tag36h11".to_string(),
+            id,
+            hamming: 0,
+            decision_margin: 50.0,
+            center: [x, 10.0],
+            corners: [
+                [x - 5.0, 5.0],
+                [x + 5.0, 5.0],
+                [x + 5.0, 15.0],
+                [x - 5.0, 15.0],
+            ],
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+        }
+    }
+
+    fn key(id: i32) -> WasmTagKey {
+        WasmTagKey {
+            family: "tag36h11".to_string(),
+            id,
+        }
+    }
+
+    #[test]
+    fn scan_config_is_validated() {
+        let config = |max_rate_hz, lost_timeout_ms| WasmScanConfig {
+            max_rate_hz,
+            lost_timeout_ms,
+            predict: None,
+        };
+        let t = Tracker::from_config(&WasmScanConfig::default()).unwrap();
+        assert_eq!(
+            (t.interval_ms, t.lost_timeout_ms, t.predict),
+            (100.0, 500.0, true)
+        );
+        let t = Tracker::from_config(&config(Some(20.0), Some(1000.0))).unwrap();
+        assert_eq!((t.interval_ms, t.lost_timeout_ms), (50.0, 1000.0));
+
+        for rate in [0.0, -5.0, f64::NAN] {
+            assert!(Tracker::from_config(&config(Some(rate), None)).is_err());
+        }
+        for timeout in [0.0, -100.0, f64::NAN, f64::INFINITY] {
+            let err = Tracker::from_config(&config(None, Some(timeout)))
+                .err()
+                .unwrap();
+            assert!(err.contains("lost_timeout_ms"), "{err}");
+        }
+    }
+
+    #[test]
+    fn detection_is_rate_limited() {
+        let mut t = Tracker::new(100.0, 500.0, false);
+        assert!(t.due(0.0));
+        t.update(0.0, vec![det(1, 10.0)]);
+        assert!(!t.due(50.0));
+        assert!(t.due(100.0));
+        // A clock that jumps backwards forces a run
+        assert!(t.due(-10.0));
+    }
+
+    #[test]
+    fn appeared_then_lost_after_timeout() {
+        let mut t = Tracker::new(100.0, 250.0, false);
+        let r = t.update(0.0, vec![det(1, 10.0)]);
+        assert_eq!(r.appeared, vec![key(1)]);
+
+        // Still tracked while within the timeout, and not re-announced
+        let r = t.update(200.0, vec![]);
+        assert!(r.lost.is_empty());
+        assert_eq!(r.detections.len(), 1);
+        let r = t.update(220.0, vec![det(1, 10.0)]);
+        assert!(r.appeared.is_empty());
+
+        let r = t.update(500.0, vec![]);
+        assert_eq!(r.lost, vec![key(1)]);
+        assert!(r.detections.is_empty());
+    }
+
+    #[test]
+    fn same_id_twice_in_one_frame_keeps_two_tracks() {
+        let mut t = Tracker::new(100.0, 250.0, false);
+        let r = t.update(0.0, vec![det(1, 10.0), det(1, 100.0)]);
+        assert_eq!(r.appeared, vec![key(1), key(1)]);
+        assert_eq!(r.detections.len(), 2);
+
+        // Each print follows its nearest track, listed in either order
+        let r = t.update(100.0, vec![det(1, 102.0), det(1, 12.0)]);
+        assert!(r.appeared.is_empty() && r.lost.is_empty());
+        let mut xs: Vec<f64> = r.detections.iter().map(|d| d.center[0]).collect();
+        xs.sort_by(f64::total_cmp);
+        assert_eq!(xs, [12.0, 102.0]);
+
+        // One print leaves; its track is lost after the timeout
+        t.update(200.0, vec![det(1, 14.0)]);
+        let r = t.update(400.0, vec![det(1, 16.0)]);
+        assert_eq!(r.lost, vec![key(1)]);
+        assert_eq!(r.detections.len(), 1);
+    }
+
+    #[test]
+    fn skipped_frames_reuse_last_result() {
+        let mut t = Tracker::new(100.0, 500.0, false);
+        t.update(0.0, vec![det(3, 10.0)]);
+        let r = t.carry(40.0);
+        assert!(!r.detected);
+        assert_eq!(r.detections, vec![det(3, 10.0)]);
+    }
+
+    #[test]
+    fn prediction_extrapolates_motion() {
+        let mut t = Tracker::new(100.0, 500.0, true);
+        t.update(0.0, vec![det(3, 10.0)]);
+        t.update(100.0, vec![det(3, 20.0)]);
+        // Moving 0.1 px/ms: halfway to the next run adds 5 px
+        let r = t.carry(150.0);
+        assert!((r.detections[0].center[0] - 25.0).abs() < 1e-9);
+        // Extrapolation is capped at one detection interval
+        let r = t.carry(1000.0);
+        assert!((r.detections[0].center[0] - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reset_forgets_tracks() {
+        let mut t = Tracker::new(100.0, 500.0, false);
+        t.update(0.0, vec![det(1, 10.0)]);
+        t.reset();
+        assert!(t.due(10.0));
+        let r = t.update(10.0, vec![det(1, 10.0)]);
+        assert_eq!(r.appeared.len(), 1);
+    }
+}
@@ -0,0 +1,372 @@
+//! Detection inside a Web Worker.
+//!
+//! [`init_worker`] turns the calling worker into a detection service with a
+//! fixed message protocol, so pages can keep detection off the main thread
+//! without designing one. Frames travel as transferable `ArrayBuffer`s and
+//! are handed back with each response, so a page can recycle a small pool of
+//! buffers without copying them between threads.
+//!
+//! ```js
+//! // worker.js
+//! import init, { initWorker } from "./apriltag_wasm.js";
+//! await init();
+//! initWorker(); // posts { type: "ready" }
+//!
+//! // main.js
+//! const worker = new Worker("worker.js", { type: "module" });
+//! worker.postMessage({ type: "configure", config: { families: ["tag36h11"] } });
+//! const frame = new Uint8Array(width * height); // grayscale pixels
+//! worker.postMessage(
+//!     { type: "detect", id: 1, width, height, format: "gray", data: frame.buffer },
+//!     [frame.buffer],
+//! );
+//! worker.onmessage = ({ data: msg }) => {
+//!     if (msg.type === "detections") draw(msg.id, msg.detections); // msg.data is the frame
+//!     if (msg.type === "error") console.error(msg.id, msg.message); // so is msg.data
+//! };
+//! ```
+//!
+//! Messages posted before the worker reports `ready` are lost, since the
+//! handler is only installed once the module has loaded.
+
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::*;
+
+use apriltag::{Detector as CoreDetector, DetectorBuffers, ImageRef};
+
+use crate::{
+    build_core_detector, check_gray_len, check_rgba_len, rgba_to_gray, Downscale, WasmDetection,
+    WasmDetectorConfig,
+};
+
+/// Pixel layout of a frame sent to the worker.
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmFrameFormat {
+    /// One byte per pixel.
+    Gray,
+    /// Four bytes per pixel, e.g. `ImageData.data`.
+    Rgba,
+}
+
+/// A message from the page to a worker started with [`init_worker`].
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmWorkerRequest {
+    /// Create (or replace) the worker's detector. Answered with
+    /// `configured`, or `error` without an `id`.
+    Configure { config: WasmDetectorConfig },
+    /// Detect tags in a frame. Answered with `detections` or `error`
+    /// carrying the same `id`.
+    Detect {
+        id: u32,
+        width: u32,
+        height: u32,
+        format: WasmFrameFormat,
+        /// Pixel data: an `ArrayBuffer` (transfer it) or a typed array view.
+        #[tsify(type = "ArrayBuffer | ArrayBufferView")]
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        data: JsValue,
+    },
+}
+
+/// A message from a worker started with [`init_worker`] to the page.
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmWorkerResponse {
+    /// The worker is listening for requests.
+    Ready,
+    /// The detector was (re)created.
+    Configured,
+    /// Detections for the frame with the given `id`.
+    Detections {
+        id: u32,
+        detections: Vec<WasmDetection>,
+        /// The request's frame buffer, transferred back for reuse.
+        #[tsify(type = "ArrayBuffer")]
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        data: JsValue,
+    },
+    /// A request failed. `id` is set for `detect` requests, and `data`
+    /// is then the request's frame buffer, transferred back as with
+    /// `detections`.
+    Error {
+        id: Option<u32>,
+        message: String,
+        #[tsify(type = "ArrayBuffer | undefined")]
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        data: JsValue,
+    },
+}
+
+/// Turn the calling Web Worker into a detection service.
+///
+/// Installs an `onmessage` handler that answers [`WasmWorkerRequest`]s with
+/// [`WasmWorkerResponse`]s, then posts `ready`. See the
+/// [module documentation](self) for the protocol.
+#[wasm_bindgen(js_name = initWorker)]
+pub fn init_worker() -> Result<(), JsError> {
+    let scope = js_sys::global();
+    if !js_sys::Reflect::has(&scope, &"importScripts".into()).unwrap_or(false) {
+        return Err(JsError::new(
+            "initWorker must be called inside a Web Worker",
+        ));
+    }
+    let post: js_sys::Function = js_sys::Reflect::get(&scope, &"postMessage".into())
+        .ok()
+        .and_then(|f| f.dyn_into().ok())
+        .ok_or_else(|| JsError::new("worker scope has no postMessage"))?;
+
+    let mut state = WorkerState::default();
+    let (handler_post, target) = (post.clone(), scope.clone());
+    let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let message = js_sys::Reflect::get(&event, &"data".into()).unwrap_or(JsValue::UNDEFINED);
+        let response = state.handle(message);
+        post_response(&handler_post, &target, &response);
+    });
+    js_sys::Reflect::set(&scope, &"onmessage".into(), handler.as_ref())
+        .map_err(|_| JsError::new("failed to install the worker message handler"))?;
+    // The handler lives as long as the worker.
+    handler.forget();
+
+    post_response(&post, &scope, &WasmWorkerResponse::Ready);
+    Ok(())
+}
+
+/// Build a `detect` request and its transfer list, for
+/// `worker.postMessage(...workerDetectRequest(...))`.
+///
+/// `data` may be an `ArrayBuffer` or a typed array; its whole underlying
+/// buffer is transferred, so the page must not use it until the worker hands
+/// it back.
+#[wasm_bindgen(js_name = workerDetectRequest)]
+pub fn worker_detect_request(
+    id: u32,
+    width: u32,
+    height: u32,
+    format: WasmFrameFormat,
+    data: JsValue,
+) -> Result<js_sys::Array, JsError> {
+    let transfer = js_sys::Array::new();
+    if let Some(buffer) = underlying_buffer(&data) {
+        transfer.push(&buffer);
+    }
+    let request = WasmWorkerRequest::Detect {
+        id,
+        width,
+        height,
+        format,
+        data,
+    };
+    let message =
+        serde_wasm_bindgen::to_value(&request).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(js_sys::Array::of2(&message, &transfer))
+}
+
+fn post_response(post: &js_sys::Function, scope: &JsValue, response: &WasmWorkerResponse) {
+    let transfer = js_sys::Array::new();
+    if let WasmWorkerResponse::Detections { data, .. } | WasmWorkerResponse::Error { data, .. } =
+        response
+    {
+        if let Some(buffer) = underlying_buffer(data) {
+            transfer.push(&buffer);
+        }
+    }
+    let message = serde_wasm_bindgen::to_value(response).unwrap_or_else(|e| {
+        serde_wasm_bindgen::to_value(&WasmWorkerResponse::Error {
+            id: None,
+            message: e.to_string(),
+            data: JsValue::UNDEFINED,
+        })
+        .unwrap_or(JsValue::NULL)
+    });
+    // A failed post has nobody left to report to.
+    let _ = post.call2(scope, &message, &transfer);
+}
+
+/// The `ArrayBuffer` itself, or the buffer behind a typed array view.
+fn underlying_buffer(data: &JsValue) -> Option<js_sys::ArrayBuffer> {
+    if let Some(buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+        return Some(buffer.clone());
+    }
+    if js_sys::ArrayBuffer::is_view(data) {
+        return js_sys::Reflect::get(data, &"buffer".into())
+            .ok()
+            .and_then(|b| b.dyn_into().ok());
+    }
+    None
+}
+
+/// Copy the bytes of an `ArrayBuffer` or typed array view into `out`.
+fn copy_bytes(data: &JsValue, out: &mut Vec<u8>) -> Result<(), String> {
+    let bytes = if data.is_instance_of::<js_sys::ArrayBuffer>() {
+        js_sys::Uint8Array::new(data)
+    } else if js_sys::ArrayBuffer::is_view(data) {
+        let field = |name: &str| {
+            js_sys::Reflect::get(data, &name.into())
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0) as u32
+        };
+        let buffer = underlying_buffer(data).ok_or("typed array has no buffer")?;
+        js_sys::Uint8Array::new_with_byte_offset_and_length(
+            &buffer,
+            field("byteOffset"),
+            field("byteLength"),
+        )
+    } else {
+        return Err("frame data must be an ArrayBuffer or typed array".to_string());
+    };
+    out.resize(bytes.length() as usize, 0);
+    bytes.copy_to(out);
+    Ok(())
+}
+
+// ── Worker state (independent of wasm-bindgen) ──
+
+/// The worker's detector and scratch buffers.
+#[derive(Default)]
+struct WorkerState {
+    detector: Option<(CoreDetector, Downscale)>,
+    buffers: DetectorBuffers,
+    frame: Vec<u8>,
+    gray_buf: Vec<u8>,
+}
+
+impl WorkerState {
+    fn handle(&mut self, message: JsValue) -> WasmWorkerResponse {
+        let request: WasmWorkerRequest = match serde_wasm_bindgen::from_value(message) {
+            Ok(request) => request,
+            Err(e) => {
+                return WasmWorkerResponse::Error {
+                    id: None,
+                    message: format!("invalid request: {e}"),
+                    data: JsValue::UNDEFINED,
+                }
+            }
+        };
+        match request {
+            WasmWorkerRequest::Configure { config } => match self.configure(&config) {
+                Ok(()) => WasmWorkerResponse::Configured,
+                Err(message) => WasmWorkerResponse::Error {
+                    id: None,
+                    message,
+                    data: JsValue::UNDEFINED,
+                },
+            },
+            WasmWorkerRequest::Detect {
+                id,
+                width,
+                height,
+                format,
+                data,
+            } => {
+                let mut frame = std::mem::take(&mut self.frame);
+                let result = copy_bytes(&data, &mut frame)
+                    .and_then(|()| self.detect(&frame, width, height, format));
+                self.frame = frame;
+                // The frame goes back whether or not detection succeeded
+                let data = underlying_buffer(&data).map_or(data, Into::into);
+                match result {
+                    Ok(detections) => WasmWorkerResponse::Detections {
+                        id,
+                        detections,
+                        data,
+                    },
+                    Err(message) => WasmWorkerResponse::Error {
+                        id: Some(id),
+                        message,
+                        data,
+                    },
+                }
+            }
+        }
+    }
+
+    fn configure(&mut self, config: &WasmDetectorConfig) -> Result<(), String> {
+        let detector = build_core_detector(config)?;
+        self.detector = Some((detector, Downscale::new(config.max_dimension)));
+        Ok(())
+    }
+
+    fn detect(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        format: WasmFrameFormat,
+    ) -> Result<Vec<WasmDetection>, String> {
+        let (detector, downscale) = self
+            .detector
+            .as_mut()
+            .ok_or("detect sent before configure")?;
+        let pixels = match format {
+            WasmFrameFormat::Gray => {
+                check_gray_len(data, width, height)?;
+                data
+            }
+            WasmFrameFormat::Rgba => {
+                check_rgba_len(data, width, height)?;
+                rgba_to_gray(data, &mut self.gray_buf);
+                &self.gray_buf
+            }
+        };
+        let img = ImageRef::new(width, height, width, pixels);
+        Ok(downscale.detect(detector, &img, &mut self.buffers))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn config(families: &[&str]) -> WasmDetectorConfig {
+        WasmDetectorConfig {
+            families: families.iter().map(|f| f.to_string()).collect(),
+            ..WasmDetectorConfig::default()
+        }
+    }
+
+    #[test]
+    fn detect_requires_configure() {
+        let mut state = WorkerState::default();
+        let err = state
+            .detect(&[0; 16], 4, 4, WasmFrameFormat::Gray)
+            .unwrap_err();
+        assert!(err.contains("configure"), "{err}");
+
+        state.configure(&config(&[])).unwrap();
+        assert!(state
+            .detect(&[0; 16], 4, 4, WasmFrameFormat::Gray)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn detect_checks_frame_length_per_format() {
+        let mut state = WorkerState::default();
+        state.configure(&config(&[])).unwrap();
+        assert!(state
+            .detect(&[0; 16], 4, 4, WasmFrameFormat::Rgba)
+            .unwrap_err()
+            .contains("RGBA"));
+        assert!(state
+            .detect(&[0; 64], 4, 4, WasmFrameFormat::Rgba)
+            .unwrap()
+            .is_empty());
+        assert!(state.detect(&[0; 15], 4, 4, WasmFrameFormat::Gray).is_err());
+    }
+
+    #[test]
+    fn configure_rejects_unknown_family() {
+        let mut state = WorkerState::default();
+        let err = state.configure(&config(&["tag99h99"])).unwrap_err();
+        assert!(err.contains("tag99h99"), "{err}");
+        assert!(state.detector.is_none());
+    }
+}
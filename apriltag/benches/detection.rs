@@ -275,6 +275,9 @@ fn bench_refine_edges(c: &mut Criterion) {
                     Vec2([x, y]),
                 ],
                 reversed_border: false,
+                line_fit_mse: 0.0,
+                refine_residual: None,
+                corner_covariance: [[0.0; 3]; 4],
             });
             x += 70.0;
         }
@@ -432,7 +435,7 @@ fn build_multi_tag_image() -> ImageU8 {
 
 fn bench_end_to_end_multi(c: &mut Criterion) {
     let img = build_multi_tag_image();
-    let mut detector = Detector::builder()
+    let detector = Detector::builder()
         .quad_sigma(0.8)
         .add_family(family::tag36h11(), 2)
         .build();
@@ -452,7 +455,7 @@ fn bench_end_to_end_multi(c: &mut Criterion) {
 
 fn bench_end_to_end(c: &mut Criterion) {
     let img = build_bench_image();
-    let mut detector = Detector::builder()
+    let detector = Detector::builder()
         .quad_sigma(0.8)
         .add_family(family::tag36h11(), 2)
         .build();
@@ -469,7 +472,7 @@ fn bench_end_to_end(c: &mut Criterion) {
 
 fn bench_end_to_end_reuse(c: &mut Criterion) {
     let img = build_bench_image();
-    let mut detector = Detector::builder()
+    let detector = Detector::builder()
         .quad_sigma(0.8)
         .add_family(family::tag36h11(), 2)
         .build();
@@ -567,13 +570,13 @@ fn build_highres_image() -> ImageU8 {
 
                     // Apply inverse perspective (approximate: scale x by row)
                     let row_scale = if ly <= 0.0 {
-                        top_scale as f64 + (1.0 + ly) * (1.0 - top_scale as f64)
+                        top_scale + (1.0 + ly) * (1.0 - top_scale)
                     } else {
-                        1.0 + ly * (bot_scale as f64 - 1.0)
+                        1.0 + ly * (bot_scale - 1.0)
                     };
                     let lx_corr = lx / row_scale;
 
-                    if lx_corr < -1.0 || lx_corr > 1.0 || ly < -1.0 || ly > 1.0 {
+                    if !(-1.0..=1.0).contains(&lx_corr) || !(-1.0..=1.0).contains(&ly) {
                         continue;
                     }
 
@@ -640,7 +643,7 @@ fn build_highres_image() -> ImageU8 {
 
 fn bench_end_to_end_highres(c: &mut Criterion) {
     let img = build_highres_image();
-    let mut detector = Detector::builder()
+    let detector = Detector::builder()
         .quad_sigma(0.8)
         .add_family(family::tag36h11(), 2)
         .build();
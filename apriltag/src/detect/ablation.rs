@@ -0,0 +1,162 @@
+//! Single-stage changes to the detection pipeline, for ablation studies.
+//!
+//! Each [`Ablation`] skips one stage or swaps it for its alternative
+//! implementation by editing a [`DetectorConfig`], so that a difference in
+//! quality or speed between two runs can be attributed to that stage alone.
+//!
+//! ```
+//! use apriltag::{Ablation, DetectorConfig};
+//!
+//! let mut config = DetectorConfig::default();
+//! Ablation::from_name("no-refine-edges").unwrap().apply(&mut config);
+//! assert!(!config.refine_edges);
+//! ```
+
+use super::detector::DetectorConfig;
+use super::preprocess::DecimateMode;
+
+/// Rows per band of [`Ablation::Banded`].
+const BAND_ROWS: u32 = 64;
+
+/// One change to a stage of the detection pipeline; see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Ablation {
+    /// Detect at full resolution ([`DetectorConfig::quad_decimate`] 1).
+    NoDecimation,
+    /// Decimate by averaging each block instead of point sampling
+    /// ([`DecimateMode::Average`]).
+    AverageDecimation,
+    /// Skip the Gaussian blur or sharpening before thresholding
+    /// ([`DetectorConfig::quad_sigma`] 0).
+    ///
+    /// The default config does not blur, so this only changes configs that
+    /// set `quad_sigma`, and is left out of [`DEFAULT`](Self::DEFAULT).
+    NoBlur,
+    /// Deglitch the thresholded image
+    /// ([`QuadParams::deglitch`](super::quad::QuadParams::deglitch)).
+    Deglitch,
+    /// Threshold, segment and cluster 64 rows at a time instead of through
+    /// whole-image intermediates ([`DetectorConfig::band_rows`]).
+    Banded,
+    /// Skip edge refinement ([`DetectorConfig::refine_edges`] off).
+    NoRefineEdges,
+    /// Sample bits without sharpening
+    /// ([`DetectorConfig::decode_sharpening`] 0).
+    NoSharpening,
+    /// Refine each decoded tag's homography from its cell edges
+    /// ([`DetectorConfig::refine_homography`]).
+    RefineHomography,
+    /// Align each decoded tag's bit pattern with the image
+    /// ([`DetectorConfig::refine_decode`]).
+    RefineDecode,
+}
+
+impl Ablation {
+    /// Every ablation, in pipeline order.
+    pub const ALL: [Ablation; 9] = [
+        Self::NoDecimation,
+        Self::AverageDecimation,
+        Self::NoBlur,
+        Self::Deglitch,
+        Self::Banded,
+        Self::NoRefineEdges,
+        Self::NoSharpening,
+        Self::RefineHomography,
+        Self::RefineDecode,
+    ];
+
+    /// The ablations that change the default config: every one but
+    /// [`NoBlur`](Self::NoBlur), in pipeline order.
+    pub const DEFAULT: [Ablation; 8] = [
+        Self::NoDecimation,
+        Self::AverageDecimation,
+        Self::Deglitch,
+        Self::Banded,
+        Self::NoRefineEdges,
+        Self::NoSharpening,
+        Self::RefineHomography,
+        Self::RefineDecode,
+    ];
+
+    /// Short kebab-case name, suitable for command-line flags and reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NoDecimation => "no-decimation",
+            Self::AverageDecimation => "average-decimation",
+            Self::NoBlur => "no-blur",
+            Self::Deglitch => "deglitch",
+            Self::Banded => "banded",
+            Self::NoRefineEdges => "no-refine-edges",
+            Self::NoSharpening => "no-sharpening",
+            Self::RefineHomography => "refine-homography",
+            Self::RefineDecode => "refine-decode",
+        }
+    }
+
+    /// The ablation with [`name`](Self::name) `name`, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// Apply the change to `config`. Applying it to a config that already
+    /// has it leaves the config unchanged.
+    pub fn apply(self, config: &mut DetectorConfig) {
+        match self {
+            Self::NoDecimation => config.quad_decimate = 1.0,
+            Self::AverageDecimation => config.decimate_mode = DecimateMode::Average,
+            Self::NoBlur => config.quad_sigma = 0.0,
+            Self::Deglitch => config.quad.deglitch = true,
+            Self::Banded => config.band_rows = Some(BAND_ROWS),
+            Self::NoRefineEdges => config.refine_edges = false,
+            Self::NoSharpening => config.decode_sharpening = 0.0,
+            Self::RefineHomography => config.refine_homography = true,
+            Self::RefineDecode => config.refine_decode = true,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for ablation in Ablation::ALL {
+            assert_eq!(Ablation::from_name(ablation.name()), Some(ablation));
+        }
+        assert_eq!(Ablation::from_name("no-such-stage"), None);
+    }
+
+    #[test]
+    fn default_ablations_are_those_that_change_the_default_config() {
+        // The default config does not blur, so only `no-blur` leaves it as is
+        let default = format!("{:?}", DetectorConfig::default());
+        for ablation in Ablation::ALL {
+            let mut config = DetectorConfig::default();
+            ablation.apply(&mut config);
+            assert!(config.validate().is_ok());
+            let changed = format!("{config:?}") != default;
+            assert_eq!(
+                changed,
+                Ablation::DEFAULT.contains(&ablation),
+                "{}",
+                ablation.name()
+            );
+        }
+        assert!(!Ablation::DEFAULT.contains(&Ablation::NoBlur));
+    }
+
+    #[test]
+    fn no_blur_changes_a_blurring_config() {
+        let mut config = DetectorConfig {
+            quad_sigma: 0.8,
+            ..DetectorConfig::default()
+        };
+        Ablation::NoBlur.apply(&mut config);
+        assert_eq!(config.quad_sigma, 0.0);
+    }
+}
@@ -0,0 +1,708 @@
+use super::cluster::{
+    filter_row, mask_row, Cluster, ClusterMap, EdgeFilter, Pt, DOWN, DOWN_LEFT, DOWN_RIGHT,
+    MIN_COMPONENT_SIZE, RIGHT, WHITE,
+};
+use super::image::{GrayImage, ImageRef, ImageU8};
+use super::threshold::{
+    binarize_rows, deglitch_image, exclude_region, tile_extrema, ThresholdBuffers,
+};
+
+const NONE: u32 = u32::MAX;
+
+/// Union-find over provisional component labels, each covering one or more
+/// pixels, with sizes counted in pixels.
+#[derive(Default)]
+struct LabelSets {
+    parent: Vec<u32>,
+    size: Vec<u32>,
+    /// New number of each root while renumbering, or `NONE`.
+    renumbered: Vec<u32>,
+    /// Sizes of the renumbered components, by new number.
+    new_size: Vec<u32>,
+}
+
+impl LabelSets {
+    /// Start a new component of one pixel.
+    fn push(&mut self) -> u32 {
+        let id = self.parent.len() as u32;
+        self.parent.push(id);
+        self.size.push(1);
+        id
+    }
+
+    /// Find the root of `id`, with path splitting.
+    fn find(&mut self, mut id: u32) -> u32 {
+        loop {
+            let parent = self.parent[id as usize];
+            if parent == id {
+                return id;
+            }
+            self.parent[id as usize] = self.parent[parent as usize];
+            id = parent;
+        }
+    }
+
+    /// Union the sets containing `a` and `b`, returning the new root.
+    fn union(&mut self, a: u32, b: u32) -> u32 {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return a;
+        }
+        let (root, child) = if self.size[a as usize] >= self.size[b as usize] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[child as usize] = root;
+        self.size[root as usize] += self.size[child as usize];
+        root
+    }
+
+    /// Start numbering the components again from zero, in the order they are
+    /// passed to [`renumber`](Self::renumber).
+    fn start_renumbering(&mut self) {
+        self.renumbered.clear();
+        self.renumbered.resize(self.parent.len(), NONE);
+        self.new_size.clear();
+    }
+
+    /// The new number of component `root`, assigning the next one on first
+    /// use.
+    fn renumber(&mut self, root: u32) -> u32 {
+        let id = &mut self.renumbered[root as usize];
+        if *id == NONE {
+            *id = self.new_size.len() as u32;
+            self.new_size.push(self.size[root as usize]);
+        }
+        *id
+    }
+
+    /// Replace the sets with the renumbered components, each on its own.
+    fn finish_renumbering(&mut self) {
+        std::mem::swap(&mut self.size, &mut self.new_size);
+        self.parent.clear();
+        self.parent.extend(0..self.size.len() as u32);
+    }
+}
+
+/// Reusable buffers for [`banded_clusters`].
+///
+/// Everything here is sized by the band and by the components crossing it,
+/// not by the image.
+pub struct BandBuffers {
+    threshold_bufs: ThresholdBuffers,
+    /// Thresholded rows of the current band, plus the row below it and any
+    /// rows deglitching reads beyond it.
+    band: ImageU8,
+    /// Boundary bits of the row being scanned.
+    mask: Vec<u8>,
+    /// Component labels of the row being scanned and the row below it.
+    labels: Vec<u32>,
+    labels_below: Vec<u32>,
+    sets: LabelSets,
+    /// Boundary points of component pairs that may still grow or merge.
+    pending: ClusterMap,
+    /// Scratch maps for [`settle`].
+    settling: ClusterMap,
+    finished: ClusterMap,
+}
+
+impl Default for BandBuffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BandBuffers {
+    pub fn new() -> Self {
+        Self {
+            threshold_bufs: ThresholdBuffers::new(),
+            band: ImageU8::new(0, 0),
+            mask: Vec::new(),
+            labels: Vec::new(),
+            labels_below: Vec::new(),
+            sets: LabelSets::default(),
+            pending: ClusterMap::new(),
+            settling: ClusterMap::new(),
+            finished: ClusterMap::new(),
+        }
+    }
+}
+
+/// Threshold, segment and cluster `img` in bands of `band_rows` rows, without
+/// any image-sized intermediate.
+///
+/// Produces the same clusters as [`threshold`](super::threshold::threshold),
+/// [`connected_components`](super::connected::connected_components) and
+/// [`gradient_clusters_filtered`](super::cluster::gradient_clusters_filtered)
+/// run on the whole image, with the points of each cluster in the same
+/// order. Pixels where `region` is below 128 are left unclassified, as
+/// [`exclude_region`] does. Components are labeled row by row against the row above; at the
+/// end of each band, boundary points between two components that no longer
+/// reach the band's last row are final and are kept or dropped, and the
+/// labels still in use are renumbered, so memory stays proportional to the
+/// band width rather than the image size. Only thresholding runs in
+/// parallel.
+#[allow(clippy::too_many_arguments)]
+pub fn banded_clusters(
+    img: &impl GrayImage,
+    tile_size: u32,
+    min_white_black_diff: i32,
+    deglitch: bool,
+    band_rows: u32,
+    min_cluster_size: u32,
+    edge: Option<EdgeFilter>,
+    region: Option<ImageRef<'_>>,
+    bufs: &mut BandBuffers,
+    out: &mut Vec<Cluster>,
+) {
+    debug_assert!(
+        edge.is_none_or(|e| e.gray.width() == img.width() && e.gray.height() == img.height())
+    );
+    debug_assert!(region.is_none_or(|r| r.width() == img.width() && r.height() == img.height()));
+    out.clear();
+    let w = img.width();
+    let h = img.height();
+    // The scan skips the first and last row and column, so smaller images
+    // have no boundaries.
+    if w < 3 || h < 3 {
+        return;
+    }
+    let tile_size = tile_size.max(1);
+    let Some(tiles) = tile_extrema(img, tile_size, &mut bufs.threshold_bufs) else {
+        return;
+    };
+
+    let BandBuffers {
+        threshold_bufs,
+        band,
+        mask,
+        labels,
+        labels_below,
+        sets,
+        pending,
+        settling,
+        finished,
+    } = bufs;
+    let width = w as usize;
+    mask.clear();
+    mask.resize(width, 0);
+    labels.clear();
+    labels.resize(width, NONE);
+    labels_below.clear();
+    labels_below.resize(width, NONE);
+    sets.parent.clear();
+    sets.size.clear();
+    let band_rows = band_rows.max(1);
+    let n_buckets = (width * band_rows as usize / 5).max(16);
+    pending.reset(n_buckets);
+
+    // Deglitching closes with two 3×3 passes, so it reads two rows beyond
+    // the ones it must get right.
+    let halo = if deglitch { 2 } else { 0 };
+    for y0 in (0..h).step_by(band_rows as usize) {
+        let y1 = (y0 + band_rows).min(h);
+        let first = y0.saturating_sub(halo);
+        let last = (y1 + 1 + halo).min(h);
+        binarize_rows(
+            img,
+            first,
+            last,
+            tile_size,
+            tiles,
+            min_white_black_diff,
+            threshold_bufs,
+            band,
+        );
+        if deglitch {
+            deglitch_image(
+                band,
+                &mut threshold_bufs.morph_a,
+                &mut threshold_bufs.morph_b,
+            );
+        }
+        if let Some(region) = region {
+            exclude_region(band, &region, first);
+        }
+        let row = |y: u32| band.row(y - first);
+
+        if y0 == 0 {
+            label_row(row(0), None, labels, sets);
+        }
+        for y in y0..y1 {
+            if y + 1 < h {
+                label_row(row(y + 1), Some((row(y), &labels[..])), labels_below, sets);
+            }
+            if y >= 1 && y + 1 < h {
+                mask_row(row(y), row(y + 1), mask);
+                mask[0] = 0;
+                mask[width - 1] = 0;
+                if let Some(e) = edge {
+                    filter_row(e, y, mask);
+                }
+                scan_row(y, mask, labels, labels_below, sets, pending);
+            }
+            std::mem::swap(labels, labels_below);
+        }
+
+        // `labels` now holds the labels of row `y1`, the only row later
+        // bands can connect to.
+        let live: &mut [u32] = if y1 < h { &mut labels[..] } else { &mut [] };
+        std::mem::swap(pending, settling);
+        pending.reset(n_buckets.max(settling.len()));
+        finished.reset(n_buckets);
+        settle(live, sets, settling, pending, finished);
+        for (_, mut points) in finished.drain_keyed() {
+            if points.len() >= min_cluster_size as usize {
+                // Merged pieces interleave; put them back in scan order
+                if !points.is_sorted_by_key(|p| p.slope) {
+                    points.sort_by_key(|p| p.slope);
+                }
+                for p in &mut points {
+                    p.slope = 0;
+                }
+                out.push(Cluster { points });
+            }
+        }
+    }
+
+    // Sort by descending size for determinism
+    out.sort_by_key(|c| std::cmp::Reverse(c.points.len()));
+}
+
+/// Label the pixels of `row`, joining each to its neighbors of the same
+/// value on the left and in the row above (diagonally too for white), as in
+/// [`connected_components`](super::connected::connected_components).
+fn label_row(row: &[u8], above: Option<(&[u8], &[u32])>, out: &mut [u32], sets: &mut LabelSets) {
+    let w = row.len();
+    for x in 0..w {
+        let v = row[x];
+        if v == 127 {
+            out[x] = NONE;
+            continue;
+        }
+        let mut label = NONE;
+        let mut join = |other: u32| {
+            label = if label == NONE {
+                sets.find(other)
+            } else {
+                sets.union(label, other)
+            };
+        };
+        if x > 0 && row[x - 1] == v {
+            join(out[x - 1]);
+        }
+        if let Some((prev, prev_labels)) = above {
+            if prev[x] == v {
+                join(prev_labels[x]);
+            }
+            if v == 255 {
+                if x > 0 && prev[x - 1] == 255 {
+                    join(prev_labels[x - 1]);
+                }
+                if x + 1 < w && prev[x + 1] == 255 {
+                    join(prev_labels[x + 1]);
+                }
+            }
+        }
+        out[x] = if label == NONE {
+            sets.push()
+        } else {
+            sets.size[label as usize] += 1;
+            label
+        };
+    }
+}
+
+/// Add the boundary points of row `y` to `pending`, keyed by the component
+/// pair each separates as labeled so far.
+///
+/// Unlike the whole-image scan, component sizes are not final yet, so every
+/// boundary point is kept and [`settle`] filters them later. The whole-image
+/// scan skips a down-left point when the pixel to the left already added a
+/// down-right point at the same position; both separate the same two
+/// components, so this depends only on the mask.
+fn scan_row(
+    y: u32,
+    mask: &[u8],
+    labels: &[u32],
+    labels_below: &[u32],
+    sets: &mut LabelSets,
+    pending: &mut ClusterMap,
+) {
+    let mut down_right_last = false;
+    for (x, &m) in mask.iter().enumerate() {
+        let skip_down_left = down_right_last;
+        down_right_last = m & DOWN_RIGHT != 0;
+        if m & !WHITE == 0 {
+            continue;
+        }
+        let rep0 = sets.find(labels[x]);
+        // The neighbor has the opposite color, so the step is ±255
+        let step: i16 = if m & WHITE != 0 { -255 } else { 255 };
+        for (neighbor, (bit, dx, dy)) in [
+            (RIGHT, 1, 0),
+            (DOWN, 0, 1),
+            (DOWN_LEFT, -1, 1),
+            (DOWN_RIGHT, 1, 1),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if m & bit == 0 || (bit == DOWN_LEFT && skip_down_left) {
+                continue;
+            }
+            let nx = (x as i32 + dx) as usize;
+            let rep1 = sets.find(if dy == 0 {
+                labels[nx]
+            } else {
+                labels_below[nx]
+            });
+            let pt = Pt {
+                x: (2 * x as i32 + dx) as u16,
+                y: (2 * y as i32 + dy) as u16,
+                gx: dx as i16 * step,
+                gy: dy as i16 * step,
+                // Position in the whole-image scan until the cluster is
+                // complete; coordinates are below 2^15.
+                slope: (y << 17) | ((x as u32) << 2) | neighbor as u32,
+            };
+            pending.insert(pair_key(rep0, rep1), pt);
+        }
+    }
+}
+
+/// Settle the clusters of `settling` at the end of a band whose last row
+/// has the labels `live`.
+///
+/// A component no label in `live` belongs to is complete. Clusters touching
+/// a complete component smaller than [`MIN_COMPONENT_SIZE`] are dropped, and
+/// those between two complete components are moved to `finished`. The
+/// components still referenced are then renumbered from zero, rewriting
+/// `live` and the keys of the clusters moved back to `pending`, so the label
+/// sets only ever hold one band's worth of components.
+fn settle(
+    live: &mut [u32],
+    sets: &mut LabelSets,
+    settling: &mut ClusterMap,
+    pending: &mut ClusterMap,
+    finished: &mut ClusterMap,
+) {
+    sets.start_renumbering();
+    for label in live.iter_mut().filter(|label| **label != NONE) {
+        let root = sets.find(*label);
+        *label = sets.renumber(root);
+    }
+    let n_live = sets.new_size.len() as u32;
+    let is_live = |sets: &LabelSets, root: u32| sets.renumbered[root as usize] < n_live;
+
+    for (key, points) in settling.drain_keyed() {
+        let a = sets.find((key >> 32) as u32);
+        let b = sets.find(key as u32);
+        let (a_live, b_live) = (is_live(sets, a), is_live(sets, b));
+        if (!a_live && sets.size[a as usize] < MIN_COMPONENT_SIZE)
+            || (!b_live && sets.size[b as usize] < MIN_COMPONENT_SIZE)
+        {
+            pending.recycle(points);
+            continue;
+        }
+        if a_live || b_live {
+            let key = pair_key(sets.renumber(a), sets.renumber(b));
+            pending.insert_all(key, points);
+        } else {
+            finished.insert_all(pair_key(a, b), points);
+        }
+    }
+
+    sets.finish_renumbering();
+}
+
+/// Cluster key of two component roots, independent of their order.
+fn pair_key(a: u32, b: u32) -> u64 {
+    let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+    ((lo as u64) << 32) | hi as u64
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::detect::cluster::{gradient_clusters_filtered, ClusterMap};
+    use crate::detect::connected::connected_components;
+    use crate::detect::threshold::{threshold_tiled, DEFAULT_TILE_SIZE};
+    use crate::detect::unionfind::UnionFind;
+
+    /// Smooth blobs large enough to span several bands, with noise that
+    /// leaves many small components along their edges.
+    fn blobs(w: u32, h: u32, seed: u32) -> ImageU8 {
+        let mut state = seed;
+        let mut img = ImageU8::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (state >> 24) as f32 / 255.0 * 60.0 - 30.0;
+                let wave = (x as f32 * 0.21).sin() * (y as f32 * 0.13 + seed as f32).cos();
+                let v = 128.0 + 100.0 * wave + noise;
+                img.set(x, y, v.clamp(0.0, 255.0) as u8);
+            }
+        }
+        img
+    }
+
+    fn points(cluster: &Cluster) -> Vec<(u16, u16, i16, i16)> {
+        cluster
+            .points
+            .iter()
+            .map(|p| (p.x, p.y, p.gx, p.gy))
+            .collect()
+    }
+
+    fn sorted(clusters: &[Cluster]) -> Vec<Vec<(u16, u16, i16, i16)>> {
+        let mut all: Vec<_> = clusters.iter().map(points).collect();
+        all.sort();
+        all
+    }
+
+    fn whole_image(
+        img: &ImageU8,
+        tile_size: u32,
+        deglitch: bool,
+        edge: Option<EdgeFilter>,
+    ) -> Vec<Cluster> {
+        let mut threshed = ImageU8::new(0, 0);
+        threshold_tiled(
+            img,
+            tile_size,
+            5,
+            deglitch,
+            &mut threshed,
+            &mut ThresholdBuffers::new(),
+        );
+        let mut uf = UnionFind::empty();
+        connected_components(&threshed, &mut uf);
+        let mut out = Vec::new();
+        gradient_clusters_filtered(
+            &threshed,
+            &mut uf,
+            5,
+            edge,
+            &mut ClusterMap::new(),
+            &mut out,
+        );
+        out
+    }
+
+    fn banded(
+        img: &ImageU8,
+        tile_size: u32,
+        deglitch: bool,
+        edge: Option<EdgeFilter>,
+        band_rows: u32,
+    ) -> Vec<Cluster> {
+        let mut out = Vec::new();
+        banded_clusters(
+            img,
+            tile_size,
+            5,
+            deglitch,
+            band_rows,
+            5,
+            edge,
+            None,
+            &mut BandBuffers::new(),
+            &mut out,
+        );
+        out
+    }
+
+    #[test]
+    fn matches_whole_image_for_any_band_height() {
+        let img = blobs(83, 61, 7);
+        let expected = sorted(&whole_image(&img, DEFAULT_TILE_SIZE, false, None));
+        assert!(expected.len() > 10);
+        for band_rows in [0, 1, 2, 5, 16, 60, 61, 200] {
+            assert_eq!(
+                sorted(&banded(&img, DEFAULT_TILE_SIZE, false, None, band_rows)),
+                expected,
+                "band_rows {band_rows}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_whole_image_for_other_tile_sizes() {
+        let img = blobs(83, 61, 5);
+        for tile_size in [3, 8] {
+            let expected = sorted(&whole_image(&img, tile_size, true, None));
+            assert!(!expected.is_empty());
+            for band_rows in [1, 7] {
+                assert_eq!(
+                    sorted(&banded(&img, tile_size, true, None, band_rows)),
+                    expected,
+                    "tile_size {tile_size}, band_rows {band_rows}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_whole_image_with_deglitch_and_edge_filter() {
+        let img = blobs(70, 90, 3);
+        let edge = Some(EdgeFilter {
+            gray: img.as_image_ref(),
+            min_gradient: 40,
+        });
+        for (deglitch, edge) in [(true, None), (false, edge), (true, edge)] {
+            let expected = sorted(&whole_image(&img, DEFAULT_TILE_SIZE, deglitch, edge));
+            assert!(!expected.is_empty());
+            for band_rows in [1, 4, 13] {
+                assert_eq!(
+                    sorted(&banded(&img, DEFAULT_TILE_SIZE, deglitch, edge, band_rows)),
+                    expected,
+                    "deglitch {deglitch}, band_rows {band_rows}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn points_keep_scan_order() {
+        // One tall bar merges its two halves only at the bottom, so its
+        // boundary with the background is assembled from several bands.
+        let mut img = ImageU8::new(40, 60);
+        img.buf.fill(220);
+        for y in 5..55 {
+            for x in 5..35 {
+                let inside_gap = (15..25).contains(&x) && y < 45;
+                if !inside_gap {
+                    img.set(x, y, 20);
+                }
+            }
+        }
+        let whole = whole_image(&img, DEFAULT_TILE_SIZE, false, None);
+        let banded = banded(&img, DEFAULT_TILE_SIZE, false, None, 3);
+        assert_eq!(banded.len(), whole.len());
+        assert_eq!(
+            banded.iter().map(points).collect::<Vec<_>>(),
+            whole.iter().map(points).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn region_matches_whole_image_exclusion() {
+        let img = blobs(83, 61, 9);
+        let mut region = ImageU8::new(83, 61);
+        for y in 0..61 {
+            for x in 0..83 {
+                region.set(
+                    x,
+                    y,
+                    if (20..50).contains(&x) && y > 25 {
+                        0
+                    } else {
+                        255
+                    },
+                );
+            }
+        }
+
+        let mut threshed = ImageU8::new(0, 0);
+        threshold_tiled(
+            &img,
+            DEFAULT_TILE_SIZE,
+            5,
+            false,
+            &mut threshed,
+            &mut ThresholdBuffers::new(),
+        );
+        exclude_region(&mut threshed, &region, 0);
+        let mut uf = UnionFind::empty();
+        connected_components(&threshed, &mut uf);
+        let mut whole = Vec::new();
+        gradient_clusters_filtered(
+            &threshed,
+            &mut uf,
+            5,
+            None,
+            &mut ClusterMap::new(),
+            &mut whole,
+        );
+        let expected = sorted(&whole);
+        assert!(expected.len() < sorted(&whole_image(&img, DEFAULT_TILE_SIZE, false, None)).len());
+
+        for band_rows in [1, 7, 30] {
+            let mut out = Vec::new();
+            banded_clusters(
+                &img,
+                DEFAULT_TILE_SIZE,
+                5,
+                false,
+                band_rows,
+                5,
+                None,
+                Some(region.as_image_ref()),
+                &mut BandBuffers::new(),
+                &mut out,
+            );
+            assert_eq!(sorted(&out), expected, "band_rows {band_rows}");
+        }
+    }
+
+    #[test]
+    fn tiny_images_have_no_clusters() {
+        let mut out = vec![Cluster { points: Vec::new() }];
+        let mut bufs = BandBuffers::new();
+        banded_clusters(
+            &ImageU8::new(2, 50),
+            DEFAULT_TILE_SIZE,
+            5,
+            false,
+            8,
+            5,
+            None,
+            None,
+            &mut bufs,
+            &mut out,
+        );
+        assert!(out.is_empty());
+        banded_clusters(
+            &ImageU8::new(50, 3),
+            DEFAULT_TILE_SIZE,
+            5,
+            false,
+            8,
+            5,
+            None,
+            None,
+            &mut bufs,
+            &mut out,
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn label_sets_stay_band_sized() {
+        let img = blobs(64, 400, 11);
+        let mut bufs = BandBuffers::new();
+        let mut out = Vec::new();
+        banded_clusters(
+            &img,
+            DEFAULT_TILE_SIZE,
+            5,
+            false,
+            8,
+            5,
+            None,
+            None,
+            &mut bufs,
+            &mut out,
+        );
+        assert!(!out.is_empty());
+        assert_eq!(bufs.pending.drain_keyed().count(), 0);
+        // One band labels at most 64 × 8 pixels, on top of the components
+        // carried over from the band above.
+        let labels = bufs.sets.parent.capacity();
+        assert!(labels <= 64 * 8 * 4, "{labels}");
+    }
+}
@@ -1,4 +1,4 @@
-use super::image::ImageU8;
+use super::image::{GrayImage, ImageRef, ImageU8};
 use super::par::Par;
 use super::unionfind::UnionFind;
 
@@ -23,6 +23,19 @@ pub struct Cluster {
     pub points: Vec<Pt>,
 }
 
+/// Drops boundary points whose intensity step in the grayscale image is weak.
+///
+/// On noisy images most boundaries between thresholded components are noise,
+/// with only a few gray levels separating the two sides; filtering them out
+/// before clustering shrinks the clusters that quad fitting has to process.
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeFilter<'a> {
+    /// Grayscale image the threshold was computed from (same dimensions).
+    pub gray: ImageRef<'a>,
+    /// Minimum absolute intensity difference between the two boundary pixels.
+    pub min_gradient: u8,
+}
+
 const EMPTY: u32 = u32::MAX;
 
 /// Hash table entry with its own growable point buffer.
@@ -42,6 +55,9 @@ pub struct ClusterMap {
     entries: Vec<Entry>,
     /// Pool of cleared `Vec<Pt>` recycled from previous frames.
     free_vecs: Vec<Vec<Pt>>,
+    /// Copy of the thresholded image for [`gradient_clusters_filtered`] to
+    /// turn into boundary bits, reused across frames.
+    mask: ImageU8,
 }
 
 impl Default for ClusterMap {
@@ -56,11 +72,12 @@ impl ClusterMap {
             buckets: Vec::new(),
             entries: Vec::new(),
             free_vecs: Vec::new(),
+            mask: ImageU8::new(0, 0),
         }
     }
 
     /// Prepare for a new frame. Recycles inner Vecs and clears the table.
-    fn reset(&mut self, n_buckets: usize) {
+    pub(super) fn reset(&mut self, n_buckets: usize) {
         // Recycle entry Vecs into the free pool
         for mut entry in self.entries.drain(..) {
             entry.points.clear();
@@ -76,13 +93,10 @@ impl ClusterMap {
         self.free_vecs.pop().unwrap_or_default()
     }
 
-    /// Insert a point into the cluster identified by `key`.
+    /// Index of the entry for `key` in the chain of `bucket`, or `EMPTY`.
     #[inline]
-    fn insert(&mut self, key: u64, pt: Pt) {
-        let bucket = self.bucket_index(key);
+    fn find_entry(&self, bucket: usize, key: u64) -> u32 {
         let mut idx = self.buckets[bucket];
-
-        // Walk chain looking for existing entry with this key
         while idx != EMPTY {
             let entry = &self.entries[idx as usize];
             if entry.key == key {
@@ -90,6 +104,14 @@ impl ClusterMap {
             }
             idx = entry.next;
         }
+        idx
+    }
+
+    /// Insert a point into the cluster identified by `key`.
+    #[inline]
+    pub(super) fn insert(&mut self, key: u64, pt: Pt) {
+        let bucket = self.bucket_index(key);
+        let idx = self.find_entry(bucket, key);
 
         if idx != EMPTY {
             self.entries[idx as usize].points.push(pt);
@@ -107,6 +129,38 @@ impl ClusterMap {
         }
     }
 
+    /// Append `points` to the cluster identified by `key`.
+    pub(super) fn insert_all(&mut self, key: u64, points: Vec<Pt>) {
+        let bucket = self.bucket_index(key);
+        let idx = self.find_entry(bucket, key);
+
+        if idx != EMPTY {
+            self.entries[idx as usize].points.extend_from_slice(&points);
+            self.recycle(points);
+        } else {
+            let entry_idx = self.entries.len() as u32;
+            self.entries.push(Entry {
+                key,
+                next: self.buckets[bucket],
+                points,
+            });
+            self.buckets[bucket] = entry_idx;
+        }
+    }
+
+    /// Number of clusters.
+    pub(super) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Move every cluster out with its key. The table must be
+    /// [`reset`](Self::reset) before its next insert.
+    pub(super) fn drain_keyed(&mut self) -> impl Iterator<Item = (u64, Vec<Pt>)> + '_ {
+        self.entries
+            .drain(..)
+            .map(|entry| (entry.key, entry.points))
+    }
+
     /// Create a new ClusterMap pre-sized for `n_buckets`.
     #[cfg(feature = "parallel")]
     fn with_capacity(n_buckets: usize) -> Self {
@@ -114,6 +168,7 @@ impl ClusterMap {
             buckets: vec![EMPTY; n_buckets],
             entries: Vec::new(),
             free_vecs: Vec::new(),
+            mask: ImageU8::new(0, 0),
         }
     }
 
@@ -133,12 +188,16 @@ impl ClusterMap {
     /// Recycle point Vecs from consumed clusters back into the free pool.
     pub fn recycle_clusters(&mut self, clusters: &mut Vec<Cluster>) {
         for cluster in clusters.drain(..) {
-            let mut points = cluster.points;
-            points.clear();
-            self.free_vecs.push(points);
+            self.recycle(cluster.points);
         }
     }
 
+    /// Return a point Vec to the free pool.
+    pub(super) fn recycle(&mut self, mut points: Vec<Pt>) {
+        points.clear();
+        self.free_vecs.push(points);
+    }
+
     #[inline(always)]
     fn bucket_index(&self, key: u64) -> usize {
         // FxHash-style multiply for u64 keys
@@ -147,7 +206,118 @@ impl ClusterMap {
     }
 }
 
-// Check a neighbor offset and add a boundary point if valid.
+/// Boundary bits of [`boundary_mask_in_place`]: one per forward neighbor visited by the
+/// scan, set when that neighbor has the opposite color (and passes the edge
+/// filter), plus the color of the pixel itself.
+pub(super) const RIGHT: u8 = 1;
+pub(super) const DOWN: u8 = 2;
+pub(super) const DOWN_LEFT: u8 = 4;
+pub(super) const DOWN_RIGHT: u8 = 8;
+pub(super) const WHITE: u8 = 16;
+
+/// Components with fewer pixels than this contribute no boundary points.
+pub(super) const MIN_COMPONENT_SIZE: u32 = 25;
+
+/// Overwrite the thresholded image `img` with the boundary bits of every
+/// pixel.
+///
+/// One dense pass over the thresholded image (and the grayscale image, when
+/// filtering) replaces the per-pixel neighbor reads of the scan, which then
+/// only visits pixels with at least one boundary and never calls `find` on
+/// component interiors. Working in place needs no image-sized buffer: each
+/// row is rewritten from a copy of itself and the still-untouched row below,
+/// and the first row of every chunk is saved beforehand for the last row of
+/// the chunk above. The first and last row and column are cleared, since the
+/// scan skips them.
+fn boundary_mask_in_place(img: &mut ImageU8, edge: Option<EdgeFilter>) {
+    let w = img.width as usize;
+    let h = img.height as usize;
+    let stride = img.stride as usize;
+    let buf = &mut img.buf[..stride * h];
+    if w < 3 || h < 3 {
+        buf.fill(0);
+        return;
+    }
+
+    let rows_per_chunk = 64;
+    let halo: Vec<u8> = (rows_per_chunk..h)
+        .step_by(rows_per_chunk)
+        .flat_map(|y| buf[y * stride..y * stride + w].iter().copied())
+        .collect();
+    Par::get().chunks_mut_for_each(buf, rows_per_chunk * stride, |chunk_idx, chunk| {
+        let mut orig = vec![0u8; w];
+        let rows = chunk.len() / stride;
+        for i in 0..rows {
+            let y = chunk_idx * rows_per_chunk + i;
+            let (row, below) = chunk[i * stride..].split_at_mut(stride);
+            let out = &mut row[..w];
+            if y == 0 || y + 1 >= h {
+                out.fill(0);
+                continue;
+            }
+            orig.copy_from_slice(out);
+            let next = if i + 1 < rows {
+                &below[..w]
+            } else {
+                &halo[chunk_idx * w..(chunk_idx + 1) * w]
+            };
+            mask_row(&orig, next, out);
+            out[0] = 0;
+            out[w - 1] = 0;
+            if let Some(e) = edge {
+                filter_row(e, y as u32, out);
+            }
+        }
+    });
+}
+
+/// Boundary bits for one row, from the row and the one below it.
+#[inline]
+pub(super) fn mask_row(r0: &[u8], r1: &[u8], out: &mut [u8]) {
+    let w = r0.len();
+    let across = |a: u8, b: u8| (a as u16 + b as u16 == 255) as u8;
+    let cells = r0[1..w - 1]
+        .iter()
+        .zip(&r0[2..])
+        .zip(r1[1..w - 1].iter().zip(&r1[..w - 2]).zip(&r1[2..]));
+    for (o, ((&v0, &right), ((&down, &down_left), &down_right))) in
+        out[1..w - 1].iter_mut().zip(cells)
+    {
+        let bits = (across(v0, right) * RIGHT)
+            | (across(v0, down) * DOWN)
+            | (across(v0, down_left) * DOWN_LEFT)
+            | (across(v0, down_right) * DOWN_RIGHT);
+        *o = bits | (u8::from(v0 == 255) * WHITE);
+    }
+}
+
+/// Clear the boundary bits of row `y` whose grayscale step is below the
+/// filter's minimum.
+#[inline]
+pub(super) fn filter_row(edge: EdgeFilter, y: u32, out: &mut [u8]) {
+    let g0 = edge.gray.row(y);
+    let g1 = edge.gray.row(y + 1);
+    let w = g0.len();
+    let min = edge.min_gradient;
+    let strong = |a: u8, b: u8| (a.abs_diff(b) >= min) as u8;
+    let cells = g0[1..w - 1]
+        .iter()
+        .zip(&g0[2..])
+        .zip(g1[1..w - 1].iter().zip(&g1[..w - 2]).zip(&g1[2..]));
+    for (o, ((&v0, &right), ((&down, &down_left), &down_right))) in
+        out[1..w - 1].iter_mut().zip(cells)
+    {
+        let keep = (strong(v0, right) * RIGHT)
+            | (strong(v0, down) * DOWN)
+            | (strong(v0, down_left) * DOWN_LEFT)
+            | (strong(v0, down_right) * DOWN_RIGHT)
+            | WHITE;
+        *o &= keep;
+    }
+}
+
+// Add a boundary point towards a neighbor if its mask bit is set and its
+// component is large enough.
 // All accesses are guaranteed in-bounds because x ∈ [1, w-2] and y ∈ [1, h-2]
 // with dx, dy ∈ {-1, 0, 1}.
 // `rep0` is pre-computed by the caller to avoid redundant find() calls.
@@ -156,15 +326,13 @@ impl ClusterMap {
 // `$uf` is a union-find; `$find_method` is the method name to look up a root
 // (`find` for mutable sequential path, `find_flat` for immutable parallel path).
 macro_rules! do_conn {
-    ($map:expr, $uf:expr, $find_method:ident, $buf:expr, $stride:expr,
-     $x:expr, $y:expr, $rep0:expr, $v0:expr,
+    ($map:expr, $uf:expr, $find_method:ident, $m:expr, $bit:expr,
+     $x:expr, $y:expr, $rep0:expr,
      $dx:expr, $dy:expr, $w:expr, $min_component_size:expr) => {{
-        let nx = ($x as i32 + $dx) as usize;
-        let ny = ($y as i32 + $dy) as usize;
-        let v1 = $buf[ny * $stride + nx];
-        if $v0 as i32 + v1 as i32 == 255 {
-            let id1 = ny as u32 * $w + nx as u32;
-            let rep1_root = $uf.$find_method(id1);
+        if $m & $bit != 0 {
+            let nx = ($x as i32 + $dx) as u32;
+            let ny = ($y as i32 + $dy) as u32;
+            let rep1_root = $uf.$find_method(ny * $w + nx);
             if $uf.root_size(rep1_root) >= $min_component_size {
                 let rep0 = $rep0 as u64;
                 let rep1 = rep1_root as u64;
@@ -173,13 +341,13 @@ macro_rules! do_conn {
                 } else {
                     (rep1 << 32) | rep0
                 };
-                let gx = $dx as i16 * (v1 as i16 - $v0 as i16);
-                let gy = $dy as i16 * (v1 as i16 - $v0 as i16);
+                // The neighbor has the opposite color, so the step is ±255
+                let step: i16 = if $m & WHITE != 0 { -255 } else { 255 };
                 let pt = Pt {
                     x: (2 * $x as i32 + $dx) as u16,
                     y: (2 * $y as i32 + $dy) as u16,
-                    gx,
-                    gy,
+                    gx: $dx as i16 * step,
+                    gy: $dy as i16 * step,
                     slope: 0,
                 };
                 $map.insert(key, pt);
@@ -193,204 +361,118 @@ macro_rules! do_conn {
     }};
 }
 
-/// Scan rows `y0..y1` and insert boundary points into `cluster_map`.
-///
-/// Uses `&mut UnionFind` with `find()` for path compression during the scan.
-/// This is the sequential path — no `flatten()` needed.
-fn scan_rows_mut(
-    buf: &[u8],
-    stride: usize,
-    w: u32,
-    y0: u32,
-    y1: u32,
-    uf: &mut UnionFind,
-    cluster_map: &mut ClusterMap,
-) {
-    let min_component_size = 25u32;
-    for y in y0..y1 {
-        let row_off = y as usize * stride;
-        let mut connected_last = false;
-        for x in 1..w.saturating_sub(1) {
-            let v0 = buf[row_off + x as usize];
-            if v0 == 127 {
-                connected_last = false;
-                continue;
-            }
-
-            let rep0 = uf.find(y * w + x);
-            if uf.root_size(rep0) < min_component_size {
-                connected_last = false;
-                continue;
-            }
+// Scan rows `y0..y1` of a boundary mask and insert boundary points into a
+// cluster map. Shared by the sequential and parallel scans, which differ only
+// in how they look up union-find roots.
+macro_rules! scan_rows {
+    ($mask:expr, $y0:expr, $y1:expr, $uf:expr, $find_method:ident, $map:expr) => {{
+        let min_component_size = MIN_COMPONENT_SIZE;
+        let mask: &ImageU8 = $mask;
+        let w = mask.width;
+        for y in $y0..$y1 {
+            let row = mask.row(y);
+            let mut connected_last = false;
+            for (x, &m) in row.iter().enumerate() {
+                if m & !WHITE == 0 {
+                    connected_last = false;
+                    continue;
+                }
+                let x = x as u32;
 
-            // 4-connectivity
-            do_conn!(
-                cluster_map,
-                uf,
-                find,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                1,
-                0,
-                w,
-                min_component_size
-            );
-            do_conn!(
-                cluster_map,
-                uf,
-                find,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                0,
-                1,
-                w,
-                min_component_size
-            );
+                let rep0 = $uf.$find_method(y * w + x);
+                if $uf.root_size(rep0) < min_component_size {
+                    connected_last = false;
+                    continue;
+                }
 
-            // 8-connectivity with deduplication
-            if !connected_last {
+                // 4-connectivity
+                do_conn!(
+                    $map,
+                    $uf,
+                    $find_method,
+                    m,
+                    RIGHT,
+                    x,
+                    y,
+                    rep0,
+                    1,
+                    0,
+                    w,
+                    min_component_size
+                );
                 do_conn!(
-                    cluster_map,
-                    uf,
-                    find,
-                    buf,
-                    stride,
+                    $map,
+                    $uf,
+                    $find_method,
+                    m,
+                    DOWN,
                     x,
                     y,
                     rep0,
-                    v0,
-                    -1,
+                    0,
+                    1,
+                    w,
+                    min_component_size
+                );
+
+                // 8-connectivity with deduplication
+                if !connected_last {
+                    do_conn!(
+                        $map,
+                        $uf,
+                        $find_method,
+                        m,
+                        DOWN_LEFT,
+                        x,
+                        y,
+                        rep0,
+                        -1,
+                        1,
+                        w,
+                        min_component_size
+                    );
+                }
+                connected_last = do_conn!(
+                    $map,
+                    $uf,
+                    $find_method,
+                    m,
+                    DOWN_RIGHT,
+                    x,
+                    y,
+                    rep0,
+                    1,
                     1,
                     w,
                     min_component_size
                 );
             }
-            connected_last = do_conn!(
-                cluster_map,
-                uf,
-                find,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                1,
-                1,
-                w,
-                min_component_size
-            );
         }
-    }
+    }};
 }
 
 /// Scan rows `y0..y1` and insert boundary points into `cluster_map`.
 ///
-/// Requires [`UnionFind::flatten`] to have been called so that
-/// [`find_flat`](UnionFind::find_flat) returns the correct root in O(1).
-/// Takes `&UnionFind` (immutable) so it can be shared across parallel tasks.
-#[cfg(feature = "parallel")]
-fn scan_rows_flat(
-    buf: &[u8],
-    stride: usize,
-    w: u32,
+/// Uses `&mut UnionFind` with `find()` for path compression during the scan.
+/// This is the sequential path — no `flatten()` needed.
+fn scan_rows_mut(
+    mask: &ImageU8,
     y0: u32,
     y1: u32,
-    uf: &UnionFind,
+    uf: &mut UnionFind,
     cluster_map: &mut ClusterMap,
 ) {
-    let min_component_size = 25u32;
-    for y in y0..y1 {
-        let row_off = y as usize * stride;
-        let mut connected_last = false;
-        for x in 1..w.saturating_sub(1) {
-            let v0 = buf[row_off + x as usize];
-            if v0 == 127 {
-                connected_last = false;
-                continue;
-            }
-
-            let rep0 = uf.find_flat(y * w + x);
-            if uf.root_size(rep0) < min_component_size {
-                connected_last = false;
-                continue;
-            }
-
-            // 4-connectivity
-            do_conn!(
-                cluster_map,
-                uf,
-                find_flat,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                1,
-                0,
-                w,
-                min_component_size
-            );
-            do_conn!(
-                cluster_map,
-                uf,
-                find_flat,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                0,
-                1,
-                w,
-                min_component_size
-            );
+    scan_rows!(mask, y0, y1, uf, find, cluster_map);
+}
 
-            // 8-connectivity with deduplication
-            if !connected_last {
-                do_conn!(
-                    cluster_map,
-                    uf,
-                    find_flat,
-                    buf,
-                    stride,
-                    x,
-                    y,
-                    rep0,
-                    v0,
-                    -1,
-                    1,
-                    w,
-                    min_component_size
-                );
-            }
-            connected_last = do_conn!(
-                cluster_map,
-                uf,
-                find_flat,
-                buf,
-                stride,
-                x,
-                y,
-                rep0,
-                v0,
-                1,
-                1,
-                w,
-                min_component_size
-            );
-        }
-    }
+/// Scan rows `y0..y1` and insert boundary points into `cluster_map`.
+///
+/// Requires [`UnionFind::flatten`] to have been called so that
+/// [`find_flat`](UnionFind::find_flat) returns the correct root in O(1).
+/// Takes `&UnionFind` (immutable) so it can be shared across parallel tasks.
+#[cfg(feature = "parallel")]
+fn scan_rows_flat(mask: &ImageU8, y0: u32, y1: u32, uf: &UnionFind, cluster_map: &mut ClusterMap) {
+    scan_rows!(mask, y0, y1, uf, find_flat, cluster_map);
 }
 
 /// Extract boundary points between adjacent black/white components and group
@@ -407,11 +489,48 @@ pub fn gradient_clusters(
     cluster_map: &mut ClusterMap,
     out: &mut Vec<Cluster>,
 ) {
+    gradient_clusters_filtered(threshed, uf, min_cluster_size, None, cluster_map, out);
+}
+
+/// Like [`gradient_clusters`], but only keeps boundary points that pass `edge`.
+pub fn gradient_clusters_filtered(
+    threshed: &ImageU8,
+    uf: &mut UnionFind,
+    min_cluster_size: u32,
+    edge: Option<EdgeFilter>,
+    cluster_map: &mut ClusterMap,
+    out: &mut Vec<Cluster>,
+) {
+    let mut mask = std::mem::replace(&mut cluster_map.mask, ImageU8::new(0, 0));
+    mask.width = threshed.width;
+    mask.height = threshed.height;
+    mask.stride = threshed.stride;
+    mask.buf.clear();
+    mask.buf.extend_from_slice(&threshed.buf);
+    gradient_clusters_in_place(&mut mask, uf, min_cluster_size, edge, cluster_map, out);
+    cluster_map.mask = mask;
+}
+
+/// Like [`gradient_clusters_filtered`], but reuses `threshed` for the
+/// per-pixel boundary bits instead of a copy, leaving it overwritten. The
+/// detector uses this, since nothing reads the thresholded image after
+/// clustering.
+pub fn gradient_clusters_in_place(
+    threshed: &mut ImageU8,
+    uf: &mut UnionFind,
+    min_cluster_size: u32,
+    edge: Option<EdgeFilter>,
+    cluster_map: &mut ClusterMap,
+    out: &mut Vec<Cluster>,
+) {
+    debug_assert!(
+        edge.is_none_or(|e| e.gray.width() == threshed.width && e.gray.height() == threshed.height)
+    );
     let w = threshed.width;
     let h = threshed.height;
 
-    let buf = &threshed.buf;
-    let stride = threshed.stride as usize;
+    boundary_mask_in_place(threshed, edge);
+    let mask = &*threshed;
 
     let y_start = 1u32;
     let y_end = h.saturating_sub(1);
@@ -420,7 +539,7 @@ pub fn gradient_clusters(
         Par::Sequential => {
             let n_buckets = ((w as usize * h as usize) / 5).max(16);
             cluster_map.reset(n_buckets);
-            scan_rows_mut(buf, stride, w, y_start, y_end, uf, cluster_map);
+            scan_rows_mut(mask, y_start, y_end, uf, cluster_map);
 
             out.clear();
             for entry in &mut cluster_map.entries {
@@ -437,7 +556,7 @@ pub fn gradient_clusters(
             // Flatten the union-find so find_flat() works in O(1) for parallel access
             uf.flatten();
 
-            let _ = cluster_map; // per-thread maps used instead
+            // Per-thread maps are used instead of `cluster_map`
             let n_threads = rayon::current_num_threads();
 
             // Split image into horizontal strips, one per rayon task.
@@ -464,7 +583,7 @@ pub fn gradient_clusters(
                         let cy0 = y_start + (chunk_idx * rows_per_chunk) as u32;
                         let cy1 = y_end.min(cy0 + rows_per_chunk as u32);
                         local_map.reset(n_buckets);
-                        scan_rows_flat(buf, stride, w, cy0, cy1, uf, local_map);
+                        scan_rows_flat(mask, cy0, cy1, uf, local_map);
                         // Collect all clusters (even small ones) so merging
                         // can combine strips that individually are below threshold
                         local_map.collect_keyed(1)
@@ -478,7 +597,7 @@ pub fn gradient_clusters(
     }
 
     // Sort by descending size for determinism
-    out.sort_by(|a, b| b.points.len().cmp(&a.points.len()));
+    out.sort_by_key(|c| std::cmp::Reverse(c.points.len()));
 }
 
 /// Merge keyed clusters from multiple strips. Clusters with the same key
@@ -544,7 +663,7 @@ mod tests {
 
     #[test]
     fn no_clusters_in_uniform_image() {
-        let img = make_thresh(8, 8, &vec![0u8; 64]);
+        let img = make_thresh(8, 8, &[0u8; 64]);
         let mut uf = run_cc(&img);
         let mut clusters = Vec::new();
         gradient_clusters(&img, &mut uf, 5, &mut ClusterMap::default(), &mut clusters);
@@ -674,6 +793,108 @@ mod tests {
         assert!(filtered.is_empty());
     }
 
+    #[test]
+    fn boundary_mask_marks_forward_neighbors() {
+        // 4x4: white pixel at (1, 1), black elsewhere except one unknown
+        #[rustfmt::skip]
+        let pixels = [
+            0,   0, 0, 0,
+            0, 255, 0, 0,
+            0,   0, 127, 0,
+            0,   0, 0, 0,
+        ];
+        let mut img = make_thresh(4, 4, &pixels);
+        boundary_mask_in_place(&mut img, None);
+        let mask = img.buf;
+
+        assert_eq!(mask.len(), 16);
+        // Border rows and columns are never scanned
+        assert!(mask[..4].iter().all(|&m| m == 0));
+        assert!(mask.iter().step_by(4).all(|&m| m == 0));
+        // (1, 1) is white; its right, down and down-left neighbors are black
+        assert_eq!(mask[5], WHITE | RIGHT | DOWN | DOWN_LEFT);
+        // (2, 1) is black; the white pixel to its left is not a forward
+        // neighbor, and the unknown pixel below is never a boundary
+        assert_eq!(mask[6], 0);
+        // (2, 2) is unknown
+        assert_eq!(mask[10], 0);
+        // (1, 2) is black; nothing opposite below or to the right
+        assert_eq!(mask[9], 0);
+    }
+
+    #[test]
+    fn boundary_mask_in_place_matches_row_by_row_across_chunks() {
+        // Taller than one 64-row chunk, with padding past the width
+        let (w, h, stride) = (37u32, 150u32, 40u32);
+        let mut state = 0x2545_f491u32;
+        let buf: Vec<u8> = (0..stride * h)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                [0, 127, 255][(state % 3) as usize]
+            })
+            .collect();
+        let orig = ImageU8::from_buf(w, h, stride, buf);
+        let mut img = orig.clone();
+        boundary_mask_in_place(&mut img, None);
+
+        let mut expected = vec![0u8; w as usize];
+        for y in 1..h - 1 {
+            mask_row(orig.row(y), orig.row(y + 1), &mut expected);
+            expected[0] = 0;
+            expected[w as usize - 1] = 0;
+            assert_eq!(img.row(y), &expected[..], "row {y}");
+        }
+        assert!(img.row(0).iter().chain(img.row(h - 1)).all(|&m| m == 0));
+    }
+
+    #[test]
+    fn edge_filter_drops_weak_boundaries() {
+        // Left half black, right half white; the gray image has a strong step
+        // in the top half and a weak one in the bottom half.
+        let size = 40u32;
+        let mut pixels = vec![0u8; (size * size) as usize];
+        let mut gray_pixels = vec![100u8; (size * size) as usize];
+        for y in 0..size {
+            for x in (size / 2)..size {
+                pixels[(y * size + x) as usize] = 255;
+                gray_pixels[(y * size + x) as usize] = if y < size / 2 { 200 } else { 110 };
+            }
+        }
+        let img = make_thresh(size, size, &pixels);
+        let gray = make_thresh(size, size, &gray_pixels);
+
+        let count = |min_gradient: u8| {
+            let mut uf = run_cc(&img);
+            let mut clusters = Vec::new();
+            let edge = EdgeFilter {
+                gray: gray.as_image_ref(),
+                min_gradient,
+            };
+            gradient_clusters_filtered(
+                &img,
+                &mut uf,
+                1,
+                Some(edge),
+                &mut ClusterMap::new(),
+                &mut clusters,
+            );
+            clusters.iter().map(|c| c.points.len()).sum::<usize>()
+        };
+
+        let mut uf = run_cc(&img);
+        let mut unfiltered = Vec::new();
+        gradient_clusters(&img, &mut uf, 1, &mut ClusterMap::new(), &mut unfiltered);
+        let all: usize = unfiltered.iter().map(|c| c.points.len()).sum();
+
+        assert_eq!(count(0), all);
+        assert_eq!(count(10), all);
+        let strong_only = count(50);
+        assert!(strong_only > 0 && strong_only < all);
+        assert_eq!(count(101), 0);
+    }
+
     #[test]
     fn hash_collision_chain_walk() {
         // Exercise the chain-walk path (lines 88-90) in ClusterMap::insert by
@@ -787,7 +1008,7 @@ mod tests {
             assert!(!h_clusters.is_empty());
 
             // Also test uniform image (no clusters) on single-thread path
-            let uniform = make_thresh(8, 8, &vec![0u8; 64]);
+            let uniform = make_thresh(8, 8, &[0u8; 64]);
             let mut uf2 = run_cc(&uniform);
             let mut empty_clusters = Vec::new();
             gradient_clusters(
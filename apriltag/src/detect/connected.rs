@@ -14,7 +14,8 @@ use super::unionfind::UnionFind;
 /// Use `&mut UnionFind::empty()` for one-shot usage.
 ///
 /// When the `parallel` feature is enabled and multiple rayon threads are
-/// available, uses row-strip decomposition with shared atomic union-find.
+/// available, row strips are linked in parallel as independent union-find
+/// tiles and then merged along their boundaries.
 pub fn connected_components(threshed: &ImageU8, uf: &mut UnionFind) {
     #[cfg(feature = "parallel")]
     if matches!(Par::get(), Par::Parallel) {
@@ -34,77 +35,32 @@ fn connected_components_seq(threshed: &ImageU8, uf: &mut UnionFind) {
     assert!(buf.len() >= (h * stride) as usize);
 
     uf.reset((w * h) as usize);
+    if h == 0 {
+        return;
+    }
 
-    for y in 0..h {
-        let row = (y * stride) as usize;
-        for x in 0..w {
-            let v = buf[row + x as usize];
-            if v == 127 {
-                continue;
-            }
-
-            let id = y * w + x;
-
-            let left = if x > 0 {
-                buf[row + x as usize - 1]
-            } else {
-                127
-            };
-
-            if left == v {
-                uf.union(id, id - 1);
-            }
-
-            if y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up == v {
-                    let upper_left = if x > 0 {
-                        buf[prev_row + x as usize - 1]
-                    } else {
-                        127
-                    };
-                    if !(left == v && upper_left == v) {
-                        uf.union(id, id - w);
-                    }
-                }
-            }
-
-            if v == 255 && left != 255 && x > 0 && y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up != 255 {
-                    let upper_left = buf[prev_row + x as usize - 1];
-                    if upper_left == 255 {
-                        uf.union(id, id - w - 1);
-                    }
-                }
-            }
-
-            if v == 255 && x + 1 < w && y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up != 255 {
-                    let upper_right = buf[prev_row + x as usize + 1];
-                    if upper_right == 255 {
-                        uf.union(id, id - w + 1);
-                    }
-                }
-            }
-        }
+    link_row::<true, false>(buf, w, stride, 0, |a, b| {
+        uf.union(a, b);
+    });
+    for y in 1..h {
+        link_row::<true, true>(buf, w, stride, y, |a, b| {
+            uf.union(a, b);
+        });
     }
 }
 
-/// Parallel connected components using row-strip decomposition.
+/// Parallel connected components using row-strip tiles.
 ///
 /// Algorithm:
 /// 1. Reset UF for w*h elements
-/// 2. Process first row serially (left-neighbor only, &mut self)
-/// 3. Split remaining rows into strips with 1-row gaps between them
-/// 4. Process strips in parallel using find_shared/union_shared (&self)
-/// 5. Stitch gap rows serially (&mut self)
+/// 2. Split the rows into strips, each owning a disjoint UF tile
+/// 3. Link each strip in parallel, ignoring its first row's upward links
+/// 4. Merge the strips by linking each strip's first row to the row above
+///    (serial, &mut UF)
 #[cfg(feature = "parallel")]
 fn connected_components_par(threshed: &ImageU8, uf: &mut UnionFind) {
+    use rayon::prelude::*;
+
     let w = threshed.width;
     let h = threshed.height;
     let buf = &threshed.buf;
@@ -118,141 +74,49 @@ fn connected_components_par(threshed: &ImageU8, uf: &mut UnionFind) {
         return;
     }
 
-    // Process first row serially (left-neighbor only)
-    {
-        let row = 0usize;
-        for x in 1..w {
-            let v = buf[row + x as usize];
-            if v == 127 {
-                continue;
-            }
-            let left = buf[row + x as usize - 1];
-            if left == v {
-                uf.union(x, x - 1);
-            }
-        }
-    }
-
-    // COVERAGE: parallel path only taken with parallel feature + multi-thread
-    if h <= 1 {
-        return;
-    }
-
-    // Compute strip boundaries for rows 1..h
+    // Strip boundaries: [y_start, y_end) rows
     let nthreads = rayon::current_num_threads();
-    let chunk = (h as usize - 1).max(1) / (nthreads * 4).max(1);
-    let chunk = chunk.max(64) as u32;
-
-    // Build strip ranges: [y_start, y_end) with gap rows between strips
-    let mut strips: Vec<(u32, u32)> = Vec::new();
-    let mut y = 1u32;
-    while y < h {
-        let y_end = (y + chunk).min(h);
-        strips.push((y, y_end));
-        // Skip gap row (will be stitched serially)
-        y = y_end + 1;
-    }
-
-    // Process strips in parallel using shared atomic union-find
-    rayon::scope(|s| {
-        for &(y_start, y_end) in &strips {
-            // SAFETY rationale: each strip processes its own rows independently.
-            // union_shared/find_shared use atomic CAS — benign data races on
-            // shared UF nodes are correct by construction.
-            let uf_ref: &UnionFind = uf;
-            s.spawn(move |_| {
-                connected_components_strip(buf, w, stride, uf_ref, y_start, y_end);
+    let chunk = (h as usize / (nthreads * 4).max(1)).max(64) as u32;
+    let strips: Vec<(u32, u32)> = (0..h)
+        .step_by(chunk as usize)
+        .map(|y| (y, (y + chunk).min(h)))
+        .collect();
+
+    let ends: Vec<u32> = strips.iter().map(|&(_, y_end)| y_end * w).collect();
+    let mut tiles: Vec<_> = uf.tiles(&ends).into_iter().zip(&strips).collect();
+    tiles.par_iter_mut().for_each(|(tile, &(y_start, y_end))| {
+        link_row::<true, false>(buf, w, stride, y_start, |a, b| {
+            tile.union(a, b);
+        });
+        for y in y_start + 1..y_end {
+            link_row::<true, true>(buf, w, stride, y, |a, b| {
+                tile.union(a, b);
             });
         }
     });
+    drop(tiles);
 
-    // Stitch gap rows serially (the row between each pair of strips)
-    for window in strips.windows(2) {
-        let gap_y = window[0].1; // first row after strip 0 = gap row
-                                 // COVERAGE: guard for edge case where last strip ends at image boundary
-        if gap_y >= h {
-            continue;
-        }
-        stitch_row(buf, w, stride, uf, gap_y);
+    // Boundary merge: each strip's first row against the last row above it
+    for &(y_start, _) in &strips[1..] {
+        link_row::<false, true>(buf, w, stride, y_start, |a, b| {
+            uf.union(a, b);
+        });
     }
 }
 
-/// Process rows [y_start, y_end) using lock-free find_shared/union_shared.
-#[cfg(feature = "parallel")]
-fn connected_components_strip(
+/// Call `union` for each connection from row `y` to its left neighbors
+/// (`LEFT`) and to the row above (`UP`, requires `y > 0`).
+///
+/// Linking a row in two passes, once with only `LEFT` and once with only
+/// `UP`, yields the same components as a single pass with both.
+#[inline(always)]
+fn link_row<const LEFT: bool, const UP: bool>(
     buf: &[u8],
     w: u32,
     stride: u32,
-    uf: &UnionFind,
-    y_start: u32,
-    y_end: u32,
+    y: u32,
+    mut union: impl FnMut(u32, u32),
 ) {
-    for y in y_start..y_end {
-        let row = (y * stride) as usize;
-        for x in 0..w {
-            let v = buf[row + x as usize];
-            if v == 127 {
-                continue;
-            }
-
-            let id = y * w + x;
-
-            let left = if x > 0 {
-                buf[row + x as usize - 1]
-            } else {
-                127
-            };
-
-            if left == v {
-                uf.union_shared(id, id - 1);
-            }
-
-            if y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up == v {
-                    let upper_left = if x > 0 {
-                        buf[prev_row + x as usize - 1]
-                    } else {
-                        127
-                    };
-                    if !(left == v && upper_left == v) {
-                        uf.union_shared(id, id - w);
-                    }
-                }
-            }
-
-            if v == 255 && left != 255 && x > 0 && y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up != 255 {
-                    let upper_left = buf[prev_row + x as usize - 1];
-                    if upper_left == 255 {
-                        uf.union_shared(id, id - w - 1);
-                    }
-                }
-            }
-
-            if v == 255 && x + 1 < w && y > 0 {
-                let prev_row = row - stride as usize;
-                let up = buf[prev_row + x as usize];
-                if up != 255 {
-                    let upper_right = buf[prev_row + x as usize + 1];
-                    if upper_right == 255 {
-                        uf.union_shared(id, id - w + 1);
-                    }
-                }
-            }
-        }
-    }
-}
-
-/// Stitch a single gap row using serial &mut self union.
-///
-/// Connects pixels in row `y` to their neighbors in row `y-1` (already
-/// processed by the strip above) and to left neighbors in the same row.
-#[cfg(feature = "parallel")]
-fn stitch_row(buf: &[u8], w: u32, stride: u32, uf: &mut UnionFind, y: u32) {
     let row = (y * stride) as usize;
     for x in 0..w {
         let v = buf[row + x as usize];
@@ -268,108 +132,35 @@ fn stitch_row(buf: &[u8], w: u32, stride: u32, uf: &mut UnionFind, y: u32) {
             127
         };
 
-        // Left neighbor
-        if left == v {
-            uf.union(id, id - 1);
-        }
-
-        // Up neighbor
-        let prev_row = row - stride as usize;
-        let up = buf[prev_row + x as usize];
-        if up == v {
-            let upper_left = if x > 0 {
-                buf[prev_row + x as usize - 1]
-            } else {
-                127
-            };
-            if !(left == v && upper_left == v) {
-                uf.union(id, id - w);
-            }
-        }
-
-        // Upper-left diagonal (white only)
-        if v == 255 && left != 255 && x > 0 {
-            let up = buf[prev_row + x as usize];
-            if up != 255 {
-                let upper_left = buf[prev_row + x as usize - 1];
-                if upper_left == 255 {
-                    uf.union(id, id - w - 1);
-                }
-            }
-        }
-
-        // Upper-right diagonal (white only)
-        if v == 255 && x + 1 < w {
-            let up = buf[prev_row + x as usize];
-            if up != 255 {
-                let upper_right = buf[prev_row + x as usize + 1];
-                if upper_right == 255 {
-                    uf.union(id, id - w + 1);
-                }
-            }
+        if LEFT && left == v {
+            union(id, id - 1);
         }
-    }
 
-    // Also connect to the row below (y+1, first row of next strip)
-    // That row was processed by the next strip which could see y+1's
-    // up-neighbors, but the gap row y wasn't processed yet, so we
-    // need to process y+1 looking at y as well.
-    let next_y = y + 1;
-    // COVERAGE: bounds guards; gap rows are always interior in practice
-    if next_y >= (buf.len() as u32).div_ceil(stride) {
-        return; // no row below
-    }
-    let next_row = (next_y * stride) as usize;
-    if next_row + w as usize > buf.len() {
-        return;
-    }
-    for x in 0..w {
-        let v = buf[next_row + x as usize];
-        if v == 127 {
+        if !UP {
             continue;
         }
-        let id = next_y * w + x;
 
-        // Up neighbor (row y)
-        let up = buf[row + x as usize];
+        let prev_row = row - stride as usize;
+        let up = buf[prev_row + x as usize];
         if up == v {
-            let left = if x > 0 {
-                buf[next_row + x as usize - 1]
-            } else {
-                127
-            };
             let upper_left = if x > 0 {
-                buf[row + x as usize - 1]
+                buf[prev_row + x as usize - 1]
             } else {
                 127
             };
+            // Already connected through the left and upper-left pixels
             if !(left == v && upper_left == v) {
-                uf.union(id, id - w);
+                union(id, id - w);
             }
         }
 
-        // Upper-left diagonal (white only)
-        if v == 255 && x > 0 {
-            let left = buf[next_row + x as usize - 1];
-            if left != 255 {
-                let up = buf[row + x as usize];
-                if up != 255 {
-                    let upper_left = buf[row + x as usize - 1];
-                    if upper_left == 255 {
-                        uf.union(id, id - w - 1);
-                    }
-                }
+        // Diagonals (white only)
+        if v == 255 && up != 255 {
+            if left != 255 && x > 0 && buf[prev_row + x as usize - 1] == 255 {
+                union(id, id - w - 1);
             }
-        }
-
-        // Upper-right diagonal (white only)
-        if v == 255 && x + 1 < w {
-            let up = buf[row + x as usize];
-            if up != 255 {
-                let upper_right = buf[row + x as usize + 1];
-                if upper_right == 255 {
-                    uf.union(id, id - w + 1);
-                }
+            if x + 1 < w && buf[prev_row + x as usize + 1] == 255 {
+                union(id, id - w + 1);
             }
         }
     }
@@ -518,7 +309,7 @@ mod tests {
         uf_seq.flatten();
         uf_par.flatten();
 
-        let n = (img.width * img.height) as u32;
+        let n = img.width * img.height;
 
         // Build equivalence classes for sequential
         let mut seq_classes: HashMap<u32, Vec<u32>> = HashMap::new();
@@ -527,7 +318,7 @@ mod tests {
         }
 
         // Verify parallel produces the same equivalence classes
-        for (_, members) in &seq_classes {
+        for members in seq_classes.values() {
             let par_root = uf_par.find_flat(members[0]);
             for &m in &members[1..] {
                 // COVERAGE: assertion message only reached on failure
@@ -617,7 +408,7 @@ mod tests {
     #[test]
     fn parallel_component_spanning_strip_boundary() {
         // A single black component that spans many rows, forcing it to cross
-        // strip boundaries. The gap-stitching must merge them correctly.
+        // strip boundaries. The boundary merge must join the tiles.
         let w = 10u32;
         let h = 300u32;
         let mut pixels = vec![127u8; (w * h) as usize];
@@ -643,4 +434,30 @@ mod tests {
         let img = make_thresh(w, h, &pixels);
         assert_par_matches_seq(&img);
     }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_sequential_noise_with_stride() {
+        // Pseudo-random ternary noise in a padded buffer, so every kind of
+        // link crosses the tile boundaries.
+        let (w, stride, h) = (37u32, 40u32, 300u32);
+        let mut state = 0x2545_f491u32;
+        let pixels: Vec<u8> = (0..stride * h)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                [0, 127, 255][state as usize % 3]
+            })
+            .collect();
+        let img = ImageU8::from_buf(w, h, stride, pixels);
+        assert_par_matches_seq(&img);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_single_strip() {
+        let img = make_thresh(4, 3, &[0, 0, 255, 255, 127, 0, 255, 0, 0, 0, 0, 255]);
+        assert_par_matches_seq(&img);
+    }
 }
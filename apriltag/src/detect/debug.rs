@@ -0,0 +1,281 @@
+//! Images of intermediate pipeline stages, for diagnosing why a tag is missed.
+//!
+//! Install a [`DebugOutput`] with
+//! [`DetectorBuilder::debug_output`](super::detector::DetectorBuilder::debug_output)
+//! to receive one image per [`DebugStage`] and frame, the counterpart of the
+//! C library's `debug` dumps. [`DebugImages`] keeps them for the caller, and
+//! [`DebugImage::to_pnm`] encodes one as a PGM or PPM file.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use apriltag::detect::debug::{DebugImages, DebugStage};
+//! use apriltag::{Detector, DetectorBuffers, ImageU8};
+//!
+//! let images = Arc::new(DebugImages::default());
+//! let det = Detector::builder()
+//!     .add_family(apriltag::family::tag36h11(), 2)
+//!     .debug_output(images.clone())
+//!     .build();
+//! det.detect(&ImageU8::new(64, 64), &mut DetectorBuffers::new());
+//!
+//! let dumped = images.take();
+//! assert_eq!(dumped[0].0, DebugStage::Preprocessed);
+//! let pnm = dumped[0].1.to_pnm();
+//! assert!(pnm.starts_with(b"P5\n32 32\n255\n"));
+//! ```
+
+use std::sync::{Mutex, PoisonError};
+
+use super::cluster::Cluster;
+use super::geometry::Vec2;
+use super::image::GrayImage;
+use super::quad::Quad;
+
+/// Pipeline stage an image was taken at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DebugStage {
+    /// The decimated and blurred grayscale image that is thresholded.
+    Preprocessed,
+    /// The threshold image: black 0, white 255, and 127 for pixels without
+    /// enough local contrast.
+    Threshold,
+    /// Boundary points of each gradient cluster, one color per cluster, at
+    /// the preprocessed resolution.
+    Clusters,
+    /// Every candidate quad over the input image, one color per quad.
+    Quads,
+    /// Candidate quads that no family decoded, in red over the input image.
+    Rejected,
+}
+
+impl DebugStage {
+    /// Short kebab-case name, suitable for file names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Preprocessed => "preprocessed",
+            Self::Threshold => "threshold",
+            Self::Clusters => "clusters",
+            Self::Quads => "quads",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// An 8-bit grayscale or RGB image, rows packed without padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugImage {
+    pub width: u32,
+    pub height: u32,
+    /// 1 for grayscale, 3 for RGB.
+    pub channels: u8,
+    pub data: Vec<u8>,
+}
+
+impl DebugImage {
+    /// Copy a grayscale image.
+    pub(crate) fn gray(img: &impl GrayImage) -> Self {
+        let mut data = Vec::with_capacity(img.width() as usize * img.height() as usize);
+        for y in 0..img.height() {
+            data.extend_from_slice(img.row(y));
+        }
+        Self {
+            width: img.width(),
+            height: img.height(),
+            channels: 1,
+            data,
+        }
+    }
+
+    /// An RGB copy of a grayscale image at half brightness, so that
+    /// annotations stand out.
+    fn dimmed(img: &impl GrayImage) -> Self {
+        let mut data = Vec::with_capacity(3 * img.width() as usize * img.height() as usize);
+        for y in 0..img.height() {
+            for &v in img.row(y) {
+                data.extend_from_slice(&[v / 2; 3]);
+            }
+        }
+        Self {
+            width: img.width(),
+            height: img.height(),
+            channels: 3,
+            data,
+        }
+    }
+
+    /// Set an RGB pixel, ignoring coordinates outside the image.
+    fn plot(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y) {
+            let i = 3 * (y as usize * self.width as usize + x as usize);
+            self.data[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    fn line(&mut self, a: Vec2, b: Vec2, color: [u8; 3]) {
+        let (dx, dy) = (b.0[0] - a.0[0], b.0[1] - a.0[1]);
+        let steps = dx.abs().max(dy.abs()).ceil().clamp(1.0, 1e5) as usize;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            let x = (a.0[0] + t * dx).floor() as i64;
+            let y = (a.0[1] + t * dy).floor() as i64;
+            self.plot(x, y, color);
+        }
+    }
+
+    fn outline(&mut self, quad: &Quad, color: [u8; 3]) {
+        for i in 0..4 {
+            self.line(quad.corners[i], quad.corners[(i + 1) % 4], color);
+        }
+    }
+
+    /// Encode as a binary PGM (grayscale) or PPM (RGB) file.
+    pub fn to_pnm(&self) -> Vec<u8> {
+        let magic = if self.channels == 3 { "P6" } else { "P5" };
+        let mut out = format!("{magic}\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+/// Receives stage images from [`Detector::detect`](super::detector::Detector::detect).
+///
+/// Images are only rendered when an output is installed, and are handed over
+/// by value, so implementors may keep them.
+pub trait DebugOutput: Send + Sync {
+    /// Called once per frame and stage, in pipeline order.
+    fn on_image(&self, stage: DebugStage, image: DebugImage);
+}
+
+/// A [`DebugOutput`] that keeps every image until [`take`](Self::take)n.
+#[derive(Debug, Default)]
+pub struct DebugImages {
+    images: Mutex<Vec<(DebugStage, DebugImage)>>,
+}
+
+impl DebugImages {
+    /// Remove and return the images collected so far, in arrival order.
+    pub fn take(&self) -> Vec<(DebugStage, DebugImage)> {
+        std::mem::take(&mut *self.images.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl DebugOutput for DebugImages {
+    fn on_image(&self, stage: DebugStage, image: DebugImage) {
+        self.images
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((stage, image));
+    }
+}
+
+/// A bright color that differs between neighboring indices.
+fn palette(i: usize) -> [u8; 3] {
+    let h = (i as u32 + 1).wrapping_mul(0x9E37_79B9);
+    [
+        96 + (h >> 8) as u8 % 160,
+        96 + (h >> 16) as u8 % 160,
+        96 + (h >> 24) as u8 % 160,
+    ]
+}
+
+/// Draw cluster boundary points on a black `width` × `height` image.
+pub(crate) fn draw_clusters(width: u32, height: u32, clusters: &[Cluster]) -> DebugImage {
+    let mut out = DebugImage {
+        width,
+        height,
+        channels: 3,
+        data: vec![0; 3 * width as usize * height as usize],
+    };
+    for (i, cluster) in clusters.iter().enumerate() {
+        let color = palette(i);
+        for p in &cluster.points {
+            // Points sit between pixels, at twice pixel resolution
+            out.plot(i64::from(p.x / 2), i64::from(p.y / 2), color);
+        }
+    }
+    out
+}
+
+/// Outline `quads` over a dimmed copy of `img`, colored by `color(index)`.
+fn draw_quads(
+    img: &impl GrayImage,
+    quads: &[Quad],
+    color: impl Fn(usize) -> [u8; 3],
+) -> DebugImage {
+    let mut out = DebugImage::dimmed(img);
+    for (i, quad) in quads.iter().enumerate() {
+        out.outline(quad, color(i));
+    }
+    out
+}
+
+/// Outline every candidate quad in its own color.
+pub(crate) fn draw_candidates(img: &impl GrayImage, quads: &[Quad]) -> DebugImage {
+    draw_quads(img, quads, palette)
+}
+
+/// Outline rejected quads in red.
+pub(crate) fn draw_rejected(img: &impl GrayImage, quads: &[Quad]) -> DebugImage {
+    draw_quads(img, quads, |_| [255, 0, 0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detect::cluster::Pt;
+    use crate::detect::image::ImageU8;
+
+    #[test]
+    fn quad_outline_and_pnm_header() {
+        let mut img = ImageU8::new(8, 6);
+        img.set(0, 0, 200);
+        let quad = Quad {
+            corners: [
+                Vec2::new(1.5, 1.5),
+                Vec2::new(5.5, 1.5),
+                Vec2::new(5.5, 4.5),
+                Vec2::new(1.5, 4.5),
+            ],
+            reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
+        };
+        let out = draw_rejected(&img, &[quad]);
+        let px = |x: usize, y: usize| &out.data[3 * (y * 8 + x)..3 * (y * 8 + x) + 3];
+        assert_eq!(px(0, 0), [100; 3]);
+        assert_eq!(px(3, 1), [255, 0, 0]);
+        assert_eq!(px(5, 3), [255, 0, 0]);
+        assert_eq!(px(3, 3), [0; 3]);
+
+        let pnm = out.to_pnm();
+        assert!(pnm.starts_with(b"P6\n8 6\n255\n"));
+        assert_eq!(pnm.len(), 11 + 8 * 6 * 3);
+    }
+
+    #[test]
+    fn clusters_at_half_resolution() {
+        let pt = |x, y| Pt {
+            x,
+            y,
+            gx: 0,
+            gy: 0,
+            slope: 0,
+        };
+        let clusters = [
+            Cluster {
+                points: vec![pt(3, 4)],
+            },
+            Cluster {
+                points: vec![pt(8, 2), pt(100, 100)],
+            },
+        ];
+        let out = draw_clusters(5, 3, &clusters);
+        let px = |x: usize, y: usize| [out.data[3 * (y * 5 + x)], out.data[3 * (y * 5 + x) + 1]];
+        assert_ne!(px(1, 2), [0, 0]);
+        assert_ne!(px(4, 1), [0, 0]);
+        assert_eq!(px(0, 0), [0, 0]);
+        assert_ne!(palette(0), palette(1));
+    }
+}
@@ -1,10 +1,46 @@
+use crate::bits::BitLocation;
 use crate::family::{FamilyId, TagFamily};
 use crate::hamming;
+use std::sync::OnceLock;
 
-use super::geometry::forward_eliminate;
-use super::homography::Homography;
+use super::geometry::{forward_eliminate, Mat3, Vec2};
+use super::homography::{Homography, TagLine};
+use super::hooks::RejectReason;
 use super::image::GrayImage;
 
+/// Scalar type for decode sampling: `f32` with the `f32` feature, else `f64`.
+#[cfg(feature = "f32")]
+type Real = f32;
+#[cfg(not(feature = "f32"))]
+type Real = f64;
+
+/// The decode homography at sampling precision.
+struct SampleHomography([[Real; 3]; 3]);
+
+impl SampleHomography {
+    fn new(h: &Homography) -> Self {
+        Self(h.data.0.map(|row| row.map(|v| v as Real)))
+    }
+
+    #[inline]
+    fn project(&self, x: Real, y: Real) -> (Real, Real) {
+        let h = &self.0;
+        let xx = h[0][0] * x + h[0][1] * y + h[0][2];
+        let yy = h[1][0] * x + h[1][1] * y + h[1][2];
+        let zz = h[2][0] * x + h[2][1] * y + h[2][2];
+        (xx / zz, yy / zz)
+    }
+}
+
+/// Bilinear sample at sampling precision.
+#[inline]
+fn sample(img: &impl GrayImage, px: Real, py: Real) -> Real {
+    #[cfg(feature = "f32")]
+    return img.interpolate_f32(px, py);
+    #[cfg(not(feature = "f32"))]
+    return img.interpolate(px, py);
+}
+
 /// Result of decoding a tag from a quad.
 #[derive(Debug, Clone)]
 pub struct DecodeResult {
@@ -13,18 +49,20 @@ pub struct DecodeResult {
     pub hamming: i32,
     pub decision_margin: f32,
     pub rotation: i32,
+    /// Whether the tag was seen mirrored.
+    pub mirrored: bool,
 }
 
 /// A spatially-varying intensity model: intensity(x,y) = C[0]*x + C[1]*y + C[2].
 #[derive(Debug, Clone, Default)]
 struct GrayModel {
-    a: [[f64; 3]; 3], // J'J (upper triangular)
-    b: [f64; 3],      // J'gray
-    c: [f64; 3],      // solved coefficients
+    a: [[Real; 3]; 3], // J'J (upper triangular)
+    b: [Real; 3],      // J'gray
+    c: [Real; 3],      // solved coefficients
 }
 
 impl GrayModel {
-    fn add(&mut self, x: f64, y: f64, gray: f64) {
+    fn add(&mut self, x: Real, y: Real, gray: Real) {
         self.a[0][0] += x * x;
         self.a[0][1] += x * y;
         self.a[0][2] += x;
@@ -36,19 +74,21 @@ impl GrayModel {
         self.b[2] += gray;
     }
 
+    #[allow(clippy::unnecessary_cast)] // `Real` is `f64` without the `f32` feature
     fn solve(&mut self) {
         // Fill symmetric part
         self.a[1][0] = self.a[0][1];
         self.a[2][0] = self.a[0][2];
         self.a[2][1] = self.a[1][2];
 
-        // Solve Ac = b using Gaussian elimination
+        // Solve Ac = b using Gaussian elimination. The 3x3 solve runs once per
+        // model, so it stays in f64 regardless of sampling precision.
         let mut aug = [[0.0f64; 4]; 3];
         for i in 0..3 {
             for j in 0..3 {
-                aug[i][j] = self.a[i][j];
+                aug[i][j] = self.a[i][j] as f64;
             }
-            aug[i][3] = self.b[i];
+            aug[i][3] = self.b[i] as f64;
         }
 
         if forward_eliminate::<3, 4>(&mut aug, 1e-20).is_none() {
@@ -59,15 +99,15 @@ impl GrayModel {
         for row in (0..3).rev() {
             let mut sum = aug[row][3];
             for c in (row + 1)..3 {
-                sum -= aug[row][c] * self.c[c];
+                sum -= aug[row][c] * self.c[c] as f64;
             }
             if aug[row][row].abs() > 1e-20 {
-                self.c[row] = sum / aug[row][row];
+                self.c[row] = (sum / aug[row][row]) as Real;
             }
         }
     }
 
-    fn interpolate(&self, x: f64, y: f64) -> f64 {
+    fn interpolate(&self, x: Real, y: Real) -> Real {
         self.c[0] * x + self.c[1] * y + self.c[2]
     }
 }
@@ -78,96 +118,296 @@ pub(crate) struct QuickDecodeMatch {
     pub id: i32,
     pub hamming: i32,
     pub rotation: i32,
+    /// Whether the code matched a mirror image of the tag.
+    pub mirrored: bool,
 }
 
-/// Quick decode lookup table for fast code matching.
+/// Per-chunk index from chunk value to the ids of codes containing it.
 #[derive(Debug, Clone)]
-pub struct QuickDecode {
-    nbits: u32,
-    chunk_mask: u32,
-    shifts: [u32; 4],
-    chunk_offsets: [Vec<u16>; 4],
-    chunk_ids: [Vec<u16>; 4],
-    max_hamming: u32,
+struct ChunkTable {
+    offsets: [Vec<u16>; 4],
+    ids: [Vec<u16>; 4],
 }
 
-impl QuickDecode {
-    /// Build a quick decode table from a tag family.
-    pub fn new(family: &TagFamily, max_hamming: u32) -> Self {
-        let nbits = family.layout.nbits as u32;
-        let chunk_size = nbits.div_ceil(4);
-        let capacity = 1u32 << chunk_size;
-        let chunk_mask = capacity - 1;
-        let shifts = [0, chunk_size, 2 * chunk_size, 3 * chunk_size];
-        let ncodes = family.codes.len();
-
-        let mut chunk_offsets = [
-            vec![0u16; capacity as usize + 1],
-            vec![0u16; capacity as usize + 1],
-            vec![0u16; capacity as usize + 1],
-            vec![0u16; capacity as usize + 1],
+impl ChunkTable {
+    /// Index the codes whose ids `filter` allows; the others can never match.
+    fn new(codes: &[u64], filter: &IdFilter, shifts: &[u32; 4], chunk_mask: u32) -> Self {
+        let capacity = chunk_mask as usize + 1;
+        let kept: Vec<(usize, u64)> = codes
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(id, _)| filter.allows(id as u32))
+            .collect();
+        let mut offsets = [
+            vec![0u16; capacity + 1],
+            vec![0u16; capacity + 1],
+            vec![0u16; capacity + 1],
+            vec![0u16; capacity + 1],
         ];
-        let mut chunk_ids = [
-            vec![0u16; ncodes],
-            vec![0u16; ncodes],
-            vec![0u16; ncodes],
-            vec![0u16; ncodes],
+        let mut ids = [
+            vec![0u16; kept.len()],
+            vec![0u16; kept.len()],
+            vec![0u16; kept.len()],
+            vec![0u16; kept.len()],
         ];
 
         for j in 0..4 {
             // Count frequencies
-            let mut counts = vec![0u16; capacity as usize];
-            for &code in family.codes.iter() {
+            let mut counts = vec![0u16; capacity];
+            for &(_, code) in &kept {
                 let val = ((code >> shifts[j]) & chunk_mask as u64) as usize;
                 counts[val] += 1;
             }
 
             // Prefix sum
-            chunk_offsets[j][0] = 0;
-            for v in 0..capacity as usize {
-                chunk_offsets[j][v + 1] = chunk_offsets[j][v] + counts[v];
+            offsets[j][0] = 0;
+            for v in 0..capacity {
+                offsets[j][v + 1] = offsets[j][v] + counts[v];
             }
 
             // Fill ids
-            let mut pos = chunk_offsets[j].clone();
-            for (idx, &code) in family.codes.iter().enumerate() {
+            let mut pos = offsets[j].clone();
+            for &(idx, code) in &kept {
                 let val = ((code >> shifts[j]) & chunk_mask as u64) as usize;
-                chunk_ids[j][pos[val] as usize] = idx as u16;
+                ids[j][pos[val] as usize] = idx as u16;
                 pos[val] += 1;
             }
         }
 
-        Self {
+        Self { offsets, ids }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.offsets
+            .iter()
+            .chain(&self.ids)
+            .map(|v| v.capacity() * std::mem::size_of::<u16>())
+            .sum()
+    }
+}
+
+/// Codes of a family as seen in a mirror, with their own lookup table.
+#[derive(Debug, Clone)]
+struct MirroredCodes {
+    codes: Vec<u64>,
+    table: ChunkTable,
+}
+
+/// Which tag IDs of a family a detector decodes; see
+/// [`Detector::add_family_with_ids`](super::detector::Detector::add_family_with_ids).
+///
+/// ```
+/// use apriltag::IdFilter;
+///
+/// let deployed = IdFilter::only(0..=10);
+/// assert!(deployed.allows(10));
+/// assert!(!deployed.allows(11));
+/// assert!(!IdFilter::Except(vec![3]).allows(3));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum IdFilter {
+    /// Every ID in the family.
+    #[default]
+    All,
+    /// Only these IDs.
+    Only(Vec<u32>),
+    /// Every ID except these.
+    Except(Vec<u32>),
+}
+
+impl IdFilter {
+    /// Only the IDs in `ids`.
+    pub fn only(ids: impl IntoIterator<Item = u32>) -> Self {
+        Self::Only(ids.into_iter().collect())
+    }
+
+    /// Whether tag `id` passes the filter.
+    pub fn allows(&self, id: u32) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(ids) => ids.contains(&id),
+            Self::Except(ids) => !ids.contains(&id),
+        }
+    }
+}
+
+/// Quick decode lookup table for fast code matching.
+#[derive(Debug, Clone)]
+pub struct QuickDecode {
+    nbits: u32,
+    chunk_mask: u32,
+    shifts: [u32; 4],
+    table: ChunkTable,
+    filter: IdFilter,
+    /// Built on construction when mirrored matching is asked for, or on
+    /// the first mirrored lookup; `None` inside if the family's layout is
+    /// not mirror-symmetric.
+    mirrored: OnceLock<Option<MirroredCodes>>,
+    /// Whether [`decode`](Self::decode) tries mirrored codes.
+    decode_mirrored: bool,
+    max_hamming: u32,
+}
+
+impl QuickDecode {
+    /// Build a quick decode table from a tag family.
+    pub fn new(family: &TagFamily, max_hamming: u32) -> Self {
+        Self::with_ids(family, max_hamming, false, &IdFilter::All)
+    }
+
+    /// Build a quick decode table that also matches mirror images of the
+    /// family's tags, e.g. tags seen via a mirror or from behind a
+    /// transparent substrate.
+    ///
+    /// Falls back to [`QuickDecode::new`] behavior if the family's bit layout
+    /// is not mirror-symmetric.
+    pub fn with_mirrored(family: &TagFamily, max_hamming: u32) -> Self {
+        Self::with_ids(family, max_hamming, true, &IdFilter::All)
+    }
+
+    /// Build a quick decode table that only matches the IDs `filter` allows,
+    /// and mirrored codes too if `mirrored` (see
+    /// [`with_mirrored`](Self::with_mirrored)).
+    ///
+    /// Other codes are left out of the table, so a read of an excluded tag
+    /// is rejected instead of reported, and lookups have fewer candidates
+    /// to compare.
+    pub fn with_ids(
+        family: &TagFamily,
+        max_hamming: u32,
+        mirrored: bool,
+        filter: &IdFilter,
+    ) -> Self {
+        let nbits = family.layout.nbits as u32;
+        let chunk_size = nbits.div_ceil(4);
+        let chunk_mask = (1u32 << chunk_size) - 1;
+        let shifts = [0, chunk_size, 2 * chunk_size, 3 * chunk_size];
+
+        let qd = Self {
             nbits,
             chunk_mask,
             shifts,
-            chunk_offsets,
-            chunk_ids,
+            table: ChunkTable::new(&family.codes, filter, &shifts, chunk_mask),
+            filter: filter.clone(),
+            mirrored: OnceLock::new(),
+            decode_mirrored: mirrored,
             max_hamming,
+        };
+        if mirrored {
+            qd.mirrored_codes(family);
         }
+        qd
+    }
+
+    /// The mirrored codes and their table, built on first use.
+    fn mirrored_codes(&self, family: &TagFamily) -> Option<&MirroredCodes> {
+        self.mirrored
+            .get_or_init(|| {
+                let perm = mirror_permutation(family)?;
+                let codes: Vec<u64> = family
+                    .codes
+                    .iter()
+                    .map(|&c| mirror_code(c, &perm))
+                    .collect();
+                let table = ChunkTable::new(&codes, &self.filter, &self.shifts, self.chunk_mask);
+                Some(MirroredCodes { codes, table })
+            })
+            .as_ref()
+    }
+
+    /// Largest Hamming distance a read may be from a code and still match.
+    pub fn max_hamming(&self) -> u32 {
+        self.max_hamming
+    }
+
+    /// Change the largest accepted Hamming distance. The lookup tables do
+    /// not depend on it, so nothing is rebuilt.
+    pub fn set_max_hamming(&mut self, max_hamming: u32) {
+        self.max_hamming = max_hamming;
+    }
+
+    /// Whether the mirrored lookup tables have been built: on construction
+    /// with [`with_mirrored`](Self::with_mirrored), or by the first lookup
+    /// that tried mirrored codes. Always `false` for a family whose layout
+    /// is not mirror-symmetric.
+    pub fn matches_mirrored(&self) -> bool {
+        self.mirrored.get().is_some_and(Option::is_some)
+    }
+
+    /// Heap memory owned by the lookup tables, in bytes.
+    ///
+    /// The table size depends only on the family's bit count and the number
+    /// of codes it indexes, not on `max_hamming`: Hamming neighbours are
+    /// searched at lookup time rather than expanded up front.
+    pub fn heap_bytes(&self) -> usize {
+        let mirrored = self.mirrored.get().and_then(Option::as_ref).map_or(0, |m| {
+            m.codes.capacity() * std::mem::size_of::<u64>() + m.table.heap_bytes()
+        });
+        self.table.heap_bytes() + mirrored
     }
 
     /// Look up a code in the quick decode table.
     ///
     /// Returns a [`QuickDecodeMatch`] or `None` if no match within `max_hamming`.
+    /// Mirrored codes are tried if the table was built with them.
+    #[cfg(test)]
     pub(crate) fn decode(&self, family: &TagFamily, rcode: u64) -> Option<QuickDecodeMatch> {
+        self.decode_with(family, rcode, self.decode_mirrored, self.max_hamming)
+    }
+
+    /// Like [`decode`](Self::decode), but tries mirrored codes if and only
+    /// if `mirrored`, building their table on first use, and accepts reads
+    /// within `max_hamming` instead of the table's own limit.
+    ///
+    /// Mirrored codes are only tried when no direct match exists, so a
+    /// mirrored tag whose reflected code is within `max_hamming` of
+    /// another tag's code is reported as that tag, unmirrored.
+    pub(crate) fn decode_with(
+        &self,
+        family: &TagFamily,
+        rcode: u64,
+        mirrored: bool,
+        max_hamming: u32,
+    ) -> Option<QuickDecodeMatch> {
+        if let Some(m) = self.lookup(&self.table, &family.codes, rcode, max_hamming) {
+            return Some(m);
+        }
+        if !mirrored {
+            return None;
+        }
+        let mirrored = self.mirrored_codes(family)?;
+        self.lookup(&mirrored.table, &mirrored.codes, rcode, max_hamming)
+            .map(|m| QuickDecodeMatch {
+                mirrored: true,
+                ..m
+            })
+    }
+
+    fn lookup(
+        &self,
+        table: &ChunkTable,
+        codes: &[u64],
+        rcode: u64,
+        max_hamming: u32,
+    ) -> Option<QuickDecodeMatch> {
         let mut rcode = rcode;
         let nbits = self.nbits;
 
         for rotation in 0..4 {
             for j in 0..4 {
                 let val = ((rcode >> self.shifts[j]) & self.chunk_mask as u64) as usize;
-                let start = self.chunk_offsets[j][val] as usize;
-                let end = self.chunk_offsets[j][val + 1] as usize;
+                let start = table.offsets[j][val] as usize;
+                let end = table.offsets[j][val + 1] as usize;
 
                 for k in start..end {
-                    let id = self.chunk_ids[j][k] as usize;
-                    let h = (family.codes[id] ^ rcode).count_ones();
-                    if h <= self.max_hamming {
+                    let id = table.ids[j][k] as usize;
+                    let h = (codes[id] ^ rcode).count_ones();
+                    if h <= max_hamming {
                         return Some(QuickDecodeMatch {
                             id: id as i32,
                             hamming: h as i32,
                             rotation,
+                            mirrored: false,
                         });
                     }
                 }
@@ -180,11 +420,648 @@ impl QuickDecode {
     }
 }
 
+/// For each bit index `i`, the index of the bit at the horizontally mirrored
+/// cell. `None` if some mirrored cell is not a data bit.
+fn mirror_permutation(family: &TagFamily) -> Option<Vec<usize>> {
+    let locs = &family.bit_locations;
+    // Bit locations are relative to border_start; mirror within the full grid.
+    let span = family.layout.grid_size as i32 - 1 - 2 * family.layout.border_start as i32;
+    locs.iter()
+        .map(|l| locs.iter().position(|m| m.x == span - l.x && m.y == l.y))
+        .collect()
+}
+
+/// Apply a bit permutation to a code. Bit `i` is the `i`-th most significant
+/// of the `perm.len()` code bits, matching the sampling order in [`decode_quad`].
+fn mirror_code(code: u64, perm: &[usize]) -> u64 {
+    let n = perm.len();
+    let mut out = 0u64;
+    for (i, &j) in perm.iter().enumerate() {
+        let bit = (code >> (n - 1 - i)) & 1;
+        out |= bit << (n - 1 - j);
+    }
+    out
+}
+
+/// What a decode sample measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleKind {
+    /// The white ring just outside the black border (black inside it for
+    /// reversed-border families).
+    WhiteBorder,
+    /// The black border ring.
+    BlackBorder,
+    /// A data bit, by bit index (0 = MSB of the code).
+    Bit(usize),
+}
+
+/// One image position sampled while decoding a quad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplePoint {
+    pub kind: SampleKind,
+    /// Pixel coordinates, at the precision used for sampling.
+    pub x: f64,
+    pub y: f64,
+    /// Whether the sample was used. Border samples too close to the image
+    /// edge to interpolate are skipped.
+    pub used: bool,
+}
+
+/// The pixel positions [`try_decode_quad`] samples for `family` under the
+/// quad homography `h`, border models first, then data bits in code order.
+///
+/// Intended for debugging decode failures, e.g. near image edges or at
+/// extreme perspective; see also
+/// [`DetectorHooks::on_samples`](super::hooks::DetectorHooks::on_samples).
+#[allow(clippy::unnecessary_cast)] // `Real` is `f64` without the `f32` feature
+pub fn sample_points(
+    family: &TagFamily,
+    h: &Homography,
+    width: u32,
+    height: u32,
+) -> Vec<SamplePoint> {
+    let w = family.layout.border_width as Real;
+    let h = SampleHomography::new(h);
+    let mut out = Vec::with_capacity(8 * family.layout.border_width + family.layout.nbits);
+
+    for (tagx, tagy, is_white) in border_samples(w) {
+        let (px, py) = h.project(tagx, tagy);
+        out.push(SamplePoint {
+            kind: if is_white {
+                SampleKind::WhiteBorder
+            } else {
+                SampleKind::BlackBorder
+            },
+            x: px as f64,
+            y: py as f64,
+            used: !outside_sample_bounds(px, py, width, height),
+        });
+    }
+    for (i, loc) in family.bit_locations.iter().enumerate() {
+        let (tagx, tagy) = bit_sample(loc, w);
+        let (px, py) = h.project(tagx, tagy);
+        out.push(SamplePoint {
+            kind: SampleKind::Bit(i),
+            x: px as f64,
+            y: py as f64,
+            used: true,
+        });
+    }
+    out
+}
+
+/// Border model samples in tag coordinates: `(tagx, tagy, is_white)`.
+fn border_samples(w: Real) -> impl Iterator<Item = (Real, Real, bool)> {
+    // Border sampling patterns: (start_x, start_y, dx, dy, is_white)
+    let patterns: [(Real, Real, Real, Real, bool); 8] = [
+        (-0.5, 0.5, 0.0, 1.0, true),     // left white column
+        (0.5, 0.5, 0.0, 1.0, false),     // left black column
+        (w + 0.5, 0.5, 0.0, 1.0, true),  // right white column
+        (w - 0.5, 0.5, 0.0, 1.0, false), // right black column
+        (0.5, -0.5, 1.0, 0.0, true),     // top white row
+        (0.5, 0.5, 1.0, 0.0, false),     // top black row
+        (0.5, w + 0.5, 1.0, 0.0, true),  // bottom white row
+        (0.5, w - 0.5, 1.0, 0.0, false), // bottom black row
+    ];
+
+    patterns
+        .into_iter()
+        .flat_map(move |(sx, sy, dx, dy, is_white)| {
+            (0..w as usize).map(move |step| {
+                let bx = sx + dx * step as Real;
+                let by = sy + dy * step as Real;
+                (2.0 * (bx / w - 0.5), 2.0 * (by / w - 0.5), is_white)
+            })
+        })
+}
+
+/// Data bit sample in tag coordinates.
+#[inline]
+fn bit_sample(loc: &BitLocation, w: Real) -> (Real, Real) {
+    let bx = loc.x as Real + 0.5;
+    let by = loc.y as Real + 0.5;
+    (2.0 * (bx / w - 0.5), 2.0 * (by / w - 0.5))
+}
+
+/// Whether a border sample at `(px, py)` is too close to the image edge to
+/// be bilinearly interpolated.
+#[inline]
+fn outside_sample_bounds(px: Real, py: Real, width: u32, height: u32) -> bool {
+    px < 0.0 || py < 0.0 || px >= width as Real - 1.0 || py >= height as Real - 1.0
+}
+
+/// Samples across a cell boundary when searching for its edge.
+const EDGE_SEARCH_STEPS: usize = 16;
+
+/// Where edge points are searched along each cell boundary, relative to the
+/// cell center. Kept away from the cell corners, where perpendicular
+/// boundaries would disturb the search.
+const EDGE_POINT_OFFSETS: [f64; 5] = [-0.3, -0.15, 0.0, 0.15, 0.3];
+
+/// Smallest intensity step across a cell boundary that is located.
+const MIN_EDGE_CONTRAST: f64 = 10.0;
+
+/// Least number of located edge points on each side of the tag.
+const MIN_EDGE_POINTS_PER_SIDE: usize = 2;
+
+/// Edge points farther than this multiple of the median residual from the
+/// first fit are dropped before refitting.
+const OUTLIER_RESIDUAL_RATIO: f64 = 3.0;
+
+/// Residual in pixels below which an edge point is never an outlier.
+const MIN_OUTLIER_RESIDUAL: f64 = 0.5;
+
+/// Re-estimate a decoded quad's homography from the tag's cell edges.
+///
+/// Searches every cell boundary of the border square, from the outer edge of
+/// the black border ring inward, for an intensity step between the cell
+/// centers on either side: the outer border edges always have one, interior
+/// boundaries wherever neighbouring cells differ in color. Each located step
+/// lies on a known tag-space grid line, and a least-squares homography
+/// through all of them (see [`Homography::from_line_points`]) averages out
+/// the error in the four quad corners it replaces.
+///
+/// This helps most where the quad fit is weakest, such as blurred or
+/// strongly tilted tags. In heavy sensor noise the located steps are less
+/// precise than the quad's line fits, and corners can get slightly worse.
+///
+/// Returns `None` if a side of the border has too few edge points with
+/// enough contrast, or if a refined corner would move by more than half a
+/// cell.
+pub fn refine_homography(
+    img: &impl GrayImage,
+    family: &TagFamily,
+    h: &Homography,
+) -> Option<Homography> {
+    let w = family.layout.border_width;
+    let wf = w as f64;
+    let to_tag = |b: f64| 2.0 * (b / wf - 0.5);
+    // Tag coordinates of a point offset `u` cells across the boundary
+    // `x = b` (or `y = b`) at `t` cells along it, in border cells
+    let point = |vertical: bool, b: f64, t: f64, u: f64| {
+        if vertical {
+            (to_tag(b + u), to_tag(t))
+        } else {
+            (to_tag(t), to_tag(b + u))
+        }
+    };
+    let sample_tag = |(tx, ty): (f64, f64)| {
+        let (px, py) = h.project(tx, ty);
+        (!outside_sample_bounds(px as Real, py as Real, img.width(), img.height()))
+            .then(|| img.interpolate(px, py))
+    };
+
+    // Interior boundaries count as edges against the typical border contrast
+    let mut contrasts = Vec::with_capacity(4 * w);
+    for vertical in [true, false] {
+        for b in [0.0, wf] {
+            for k in 0..w {
+                let t = k as f64 + 0.5;
+                if let (Some(a), Some(c)) = (
+                    sample_tag(point(vertical, b, t, -0.5)),
+                    sample_tag(point(vertical, b, t, 0.5)),
+                ) {
+                    contrasts.push((c - a).abs());
+                }
+            }
+        }
+    }
+    if contrasts.is_empty() {
+        return None;
+    }
+    contrasts.sort_by(f64::total_cmp);
+    let min_contrast = (0.5 * contrasts[contrasts.len() / 2]).max(MIN_EDGE_CONTRAST);
+
+    let mut points = Vec::new();
+    for vertical in [true, false] {
+        for i in 0..=w {
+            let b = i as f64;
+            let before = points.len();
+            for k in 0..w {
+                for offset in EDGE_POINT_OFFSETS {
+                    let t = k as f64 + 0.5 + offset;
+                    let at = |u: f64| point(vertical, b, t, u);
+                    let Some(u) = locate_edge(img, h, at, min_contrast) else {
+                        continue;
+                    };
+                    let (tx, ty) = at(u);
+                    let (px, py) = h.project(tx, ty);
+                    let line = if vertical {
+                        TagLine::X(to_tag(b))
+                    } else {
+                        TagLine::Y(to_tag(b))
+                    };
+                    points.push((line, Vec2::new(px, py)));
+                }
+            }
+            let outer = i == 0 || i == w;
+            if outer && points.len() - before < MIN_EDGE_POINTS_PER_SIDE {
+                return None;
+            }
+        }
+    }
+
+    let corners =
+        [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| h.project(x, y));
+    let min_side = (0..4)
+        .map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            (a.0 - b.0).hypot(a.1 - b.1)
+        })
+        .fold(f64::INFINITY, f64::min);
+
+    let mut refined = Homography::from_line_points(&points)?;
+
+    // Refit without outliers, e.g. from noise or occlusion. Residuals are
+    // distances across the line, converted from tag units to pixels.
+    let inverse = refined.inverse()?;
+    let residuals: Vec<f64> = points
+        .iter()
+        .map(|(line, p)| {
+            let (tx, ty) = inverse.project(p[0], p[1]);
+            let off = match *line {
+                TagLine::X(c) => tx - c,
+                TagLine::Y(c) => ty - c,
+            };
+            off.abs() * min_side / 2.0
+        })
+        .collect();
+    let mut sorted = residuals.clone();
+    sorted.sort_by(f64::total_cmp);
+    let cutoff = (OUTLIER_RESIDUAL_RATIO * sorted[sorted.len() / 2]).max(MIN_OUTLIER_RESIDUAL);
+    if residuals.iter().any(|&r| r > cutoff) {
+        let kept: Vec<(TagLine, Vec2)> = points
+            .iter()
+            .zip(&residuals)
+            .filter(|(_, &r)| r <= cutoff)
+            .map(|(p, _)| *p)
+            .collect();
+        refined = Homography::from_line_points(&kept).unwrap_or(refined);
+    }
+
+    let max_shift = 0.5 * min_side / wf;
+    let within = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+        .iter()
+        .zip(&corners)
+        .all(|(&[x, y], &(cx, cy))| {
+            let (rx, ry) = refined.project(x, y);
+            (rx - cx).hypot(ry - cy) <= max_shift
+        });
+    within.then_some(refined)
+}
+
+/// Find the intensity step along a one-cell segment across a boundary,
+/// parameterized by `u` in `[-0.5, 0.5]` cells and mapped to tag coordinates
+/// by `at`. The step is located from the profile's mean between the levels
+/// at either end, which averages noise over the whole segment. Returns
+/// `None` if the ends differ by less than `min_contrast`.
+fn locate_edge(
+    img: &impl GrayImage,
+    h: &Homography,
+    at: impl Fn(f64) -> (f64, f64),
+    min_contrast: f64,
+) -> Option<f64> {
+    let mut profile = [0.0f64; EDGE_SEARCH_STEPS + 1];
+    for (i, v) in profile.iter_mut().enumerate() {
+        let (tx, ty) = at(i as f64 / EDGE_SEARCH_STEPS as f64 - 0.5);
+        let (px, py) = h.project(tx, ty);
+        if outside_sample_bounds(px as Real, py as Real, img.width(), img.height()) {
+            return None;
+        }
+        *v = img.interpolate(px, py);
+    }
+
+    let contrast = profile[EDGE_SEARCH_STEPS] - profile[0];
+    if contrast.abs() < min_contrast {
+        return None;
+    }
+    // For a step from `lo` to `hi` at `u = e`, the profile's mean is
+    // `hi - (e + 0.5)(hi - lo)`; solve for `e` with trapezoid integration
+    let n = EDGE_SEARCH_STEPS;
+    let lo = (profile[0] + profile[1]) / 2.0;
+    let hi = (profile[n - 1] + profile[n]) / 2.0;
+    if (hi - lo).abs() < min_contrast {
+        return None;
+    }
+    let mean = (profile.iter().sum::<f64>() - (profile[0] + profile[n]) / 2.0) / n as f64;
+    let e = (hi - mean) / (hi - lo) - 0.5;
+    (e.abs() < 0.5).then_some(e)
+}
+
+/// Distances of [`refine_decode`]'s samples from each edge, in pixels
+/// across it. Bilinear interpolation keeps a pixel-sampled step
+/// antisymmetric only at odd multiples of half a pixel, so these hold for
+/// unblurred images too.
+const ALIGN_OFFSETS: [f64; 2] = [0.5, 1.5];
+
+/// How far in pixels each edge sample of [`refine_decode`] must be from the
+/// nearest cell corner, where the blur of two edges overlaps and the image
+/// no longer steps symmetrically across the edge.
+const ALIGN_REACH: f64 = 3.0;
+
+/// Gauss-Newton iterations of [`refine_decode`].
+const ALIGN_ITERATIONS: usize = 10;
+
+/// Smallest cell, in pixels, that [`refine_decode`] aligns. Smaller tags
+/// have too few samples per edge to beat the quad fit.
+const MIN_ALIGN_CELL: f64 = 2.0;
+
+/// Corner movement in pixels below which [`refine_decode`] has converged.
+const ALIGN_TOLERANCE: f64 = 1e-3;
+
+/// Re-fit a decoded tag's homography by aligning its known bit pattern
+/// with the image.
+///
+/// Once decoded, the color of every cell is known, so every edge of the
+/// pattern is: the outer edge of the border and each boundary between
+/// differently colored cells. Any symmetric blur leaves an image step
+/// antisymmetric about its edge, so two samples at the same distance on
+/// either side of it average to the midpoint between the black and white
+/// levels. This samples such pairs along all straight stretches of the
+/// edges and adjusts the homography by Levenberg-Marquardt until every pair
+/// averages to that midpoint, taken from planes fitted to the cell centers
+/// so uneven lighting does not pull the edges. Unlike
+/// [`refine_homography`], which locates each edge on its own and then fits
+/// lines through the steps, the alignment weighs every sample by the image
+/// gradient at it. On sharp to moderately blurred tags it brings corner
+/// error well below the quad fit's; `apriltag-bench curve --refine-decode`
+/// measures the gain.
+///
+/// `h` is the tag's homography in its own frame (after rotation and
+/// mirroring), as in [`Detection::homography`](super::Detection::homography),
+/// and `id` the decoded id in `family`.
+///
+/// Returns `None` if cells are smaller than two pixels, too much of the
+/// pattern falls outside the image or lacks contrast, or a corner would move
+/// by more than half a cell.
+#[allow(clippy::unnecessary_cast)] // `Real` is `f64` without the `f32` feature
+pub fn refine_decode(
+    img: &impl GrayImage,
+    family: &TagFamily,
+    id: usize,
+    h: &Homography,
+) -> Option<Homography> {
+    let tag = family.get_tag(id)?.render();
+    let w = family.layout.border_width as f64;
+    let start = family.layout.border_start as f64;
+    let to_tag = |b: f64| 2.0 * ((b - start) / w - 0.5);
+
+    let corners =
+        [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| h.project(x, y));
+    let min_side = (0..4)
+        .map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            (a.0 - b.0).hypot(a.1 - b.1)
+        })
+        .fold(f64::INFINITY, f64::min);
+    let cell = min_side / w;
+    if cell < MIN_ALIGN_CELL {
+        return None;
+    }
+
+    let n = tag.grid_size;
+    let color = |x: usize, y: usize| match tag.pixel(x, y) {
+        crate::types::Pixel::White => Some(true),
+        crate::types::Pixel::Black => Some(false),
+        crate::types::Pixel::Transparent => None,
+    };
+    // Whether the boundary `b` between cells of color `c` and `!c` runs
+    // straight for `ALIGN_REACH` either way of `t`
+    let straight = |vertical: bool, b: usize, t: f64, c: bool| {
+        let reach = ALIGN_REACH / cell;
+        let (lo, hi) = ((t - reach).floor(), (t + reach).floor());
+        if lo < 0.0 || hi >= n as f64 {
+            return false;
+        }
+        (lo as usize..=hi as usize).all(|k| {
+            let (before, after) = if vertical {
+                ((b - 1, k), (b, k))
+            } else {
+                ((k, b - 1), (k, b))
+            };
+            color(before.0, before.1) == Some(c) && color(after.0, after.1) == Some(!c)
+        })
+    };
+
+    // Sample pairs `[before, after, on the edge]` in tag coordinates, at
+    // about one pixel spacing along each edge
+    let along = (cell.round() as usize).clamp(2, 8);
+    let mut pairs = Vec::new();
+    for y in 0..n {
+        for x in 0..n {
+            let Some(c) = color(x, y) else {
+                continue;
+            };
+            for (vertical, nx, ny) in [(true, x + 1, y), (false, x, y + 1)] {
+                if nx >= n || ny >= n || color(nx, ny) != Some(!c) {
+                    continue;
+                }
+                let (b, t0) = if vertical { (nx, y) } else { (ny, x) };
+                for k in 0..along {
+                    let t = t0 as f64 + (k as f64 + 0.5) / along as f64;
+                    if !straight(vertical, b, t, c) {
+                        continue;
+                    }
+                    let at = |u: f64, t: f64| {
+                        let b = b as f64 + u;
+                        if vertical {
+                            (to_tag(b), to_tag(t))
+                        } else {
+                            (to_tag(t), to_tag(b))
+                        }
+                    };
+                    // Pixels per cell across the edge, perpendicular to its
+                    // image, which perspective tilts from the cell grid
+                    let image = |(u, v): (f64, f64)| {
+                        let (x, y) = h.project(u, v);
+                        Vec2::new(x, y)
+                    };
+                    let dir = image(at(0.0, t + 0.1)) - image(at(0.0, t - 0.1));
+                    let across = (image(at(0.1, t)) - image(at(-0.1, t))) * 5.0;
+                    let scale = (dir[0] * across[1] - dir[1] * across[0]).abs() / dir.norm();
+                    for offset in ALIGN_OFFSETS.iter().map(|o| o / scale) {
+                        if offset >= 0.5 {
+                            break;
+                        }
+                        pairs.push([at(-offset, t), at(offset, t), at(0.0, t)]);
+                    }
+                }
+            }
+        }
+    }
+
+    let s = h.data.0[2][2];
+    if pairs.is_empty() || s.abs() < 1e-12 {
+        return None;
+    }
+    // Normalized so the tag center's projective scale is 1, leaving eight
+    // free entries
+    let initial = h.data.0.map(|row| row.map(|v| v / s));
+    let project = |m: &[[f64; 3]; 3], (u, v): (f64, f64)| {
+        let d = m[2][0] * u + m[2][1] * v + 1.0;
+        (
+            (m[0][0] * u + m[0][1] * v + m[0][2]) / d,
+            (m[1][0] * u + m[1][1] * v + m[1][2]) / d,
+            d,
+        )
+    };
+    // Gradients are central differences half a pixel either side
+    let inside = |(x, y, _): (f64, f64, f64)| {
+        let (w, h) = (img.width(), img.height());
+        !outside_sample_bounds((x - 0.5) as Real, (y - 0.5) as Real, w, h)
+            && !outside_sample_bounds((x + 0.5) as Real, (y + 0.5) as Real, w, h)
+    };
+    let usable = pairs
+        .iter()
+        .filter(|[a, b, _]| inside(project(&initial, *a)) && inside(project(&initial, *b)))
+        .count();
+    if 2 * usable < pairs.len() {
+        return None;
+    }
+
+    // Black and white levels as planes through the cell centers
+    let mut models = [GrayModel::default(), GrayModel::default()];
+    for y in 0..n {
+        for x in 0..n {
+            let Some(white) = color(x, y) else {
+                continue;
+            };
+            let (u, v) = (to_tag(x as f64 + 0.5), to_tag(y as f64 + 0.5));
+            let p = project(&initial, (u, v));
+            if inside(p) {
+                models[usize::from(white)].add(
+                    u as Real,
+                    v as Real,
+                    img.interpolate(p.0, p.1) as Real,
+                );
+            }
+        }
+    }
+    for model in &mut models {
+        model.solve();
+    }
+    let mid = |(u, v): (f64, f64)| {
+        (models[0].interpolate(u as Real, v as Real) + models[1].interpolate(u as Real, v as Real))
+            as f64
+    };
+    let contrast = models[1].interpolate(0.0, 0.0) - models[0].interpolate(0.0, 0.0);
+    if (contrast as f64).abs() < MIN_EDGE_CONTRAST {
+        return None;
+    }
+
+    // Residual of each pair: its sum less twice the midpoint level
+    let cost = |m: &[[f64; 3]; 3]| {
+        pairs
+            .iter()
+            .filter_map(|&[a, b, e]| {
+                let (pa, pb) = (project(m, a), project(m, b));
+                (inside(pa) && inside(pb))
+                    .then(|| img.interpolate(pa.0, pa.1) + img.interpolate(pb.0, pb.1) - mid(e))
+            })
+            .map(|r| r * r)
+            .sum::<f64>()
+    };
+
+    let mut m = initial;
+    let mut lambda = 1e-3;
+    for _ in 0..ALIGN_ITERATIONS {
+        let mut jtj = [[0.0f64; 8]; 8];
+        let mut jtr = [0.0f64; 8];
+        for &[a, b, e] in &pairs {
+            let (pa, pb) = (project(&m, a), project(&m, b));
+            if !(inside(pa) && inside(pb)) {
+                continue;
+            }
+            let mut j = [0.0f64; 8];
+            for ((u, v), (x, y, d)) in [(a, pa), (b, pb)] {
+                let gx = img.interpolate(x + 0.5, y) - img.interpolate(x - 0.5, y);
+                let gy = img.interpolate(x, y + 0.5) - img.interpolate(x, y - 0.5);
+                let g = gx * x + gy * y;
+                let dj = [gx * u, gx * v, gx, gy * u, gy * v, gy, -g * u, -g * v];
+                for (jk, dk) in j.iter_mut().zip(dj) {
+                    *jk += dk / d;
+                }
+            }
+            let r = img.interpolate(pa.0, pa.1) + img.interpolate(pb.0, pb.1) - mid(e);
+            for p in 0..8 {
+                jtr[p] += j[p] * r;
+                for q in p..8 {
+                    jtj[p][q] += j[p] * j[q];
+                }
+            }
+        }
+        for p in 0..8 {
+            for q in 0..p {
+                jtj[p][q] = jtj[q][p];
+            }
+        }
+
+        let current = cost(&m);
+        let mut step = None;
+        while lambda < 1e6 {
+            let mut aug = [[0.0f64; 9]; 8];
+            for p in 0..8 {
+                aug[p][..8].copy_from_slice(&jtj[p]);
+                aug[p][p] += lambda * jtj[p][p];
+                aug[p][8] = -jtr[p];
+            }
+            if forward_eliminate::<8, 9>(&mut aug, 1e-12).is_none() {
+                lambda *= 10.0;
+                continue;
+            }
+            let mut delta = [0.0f64; 8];
+            for row in (0..8).rev() {
+                let mut sum = aug[row][8];
+                for c in (row + 1)..8 {
+                    sum -= aug[row][c] * delta[c];
+                }
+                delta[row] = sum / aug[row][row];
+            }
+            let mut next = m;
+            for (k, d) in delta.iter().enumerate() {
+                next[k / 3][k % 3] += d;
+            }
+            if cost(&next) < current {
+                lambda = (lambda / 10.0).max(1e-9);
+                step = Some(next);
+                break;
+            }
+            lambda *= 10.0;
+        }
+        let Some(next) = step else {
+            break;
+        };
+        let moved = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+            .iter()
+            .map(|&[u, v]| {
+                let (ax, ay, _) = project(&m, (u, v));
+                let (bx, by, _) = project(&next, (u, v));
+                (ax - bx).hypot(ay - by)
+            })
+            .fold(0.0, f64::max);
+        m = next;
+        if moved < ALIGN_TOLERANCE {
+            break;
+        }
+    }
+
+    let refined = Homography { data: Mat3(m) };
+    let max_shift = 0.5 * cell;
+    let within = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+        .iter()
+        .zip(&corners)
+        .all(|(&[x, y], &(cx, cy))| {
+            let (rx, ry) = refined.project(x, y);
+            (rx - cx).hypot(ry - cy) <= max_shift
+        });
+    within.then_some(refined)
+}
+
 /// Reusable scratch buffers for decode, avoiding per-quad allocation.
 #[derive(Default)]
 pub struct DecodeBufs {
-    values: Vec<f64>,
-    sharp: Vec<f64>,
+    values: Vec<Real>,
+    sharp: Vec<Real>,
 }
 
 impl DecodeBufs {
@@ -206,51 +1083,75 @@ pub fn decode_quad(
     decode_sharpening: f64,
     bufs: &mut DecodeBufs,
 ) -> Option<DecodeResult> {
-    let w = family.layout.border_width as f64;
+    try_decode_quad(img, family, qd, h, reversed_border, decode_sharpening, bufs).ok()
+}
+
+/// Like [`decode_quad`], but reports why decoding failed.
+pub fn try_decode_quad(
+    img: &impl GrayImage,
+    family: &TagFamily,
+    qd: &QuickDecode,
+    h: &Homography,
+    reversed_border: bool,
+    decode_sharpening: f64,
+    bufs: &mut DecodeBufs,
+) -> Result<DecodeResult, RejectReason> {
+    try_decode_quad_polarity(
+        img,
+        family,
+        qd,
+        h,
+        reversed_border,
+        false,
+        qd.decode_mirrored,
+        qd.max_hamming,
+        decode_sharpening,
+        bufs,
+    )
+}
+
+/// Like [`try_decode_quad`], but if `inverted`, read a tag printed with
+/// black and white swapped by negating every sample. `reversed_border` is
+/// then the border polarity of the tag as it would be printed normally.
+/// Mirrored codes are tried if `mirrored`, and reads within `max_hamming`
+/// accepted, whatever `qd` was built with.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn try_decode_quad_polarity(
+    img: &impl GrayImage,
+    family: &TagFamily,
+    qd: &QuickDecode,
+    h: &Homography,
+    reversed_border: bool,
+    inverted: bool,
+    mirrored: bool,
+    max_hamming: u32,
+    decode_sharpening: f64,
+    bufs: &mut DecodeBufs,
+) -> Result<DecodeResult, RejectReason> {
+    let w = family.layout.border_width as Real;
     let total_width = family.layout.grid_size;
+    let h = SampleHomography::new(h);
+    let decode_sharpening = decode_sharpening as Real;
+    // Models and thresholds are linear in the samples, so negating them
+    // swaps black and white throughout
+    let sign: Real = if inverted { -1.0 } else { 1.0 };
 
     // Build gray models for white and black borders
     let mut white_model = GrayModel::default();
     let mut black_model = GrayModel::default();
 
-    // Border sampling patterns: (start_x, start_y, dx, dy, is_white)
-    let patterns: [(f64, f64, f64, f64, bool); 8] = [
-        (-0.5, 0.5, 0.0, 1.0, true),     // left white column
-        (0.5, 0.5, 0.0, 1.0, false),     // left black column
-        (w + 0.5, 0.5, 0.0, 1.0, true),  // right white column
-        (w - 0.5, 0.5, 0.0, 1.0, false), // right black column
-        (0.5, -0.5, 1.0, 0.0, true),     // top white row
-        (0.5, 0.5, 1.0, 0.0, false),     // top black row
-        (0.5, w + 0.5, 1.0, 0.0, true),  // bottom white row
-        (0.5, w - 0.5, 1.0, 0.0, false), // bottom black row
-    ];
-
-    for &(sx, sy, dx, dy, is_white) in &patterns {
-        let n = w as usize;
-        for step in 0..n {
-            let bx = sx + dx * step as f64;
-            let by = sy + dy * step as f64;
-
-            let tagx = 2.0 * (bx / w - 0.5);
-            let tagy = 2.0 * (by / w - 0.5);
-
-            let (px, py) = h.project(tagx, tagy);
-
-            if px < 0.0
-                || py < 0.0
-                || px >= img.width() as f64 - 1.0
-                || py >= img.height() as f64 - 1.0
-            {
-                continue;
-            }
+    for (tagx, tagy, is_white) in border_samples(w) {
+        let (px, py) = h.project(tagx, tagy);
+        if outside_sample_bounds(px, py, img.width(), img.height()) {
+            continue;
+        }
 
-            let gray = img.interpolate(px, py);
+        let gray = sign * sample(img, px, py);
 
-            if is_white {
-                white_model.add(tagx, tagy, gray);
-            } else {
-                black_model.add(tagx, tagy, gray);
-            }
+        if is_white {
+            white_model.add(tagx, tagy, gray);
+        } else {
+            black_model.add(tagx, tagy, gray);
         }
     }
 
@@ -262,10 +1163,10 @@ pub fn decode_quad(
     let black_at_center = black_model.interpolate(0.0, 0.0);
 
     if !reversed_border && white_at_center <= black_at_center {
-        return None;
+        return Err(RejectReason::Polarity);
     }
     if reversed_border && white_at_center >= black_at_center {
-        return None;
+        return Err(RejectReason::Polarity);
     }
 
     // Sample data bits
@@ -276,17 +1177,12 @@ pub fn decode_quad(
     let grid_len = total_width * total_width;
     let values = &mut bufs.values;
     values.clear();
-    values.resize(grid_len, 0.0f64);
+    values.resize(grid_len, 0.0);
 
     for i in 0..nbits {
-        let bx = bit_locs[i].x as f64 + 0.5;
-        let by = bit_locs[i].y as f64 + 0.5;
-
-        let tagx = 2.0 * (bx / w - 0.5);
-        let tagy = 2.0 * (by / w - 0.5);
-
+        let (tagx, tagy) = bit_sample(&bit_locs[i], w);
         let (px, py) = h.project(tagx, tagy);
-        let pixel_val = img.interpolate(px, py);
+        let pixel_val = sign * sample(img, px, py);
         let thresh =
             (black_model.interpolate(tagx, tagy) + white_model.interpolate(tagx, tagy)) / 2.0;
 
@@ -319,10 +1215,10 @@ pub fn decode_quad(
 
     // Extract code and compute decision margin
     let mut rcode = 0u64;
-    let mut white_score = 0.0f64;
-    let mut black_score = 0.0f64;
-    let mut white_count = 1.0f64; // Laplace smoothing
-    let mut black_count = 1.0f64;
+    let mut white_score: Real = 0.0;
+    let mut black_score: Real = 0.0;
+    let mut white_count: Real = 1.0; // Laplace smoothing
+    let mut black_count: Real = 1.0;
 
     for i in 0..nbits {
         rcode <<= 1;
@@ -349,14 +1245,17 @@ pub fn decode_quad(
     debug_assert!(decision_margin >= 0.0);
 
     // Quick decode
-    let m = qd.decode(family, rcode)?;
+    let m = qd
+        .decode_with(family, rcode, mirrored, max_hamming)
+        .ok_or(RejectReason::NoCodeMatch)?;
 
-    Some(DecodeResult {
+    Ok(DecodeResult {
         family_id: family.config.name.clone(),
         id: m.id,
         hamming: m.hamming,
         decision_margin,
         rotation: m.rotation,
+        mirrored: m.mirrored,
     })
 }
 
@@ -427,6 +1326,96 @@ mod tests {
         (img, h)
     }
 
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn refine_homography_corrects_perturbed_corners() {
+        let family = crate::family::tag16h5();
+        let (img, h) = build_decode_test_image(&family, 0, false);
+        let truth =
+            [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| h.project(x, y));
+
+        // Shift the corners by up to 1.5px, well under half a 10px cell
+        let offsets = [[1.5, -1.0], [-0.5, 1.2], [1.0, 1.5], [-1.2, -0.8]];
+        let perturbed: [Vec2; 4] = std::array::from_fn(|i| {
+            Vec2::new(truth[i].0 + offsets[i][0], truth[i].1 + offsets[i][1])
+        });
+        let rough = Homography::from_quad_corners(&perturbed).unwrap();
+
+        let refined = refine_homography(&img, &family, &rough).unwrap();
+        for (&[x, y], t) in [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+            .iter()
+            .zip(truth)
+        {
+            let (rx, ry) = refined.project(x, y);
+            assert!((rx - t.0).hypot(ry - t.1) < 0.1, "({rx}, {ry}) vs {t:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn refine_homography_needs_edge_contrast() {
+        let family = crate::family::tag16h5();
+        let (_, h) = build_decode_test_image(&family, 0, false);
+        let blank = ImageU8::new(200, 200);
+        assert!(refine_homography(&blank, &family, &h).is_none());
+    }
+
+    /// `img` box-blurred twice over 3x3, softening its edges over about two
+    /// pixels like a slightly defocused lens.
+    fn blurred(img: &ImageU8) -> ImageU8 {
+        let mut out = img.clone();
+        for _ in 0..2 {
+            let src = out.clone();
+            for y in 1..src.height - 1 {
+                for x in 1..src.width - 1 {
+                    let sum: u32 = (0..9)
+                        .map(|k| u32::from(src.get(x + k % 3 - 1, y + k / 3 - 1)))
+                        .sum();
+                    out.set(x, y, ((sum + 4) / 9) as u8);
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn refine_decode_aligns_perturbed_corners() {
+        let family = crate::family::tag16h5();
+        let (img, h) = build_decode_test_image(&family, 3, false);
+        let img = blurred(&img);
+        let truth =
+            [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| h.project(x, y));
+
+        let offsets = [[0.8, -0.5], [-0.3, 0.7], [0.6, 0.9], [-0.7, -0.4]];
+        let perturbed: [Vec2; 4] = std::array::from_fn(|i| {
+            Vec2::new(truth[i].0 + offsets[i][0], truth[i].1 + offsets[i][1])
+        });
+        let rough = Homography::from_quad_corners(&perturbed).unwrap();
+
+        let refined = refine_decode(&img, &family, 3, &rough).unwrap();
+        for (&[x, y], t) in [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+            .iter()
+            .zip(truth)
+        {
+            let (rx, ry) = refined.project(x, y);
+            assert!((rx - t.0).hypot(ry - t.1) < 0.02, "({rx}, {ry}) vs {t:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn refine_decode_needs_contrast_and_cell_size() {
+        let family = crate::family::tag16h5();
+        let (img, h) = build_decode_test_image(&family, 0, false);
+        assert!(refine_decode(&ImageU8::new(200, 200), &family, 0, &h).is_none());
+
+        // Cells of a tenth of their rendered size are under two pixels
+        let tiny = h.then_scale_translate(0.1, 0.1, 0.0, 0.0);
+        assert!(refine_decode(&img, &family, 0, &tiny).is_none());
+        assert!(refine_decode(&img, &family, family.codes.len(), &h).is_none());
+    }
+
     #[test]
     fn gray_model_solve_singular() {
         // With only a single observation, the system is rank-deficient;
@@ -509,6 +1498,36 @@ mod tests {
         let _result = decode_quad(&img, &family, &qd, &h, false, 0.0, &mut DecodeBufs::new());
     }
 
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn sample_points_flag_out_of_bounds_border_samples() {
+        let family = crate::family::tag16h5();
+        let corners = [[0.0, 0.0], [19.0, 0.0], [19.0, 19.0], [0.0, 19.0]].map(Vec2::from);
+        let h = Homography::from_quad_corners(&corners).unwrap();
+        let samples = sample_points(&family, &h, 20, 20);
+
+        let w = family.layout.border_width;
+        assert_eq!(samples.len(), 8 * w + family.layout.nbits);
+        let count = |kind: SampleKind| samples.iter().filter(|s| s.kind == kind).count();
+        assert_eq!(count(SampleKind::WhiteBorder), 4 * w);
+        assert_eq!(count(SampleKind::BlackBorder), 4 * w);
+        for (i, s) in samples[8 * w..].iter().enumerate() {
+            assert_eq!(s.kind, SampleKind::Bit(i));
+        }
+
+        // The white ring lies outside the quad, i.e. outside the image
+        for s in &samples {
+            let inside = s.x >= 0.0 && s.y >= 0.0 && s.x < 19.0 && s.y < 19.0;
+            assert_eq!(s.used, inside);
+            if s.kind == SampleKind::WhiteBorder {
+                assert!(!s.used);
+            }
+        }
+        assert!(samples
+            .iter()
+            .any(|s| s.kind == SampleKind::BlackBorder && s.used));
+    }
+
     #[test]
     #[cfg(feature = "family-tag16h5")]
     fn decode_quad_normal_border_wrong_polarity() {
@@ -522,17 +1541,19 @@ mod tests {
         assert!(result.is_none());
     }
 
+    const MODEL_TOL: Real = if cfg!(feature = "f32") { 1e-3 } else { 1e-6 };
+
     #[test]
     fn gray_model_constant_field() {
         let mut gm = GrayModel::default();
         for i in 0..10 {
             for j in 0..10 {
-                gm.add(i as f64, j as f64, 100.0);
+                gm.add(i as Real, j as Real, 100.0);
             }
         }
         gm.solve();
         let v = gm.interpolate(5.0, 5.0);
-        assert!((v - 100.0).abs() < 1e-6, "v={v}");
+        assert!((v - 100.0).abs() < MODEL_TOL, "v={v}");
     }
 
     #[test]
@@ -540,15 +1561,18 @@ mod tests {
         let mut gm = GrayModel::default();
         for i in 0..10 {
             for j in 0..10 {
-                let x = i as f64 / 10.0;
-                let y = j as f64 / 10.0;
+                let x = i as Real / 10.0;
+                let y = j as Real / 10.0;
                 gm.add(x, y, 50.0 * x + 30.0 * y + 10.0);
             }
         }
         gm.solve();
         let v = gm.interpolate(0.5, 0.5);
         let expected = 50.0 * 0.5 + 30.0 * 0.5 + 10.0;
-        assert!((v - expected).abs() < 1e-6, "v={v}, expected={expected}");
+        assert!(
+            (v - expected).abs() < MODEL_TOL,
+            "v={v}, expected={expected}"
+        );
     }
 
     #[test]
@@ -577,6 +1601,22 @@ mod tests {
         assert_eq!(m.hamming, 1);
     }
 
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn quick_decode_max_hamming_can_be_changed() {
+        let family = crate::family::tag16h5();
+        let mut qd = QuickDecode::new(&family, 2);
+        let corrupted = family.codes[0] ^ 1;
+
+        qd.set_max_hamming(0);
+        assert_eq!(qd.max_hamming(), 0);
+        assert!(qd.decode(&family, corrupted).is_none());
+        assert!(qd.decode(&family, family.codes[0]).is_some());
+
+        qd.set_max_hamming(1);
+        assert_eq!(qd.decode(&family, corrupted).unwrap().id, 0);
+    }
+
     #[test]
     #[cfg(feature = "family-tag36h11")]
     fn quick_decode_too_many_errors_returns_none() {
@@ -603,6 +1643,111 @@ mod tests {
         assert_eq!(m.hamming, 0);
     }
 
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn mirror_code_is_an_involution() {
+        let family = crate::family::tag16h5();
+        let perm = mirror_permutation(&family).unwrap();
+        for &code in &family.codes {
+            let m = mirror_code(code, &perm);
+            assert_eq!(m.count_ones(), code.count_ones());
+            assert_eq!(mirror_code(m, &perm), code);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn quick_decode_mirrored_code() {
+        let family = crate::family::tag36h11();
+        let perm = mirror_permutation(&family).unwrap();
+        let mirrored = hamming::rotate90(mirror_code(family.codes[7], &perm), 36);
+
+        assert!(QuickDecode::new(&family, 2)
+            .decode(&family, mirrored)
+            .is_none());
+
+        let qd = QuickDecode::with_mirrored(&family, 2);
+        assert!(qd.matches_mirrored());
+        let m = qd.decode(&family, mirrored).unwrap();
+        assert_eq!((m.id, m.hamming, m.mirrored), (7, 0, true));
+        assert_eq!(m.rotation, 3);
+
+        // Direct matches are still reported unmirrored
+        let m = qd.decode(&family, family.codes[7]).unwrap();
+        assert_eq!((m.id, m.mirrored), (7, false));
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn mirrored_lookup_is_chosen_per_decode() {
+        let family = crate::family::tag36h11();
+        let perm = mirror_permutation(&family).unwrap();
+        let mirrored = mirror_code(family.codes[7], &perm);
+
+        let qd = QuickDecode::new(&family, 2);
+        assert!(qd.decode_with(&family, mirrored, false, 2).is_none());
+        assert!(!qd.matches_mirrored());
+        let m = qd.decode_with(&family, mirrored, true, 2).unwrap();
+        assert_eq!((m.id, m.mirrored), (7, true));
+        assert!(qd.matches_mirrored());
+
+        // Tables built up front are not consulted when mirroring is off
+        let qd = QuickDecode::with_mirrored(&family, 2);
+        assert!(qd.decode_with(&family, mirrored, false, 2).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn mirrored_code_near_another_id_reads_as_that_id() {
+        // Tag 11 of tag16h5 seen in a mirror is 2 bits from tag 23; direct
+        // matches win, so it is reported as tag 23, not mirrored
+        let family = crate::family::tag16h5();
+        let perm = mirror_permutation(&family).unwrap();
+        let mirrored = mirror_code(family.codes[11], &perm);
+
+        let qd = QuickDecode::with_mirrored(&family, 2);
+        let m = qd.decode(&family, mirrored).unwrap();
+        assert_eq!((m.id, m.hamming, m.mirrored), (23, 2, false));
+
+        // Within 1 bit it is unambiguous
+        let qd = QuickDecode::with_mirrored(&family, 1);
+        let m = qd.decode(&family, mirrored).unwrap();
+        assert_eq!((m.id, m.hamming, m.mirrored), (11, 0, true));
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn quick_decode_heap_bytes() {
+        let family = crate::family::tag36h11();
+        // 4 chunks of 9 bits: 513 offsets and 587 ids each, all u16.
+        let direct = 4 * (513 + 587) * 2;
+        assert_eq!(QuickDecode::new(&family, 2).heap_bytes(), direct);
+        // Independent of the Hamming limit
+        assert_eq!(QuickDecode::new(&family, 3).heap_bytes(), direct);
+
+        let mirrored = direct + 587 * 8;
+        assert_eq!(
+            QuickDecode::with_mirrored(&family, 2).heap_bytes(),
+            direct + mirrored
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn quick_decode_skips_filtered_ids() {
+        let family = crate::family::tag36h11();
+        let qd = QuickDecode::with_ids(&family, 2, true, &IdFilter::only(0..=10));
+        let m = qd.decode(&family, family.codes[10] ^ 1).unwrap();
+        assert_eq!((m.id, m.hamming), (10, 1));
+        assert!(qd.decode(&family, family.codes[11]).is_none());
+        // Only the 11 allowed codes are indexed
+        assert!(qd.heap_bytes() < QuickDecode::with_mirrored(&family, 2).heap_bytes());
+
+        let qd = QuickDecode::with_ids(&family, 2, false, &IdFilter::Except(vec![5]));
+        assert!(qd.decode(&family, family.codes[5]).is_none());
+        assert_eq!(qd.decode(&family, family.codes[6]).unwrap().id, 6);
+    }
+
     #[test]
     #[cfg(feature = "family-tag36h11")]
     fn quick_decode_tag36h11() {
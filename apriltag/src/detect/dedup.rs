@@ -92,13 +92,19 @@ mod tests {
     use super::*;
 
     fn make_detection(id: i32, hamming: i32, margin: f32, corners: [[f64; 2]; 4]) -> Detection {
+        let corners = corners.map(Vec2::from);
         Detection {
             family_id: crate::family::FamilyId::from("test"),
             id,
             hamming,
             decision_margin: margin,
-            corners: corners.map(Vec2::from),
+            corners,
             center: Vec2::new(0.0, 0.0),
+            homography: crate::detect::homography::Homography::from_quad_corners(&corners).unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         }
     }
 
@@ -1,19 +1,38 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use smallvec::SmallVec;
 
-use crate::family::{FamilyId, TagFamily};
+use crate::error::{BuildError, DetectError};
+use crate::family::{self, FamilyId, TagFamily};
 
-use super::cluster::{gradient_clusters, Cluster};
+use super::banded::{banded_clusters, BandBuffers};
+use super::cluster::{gradient_clusters_in_place, Cluster, EdgeFilter};
 use super::connected::connected_components;
-use super::decode::{decode_quad, DecodeBufs, QuickDecode};
+#[cfg(feature = "debug-images")]
+use super::debug::{self, DebugImage, DebugOutput, DebugStage};
+use super::decode::{
+    refine_decode, refine_homography, sample_points, try_decode_quad_polarity, DecodeBufs,
+    IdFilter, QuickDecode,
+};
 use super::dedup::deduplicate;
-use super::geometry::Vec2;
+use super::geometry::{Mat3, Vec2};
 use super::homography::Homography;
-use super::image::{GrayImage, ImageU8};
+use super::hooks::{DetectorHooks, RejectReason};
+use super::image::{GrayImage, ImageRef, ImageU16, ImageU8, ToneMap};
 use super::par::Par;
-use super::preprocess::{apply_sigma, decimate};
-use super::quad::{fit_quads, Quad, QuadThreshParams};
+use super::preprocess::{
+    apply_sigma, extract_field, sigma_is_identity, DecimateMode, Decimation, InterlaceField,
+};
+use super::profile::{TimeProfile, HAS_CLOCK};
+use super::quad::{
+    bounding_quad, fit_quads_reporting, max_cluster_points, scale_covariance, validate_quad, Quad,
+    QuadParams,
+};
 use super::refine::refine_edges;
-use super::threshold::{threshold, ThresholdBuffers};
+use super::threshold::{exclude_region, threshold_tiled, ThresholdBuffers};
+use super::trace::stage;
 use super::unionfind::UnionFind;
 
 /// A detected AprilTag in an image.
@@ -53,38 +72,411 @@ use super::unionfind::UnionFind;
 /// assert_eq!(detections[0].id, 0);
 /// assert_eq!(detections[0].hamming, 0);
 /// ```
+///
+/// With the `serde` feature, detections serialize with the family name under
+/// `family` and the homography as a row-major 3×3 array, omitting `mirrored`
+/// when false and `refine_residual` when absent.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Detection {
+    #[cfg_attr(feature = "serde", serde(rename = "family"))]
     pub family_id: FamilyId,
     pub id: i32,
     pub hamming: i32,
     pub decision_margin: f32,
     pub corners: [Vec2; 4],
     pub center: Vec2,
+    /// Maps tag coordinates to image pixels: `(-1, -1)`, `(1, -1)`,
+    /// `(1, 1)` and `(-1, 1)` project to `corners` in order and `(0, 0)` to
+    /// `center`, so any point on the tag can be located, e.g. for overlays.
+    /// Includes the reflection of mirrored tags.
+    pub homography: Homography,
+    /// Whether the tag was seen mirrored (only with [`DetectorConfig::decode_mirrored`]).
+    ///
+    /// Corners are still reported in the tag's own frame, so they wind clockwise
+    /// in the image instead of counter-clockwise.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub mirrored: bool,
+    /// How well the quad's edges fit straight lines: the largest per-edge
+    /// mean squared error of the line fit, in pixels². Independent of the
+    /// decode margin; motion blur and lens distortion raise it.
+    pub line_fit_mse: f32,
+    /// Largest per-edge mean squared residual after edge refinement, in
+    /// pixels², or `None` if [`DetectorConfig::refine_edges`] is off.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub refine_residual: Option<f32>,
+    /// Covariance of each of `corners` as `[xx, xy, yy]`, in pixels²,
+    /// propagated from the residuals of the two edge fits meeting there.
+    /// Weights corners as measurements, e.g. in a pose graph; corners on
+    /// short, noisy or blurred edges are less certain.
+    pub corner_covariance: [[f64; 3]; 4],
+}
+
+impl Detection {
+    /// Scale corner and center coordinates by `(sx, sy)`, e.g. to map
+    /// detections on a [resized](super::image::resize) frame back to the
+    /// original image. Fit errors are scaled by `sx * sy`, which is exact for
+    /// uniform scaling, and corner covariances exactly.
+    ///
+    /// `convention` is the [`PixelConvention`] the coordinates are in, as
+    /// set by [`DetectorConfig::pixel_convention`]. Image edges, not pixel
+    /// centers, scale with the image: under [`PixelConvention::Center`] the
+    /// coordinates are moved to pixel corners, scaled and moved back, since
+    /// scaling them directly would be off by `(s - 1) / 2` pixels.
+    pub fn scale(&mut self, sx: f64, sy: f64, convention: PixelConvention) {
+        self.convert_pixels(convention, PixelConvention::Corner);
+        for p in self
+            .corners
+            .iter_mut()
+            .chain(std::iter::once(&mut self.center))
+        {
+            p.0[0] *= sx;
+            p.0[1] *= sy;
+        }
+        self.homography = self.homography.then_scale_translate(sx, sy, 0.0, 0.0);
+        let area = (sx * sy).abs() as f32;
+        self.line_fit_mse *= area;
+        self.refine_residual = self.refine_residual.map(|r| r * area);
+        for c in &mut self.corner_covariance {
+            scale_covariance(c, sx, sy);
+        }
+        self.convert_pixels(PixelConvention::Corner, convention);
+    }
+
+    /// Move corner and center coordinates from pixel convention `from` to
+    /// `to`, e.g. to compare with another implementation's output.
+    pub fn convert_pixels(&mut self, from: PixelConvention, to: PixelConvention) {
+        let shift = to.pixel_center() - from.pixel_center();
+        self.translate(shift, shift);
+    }
+
+    /// Shift corner and center coordinates by `(dx, dy)`, e.g. to map
+    /// detections in a crop back to the full image.
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        for p in self
+            .corners
+            .iter_mut()
+            .chain(std::iter::once(&mut self.center))
+        {
+            p.0[0] += dx;
+            p.0[1] += dy;
+        }
+        self.homography = self.homography.then_scale_translate(1.0, 1.0, dx, dy);
+    }
+}
+
+/// Which point of a pixel integer image coordinates refer to.
+///
+/// The pipeline works with integer coordinates at pixel corners, as the C
+/// library reports them: pixel `(i, j)` covers `[i, i + 1] × [j, j + 1]` and
+/// its center is `(i + 0.5, j + 0.5)`. OpenCV, ROS and most calibration
+/// tools put integer coordinates at pixel centers instead. Detections and
+/// camera intrinsics must agree, or poses are biased by the half-pixel
+/// shift of the principal point.
+///
+/// ```
+/// use apriltag::detect::geometry::Vec2;
+/// use apriltag::PixelConvention;
+///
+/// let corner = Vec2::new(10.5, 20.5);
+/// let center = PixelConvention::Corner.convert(corner, PixelConvention::Center);
+/// assert_eq!(center, Vec2::new(10.0, 20.0));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PixelConvention {
+    /// Integer coordinates at pixel corners (the C library's convention).
+    #[default]
+    Corner,
+    /// Integer coordinates at pixel centers (OpenCV's convention).
+    Center,
+}
+
+impl PixelConvention {
+    /// The coordinate of the center of pixel 0 along either axis.
+    pub fn pixel_center(self) -> f64 {
+        match self {
+            Self::Corner => 0.5,
+            Self::Center => 0.0,
+        }
+    }
+
+    /// Convert point `p` from this convention to `to`.
+    pub fn convert(self, p: Vec2, to: PixelConvention) -> Vec2 {
+        let shift = to.pixel_center() - self.pixel_center();
+        Vec2::new(p[0] + shift, p[1] + shift)
+    }
+}
+
+/// Which way round black and white are printed on the tags to detect.
+///
+/// Tags printed or engraved on dark materials come out with white cells on
+/// a black substrate. Rather than generating an inverted family, detect
+/// them with [`Polarity::Inverted`], or [`Polarity::Either`] to find both
+/// kinds in the same frame.
+///
+/// ```
+/// use apriltag::{Detector, Polarity};
+///
+/// let detector = Detector::builder().polarity(Polarity::Either).build();
+/// assert_eq!(detector.config.polarity, Polarity::Either);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Polarity {
+    /// As the family is rendered: black cells on white.
+    #[default]
+    Normal,
+    /// Black and white swapped throughout, including the borders.
+    Inverted,
+    /// Both normal and inverted prints.
+    Either,
+}
+
+impl Polarity {
+    /// Whether tags printed normally (`inverted == false`) or inverted are
+    /// detected.
+    pub fn accepts(self, inverted: bool) -> bool {
+        match self {
+            Self::Normal => !inverted,
+            Self::Inverted => inverted,
+            Self::Either => true,
+        }
+    }
+}
+
+/// Per-frame decode statistics, from [`Detector::detect_with_info`].
+#[derive(Debug, Clone, Default)]
+pub struct DetectInfo {
+    /// One entry per added family, in the order the families were added.
+    pub families: Vec<FamilyDecodeInfo>,
+    /// Why the frame was not searched for tags, if it was skipped.
+    pub skipped: Option<SkipReason>,
+    /// Clusters dropped without fitting a quad because the frame had more
+    /// than [`DetectorConfig::max_clusters`].
+    pub truncated_clusters: usize,
+}
+
+/// Why a frame was skipped after preprocessing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The preprocessed frame's brightest and darkest pixels differ by less
+    /// than the quad threshold's minimum white-black difference, so no pixel
+    /// could be classified as black or white. Typical of a covered lens (all
+    /// dark) or a saturated sensor (all bright).
+    LowDynamicRange { min: u8, max: u8 },
+}
+
+/// Decode cost and yield of one family in one frame.
+///
+/// With several families registered, a family with a high `decode_time` and
+/// no `detections` across a representative run is a candidate for removal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyDecodeInfo {
+    pub family: FamilyId,
+    /// Quads with a border polarity this family accepts (see
+    /// [`DetectorConfig::polarity`]) that were decoded against it.
+    pub attempts: usize,
+    /// Attempts that matched a code, before deduplication.
+    pub matches: usize,
+    /// Detections of this family that survived deduplication.
+    pub detections: usize,
+    /// Time spent decoding this family's attempts, summed across threads.
+    /// Always zero on `wasm32-unknown-unknown`, which has no clock.
+    pub decode_time: Duration,
+}
+
+/// Shared per-family counters, updated from decode worker threads.
+#[derive(Default)]
+struct FamilyCounters {
+    attempts: AtomicUsize,
+    matches: AtomicUsize,
+    nanos: AtomicU64,
+}
+
+/// Counters behind [`DetectInfo`].
+struct FrameCounters {
+    families: Vec<FamilyCounters>,
+    truncated_clusters: AtomicUsize,
 }
 
 /// Detector configuration.
+///
+/// With the `serde` feature, missing fields take their default values, so a
+/// config file need only list what it changes.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct DetectorConfig {
     pub quad_decimate: f32,
+    /// How integer `quad_decimate` factors pick each working-image pixel
+    /// (default: [`DecimateMode::Point`]). [`DecimateMode::Average`] keeps
+    /// small tags detectable at factors of 3 and above.
+    pub decimate_mode: DecimateMode,
     pub quad_sigma: f32,
     pub refine_edges: bool,
     pub decode_sharpening: f64,
-    pub qtp: QuadThreshParams,
+    /// Drop decodes whose [`Detection::decision_margin`] is below this
+    /// (default: 0.0, keep all). Such quads are reported as
+    /// [`RejectReason::LowDecisionMargin`].
+    pub min_decision_margin: f32,
+    /// Also match mirror images of tags (default: false). Read when each
+    /// quad is decoded, so it can be changed after families are added;
+    /// families added while it is set build their mirrored lookup tables
+    /// up front, the others on the first mirrored lookup.
+    ///
+    /// Mirrored codes are only tried when no direct code matches. A
+    /// mirrored tag whose reflected code is within the family's Hamming
+    /// distance of another tag's code is reported as that other tag, not
+    /// mirrored; use a smaller Hamming distance, or an [`IdFilter`], to
+    /// reduce the chance of this.
+    pub decode_mirrored: bool,
+    /// Quad detection parameters, converted to the working resolution at detect time.
+    pub quad: QuadParams,
+    /// Largest accepted input width or height (default: no limit beyond
+    /// [`MAX_WORKING_DIMENSION`] after decimation).
+    pub max_image_dimension: Option<u32>,
+    /// Fit quads to at most this many clusters per frame (default: no
+    /// limit). Heavy noise can produce thousands of clusters and blow a
+    /// latency budget; past the limit the smallest are dropped, keeping the
+    /// largest that can still be fitted, where tags are. Dropped clusters
+    /// are counted in [`DetectInfo::truncated_clusters`] and reported as
+    /// [`RejectReason::TooManyClusters`].
+    pub max_clusters: Option<usize>,
+    /// Re-estimate each decoded tag's homography from its cell edges and
+    /// report corners from it (default: false). See
+    /// [`refine_homography`](super::decode::refine_homography).
+    pub refine_homography: bool,
+    /// Align each decoded tag's known bit pattern with the image and report
+    /// corners from the aligned homography (default: false). Runs after
+    /// [`refine_homography`](Self::refine_homography) if both are set. See
+    /// [`refine_decode`](super::decode::refine_decode).
+    pub refine_decode: bool,
+    /// Threads to run the pipeline on (default: `None`, the current rayon
+    /// pool). `Some(1)` runs on the calling thread only; larger counts give
+    /// the detector its own pool. Read only when the detector is
+    /// constructed: changing it on an existing detector has no effect, use
+    /// [`Detector::set_thread_pool`] instead. Has no effect without the
+    /// `parallel` feature.
+    pub num_threads: Option<usize>,
+    /// Threshold, segment and cluster the working image this many rows at a
+    /// time instead of through whole-image intermediates (default: `None`).
+    /// Cuts the memory of those stages to a few bands of rows, for large
+    /// frames on memory-constrained devices, at some cost in speed; the
+    /// detections are the same. No thresholded debug image is produced.
+    pub band_rows: Option<u32>,
+    /// Detect on one field of interlaced frames only (default: `None`, the
+    /// whole frame). Analog capture of moving scenes combs every edge,
+    /// since the two fields are exposed at different times; a single
+    /// field is clean at half the vertical resolution. Its pixels are
+    /// fitted as twice as tall as they are wide, on top of
+    /// [`QuadParams::pixel_aspect_ratio`], and detections are reported in
+    /// frame coordinates. Hooks and debug images see the field image.
+    pub interlace_field: Option<InterlaceField>,
+    /// Where integer coordinates fall on a pixel in reported detections and
+    /// quads (default: [`PixelConvention::Corner`], like the C library).
+    /// Hooks and debug images always use the corner convention.
+    pub pixel_convention: PixelConvention,
+    /// Detect tags printed normally, inverted (white on black), or either
+    /// (default: [`Polarity::Normal`]). Inverting a tag also reverses its
+    /// border, so each family is matched against quads of the opposite
+    /// [`Quad::reversed_border`].
+    pub polarity: Polarity,
 }
 
 impl Default for DetectorConfig {
     fn default() -> Self {
         Self {
             quad_decimate: 2.0,
+            decimate_mode: DecimateMode::Point,
             quad_sigma: 0.0,
             refine_edges: true,
             decode_sharpening: 0.25,
-            qtp: QuadThreshParams::default(),
+            min_decision_margin: 0.0,
+            decode_mirrored: false,
+            quad: QuadParams::default(),
+            max_image_dimension: None,
+            max_clusters: None,
+            refine_homography: false,
+            refine_decode: false,
+            num_threads: None,
+            band_rows: None,
+            interlace_field: None,
+            pixel_convention: PixelConvention::Corner,
+            polarity: Polarity::Normal,
         }
     }
 }
 
+impl DetectorConfig {
+    /// Check that every field is in range, as
+    /// [`DetectorBuilder::try_build`] does.
+    ///
+    /// ```
+    /// use apriltag::DetectorConfig;
+    ///
+    /// assert!(DetectorConfig::default().validate().is_ok());
+    /// let config = DetectorConfig { quad_sigma: f32::NAN, ..Default::default() };
+    /// assert!(config.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), BuildError> {
+        let check = |ok: bool, field, reason| {
+            if ok {
+                Ok(())
+            } else {
+                Err(BuildError::InvalidConfig { field, reason })
+            }
+        };
+        check(
+            self.quad_decimate >= 1.0 && self.quad_decimate.is_finite(),
+            "quad_decimate",
+            "must be at least 1",
+        )?;
+        check(self.quad_sigma.is_finite(), "quad_sigma", "must be finite")?;
+        check(
+            self.decode_sharpening.is_finite(),
+            "decode_sharpening",
+            "must be finite",
+        )?;
+        check(
+            !self.min_decision_margin.is_nan(),
+            "min_decision_margin",
+            "must be a number",
+        )?;
+        check(
+            self.quad.pixel_aspect_ratio > 0.0 && self.quad.pixel_aspect_ratio.is_finite(),
+            "quad.pixel_aspect_ratio",
+            "must be positive",
+        )?;
+        check(
+            self.quad.tile_size != Some(0),
+            "quad.tile_size",
+            "must be positive",
+        )?;
+        check(
+            self.num_threads != Some(0),
+            "num_threads",
+            "must be positive",
+        )?;
+        check(self.band_rows != Some(0), "band_rows", "must be positive")
+    }
+}
+
+/// Largest width or height of the image after decimation.
+///
+/// Cluster points store doubled pixel coordinates in 16 bits. This also keeps
+/// the pixel count, used for union-find IDs, within `u32`.
+pub const MAX_WORKING_DIMENSION: u32 = 32768;
+
 /// Reusable buffers for [`Detector::detect`].
 ///
 /// Holds pre-allocated buffers that are reused across consecutive `detect` calls,
@@ -113,9 +505,16 @@ pub struct DetectorBuffers {
     threshed: ImageU8,
     threshold_bufs: ThresholdBuffers,
     uf: UnionFind,
+    banded: BandBuffers,
     cluster_map: super::cluster::ClusterMap,
     clusters: Vec<Cluster>,
     quads: Vec<Quad>,
+    tone_mapped: ImageU8,
+    tone_hist: Vec<u32>,
+    field: ImageU8,
+    /// Region mask at the working resolution, or of one field.
+    region: ImageU8,
+    region_field: ImageU8,
 }
 
 impl DetectorBuffers {
@@ -128,9 +527,15 @@ impl DetectorBuffers {
             threshed: ImageU8::new(0, 0),
             threshold_bufs: ThresholdBuffers::new(),
             uf: UnionFind::empty(),
+            banded: BandBuffers::new(),
             cluster_map: super::cluster::ClusterMap::new(),
             clusters: Vec::new(),
             quads: Vec::new(),
+            tone_mapped: ImageU8::new(0, 0),
+            tone_hist: Vec::new(),
+            field: ImageU8::new(0, 0),
+            region: ImageU8::new(0, 0),
+            region_field: ImageU8::new(0, 0),
         }
     }
 }
@@ -144,7 +549,7 @@ impl Default for DetectorBuffers {
 /// Builder for constructing a [`Detector`] with a fluent API.
 ///
 /// Exposes the most commonly used configuration parameters as chainable methods.
-/// For expert-only parameters (e.g. `min_cluster_pixels`, `cos_critical_rad`),
+/// For expert-only parameters (e.g. `min_cluster_pixels`, `critical_angle_deg`),
 /// use [`Detector::new`] with a [`DetectorConfig`] directly.
 ///
 /// ```
@@ -156,9 +561,30 @@ impl Default for DetectorBuffers {
 ///     .quad_decimate(1.0)
 ///     .build();
 /// ```
+///
+/// [`try_build`](Self::try_build) reports invalid settings and unknown
+/// family names instead of panicking:
+///
+/// ```
+/// use apriltag::Detector;
+///
+/// let detector = Detector::builder()
+///     .quad_decimate(2.0)
+///     .quad_sigma(0.8)
+///     .family("tag36h11", 2)
+///     .try_build()?;
+/// # Ok::<(), apriltag::error::BuildError>(())
+/// ```
 pub struct DetectorBuilder {
     config: DetectorConfig,
-    families: Vec<(TagFamily, u32)>,
+    families: Vec<(TagFamily, u32, IdFilter)>,
+    /// The first family name that did not resolve, reported by `try_build`
+    error: Option<BuildError>,
+    hooks: Option<Arc<dyn DetectorHooks>>,
+    #[cfg(feature = "debug-images")]
+    debug: Option<Arc<dyn DebugOutput>>,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
 }
 
 impl DetectorBuilder {
@@ -167,15 +593,41 @@ impl DetectorBuilder {
         Self {
             config: DetectorConfig::default(),
             families: Vec::new(),
+            error: None,
+            hooks: None,
+            #[cfg(feature = "debug-images")]
+            debug: None,
+            #[cfg(feature = "parallel")]
+            pool: None,
+        }
+    }
+
+    /// Start from `config` instead of the defaults, e.g. one loaded from a
+    /// file or assembled from command-line options.
+    pub fn with_config(config: DetectorConfig) -> Self {
+        Self {
+            config,
+            ..Self::new()
         }
     }
 
     /// Set the decimation factor for input images (default: 2.0).
+    ///
+    /// Integer factors keep every n-th pixel; 1.5 averages each 3×3 block
+    /// down to 2×2 pixels, as in the C library. Other fractions are
+    /// truncated.
     pub fn quad_decimate(mut self, v: f32) -> Self {
         self.config.quad_decimate = v;
         self
     }
 
+    /// Set how integer decimation factors pick each pixel (default:
+    /// [`DecimateMode::Point`]).
+    pub fn decimate_mode(mut self, v: DecimateMode) -> Self {
+        self.config.decimate_mode = v;
+        self
+    }
+
     /// Set the Gaussian blur sigma (default: 0.0, 0 = no blur).
     pub fn quad_sigma(mut self, v: f32) -> Self {
         self.config.quad_sigma = v;
@@ -194,25 +646,210 @@ impl DetectorBuilder {
         self
     }
 
+    /// Drop decodes with a decision margin below `v` (default: 0.0).
+    /// See [`DetectorConfig::min_decision_margin`].
+    pub fn min_decision_margin(mut self, v: f32) -> Self {
+        self.config.min_decision_margin = v;
+        self
+    }
+
+    /// Also match mirror images of tags (default: false).
+    pub fn decode_mirrored(mut self, v: bool) -> Self {
+        self.config.decode_mirrored = v;
+        self
+    }
+
     /// Enable or disable deglitching (default: false).
     pub fn deglitch(mut self, v: bool) -> Self {
-        self.config.qtp.deglitch = v;
+        self.config.quad.deglitch = v;
+        self
+    }
+
+    /// Set the minimum local contrast for a threshold tile to be classified
+    /// (default: 5). See [`QuadParams::min_white_black_diff`].
+    pub fn min_white_black_diff(mut self, v: u8) -> Self {
+        self.config.quad.min_white_black_diff = v;
+        self
+    }
+
+    /// Set the adaptive-threshold tile side in full-resolution pixels
+    /// (default: 4 working-resolution pixels). See [`QuadParams::tile_size`].
+    pub fn tile_size(mut self, v: u32) -> Self {
+        self.config.quad.tile_size = Some(v);
+        self
+    }
+
+    /// Re-estimate decoded homographies from the cell edges (default: false).
+    pub fn refine_homography(mut self, v: bool) -> Self {
+        self.config.refine_homography = v;
+        self
+    }
+
+    /// Align decoded tags' bit patterns with the image (default: false).
+    pub fn refine_decode(mut self, v: bool) -> Self {
+        self.config.refine_decode = v;
+        self
+    }
+
+    /// Reject input images wider or taller than `v` pixels (default: no limit).
+    pub fn max_image_dimension(mut self, v: u32) -> Self {
+        self.config.max_image_dimension = Some(v);
+        self
+    }
+
+    /// Run the pipeline on `v` threads (default: the current rayon pool).
+    /// See [`DetectorConfig::num_threads`].
+    pub fn num_threads(mut self, v: usize) -> Self {
+        self.config.num_threads = Some(v);
+        self
+    }
+
+    /// Fit quads to at most `v` clusters per frame (default: no limit).
+    /// See [`DetectorConfig::max_clusters`].
+    pub fn max_clusters(mut self, v: usize) -> Self {
+        self.config.max_clusters = Some(v);
+        self
+    }
+
+    /// Threshold, segment and cluster in bands of `v` rows (default: whole
+    /// image). See [`DetectorConfig::band_rows`].
+    pub fn band_rows(mut self, v: u32) -> Self {
+        self.config.band_rows = Some(v);
+        self
+    }
+
+    /// Detect on one field of interlaced frames only. See
+    /// [`DetectorConfig::interlace_field`].
+    pub fn interlace_field(mut self, v: InterlaceField) -> Self {
+        self.config.interlace_field = Some(v);
+        self
+    }
+
+    /// Report coordinates with integer values at pixel corners or centers
+    /// (default: corners). See [`DetectorConfig::pixel_convention`].
+    pub fn pixel_convention(mut self, v: PixelConvention) -> Self {
+        self.config.pixel_convention = v;
+        self
+    }
+
+    /// Detect normal, inverted or both kinds of prints (default: normal).
+    /// See [`DetectorConfig::polarity`].
+    pub fn polarity(mut self, v: Polarity) -> Self {
+        self.config.polarity = v;
+        self
+    }
+
+    /// Run the pipeline on `pool` instead of the current rayon pool, e.g.
+    /// to share a bounded pool with the rest of an application. Takes
+    /// precedence over [`num_threads`](Self::num_threads).
+    #[cfg(feature = "parallel")]
+    pub fn thread_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
         self
     }
 
     /// Add a tag family with the given maximum Hamming distance.
     pub fn add_family(mut self, family: TagFamily, max_hamming: u32) -> Self {
-        self.families.push((family, max_hamming));
+        self.families.push((family, max_hamming, IdFilter::All));
+        self
+    }
+
+    /// Add the built-in family named `name` (see
+    /// [`builtin_family`](crate::family::builtin_family)) with the given
+    /// maximum Hamming distance. An unknown name fails the build.
+    pub fn family(mut self, name: &str, max_hamming: u32) -> Self {
+        match family::builtin_family(name) {
+            Some(family) => self.families.push((family, max_hamming, IdFilter::All)),
+            None => {
+                self.error
+                    .get_or_insert_with(|| BuildError::UnknownFamily(name.to_string()));
+            }
+        }
+        self
+    }
+
+    /// Add a tag family, decoding only the IDs `ids` allows. See
+    /// [`Detector::add_family_with_ids`].
+    pub fn add_family_with_ids(
+        mut self,
+        family: TagFamily,
+        max_hamming: u32,
+        ids: IdFilter,
+    ) -> Self {
+        self.families.push((family, max_hamming, ids));
+        self
+    }
+
+    /// Install per-stage [`DetectorHooks`] (default: none).
+    pub fn hooks(mut self, hooks: Arc<dyn DetectorHooks>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Hand images of intermediate stages to `debug` (default: none).
+    #[cfg(feature = "debug-images")]
+    pub fn debug_output(mut self, debug: Arc<dyn DebugOutput>) -> Self {
+        self.debug = Some(debug);
         self
     }
 
     /// Build the detector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a setting is out of range or a family is unknown or has no
+    /// codes; see [`try_build`](Self::try_build).
     pub fn build(self) -> Detector {
-        let mut detector = Detector::new(self.config);
-        for (family, max_hamming) in self.families {
-            detector.add_family(family, max_hamming);
+        self.try_build().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Build the detector, or report the first problem: a family name
+    /// [`family`](Self::family) did not know, a family without codes, a
+    /// setting [`DetectorConfig::validate`] rejects, or a thread pool for
+    /// [`num_threads`](Self::num_threads) that could not be built.
+    pub fn try_build(self) -> Result<Detector, BuildError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.config.validate()?;
+        if let Some((family, ..)) = self.families.iter().find(|f| f.0.codes.is_empty()) {
+            return Err(BuildError::EmptyFamily(family.config.name.to_string()));
         }
-        detector
+        #[cfg(feature = "parallel")]
+        let pool = match self.pool {
+            Some(pool) => Some(pool),
+            None => build_pool(self.config.num_threads)
+                .map_err(|err| BuildError::ThreadPool(err.to_string()))?,
+        };
+        let mut detector = Detector::with_pool(
+            self.config,
+            #[cfg(feature = "parallel")]
+            pool,
+        );
+        detector.hooks = self.hooks;
+        #[cfg(feature = "debug-images")]
+        {
+            detector.debug = self.debug;
+        }
+        for (family, max_hamming, ids) in self.families {
+            detector.add_family_with_ids(family, max_hamming, ids);
+        }
+        Ok(detector)
+    }
+}
+
+/// The detector's own thread pool for `num_threads`, if it asks for more
+/// than one thread.
+#[cfg(feature = "parallel")]
+fn build_pool(
+    num_threads: Option<usize>,
+) -> Result<Option<Arc<rayon::ThreadPool>>, rayon::ThreadPoolBuildError> {
+    match num_threads {
+        Some(n) if n > 1 => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map(|pool| Some(Arc::new(pool))),
+        _ => Ok(None),
     }
 }
 
@@ -224,6 +861,11 @@ impl Default for DetectorBuilder {
 
 /// An AprilTag detector with pre-built lookup tables.
 ///
+/// Cloning is cheap: families and their lookup tables are shared between
+/// clones, while all per-frame scratch state lives in [`DetectorBuffers`].
+/// This makes one detector (and one set of buffers) per thread practical
+/// for batch processing.
+///
 /// ```
 /// use apriltag::Detector;
 /// use apriltag::family;
@@ -232,9 +874,26 @@ impl Default for DetectorBuilder {
 ///     .add_family(family::tag36h11(), 2)
 ///     .build();
 /// ```
+#[derive(Clone)]
 pub struct Detector {
     pub config: DetectorConfig,
-    families: Vec<(TagFamily, QuickDecode)>,
+    families: Vec<AddedFamily>,
+    hooks: Option<Arc<dyn DetectorHooks>>,
+    #[cfg(feature = "debug-images")]
+    debug: Option<Arc<dyn DebugOutput>>,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+    /// Whether [`DetectorConfig::num_threads`] was `Some(1)` at construction.
+    #[cfg(feature = "parallel")]
+    sequential: bool,
+}
+
+/// A family added to a [`Detector`]: the family and its lookup tables,
+/// shared between clones, and this detector's maximum Hamming distance.
+#[derive(Clone)]
+struct AddedFamily {
+    tables: Arc<(TagFamily, QuickDecode)>,
+    max_hamming: u32,
 }
 
 impl Detector {
@@ -252,17 +911,156 @@ impl Detector {
     }
 
     /// Create a new detector with the given configuration.
+    ///
+    /// If [`num_threads`](DetectorConfig::num_threads) asks for more than one
+    /// thread, the detector's thread pool is built here; should that fail,
+    /// the current rayon pool is used instead.
+    /// [`DetectorBuilder::try_build`] reports the failure instead.
     pub fn new(config: DetectorConfig) -> Self {
+        #[cfg(feature = "parallel")]
+        let pool = build_pool(config.num_threads).ok().flatten();
+        Self::with_pool(
+            config,
+            #[cfg(feature = "parallel")]
+            pool,
+        )
+    }
+
+    fn with_pool(
+        config: DetectorConfig,
+        #[cfg(feature = "parallel")] pool: Option<Arc<rayon::ThreadPool>>,
+    ) -> Self {
         Self {
+            #[cfg(feature = "parallel")]
+            sequential: config.num_threads == Some(1),
             config,
             families: Vec::new(),
+            hooks: None,
+            #[cfg(feature = "debug-images")]
+            debug: None,
+            #[cfg(feature = "parallel")]
+            pool,
         }
     }
 
+    /// Run the pipeline on `pool`, or with `None`, on the current rayon pool
+    /// (or the calling thread only, if
+    /// [`num_threads`](DetectorConfig::num_threads) was `Some(1)` when the
+    /// detector was constructed).
+    #[cfg(feature = "parallel")]
+    pub fn set_thread_pool(&mut self, pool: Option<Arc<rayon::ThreadPool>>) {
+        self.pool = pool;
+    }
+
+    /// Install or remove per-stage [`DetectorHooks`].
+    pub fn set_hooks(&mut self, hooks: Option<Arc<dyn DetectorHooks>>) {
+        self.hooks = hooks;
+    }
+
+    /// Install or remove a [`DebugOutput`] for intermediate stage images.
+    #[cfg(feature = "debug-images")]
+    pub fn set_debug_output(&mut self, debug: Option<Arc<dyn DebugOutput>>) {
+        self.debug = debug;
+    }
+
     /// Add a tag family to the detector with the given maximum Hamming distance.
     pub fn add_family(&mut self, family: TagFamily, max_hamming: u32) {
-        let qd = QuickDecode::new(&family, max_hamming);
-        self.families.push((family, qd));
+        self.add_family_with_ids(family, max_hamming, IdFilter::All);
+    }
+
+    /// Add a tag family, decoding only the IDs `ids` allows.
+    ///
+    /// When only a few IDs are deployed, leaving the rest out avoids false
+    /// positives from misreads that land on them, and each lookup has fewer
+    /// candidate codes to compare.
+    ///
+    /// ```
+    /// use apriltag::{family, Detector, IdFilter};
+    ///
+    /// let det = Detector::builder()
+    ///     .add_family_with_ids(family::tag36h11(), 2, IdFilter::only(0..=10))
+    ///     .build();
+    /// ```
+    pub fn add_family_with_ids(&mut self, family: TagFamily, max_hamming: u32, ids: IdFilter) {
+        let qd = QuickDecode::with_ids(&family, max_hamming, self.config.decode_mirrored, &ids);
+        self.families.push(AddedFamily {
+            tables: Arc::new((family, qd)),
+            max_hamming,
+        });
+    }
+
+    /// Remove every added family named `family`, keeping the others and
+    /// their lookup tables. Returns `false` if no such family was added.
+    ///
+    /// To swap a family for another, remove it and
+    /// [`add_family`](Self::add_family) the replacement; only the new
+    /// family's tables are built.
+    ///
+    /// ```
+    /// use apriltag::{family, Detector};
+    ///
+    /// let mut det = Detector::builder()
+    ///     .add_family(family::tag36h11(), 2)
+    ///     .add_family(family::tag16h5(), 0)
+    ///     .build();
+    /// assert!(det.remove_family("tag36h11"));
+    /// assert!(!det.remove_family("tag36h11"));
+    /// let names: Vec<_> = det.families().map(|f| f.config.name.to_string()).collect();
+    /// assert_eq!(names, ["tag16h5"]);
+    /// ```
+    pub fn remove_family(&mut self, family: &str) -> bool {
+        let before = self.families.len();
+        self.families
+            .retain(|entry| entry.tables.0.config.name != family);
+        self.families.len() != before
+    }
+
+    /// Remove all added families. Detection finds no tags until families
+    /// are added again.
+    pub fn clear_families(&mut self) {
+        self.families.clear();
+    }
+
+    /// The added families, in the order they were added.
+    pub fn families(&self) -> impl ExactSizeIterator<Item = &TagFamily> + '_ {
+        self.families.iter().map(|entry| &entry.tables.0)
+    }
+
+    /// Change the maximum Hamming distance of the added family named
+    /// `family`, without rebuilding its lookup tables. Returns `false` if
+    /// no such family was added.
+    ///
+    /// Only this detector is affected: clones keep sharing the tables but
+    /// not the limit.
+    ///
+    /// Small families such as tag16h5 have few bits between codes, so they
+    /// need a stricter limit than large ones to avoid false positives.
+    ///
+    /// ```
+    /// use apriltag::{family, Detector};
+    ///
+    /// let mut det = Detector::builder()
+    ///     .add_family(family::tag36h11(), 2)
+    ///     .add_family(family::tag16h5(), 2)
+    ///     .build();
+    /// assert!(det.set_max_hamming("tag16h5", 0));
+    /// assert!(!det.set_max_hamming("tag25h9", 0));
+    /// ```
+    pub fn set_max_hamming(&mut self, family: &str, max_hamming: u32) -> bool {
+        let mut found = false;
+        for entry in &mut self.families {
+            if entry.tables.0.config.name == family {
+                entry.max_hamming = max_hamming;
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// Heap memory held by the decode lookup tables of all added families,
+    /// in bytes. See [`QuickDecode::heap_bytes`].
+    pub fn decode_table_bytes(&self) -> usize {
+        self.families.iter().map(|f| f.tables.1.heap_bytes()).sum()
     }
 
     /// Detect tags in a grayscale image, reusing buffers to avoid per-frame allocation.
@@ -271,155 +1069,1068 @@ impl Detector {
     /// with the same (or smaller) image dimensions, no allocation occurs.
     ///
     /// Accepts any type implementing [`GrayImage`], including borrowed [`ImageRef`](super::ImageRef)
-    /// for zero-copy detection from a `&[u8]` slice.
+    /// for zero-copy detection from a `&[u8]` slice with any row stride. With
+    /// `quad_decimate` 1 and no `quad_sigma` filtering, the image is not
+    /// copied at all.
+    ///
+    /// # Video frames
+    ///
+    /// Detection only uses luma. For YUV 4:2:0 frames, wrap the Y plane with
+    /// [`ImageRef::from_nv12`](super::ImageRef::from_nv12) or
+    /// [`ImageRef::from_i420`](super::ImageRef::from_i420) and pass it
+    /// directly; no repacking is needed:
+    ///
+    /// ```
+    /// use apriltag::{Detector, DetectorBuffers, ImageRef};
+    ///
+    /// # let (width, height, stride) = (320, 240, 320);
+    /// # let frame = vec![128u8; stride * height * 3 / 2];
+    /// let det = Detector::builder()
+    ///     .add_family(apriltag::family::tag36h11(), 2)
+    ///     .build();
+    /// let img = ImageRef::from_nv12(width as u32, height as u32, stride as u32, &frame);
+    /// let detections = det.detect(&img, &mut DetectorBuffers::new());
+    /// # assert!(detections.is_empty());
+    /// ```
+    ///
+    /// Packed formats such as YUYV interleave luma with chroma and must be
+    /// copied into an [`ImageU8`] first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large; see [`try_detect`](Self::try_detect).
     pub fn detect(
         &self,
         img: &(impl GrayImage + Sync),
         buffers: &mut DetectorBuffers,
     ) -> Vec<Detection> {
-        let f = self.config.quad_decimate as u32;
-
-        // Stage 1: Preprocess
-        decimate(img, f, &mut buffers.decimated);
-        apply_sigma(
-            &buffers.decimated,
-            self.config.quad_sigma,
-            &mut buffers.filtered,
-            &mut buffers.blur_tmp,
-        );
+        self.try_detect(img, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        // Save filtered dimensions
-        let filtered_w = buffers.filtered.width;
-        let filtered_h = buffers.filtered.height;
+    /// Detect tags in a high-bit-depth frame, converting it to 8 bits with
+    /// `tone_map` first. The converted frame is kept in `buffers`, so
+    /// repeated calls do not allocate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large; see
+    /// [`try_detect_u16`](Self::try_detect_u16).
+    pub fn detect_u16(
+        &self,
+        img: &ImageU16,
+        tone_map: ToneMap,
+        buffers: &mut DetectorBuffers,
+    ) -> Vec<Detection> {
+        self.try_detect_u16(img, tone_map, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        // Stage 2: Threshold
-        threshold(
-            &buffers.filtered,
-            self.config.qtp.min_white_black_diff,
-            self.config.qtp.deglitch,
-            &mut buffers.threshed,
-            &mut buffers.threshold_bufs,
-        );
+    /// Like [`detect_u16`](Self::detect_u16), but fails instead of panicking
+    /// if the image is too large (see [`try_detect`](Self::try_detect)).
+    /// The size is checked before the frame is converted.
+    pub fn try_detect_u16(
+        &self,
+        img: &ImageU16,
+        tone_map: ToneMap,
+        buffers: &mut DetectorBuffers,
+    ) -> Result<Vec<Detection>, DetectError> {
+        self.checked_decimation(img.width, img.height)?;
+        let mut frame = std::mem::replace(&mut buffers.tone_mapped, ImageU8::new(0, 0));
+        img.to_u8_with(tone_map, &mut frame, &mut buffers.tone_hist);
+        let detections = self.try_detect(&frame, buffers);
+        buffers.tone_mapped = frame;
+        detections
+    }
 
-        // Stage 3: Connected components
-        connected_components(&buffers.threshed, &mut buffers.uf);
+    /// Like [`detect`](Self::detect), but fails instead of panicking if the
+    /// image exceeds [`DetectorConfig::max_image_dimension`] or is still
+    /// larger than [`MAX_WORKING_DIMENSION`] after decimation.
+    pub fn try_detect(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Result<Vec<Detection>, DetectError> {
+        let (_, dec) = self.checked_decimation(img.width(), img.height())?;
+        Ok(self
+            .detect_unchecked(img, dec, buffers, None, None, None)
+            .unwrap_or_default())
+    }
 
-        // Stage 4: Gradient clustering
-        gradient_clusters(
-            &buffers.threshed,
-            &mut buffers.uf,
-            self.config.qtp.min_cluster_pixels as u32,
-            &mut buffers.cluster_map,
-            &mut buffers.clusters,
-        );
+    /// Like [`detect`](Self::detect), but only search where `mask` is 128 or
+    /// above; pixels below are left out of thresholding and clustering, so
+    /// no quad forms there. Excluding known static structure, such as the
+    /// robot's own chassis or a timestamp overlay, stops it from producing
+    /// the same false positives frame after frame. A tag straddling the
+    /// mask edge is usually lost.
+    ///
+    /// `mask` must have the size of `img`. It is decimated along with the
+    /// image, so with averaging decimation a working pixel is excluded
+    /// when most of its block is.
+    ///
+    /// ```
+    /// use apriltag::{Detector, DetectorBuffers, ImageU8};
+    ///
+    /// let det = Detector::builder()
+    ///     .add_family(apriltag::family::tag36h11(), 2)
+    ///     .build();
+    /// let img = ImageU8::new(64, 48);
+    /// // Search everywhere but the bottom 8 rows
+    /// let mut mask = ImageU8::new(64, 48);
+    /// for y in 0..40 {
+    ///     for x in 0..64 {
+    ///         mask.set(x, y, 255);
+    ///     }
+    /// }
+    /// let detections = det.detect_masked(&img, &mask, &mut DetectorBuffers::new());
+    /// # assert!(detections.is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large or the mask does not match it; see
+    /// [`try_detect_masked`](Self::try_detect_masked).
+    pub fn detect_masked(
+        &self,
+        img: &(impl GrayImage + Sync),
+        mask: &impl GrayImage,
+        buffers: &mut DetectorBuffers,
+    ) -> Vec<Detection> {
+        self.try_detect_masked(img, mask, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`detect_masked`](Self::detect_masked), but fails instead of
+    /// panicking if the image is too large (see
+    /// [`try_detect`](Self::try_detect)) or the mask is not its size.
+    pub fn try_detect_masked(
+        &self,
+        img: &(impl GrayImage + Sync),
+        mask: &impl GrayImage,
+        buffers: &mut DetectorBuffers,
+    ) -> Result<Vec<Detection>, DetectError> {
+        let ((width, height), dec) = self.checked_decimation(img.width(), img.height())?;
+        if (mask.width(), mask.height()) != (width, height) {
+            return Err(DetectError::MaskSizeMismatch {
+                width,
+                height,
+                mask_width: mask.width(),
+                mask_height: mask.height(),
+            });
+        }
+        Ok(self
+            .detect_unchecked(img, dec, buffers, None, None, Some(mask.as_image_ref()))
+            .unwrap_or_default())
+    }
 
-        // Determine border orientations needed
-        let has_normal = self.families.iter().any(|(f, _)| !f.layout.reversed_border);
-        let has_reversed = self.families.iter().any(|(f, _)| f.layout.reversed_border);
+    /// Like [`detect`](Self::detect), but also report how much decode time
+    /// each family cost and how many detections it produced.
+    ///
+    /// ```
+    /// use apriltag::{Detector, DetectorBuffers, ImageU8};
+    ///
+    /// let det = Detector::builder()
+    ///     .add_family(apriltag::family::tag36h11(), 2)
+    ///     .add_family(apriltag::family::tag16h5(), 0)
+    ///     .build();
+    /// let (detections, info) = det.detect_with_info(&ImageU8::new(64, 64), &mut DetectorBuffers::new());
+    /// assert!(detections.is_empty());
+    /// assert_eq!(info.families.len(), 2);
+    /// assert_eq!(info.families[1].family.to_string(), "tag16h5");
+    /// // A blank frame has no contrast to threshold
+    /// assert!(info.skipped.is_some());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large; see
+    /// [`try_detect_with_info`](Self::try_detect_with_info).
+    pub fn detect_with_info(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> (Vec<Detection>, DetectInfo) {
+        self.try_detect_with_info(img, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        // Stage 5: Quad fitting
-        fit_quads(
-            &mut buffers.clusters,
-            filtered_w,
-            filtered_h,
-            &self.config.qtp,
-            has_normal,
-            has_reversed,
-            &mut buffers.quads,
-        );
+    /// Like [`detect_with_info`](Self::detect_with_info), but fails instead
+    /// of panicking if the image is too large; see
+    /// [`try_detect`](Self::try_detect).
+    pub fn try_detect_with_info(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Result<(Vec<Detection>, DetectInfo), DetectError> {
+        let (_, dec) = self.checked_decimation(img.width(), img.height())?;
 
-        // Recycle cluster point Vecs back into ClusterMap's free pool
-        buffers.cluster_map.recycle_clusters(&mut buffers.clusters);
+        let counters = FrameCounters {
+            families: self
+                .families
+                .iter()
+                .map(|_| FamilyCounters::default())
+                .collect(),
+            truncated_clusters: AtomicUsize::new(0),
+        };
+        let (detections, skipped) =
+            match self.detect_unchecked(img, dec, buffers, Some(&counters), None, None) {
+                Ok(detections) => (detections, None),
+                Err(reason) => (Vec::new(), Some(reason)),
+            };
 
-        // Scale quad corners back to original image coordinates
-        if f > 1 {
-            for quad in &mut buffers.quads {
-                for corner in &mut quad.corners {
-                    corner[0] *= f as f64;
-                    corner[1] *= f as f64;
+        let families = self
+            .families
+            .iter()
+            .zip(counters.families)
+            .map(|(entry, c)| {
+                let family = entry.tables.0.config.name.clone();
+                FamilyDecodeInfo {
+                    detections: detections.iter().filter(|d| d.family_id == family).count(),
+                    family,
+                    attempts: c.attempts.into_inner(),
+                    matches: c.matches.into_inner(),
+                    decode_time: Duration::from_nanos(c.nanos.into_inner()),
                 }
-            }
-        }
-
-        // Stage 6: Edge refinement
-        if self.config.refine_edges {
-            let quad_decimate = self.config.quad_decimate;
-            Par::get().for_each_init(&mut buffers.quads, Vec::new, |vals, quad| {
-                refine_edges(quad, img, quad_decimate, vals);
-            });
-        }
+            })
+            .collect();
+        Ok((
+            detections,
+            DetectInfo {
+                families,
+                skipped,
+                truncated_clusters: counters.truncated_clusters.into_inner(),
+            },
+        ))
+    }
 
-        // Stages 7-8: Homography + Decode
-        let families = &self.families;
+    /// Like [`detect`](Self::detect), but also time each pipeline stage.
+    ///
+    /// ```
+    /// use apriltag::{Detector, DetectorBuffers, ImageU8};
+    ///
+    /// let det = Detector::builder()
+    ///     .add_family(apriltag::family::tag36h11(), 2)
+    ///     .build();
+    /// // Gray ramp; a blank frame would be skipped before thresholding
+    /// let img = ImageU8::from_buf(64, 64, 64, (0..64 * 64).map(|i| (i % 64 * 4) as u8).collect());
+    /// let (_, profile) = det.detect_with_profile(&img, &mut DetectorBuffers::new());
+    /// assert!(profile.get("threshold").is_some());
+    /// print!("{profile}");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large; see
+    /// [`try_detect_with_profile`](Self::try_detect_with_profile).
+    pub fn detect_with_profile(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> (Vec<Detection>, TimeProfile) {
+        self.try_detect_with_profile(img, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`detect_with_profile`](Self::detect_with_profile), but fails
+    /// instead of panicking if the image is too large; see
+    /// [`try_detect`](Self::try_detect).
+    pub fn try_detect_with_profile(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Result<(Vec<Detection>, TimeProfile), DetectError> {
+        let (_, dec) = self.checked_decimation(img.width(), img.height())?;
+
+        let mut profile = TimeProfile::default();
+        let detections = self
+            .detect_unchecked(img, dec, buffers, None, Some(&mut profile), None)
+            .unwrap_or_default();
+        Ok((detections, profile))
+    }
+
+    /// Run the pipeline up to and including edge refinement and return the
+    /// candidate quads in input coordinates, without decoding them.
+    ///
+    /// Quads of both border polarities are returned, whichever families are
+    /// registered; [`Quad::reversed_border`] tells them apart. Useful for
+    /// custom decoders and for drawing what the detector considered.
+    ///
+    /// ```
+    /// use apriltag::{Detector, DetectorBuffers, ImageU8};
+    ///
+    /// // A black square on white
+    /// let img = ImageU8::from_buf(64, 64, 64, (0..64 * 64)
+    ///     .map(|i| if (16..48).contains(&(i % 64)) && (16..48).contains(&(i / 64)) { 0 } else { 255 })
+    ///     .collect());
+    /// let det = Detector::builder().quad_decimate(1.0).build();
+    /// let quads = det.detect_quads(&img, &mut DetectorBuffers::new());
+    /// assert_eq!(quads.len(), 1);
+    /// assert!(!quads[0].reversed_border);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is too large; see
+    /// [`try_detect_quads`](Self::try_detect_quads).
+    pub fn detect_quads(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Vec<Quad> {
+        self.try_detect_quads(img, buffers)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`detect_quads`](Self::detect_quads), but fails instead of
+    /// panicking if the image is too large; see
+    /// [`try_detect`](Self::try_detect).
+    pub fn try_detect_quads(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Result<Vec<Quad>, DetectError> {
+        let (_, dec) = self.checked_decimation(img.width(), img.height())?;
+
+        let found = self.in_pool(|| {
+            #[cfg(feature = "tracing")]
+            let _span =
+                tracing::debug_span!("detect_quads", width = img.width(), height = img.height())
+                    .entered();
+            match self.config.interlace_field {
+                Some(field) => {
+                    let frame = take_field(img, field, buffers);
+                    let found =
+                        self.find_quads(&frame, dec, buffers, (true, true), None, None, None);
+                    buffers.field = frame;
+                    found
+                }
+                None => self.find_quads(img, dec, buffers, (true, true), None, None, None),
+            }
+        });
+        let mut quads = match found {
+            Ok(()) => buffers.quads.clone(),
+            Err(_) => Vec::new(),
+        };
+        if let Some(field) = self.config.interlace_field {
+            let (sy, dy) = field_rows(field);
+            for quad in &mut quads {
+                for corner in &mut quad.corners {
+                    corner[1] = corner[1] * sy + dy;
+                }
+                quad.line_fit_mse *= sy as f32;
+                quad.refine_residual = quad.refine_residual.map(|r| r * sy as f32);
+                for c in &mut quad.corner_covariance {
+                    scale_covariance(c, 1.0, sy);
+                }
+            }
+        }
+        let to = self.config.pixel_convention;
+        for corner in quads.iter_mut().flat_map(|quad| &mut quad.corners) {
+            *corner = PixelConvention::Corner.convert(*corner, to);
+        }
+        Ok(quads)
+    }
+
+    /// Check a `width` × `height` input against the size limits (see
+    /// [`check_dimensions`]) and return those dimensions with the
+    /// configured decimation.
+    fn checked_decimation(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> Result<((u32, u32), Decimation), DetectError> {
+        let dec =
+            Decimation::from_factor(self.config.quad_decimate).with_mode(self.config.decimate_mode);
+        check_dimensions(width, height, dec, self.config.max_image_dimension)?;
+        Ok(((width, height), dec))
+    }
+
+    fn detect_unchecked(
+        &self,
+        img: &(impl GrayImage + Sync),
+        dec: Decimation,
+        buffers: &mut DetectorBuffers,
+        counters: Option<&FrameCounters>,
+        profile: Option<&mut TimeProfile>,
+        region: Option<ImageRef<'_>>,
+    ) -> Result<Vec<Detection>, SkipReason> {
+        let mut detections = match self.config.interlace_field {
+            None => {
+                self.in_pool(|| self.run_pipeline(img, dec, buffers, counters, profile, region))?
+            }
+            Some(field) => {
+                let frame = take_field(img, field, buffers);
+                let field_region = region.map(|region| {
+                    let mut out = std::mem::replace(&mut buffers.region_field, ImageU8::new(0, 0));
+                    extract_field(&region, field, &mut out);
+                    out
+                });
+                let result = self.in_pool(|| {
+                    let region = field_region.as_ref().map(GrayImage::as_image_ref);
+                    self.run_pipeline(&frame, dec, buffers, counters, profile, region)
+                });
+                buffers.field = frame;
+                if let Some(out) = field_region {
+                    buffers.region_field = out;
+                }
+                let mut detections = result?;
+                let (sy, dy) = field_rows(field);
+                for det in &mut detections {
+                    det.scale(1.0, sy, PixelConvention::Corner);
+                    det.translate(0.0, dy);
+                }
+                detections
+            }
+        };
+        if self.config.pixel_convention != PixelConvention::Corner {
+            for det in &mut detections {
+                det.convert_pixels(PixelConvention::Corner, self.config.pixel_convention);
+            }
+        }
+        Ok(detections)
+    }
+
+    /// Run `f` on the configured thread pool, or single-threaded if so
+    /// configured.
+    fn in_pool<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = &self.pool {
+            return pool.install(f);
+        } else if self.sequential {
+            return Par::sequential(f);
+        }
+        f()
+    }
+
+    fn run_pipeline(
+        &self,
+        img: &(impl GrayImage + Sync),
+        dec: Decimation,
+        buffers: &mut DetectorBuffers,
+        counters: Option<&FrameCounters>,
+        mut profile: Option<&mut TimeProfile>,
+        region: Option<ImageRef<'_>>,
+    ) -> Result<Vec<Detection>, SkipReason> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "detect",
+            width = img.width(),
+            height = img.height(),
+            families = self.families.len()
+        )
+        .entered();
+
+        // Only fit the border orientations some family needs. An inverted
+        // print has the opposite border of its family.
+        let polarity = self.config.polarity;
+        let needs = |reversed: bool| {
+            self.families.iter().any(|e| {
+                let inverted = reversed != e.tables.0.layout.reversed_border;
+                polarity.accepts(inverted)
+            })
+        };
+        let (has_normal, has_reversed) = (needs(false), needs(true));
+
+        // Stages 1-6
+        self.find_quads(
+            img,
+            dec,
+            buffers,
+            (has_normal, has_reversed),
+            counters,
+            profile.as_deref_mut(),
+            region,
+        )?;
+
+        let hooks = self.hooks.as_deref();
+        #[cfg(feature = "debug-images")]
+        let debug = self.debug.as_deref();
+        #[cfg(feature = "debug-images")]
+        let rejected = std::sync::Mutex::new(Vec::new());
+
+        // Stages 7-8: Homography + Decode
+        let families = &self.families;
         let config = &self.config;
-        let mut detections: Vec<Detection> =
+        let mut detections: Vec<Detection> = stage!(profile, "decode", {
+            // Rejections may be reported from worker threads, which do not
+            // inherit the current span.
+            #[cfg(feature = "tracing")]
+            let span = tracing::Span::current();
             Par::get().flat_map_init_collect(&buffers.quads, DecodeBufs::new, |bufs, quad, out| {
-                decode_quad_to_detections(quad, img, families, config, bufs, out);
-            });
+                if let Err(reason) = decode_quad_to_detections(
+                    quad,
+                    img,
+                    families,
+                    config,
+                    hooks,
+                    counters.map(|c| &c.families[..]),
+                    bufs,
+                    out,
+                ) {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        parent: &span,
+                        reason = reason.name(),
+                        corners = ?quad.corners,
+                        "candidate rejected"
+                    );
+                    if let Some(hooks) = hooks {
+                        hooks.on_candidate_rejected(quad, reason);
+                    }
+                    #[cfg(feature = "debug-images")]
+                    if debug.is_some() {
+                        rejected
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .push(quad.clone());
+                    }
+                }
+            })
+        });
+        #[cfg(feature = "debug-images")]
+        if let Some(debug) = debug {
+            let rejected = rejected
+                .into_inner()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            debug.on_image(DebugStage::Rejected, debug::draw_rejected(img, &rejected));
+        }
 
         // Stage 9: Deduplication
-        deduplicate(&mut detections);
+        stage!(profile, "deduplicate", deduplicate(&mut detections));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(detections = detections.len());
 
-        detections
+        Ok(detections)
+    }
+
+    /// Run stages 1-6 on `img`, leaving the candidate quads, in input
+    /// coordinates, in `buffers.quads`. `borders` says whether to fit quads
+    /// with normal and with reversed borders. Pixels where `region`, the
+    /// size of `img`, is below 128 are not searched.
+    #[allow(clippy::too_many_arguments)]
+    fn find_quads(
+        &self,
+        img: &(impl GrayImage + Sync),
+        dec: Decimation,
+        buffers: &mut DetectorBuffers,
+        borders: (bool, bool),
+        counters: Option<&FrameCounters>,
+        mut profile: Option<&mut TimeProfile>,
+        region: Option<ImageRef<'_>>,
+    ) -> Result<(), SkipReason> {
+        let mut qtp = self.config.quad.thresh_params(self.config.quad_decimate);
+        if self.config.interlace_field.is_some() {
+            // Field pixels are two frame rows tall
+            qtp.pixel_aspect_ratio *= 0.5;
+        }
+        // Decoding needs a pixel per cell along each border edge, so no
+        // tag is smaller than the narrowest family border in input pixels
+        if let Some(width) = self
+            .families
+            .iter()
+            .map(|entry| entry.tables.0.layout.border_width)
+            .min()
+        {
+            qtp.limit_tag_width(width as f64 / dec.scale());
+        }
+
+        // Stage 1: Preprocess. Without decimation or filtering there is
+        // nothing to do, and later stages read the caller's image in place.
+        let gray = if dec.is_identity() && sigma_is_identity(self.config.quad_sigma) {
+            // Subscribers still see every stage, marked as skipped
+            #[cfg(feature = "tracing")]
+            drop(tracing::debug_span!("preprocess", skipped = true).entered());
+            img.as_image_ref()
+        } else {
+            stage!(profile, "preprocess", {
+                dec.apply(img, &mut buffers.decimated);
+                apply_sigma(
+                    &buffers.decimated,
+                    self.config.quad_sigma,
+                    &mut buffers.filtered,
+                    &mut buffers.blur_tmp,
+                );
+            });
+            buffers.filtered.as_image_ref()
+        };
+
+        // Save filtered dimensions
+        let filtered_w = gray.width();
+        let filtered_h = gray.height();
+
+        // The region mask is decimated like the image
+        let region = match region {
+            Some(region) if !dec.is_identity() => {
+                dec.apply(&region, &mut buffers.region);
+                Some(buffers.region.as_image_ref())
+            }
+            region => region,
+        };
+
+        #[cfg(feature = "debug-images")]
+        let debug = self.debug.as_deref();
+        #[cfg(feature = "debug-images")]
+        if let Some(debug) = debug {
+            debug.on_image(DebugStage::Preprocessed, DebugImage::gray(&gray));
+        }
+
+        // A frame with less contrast than the threshold requires everywhere
+        // thresholds to all-unknown and yields nothing; skip the remaining
+        // stages.
+        if let Some((min, max)) = low_dynamic_range(&gray, qtp.min_white_black_diff) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(min, max, "frame skipped: low dynamic range");
+            buffers.quads.clear();
+            if let Some(hooks) = self.hooks.as_deref() {
+                hooks.on_quads(&buffers.quads);
+            }
+            return Err(SkipReason::LowDynamicRange { min, max });
+        }
+
+        // Candidates rejected before decoding are reported to the hooks in
+        // input coordinates.
+        let hooks = self.hooks.as_deref();
+        let report_rejected = |mut quad: Quad, reason: RejectReason| {
+            if let Some(hooks) = hooks {
+                scale_quad(&mut quad, dec);
+                hooks.on_candidate_rejected(&quad, reason);
+            }
+        };
+        let edge = (qtp.min_edge_gradient > 0).then_some(EdgeFilter {
+            gray,
+            min_gradient: qtp.min_edge_gradient,
+        });
+        // Hooks also see the clusters too small to fit, so keep every
+        // cluster and drop the small ones after reporting them.
+        let min_cluster_pixels = qtp.min_cluster_pixels.max(0) as usize;
+        let min_cluster_size = if hooks.is_some() {
+            1
+        } else {
+            min_cluster_pixels as u32
+        };
+
+        if let Some(band_rows) = self.config.band_rows {
+            // Stages 2-4 a band of rows at a time
+            stage!(
+                profile,
+                "banded_clusters",
+                banded_clusters(
+                    &gray,
+                    qtp.tile_size,
+                    qtp.min_white_black_diff,
+                    qtp.deglitch,
+                    band_rows,
+                    min_cluster_size,
+                    edge,
+                    region,
+                    &mut buffers.banded,
+                    &mut buffers.clusters,
+                )
+            );
+        } else {
+            // Stage 2: Threshold
+            stage!(
+                profile,
+                "threshold",
+                threshold_tiled(
+                    &gray,
+                    qtp.tile_size,
+                    qtp.min_white_black_diff,
+                    qtp.deglitch,
+                    &mut buffers.threshed,
+                    &mut buffers.threshold_bufs,
+                )
+            );
+            if let Some(region) = region {
+                exclude_region(&mut buffers.threshed, &region, 0);
+            }
+
+            #[cfg(feature = "debug-images")]
+            if let Some(debug) = debug {
+                debug.on_image(DebugStage::Threshold, DebugImage::gray(&buffers.threshed));
+            }
+
+            // Stage 3: Connected components
+            stage!(
+                profile,
+                "connected_components",
+                connected_components(&buffers.threshed, &mut buffers.uf)
+            );
+
+            // Stage 4: Gradient clustering
+            stage!(
+                profile,
+                "gradient_clusters",
+                gradient_clusters_in_place(
+                    &mut buffers.threshed,
+                    &mut buffers.uf,
+                    min_cluster_size,
+                    edge,
+                    &mut buffers.cluster_map,
+                    &mut buffers.clusters,
+                )
+            );
+        }
+        if hooks.is_some() {
+            buffers.clusters.retain(|cluster| {
+                let keep = cluster.points.len() >= min_cluster_pixels;
+                if !keep {
+                    report_rejected(bounding_quad(cluster), RejectReason::ClusterTooSmall);
+                }
+                keep
+            });
+        }
+        // Past the cluster limit, fit only the clusters most likely to be
+        // tags. The dropped ones stay at the end of the list so their point
+        // buffers are recycled with the rest.
+        let mut fitted = buffers.clusters.len();
+        if let Some(max_clusters) = self.config.max_clusters {
+            let max_points = max_cluster_points(filtered_w, filtered_h);
+            fitted = cap_clusters(&mut buffers.clusters, max_clusters, max_points);
+            let truncated = buffers.clusters.len() - fitted;
+            if truncated > 0 {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(truncated, max_clusters, "cluster limit reached");
+                if let Some(counters) = counters {
+                    counters
+                        .truncated_clusters
+                        .store(truncated, Ordering::Relaxed);
+                }
+                for cluster in &buffers.clusters[fitted..] {
+                    report_rejected(bounding_quad(cluster), RejectReason::TooManyClusters);
+                }
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(clusters = buffers.clusters.len());
+        #[cfg(feature = "debug-images")]
+        if let Some(debug) = debug {
+            let image = debug::draw_clusters(filtered_w, filtered_h, &buffers.clusters);
+            debug.on_image(DebugStage::Clusters, image);
+        }
+
+        // Stage 5: Quad fitting
+        stage!(
+            profile,
+            "fit_quads",
+            fit_quads_reporting(
+                &mut buffers.clusters[..fitted],
+                filtered_w,
+                filtered_h,
+                &qtp,
+                borders.0,
+                borders.1,
+                hooks.map(|_| &report_rejected as &(dyn Fn(Quad, RejectReason) + Sync)),
+                &mut buffers.quads,
+            )
+        );
+        #[cfg(feature = "tracing")]
+        tracing::debug!(quads = buffers.quads.len());
+
+        // Recycle cluster point Vecs back into ClusterMap's free pool
+        buffers.cluster_map.recycle_clusters(&mut buffers.clusters);
+
+        // Scale quad corners back to original image coordinates
+        if !dec.is_identity() {
+            for quad in &mut buffers.quads {
+                scale_quad(quad, dec);
+            }
+        }
+
+        // Stage 6: Edge refinement
+        if self.config.refine_edges {
+            let quad_decimate = self.config.quad_decimate;
+            stage!(profile, "refine_edges", {
+                Par::get().for_each_init(&mut buffers.quads, Vec::new, |vals, quad| {
+                    refine_edges(quad, img, quad_decimate, vals);
+                });
+                // Refinement can move a corner past its neighbours on
+                // tiny or badly blurred quads.
+                buffers.quads.retain(|quad| {
+                    if validate_quad(&quad.corners, &qtp).is_some() {
+                        return true;
+                    }
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        reason = RejectReason::EdgeRefinement.name(),
+                        corners = ?quad.corners,
+                        "candidate rejected"
+                    );
+                    if let Some(hooks) = hooks {
+                        hooks.on_candidate_rejected(quad, RejectReason::EdgeRefinement);
+                    }
+                    false
+                });
+            });
+        }
+
+        if let Some(hooks) = hooks {
+            hooks.on_quads(&buffers.quads);
+        }
+        #[cfg(feature = "debug-images")]
+        if let Some(debug) = debug {
+            debug.on_image(
+                DebugStage::Quads,
+                debug::draw_candidates(img, &buffers.quads),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Copy one field of `img` into the image taken from `buffers.field`. The
+/// caller puts it back after detecting on it.
+fn take_field(
+    img: &impl GrayImage,
+    field: InterlaceField,
+    buffers: &mut DetectorBuffers,
+) -> ImageU8 {
+    let mut frame = std::mem::replace(&mut buffers.field, ImageU8::new(0, 0));
+    extract_field(img, field, &mut frame);
+    frame
+}
+
+/// Scale and offset taking y coordinates on `field` to the frame, as
+/// [`InterlaceField::to_frame_y`] does. Fit errors and covariances scale
+/// with the row pitch.
+fn field_rows(field: InterlaceField) -> (f64, f64) {
+    (2.0, field.to_frame_y(0.0))
+}
+
+/// Map a quad fitted on the decimated image back to input coordinates.
+fn scale_quad(quad: &mut Quad, dec: Decimation) {
+    if dec.is_identity() {
+        return;
+    }
+    let (scale, offset) = (dec.scale(), dec.offset());
+    for corner in &mut quad.corners {
+        corner[0] = corner[0] * scale + offset;
+        corner[1] = corner[1] * scale + offset;
+    }
+    quad.line_fit_mse *= (scale * scale) as f32;
+    for c in &mut quad.corner_covariance {
+        scale_covariance(c, scale, scale);
+    }
+}
+
+/// Move the `max` clusters most likely to be tags to the front of `clusters`,
+/// keeping their order, and return how many that is.
+///
+/// Clusters with more than `max_points` points cannot be fitted and go last;
+/// the rest rank by size, since noise yields many small clusters while a
+/// tag's border is among the longest boundaries in the frame.
+fn cap_clusters(clusters: &mut Vec<Cluster>, max: usize, max_points: usize) -> usize {
+    if clusters.len() <= max {
+        return clusters.len();
+    }
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    order.sort_by_key(|&i| {
+        let len = clusters[i].points.len();
+        std::cmp::Reverse((len <= max_points, len))
+    });
+    let mut keep = vec![false; clusters.len()];
+    for &i in &order[..max] {
+        keep[i] = true;
+    }
+    let (kept, dropped): (Vec<_>, Vec<_>) =
+        clusters.drain(..).zip(keep).partition(|&(_, keep)| keep);
+    clusters.extend(kept.into_iter().chain(dropped).map(|(cluster, _)| cluster));
+    max
+}
+
+/// The darkest and brightest pixel of `img`, if they differ by less than
+/// `min_white_black_diff`. Stops scanning at the first row that shows enough
+/// contrast, so ordinary frames pay for a few rows at most.
+fn low_dynamic_range(img: &impl GrayImage, min_white_black_diff: i32) -> Option<(u8, u8)> {
+    if img.width() == 0 || img.height() == 0 {
+        return None;
+    }
+    let (mut min, mut max) = (u8::MAX, u8::MIN);
+    for y in 0..img.height() {
+        for &v in img.row(y) {
+            min = min.min(v);
+            max = max.max(v);
+        }
+        if i32::from(max) - i32::from(min) >= min_white_black_diff {
+            return None;
+        }
+    }
+    Some((min, max))
+}
+
+/// Check input dimensions against the configured limit and the working image
+/// against the pipeline's internal limits, given decimation `dec`.
+fn check_dimensions(
+    width: u32,
+    height: u32,
+    dec: Decimation,
+    max_image_dimension: Option<u32>,
+) -> Result<(), DetectError> {
+    if let Some(max) = max_image_dimension {
+        if width > max || height > max {
+            return Err(DetectError::ExceedsMaxDimension { width, height, max });
+        }
+    }
+    let (ww, wh) = dec.output_size(width, height);
+    if ww > MAX_WORKING_DIMENSION || wh > MAX_WORKING_DIMENSION {
+        return Err(DetectError::WorkingImageTooLarge {
+            width: ww,
+            height: wh,
+        });
     }
+    Ok(())
 }
 
 /// Decode a single quad against all families, appending detections to `out`.
+///
+/// Returns why the quad was rejected if no family decoded it. A code matched
+/// below [`DetectorConfig::min_decision_margin`] takes precedence over a code
+/// mismatch, which takes precedence over a polarity failure, since it means
+/// the border matched at least one family.
+#[allow(clippy::too_many_arguments)]
 fn decode_quad_to_detections(
     quad: &super::quad::Quad,
     img: &(impl GrayImage + Sync),
-    families: &[(TagFamily, QuickDecode)],
+    families: &[AddedFamily],
     config: &DetectorConfig,
+    hooks: Option<&dyn DetectorHooks>,
+    counters: Option<&[FamilyCounters]>,
     bufs: &mut DecodeBufs,
     out: &mut SmallVec<[Detection; 1]>,
-) {
+) -> Result<(), RejectReason> {
     // COVERAGE: None branch requires a degenerate quad (all corners collinear)
     // surviving all prior pipeline stages — not reachable in practice.
     let Some(h) = Homography::from_quad_corners(&quad.corners) else {
-        return;
+        return Err(RejectReason::DegenerateHomography);
     };
 
-    for (family, qd) in families {
-        if quad.reversed_border != family.layout.reversed_border {
+    let mut reason = RejectReason::Polarity;
+    let mut decoded = false;
+
+    for (i, entry) in families.iter().enumerate() {
+        let (family, qd) = &*entry.tables;
+        let inverted = quad.reversed_border != family.layout.reversed_border;
+        if !config.polarity.accepts(inverted) {
             continue;
         }
+        if let Some(hooks) = hooks.filter(|hooks| hooks.records_samples()) {
+            let samples = sample_points(family, &h, img.width(), img.height());
+            hooks.on_samples(quad, &family.config.name, &samples);
+        }
 
-        if let Some(result) = decode_quad(
+        let counter = counters.map(|c| &c[i]);
+        let start = counter.filter(|_| HAS_CLOCK).map(|_| Instant::now());
+        let matched = match try_decode_quad_polarity(
             img,
             family,
             qd,
             &h,
-            quad.reversed_border,
+            family.layout.reversed_border,
+            inverted,
+            config.decode_mirrored,
+            entry.max_hamming,
             config.decode_sharpening,
             bufs,
         ) {
-            let (center, corners) = compute_detection_geometry(&h, result.rotation);
-
-            out.push(Detection {
-                family_id: result.family_id,
-                id: result.id,
-                hamming: result.hamming,
-                decision_margin: result.decision_margin,
-                corners,
-                center,
-            });
+            Ok(result) if result.decision_margin < config.min_decision_margin => {
+                reason = RejectReason::LowDecisionMargin;
+                false
+            }
+            Ok(result) => {
+                let refined = config
+                    .refine_homography
+                    .then(|| refine_homography(img, family, &h))
+                    .flatten();
+                let mut homography = tag_homography(
+                    refined.as_ref().unwrap_or(&h),
+                    result.rotation,
+                    result.mirrored,
+                );
+                if config.refine_decode {
+                    if let Some(aligned) =
+                        refine_decode(img, family, result.id as usize, &homography)
+                    {
+                        homography = aligned;
+                    }
+                }
+                let (center, corners) = compute_detection_geometry(&homography);
+                // Tag corner i is quad corner i + rotation (flipped first if mirrored)
+                let corner_covariance = std::array::from_fn(|i| {
+                    let k = if result.mirrored { i ^ 1 } else { i };
+                    quad.corner_covariance[(k + result.rotation.rem_euclid(4) as usize) % 4]
+                });
+
+                out.push(Detection {
+                    family_id: result.family_id,
+                    id: result.id,
+                    hamming: result.hamming,
+                    decision_margin: result.decision_margin,
+                    corners,
+                    center,
+                    homography,
+                    mirrored: result.mirrored,
+                    line_fit_mse: quad.line_fit_mse,
+                    refine_residual: quad.refine_residual,
+                    corner_covariance,
+                });
+                decoded = true;
+                true
+            }
+            Err(RejectReason::NoCodeMatch) => {
+                if reason != RejectReason::LowDecisionMargin {
+                    reason = RejectReason::NoCodeMatch;
+                }
+                false
+            }
+            Err(_) => false,
+        };
+
+        if let Some(counter) = counter {
+            counter.attempts.fetch_add(1, Ordering::Relaxed);
+            counter
+                .matches
+                .fetch_add(usize::from(matched), Ordering::Relaxed);
+            if let Some(start) = start {
+                counter
+                    .nanos
+                    .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            }
         }
     }
-}
 
-/// Compute center and rotation-corrected corner positions.
-fn compute_detection_geometry(h: &Homography, rotation: i32) -> (Vec2, [Vec2; 4]) {
-    let (cx, cy) = h.project(0.0, 0.0);
+    if decoded {
+        Ok(())
+    } else {
+        Err(reason)
+    }
+}
 
-    // Tag corners in canonical order, rotated by the detected rotation
-    let base_corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
-    let mut corners = [Vec2::new(0.0, 0.0); 4];
-    for i in 0..4 {
-        let src = &base_corners[(i + rotation as usize) % 4];
-        let (px, py) = h.project(src[0], src[1]);
-        corners[i] = Vec2::new(px, py);
+/// The homography in the tag's own frame, from the quad's homography `h`
+/// and the decoded rotation.
+///
+/// Quad corner `(rotation + i) % 4` is tag corner `i`, so tag coordinates are
+/// rotated `rotation` quarter turns into quad coordinates. Mirrored tags are
+/// reflected first (`x -> -x`), which swaps corners 0↔1 and 2↔3.
+fn tag_homography(h: &Homography, rotation: i32, mirrored: bool) -> Homography {
+    // (x, y) -> (-y, x) advances each canonical corner to the next
+    let quarter_turn = Mat3([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+    let mut data = h.data;
+    for _ in 0..rotation.rem_euclid(4) {
+        data = data * quarter_turn;
     }
+    if mirrored {
+        data = data * Mat3([[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    }
+    Homography { data }
+}
 
+/// Center and corner positions of a tag with homography `h`.
+fn compute_detection_geometry(h: &Homography) -> (Vec2, [Vec2; 4]) {
+    let (cx, cy) = h.project(0.0, 0.0);
+    let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| {
+        let (px, py) = h.project(x, y);
+        Vec2::new(px, py)
+    });
     (Vec2::new(cx, cy), corners)
 }
 
@@ -427,6 +2138,7 @@ fn compute_detection_geometry(h: &Homography, rotation: i32) -> (Vec2, [Vec2; 4]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
+    use crate::detect::decode::{SampleKind, SamplePoint};
     use crate::detect::image::{ImageRef, ImageU8};
     use crate::family;
 
@@ -438,190 +2150,1599 @@ mod tests {
         assert!((builder.config.quad_sigma - config.quad_sigma).abs() < 1e-6);
         assert_eq!(builder.config.refine_edges, config.refine_edges);
         assert!((builder.config.decode_sharpening - config.decode_sharpening).abs() < 1e-6);
-        assert_eq!(builder.config.qtp.deglitch, config.qtp.deglitch);
+        assert_eq!(builder.config.quad.deglitch, config.quad.deglitch);
     }
 
     #[test]
     fn builder_sets_fields() {
         let det = Detector::builder()
             .quad_decimate(1.0)
-            .quad_sigma(0.5)
-            .refine_edges(false)
-            .decode_sharpening(0.5)
-            .deglitch(true)
+            .quad_sigma(0.5)
+            .refine_edges(false)
+            .decode_sharpening(0.5)
+            .deglitch(true)
+            .build();
+        assert!((det.config.quad_decimate - 1.0).abs() < 1e-6);
+        assert!((det.config.quad_sigma - 0.5).abs() < 1e-6);
+        assert!(!det.config.refine_edges);
+        assert!((det.config.decode_sharpening - 0.5).abs() < 1e-6);
+        assert!(det.config.quad.deglitch);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn builder_adds_families() {
+        let det = Detector::builder()
+            .add_family(family::tag36h11(), 2)
+            .build();
+        assert_eq!(det.families.len(), 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "family-tag36h11", feature = "family-tag16h5"))]
+    fn try_build_validates_families_and_config() {
+        let det = Detector::builder()
+            .family("tag36h11", 2)
+            .family("tag16h5", 0)
+            .try_build()
+            .unwrap();
+        let names: Vec<_> = det.families().map(|f| f.config.name.to_string()).collect();
+        assert_eq!(names, ["tag36h11", "tag16h5"]);
+
+        // The first unknown name is reported, ahead of config problems
+        let err = Detector::builder()
+            .quad_decimate(0.0)
+            .family("tag99h1", 2)
+            .family("tag98h1", 2)
+            .try_build()
+            .err();
+        assert_eq!(err, Some(BuildError::UnknownFamily("tag99h1".to_string())));
+
+        let mut empty = family::tag16h5();
+        empty.codes.clear();
+        let err = Detector::builder().add_family(empty, 0).try_build().err();
+        assert_eq!(err, Some(BuildError::EmptyFamily("tag16h5".to_string())));
+
+        let config = DetectorConfig {
+            band_rows: Some(0),
+            ..DetectorConfig::default()
+        };
+        let err = DetectorBuilder::with_config(config).try_build().err();
+        assert!(matches!(
+            err,
+            Some(BuildError::InvalidConfig {
+                field: "band_rows",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn buffers_convenience_returns_empty() {
+        let bufs = Detector::buffers();
+        // Just verify it returns a valid DetectorBuffers (same as DetectorBuffers::new())
+        assert_eq!(
+            std::mem::size_of_val(&bufs),
+            std::mem::size_of::<DetectorBuffers>()
+        );
+    }
+
+    #[test]
+    fn builder_default_trait() {
+        let b = DetectorBuilder::default();
+        assert!((b.config.quad_decimate - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn builder_detect_synthetic_tag() {
+        let (img, fam) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(fam, 2)
+            .build();
+        let dets = det.detect(&img, &mut DetectorBuffers::new());
+        assert!(!dets.is_empty());
+        assert_eq!(dets[0].id, 0);
+    }
+
+    #[test]
+    fn detector_default_config() {
+        let config = DetectorConfig::default();
+        assert!((config.quad_decimate - 2.0).abs() < 1e-6);
+        assert!((config.quad_sigma - 0.0).abs() < 1e-6);
+        assert!(config.refine_edges);
+        assert!((config.decode_sharpening - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag36h11")]
+    fn detector_add_family() {
+        let mut det = Detector::new(DetectorConfig::default());
+        det.add_family(family::tag36h11(), 2);
+        assert_eq!(det.families.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_empty_image_no_crash() {
+        let mut det = Detector::new(DetectorConfig::default());
+        det.add_family(family::tag16h5(), 2);
+        let img = ImageU8::new(100, 100);
+        let dets = det.detect(&img, &mut DetectorBuffers::new());
+        assert!(dets.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_synthetic_tag() {
+        // Render a tag16h5 tag and embed it in a larger image
+        let family = family::tag16h5();
+        let rendered = family.tag(0).render();
+
+        // Create a 200x200 image with white background
+        let mut img = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                img.set(x, y, 255);
+            }
+        }
+
+        // Place the 8x8 tag at center, scaled up by 10x
+        let scale = 10;
+        let ox = 60u32;
+        let oy = 60u32;
+        for ty in 0..rendered.grid_size {
+            for tx in 0..rendered.grid_size {
+                let pixel = rendered.pixel(tx, ty);
+                let val = match pixel {
+                    crate::types::Pixel::Black => 0u8,
+                    _ => 255u8,
+                };
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        img.set(
+                            ox + tx as u32 * scale + dx,
+                            oy + ty as u32 * scale + dy,
+                            val,
+                        );
+                    }
+                }
+            }
+        }
+
+        let config = DetectorConfig {
+            quad_decimate: 1.0, // no decimation for synthetic test
+            quad_sigma: 0.0,
+            ..DetectorConfig::default()
+        };
+        let mut det = Detector::new(config);
+        det.add_family(family, 2);
+
+        let dets = det.detect(&img, &mut DetectorBuffers::new());
+
+        // We should detect tag ID 0
+        assert!(!dets.is_empty(), "Should detect at least one tag, got none");
+        assert_eq!(dets[0].id, 0, "Should detect tag ID 0");
+    }
+
+    #[test]
+    fn tag_homography_puts_each_tag_corner_on_its_quad_corner() {
+        let quad = [[10.0, 20.0], [90.0, 25.0], [85.0, 95.0], [15.0, 80.0]].map(Vec2::from);
+        let h = Homography::from_quad_corners(&quad).unwrap();
+        for rotation in 0..4 {
+            for mirrored in [false, true] {
+                let (_, corners) =
+                    compute_detection_geometry(&tag_homography(&h, rotation, mirrored));
+                for (i, corner) in corners.iter().enumerate() {
+                    let k = if mirrored { i ^ 1 } else { i };
+                    let expected = quad[(k + rotation as usize) % 4];
+                    assert!(
+                        (*corner - expected).norm() < 1e-9,
+                        "rotation {rotation}, mirrored {mirrored}: corner {i} at {corner:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compute_detection_geometry_identity() {
+        let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(Vec2::from);
+        let h = Homography::from_quad_corners(&corners).unwrap();
+        let (center, det_corners) = compute_detection_geometry(&tag_homography(&h, 0, false));
+        assert!((center[0] - 0.0).abs() < 1e-6);
+        assert!((center[1] - 0.0).abs() < 1e-6);
+        for i in 0..4 {
+            assert!((det_corners[i][0] - corners[i][0]).abs() < 1e-6);
+            assert!((det_corners[i][1] - corners[i][1]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn try_detect_enforces_max_image_dimension() {
+        let det = Detector::builder().max_image_dimension(100).build();
+        let mut buffers = DetectorBuffers::new();
+        assert_eq!(
+            det.try_detect(&ImageU8::new(120, 80), &mut buffers)
+                .unwrap_err(),
+            DetectError::ExceedsMaxDimension {
+                width: 120,
+                height: 80,
+                max: 100
+            }
+        );
+        assert!(det
+            .try_detect(&ImageU8::new(100, 100), &mut buffers)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn info_and_profile_variants_fail_instead_of_panicking() {
+        let det = Detector::builder().max_image_dimension(10).build();
+        let img = ImageU8::new(20, 20);
+        let mut buffers = DetectorBuffers::new();
+        let expected = DetectError::ExceedsMaxDimension {
+            width: 20,
+            height: 20,
+            max: 10,
+        };
+        assert_eq!(
+            det.try_detect_with_info(&img, &mut buffers).unwrap_err(),
+            expected
+        );
+        assert_eq!(
+            det.try_detect_with_profile(&img, &mut buffers).unwrap_err(),
+            expected
+        );
+
+        let small = ImageU8::new(10, 10);
+        let (_, info) = det.try_detect_with_info(&small, &mut buffers).unwrap();
+        assert!(info.skipped.is_some());
+        assert!(det.try_detect_with_profile(&small, &mut buffers).is_ok());
+    }
+
+    #[test]
+    fn working_image_limit_depends_on_decimation() {
+        let wide = ImageU8::new(2 * MAX_WORKING_DIMENSION, 8);
+        let mut buffers = DetectorBuffers::new();
+
+        let det = Detector::builder().quad_decimate(1.0).build();
+        assert_eq!(
+            det.try_detect(&wide, &mut buffers).unwrap_err(),
+            DetectError::WorkingImageTooLarge {
+                width: 2 * MAX_WORKING_DIMENSION,
+                height: 8
+            }
+        );
+
+        let det = Detector::builder().quad_decimate(2.0).build();
+        assert!(det.try_detect(&wide, &mut buffers).unwrap().is_empty());
+        assert!(check_dimensions(
+            MAX_WORKING_DIMENSION,
+            MAX_WORKING_DIMENSION,
+            Decimation::Subsample(1),
+            None
+        )
+        .is_ok());
+
+        // 1.5x keeps two of every three pixels
+        let det = Detector::builder().quad_decimate(1.5).build();
+        assert_eq!(
+            det.try_detect(&wide, &mut buffers).unwrap_err(),
+            DetectError::WorkingImageTooLarge {
+                width: 2 * MAX_WORKING_DIMENSION / 3 * 2,
+                height: 4
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the maximum dimension")]
+    fn detect_panics_on_oversized_image() {
+        let det = Detector::builder().max_image_dimension(10).build();
+        det.detect(&ImageU8::new(20, 20), &mut DetectorBuffers::new());
+    }
+
+    #[test]
+    fn cap_clusters_keeps_largest_fittable_in_order() {
+        let pt = super::super::cluster::Pt {
+            x: 0,
+            y: 0,
+            gx: 0,
+            gy: 0,
+            slope: 0,
+        };
+        let mut clusters: Vec<Cluster> = [30, 500, 40, 200, 10, 90]
+            .into_iter()
+            .map(|n| Cluster {
+                points: vec![pt; n],
+            })
+            .collect();
+        let sizes =
+            |clusters: &[Cluster]| clusters.iter().map(|c| c.points.len()).collect::<Vec<_>>();
+
+        assert_eq!(cap_clusters(&mut clusters, 6, 300), 6);
+        assert_eq!(sizes(&clusters), [30, 500, 40, 200, 10, 90]);
+        // The 500-point cluster cannot be fitted, so it ranks below the small ones
+        assert_eq!(cap_clusters(&mut clusters, 5, 300), 5);
+        assert_eq!(sizes(&clusters), [30, 40, 200, 10, 90, 500]);
+        assert_eq!(cap_clusters(&mut clusters, 3, 300), 3);
+        assert_eq!(sizes(&clusters), [40, 200, 90, 30, 10, 500]);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn max_clusters_drops_specks_before_the_tag() {
+        let (mut img, family) = build_synthetic_tag_image();
+        // A field of dark specks above the tag, each its own small cluster
+        for y in (4..50).step_by(9) {
+            for x in (4..190).step_by(9) {
+                for i in 0..36 {
+                    img.set(x + i % 6, y + i / 6, 0);
+                }
+            }
+        }
+        let detect = |max_clusters| {
+            let hooks = Arc::new(RecordingHooks::default());
+            let mut builder = Detector::builder()
+                .quad_decimate(1.0)
+                .add_family(family.clone(), 2)
+                .hooks(hooks.clone());
+            if let Some(max) = max_clusters {
+                builder = builder.max_clusters(max);
+            }
+            let (dets, info) = builder
+                .build()
+                .detect_with_info(&img, &mut DetectorBuffers::new());
+            let rejected = hooks.rejected.lock().unwrap().clone();
+            (dets, info, rejected)
+        };
+
+        let (dets, info, rejected) = detect(None);
+        assert_eq!(dets.len(), 1);
+        assert_eq!(info.truncated_clusters, 0);
+        assert!(!rejected.contains(&RejectReason::TooManyClusters));
+
+        let (capped, info, rejected) = detect(Some(4));
+        assert_eq!(capped.len(), 1);
+        assert_eq!(capped[0].corners, dets[0].corners);
+        assert!(info.truncated_clusters > 100);
+        let dropped = rejected
+            .iter()
+            .filter(|&&r| r == RejectReason::TooManyClusters)
+            .count();
+        assert_eq!(dropped, info.truncated_clusters);
+    }
+
+    #[derive(Default)]
+    struct RecordingHooks {
+        quad_counts: std::sync::Mutex<Vec<usize>>,
+        rejected: std::sync::Mutex<Vec<RejectReason>>,
+    }
+
+    impl DetectorHooks for RecordingHooks {
+        fn on_quads(&self, quads: &[Quad]) {
+            self.quad_counts.lock().unwrap().push(quads.len());
+        }
+        fn on_candidate_rejected(&self, _quad: &Quad, reason: RejectReason) {
+            self.rejected.lock().unwrap().push(reason);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn hooks_see_quads_and_rejections() {
+        let (img, family) = build_synthetic_tag_image();
+        let hooks = Arc::new(RecordingHooks::default());
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .hooks(hooks.clone())
+            .build();
+
+        let dets = det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(dets.len(), 1);
+
+        let quad_counts = hooks.quad_counts.lock().unwrap().clone();
+        assert_eq!(quad_counts.len(), 1, "on_quads fires once per frame");
+        let rejected = hooks
+            .rejected
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|reason| {
+                matches!(
+                    reason,
+                    RejectReason::DegenerateHomography
+                        | RejectReason::Polarity
+                        | RejectReason::NoCodeMatch
+                )
+            })
+            .count();
+        assert!(quad_counts[0] >= 1);
+        assert!(
+            rejected < quad_counts[0],
+            "the tag quad must not be rejected"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn hooks_report_quad_stage_rejections() {
+        // Shear the tag into a staircase, since perfectly straight edges fit
+        // with an MSE of exactly zero
+        let (straight, family) = build_synthetic_tag_image();
+        let mut img = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                img.set(x, y, straight.get(x.saturating_sub(y / 20), y));
+            }
+        }
+        let rejections = |quad: QuadParams| {
+            let hooks = Arc::new(RecordingHooks::default());
+            let mut det = Detector::new(DetectorConfig {
+                quad_decimate: 1.0,
+                quad,
+                ..DetectorConfig::default()
+            });
+            det.add_family(family.clone(), 2);
+            det.set_hooks(Some(hooks.clone()));
+            assert!(det.detect(&img, &mut DetectorBuffers::new()).is_empty());
+            let rejected = hooks.rejected.lock().unwrap().clone();
+            rejected
+        };
+
+        let rejected = rejections(QuadParams {
+            min_cluster_pixels: Some(100_000),
+            ..QuadParams::default()
+        });
+        assert!(rejected.contains(&RejectReason::ClusterTooSmall));
+        assert!(!rejected.contains(&RejectReason::NoCodeMatch));
+
+        let rejected = rejections(QuadParams {
+            max_line_fit_mse: Some(0.0),
+            ..QuadParams::default()
+        });
+        assert!(rejected.contains(&RejectReason::LineFitMse));
+    }
+
+    #[test]
+    #[cfg(all(feature = "family-tag16h5", feature = "family-tag36h11"))]
+    fn hooks_report_code_mismatch() {
+        let (img, _) = build_synthetic_tag_image();
+        let hooks = Arc::new(RecordingHooks::default());
+        let mut det = Detector::new(DetectorConfig {
+            quad_decimate: 1.0,
+            ..DetectorConfig::default()
+        });
+        det.add_family(family::tag36h11(), 0);
+        det.set_hooks(Some(hooks.clone()));
+
+        assert!(det.detect(&img, &mut DetectorBuffers::new()).is_empty());
+        let rejected = hooks.rejected.lock().unwrap();
+        assert!(rejected.contains(&RejectReason::NoCodeMatch));
+
+        // Removing the hooks stops further callbacks
+        det.set_hooks(None);
+        drop(rejected);
+        det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(hooks.quad_counts.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn homography_projects_to_the_reported_corners() {
+        let (img, family) = build_synthetic_tag_image();
+        let builders = [
+            Detector::builder().quad_decimate(1.0),
+            Detector::builder()
+                .quad_decimate(2.0)
+                .refine_homography(true),
+            Detector::builder().pixel_convention(PixelConvention::Center),
+            Detector::builder().quad_decimate(1.0).refine_decode(true),
+            Detector::builder().interlace_field(InterlaceField::Odd),
+        ];
+        for builder in builders {
+            let det = builder.add_family(family.clone(), 2).build();
+            let mut dets = det.detect(&img, &mut DetectorBuffers::new());
+            assert_eq!(dets.len(), 1);
+            dets[0].scale(0.5, 0.25, det.config.pixel_convention);
+            for d in &dets {
+                let tag = [
+                    [-1.0, -1.0],
+                    [1.0, -1.0],
+                    [1.0, 1.0],
+                    [-1.0, 1.0],
+                    [0.0, 0.0],
+                ];
+                let reported = d.corners.iter().chain([&d.center]);
+                for ([x, y], p) in tag.into_iter().zip(reported) {
+                    let (px, py) = d.homography.project(x, y);
+                    assert!(
+                        (px - p[0]).abs() < 1e-6 && (py - p[1]).abs() < 1e-6,
+                        "({x}, {y}) projects to ({px}, {py}), not {p:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn pixel_convention_shifts_reported_coordinates() {
+        let (img, family) = build_synthetic_tag_image();
+        let detect = |convention| {
+            let det = Detector::builder()
+                .quad_decimate(1.0)
+                .pixel_convention(convention)
+                .add_family(family.clone(), 2)
+                .build();
+            let mut buffers = DetectorBuffers::new();
+            let dets = det.detect(&img, &mut buffers);
+            let quads = det.detect_quads(&img, &mut buffers);
+            (dets, quads)
+        };
+
+        let (corner, corner_quads) = detect(PixelConvention::Corner);
+        let (center, center_quads) = detect(PixelConvention::Center);
+        assert_eq!(corner.len(), 1);
+        let shifted =
+            |a: Vec2, b: Vec2| (a[0] - b[0] - 0.5).abs() < 1e-9 && (a[1] - b[1] - 0.5).abs() < 1e-9;
+        for (a, b) in corner[0].corners.iter().zip(&center[0].corners) {
+            assert!(shifted(*a, *b), "{a:?} vs {b:?}");
+        }
+        assert!(shifted(corner[0].center, center[0].center));
+        assert!(shifted(
+            corner_quads[0].corners[0],
+            center_quads[0].corners[0]
+        ));
+
+        let mut back = center[0].clone();
+        back.convert_pixels(PixelConvention::Center, PixelConvention::Corner);
+        assert!(shifted(back.center, center[0].center));
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn min_decision_margin_drops_weak_decodes() {
+        let (img, family) = build_synthetic_tag_image();
+        let detect = |min_decision_margin: f32| {
+            let hooks = Arc::new(RecordingHooks::default());
+            let det = Detector::builder()
+                .quad_decimate(1.0)
+                .min_decision_margin(min_decision_margin)
+                .add_family(family.clone(), 2)
+                .hooks(hooks.clone())
+                .build();
+            let dets = det.detect(&img, &mut DetectorBuffers::new());
+            let rejected = hooks.rejected.lock().unwrap().clone();
+            (dets, rejected)
+        };
+
+        let (dets, rejected) = detect(0.0);
+        assert_eq!(dets.len(), 1);
+        assert!(!rejected.contains(&RejectReason::LowDecisionMargin));
+        let margin = dets[0].decision_margin;
+
+        assert_eq!(detect(margin - 1.0).0.len(), 1);
+        let (dets, rejected) = detect(margin + 1.0);
+        assert!(dets.is_empty());
+        assert!(rejected.contains(&RejectReason::LowDecisionMargin));
+    }
+
+    #[test]
+    #[cfg(all(feature = "family-tag16h5", feature = "family-tag36h11"))]
+    fn detect_with_info_reports_per_family_yield() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family::tag36h11(), 2)
+            .add_family(family, 2)
+            .build();
+
+        let mut buffers = DetectorBuffers::new();
+        let (dets, info) = det.detect_with_info(&img, &mut buffers);
+        assert_eq!(dets.len(), det.detect(&img, &mut buffers).len());
+
+        let [unused, used] = &info.families[..] else {
+            panic!("expected one entry per family");
+        };
+        assert_eq!(unused.family.to_string(), "tag36h11");
+        assert_eq!((unused.matches, unused.detections), (0, 0));
+        assert_eq!(used.family.to_string(), "tag16h5");
+        assert_eq!(used.detections, 1);
+        assert!(used.matches >= used.detections);
+        // Both families share border polarity, so see the same quads
+        assert!(unused.attempts >= 1);
+        assert_eq!(unused.attempts, used.attempts);
+        assert_eq!(info.skipped, None);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn flat_frames_are_skipped() {
+        let (_, family) = build_synthetic_tag_image();
+        let hooks = Arc::new(RecordingHooks::default());
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .hooks(hooks.clone())
+            .build();
+        let mut buffers = DetectorBuffers::new();
+
+        for (level, noise) in [(0u8, 3u8), (250, 5)] {
+            let img = ImageU8::from_buf(
+                40,
+                40,
+                40,
+                (0..40 * 40)
+                    .map(|i| level + (i % 7) as u8 % noise)
+                    .collect(),
+            );
+            let (dets, info) = det.detect_with_info(&img, &mut buffers);
+            assert!(dets.is_empty());
+            assert_eq!(
+                info.skipped,
+                Some(SkipReason::LowDynamicRange {
+                    min: level,
+                    max: level + noise - 1
+                })
+            );
+            assert_eq!(info.families[0].attempts, 0);
+        }
+        // Hooks still see one (empty) quad list per frame
+        assert_eq!(*hooks.quad_counts.lock().unwrap(), [0, 0]);
+
+        let (_, profile) = det.detect_with_profile(&ImageU8::new(40, 40), &mut buffers);
+        assert_eq!(profile.get("threshold"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_with_profile_times_stages_that_ran() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .build();
+
+        let (dets, profile) = det.detect_with_profile(&img, &mut DetectorBuffers::new());
+        assert_eq!(dets.len(), 1);
+        let names: Vec<&str> = profile.stages.iter().map(|s| s.name).collect();
+        assert_eq!(
+            names,
+            [
+                "threshold",
+                "connected_components",
+                "gradient_clusters",
+                "fit_quads",
+                "refine_edges",
+                "decode",
+                "deduplicate"
+            ]
+        );
+
+        det.config.quad_decimate = 2.0;
+        let (_, profile) = det.detect_with_profile(&img, &mut DetectorBuffers::new());
+        assert_eq!(profile.stages[0].name, "preprocess");
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn band_rows_detect_the_same_tags() {
+        let (mut img, family) = build_synthetic_tag_image();
+        let mut state = 1u32;
+        for v in &mut img.buf {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            *v = (*v as i32 + (state >> 27) as i32 - 16).clamp(0, 255) as u8;
+        }
+        let mut det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .build();
+        let whole = det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(whole.len(), 1);
+
+        det = Detector::builder()
+            .quad_decimate(1.0)
+            .band_rows(7)
+            .add_family(family::tag16h5(), 2)
+            .build();
+        let mut buffers = DetectorBuffers::new();
+        let (banded, profile) = det.detect_with_profile(&img, &mut buffers);
+        assert_eq!(banded.len(), 1);
+        assert_eq!(banded[0].id, whole[0].id);
+        assert_eq!(
+            format!("{:?}", banded[0].corners),
+            format!("{:?}", whole[0].corners)
+        );
+        // The whole-image intermediates are never allocated
+        assert!(buffers.threshed.buf.is_empty());
+        assert_eq!(profile.get("threshold"), None);
+        assert!(profile.get("banded_clusters").is_some());
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "family-tag16h5"))]
+    fn detections_and_config_round_trip_through_serde() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder().add_family(family, 2).build();
+        let detection = det.detect(&img, &mut DetectorBuffers::new()).remove(0);
+        let text = toml::to_string(&detection).unwrap();
+        assert!(text.contains("family = \"tag16h5\""), "{text}");
+        assert!(!text.contains("mirrored"), "{text}");
+        let back: Detection = toml::from_str(&text).unwrap();
+        assert_eq!(format!("{back:?}"), format!("{detection:?}"));
+
+        let config = DetectorConfig {
+            decimate_mode: DecimateMode::Average,
+            max_clusters: Some(200),
+            ..DetectorConfig::default()
+        };
+        let back: DetectorConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(format!("{back:?}"), format!("{config:?}"));
+
+        // Unlisted fields keep their defaults
+        let partial: DetectorConfig = toml::from_str(
+            "quad_decimate = 1.0\ndecimate_mode = \"average\"\n[quad]\ntile_size = 8\n",
+        )
+        .unwrap();
+        assert_eq!(partial.quad_decimate, 1.0);
+        assert_eq!(partial.decimate_mode, DecimateMode::Average);
+        assert_eq!(partial.quad.tile_size, Some(8));
+        assert_eq!(partial.quad.min_white_black_diff, 5);
+        assert!(partial.refine_edges);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_quads_returns_undecoded_candidates() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder().add_family(family, 2).build();
+        let mut buffers = DetectorBuffers::new();
+        let detections = det.detect(&img, &mut buffers);
+        assert_eq!(detections.len(), 1);
+        let quads = det.detect_quads(&img, &mut buffers);
+        let tag = quads
+            .iter()
+            .find(|q| {
+                detections[0]
+                    .corners
+                    .iter()
+                    .all(|&c| q.corners.iter().any(|&qc| (qc - c).norm() < 2.0))
+            })
+            .expect("the tag's quad");
+        assert!(!tag.reversed_border);
+
+        // Reversed borders are found though only tag16h5 is registered
+        let mut inverted = img.clone();
+        for v in &mut inverted.buf {
+            *v = 255 - *v;
+        }
+        assert!(det.detect(&inverted, &mut buffers).is_empty());
+        let quads = det.detect_quads(&inverted, &mut buffers);
+        assert!(quads.iter().any(|q| q.reversed_border));
+
+        // Flat frames have no candidates
+        assert!(det
+            .detect_quads(&ImageU8::new(64, 64), &mut buffers)
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn threshold_settings_reach_the_threshold_stage() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .tile_size(16)
+            .min_white_black_diff(20)
+            .deglitch(true)
+            .add_family(family, 2)
+            .build();
+        let mut buffers = DetectorBuffers::new();
+        assert_eq!(det.detect(&img, &mut buffers).len(), 1);
+
+        // Full-resolution tiles of 300 pixels are 150 working pixels at the
+        // default decimation, more than the decimated image holds
+        let coarse = Detector::builder()
+            .tile_size(300)
+            .add_family(family::tag16h5(), 2)
+            .build();
+        assert!(coarse.detect(&img, &mut buffers).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn id_filter_limits_reported_tags() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut buffers = DetectorBuffers::new();
+        let allowed = Detector::builder()
+            .add_family_with_ids(family.clone(), 2, IdFilter::only([0, 7]))
+            .build();
+        assert_eq!(allowed.detect(&img, &mut buffers).len(), 1);
+        let excluded = Detector::builder()
+            .add_family_with_ids(family, 2, IdFilter::Except(vec![0]))
+            .build();
+        assert!(excluded.detect(&img, &mut buffers).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn interlace_field_detects_on_one_field_in_frame_coordinates() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut buffers = DetectorBuffers::new();
+        let whole = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family.clone(), 0)
+            .build()
+            .detect(&img, &mut buffers);
+        assert_eq!(whole.len(), 1);
+
+        // Comb the odd rows, as a tag moving between field exposures would
+        let mut combed = img.clone();
+        for y in (1..200).step_by(2) {
+            for x in 0..200 {
+                combed.set(x, y, img.get((x + 190) % 200, y));
+            }
+        }
+        for field in [InterlaceField::Even, InterlaceField::Odd] {
+            let det = Detector::builder()
+                .quad_decimate(1.0)
+                .interlace_field(field)
+                .add_family(family.clone(), 0)
+                .build();
+            let source = if field == InterlaceField::Even {
+                &combed
+            } else {
+                &img
+            };
+            let dets = det.detect(source, &mut buffers);
+            assert_eq!(dets.len(), 1, "{field:?}");
+            for (a, b) in dets[0].corners.iter().zip(&whole[0].corners) {
+                assert!((a.0[0] - b.0[0]).abs() < 1.0, "{field:?}: {a:?} vs {b:?}");
+                assert!((a.0[1] - b.0[1]).abs() < 1.0, "{field:?}: {a:?} vs {b:?}");
+            }
+            let quads = det.detect_quads(source, &mut buffers);
+            assert!(quads.iter().any(|q| q
+                .corners
+                .iter()
+                .all(|c| whole[0].corners.iter().any(|w| (c[1] - w.0[1]).abs() < 1.0))));
+            // Quads report fit errors in frame pixels, as detections do
+            let quad = quads
+                .iter()
+                .find(|q| q.refine_residual == dets[0].refine_residual)
+                .expect("quad of the detection");
+            assert!(quad.refine_residual.is_some());
+            assert_eq!(quad.line_fit_mse, dets[0].line_fit_mse);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "debug-images", feature = "family-tag16h5"))]
+    fn debug_output_sees_every_stage() {
+        use crate::detect::debug::DebugImages;
+
+        let (img, family) = build_synthetic_tag_image();
+        let images = Arc::new(DebugImages::default());
+        let det = Detector::builder()
+            .add_family(family, 2)
+            .debug_output(images.clone())
+            .build();
+        assert_eq!(det.detect(&img, &mut DetectorBuffers::new()).len(), 1);
+
+        let dumped = images.take();
+        let stages: Vec<DebugStage> = dumped.iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(
+            stages,
+            [
+                DebugStage::Preprocessed,
+                DebugStage::Threshold,
+                DebugStage::Clusters,
+                DebugStage::Quads,
+                DebugStage::Rejected
+            ]
+        );
+        let sizes: Vec<(u32, u32, u8)> = dumped
+            .iter()
+            .map(|(_, i)| (i.width, i.height, i.channels))
+            .collect();
+        let (w, h) = (img.width, img.height);
+        assert_eq!(
+            sizes,
+            [
+                (w / 2, h / 2, 1),
+                (w / 2, h / 2, 1),
+                (w / 2, h / 2, 3),
+                (w, h, 3),
+                (w, h, 3)
+            ]
+        );
+        // The threshold image is strictly ternary
+        assert!(dumped[1].1.data.iter().all(|v| [0, 127, 255].contains(v)));
+        assert!(images.take().is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "parallel", feature = "family-tag16h5"))]
+    fn thread_settings_do_not_change_detections() {
+        let (img, family) = build_synthetic_tag_image();
+        let corners = |det: &Detector| -> Vec<[Vec2; 4]> {
+            det.detect(&img, &mut DetectorBuffers::new())
+                .iter()
+                .map(|d| d.corners)
+                .collect()
+        };
+        let builder = || {
+            Detector::builder()
+                .quad_decimate(1.0)
+                .add_family(family.clone(), 2)
+        };
+        let expected = corners(&builder().build());
+        assert_eq!(expected.len(), 1);
+
+        for n in [1, 3] {
+            let det = builder().num_threads(n).build();
+            assert_eq!(det.pool.is_some(), n > 1);
+            assert_eq!(det.sequential, n == 1);
+            assert_eq!(corners(&det), expected);
+        }
+
+        // The thread count is fixed at construction
+        let mut det = builder().num_threads(1).build();
+        det.config.num_threads = Some(4);
+        assert!(det.sequential && det.pool.is_none());
+        assert_eq!(corners(&det), expected);
+
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap(),
+        );
+        let mut det = builder().num_threads(1).thread_pool(pool.clone()).build();
+        assert_eq!(corners(&det), expected);
+        det.set_thread_pool(None);
+        assert_eq!(corners(&det), expected);
+    }
+
+    #[derive(Default)]
+    struct SampleHooks {
+        samples: std::sync::Mutex<Vec<(FamilyId, Vec<SamplePoint>)>>,
+    }
+
+    impl DetectorHooks for SampleHooks {
+        fn records_samples(&self) -> bool {
+            true
+        }
+        fn on_samples(&self, _quad: &Quad, family: &FamilyId, samples: &[SamplePoint]) {
+            self.samples
+                .lock()
+                .unwrap()
+                .push((family.clone(), samples.to_vec()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn hooks_see_sample_positions() {
+        let (img, family) = build_synthetic_tag_image();
+        let hooks = Arc::new(SampleHooks::default());
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .hooks(hooks.clone())
+            .build();
+        assert_eq!(det.detect(&img, &mut DetectorBuffers::new()).len(), 1);
+
+        // The tag occupies cells 60..140 px, 10 px each; pick the attempt on
+        // its quad, where all samples are inside the image.
+        let samples = hooks.samples.lock().unwrap();
+        let (family_id, tag_samples) = samples
+            .iter()
+            .find(|(_, samples)| samples.iter().all(|s| s.used))
+            .expect("the tag quad's decode attempt is reported");
+        assert_eq!(&**family_id, "tag16h5");
+
+        let mut bit_cells = Vec::new();
+        for s in tag_samples {
+            let pixel = img.get(s.x.round() as u32, s.y.round() as u32);
+            match s.kind {
+                SampleKind::WhiteBorder => assert_eq!(pixel, 255),
+                SampleKind::BlackBorder => assert_eq!(pixel, 0),
+                SampleKind::Bit(_) => {
+                    // Data bits are sampled at cell centers
+                    let cell = |v: f64| ((v - 65.0) / 10.0).round();
+                    let (cx, cy) = (cell(s.x), cell(s.y));
+                    assert!((s.x - (65.0 + 10.0 * cx)).abs() < 0.5);
+                    assert!((s.y - (65.0 + 10.0 * cy)).abs() < 0.5);
+                    bit_cells.push((cx as i32, cy as i32));
+                }
+            }
+        }
+        bit_cells.sort_unstable();
+        bit_cells.dedup();
+        assert_eq!(bit_cells.len(), 16);
+    }
+
+    /// Helper to build the synthetic tag image used across tests.
+    #[cfg(feature = "family-tag16h5")]
+    fn build_synthetic_tag_image() -> (ImageU8, crate::family::TagFamily) {
+        let family = family::tag16h5();
+        let rendered = family.tag(0).render();
+
+        let mut img = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                img.set(x, y, 255);
+            }
+        }
+
+        let scale = 10u32;
+        let ox = 60u32;
+        let oy = 60u32;
+        for ty in 0..rendered.grid_size {
+            for tx in 0..rendered.grid_size {
+                let pixel = rendered.pixel(tx, ty);
+                let val = match pixel {
+                    crate::types::Pixel::Black => 0u8,
+                    _ => 255u8,
+                };
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        img.set(
+                            ox + tx as u32 * scale + dx,
+                            oy + ty as u32 * scale + dy,
+                            val,
+                        );
+                    }
+                }
+            }
+        }
+
+        (img, family)
+    }
+
+    /// Bending a tag's edges shows up in the fit errors but not in the ID.
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detections_report_edge_fit_quality() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family.clone(), 2)
+            .build();
+        let straight = det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(straight.len(), 1);
+
+        // Shift each row sideways along a half sine, bowing the vertical edges
+        let mut bent = ImageU8::new(200, 200);
+        for y in 0..200 {
+            let shift = (6.0 * (std::f64::consts::PI * (y as f64 - 60.0) / 80.0).sin()) as i32;
+            let shift = if (60..140).contains(&y) { shift } else { 0 };
+            for x in 0..200 {
+                let sx = (x as i32 - shift).clamp(0, 199) as u32;
+                bent.set(x, y, img.get(sx, y));
+            }
+        }
+        let bent = det.detect(&bent, &mut DetectorBuffers::new());
+        assert_eq!(bent.len(), 1);
+        assert_eq!(bent[0].id, straight[0].id);
+
+        let (s, b) = (
+            straight[0].refine_residual.unwrap(),
+            bent[0].refine_residual.unwrap(),
+        );
+        assert!(straight[0].line_fit_mse < 1.0 && s < 0.1);
+        assert!(bent[0].line_fit_mse > 4.0 * straight[0].line_fit_mse);
+        assert!(b > 4.0 * s);
+
+        let unrefined = Detector::builder()
+            .quad_decimate(1.0)
+            .refine_edges(false)
+            .add_family(family, 2)
+            .build()
+            .detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(unrefined[0].refine_residual, None);
+        assert_eq!(unrefined[0].line_fit_mse, straight[0].line_fit_mse);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn region_mask_hides_tags_in_every_pipeline() {
+        let (img, family) = build_synthetic_tag_image();
+        // The tag spans roughly 60..140 in both axes
+        let mask = |excluded: fn(u32, u32) -> bool| {
+            let mut mask = ImageU8::new(200, 200);
+            for y in 0..200 {
+                for x in 0..200 {
+                    mask.set(x, y, if excluded(x, y) { 0 } else { 255 });
+                }
+            }
+            mask
+        };
+        let over_tag = mask(|x, y| (50..150).contains(&x) && (50..150).contains(&y));
+        let elsewhere = mask(|x, _| !(40..170).contains(&x));
+
+        for builder in [
+            Detector::builder().quad_decimate(1.0),
+            Detector::builder().quad_decimate(2.0),
+            Detector::builder().quad_decimate(1.0).band_rows(16),
+            Detector::builder().interlace_field(InterlaceField::Even),
+        ] {
+            let det = builder.add_family(family.clone(), 0).build();
+            let mut buffers = DetectorBuffers::new();
+            let expected = det.detect(&img, &mut buffers);
+            assert_eq!(expected.len(), 1, "{:?}", det.config);
+            let found = det.detect_masked(&img, &elsewhere, &mut buffers);
+            assert_eq!(found.len(), 1, "{:?}", det.config);
+            assert_eq!(found[0].corners, expected[0].corners);
+            assert!(det.detect_masked(&img, &over_tag, &mut buffers).is_empty());
+        }
+
+        let det = Detector::builder().add_family(family, 0).build();
+        assert_eq!(
+            det.try_detect_masked(&img, &ImageU8::new(100, 100), &mut DetectorBuffers::new())
+                .unwrap_err(),
+            DetectError::MaskSizeMismatch {
+                width: 200,
+                height: 200,
+                mask_width: 100,
+                mask_height: 100,
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn inverted_prints_need_their_polarity() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut inverted = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                inverted.set(x, y, 255 - img.get(x, y));
+            }
+        }
+        let detect = |polarity, img: &ImageU8| {
+            Detector::builder()
+                .quad_decimate(1.0)
+                .polarity(polarity)
+                .add_family(family.clone(), 0)
+                .build()
+                .detect(img, &mut DetectorBuffers::new())
+        };
+
+        let normal = detect(Polarity::Normal, &img);
+        assert_eq!(normal.len(), 1);
+        assert!(detect(Polarity::Normal, &inverted).is_empty());
+        assert!(detect(Polarity::Inverted, &img).is_empty());
+        for polarity in [Polarity::Inverted, Polarity::Either] {
+            let found = detect(polarity, &inverted);
+            assert_eq!(found.len(), 1, "{polarity:?}");
+            assert_eq!((found[0].id, found[0].hamming), (normal[0].id, 0));
+            for (a, b) in found[0].corners.iter().zip(&normal[0].corners) {
+                assert!((*a - *b).norm() < 0.5, "{polarity:?}: {a:?} vs {b:?}");
+            }
+        }
+        assert_eq!(detect(Polarity::Either, &img).len(), 1);
+    }
+
+    /// Bowed vertical edges make corners uncertain in x, and the covariance
+    /// follows its corner when the tag is seen transposed.
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn corner_covariance_follows_edge_noise() {
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .decode_mirrored(true)
             .build();
-        assert!((det.config.quad_decimate - 1.0).abs() < 1e-6);
-        assert!((det.config.quad_sigma - 0.5).abs() < 1e-6);
-        assert!(!det.config.refine_edges);
-        assert!((det.config.decode_sharpening - 0.5).abs() < 1e-6);
-        assert!(det.config.qtp.deglitch);
+        let straight = det.detect(&img, &mut DetectorBuffers::new());
+
+        let mut bent = ImageU8::new(200, 200);
+        let mut transposed = ImageU8::new(200, 200);
+        for y in 0..200 {
+            let shift = (6.0 * (std::f64::consts::PI * (y as f64 - 60.0) / 80.0).sin()) as i32;
+            let shift = if (60..140).contains(&y) { shift } else { 0 };
+            for x in 0..200 {
+                let v = img.get((x as i32 - shift).clamp(0, 199) as u32, y);
+                bent.set(x, y, v);
+                transposed.set(y, x, v);
+            }
+        }
+        let bent = det.detect(&bent, &mut DetectorBuffers::new());
+        let transposed = det.detect(&transposed, &mut DetectorBuffers::new());
+        assert_eq!((straight.len(), bent.len(), transposed.len()), (1, 1, 1));
+
+        for i in 0..4 {
+            let [xx, xy, yy] = straight[0].corner_covariance[i];
+            assert!(xx > 0.0 && yy > 0.0 && xx * yy > xy * xy);
+            assert!(xx + yy < 0.05, "corner {i}: {xx} {yy}");
+            let [bxx, _, byy] = bent[0].corner_covariance[i];
+            assert!(bxx > 4.0 * xx && bxx > byy, "corner {i}: {bxx} {byy}");
+
+            let corner = bent[0].corners[i];
+            let j = (0..4)
+                .find(|&j| {
+                    (transposed[0].corners[j] - Vec2::new(corner[1], corner[0])).norm() < 0.5
+                })
+                .unwrap();
+            let [txx, _, tyy] = transposed[0].corner_covariance[j];
+            assert!((txx - byy).abs() < 0.1 * byy && (tyy - bxx).abs() < 0.1 * bxx);
+        }
     }
 
+    /// Detect a tag in a horizontally mirrored and in a transposed
+    /// (mirrored + rotated) image, checking that corners are reported in the
+    /// tag's own frame.
     #[test]
-    #[cfg(feature = "family-tag36h11")]
-    fn builder_adds_families() {
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_mirrored_tag() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut flipped = ImageU8::new(200, 200);
+        let mut transposed = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                flipped.set(199 - x, y, img.get(x, y));
+                transposed.set(y, x, img.get(x, y));
+            }
+        }
+
         let det = Detector::builder()
-            .add_family(family::tag36h11(), 2)
+            .quad_decimate(1.0)
+            .decode_mirrored(true)
+            .add_family(family, 2)
             .build();
-        assert_eq!(det.families.len(), 1);
+        let reference = det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(reference.len(), 1);
+        assert!(!reference[0].mirrored);
+
+        let flip = |c: Vec2| Vec2::new(200.0 - c[0], c[1]);
+        let transpose = |c: Vec2| Vec2::new(c[1], c[0]);
+        for (image, map) in [
+            (&flipped, &flip as &dyn Fn(Vec2) -> Vec2),
+            (&transposed, &transpose),
+        ] {
+            let dets = det.detect(image, &mut DetectorBuffers::new());
+            assert_eq!(dets.len(), 1);
+            assert_eq!(dets[0].id, 0);
+            assert!(dets[0].mirrored);
+            for (c, r) in dets[0].corners.iter().zip(&reference[0].corners) {
+                let expected = map(*r);
+                assert!(
+                    (c[0] - expected[0]).abs() < 0.5 && (c[1] - expected[1]).abs() < 0.5,
+                    "corner {c:?} != {expected:?}"
+                );
+            }
+        }
     }
 
     #[test]
-    fn buffers_convenience_returns_empty() {
-        let bufs = Detector::buffers();
-        // Just verify it returns a valid DetectorBuffers (same as DetectorBuffers::new())
-        assert_eq!(
-            std::mem::size_of_val(&bufs),
-            std::mem::size_of::<DetectorBuffers>()
-        );
+    fn decode_mirrored_can_change_after_families_are_added() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut flipped = ImageU8::new(200, 200);
+        for y in 0..200 {
+            for x in 0..200 {
+                flipped.set(199 - x, y, img.get(x, y));
+            }
+        }
+
+        let mut det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .build();
+        let mut buffers = DetectorBuffers::new();
+        assert!(det.detect(&flipped, &mut buffers).is_empty());
+        det.config.decode_mirrored = true;
+        let dets = det.detect(&flipped, &mut buffers);
+        assert_eq!(dets.len(), 1);
+        assert!(dets[0].mirrored);
+        det.config.decode_mirrored = false;
+        assert!(det.detect(&flipped, &mut buffers).is_empty());
     }
 
     #[test]
-    fn builder_default_trait() {
-        let b = DetectorBuilder::default();
-        assert!((b.config.quad_decimate - 2.0).abs() < 1e-6);
+    #[cfg(all(feature = "family-tag16h5", feature = "family-tag36h11"))]
+    fn removing_a_family_keeps_the_others_tables() {
+        let (img, family) = build_synthetic_tag_image();
+        let mut det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family::tag36h11(), 2)
+            .add_family(family, 0)
+            .build();
+        let tag16h5 = Arc::clone(&det.families[1].tables);
+        let mut buffers = DetectorBuffers::new();
+        assert_eq!(det.detect(&img, &mut buffers).len(), 1);
+
+        assert!(det.remove_family("tag36h11"));
+        assert_eq!(det.families().len(), 1);
+        assert!(Arc::ptr_eq(&det.families[0].tables, &tag16h5));
+        assert_eq!(det.detect(&img, &mut buffers).len(), 1);
+
+        det.clear_families();
+        assert_eq!(det.families().len(), 0);
+        assert_eq!(det.decode_table_bytes(), 0);
+        assert!(det.detect(&img, &mut buffers).is_empty());
+        assert!(!det.remove_family("tag16h5"));
     }
 
     #[test]
     #[cfg(feature = "family-tag16h5")]
-    fn builder_detect_synthetic_tag() {
-        let (img, fam) = build_synthetic_tag_image();
+    fn clone_shares_families_across_threads() {
+        let (img, family) = build_synthetic_tag_image();
         let det = Detector::builder()
             .quad_decimate(1.0)
-            .add_family(fam, 2)
+            .add_family(family, 2)
             .build();
-        let dets = det.detect(&img, &mut DetectorBuffers::new());
-        assert!(!dets.is_empty());
-        assert_eq!(dets[0].id, 0);
-    }
+        let mut clone = det.clone();
+        assert!(Arc::ptr_eq(
+            &det.families[0].tables,
+            &clone.families[0].tables
+        ));
 
-    #[test]
-    fn detector_default_config() {
-        let config = DetectorConfig::default();
-        assert!((config.quad_decimate - 2.0).abs() < 1e-6);
-        assert!((config.quad_sigma - 0.0).abs() < 1e-6);
-        assert!(config.refine_edges);
-        assert!((config.decode_sharpening - 0.25).abs() < 1e-6);
-    }
+        // Config and later families are per-instance
+        clone.config.quad_sigma = 0.8;
+        clone.add_family(crate::family::tag16h5(), 0);
+        assert_eq!((det.families.len(), clone.families.len()), (1, 2));
+        assert_eq!(det.config.quad_sigma, 0.0);
 
-    #[test]
-    #[cfg(feature = "family-tag36h11")]
-    fn detector_add_family() {
-        let mut det = Detector::new(DetectorConfig::default());
-        det.add_family(family::tag36h11(), 2);
-        assert_eq!(det.families.len(), 1);
+        let ids: Vec<Vec<i32>> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..3)
+                .map(|_| {
+                    let det = det.clone();
+                    let img = &img;
+                    s.spawn(move || {
+                        let mut buffers = DetectorBuffers::new();
+                        det.detect(img, &mut buffers).iter().map(|d| d.id).collect()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(ids.iter().all(|v| v == &[0]));
     }
 
     #[test]
     #[cfg(feature = "family-tag16h5")]
-    fn detect_empty_image_no_crash() {
-        let mut det = Detector::new(DetectorConfig::default());
-        det.add_family(family::tag16h5(), 2);
-        let img = ImageU8::new(100, 100);
-        let dets = det.detect(&img, &mut DetectorBuffers::new());
-        assert!(dets.is_empty());
+    fn set_max_hamming_on_a_clone_keeps_sharing_tables() {
+        let (mut img, family) = build_synthetic_tag_image();
+        // Flip one data cell so the tag reads one bit off its code
+        for y in 90..100 {
+            for x in 90..100 {
+                img.set(x, y, 255 - img.get(x, y));
+            }
+        }
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .build();
+        let mut clone = det.clone();
+        assert!(clone.set_max_hamming("tag16h5", 0));
+        assert!(Arc::ptr_eq(
+            &det.families[0].tables,
+            &clone.families[0].tables
+        ));
+        assert_eq!(det.families[0].tables.1.max_hamming(), 2);
+
+        let mut buffers = DetectorBuffers::new();
+        let dets = det.detect(&img, &mut buffers);
+        assert_eq!((dets.len(), dets[0].hamming), (1, 1));
+        assert!(clone.detect(&img, &mut buffers).is_empty());
     }
 
     #[test]
-    #[cfg(feature = "family-tag16h5")]
-    fn detect_synthetic_tag() {
-        // Render a tag16h5 tag and embed it in a larger image
-        let family = family::tag16h5();
-        let rendered = family.tag(0).render();
+    #[cfg(all(
+        feature = "tracing",
+        feature = "family-tag16h5",
+        feature = "family-tag36h11"
+    ))]
+    fn tracing_reports_stages_and_rejections() {
+        use std::sync::Mutex;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
 
-        // Create a 200x200 image with white background
-        let mut img = ImageU8::new(200, 200);
-        for y in 0..200 {
-            for x in 0..200 {
-                img.set(x, y, 255);
+        #[derive(Default)]
+        struct Recorder {
+            spans: Mutex<Vec<&'static str>>,
+            span_fields: Mutex<Vec<String>>,
+            events: Mutex<Vec<String>>,
+        }
+
+        struct Fields(String);
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0 += &format!("{}={:?} ", field.name(), value);
             }
         }
 
-        // Place the 8x8 tag at center, scaled up by 10x
-        let scale = 10;
-        let ox = 60u32;
-        let oy = 60u32;
-        for ty in 0..rendered.grid_size {
-            for tx in 0..rendered.grid_size {
-                let pixel = rendered.pixel(tx, ty);
-                let val = match pixel {
-                    crate::types::Pixel::Black => 0u8,
-                    _ => 255u8,
-                };
-                for dy in 0..scale {
-                    for dx in 0..scale {
-                        img.set(
-                            ox + tx as u32 * scale + dx,
-                            oy + ty as u32 * scale + dy,
-                            val,
-                        );
-                    }
-                }
+        impl Subscriber for &'static Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = Fields(String::new());
+                span.record(&mut fields);
+                self.span_fields.lock().unwrap().push(fields.0);
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span.metadata().name());
+                Id::from_u64(spans.len() as u64)
             }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.events.lock().unwrap().push(fields.0);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
         }
 
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = 1.0; // no decimation for synthetic test
-        config.quad_sigma = 0.0;
-        let mut det = Detector::new(config);
-        det.add_family(family, 2);
+        // A tag16h5 tag seen by a tag36h11 detector: the quad is found but
+        // does not decode.
+        let (img, _) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(crate::family::tag36h11(), 2)
+            .build();
 
-        let dets = det.detect(&img, &mut DetectorBuffers::new());
+        let recorder: &'static Recorder = Box::leak(Box::default());
+        let dets = tracing::subscriber::with_default(recorder, || {
+            det.detect(&img, &mut DetectorBuffers::new())
+        });
+        assert!(dets.is_empty());
 
-        // We should detect tag ID 0
-        assert!(!dets.is_empty(), "Should detect at least one tag, got none");
-        assert_eq!(dets[0].id, 0, "Should detect tag ID 0");
+        let spans = recorder.spans.lock().unwrap();
+        for name in ["detect", "preprocess", "threshold", "fit_quads", "decode"] {
+            assert!(spans.contains(&name), "missing span {name}: {spans:?}");
+        }
+        // Without decimation or blur, preprocessing reads the image in place
+        let preprocess = spans.iter().position(|&name| name == "preprocess").unwrap();
+        assert_eq!(
+            recorder.span_fields.lock().unwrap()[preprocess],
+            "skipped=true "
+        );
+        let events = recorder.events.lock().unwrap();
+        assert!(events.iter().any(|e| e.starts_with("quads=")));
+        assert!(events
+            .iter()
+            .any(|e| e.contains("candidate rejected") && e.contains("reason=\"no-code-match\"")));
+        assert!(events.iter().any(|e| e.contains("cluster rejected")));
+        assert!(events.iter().any(|e| e.starts_with("detections=0")));
     }
 
     #[test]
-    fn compute_detection_geometry_identity() {
-        let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(Vec2::from);
-        let h = Homography::from_quad_corners(&corners).unwrap();
-        let (center, det_corners) = compute_detection_geometry(&h, 0);
-        assert!((center[0] - 0.0).abs() < 1e-6);
-        assert!((center[1] - 0.0).abs() < 1e-6);
-        for i in 0..4 {
-            assert!((det_corners[i][0] - corners[i][0]).abs() < 1e-6);
-            assert!((det_corners[i][1] - corners[i][1]).abs() < 1e-6);
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_on_resized_frame_scales_back() {
+        use crate::detect::image::{resize, ResizeFilter};
+
+        let (img, family) = build_synthetic_tag_image();
+        let det = Detector::builder()
+            .quad_decimate(1.0)
+            .add_family(family, 2)
+            .build();
+        let reference = det.detect(&img, &mut DetectorBuffers::new());
+        assert_eq!(reference.len(), 1);
+
+        let small = resize(&img, 150, 150, ResizeFilter::Area);
+        let mut dets = det.detect(&small, &mut DetectorBuffers::new());
+        assert_eq!(dets.len(), 1);
+        dets[0].scale(200.0 / 150.0, 200.0 / 150.0, PixelConvention::Corner);
+        for (c, r) in dets[0].corners.iter().zip(&reference[0].corners) {
+            assert!(
+                (c[0] - r[0]).abs() < 1.0 && (c[1] - r[1]).abs() < 1.0,
+                "corner {c:?} != {r:?}"
+            );
         }
     }
 
-    /// Helper to build the synthetic tag image used across tests.
+    #[test]
     #[cfg(feature = "family-tag16h5")]
-    fn build_synthetic_tag_image() -> (ImageU8, crate::family::TagFamily) {
-        let family = family::tag16h5();
-        let rendered = family.tag(0).render();
+    fn scale_respects_the_pixel_convention() {
+        use crate::detect::image::{resize, ResizeFilter};
 
-        let mut img = ImageU8::new(200, 200);
-        for y in 0..200 {
-            for x in 0..200 {
-                img.set(x, y, 255);
+        let (img, family) = build_synthetic_tag_image();
+        let small = resize(&img, 50, 50, ResizeFilter::Area);
+        let detect = |convention| {
+            let det = Detector::builder()
+                .quad_decimate(1.0)
+                .pixel_convention(convention)
+                .add_family(family.clone(), 2)
+                .build();
+            let mut dets = det.detect(&small, &mut DetectorBuffers::new());
+            assert_eq!(dets.len(), 1);
+            dets.remove(0)
+        };
+
+        // Scaled at pixel corners and then shifted to centers
+        let mut expected = detect(PixelConvention::Corner);
+        expected.scale(4.0, 4.0, PixelConvention::Corner);
+        expected.convert_pixels(PixelConvention::Corner, PixelConvention::Center);
+
+        let mut center = detect(PixelConvention::Center);
+        let unscaled = center.corners[0];
+        center.scale(4.0, 4.0, PixelConvention::Center);
+        let reported = center.corners.iter().chain([&center.center]);
+        let wanted = expected.corners.iter().chain([&expected.center]);
+        for (c, e) in reported.zip(wanted) {
+            assert!(
+                (c[0] - e[0]).abs() < 1e-9 && (c[1] - e[1]).abs() < 1e-9,
+                "{c:?} != {e:?}"
+            );
+        }
+        // Scaling center coordinates directly would be (4 - 1) / 2 px off
+        let naive = unscaled[0] * 4.0;
+        assert!((center.corners[0][0] - (naive + 1.5)).abs() < 1e-9);
+        let (px, py) = center.homography.project(0.0, 0.0);
+        assert!((px - center.center[0]).abs() < 1e-9 && (py - center.center[1]).abs() < 1e-9);
+    }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn three_halves_decimation_scales_corners_back() {
+        let (img, family) = build_synthetic_tag_image();
+        for refine in [false, true] {
+            let detect = |quad_decimate| {
+                let det = Detector::builder()
+                    .quad_decimate(quad_decimate)
+                    .refine_edges(refine)
+                    .add_family(family.clone(), 2)
+                    .build();
+                det.detect(&img, &mut DetectorBuffers::new())
+            };
+            let reference = detect(1.0);
+            let dets = detect(1.5);
+            assert_eq!((reference.len(), dets.len()), (1, 1));
+            for (c, r) in dets[0].corners.iter().zip(&reference[0].corners) {
+                assert!(
+                    (c[0] - r[0]).abs() < 1.0 && (c[1] - r[1]).abs() < 1.0,
+                    "refine={refine}: corner {c:?} != {r:?}"
+                );
             }
         }
+    }
 
-        let scale = 10u32;
-        let ox = 60u32;
-        let oy = 60u32;
-        for ty in 0..rendered.grid_size {
-            for tx in 0..rendered.grid_size {
-                let pixel = rendered.pixel(tx, ty);
-                let val = match pixel {
-                    crate::types::Pixel::Black => 0u8,
-                    _ => 255u8,
-                };
-                for dy in 0..scale {
-                    for dx in 0..scale {
-                        img.set(
-                            ox + tx as u32 * scale + dx,
-                            oy + ty as u32 * scale + dy,
-                            val,
-                        );
-                    }
-                }
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn average_decimation_scales_corners_back() {
+        let (img, family) = build_synthetic_tag_image();
+        let detect = |quad_decimate, mode| {
+            let det = Detector::builder()
+                .quad_decimate(quad_decimate)
+                .decimate_mode(mode)
+                .refine_edges(false)
+                .add_family(family.clone(), 2)
+                .build();
+            det.detect(&img, &mut DetectorBuffers::new())
+        };
+        let reference = detect(1.0, DecimateMode::Point);
+        for quad_decimate in [2.0, 3.0] {
+            let dets = detect(quad_decimate, DecimateMode::Average);
+            assert_eq!((reference.len(), dets.len()), (1, 1));
+            for (c, r) in dets[0].corners.iter().zip(&reference[0].corners) {
+                assert!(
+                    (c[0] - r[0]).abs() < 1.0 && (c[1] - r[1]).abs() < 1.0,
+                    "quad_decimate={quad_decimate}: corner {c:?} != {r:?}"
+                );
             }
         }
+    }
 
-        (img, family)
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn decimation_larger_than_the_image_finds_nothing() {
+        let (img, family) = build_synthetic_tag_image();
+        for mode in [DecimateMode::Point, DecimateMode::Average] {
+            let det = Detector::builder()
+                .quad_decimate(70_000.0)
+                .decimate_mode(mode)
+                .add_family(family.clone(), 2)
+                .build();
+            assert!(det.detect(&img, &mut DetectorBuffers::new()).is_empty());
+        }
     }
 
     /// Regression test: large tags (200px in 500x500) must be detected with
@@ -797,9 +3918,11 @@ mod tests {
         let (img, tag16h5) = build_synthetic_tag_image();
         let circle21h7 = family::tag_circle21h7();
 
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = 1.0;
-        config.quad_sigma = 0.0;
+        let config = DetectorConfig {
+            quad_decimate: 1.0,
+            quad_sigma: 0.0,
+            ..DetectorConfig::default()
+        };
         let mut det = Detector::new(config);
         det.add_family(tag16h5, 2);
         det.add_family(circle21h7, 2);
@@ -829,9 +3952,11 @@ mod tests {
     fn detect_deterministic_across_buffer_reuse() {
         let (img, family) = build_synthetic_tag_image();
 
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = 1.0;
-        config.quad_sigma = 0.0;
+        let config = DetectorConfig {
+            quad_decimate: 1.0,
+            quad_sigma: 0.0,
+            ..DetectorConfig::default()
+        };
         let mut det = Detector::new(config);
         det.add_family(family, 2);
 
@@ -855,9 +3980,11 @@ mod tests {
     fn detect_reuses_allocations() {
         let (img, family) = build_synthetic_tag_image();
 
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = 1.0;
-        config.quad_sigma = 0.0;
+        let config = DetectorConfig {
+            quad_decimate: 1.0,
+            quad_sigma: 0.0,
+            ..DetectorConfig::default()
+        };
         let mut det = Detector::new(config);
         det.add_family(family, 2);
 
@@ -940,9 +4067,11 @@ mod tests {
     fn detect_image_ref_matches_image_u8() {
         let (img, family) = build_synthetic_tag_image();
 
-        let mut config = DetectorConfig::default();
-        config.quad_decimate = 1.0;
-        config.quad_sigma = 0.0;
+        let config = DetectorConfig {
+            quad_decimate: 1.0,
+            quad_sigma: 0.0,
+            ..DetectorConfig::default()
+        };
         let mut det = Detector::new(config);
         det.add_family(family, 2);
 
@@ -962,4 +4091,97 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn detect_strided_image_ref_without_copy() {
+        let (img, family) = build_synthetic_tag_image();
+
+        // Pad each row with bytes that would form edges if they were read
+        let stride = img.width + 13;
+        let mut padded = vec![255u8; (stride * img.height) as usize];
+        for y in 0..img.height {
+            let off = (y * stride) as usize;
+            padded[off..off + img.width as usize].copy_from_slice(img.row(y));
+        }
+        let img_ref = ImageRef::new(img.width, img.height, stride, &padded);
+
+        let mut det = Detector::new(DetectorConfig {
+            quad_decimate: 1.0,
+            quad_sigma: 0.0,
+            quad: QuadParams {
+                min_edge_gradient: 5,
+                ..QuadParams::default()
+            },
+            ..DetectorConfig::default()
+        });
+        det.add_family(family, 2);
+
+        let dets_owned = det.detect(&img, &mut DetectorBuffers::new());
+        let mut buffers = DetectorBuffers::new();
+        let dets_borrowed = det.detect(&img_ref, &mut buffers);
+
+        assert_eq!(dets_owned.len(), 1);
+        assert_eq!(dets_borrowed.len(), 1);
+        assert_eq!(dets_owned[0].id, dets_borrowed[0].id);
+        for i in 0..4 {
+            assert_eq!(dets_owned[0].corners[i], dets_borrowed[0].corners[i]);
+        }
+        // The pipeline read the caller's buffer in place
+        assert!(buffers.decimated.buf.is_empty());
+        assert!(buffers.filtered.buf.is_empty());
+    }
+
+    #[test]
+    fn detect_u16_dark_frame() {
+        let (img, family) = build_synthetic_tag_image();
+
+        // A dim 12-bit exposure: the whole scene spans 64 raw levels, which a
+        // fixed bit-depth mapping squeezes into 4 intensity levels.
+        let pixels: Vec<u16> = (0..img.height)
+            .flat_map(|y| img.row(y).to_vec())
+            .map(|v| 200 + v as u16 / 4)
+            .collect();
+        let img16 = ImageU16::from_pixels(img.width, img.height, pixels);
+
+        let mut det = Detector::new(DetectorConfig {
+            quad_decimate: 1.0,
+            ..DetectorConfig::default()
+        });
+        det.add_family(family, 2);
+        let mut buffers = DetectorBuffers::new();
+
+        assert!(det
+            .detect_u16(&img16, ToneMap::BitDepth(12), &mut buffers)
+            .is_empty());
+        let dets = det.detect_u16(&img16, ToneMap::default(), &mut buffers);
+        assert_eq!(dets.len(), 1);
+        assert_eq!(buffers.tone_mapped.width, img.width);
+        assert_eq!(buffers.tone_hist.len(), 1 << 16);
+        // The histogram is cleared between frames
+        let again = det.detect_u16(&img16, ToneMap::default(), &mut buffers);
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].corners, dets[0].corners);
+    }
+
+    #[test]
+    fn try_detect_u16_rejects_oversized_frames() {
+        let det = Detector::builder().max_image_dimension(100).build();
+        let mut buffers = DetectorBuffers::new();
+        assert_eq!(
+            det.try_detect_u16(&ImageU16::new(120, 80), ToneMap::default(), &mut buffers)
+                .unwrap_err(),
+            DetectError::ExceedsMaxDimension {
+                width: 120,
+                height: 80,
+                max: 100
+            }
+        );
+        // Rejected before conversion
+        assert_eq!(buffers.tone_mapped.width, 0);
+        assert!(det
+            .try_detect_u16(&ImageU16::new(100, 100), ToneMap::default(), &mut buffers)
+            .unwrap()
+            .is_empty());
+    }
 }
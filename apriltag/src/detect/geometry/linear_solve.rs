@@ -41,6 +41,7 @@ pub(crate) fn forward_eliminate<const R: usize, const C: usize>(
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_range_loop)]
 mod tests {
     use super::*;
 
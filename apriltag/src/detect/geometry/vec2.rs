@@ -4,6 +4,7 @@ use core::ops;
 ///
 /// Zero-cost abstraction over `[f64; 2]` via `#[repr(transparent)]`.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(transparent)]
 pub struct Vec2(pub [f64; 2]);
 
@@ -1,7 +1,11 @@
-use super::geometry::{forward_eliminate, Mat3, Vec2};
+use super::geometry::{forward_eliminate, Mat3, Vec2, Vec3};
 
 /// A 3x3 homography matrix.
+///
+/// With the `serde` feature, it serializes as its row-major 3×3 array.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Homography {
     pub data: Mat3,
 }
@@ -58,6 +62,91 @@ impl Homography {
         })
     }
 
+    /// Least-squares homography from pixels known to lie on tag-space lines.
+    ///
+    /// Only the distance across each point's line constrains the fit, so
+    /// points need not correspond to known positions along it. Needs points
+    /// on at least two `x` lines and two `y` lines; returns `None` for
+    /// degenerate input. Pixel coordinates are normalized before solving.
+    ///
+    /// ```
+    /// use apriltag::detect::geometry::Vec2;
+    /// use apriltag::detect::homography::{Homography, TagLine};
+    ///
+    /// // A 20px square centered on (20, 20), with points along each edge
+    /// let mut points = Vec::new();
+    /// for t in [12.0, 20.0, 27.0] {
+    ///     points.push((TagLine::X(-1.0), Vec2::new(10.0, t)));
+    ///     points.push((TagLine::X(1.0), Vec2::new(30.0, t)));
+    ///     points.push((TagLine::Y(-1.0), Vec2::new(t, 10.0)));
+    ///     points.push((TagLine::Y(1.0), Vec2::new(t, 30.0)));
+    /// }
+    /// let h = Homography::from_line_points(&points).unwrap();
+    /// let (x, y) = h.project(1.0, -1.0);
+    /// assert!((x - 30.0).abs() < 1e-9 && (y - 10.0).abs() < 1e-9);
+    /// ```
+    pub fn from_line_points(points: &[(TagLine, Vec2)]) -> Option<Self> {
+        if points.is_empty() {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mx = points.iter().map(|(_, p)| p[0]).sum::<f64>() / n;
+        let my = points.iter().map(|(_, p)| p[1]).sum::<f64>() / n;
+        let mean_dist = points
+            .iter()
+            .map(|(_, p)| (p[0] - mx).hypot(p[1] - my))
+            .sum::<f64>()
+            / n;
+        if mean_dist.is_nan() || mean_dist <= 0.0 {
+            return None;
+        }
+        let s = std::f64::consts::SQRT_2 / mean_dist;
+
+        // Solve for the inverse homography G (normalized pixel → tag), whose
+        // rows g0, g1, g2 satisfy (g0 - c g2)·q = 0 for a point q on x = c
+        // (g1 for y = c). Normal equations with g22 = 1, augmented.
+        let mut a = [[0.0f64; 9]; 8];
+        for (line, p) in points {
+            let q = [s * (p[0] - mx), s * (p[1] - my), 1.0];
+            let (row, c) = match *line {
+                TagLine::X(c) => (0, c),
+                TagLine::Y(c) => (1, c),
+            };
+            let mut r = [0.0f64; 9];
+            for j in 0..3 {
+                r[3 * row + j] = q[j];
+                r[6 + j] = -c * q[j];
+            }
+            for i in 0..8 {
+                for j in 0..8 {
+                    a[i][j] += r[i] * r[j];
+                }
+                a[i][8] -= r[i] * r[8];
+            }
+        }
+
+        forward_eliminate::<8, 9>(&mut a, 1e-10)?;
+        let mut g = [0.0f64; 9];
+        g[8] = 1.0;
+        for row in (0..8).rev() {
+            let mut sum = a[row][8];
+            for c in (row + 1)..8 {
+                sum -= a[row][c] * g[c];
+            }
+            g[row] = sum / a[row][row];
+        }
+
+        // H = T⁻¹ G⁻¹, where T normalizes pixel coordinates
+        let g_inv = Mat3([[g[0], g[1], g[2]], [g[3], g[4], g[5]], [g[6], g[7], g[8]]]).inv()?;
+        let t_inv = Mat3([[1.0 / s, 0.0, mx], [0.0, 1.0 / s, my], [0.0, 0.0, 1.0]]);
+        let data = t_inv * g_inv;
+        data.0
+            .iter()
+            .flatten()
+            .all(|v| v.is_finite())
+            .then_some(Homography { data })
+    }
+
     /// Project a point from tag-space to pixel-space.
     pub fn project(&self, x: f64, y: f64) -> (f64, f64) {
         let h = &self.data.0;
@@ -67,10 +156,101 @@ impl Homography {
         (xx / zz, yy / zz)
     }
 
+    /// Follow this homography with the image-space map `(x, y) ->
+    /// (sx * x + dx, sy * y + dy)`.
+    pub fn then_scale_translate(&self, sx: f64, sy: f64, dx: f64, dy: f64) -> Self {
+        let [r0, r1, r2] = self.data.0;
+        let row = |r: [f64; 3], s: f64, d: f64| [0, 1, 2].map(|j| s * r[j] + d * r2[j]);
+        Homography {
+            data: Mat3([row(r0, sx, dx), row(r1, sy, dy), r2]),
+        }
+    }
+
     /// Compute the inverse homography.
     pub fn inverse(&self) -> Option<Self> {
         self.data.inv().map(|data| Homography { data })
     }
+
+    /// Decompose into approximate viewing angles and apparent size, given
+    /// camera intrinsics.
+    ///
+    /// This is a single closed-form step — much cheaper than
+    /// [`estimate_tag_pose`](super::pose::estimate_tag_pose), but without its
+    /// refinement or ambiguity resolution. Returns `None` for degenerate
+    /// homographies.
+    ///
+    /// ```
+    /// use apriltag::detect::geometry::Vec2;
+    /// use apriltag::detect::homography::Homography;
+    ///
+    /// // Fronto-parallel 100px tag at the principal point
+    /// let corners = [[270.0, 190.0], [370.0, 190.0], [370.0, 290.0], [270.0, 290.0]];
+    /// let h = Homography::from_quad_corners(&corners.map(Vec2::from)).unwrap();
+    /// let view = h.view_geometry(500.0, 500.0, 320.0, 240.0).unwrap();
+    /// assert!(view.tilt.abs() < 1e-9);
+    /// assert!((view.size - 100.0).abs() < 1e-9);
+    /// ```
+    pub fn view_geometry(&self, fx: f64, fy: f64, cx: f64, cy: f64) -> Option<ViewGeometry> {
+        let hd = &self.data.0;
+        let col = |j: usize| {
+            Vec3::new(
+                (hd[0][j] - cx * hd[2][j]) / fx,
+                (hd[1][j] - cy * hd[2][j]) / fy,
+                hd[2][j],
+            )
+        };
+        let (c0, c1, c2) = (col(0), col(1), col(2));
+
+        // K^-1 H = λ [r0 | r1 | t]; pick the sign that puts the tag in front.
+        let scale = (c0.norm() + c1.norm()) / 2.0 * c2[2].signum();
+        if !(scale.is_finite() && scale != 0.0) {
+            return None;
+        }
+        let t = c2 / scale;
+        let r0 = (c0 / scale).normalized();
+        let c1 = c1 / scale;
+        let r1 = (c1 - r0 * r0.dot(c1)).normalized();
+        let r2 = r0.cross(r1);
+        if !(r1.norm().is_finite() && t[2] > 0.0) {
+            return None;
+        }
+
+        // R = Rz(roll) · Ry(tilt_x) · Rx(tilt_y), with tag-space y pointing down.
+        Some(ViewGeometry {
+            roll: r0[1].atan2(r0[0]),
+            tilt_x: (-r0[2]).atan2(r0[0].hypot(r0[1])),
+            tilt_y: r1[2].atan2(r2[2]),
+            tilt: r2.dot(t.normalized()).abs().min(1.0).acos(),
+            size: 2.0 * fx / t[2],
+        })
+    }
+}
+
+/// A line of constant tag-space `x` or `y`, such as a cell boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagLine {
+    X(f64),
+    Y(f64),
+}
+
+/// Approximate viewing geometry of a tag, from [`Homography::view_geometry`].
+///
+/// Angles are in radians and follow the same `Rz(roll) · Ry(tilt_x) · Rx(tilt_y)`
+/// convention as tag space (x right, y down, z into the tag).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewGeometry {
+    /// In-plane rotation.
+    pub roll: f64,
+    /// Rotation about the tag's vertical axis (left-right lean).
+    pub tilt_x: f64,
+    /// Rotation about the tag's horizontal axis (top-bottom lean).
+    pub tilt_y: f64,
+    /// Angle between the tag normal and the line of sight to its center;
+    /// 0 when viewed head-on.
+    pub tilt: f64,
+    /// Width in pixels the tag would have if turned to face the camera at
+    /// the same depth.
+    pub size: f64,
 }
 
 #[cfg(test)]
@@ -132,6 +312,76 @@ mod tests {
         assert!((ty - (-0.3)).abs() < 1e-6, "ty={ty}");
     }
 
+    #[test]
+    fn view_geometry_recovers_angles() {
+        let (fx, fy, cx, cy) = (600.0, 580.0, 320.0, 240.0);
+        let (roll, tilt_x, tilt_y) = (0.5f64, 0.6f64, -0.3f64);
+        let (sr, cr) = roll.sin_cos();
+        let (sx, cx_) = tilt_x.sin_cos();
+        let (sy, cy_) = tilt_y.sin_cos();
+        let r0 = [cr * cx_, sr * cx_, -sx];
+        let r1 = [cr * sx * sy - sr * cy_, sr * sx * sy + cr * cy_, cx_ * sy];
+        let t = [0.0, 0.0, 12.0];
+        let k = |v: [f64; 3]| [fx * v[0] + cx * v[2], fy * v[1] + cy * v[2], v[2]];
+        let (h0, h1, h2) = (k(r0), k(r1), k(t));
+        // Arbitrary (negative) scale must not matter
+        let h = Homography {
+            data: Mat3(std::array::from_fn(|i| [h0[i], h1[i], h2[i]])) * -0.3,
+        };
+
+        let view = h.view_geometry(fx, fy, cx, cy).unwrap();
+        assert!((view.roll - roll).abs() < 1e-9);
+        assert!((view.tilt_x - tilt_x).abs() < 1e-9);
+        assert!((view.tilt_y - tilt_y).abs() < 1e-9);
+        assert!((view.size - 100.0).abs() < 1e-9);
+        // On the optical axis, tilt is the angle between normal and z
+        let normal_z = (cx_ * cy_).acos();
+        assert!((view.tilt - normal_z).abs() < 1e-9);
+    }
+
+    #[test]
+    fn view_geometry_degenerate_returns_none() {
+        let h = Homography {
+            data: Mat3([[0.0; 3]; 3]),
+        };
+        assert!(h.view_geometry(500.0, 500.0, 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn from_line_points_recovers_homography() {
+        let corners = v([[10.0, 20.0], [90.0, 15.0], [95.0, 85.0], [5.0, 90.0]]);
+        let h = Homography::from_quad_corners(&corners).unwrap();
+
+        // Points anywhere along interior and border lines
+        let mut points = Vec::new();
+        for c in [-1.0, -0.5, 0.25, 1.0] {
+            for t in [-0.9, -0.3, 0.4, 0.8] {
+                let (px, py) = h.project(c, t);
+                points.push((TagLine::X(c), Vec2::new(px, py)));
+                let (px, py) = h.project(t * 0.7, c);
+                points.push((TagLine::Y(c), Vec2::new(px, py)));
+            }
+        }
+        let fit = Homography::from_line_points(&points).unwrap();
+        for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (0.3, 0.6), (-1.0, 1.0)] {
+            let (fx, fy) = fit.project(x, y);
+            let (ex, ey) = h.project(x, y);
+            assert!((fx - ex).abs() < 1e-6 && (fy - ey).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn from_line_points_rejects_degenerate_input() {
+        assert!(Homography::from_line_points(&[]).is_none());
+
+        // Only x lines: nothing constrains the y direction
+        let points: Vec<_> = [-1.0, 0.0, 1.0]
+            .iter()
+            .flat_map(|&c| [0.0, 5.0, 10.0].map(|t| (TagLine::X(c), Vec2::new(c * 10.0, t))))
+            .collect();
+        assert!(Homography::from_line_points(&points).is_none());
+    }
+
     #[test]
     fn degenerate_returns_none() {
         let corners = v([[5.0, 5.0], [5.0, 5.0], [5.0, 5.0], [5.0, 5.0]]);
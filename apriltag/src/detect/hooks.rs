@@ -0,0 +1,145 @@
+use super::decode::SamplePoint;
+use super::quad::Quad;
+use crate::family::FamilyId;
+
+/// Why a candidate quad did not produce any detection.
+///
+/// The first group of reasons comes from quad fitting, before any family is
+/// tried; the last four from decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RejectReason {
+    /// The cluster has fewer boundary pixels than
+    /// [`QuadParams::min_cluster_pixels`](super::quad::QuadParams::min_cluster_pixels)
+    /// (or the 24 needed to fit four edges), or its bounding box is too small
+    /// to hold a tag of any family; see
+    /// [`QuadThreshParams::min_tag_width`](super::quad::QuadThreshParams::min_tag_width).
+    ClusterTooSmall,
+    /// The cluster has more boundary pixels than the image perimeter allows.
+    ClusterTooLarge,
+    /// The frame had more clusters than
+    /// [`DetectorConfig::max_clusters`](super::detector::DetectorConfig::max_clusters)
+    /// and this one was among the least tag-like by size.
+    TooManyClusters,
+    /// The cluster's border polarity is ambiguous or used by no family.
+    BorderDirection,
+    /// The cluster's boundary has fewer than four corner candidates.
+    NoCorners,
+    /// No four corners split the cluster into edges that fit straight lines
+    /// within [`QuadParams::max_line_fit_mse`](super::quad::QuadParams::max_line_fit_mse).
+    LineFitMse,
+    /// Every corner combination with straight enough edges had a corner
+    /// within [`QuadParams::critical_angle_deg`](super::quad::QuadParams::critical_angle_deg)
+    /// of 0° or 180°.
+    CriticalAngle,
+    /// Adjacent edge lines are (nearly) parallel, so a corner is undefined.
+    NoIntersection,
+    /// The fitted corners enclose a negative area (wrong winding) or are not
+    /// convex.
+    Area,
+    /// Edge refinement moved the corners to an invalid quad.
+    EdgeRefinement,
+    /// The quad corners do not define a valid homography.
+    DegenerateHomography,
+    /// The border polarity did not match any family (e.g. dark inside a
+    /// normal-border family's white ring).
+    Polarity,
+    /// The sampled bits did not match any code within the Hamming limit.
+    NoCodeMatch,
+    /// A code matched, but with a decision margin below
+    /// [`DetectorConfig::min_decision_margin`](super::detector::DetectorConfig::min_decision_margin).
+    LowDecisionMargin,
+}
+
+impl RejectReason {
+    /// Short kebab-case name, suitable for metrics keys.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ClusterTooSmall => "cluster-too-small",
+            Self::ClusterTooLarge => "cluster-too-large",
+            Self::TooManyClusters => "too-many-clusters",
+            Self::BorderDirection => "border-direction",
+            Self::NoCorners => "no-corners",
+            Self::LineFitMse => "line-fit-mse",
+            Self::CriticalAngle => "critical-angle",
+            Self::NoIntersection => "no-intersection",
+            Self::Area => "area",
+            Self::EdgeRefinement => "edge-refinement",
+            Self::DegenerateHomography => "degenerate-homography",
+            Self::Polarity => "polarity",
+            Self::NoCodeMatch => "no-code-match",
+            Self::LowDecisionMargin => "low-decision-margin",
+        }
+    }
+}
+
+/// Per-stage callbacks invoked by [`Detector::detect`](super::detector::Detector::detect).
+///
+/// All methods have no-op defaults, so implementors only override the stages
+/// they care about. Decoding runs in parallel when the `parallel` feature is
+/// enabled, so [`on_candidate_rejected`](Self::on_candidate_rejected) may be
+/// called concurrently and in no particular order.
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use apriltag::detect::hooks::{DetectorHooks, RejectReason};
+/// use apriltag::detect::quad::Quad;
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     quads: AtomicUsize,
+///     rejected: AtomicUsize,
+/// }
+///
+/// impl DetectorHooks for Counter {
+///     fn on_quads(&self, quads: &[Quad]) {
+///         self.quads.fetch_add(quads.len(), Ordering::Relaxed);
+///     }
+///     fn on_candidate_rejected(&self, _quad: &Quad, _reason: RejectReason) {
+///         self.rejected.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(Counter::default());
+/// let det = apriltag::Detector::builder()
+///     .add_family(apriltag::family::tag36h11(), 2)
+///     .hooks(counter.clone())
+///     .build();
+/// ```
+pub trait DetectorHooks: Send + Sync {
+    /// Called once per frame with the fitted (and, if enabled, refined) quads
+    /// in full-resolution pixel coordinates, before decoding.
+    fn on_quads(&self, quads: &[Quad]) {
+        let _ = quads;
+    }
+
+    /// Called for each candidate that did not become a detection: clusters
+    /// and quads dropped during quad fitting or edge refinement, and quads
+    /// that no family decoded.
+    ///
+    /// Coordinates are in full-resolution pixels. Candidates rejected before
+    /// their corners were fitted ([`ClusterTooSmall`](RejectReason::ClusterTooSmall)
+    /// through [`NoIntersection`](RejectReason::NoIntersection)) are reported
+    /// as their cluster's bounding box.
+    fn on_candidate_rejected(&self, quad: &Quad, reason: RejectReason) {
+        let _ = (quad, reason);
+    }
+
+    /// Whether to compute sample positions for
+    /// [`on_samples`](Self::on_samples). Off by default, since it allocates
+    /// per decode attempt.
+    fn records_samples(&self) -> bool {
+        false
+    }
+
+    /// Called for each decode attempt of a quad against a family, whether or
+    /// not it succeeded, with the pixel positions sampled for the border
+    /// models and data bits. Only called if
+    /// [`records_samples`](Self::records_samples) returns `true`; like
+    /// [`on_candidate_rejected`](Self::on_candidate_rejected) it may be
+    /// called concurrently.
+    fn on_samples(&self, quad: &Quad, family: &FamilyId, samples: &[SamplePoint]) {
+        let _ = (quad, family, samples);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::DetectError;
+
 /// Read-only access to a grayscale image.
 ///
 /// Implemented by both [`ImageU8`] (owned) and [`ImageRef`] (borrowed).
@@ -12,13 +14,13 @@ pub trait GrayImage {
     /// Get the pixel value at (x, y).
     #[inline]
     fn get(&self, x: u32, y: u32) -> u8 {
-        self.buf()[(y * self.stride() + x) as usize]
+        self.buf()[y as usize * self.stride() as usize + x as usize]
     }
 
     /// Get a slice of the pixel data for row `y` (width pixels, ignoring stride padding).
     #[inline]
     fn row(&self, y: u32) -> &[u8] {
-        let offset = (y * self.stride()) as usize;
+        let offset = y as usize * self.stride() as usize;
         &self.buf()[offset..offset + self.width() as usize]
     }
 
@@ -89,6 +91,39 @@ pub trait GrayImage {
             + v11 * fx * fy
     }
 
+    /// Single-precision [`interpolate`](Self::interpolate), for targets
+    /// without fast `f64`.
+    fn interpolate_f32(&self, px: f32, py: f32) -> f32 {
+        let x = px - 0.5;
+        let y = py - 0.5;
+        let x0 = x.floor() as i64;
+        let y0 = y.floor() as i64;
+
+        let fx = x - x0 as f32;
+        let fy = y - y0 as f32;
+
+        let w = self.width() as i64;
+        let h = self.height() as i64;
+
+        let clamp_x = |v: i64| v.clamp(0, w - 1) as u32;
+        let clamp_y = |v: i64| v.clamp(0, h - 1) as u32;
+
+        let v00 = self.get(clamp_x(x0), clamp_y(y0)) as f32;
+        let v10 = self.get(clamp_x(x0 + 1), clamp_y(y0)) as f32;
+        let v01 = self.get(clamp_x(x0), clamp_y(y0 + 1)) as f32;
+        let v11 = self.get(clamp_x(x0 + 1), clamp_y(y0 + 1)) as f32;
+
+        v00 * (1.0 - fx) * (1.0 - fy)
+            + v10 * fx * (1.0 - fy)
+            + v01 * (1.0 - fx) * fy
+            + v11 * fx * fy
+    }
+
+    /// Borrow the image data as an [`ImageRef`], without copying.
+    fn as_image_ref(&self) -> ImageRef<'_> {
+        ImageRef::new(self.width(), self.height(), self.stride(), self.buf())
+    }
+
     /// Copy the image data into an owned [`ImageU8`].
     fn to_image_u8(&self) -> ImageU8 {
         ImageU8::from_buf(
@@ -126,7 +161,7 @@ impl<'a> ImageRef<'a> {
     /// `stride` must be >= `width`, and `buf` must contain at least `stride * height` bytes.
     pub fn new(width: u32, height: u32, stride: u32, buf: &'a [u8]) -> Self {
         assert!(stride >= width);
-        assert!(buf.len() >= (stride * height) as usize);
+        assert!(buf.len() >= stride as usize * height as usize);
         Self {
             width,
             height,
@@ -135,12 +170,121 @@ impl<'a> ImageRef<'a> {
         }
     }
 
+    /// Like [`new`](Self::new), but fails instead of panicking if `buf` or
+    /// `stride` is too small, e.g. for a truncated camera frame.
+    ///
+    /// ```
+    /// use apriltag::ImageRef;
+    /// use apriltag::error::DetectError;
+    ///
+    /// let truncated = vec![0u8; 640 * 479];
+    /// let err = ImageRef::try_new(640, 480, 640, &truncated).unwrap_err();
+    /// assert!(matches!(err, DetectError::InvalidImage { len: 306560, .. }));
+    /// ```
+    pub fn try_new(
+        width: u32,
+        height: u32,
+        stride: u32,
+        buf: &'a [u8],
+    ) -> Result<Self, DetectError> {
+        if stride < width || buf.len() < stride as usize * height as usize {
+            return Err(DetectError::InvalidImage {
+                width,
+                height,
+                stride,
+                len: buf.len(),
+            });
+        }
+        Ok(Self::new(width, height, stride, buf))
+    }
+
     /// Create a borrowed image view with stride equal to width (no padding).
     ///
     /// `buf` must contain at least `width * height` bytes.
     pub fn from_pixels(width: u32, height: u32, buf: &'a [u8]) -> Self {
         Self::new(width, height, width, buf)
     }
+
+    /// View the luma plane of an NV12 (or NV21) frame, without copying.
+    ///
+    /// `stride` is the row stride of both planes in bytes. `frame` must hold
+    /// the Y plane followed by the interleaved chroma plane of
+    /// `ceil(height / 2)` rows; the chroma samples are ignored.
+    ///
+    /// ```
+    /// use apriltag::{GrayImage, ImageRef};
+    ///
+    /// let (width, height, stride) = (640, 480, 704);
+    /// let frame = vec![0u8; stride * (height + height / 2)];
+    /// let img = ImageRef::from_nv12(640, 480, 704, &frame);
+    /// assert_eq!((img.width(), img.height()), (640, 480));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is too short; see [`try_from_nv12`](Self::try_from_nv12).
+    pub fn from_nv12(width: u32, height: u32, stride: u32, frame: &'a [u8]) -> Self {
+        Self::try_from_nv12(width, height, stride, frame).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`from_nv12`](Self::from_nv12), but fails instead of panicking
+    /// if `frame` cannot hold both planes or `stride` is narrower than a
+    /// row.
+    pub fn try_from_nv12(
+        width: u32,
+        height: u32,
+        stride: u32,
+        frame: &'a [u8],
+    ) -> Result<Self, DetectError> {
+        let chroma = stride as usize * height.div_ceil(2) as usize;
+        Self::try_yuv420(width, height, stride, frame, chroma)
+    }
+
+    /// View the luma plane of an I420 (or YV12) frame, without copying.
+    ///
+    /// `stride` is the Y plane row stride in bytes; the two chroma planes
+    /// that follow have half the stride and `ceil(height / 2)` rows each,
+    /// and are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is too short; see [`try_from_i420`](Self::try_from_i420).
+    pub fn from_i420(width: u32, height: u32, stride: u32, frame: &'a [u8]) -> Self {
+        Self::try_from_i420(width, height, stride, frame).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`from_i420`](Self::from_i420), but fails instead of panicking
+    /// if `frame` cannot hold all three planes or `stride` is narrower than
+    /// a row.
+    pub fn try_from_i420(
+        width: u32,
+        height: u32,
+        stride: u32,
+        frame: &'a [u8],
+    ) -> Result<Self, DetectError> {
+        let chroma = stride.div_ceil(2) as usize * height.div_ceil(2) as usize;
+        Self::try_yuv420(width, height, stride, frame, 2 * chroma)
+    }
+
+    /// The luma plane of a 4:2:0 frame whose chroma takes `chroma` bytes
+    /// after it.
+    fn try_yuv420(
+        width: u32,
+        height: u32,
+        stride: u32,
+        frame: &'a [u8],
+        chroma: usize,
+    ) -> Result<Self, DetectError> {
+        if stride < width || frame.len() < stride as usize * height as usize + chroma {
+            return Err(DetectError::InvalidImage {
+                width,
+                height,
+                stride,
+                len: frame.len(),
+            });
+        }
+        Ok(Self::new(width, height, stride, frame))
+    }
 }
 
 impl GrayImage for ImageRef<'_> {
@@ -206,7 +350,7 @@ impl ImageU8 {
     /// Create a new image filled with zeros.
     pub fn new(width: u32, height: u32) -> Self {
         let stride = width;
-        let buf = vec![0u8; (stride * height) as usize];
+        let buf = vec![0u8; stride as usize * height as usize];
         Self {
             width,
             height,
@@ -227,7 +371,7 @@ impl ImageU8 {
     /// `stride` must be >= `width`, and `buf` must contain at least `stride * height` bytes.
     pub fn from_buf(width: u32, height: u32, stride: u32, buf: Vec<u8>) -> Self {
         assert!(stride >= width);
-        assert!(buf.len() >= (stride * height) as usize);
+        assert!(buf.len() >= stride as usize * height as usize);
         Self {
             width,
             height,
@@ -239,20 +383,20 @@ impl ImageU8 {
     /// Get the pixel value at (x, y).
     #[inline]
     pub fn get(&self, x: u32, y: u32) -> u8 {
-        self.buf[(y * self.stride + x) as usize]
+        self.buf[y as usize * self.stride as usize + x as usize]
     }
 
     /// Get a slice of the pixel data for row `y` (width pixels, ignoring stride padding).
     #[inline]
     pub fn row(&self, y: u32) -> &[u8] {
-        let offset = (y * self.stride) as usize;
+        let offset = y as usize * self.stride as usize;
         &self.buf[offset..offset + self.width as usize]
     }
 
     /// Set the pixel value at (x, y).
     #[inline]
     pub fn set(&mut self, x: u32, y: u32, val: u8) {
-        self.buf[(y * self.stride + x) as usize] = val;
+        self.buf[y as usize * self.stride as usize + x as usize] = val;
     }
 
     /// Create a zeroed image, reusing an existing buffer to avoid allocation.
@@ -260,7 +404,7 @@ impl ImageU8 {
     /// The buffer is cleared and resized to fit `width * height` pixels.
     /// If the buffer already has sufficient capacity, no allocation occurs.
     pub fn new_reuse(width: u32, height: u32, mut buf: Vec<u8>) -> Self {
-        let len = (width * height) as usize;
+        let len = width as usize * height as usize;
         buf.clear();
         buf.resize(len, 0);
         Self {
@@ -273,7 +417,7 @@ impl ImageU8 {
 
     /// Reconfigure for new dimensions, reusing the allocation. Clears pixel data.
     pub fn reshape(&mut self, width: u32, height: u32) {
-        let len = (width * height) as usize;
+        let len = width as usize * height as usize;
         self.buf.clear();
         self.buf.resize(len, 0);
         self.width = width;
@@ -305,11 +449,427 @@ impl ImageU8 {
     }
 }
 
+/// High-bit-depth grayscale image, e.g. a 10-, 12- or 16-bit mono camera
+/// frame.
+///
+/// The detector works on 8-bit intensities; convert with
+/// [`to_u8`](Self::to_u8) or pass the frame to
+/// [`Detector::detect_u16`](super::detector::Detector::detect_u16).
+///
+/// ```
+/// use apriltag::detect::image::{ImageU16, ToneMap};
+///
+/// let img = ImageU16::from_pixels(2, 1, vec![0, 4095]);
+/// assert_eq!(img.to_u8(ToneMap::BitDepth(12)).buf, vec![0, 255]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImageU16 {
+    pub width: u32,
+    pub height: u32,
+    /// Row stride in pixels, not bytes.
+    pub stride: u32,
+    pub buf: Vec<u16>,
+}
+
+impl ImageU16 {
+    /// Create a new image filled with zeros.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(width, height, vec![0; width as usize * height as usize])
+    }
+
+    /// Create an image from pixel data where stride equals width.
+    pub fn from_pixels(width: u32, height: u32, buf: Vec<u16>) -> Self {
+        Self::from_buf(width, height, width, buf)
+    }
+
+    /// Create an image from existing pixel data.
+    ///
+    /// `stride` must be >= `width`, and `buf` must contain at least `stride * height` pixels.
+    pub fn from_buf(width: u32, height: u32, stride: u32, buf: Vec<u16>) -> Self {
+        assert!(stride >= width);
+        assert!(buf.len() >= stride as usize * height as usize);
+        Self {
+            width,
+            height,
+            stride,
+            buf,
+        }
+    }
+
+    /// Get the pixel value at (x, y).
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> u16 {
+        self.buf[y as usize * self.stride as usize + x as usize]
+    }
+
+    /// Get a slice of the pixel data for row `y` (width pixels, ignoring stride padding).
+    #[inline]
+    pub fn row(&self, y: u32) -> &[u16] {
+        let offset = y as usize * self.stride as usize;
+        &self.buf[offset..offset + self.width as usize]
+    }
+
+    /// Set the pixel value at (x, y).
+    #[inline]
+    pub fn set(&mut self, x: u32, y: u32, val: u16) {
+        self.buf[y as usize * self.stride as usize + x as usize] = val;
+    }
+
+    /// Convert to 8 bits with the given tone mapping.
+    pub fn to_u8(&self, tone_map: ToneMap) -> ImageU8 {
+        let mut out = ImageU8::new(0, 0);
+        self.to_u8_into(tone_map, &mut out);
+        out
+    }
+
+    /// Like [`to_u8`](Self::to_u8), but writes into `out`, reusing its
+    /// allocation.
+    pub fn to_u8_into(&self, tone_map: ToneMap, out: &mut ImageU8) {
+        self.to_u8_with(tone_map, out, &mut Vec::new());
+    }
+
+    /// Like [`to_u8_into`](Self::to_u8_into), but keeps the percentile
+    /// histogram in `hist` so repeated conversions do not allocate.
+    pub(crate) fn to_u8_with(&self, tone_map: ToneMap, out: &mut ImageU8, hist: &mut Vec<u32>) {
+        out.reshape(self.width, self.height);
+        let (lo, hi) = match tone_map {
+            ToneMap::BitDepth(bits) => (0, (1u32 << bits.clamp(8, 16)) - 1),
+            ToneMap::Percentile { low, high } => self.percentile_range(low, high, hist),
+        };
+        let range = (hi - lo).max(1);
+        for y in 0..self.height {
+            let dst = &mut out.buf[y as usize * out.stride as usize..][..self.width as usize];
+            for (d, &v) in dst.iter_mut().zip(self.row(y)) {
+                let v = (v as u32).clamp(lo, hi) - lo;
+                *d = ((v * 255 + range / 2) / range) as u8;
+            }
+        }
+    }
+
+    /// Sample values at the `low` and `high` percentiles.
+    fn percentile_range(&self, low: f32, high: f32, hist: &mut Vec<u32>) -> (u32, u32) {
+        hist.clear();
+        hist.resize(1 << 16, 0);
+        for y in 0..self.height {
+            for &v in self.row(y) {
+                hist[v as usize] += 1;
+            }
+        }
+        let n = self.width as f64 * self.height as f64;
+        let rank = |p: f32| (p.clamp(0.0, 100.0) as f64 / 100.0 * n) as u64;
+        let value_at = |rank: u64| {
+            let mut seen = 0u64;
+            hist.iter()
+                .position(|&c| {
+                    seen += c as u64;
+                    seen > rank
+                })
+                .unwrap_or(hist.len() - 1) as u32
+        };
+        let lo = value_at(rank(low));
+        let hi = value_at(rank(high).min((n as u64).saturating_sub(1)));
+        (lo, hi.max(lo))
+    }
+}
+
+/// How [`ImageU16::to_u8`] maps high-bit-depth samples to 8 bits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    /// Linear over the full range of samples with the given bit depth (e.g.
+    /// 12 for a 12-bit sensor); brighter samples saturate. Keeps intensities
+    /// comparable across frames.
+    BitDepth(u32),
+    /// Linear between the `low` and `high` percentiles of the frame's
+    /// samples, clipping outside them. Recovers contrast in dark or flat
+    /// frames that a fixed mapping would quantize to a few levels.
+    Percentile { low: f32, high: f32 },
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        Self::Percentile {
+            low: 0.5,
+            high: 99.5,
+        }
+    }
+}
+
+/// Resampling filter for [`resize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeFilter {
+    /// Average over each output pixel's footprint in the source. Best for
+    /// downscaling: every source pixel contributes, so thin features and
+    /// noise are handled better than by integer decimation.
+    #[default]
+    Area,
+    /// Bilinear sampling at output pixel centers. Cheaper, but aliases when
+    /// shrinking by more than 2x.
+    Bilinear,
+}
+
+/// Output size that fits within `max_dimension` on the longer side while
+/// preserving aspect ratio. Never upscales.
+///
+/// ```
+/// use apriltag::detect::image::max_dimension_size;
+///
+/// assert_eq!(max_dimension_size(3840, 2160, 1280), (1280, 720));
+/// assert_eq!(max_dimension_size(640, 480, 1280), (640, 480));
+/// ```
+pub fn max_dimension_size(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_dimension || max_dimension == 0 {
+        return (width, height);
+    }
+    let scale = max_dimension as f64 / longest as f64;
+    let w = ((width as f64 * scale).round() as u32).clamp(1, max_dimension);
+    let h = ((height as f64 * scale).round() as u32).clamp(1, max_dimension);
+    (w, h)
+}
+
+/// Resize an image to `width` x `height`.
+///
+/// Coordinates map continuously: a point `(x, y)` in the output corresponds
+/// to `(x * sx, y * sy)` in the input, where `sx = input width / width` and
+/// `sy = input height / height`.
+///
+/// ```
+/// use apriltag::detect::image::{resize, ResizeFilter};
+/// use apriltag::ImageU8;
+///
+/// let img = ImageU8::from_pixels(4, 2, vec![0, 100, 200, 200, 0, 100, 200, 200]);
+/// let small = resize(&img, 2, 1, ResizeFilter::Area);
+/// assert_eq!(small.buf, vec![50, 200]);
+/// ```
+pub fn resize(img: &impl GrayImage, width: u32, height: u32, filter: ResizeFilter) -> ImageU8 {
+    let mut out = ImageU8::new(0, 0);
+    resize_into(img, width, height, filter, &mut out);
+    out
+}
+
+/// Like [`resize`], but writes into `out`, reusing its allocation.
+pub fn resize_into(
+    img: &impl GrayImage,
+    width: u32,
+    height: u32,
+    filter: ResizeFilter,
+    out: &mut ImageU8,
+) {
+    out.reshape(width, height);
+    if width == 0 || height == 0 || img.width() == 0 || img.height() == 0 {
+        return;
+    }
+    match filter {
+        ResizeFilter::Area => resize_area(img, out),
+        ResizeFilter::Bilinear => {
+            let sx = img.width() as f64 / width as f64;
+            let sy = img.height() as f64 / height as f64;
+            for y in 0..height {
+                let py = (y as f64 + 0.5) * sy;
+                for x in 0..width {
+                    let px = (x as f64 + 0.5) * sx;
+                    let v = img.interpolate(px, py);
+                    out.set(x, y, (v + 0.5).clamp(0.0, 255.0) as u8);
+                }
+            }
+        }
+    }
+}
+
+/// Source pixels overlapping each output pixel along one axis, with weights
+/// normalized to sum to 1. Returns `(first source index, weights)` per output.
+fn area_taps(src: u32, dst: u32) -> Vec<(usize, Vec<f32>)> {
+    let scale = src as f64 / dst as f64;
+    (0..dst)
+        .map(|i| {
+            let lo = i as f64 * scale;
+            let hi = ((i + 1) as f64 * scale).min(src as f64);
+            let first = lo.floor() as usize;
+            let last = (hi.ceil() as usize).clamp(first + 1, src as usize);
+            let weights = (first..last)
+                .map(|j| {
+                    let overlap = (hi.min((j + 1) as f64) - lo.max(j as f64)).max(0.0);
+                    (overlap / (hi - lo)) as f32
+                })
+                .collect();
+            (first, weights)
+        })
+        .collect()
+}
+
+/// Separable area-average resample: rows first, then columns.
+fn resize_area(img: &impl GrayImage, out: &mut ImageU8) {
+    let (width, height) = (out.width, out.height);
+    let xtaps = area_taps(img.width(), width);
+    let ytaps = area_taps(img.height(), height);
+
+    // Horizontal pass: source rows at output width.
+    let w = width as usize;
+    let mut tmp = vec![0f32; img.height() as usize * w];
+    for y in 0..img.height() {
+        let row = img.row(y);
+        let dst = &mut tmp[y as usize * w..(y as usize + 1) * w];
+        for (d, (first, weights)) in dst.iter_mut().zip(&xtaps) {
+            *d = weights
+                .iter()
+                .zip(&row[*first..])
+                .map(|(&wt, &v)| wt * v as f32)
+                .sum();
+        }
+    }
+
+    // Vertical pass.
+    let mut acc = vec![0f32; w];
+    for (y, (first, weights)) in ytaps.iter().enumerate() {
+        acc.fill(0.0);
+        for (k, &wt) in weights.iter().enumerate() {
+            let src = &tmp[(first + k) * w..(first + k + 1) * w];
+            for (a, &v) in acc.iter_mut().zip(src) {
+                *a += wt * v;
+            }
+        }
+        let row_off = y * out.stride as usize;
+        for (d, &a) in out.buf[row_off..row_off + w].iter_mut().zip(&acc) {
+            *d = (a + 0.5).clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn interpolate_f32_matches_f64() {
+        let img = ImageU8::from_buf(5, 4, 6, (0..24).map(|v| (v * 11) as u8).collect());
+        for (px, py) in [(0.0, 0.0), (1.3, 2.7), (2.5, 1.5), (4.9, 3.9), (-1.0, 7.0)] {
+            let a = img.interpolate(px, py);
+            let b = img.interpolate_f32(px as f32, py as f32);
+            assert!((a - b as f64).abs() < 1e-3, "({px}, {py}): {a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn tone_map_bit_depth_saturates() {
+        let img = ImageU16::from_buf(3, 1, 4, vec![0, 2048, 4095, 9]);
+        assert_eq!(img.to_u8(ToneMap::BitDepth(12)).buf, vec![0, 128, 255]);
+        let img = ImageU16::from_pixels(2, 1, vec![1023, 65535]);
+        assert_eq!(img.to_u8(ToneMap::BitDepth(10)).buf, vec![255, 255]);
+        assert_eq!(img.to_u8(ToneMap::BitDepth(16)).buf, vec![4, 255]);
+    }
+
+    #[test]
+    fn tone_map_percentile_stretches_and_clips() {
+        // A dark 12-bit frame using values 100..=199, plus one hot pixel
+        let mut pixels: Vec<u16> = (0..200).map(|i| 100 + (i % 100) as u16).collect();
+        pixels[0] = 4000;
+        let img = ImageU16::from_pixels(20, 10, pixels);
+        let out = img.to_u8(ToneMap::Percentile {
+            low: 1.0,
+            high: 99.0,
+        });
+        assert_eq!(out.get(0, 0), 255);
+        assert_eq!(*out.buf.iter().min().unwrap(), 0);
+        let distinct: std::collections::BTreeSet<u8> = out.buf.iter().copied().collect();
+        assert!(distinct.len() >= 90, "{} levels", distinct.len());
+
+        // A flat frame does not divide by zero
+        let flat = ImageU16::from_pixels(4, 4, vec![700; 16]);
+        assert_eq!(flat.to_u8(ToneMap::default()).buf, vec![0; 16]);
+    }
+
+    #[test]
+    fn yuv420_views_select_luma_plane() {
+        let (w, h, stride) = (5u32, 3u32, 8u32);
+        let luma = (stride * h) as usize;
+        let mut frame: Vec<u8> = (0..luma).map(|i| i as u8).collect();
+
+        // NV12: one interleaved chroma plane of ceil(3/2) = 2 rows
+        frame.resize(luma + 2 * stride as usize, 255);
+        let img = ImageRef::from_nv12(w, h, stride, &frame);
+        assert_eq!(img.row(2), &[16, 17, 18, 19, 20]);
+        assert_eq!(img.get(4, 1), 12);
+
+        // I420: two chroma planes of 4 x 2
+        frame.truncate(luma);
+        frame.resize(luma + 16, 255);
+        let img = ImageRef::from_i420(w, h, stride, &frame);
+        assert_eq!(img.row(0), &[0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nv12_view_rejects_missing_chroma() {
+        let frame = vec![0u8; 8 * 4];
+        let _ = ImageRef::from_nv12(8, 4, 8, &frame);
+    }
+
+    #[test]
+    fn yuv420_try_views_report_short_frames() {
+        let invalid = |len| DetectError::InvalidImage {
+            width: 8,
+            height: 4,
+            stride: 8,
+            len,
+        };
+        // NV12 needs 32 luma + 16 chroma bytes, I420 32 + 2 * 8
+        let frame = vec![0u8; 48];
+        assert!(ImageRef::try_from_nv12(8, 4, 8, &frame).is_ok());
+        assert!(ImageRef::try_from_i420(8, 4, 8, &frame).is_ok());
+        assert_eq!(
+            ImageRef::try_from_nv12(8, 4, 8, &frame[..47]).unwrap_err(),
+            invalid(47)
+        );
+        assert_eq!(
+            ImageRef::try_from_i420(8, 4, 8, &frame[..40]).unwrap_err(),
+            invalid(40)
+        );
+        // A stride narrower than a row is rejected, not panicked on
+        assert!(ImageRef::try_from_nv12(9, 4, 8, &frame).is_err());
+    }
+
+    #[test]
+    fn max_dimension_size_preserves_aspect() {
+        assert_eq!(max_dimension_size(4000, 3000, 1000), (1000, 750));
+        assert_eq!(max_dimension_size(1080, 1920, 960), (540, 960));
+        assert_eq!(max_dimension_size(100, 50, 0), (100, 50));
+        assert_eq!(max_dimension_size(5000, 1, 100), (100, 1));
+    }
+
+    #[test]
+    fn resize_area_non_integer_factor() {
+        // 3 -> 2 columns: each output covers 1.5 source pixels
+        let img = ImageU8::from_pixels(3, 1, vec![0, 90, 180]);
+        let out = resize(&img, 2, 1, ResizeFilter::Area);
+        assert_eq!(out.buf, vec![30, 150]);
+    }
+
+    #[test]
+    fn resize_area_preserves_mean_and_uniform() {
+        let img = ImageU8::from_buf(7, 5, 8, (0..40).map(|v| (v * 6) as u8).collect());
+        let out = resize(&img, 3, 2, ResizeFilter::Area);
+        let mean = |buf: &[u8]| buf.iter().map(|&v| v as f64).sum::<f64>() / buf.len() as f64;
+        let src_mean = (0..5).map(|y| mean(img.row(y))).sum::<f64>() / 5.0;
+        assert!((mean(&out.buf) - src_mean).abs() < 1.0);
+
+        let flat = ImageU8::from_pixels(10, 10, vec![77; 100]);
+        for filter in [ResizeFilter::Area, ResizeFilter::Bilinear] {
+            let out = resize(&flat, 3, 7, filter);
+            assert!(out.buf.iter().all(|&v| v == 77));
+        }
+    }
+
+    #[test]
+    fn resize_into_reuses_buffer() {
+        let img = ImageU8::from_pixels(8, 8, vec![200; 64]);
+        let mut out = ImageU8::new(4, 4);
+        let ptr = out.buf.as_ptr();
+        resize_into(&img, 2, 2, ResizeFilter::Bilinear, &mut out);
+        assert_eq!((out.width, out.height, out.buf.as_ptr()), (2, 2, ptr));
+        assert_eq!(out.buf, vec![200; 4]);
+    }
+
     #[test]
     fn image_ref_new() {
         let data = vec![1, 2, 3, 0, 4, 5, 6, 0];
@@ -356,7 +916,7 @@ mod tests {
     fn image_ref_interpolation() {
         let mut data = vec![0u8; 100];
         // 10x10 image, set pixel (1,1) = 100
-        data[1 * 10 + 1] = 100;
+        data[10 + 1] = 100;
         let img = ImageRef::new(10, 10, 10, &data);
         // At exact pixel center (1.5, 1.5)
         let val = img.interpolate(1.5, 1.5);
@@ -410,6 +970,22 @@ mod tests {
         ImageRef::new(2, 2, 2, &data); // buf too small
     }
 
+    #[test]
+    fn image_ref_try_new_rejects_what_new_panics_on() {
+        let data = vec![0u8; 4];
+        assert!(ImageRef::try_new(2, 2, 2, &data).is_ok());
+        assert_eq!(
+            ImageRef::try_new(3, 2, 2, &data).unwrap_err(),
+            DetectError::InvalidImage {
+                width: 3,
+                height: 2,
+                stride: 2,
+                len: 4
+            }
+        );
+        assert!(ImageRef::try_new(2, 3, 2, &data).is_err());
+    }
+
     #[test]
     fn imageu8_trait_methods_match_inherent() {
         let mut img = ImageU8::new(10, 8);
@@ -1,9 +1,15 @@
 pub(crate) mod par;
+pub(crate) mod trace;
 
+pub mod ablation;
+#[doc(hidden)]
+pub mod banded;
 #[doc(hidden)]
 pub mod cluster;
 #[doc(hidden)]
 pub mod connected;
+#[cfg(feature = "debug-images")]
+pub mod debug;
 #[doc(hidden)]
 #[allow(clippy::needless_range_loop)]
 pub mod decode;
@@ -14,6 +20,7 @@ pub mod geometry;
 #[doc(hidden)]
 #[allow(clippy::needless_range_loop)]
 pub mod homography;
+pub mod hooks;
 #[allow(clippy::needless_range_loop)]
 pub mod image;
 pub use image::{GrayImage, ImageRef};
@@ -21,10 +28,12 @@ pub mod pose;
 #[doc(hidden)]
 #[allow(clippy::needless_range_loop)]
 pub mod preprocess;
+pub mod profile;
 pub mod quad;
 #[doc(hidden)]
 #[allow(clippy::needless_range_loop)]
 pub mod refine;
+pub mod service;
 #[doc(hidden)]
 pub mod threshold;
 #[doc(hidden)]
@@ -1,5 +1,14 @@
+#[cfg(feature = "parallel")]
+use std::cell::Cell;
+
 use smallvec::SmallVec;
 
+#[cfg(feature = "parallel")]
+thread_local! {
+    /// Set while a detector limited to one thread runs on this thread.
+    static FORCE_SEQUENTIAL: Cell<bool> = const { Cell::new(false) };
+}
+
 /// Parallelism strategy: sequential or parallel (rayon).
 ///
 /// Encapsulates both compile-time feature gating and runtime thread-count
@@ -17,12 +26,27 @@ impl Par {
     /// runtime thread count.
     pub(crate) fn get() -> Self {
         #[cfg(feature = "parallel")]
-        if rayon::current_num_threads() > 1 {
+        if rayon::current_num_threads() > 1 && !FORCE_SEQUENTIAL.with(Cell::get) {
             return Self::Parallel;
         }
         Self::Sequential
     }
 
+    /// Run `f` with [`get`](Self::get) returning `Sequential` on this thread,
+    /// regardless of the rayon pool size.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn sequential<R>(f: impl FnOnce() -> R) -> R {
+        /// Restores the previous flag, even if `f` panics.
+        struct Restore(bool);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                FORCE_SEQUENTIAL.with(|c| c.set(self.0));
+            }
+        }
+        let _restore = Restore(FORCE_SEQUENTIAL.with(|c| c.replace(true)));
+        f()
+    }
+
     /// Process chunks of a mutable buffer with an indexed closure.
     ///
     /// Parallel: `par_chunks_mut` + `enumerate` + `for_each`.
@@ -165,6 +189,7 @@ impl Par {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::{Par, SmallVec};
 
@@ -210,6 +235,24 @@ mod tests {
         assert_eq!(items, vec![11, 12, 13]);
     }
 
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn sequential_overrides_pool_size() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            assert!(matches!(Par::get(), Par::Parallel));
+            Par::sequential(|| {
+                assert!(matches!(Par::get(), Par::Sequential));
+                Par::sequential(|| {});
+                assert!(matches!(Par::get(), Par::Sequential));
+            });
+            assert!(matches!(Par::get(), Par::Parallel));
+        });
+    }
+
     #[test]
     fn flat_map_init_collect_sequential() {
         let items = vec![1, 2, 3];
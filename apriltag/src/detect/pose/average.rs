@@ -0,0 +1,250 @@
+use super::super::geometry::Mat3;
+use super::{rotation_angle, Pose};
+
+/// Mean pose and spread of repeated estimates of one tag's pose, e.g. over
+/// a burst of images of a static scene. See [`average_poses`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoseAverage {
+    /// Mean rotation (normalized quaternion mean) and mean translation.
+    pub pose: Pose,
+    /// RMS angle in radians between each rotation and the mean.
+    pub rotation_std: f64,
+    /// Sample standard deviation of the translation per axis; zero for a
+    /// single pose.
+    pub translation_std: [f64; 3],
+}
+
+/// Average `poses` of one tag. Returns `None` if `poses` is empty.
+///
+/// Rotations are averaged as quaternions, each flipped onto the first's
+/// hemisphere since `q` and `-q` are the same rotation. That is accurate
+/// for the small spread of repeated estimates, not for poses far apart.
+///
+/// ```
+/// use apriltag::detect::pose::{average_poses, Pose};
+///
+/// let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+/// let poses = [
+///     Pose { r: identity, t: [0.0, 0.0, 1.0] },
+///     Pose { r: identity, t: [0.0, 0.0, 3.0] },
+/// ];
+/// let average = average_poses(&poses).unwrap();
+/// assert_eq!(average.pose.t, [0.0, 0.0, 2.0]);
+/// assert_eq!(average.translation_std[2], 2f64.sqrt());
+/// assert_eq!(average.rotation_std, 0.0);
+/// ```
+pub fn average_poses(poses: &[Pose]) -> Option<PoseAverage> {
+    if poses.is_empty() {
+        return None;
+    }
+    let n = poses.len() as f64;
+
+    let quaternions: Vec<[f64; 4]> = poses.iter().map(|p| quaternion_from_matrix(&p.r)).collect();
+    let r = matrix_from_quaternion(&mean_quaternion(&quaternions));
+
+    let angle_sq: f64 = poses
+        .iter()
+        .map(|p| rotation_angle(&Mat3(p.r), &Mat3(r)).powi(2))
+        .sum();
+
+    let mut t = [0.0; 3];
+    for p in poses {
+        for (m, v) in t.iter_mut().zip(&p.t) {
+            *m += v / n;
+        }
+    }
+    let mut var = [0.0; 3];
+    if poses.len() > 1 {
+        for p in poses {
+            for ((s, v), m) in var.iter_mut().zip(&p.t).zip(&t) {
+                *s += (v - m).powi(2) / (n - 1.0);
+            }
+        }
+    }
+
+    Some(PoseAverage {
+        pose: Pose { r, t },
+        rotation_std: (angle_sq / n).sqrt(),
+        translation_std: var.map(f64::sqrt),
+    })
+}
+
+/// Normalized sum of unit quaternions `qs`, each flipped onto the first's
+/// hemisphere.
+fn mean_quaternion(qs: &[[f64; 4]]) -> [f64; 4] {
+    let reference = qs[0];
+    let mut sum = [0.0; 4];
+    for q in qs {
+        let sign = if dot(q, &reference) < 0.0 { -1.0 } else { 1.0 };
+        for (s, v) in sum.iter_mut().zip(q) {
+            *s += sign * v;
+        }
+    }
+    let norm = dot(&sum, &sum).sqrt();
+    sum.map(|v| v / norm)
+}
+
+fn dot(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Unit quaternion (w, x, y, z) of a rotation matrix, by Shepperd's method.
+fn quaternion_from_matrix(r: &[[f64; 3]; 3]) -> [f64; 4] {
+    let trace = r[0][0] + r[1][1] + r[2][2];
+    let q = if trace > 0.0 {
+        let s = 2.0 * (1.0 + trace).sqrt();
+        [
+            s / 4.0,
+            (r[2][1] - r[1][2]) / s,
+            (r[0][2] - r[2][0]) / s,
+            (r[1][0] - r[0][1]) / s,
+        ]
+    } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+        let s = 2.0 * (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt();
+        [
+            (r[2][1] - r[1][2]) / s,
+            s / 4.0,
+            (r[0][1] + r[1][0]) / s,
+            (r[0][2] + r[2][0]) / s,
+        ]
+    } else if r[1][1] > r[2][2] {
+        let s = 2.0 * (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt();
+        [
+            (r[0][2] - r[2][0]) / s,
+            (r[0][1] + r[1][0]) / s,
+            s / 4.0,
+            (r[1][2] + r[2][1]) / s,
+        ]
+    } else {
+        let s = 2.0 * (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt();
+        [
+            (r[1][0] - r[0][1]) / s,
+            (r[0][2] + r[2][0]) / s,
+            (r[1][2] + r[2][1]) / s,
+            s / 4.0,
+        ]
+    };
+    // The pose solvers' rotations are orthonormal up to rounding
+    let norm = dot(&q, &q).sqrt();
+    q.map(|v| v / norm)
+}
+
+fn matrix_from_quaternion(q: &[f64; 4]) -> [[f64; 3]; 3] {
+    let [w, x, y, z] = *q;
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - w * z),
+            2.0 * (x * z + w * y),
+        ],
+        [
+            2.0 * (x * y + w * z),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - w * x),
+        ],
+        [
+            2.0 * (x * z - w * y),
+            2.0 * (y * z + w * x),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    /// Rotation by `angle` radians about the Z axis.
+    fn rot_z(angle: f64) -> [[f64; 3]; 3] {
+        let (s, c) = angle.sin_cos();
+        [[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn assert_matrix_eq(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) {
+        for (ra, rb) in a.iter().zip(b) {
+            for (va, vb) in ra.iter().zip(rb) {
+                assert!((va - vb).abs() < 1e-12, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_poses_have_no_average() {
+        assert!(average_poses(&[]).is_none());
+    }
+
+    #[test]
+    fn single_pose_is_its_own_average() {
+        let pose = Pose {
+            r: rot_z(0.3),
+            t: [0.1, -0.2, 1.5],
+        };
+        let average = average_poses(std::slice::from_ref(&pose)).unwrap();
+        assert_matrix_eq(&average.pose.r, &pose.r);
+        assert_eq!(average.pose.t, pose.t);
+        assert!(average.rotation_std < 1e-7);
+        assert_eq!(average.translation_std, [0.0; 3]);
+    }
+
+    #[test]
+    fn identical_identity_poses_average_to_identity() {
+        let pose = Pose {
+            r: IDENTITY,
+            t: [0.0, 0.0, 1.0],
+        };
+        let average = average_poses(&[pose.clone(), pose.clone(), pose]).unwrap();
+        assert_matrix_eq(&average.pose.r, &IDENTITY);
+        assert_eq!(average.rotation_std, 0.0);
+        assert_eq!(average.translation_std, [0.0; 3]);
+    }
+
+    #[test]
+    fn antipodal_quaternions_are_the_same_rotation() {
+        let q = quaternion_from_matrix(&rot_z(0.4));
+        let flipped = q.map(|v| -v);
+        let mean = mean_quaternion(&[q, flipped, q]);
+        for (m, v) in mean.iter().zip(&q) {
+            assert!((m - v).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn spread_matches_known_values() {
+        let angle = 0.1;
+        let poses = [
+            Pose {
+                r: rot_z(angle),
+                t: [1.0, 0.0, 2.0],
+            },
+            Pose {
+                r: rot_z(-angle),
+                t: [3.0, 0.0, 2.0],
+            },
+        ];
+        let average = average_poses(&poses).unwrap();
+        assert_matrix_eq(&average.pose.r, &IDENTITY);
+        assert_eq!(average.pose.t, [2.0, 0.0, 2.0]);
+        assert!((average.rotation_std - angle).abs() < 1e-9);
+        assert_eq!(average.translation_std, [2f64.sqrt(), 0.0, 0.0]);
+    }
+
+    #[test]
+    fn quaternions_round_trip_through_every_branch() {
+        // Half turns about X, Y and Z have trace -1, so each takes the
+        // branch of its axis; the identity takes the positive-trace one
+        let half_turns = [
+            [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]],
+            [[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]],
+            [[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+        ];
+        for r in half_turns.iter().chain([&IDENTITY, &rot_z(2.0)]) {
+            let q = quaternion_from_matrix(r);
+            assert!((dot(&q, &q) - 1.0).abs() < 1e-12);
+            assert_matrix_eq(&matrix_from_quaternion(&q), r);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+
+use super::super::detector::Detection;
+use super::{Pose, PoseParams, TagBundle};
+
+/// Default largest RMS corner reprojection error in pixels for a tag to
+/// count as part of a [`TagGridBoard`]'s pose.
+const DEFAULT_MAX_REPROJECTION_ERROR: f64 = 2.0;
+
+/// A calibration board: `columns` × `rows` tags of one family, `tag_size`
+/// wide and `gap` apart, with consecutive IDs in row-major order from the
+/// top-left tag.
+///
+/// The board frame has its origin at the top-left tag's outer top-left
+/// corner, X along the rows, Y down the columns and Z into the board, so
+/// each tag's axes match the board's. Sizes count the tag's black border but
+/// not its white margin; `tag_size` and `gap` are in the units the pose is
+/// wanted in.
+///
+/// ```
+/// use apriltag::detect::pose::{PoseParams, TagGridBoard};
+///
+/// // 6 × 4 tags of 3 cm, 6 mm apart, IDs 0..24
+/// let board = TagGridBoard::new("tag36h11", 6, 4, 0.03, 0.006);
+/// assert_eq!(board.id(5, 1), Some(11));
+/// # let detections: Vec<apriltag::Detection> = Vec::new();
+/// # let params = PoseParams { tagsize: 0.0, fx: 500.0, fy: 500.0, cx: 320.0, cy: 240.0, solver: Default::default() };
+///
+/// if let Some(board_pose) = board.estimate_pose(&detections, &params) {
+///     println!("board at {:?}, {} tags rejected", board_pose.pose.t, board_pose.outliers.len());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagGridBoard {
+    family: String,
+    columns: usize,
+    rows: usize,
+    first_id: i32,
+    tag_size: f64,
+    gap: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_max_reprojection_error"))]
+    max_reprojection_error: f64,
+}
+
+#[cfg(feature = "serde")]
+fn default_max_reprojection_error() -> f64 {
+    DEFAULT_MAX_REPROJECTION_ERROR
+}
+
+/// The pose of a [`TagGridBoard`] and which of its detected tags agree with
+/// it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardPose {
+    /// Board pose (camera ← board).
+    pub pose: Pose,
+    /// Object-space error of `pose` over the inliers' corners, as from
+    /// [`TagBundle::estimate_pose`].
+    pub error: f64,
+    /// The other local minimum, if the fit found a distinct one.
+    pub alt_pose: Option<Pose>,
+    /// Object-space error of `alt_pose`; `f64::MAX` without one.
+    pub alt_error: f64,
+    /// RMS reprojection error in pixels over the inliers' corners.
+    pub reprojection_error: f64,
+    /// IDs of the detected tags the pose was fit to, ascending.
+    pub inliers: Vec<i32>,
+    /// IDs of detected board tags left out because they do not fit the
+    /// others, such as a misdecoded ID or a tag peeling off the board,
+    /// ascending.
+    pub outliers: Vec<i32>,
+}
+
+impl TagGridBoard {
+    /// A board of `columns` × `rows` tags of `family` with IDs from 0.
+    pub fn new(family: &str, columns: usize, rows: usize, tag_size: f64, gap: f64) -> Self {
+        Self {
+            family: family.to_string(),
+            columns,
+            rows,
+            first_id: 0,
+            tag_size,
+            gap,
+            max_reprojection_error: DEFAULT_MAX_REPROJECTION_ERROR,
+        }
+    }
+
+    /// Number the tags from `id` instead of 0, for boards cut from the
+    /// middle of a family.
+    pub fn first_id(mut self, id: i32) -> Self {
+        self.first_id = id;
+        self
+    }
+
+    /// Reject tags whose RMS corner reprojection error under the board pose
+    /// exceeds `pixels` (default 2).
+    pub fn max_reprojection_error(mut self, pixels: f64) -> Self {
+        self.max_reprojection_error = pixels;
+        self
+    }
+
+    /// Number of tags on the board.
+    pub fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Whether the board has no tags.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// ID of the tag in `column` and `row`, counted from the top left, or
+    /// `None` outside the board.
+    pub fn id(&self, column: usize, row: usize) -> Option<i32> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        i32::try_from(row * self.columns + column)
+            .ok()
+            .and_then(|i| self.first_id.checked_add(i))
+    }
+
+    /// Column and row of the tag with ID `id`, or `None` if it is not on the
+    /// board.
+    pub fn position(&self, id: i32) -> Option<(usize, usize)> {
+        let index = usize::try_from(id.checked_sub(self.first_id)?).ok()?;
+        (index < self.len()).then(|| (index % self.columns, index / self.columns))
+    }
+
+    /// Center of the tag in `column` and `row` in the board frame (on the
+    /// plane `z = 0`).
+    pub fn tag_center(&self, column: usize, row: usize) -> [f64; 2] {
+        let pitch = self.tag_size + self.gap;
+        [
+            column as f64 * pitch + self.tag_size / 2.0,
+            row as f64 * pitch + self.tag_size / 2.0,
+        ]
+    }
+
+    /// The board as a [`TagBundle`] of all its tags.
+    pub fn bundle(&self) -> TagBundle {
+        let tags = (0..self.rows).flat_map(|row| {
+            (0..self.columns).filter_map(move |column| {
+                let id = self.id(column, row)?;
+                let corners =
+                    TagBundle::planar_corners(self.tag_center(column, row), self.tag_size);
+                Some((id, corners))
+            })
+        });
+        TagBundle::new(tags).family(&self.family)
+    }
+
+    /// Estimate the board pose from `detections`, leaving out detected
+    /// board tags that disagree with the rest; `params.tagsize` is ignored.
+    ///
+    /// Each detected tag's own poses are tried as a hypothesis for the whole
+    /// board, and the one that puts the most other tags within
+    /// [`max_reprojection_error`](Self::max_reprojection_error) of their
+    /// detected corners wins. The pose is then fit to those tags' corners
+    /// together, as [`TagBundle::estimate_pose`] does, and the inliers are
+    /// checked again against the fitted pose.
+    ///
+    /// Returns `None` when no board tag was detected or the fit fails.
+    pub fn estimate_pose(
+        &self,
+        detections: &[Detection],
+        params: &PoseParams,
+    ) -> Option<BoardPose> {
+        let bundle = self.bundle();
+        let members = bundle.members(detections);
+
+        let mut best: Option<(usize, f64, Vec<i32>)> = None;
+        for (&id, &det) in &members {
+            let single = TagBundle::new([(id, *bundle.corners(id)?)]);
+            let Some((pose, _, alt, _)) = single.estimate_pose(std::slice::from_ref(det), params)
+            else {
+                continue;
+            };
+            for pose in std::iter::once(pose).chain(alt) {
+                let (inliers, sum_sq) = self.inliers(&bundle, &members, &pose, params);
+                let better = best.as_ref().is_none_or(|(count, best_sq, _)| {
+                    inliers.len() > *count || (inliers.len() == *count && sum_sq < *best_sq)
+                });
+                if better {
+                    best = Some((inliers.len(), sum_sq, inliers));
+                }
+            }
+        }
+
+        // With no hypothesis fitting even its own tag, as with heavily
+        // blurred corners, fit every member instead
+        let mut inliers = match best {
+            Some((_, _, inliers)) if !inliers.is_empty() => inliers,
+            _ => members.keys().copied().collect(),
+        };
+        // Refit until the fitted pose keeps the same inliers
+        let mut fit = None;
+        for _ in 0..3 {
+            let subset = TagBundle::new(
+                inliers
+                    .iter()
+                    .filter_map(|&id| Some((id, *bundle.corners(id)?))),
+            )
+            .family(&self.family);
+            let result = subset.estimate_pose(detections, params)?;
+            let (next, _) = self.inliers(&bundle, &members, &result.0, params);
+            fit = Some(result);
+            if next == inliers || next.is_empty() {
+                break;
+            }
+            inliers = next;
+        }
+        let (pose, error, alt_pose, alt_error) = fit?;
+
+        let sum_sq: f64 = inliers
+            .iter()
+            .filter_map(|id| {
+                Some(reprojection_sq(
+                    &pose,
+                    bundle.corners(*id)?,
+                    members.get(id)?,
+                    params,
+                ))
+            })
+            .sum();
+        let outliers = members
+            .keys()
+            .copied()
+            .filter(|id| !inliers.contains(id))
+            .collect();
+        Some(BoardPose {
+            reprojection_error: (sum_sq / (4 * inliers.len()) as f64).sqrt(),
+            pose,
+            error,
+            alt_pose,
+            alt_error,
+            inliers,
+            outliers,
+        })
+    }
+
+    /// The members whose corners `pose` reprojects within the error limit,
+    /// ascending, and their total squared reprojection error in pixels².
+    fn inliers(
+        &self,
+        bundle: &TagBundle,
+        members: &BTreeMap<i32, &Detection>,
+        pose: &Pose,
+        params: &PoseParams,
+    ) -> (Vec<i32>, f64) {
+        let limit_sq = 4.0 * self.max_reprojection_error.powi(2);
+        let mut inliers = Vec::new();
+        let mut total = 0.0;
+        for (&id, det) in members {
+            let Some(corners) = bundle.corners(id) else {
+                continue;
+            };
+            let sum_sq = reprojection_sq(pose, corners, det, params);
+            // NaN (a corner behind the camera) fails the comparison
+            if sum_sq <= limit_sq {
+                inliers.push(id);
+                total += sum_sq;
+            }
+        }
+        (inliers, total)
+    }
+}
+
+/// Sum of squared distances in pixels² between `det`'s corners and
+/// `corners` projected by `pose`.
+fn reprojection_sq(
+    pose: &Pose,
+    corners: &[[f64; 3]; 4],
+    det: &Detection,
+    params: &PoseParams,
+) -> f64 {
+    pose.project_points(corners, params)
+        .iter()
+        .zip(&det.corners)
+        .map(|(p, c)| (p[0] - c[0]).powi(2) + (p[1] - c[1]).powi(2))
+        .sum()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::super::super::geometry::Vec2;
+    use super::super::super::homography::Homography;
+    use super::*;
+    use crate::family::FamilyId;
+
+    fn params() -> PoseParams {
+        PoseParams {
+            tagsize: 0.0,
+            fx: 800.0,
+            fy: 800.0,
+            cx: 640.0,
+            cy: 480.0,
+            solver: Default::default(),
+        }
+    }
+
+    fn truth() -> Pose {
+        let (a, b) = (0.3f64, -0.4f64);
+        let (ca, sa, cb, sb) = (a.cos(), a.sin(), b.cos(), b.sin());
+        Pose {
+            r: [
+                [cb, 0.0, sb],
+                [sa * sb, ca, -sa * cb],
+                [-ca * sb, sa, ca * cb],
+            ],
+            t: [-0.1, -0.06, 0.6],
+        }
+    }
+
+    /// Detections of every tag of `board` seen from `pose`.
+    fn detect(board: &TagGridBoard, pose: &Pose) -> Vec<Detection> {
+        (0..board.len())
+            .map(|i| {
+                let (column, row) = (i % board.columns, i / board.columns);
+                let id = board.id(column, row).unwrap();
+                let corners =
+                    TagBundle::planar_corners(board.tag_center(column, row), board.tag_size);
+                let image = pose.project_points(&corners, &params());
+                let corners = std::array::from_fn(|i| Vec2::from(image[i]));
+                Detection {
+                    family_id: FamilyId::from("tag36h11"),
+                    id,
+                    hamming: 0,
+                    decision_margin: 100.0,
+                    corners,
+                    center: corners[0],
+                    homography: Homography::from_quad_corners(&corners).unwrap(),
+                    mirrored: false,
+                    line_fit_mse: 0.0,
+                    refine_residual: None,
+                    corner_covariance: [[0.0; 3]; 4],
+                }
+            })
+            .collect()
+    }
+
+    fn assert_pose_near(pose: &Pose, truth: &Pose, tol: f64) {
+        let r = pose.r.iter().flatten().zip(truth.r.iter().flatten());
+        for (a, b) in r.chain(pose.t.iter().zip(&truth.t)) {
+            assert!((a - b).abs() < tol, "{pose:?}");
+        }
+    }
+
+    #[test]
+    fn board_ids_and_positions() {
+        let board = TagGridBoard::new("tag36h11", 4, 3, 0.05, 0.01).first_id(100);
+        assert_eq!(board.len(), 12);
+        assert_eq!(board.id(0, 0), Some(100));
+        assert_eq!(board.id(3, 2), Some(111));
+        assert_eq!(board.id(4, 0), None);
+        assert_eq!(board.position(105), Some((1, 1)));
+        assert_eq!(board.position(99), None);
+        assert_eq!(board.position(112), None);
+        let [x, y] = board.tag_center(1, 2);
+        assert!((x - 0.085).abs() < 1e-12 && (y - 0.145).abs() < 1e-12);
+        assert_eq!(board.bundle().len(), 12);
+    }
+
+    #[test]
+    fn board_pose_from_clean_detections() {
+        let board = TagGridBoard::new("tag36h11", 5, 4, 0.03, 0.01);
+        let detections = detect(&board, &truth());
+        let result = board.estimate_pose(&detections, &params()).unwrap();
+        assert_pose_near(&result.pose, &truth(), 1e-6);
+        assert_eq!(result.inliers.len(), 20);
+        assert!(result.outliers.is_empty());
+        assert!(result.reprojection_error < 1e-6);
+    }
+
+    #[test]
+    fn board_pose_rejects_misplaced_tags() {
+        let board = TagGridBoard::new("tag36h11", 5, 4, 0.03, 0.01);
+        let mut detections = detect(&board, &truth());
+        // Two tags with swapped IDs, and one shifted off its place
+        let (a, b) = (detections[2].id, detections[13].id);
+        detections[2].id = b;
+        detections[13].id = a;
+        detections[7].corners = detections[7].corners.map(|c| Vec2::new(c[0] + 15.0, c[1]));
+
+        let result = board.estimate_pose(&detections, &params()).unwrap();
+        assert_pose_near(&result.pose, &truth(), 1e-6);
+        assert_eq!(result.outliers, vec![2, 7, 13]);
+        assert_eq!(result.inliers.len(), 17);
+
+        // Detections of other boards or families are not outliers
+        let mut others = detect(&board, &truth());
+        others.truncate(3);
+        for det in &mut others {
+            det.family_id = FamilyId::from("tag25h9");
+        }
+        detections.extend(others);
+        let result = board.estimate_pose(&detections, &params()).unwrap();
+        assert_eq!(result.outliers, vec![2, 7, 13]);
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use smallvec::SmallVec;
+
+use super::super::detector::Detection;
+use super::super::geometry::{Mat3, Vec3};
+use super::svd::project_to_so3;
+use super::{estimate_tag_pose, orthogonal_iteration, rotation_angle, Pose, PoseParams};
+
+/// Rotation in radians below which the second pose of a bundle has
+/// converged onto the first and is dropped.
+const SAME_POSE_ANGLE: f64 = 1e-3;
+
+/// A rigid group of tags, such as a board or a cube, whose pose is estimated
+/// as one from all of its detected members.
+///
+/// Each member is given by its tag ID and the positions of its four corners
+/// in the bundle's own body frame, in the order of
+/// [`Detection::corners`]: for a tag facing the camera with X right and Y
+/// down, bottom-left, bottom-right, top-right, top-left. Fitting every
+/// visible corner at once is far more stable than any single tag's pose,
+/// and keeps working while some tags are occluded.
+///
+/// ```
+/// use apriltag::detect::pose::{PoseParams, TagBundle};
+///
+/// // Two 4 cm tags 10 cm apart on a flat board
+/// let board = TagBundle::new([
+///     (0, TagBundle::planar_corners([0.0, 0.0], 0.04)),
+///     (1, TagBundle::planar_corners([0.1, 0.0], 0.04)),
+/// ])
+/// .family("tag36h11");
+/// assert_eq!(board.len(), 2);
+/// # let detections: Vec<apriltag::Detection> = Vec::new();
+/// # let params = PoseParams { tagsize: 0.0, fx: 500.0, fy: 500.0, cx: 320.0, cy: 240.0, solver: Default::default() };
+///
+/// if let Some((pose, ..)) = board.estimate_pose(&detections, &params) {
+///     println!("board at {:?}", pose.t);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagBundle {
+    /// Only detections of this family are members; any family if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    family: Option<String>,
+    /// Corners of each member tag in the body frame, by tag ID.
+    tags: BTreeMap<i32, [[f64; 3]; 4]>,
+}
+
+impl TagBundle {
+    /// A bundle of the tags with the given IDs and body-frame corners.
+    pub fn new(tags: impl IntoIterator<Item = (i32, [[f64; 3]; 4])>) -> Self {
+        Self {
+            family: None,
+            tags: tags.into_iter().collect(),
+        }
+    }
+
+    /// Only count detections of the family named `name` as members, so a
+    /// tag with the same ID from another family is not mistaken for one.
+    pub fn family(mut self, name: &str) -> Self {
+        self.family = Some(name.to_string());
+        self
+    }
+
+    /// Number of member tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Whether the bundle has no members.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Corners of a tag of side `size`, centered at `center` on the plane
+    /// `z = 0` of a board, with the board's X and Y axes along the tag's: a
+    /// board laid out like the tags' own frame.
+    pub fn planar_corners(center: [f64; 2], size: f64) -> [[f64; 3]; 4] {
+        let s = size / 2.0;
+        let [x, y] = center;
+        [
+            [x - s, y + s, 0.0],
+            [x + s, y + s, 0.0],
+            [x + s, y - s, 0.0],
+            [x - s, y - s, 0.0],
+        ]
+    }
+
+    /// Estimate the pose of the bundle's body frame (camera ← body) from the
+    /// members among `detections`; `params.tagsize` is ignored, as each
+    /// member's corners give its size.
+    ///
+    /// Orthogonal iteration fits all members' corners together, starting
+    /// from both poses of the member that appears largest (found by
+    /// [`estimate_tag_pose`] with `params.solver`). Returns
+    /// `(best_pose, best_error, alt_pose, alt_error)` as
+    /// [`estimate_tag_pose`] does, with object-space errors in squared body
+    /// units summed over all corners; `alt_pose` is `None` when both starts
+    /// converge to the same pose. A tag detected more than once counts with
+    /// its highest decision margin.
+    ///
+    /// Returns `None` when no member was detected or the largest member's
+    /// corners are degenerate.
+    pub fn estimate_pose(
+        &self,
+        detections: &[Detection],
+        params: &PoseParams,
+    ) -> Option<(Pose, f64, Option<Pose>, f64)> {
+        let members = self.members(detections);
+
+        let mut rays: SmallVec<[Vec3; 16]> = SmallVec::new();
+        let mut pts: SmallVec<[Vec3; 16]> = SmallVec::new();
+        for (id, det) in &members {
+            for (c, p) in det.corners.iter().zip(&self.tags[id]) {
+                rays.push(Vec3::new(
+                    (c[0] - params.cx) / params.fx,
+                    (c[1] - params.cy) / params.fy,
+                    1.0,
+                ));
+                pts.push(Vec3(*p));
+            }
+        }
+
+        let seed = members
+            .values()
+            .max_by(|a, b| image_area(a).total_cmp(&image_area(b)))?;
+        let corners = &self.tags[&seed.id];
+        let side = (0..4)
+            .map(|i| (Vec3(corners[(i + 1) % 4]) - Vec3(corners[i])).norm())
+            .sum::<f64>()
+            / 4.0;
+        if !side.is_finite() || side <= 0.0 {
+            return None;
+        }
+        let seed_params = PoseParams {
+            tagsize: side,
+            ..params.clone()
+        };
+        let (pose1, err1, pose2, _) = estimate_tag_pose(seed, &seed_params);
+        if err1 == f64::MAX {
+            return None;
+        }
+
+        // Where the seed tag sits in the body frame: body = R·tag + t
+        let s = side / 2.0;
+        let tag_pts = [
+            Vec3::new(-s, s, 0.0),
+            Vec3::new(s, s, 0.0),
+            Vec3::new(s, -s, 0.0),
+            Vec3::new(-s, -s, 0.0),
+        ];
+        let centroid = corners
+            .iter()
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, p| sum + Vec3(*p))
+            / 4.0;
+        let mut m = Mat3([[0.0; 3]; 3]);
+        for (p, q) in corners.iter().zip(&tag_pts) {
+            m += (Vec3(*p) - centroid).outer(*q);
+        }
+        let r_tag = project_to_so3(&m);
+        // camera ← body = (camera ← tag) · (tag ← body)
+        let fit = |pose: &Pose| {
+            let r = Mat3(pose.r) * r_tag.transpose();
+            let t = Vec3(pose.t) - r * centroid;
+            orthogonal_iteration(&rays, &pts, &r, &t, 50)
+        };
+
+        let (best, best_err) = fit(&pose1);
+        let alt = pose2
+            .map(|pose| fit(&pose))
+            .filter(|(alt, _)| rotation_angle(&Mat3(alt.r), &Mat3(best.r)) > SAME_POSE_ANGLE);
+        Some(match alt {
+            Some((alt, alt_err)) if alt_err < best_err => (alt, alt_err, Some(best), best_err),
+            Some((alt, alt_err)) => (best, best_err, Some(alt), alt_err),
+            None => (best, best_err, None, f64::MAX),
+        })
+    }
+
+    /// The detection of each member among `detections`, by tag ID: of the
+    /// bundle's family, and with the highest decision margin where a tag was
+    /// detected more than once.
+    pub(super) fn members<'a>(&self, detections: &'a [Detection]) -> BTreeMap<i32, &'a Detection> {
+        let mut members: BTreeMap<i32, &Detection> = BTreeMap::new();
+        for det in detections {
+            if !self.tags.contains_key(&det.id)
+                || self.family.as_deref().is_some_and(|f| det.family_id != f)
+            {
+                continue;
+            }
+            members
+                .entry(det.id)
+                .and_modify(|m| {
+                    if det.decision_margin > m.decision_margin {
+                        *m = det;
+                    }
+                })
+                .or_insert(det);
+        }
+        members
+    }
+
+    /// Body-frame corners of the member with tag ID `id`.
+    pub(super) fn corners(&self, id: i32) -> Option<&[[f64; 3]; 4]> {
+        self.tags.get(&id)
+    }
+}
+
+/// Area in pixels² of a detection's quad.
+fn image_area(det: &Detection) -> f64 {
+    let c = &det.corners;
+    (0..4)
+        .map(|i| {
+            let (a, b) = (c[i], c[(i + 1) % 4]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum::<f64>()
+        .abs()
+        / 2.0
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::super::super::geometry::Vec2;
+    use super::super::super::homography::Homography;
+    use super::*;
+    use crate::family::FamilyId;
+
+    fn params() -> PoseParams {
+        PoseParams {
+            tagsize: 0.0,
+            fx: 700.0,
+            fy: 700.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        }
+    }
+
+    /// Detections of `bundle`'s members seen from `pose`, with `family`.
+    fn detect(bundle: &TagBundle, pose: &Pose, family: &str) -> Vec<Detection> {
+        bundle
+            .tags
+            .iter()
+            .map(|(&id, corners)| {
+                let image = pose.project_points(corners, &params());
+                let corners = std::array::from_fn(|i| Vec2::from(image[i]));
+                Detection {
+                    family_id: FamilyId::from(family),
+                    id,
+                    hamming: 0,
+                    decision_margin: 100.0,
+                    corners,
+                    center: corners[0],
+                    homography: Homography::from_quad_corners(&corners).unwrap(),
+                    mirrored: false,
+                    line_fit_mse: 0.0,
+                    refine_residual: None,
+                    corner_covariance: [[0.0; 3]; 4],
+                }
+            })
+            .collect()
+    }
+
+    fn rotation(ax: f64, ay: f64) -> [[f64; 3]; 3] {
+        let (cx, sx) = (ax.cos(), ax.sin());
+        let (cy, sy) = (ay.cos(), ay.sin());
+        [
+            [cy, 0.0, sy],
+            [sx * sy, cx, -sx * cy],
+            [-cx * sy, sx, cx * cy],
+        ]
+    }
+
+    fn assert_pose_near(pose: &Pose, truth: &Pose, tol: f64) {
+        for i in 0..3 {
+            assert!((pose.t[i] - truth.t[i]).abs() < tol, "{:?}", pose.t);
+            for j in 0..3 {
+                assert!((pose.r[i][j] - truth.r[i][j]).abs() < tol, "{:?}", pose.r);
+            }
+        }
+    }
+
+    fn board() -> TagBundle {
+        TagBundle::new((0..6).map(|id| {
+            let center = [(id % 3) as f64 * 0.06, (id / 3) as f64 * 0.06];
+            (id, TagBundle::planar_corners(center, 0.04))
+        }))
+    }
+
+    #[test]
+    fn bundle_recovers_board_pose() {
+        let board = board();
+        let truth = Pose {
+            r: rotation(0.4, -0.3),
+            t: [-0.05, 0.02, 0.5],
+        };
+        let detections = detect(&board, &truth, "tag36h11");
+        let (pose, err, ..) = board.estimate_pose(&detections, &params()).unwrap();
+        assert!(err < 1e-12, "{err}");
+        assert_pose_near(&pose, &truth, 1e-6);
+
+        // An occluded board still gives the pose
+        let (pose, ..) = board.estimate_pose(&detections[4..], &params()).unwrap();
+        assert_pose_near(&pose, &truth, 1e-6);
+    }
+
+    #[test]
+    fn bundle_recovers_cube_pose() {
+        // Two faces of a 6 cm cube centered on the body origin
+        let h = 0.03;
+        let s = 0.02;
+        let cube = TagBundle::new([
+            (3, [[-s, s, -h], [s, s, -h], [s, -s, -h], [-s, -s, -h]]),
+            (7, [[h, s, -s], [h, s, s], [h, -s, s], [h, -s, -s]]),
+        ]);
+        let truth = Pose {
+            r: rotation(0.2, -0.7),
+            t: [0.01, -0.02, 0.4],
+        };
+        let detections = detect(&cube, &truth, "tag36h11");
+        let (pose, err, ..) = cube.estimate_pose(&detections, &params()).unwrap();
+        assert!(err < 1e-12, "{err}");
+        assert_pose_near(&pose, &truth, 1e-6);
+    }
+
+    #[test]
+    fn bundle_ignores_non_members() {
+        let board = board().family("tag36h11");
+        let truth = Pose {
+            r: rotation(0.1, 0.2),
+            t: [0.0, 0.0, 0.6],
+        };
+        assert!(board.estimate_pose(&[], &params()).is_none());
+        let others = detect(&board, &truth, "tag25h9");
+        assert!(board.estimate_pose(&others, &params()).is_none());
+
+        // A stray detection of a member ID from another family is skipped
+        let mut detections = detect(&board, &truth, "tag36h11");
+        let mut stray = detections[0].clone();
+        stray.family_id = FamilyId::from("tag25h9");
+        stray.corners = stray.corners.map(|c| Vec2::new(c[0] + 40.0, c[1]));
+        detections.push(stray);
+        let (pose, ..) = board.estimate_pose(&detections, &params()).unwrap();
+        assert_pose_near(&pose, &truth, 1e-6);
+    }
+}
@@ -1,13 +1,25 @@
+mod average;
+mod board;
+mod bundle;
 mod svd;
 
+pub use average::{average_poses, PoseAverage};
+pub use board::{BoardPose, TagGridBoard};
+pub use bundle::TagBundle;
+use smallvec::SmallVec;
 use svd::project_to_so3;
 
 use super::detector::Detection;
-use super::geometry::{Mat3, Vec3};
+use super::geometry::{forward_eliminate, Mat3, Vec3};
 use super::homography::Homography;
+use crate::family::TagFamily;
+
+/// Levenberg–Marquardt iterations of [`refine_tag_pose`].
+const REFINE_ITERATIONS: usize = 30;
 
 /// A 3D pose estimate (rotation + translation).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pose {
     /// 3x3 rotation matrix (row-major): camera <- tag
     pub r: [[f64; 3]; 3],
@@ -15,6 +27,67 @@ pub struct Pose {
     pub t: [f64; 3],
 }
 
+impl Pose {
+    /// Project points given in the tag frame (same units as
+    /// [`PoseParams::tagsize`]) to pixel coordinates.
+    ///
+    /// Points at or behind the camera plane project to `[NaN, NaN]`.
+    pub fn project_points(&self, points: &[[f64; 3]], params: &PoseParams) -> Vec<[f64; 2]> {
+        let (r, t) = (&self.r, &self.t);
+        points
+            .iter()
+            .map(|p| {
+                let cam: [f64; 3] = std::array::from_fn(|i| {
+                    r[i][0] * p[0] + r[i][1] * p[1] + r[i][2] * p[2] + t[i]
+                });
+                if cam[2] <= 0.0 {
+                    return [f64::NAN; 2];
+                }
+                [
+                    params.fx * cam[0] / cam[2] + params.cx,
+                    params.fy * cam[1] / cam[2] + params.cy,
+                ]
+            })
+            .collect()
+    }
+
+    /// Pixel coordinates of the tag origin and the tips of its X, Y and Z
+    /// axes, each `len` long, in that order.
+    ///
+    /// The tag frame has X to the right and Y down the tag face, with Z
+    /// pointing into the tag (away from a camera that faces it).
+    ///
+    /// ```
+    /// use apriltag::detect::pose::{Pose, PoseParams};
+    ///
+    /// let pose = Pose { r: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], t: [0.0, 0.0, 1.0] };
+    /// let params = PoseParams {
+    ///     tagsize: 0.1,
+    ///     fx: 500.0,
+    ///     fy: 500.0,
+    ///     cx: 320.0,
+    ///     cy: 240.0,
+    ///     solver: Default::default(),
+    /// };
+    /// let [origin, x, y, _z] = pose.axes_endpoints(0.1, &params);
+    /// assert_eq!(origin, [320.0, 240.0]);
+    /// assert_eq!(x, [370.0, 240.0]);
+    /// assert_eq!(y, [320.0, 290.0]);
+    /// ```
+    pub fn axes_endpoints(&self, len: f64, params: &PoseParams) -> [[f64; 2]; 4] {
+        let pts = self.project_points(
+            &[
+                [0.0, 0.0, 0.0],
+                [len, 0.0, 0.0],
+                [0.0, len, 0.0],
+                [0.0, 0.0, len],
+            ],
+            params,
+        );
+        [pts[0], pts[1], pts[2], pts[3]]
+    }
+}
+
 /// Camera intrinsics and tag geometry for pose estimation.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -24,6 +97,47 @@ pub struct PoseParams {
     pub fy: f64,
     pub cx: f64,
     pub cy: f64,
+    /// How [`estimate_tag_pose`] and [`solve_planar_pnp`] solve for the pose.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver: PoseSolver,
+}
+
+/// Pose solver used by [`estimate_tag_pose`] and [`solve_planar_pnp`].
+///
+/// Both return the two poses a planar target is ambiguous between, best
+/// first.
+///
+/// ```
+/// use apriltag::detect::pose::{solve_planar_pnp, Pose, PoseParams, PoseSolver};
+///
+/// let params = PoseParams {
+///     tagsize: 0.0,
+///     fx: 500.0,
+///     fy: 500.0,
+///     cx: 320.0,
+///     cy: 240.0,
+///     solver: PoseSolver::Ippe,
+/// };
+/// let truth = Pose { r: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], t: [0.1, 0.0, 2.0] };
+/// let object = [[0.0, 0.0, 0.0], [0.3, 0.0, 0.0], [0.3, 0.2, 0.0], [0.0, 0.2, 0.0]];
+/// let image = truth.project_points(&object, &params);
+///
+/// let (pose, _, _, _) = solve_planar_pnp(&object, &image, &params).unwrap();
+/// assert!((pose.t[2] - 2.0).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PoseSolver {
+    /// Orthogonal iteration (Lu et al. 2000) from the homography's pose,
+    /// restarted from the reflected pose for the second solution, as the C
+    /// library does.
+    #[default]
+    OrthogonalIteration,
+    /// Infinitesimal Plane-based Pose Estimation (Collins & Bartoli 2014):
+    /// closed form and faster, with both solutions found analytically, as
+    /// OpenCV's `SOLVEPNP_IPPE`.
+    Ippe,
 }
 
 // ── Pose estimation ──
@@ -80,12 +194,17 @@ fn homography_to_pose(h: &Homography, params: &PoseParams) -> Pose {
     Pose { r: r.0, t: t.0 }
 }
 
-/// Estimate the pose of a detected tag.
+/// Estimate the pose of a detected tag with the [solver](PoseParams::solver)
+/// of `params`.
 ///
-/// Returns `(best_pose, best_error, alt_pose, alt_error)`.
-/// `alt_pose` is `None` when no second local minimum exists.
+/// Returns `(best_pose, best_error, alt_pose, alt_error)`, where the errors
+/// are object-space errors in squared tag units. `alt_pose` is `None` when
+/// no second local minimum exists.
 #[allow(clippy::needless_range_loop)]
 pub fn estimate_tag_pose(det: &Detection, params: &PoseParams) -> (Pose, f64, Option<Pose>, f64) {
+    if params.solver == PoseSolver::Ippe {
+        return estimate_tag_pose_ippe(det, params);
+    }
     // Build homography from detection corners
     let h = match Homography::from_quad_corners(&det.corners) {
         Some(h) => h,
@@ -123,14 +242,338 @@ pub fn estimate_tag_pose(det: &Detection, params: &PoseParams) -> (Pose, f64, Op
 
     // Initial pose from homography decomposition
     let initial = homography_to_pose(&h, params);
+    refine_pose(&v, &tag_pts, &initial)
+}
+
+/// Estimate the pose of a detected tag by IPPE, whatever the
+/// [solver](PoseParams::solver) of `params`.
+///
+/// Returns the same tuple as [`estimate_tag_pose`], with errors measured
+/// the same way, so the two solvers can be compared. IPPE always finds the
+/// second solution; it is `None` only when the corners are degenerate, and
+/// then the best pose has an error of `f64::MAX`.
+pub fn estimate_tag_pose_ippe(
+    det: &Detection,
+    params: &PoseParams,
+) -> (Pose, f64, Option<Pose>, f64) {
+    let s = params.tagsize / 2.0;
+    let tag_pts = [
+        Vec3::new(-s, s, 0.0),
+        Vec3::new(s, s, 0.0),
+        Vec3::new(s, -s, 0.0),
+        Vec3::new(-s, -s, 0.0),
+    ];
+    let rays = det.corners.map(|c| {
+        Vec3::new(
+            (c[0] - params.cx) / params.fx,
+            (c[1] - params.cy) / params.fy,
+            1.0,
+        )
+    });
+    ippe(&rays, &tag_pts).unwrap_or((
+        Pose {
+            r: Mat3::IDENTITY.0,
+            t: [0.0, 0.0, 1.0],
+        },
+        f64::MAX,
+        None,
+        f64::MAX,
+    ))
+}
+
+/// Estimate the pose of a planar target from any number of 3D-2D point
+/// correspondences, as OpenCV's `solvePnP` does.
+///
+/// `object_pts` must lie in the target's `z = 0` plane, in any units, and
+/// `image_pts` are their pixel coordinates. Use this to solve for a board of
+/// several tags from all of their corners, or for hand-labeled points; the
+/// `tagsize` of `params` is unused. The returned pose maps the target frame
+/// to the camera frame, in the units of `object_pts`.
+///
+/// Returns `(best_pose, best_error, alt_pose, alt_error)` as
+/// [`estimate_tag_pose`] does, or `None` if the slices differ in length,
+/// hold fewer than four points, have points off the plane, or are
+/// degenerate (e.g. collinear).
+///
+/// ```
+/// use apriltag::detect::pose::{solve_planar_pnp, Pose, PoseParams};
+///
+/// let params = PoseParams {
+///     tagsize: 0.0,
+///     fx: 500.0,
+///     fy: 500.0,
+///     cx: 320.0,
+///     cy: 240.0,
+///     solver: Default::default(),
+/// };
+/// let truth = Pose { r: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], t: [0.1, 0.0, 2.0] };
+/// let object = [[0.0, 0.0, 0.0], [0.3, 0.0, 0.0], [0.3, 0.2, 0.0], [0.0, 0.2, 0.0], [0.15, 0.1, 0.0]];
+/// let image = truth.project_points(&object, &params);
+///
+/// let (pose, err, _, _) = solve_planar_pnp(&object, &image, &params).unwrap();
+/// assert!(err < 1e-9);
+/// assert!((pose.t[2] - 2.0).abs() < 1e-6);
+/// ```
+pub fn solve_planar_pnp(
+    object_pts: &[[f64; 3]],
+    image_pts: &[[f64; 2]],
+    params: &PoseParams,
+) -> Option<(Pose, f64, Option<Pose>, f64)> {
+    if object_pts.len() != image_pts.len() || object_pts.len() < 4 {
+        return None;
+    }
+    let extent = object_pts
+        .iter()
+        .flat_map(|p| [p[0].abs(), p[1].abs()])
+        .fold(0.0, f64::max);
+    if object_pts.iter().any(|p| p[2].abs() > 1e-9 * extent) {
+        return None;
+    }
+
+    let pts: SmallVec<[Vec3; 4]> = object_pts
+        .iter()
+        .map(|p| Vec3::new(p[0], p[1], 0.0))
+        .collect();
+    let rays: SmallVec<[Vec3; 4]> = image_pts
+        .iter()
+        .map(|p| {
+            Vec3::new(
+                (p[0] - params.cx) / params.fx,
+                (p[1] - params.cy) / params.fy,
+                1.0,
+            )
+        })
+        .collect();
+
+    if params.solver == PoseSolver::Ippe {
+        return ippe(&rays, &pts);
+    }
+    let h = planar_homography(&pts, &rays)?;
+    let initial = planar_homography_to_pose(&h)?;
+    Some(refine_pose(&rays, &pts, &initial))
+}
+
+/// IPPE (Collins & Bartoli 2014): both poses of the plane from the
+/// homography's first-order behavior at the centroid of `pts`, best first.
+///
+/// With `R₁₂` the first two columns of the rotation, the Jacobian of the
+/// homography at the centroid is `J = B·R̃/λ`, where `Rv` turns the optical
+/// axis onto the centroid's ray, `B` is the projection Jacobian in that
+/// rotated frame and `R̃` the top 2×2 block of `Rvᵀ·R₁₂`. The block of a
+/// rotation has a largest singular value of one, which fixes `λ`; its third
+/// row follows up to sign, giving the two solutions.
+#[allow(clippy::needless_range_loop)]
+fn ippe(rays: &[Vec3], pts: &[Vec3]) -> Option<(Pose, f64, Option<Pose>, f64)> {
+    let n = pts.len() as f64;
+    let centroid = pts.iter().fold(Vec3::new(0.0, 0.0, 0.0), |a, &p| a + p) / n;
+    let centered: SmallVec<[Vec3; 4]> = pts.iter().map(|&p| p - centroid).collect();
+    let h = planar_homography(&centered, rays)?.0;
+    if h[2][2].abs() < 1e-12 {
+        return None;
+    }
+
+    // Image of the centroid, and the Jacobian of the homography there
+    let (u, v) = (h[0][2] / h[2][2], h[1][2] / h[2][2]);
+    let j = [
+        [
+            (h[0][0] - u * h[2][0]) / h[2][2],
+            (h[0][1] - u * h[2][1]) / h[2][2],
+        ],
+        [
+            (h[1][0] - v * h[2][0]) / h[2][2],
+            (h[1][1] - v * h[2][1]) / h[2][2],
+        ],
+    ];
+
+    // Rv rotates the optical axis onto the centroid's ray
+    let ray = Vec3::new(u, v, 1.0).normalized();
+    let angle = ray[2].clamp(-1.0, 1.0).acos();
+    let axis = Vec3::new(-ray[1], ray[0], 0.0);
+    let rv = if axis.norm() < 1e-12 {
+        Mat3::IDENTITY
+    } else {
+        rotation_exp(axis.normalized() * angle)
+    };
+    // B = [I₂ | -(u, v)]·Rv, whose third column vanishes
+    let b: [[f64; 2]; 2] =
+        std::array::from_fn(|r| std::array::from_fn(|c| rv.0[r][c] - [u, v][r] * rv.0[2][c]));
+    let det_b = b[0][0] * b[1][1] - b[0][1] * b[1][0];
+    if det_b.abs() < 1e-12 {
+        return None;
+    }
+    let b_inv = [
+        [b[1][1] / det_b, -b[0][1] / det_b],
+        [-b[1][0] / det_b, b[0][0] / det_b],
+    ];
+    let m: [[f64; 2]; 2] = std::array::from_fn(|r| {
+        std::array::from_fn(|c| b_inv[r][0] * j[0][c] + b_inv[r][1] * j[1][c])
+    });
+
+    // Scale M to the block of a rotation: largest singular value one
+    let mtm00 = m[0][0] * m[0][0] + m[1][0] * m[1][0];
+    let mtm01 = m[0][0] * m[0][1] + m[1][0] * m[1][1];
+    let mtm11 = m[0][1] * m[0][1] + m[1][1] * m[1][1];
+    let (tr, det) = (mtm00 + mtm11, mtm00 * mtm11 - mtm01 * mtm01);
+    let sigma = ((tr + (tr * tr - 4.0 * det).max(0.0).sqrt()) / 2.0).sqrt();
+    if sigma < 1e-12 {
+        return None;
+    }
+    let g = m.map(|row| row.map(|x| x / sigma));
+    // The third row (b0, b1) of the rotation's first two columns satisfies
+    // (b0, b1)ᵀ(b0, b1) = I − GᵀG
+    let c00 = 1.0 - (g[0][0] * g[0][0] + g[1][0] * g[1][0]);
+    let c01 = -(g[0][0] * g[0][1] + g[1][0] * g[1][1]);
+    let c11 = 1.0 - (g[0][1] * g[0][1] + g[1][1] * g[1][1]);
+    let b0 = c00.max(0.0).sqrt();
+    let b1 = c11
+        .max(0.0)
+        .sqrt()
+        .copysign(if c01 < 0.0 { -1.0 } else { 1.0 });
+
+    let f_ops: SmallVec<[Mat3; 4]> = rays.iter().map(|&v| v.outer(v) / v.dot(v)).collect();
+    let mut solutions = [1.0, -1.0].map(|sign| {
+        let r1 = Vec3::new(g[0][0], g[1][0], sign * b0);
+        let r2 = Vec3::new(g[0][1], g[1][1], sign * b1);
+        let r3 = r1.cross(r2);
+        let r_tilde = Mat3([
+            [r1[0], r2[0], r3[0]],
+            [r1[1], r2[1], r3[1]],
+            [r1[2], r2[2], r3[2]],
+        ]);
+        let r = project_to_so3(&(rv * r_tilde));
+        let t = plane_translation(rays, &centered, &r)?;
+        let err = compute_error(&f_ops, &r, &t, &centered);
+        // Back to the caller's origin: p = centered + centroid
+        let t = t - r * centroid;
+        Some((Pose { r: r.0, t: t.0 }, err))
+    });
+    if solutions[1].as_ref().map_or(f64::MAX, |s| s.1)
+        < solutions[0].as_ref().map_or(f64::MAX, |s| s.1)
+    {
+        solutions.swap(0, 1);
+    }
+    let [best, alt] = solutions;
+    let (pose, err) = best?;
+    match alt {
+        Some((alt, alt_err)) => Some((pose, err, Some(alt), alt_err)),
+        None => Some((pose, err, None, f64::MAX)),
+    }
+}
+
+/// Least-squares translation placing `pts` rotated by `r` on their `rays`,
+/// from the linear constraints `x·(r₃·p + tz) = r₁·p + tx` (and likewise
+/// for `y`).
+#[allow(clippy::needless_range_loop)]
+fn plane_translation(rays: &[Vec3], pts: &[Vec3], r: &Mat3) -> Option<Vec3> {
+    let mut ata = Mat3([[0.0; 3]; 3]);
+    let mut atb = Vec3::new(0.0, 0.0, 0.0);
+    for (ray, &p) in rays.iter().zip(pts) {
+        let rp = *r * p;
+        let (x, y) = (ray[0] / ray[2], ray[1] / ray[2]);
+        for (row, rhs) in [
+            (Vec3::new(1.0, 0.0, -x), x * rp[2] - rp[0]),
+            (Vec3::new(0.0, 1.0, -y), y * rp[2] - rp[1]),
+        ] {
+            ata += row.outer(row);
+            atb = atb + row * rhs;
+        }
+    }
+    Some(ata.inv()? * atb)
+}
 
+/// Least-squares homography from the `z = 0` plane to normalized image
+/// coordinates, with both sides normalized (Hartley) before solving.
+#[allow(clippy::needless_range_loop)]
+fn planar_homography(pts: &[Vec3], rays: &[Vec3]) -> Option<Mat3> {
+    /// Similarity that moves the centroid to the origin and scales the mean
+    /// distance from it to √2.
+    fn normalizer(pts: impl Iterator<Item = (f64, f64)> + Clone) -> Option<Mat3> {
+        let n = pts.clone().count() as f64;
+        let (mx, my) = pts
+            .clone()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (mx, my) = (mx / n, my / n);
+        let spread = pts.map(|(x, y)| (x - mx).hypot(y - my)).sum::<f64>() / n;
+        if spread < 1e-12 {
+            return None;
+        }
+        let k = std::f64::consts::SQRT_2 / spread;
+        Some(Mat3([
+            [k, 0.0, -k * mx],
+            [0.0, k, -k * my],
+            [0.0, 0.0, 1.0],
+        ]))
+    }
+    let to = normalizer(pts.iter().map(|p| (p[0], p[1])))?;
+    let ti = normalizer(rays.iter().map(|r| (r[0], r[1])))?;
+
+    // Normal equations of the DLT system with h[8] = 1, as an 8x9
+    // augmented matrix
+    let mut a = [[0.0f64; 9]; 8];
+    for (p, r) in pts.iter().zip(rays) {
+        let o = to * Vec3::new(p[0], p[1], 1.0);
+        let i = ti * *r;
+        let (x, y, u, v) = (o[0], o[1], i[0], i[1]);
+        for (row, rhs) in [
+            ([x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u], u),
+            ([0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v], v),
+        ] {
+            for j in 0..8 {
+                for k in 0..8 {
+                    a[j][k] += row[j] * row[k];
+                }
+                a[j][8] += row[j] * rhs;
+            }
+        }
+    }
+    forward_eliminate::<8, 9>(&mut a, 1e-10)?;
+
+    let mut h = [0.0f64; 9];
+    h[8] = 1.0;
+    for row in (0..8).rev() {
+        let mut sum = a[row][8];
+        for c in (row + 1)..8 {
+            sum -= a[row][c] * h[c];
+        }
+        h[row] = sum / a[row][row];
+    }
+    let hn = Mat3([[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], h[8]]]);
+    Some(ti.inv()? * hn * to)
+}
+
+/// Initial R, t from a homography mapping the `z = 0` plane to normalized
+/// image coordinates, with the target in front of the camera.
+fn planar_homography_to_pose(h: &Mat3) -> Option<Pose> {
+    let col = |j: usize| Vec3::new(h.0[0][j], h.0[1][j], h.0[2][j]);
+    let scale = (col(0).norm() + col(1).norm()) / 2.0;
+    if scale < 1e-12 {
+        return None;
+    }
+    // H is only known up to sign; pick the one with positive depth
+    let scale = if h.0[2][2] < 0.0 { -scale } else { scale };
+    let (r0, r1, t) = (col(0) / scale, col(1) / scale, col(2) / scale);
+    let r2 = r0.cross(r1);
+    let r_raw = Mat3([
+        [r0[0], r1[0], r2[0]],
+        [r0[1], r1[1], r2[1]],
+        [r0[2], r1[2], r2[2]],
+    ]);
+    Some(Pose {
+        r: project_to_so3(&r_raw).0,
+        t: t.0,
+    })
+}
+
+/// Refine `initial` by orthogonal iteration, then look for a second local
+/// minimum, returning the better pose first.
+fn refine_pose(rays: &[Vec3], pts: &[Vec3], initial: &Pose) -> (Pose, f64, Option<Pose>, f64) {
     // Run orthogonal iteration from initial estimate
     let r_init = Mat3(initial.r);
     let t_init = Vec3(initial.t);
-    let (pose1, err1) = orthogonal_iteration(&v, &tag_pts, &r_init, &t_init, 50);
+    let (pose1, err1) = orthogonal_iteration(rays, pts, &r_init, &t_init, 50);
 
     // Try to find a second local minimum
-    let (pose2, err2) = find_second_minimum(&v, &tag_pts, &pose1);
+    let (pose2, err2) = find_second_minimum(rays, pts, &pose1);
 
     match pose2 {
         Some(p2) if err2 < err1 => (p2, err2, Some(pose1), err1),
@@ -140,23 +583,20 @@ pub fn estimate_tag_pose(det: &Detection, params: &PoseParams) -> (Pose, f64, Op
     }
 }
 
-/// Orthogonal iteration (Lu et al. 2000).
+/// Orthogonal iteration (Lu et al. 2000) over paired image rays and object
+/// points.
 #[allow(clippy::needless_range_loop)]
 fn orthogonal_iteration(
-    image_rays: &[Vec3; 4],
-    tag_pts: &[Vec3; 4],
+    image_rays: &[Vec3],
+    tag_pts: &[Vec3],
     r_init: &Mat3,
     t_init: &Vec3,
     n_iters: u32,
 ) -> (Pose, f64) {
-    let n = 4;
+    let n = tag_pts.len();
 
     // Precompute projection operators F[i] = v*v' / (v'*v)
-    let mut f_ops = [Mat3([[0.0f64; 3]; 3]); 4];
-    for i in 0..n {
-        let vv = image_rays[i].dot(image_rays[i]);
-        f_ops[i] = image_rays[i].outer(image_rays[i]) / vv;
-    }
+    let f_ops: SmallVec<[Mat3; 4]> = image_rays.iter().map(|&v| v.outer(v) / v.dot(v)).collect();
 
     // Mean of object points
     let mut p_mean = Vec3::new(0.0, 0.0, 0.0);
@@ -166,10 +606,7 @@ fn orthogonal_iteration(
     p_mean = p_mean / n as f64;
 
     // Residuals
-    let mut p_res = [Vec3::new(0.0, 0.0, 0.0); 4];
-    for i in 0..n {
-        p_res[i] = tag_pts[i] - p_mean;
-    }
+    let p_res: SmallVec<[Vec3; 4]> = tag_pts.iter().map(|&p| p - p_mean).collect();
 
     // M1_inv = (I - mean(F))^{-1}
     let mut f_mean = Mat3([[0.0f64; 3]; 3]);
@@ -195,7 +632,7 @@ fn orthogonal_iteration(
 
         // Update rotation via SVD projection
         // q[i] = F[i] * (R * p[i] + t)
-        let mut q = [Vec3::new(0.0, 0.0, 0.0); 4];
+        let mut q: SmallVec<[Vec3; 4]> = SmallVec::from_elem(Vec3::new(0.0, 0.0, 0.0), n);
         let mut q_mean = Vec3::new(0.0, 0.0, 0.0);
         for i in 0..n {
             let rp = r * tag_pts[i];
@@ -222,9 +659,9 @@ fn orthogonal_iteration(
 }
 
 /// Compute object-space reprojection error.
-fn compute_error(f_ops: &[Mat3; 4], r: &Mat3, t: &Vec3, tag_pts: &[Vec3; 4]) -> f64 {
+fn compute_error(f_ops: &[Mat3], r: &Mat3, t: &Vec3, tag_pts: &[Vec3]) -> f64 {
     let mut err = 0.0;
-    for i in 0..4 {
+    for i in 0..tag_pts.len() {
         let rp = *r * tag_pts[i];
         let rp_t = rp + *t;
         let f_rp_t = f_ops[i] * rp_t;
@@ -236,11 +673,7 @@ fn compute_error(f_ops: &[Mat3; 4], r: &Mat3, t: &Vec3, tag_pts: &[Vec3; 4]) ->
 }
 
 /// Search for a second local minimum (Schweighofer & Pinz 2006).
-fn find_second_minimum(
-    image_rays: &[Vec3; 4],
-    tag_pts: &[Vec3; 4],
-    pose1: &Pose,
-) -> (Option<Pose>, f64) {
+fn find_second_minimum(image_rays: &[Vec3], tag_pts: &[Vec3], pose1: &Pose) -> (Option<Pose>, f64) {
     let t_dir = Vec3(pose1.t);
     let t_norm = t_dir.norm();
     if t_norm < 1e-10 {
@@ -265,6 +698,388 @@ fn find_second_minimum(
     (Some(pose2), err2)
 }
 
+/// Orientation of a tag known from outside the image, for choosing between
+/// the two poses of a planar target.
+///
+/// Near-frontal tags have two poses with similar errors, tilted either way,
+/// and image noise decides between them; the pose then flips from frame to
+/// frame. A prior from an IMU or from the previous frame settles it: see
+/// [`PosePrior::resolve`].
+#[derive(Debug, Clone)]
+pub enum PosePrior {
+    /// The tag's rotation (camera ← tag), e.g. its pose in the last frame.
+    Rotation([[f64; 3]; 3]),
+    /// A direction known in both frames, e.g. gravity: `camera` as measured
+    /// in the camera frame by an IMU, `tag` as fixed by how the tag is
+    /// mounted. Rotation about the direction is left free.
+    Direction { camera: [f64; 3], tag: [f64; 3] },
+}
+
+impl PosePrior {
+    /// Angle in radians by which `pose` disagrees with this prior.
+    pub fn angle(&self, pose: &Pose) -> f64 {
+        let r = Mat3(pose.r);
+        match self {
+            Self::Rotation(prior) => rotation_angle(&Mat3(*prior), &r),
+            Self::Direction { camera, tag } => (r * Vec3(*tag))
+                .normalized()
+                .dot(Vec3(*camera).normalized())
+                .clamp(-1.0, 1.0)
+                .acos(),
+        }
+    }
+
+    /// Reorder the result of [`estimate_tag_pose`] or [`solve_planar_pnp`]
+    /// so the pose closer to this prior comes first, unless its error is
+    /// more than `max_error_ratio` times the other's: the prior settles
+    /// ambiguous views, but a clear image wins over a stale or wrong prior.
+    ///
+    /// Noise can favor the wrong pose of a near-frontal tag by several times
+    /// the error, so a ratio of about 10 keeps it from flipping; 1 never
+    /// overrides the image, and `f64::INFINITY` always follows the prior.
+    ///
+    /// ```
+    /// use apriltag::detect::pose::{Pose, PosePrior};
+    ///
+    /// let tilted = |a: f64| Pose {
+    ///     r: [[a.cos(), 0.0, a.sin()], [0.0, 1.0, 0.0], [-a.sin(), 0.0, a.cos()]],
+    ///     t: [0.0, 0.0, 1.0],
+    /// };
+    /// // The image slightly prefers the wrong tilt
+    /// let result = (tilted(-0.2), 1.0e-6, Some(tilted(0.2)), 1.2e-6);
+    /// let prior = PosePrior::Rotation(tilted(0.25).r);
+    /// let (pose, err, alt, _) = prior.resolve(result, 10.0);
+    /// assert_eq!(pose.r, tilted(0.2).r);
+    /// assert_eq!(err, 1.2e-6);
+    /// assert_eq!(alt.unwrap().r, tilted(-0.2).r);
+    /// ```
+    pub fn resolve(
+        &self,
+        (best, best_err, alt, alt_err): (Pose, f64, Option<Pose>, f64),
+        max_error_ratio: f64,
+    ) -> (Pose, f64, Option<Pose>, f64) {
+        match alt {
+            Some(alt)
+                if self.angle(&alt) < self.angle(&best)
+                    && alt_err <= best_err * max_error_ratio =>
+            {
+                (alt, alt_err, Some(best), best_err)
+            }
+            alt => (best, best_err, alt, alt_err),
+        }
+    }
+}
+
+/// How far to trust the pose chosen by [`estimate_tag_pose`], for filters
+/// that fuse tag poses with other measurements: see [`pose_uncertainty`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoseUncertainty {
+    /// `alt_error / best_error`. Near 1 the image barely prefers the best
+    /// pose and noise can flip the choice; below 1 when a [`PosePrior`]
+    /// chose the pose the image fits worse; `f64::INFINITY` when there is no
+    /// second pose, or it puts the tag behind the camera.
+    pub ambiguity: f64,
+    /// Angle in radians between the rotations of the two poses, or `None`
+    /// without a second pose. A flip to the other pose moves the estimate
+    /// this far.
+    pub rotation_difference: Option<f64>,
+    /// Covariance of the best pose as `(ω, t)`, propagated from the
+    /// [corner covariances](Detection::corner_covariance) to first order:
+    /// `ω` is a small rotation in radians about the camera axes applied
+    /// before the pose's rotation, `t` the translation in the units of
+    /// [`PoseParams::tagsize`].
+    ///
+    /// The linearization sees only the best pose's own minimum: for a
+    /// near-frontal tag it shows the loosely constrained tilt, but not the
+    /// jump to the second pose that [`ambiguity`](Self::ambiguity) warns of.
+    pub covariance: [[f64; 6]; 6],
+}
+
+/// Ambiguity and covariance of `result`, the output of
+/// [`estimate_tag_pose`] (or [`PosePrior::resolve`]) for `det`.
+///
+/// Returns `None` if the best pose puts a corner behind the camera, or the
+/// corner covariances are not positive definite, as in a [`Detection`]
+/// built by hand.
+pub fn pose_uncertainty(
+    det: &Detection,
+    params: &PoseParams,
+    (best, best_err, alt, alt_err): &(Pose, f64, Option<Pose>, f64),
+) -> Option<PoseUncertainty> {
+    let (ambiguity, rotation_difference) = match alt {
+        Some(alt) if alt.t[2] > 0.0 => {
+            let ratio = if *best_err > 0.0 {
+                alt_err / best_err
+            } else if *alt_err > 0.0 {
+                f64::INFINITY
+            } else {
+                1.0
+            };
+            (ratio, Some(rotation_angle(&Mat3(best.r), &Mat3(alt.r))))
+        }
+        _ => (f64::INFINITY, None),
+    };
+
+    let s = params.tagsize / 2.0;
+    let tag_pts = [
+        Vec3::new(-s, s, 0.0),
+        Vec3::new(s, s, 0.0),
+        Vec3::new(s, -s, 0.0),
+        Vec3::new(-s, -s, 0.0),
+    ];
+    let (r, t) = (Mat3(best.r), Vec3(best.t));
+    // Information matrix JᵀΣ⁻¹J, summed over the corners
+    let mut info = [[0.0f64; 6]; 6];
+    for (p, &[xx, xy, yy]) in tag_pts.iter().zip(&det.corner_covariance) {
+        let det_cov = xx * yy - xy * xy;
+        if !(xx > 0.0 && det_cov > 0.0) {
+            return None;
+        }
+        let w = [[yy / det_cov, -xy / det_cov], [-xy / det_cov, xx / det_cov]];
+        let (_, j) = project_with_jacobian(p, &r, &t, params)?;
+        for a in 0..6 {
+            // Row a of JᵀΣ⁻¹
+            let jw = [
+                j[0][a] * w[0][0] + j[1][a] * w[1][0],
+                j[0][a] * w[0][1] + j[1][a] * w[1][1],
+            ];
+            for b in 0..6 {
+                info[a][b] += jw[0] * j[0][b] + jw[1] * j[1][b];
+            }
+        }
+    }
+
+    Some(PoseUncertainty {
+        ambiguity,
+        rotation_difference,
+        covariance: invert_symmetric6(&info)?,
+    })
+}
+
+/// Angle in radians of the rotation between `a` and `b`.
+fn rotation_angle(a: &Mat3, b: &Mat3) -> f64 {
+    let d = a.transpose() * *b;
+    ((d.0[0][0] + d.0[1][1] + d.0[2][2] - 1.0) / 2.0)
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// Pixel projection of tag point `p` under `(r, t)`, with its derivatives
+/// by a rotation ω applied on the left of `r` and by `t`: one row per image
+/// axis. `None` if the point is behind the camera.
+fn project_with_jacobian(
+    p: &Vec3,
+    r: &Mat3,
+    t: &Vec3,
+    params: &PoseParams,
+) -> Option<([f64; 2], [[f64; 6]; 2])> {
+    let (fx, fy) = (params.fx, params.fy);
+    let rp = *r * *p;
+    let c = rp + *t;
+    let z = c[2];
+    if z <= 0.0 {
+        return None;
+    }
+    // ∂c/∂ω_k = e_k × Rp
+    let dc_dw = [
+        Vec3::new(0.0, -rp[2], rp[1]),
+        Vec3::new(rp[2], 0.0, -rp[0]),
+        Vec3::new(-rp[1], rp[0], 0.0),
+    ];
+    let row = |d: Vec3| {
+        [
+            d.dot(dc_dw[0]),
+            d.dot(dc_dw[1]),
+            d.dot(dc_dw[2]),
+            d[0],
+            d[1],
+            d[2],
+        ]
+    };
+    Some((
+        [fx * c[0] / z + params.cx, fy * c[1] / z + params.cy],
+        [
+            row(Vec3::new(fx / z, 0.0, -fx * c[0] / (z * z))),
+            row(Vec3::new(0.0, fy / z, -fy * c[1] / (z * z))),
+        ],
+    ))
+}
+
+/// Inverse of a symmetric positive definite 6×6 matrix, scaled to a unit
+/// diagonal first so the pivot tolerance is relative. `None` if singular.
+#[allow(clippy::needless_range_loop)]
+fn invert_symmetric6(m: &[[f64; 6]; 6]) -> Option<[[f64; 6]; 6]> {
+    let mut scale = [0.0f64; 6];
+    for i in 0..6 {
+        if !m[i][i].is_finite() || m[i][i] <= 0.0 {
+            return None;
+        }
+        scale[i] = 1.0 / m[i][i].sqrt();
+    }
+    let mut aug = [[0.0f64; 12]; 6];
+    for i in 0..6 {
+        for j in 0..6 {
+            aug[i][j] = m[i][j] * scale[i] * scale[j];
+        }
+        aug[i][6 + i] = 1.0;
+    }
+    forward_eliminate::<6, 12>(&mut aug, 1e-12)?;
+    let mut inv = [[0.0f64; 6]; 6];
+    for col in 0..6 {
+        for row in (0..6).rev() {
+            let mut sum = aug[row][6 + col];
+            for c in (row + 1)..6 {
+                sum -= aug[row][c] * inv[c][col];
+            }
+            inv[row][col] = sum / aug[row][row];
+        }
+    }
+    for i in 0..6 {
+        for j in 0..6 {
+            inv[i][j] *= scale[i] * scale[j];
+        }
+    }
+    Some(inv)
+}
+
+/// Refine a tag pose by Levenberg–Marquardt, minimizing the reprojection
+/// error in pixels over the tag's corners and the centers of all of its bit
+/// cells.
+///
+/// [`estimate_tag_pose`] fits the four corners in object space, which
+/// weights the near side of a tilted tag more than the far side and leaves
+/// depth loosely constrained at shallow viewing angles. This fit works in
+/// image space over the whole tag, with `initial` (usually the best pose of
+/// [`estimate_tag_pose`]) as the starting point. The cell centers are
+/// located through the detection's [homography](Detection::homography) and
+/// `family`'s layout, so `family` must be the one the tag was decoded with;
+/// a homography refined from the image
+/// ([`DetectorConfig::refine_homography`](super::detector::DetectorConfig::refine_homography),
+/// [`DetectorConfig::refine_decode`](super::detector::DetectorConfig::refine_decode))
+/// carries more information than the corners alone.
+///
+/// Returns the refined pose and its RMS reprojection error in pixels, or
+/// `None` if `initial` puts part of the tag behind the camera or the tag was
+/// seen [mirrored](Detection::mirrored), which no rotation reproduces.
+#[allow(clippy::needless_range_loop)]
+pub fn refine_tag_pose(
+    det: &Detection,
+    family: &TagFamily,
+    params: &PoseParams,
+    initial: &Pose,
+) -> Option<(Pose, f64)> {
+    if det.mirrored {
+        return None;
+    }
+    let (fx, fy, cx, cy) = (params.fx, params.fy, params.cx, params.cy);
+    let s = params.tagsize / 2.0;
+    let w = family.layout.border_width as f64;
+    let cell = |b: i32| 2.0 * ((b as f64 + 0.5) / w - 0.5);
+    let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+    let cells = family
+        .bit_locations
+        .iter()
+        .map(|loc| [cell(loc.x), cell(loc.y)]);
+    // Tag parameter `(u, v)` is the point `(s*u, -s*v, 0)` of the tag frame
+    let points: Vec<(Vec3, [f64; 2])> = corners
+        .into_iter()
+        .chain(cells)
+        .map(|[u, v]| {
+            let (x, y) = det.homography.project(u, v);
+            (Vec3::new(s * u, -s * v, 0.0), [x, y])
+        })
+        .collect();
+
+    let cost = |r: &Mat3, t: &Vec3| -> Option<f64> {
+        let mut sum = 0.0;
+        for (p, m) in &points {
+            let c = *r * *p + *t;
+            if c[2] <= 0.0 {
+                return None;
+            }
+            let (du, dv) = (fx * c[0] / c[2] + cx - m[0], fy * c[1] / c[2] + cy - m[1]);
+            sum += du * du + dv * dv;
+        }
+        Some(sum)
+    };
+
+    let mut r = Mat3(initial.r);
+    let mut t = Vec3(initial.t);
+    let mut current = cost(&r, &t)?;
+    let mut lambda = 1e-3;
+    for _ in 0..REFINE_ITERATIONS {
+        // Parameters: a rotation ω applied on the left of R, then t
+        let mut jtj = [[0.0f64; 6]; 6];
+        let mut jtr = [0.0f64; 6];
+        for (p, m) in &points {
+            let (proj, rows) = project_with_jacobian(p, &r, &t, params)?;
+            for (j, res) in rows.iter().zip([proj[0] - m[0], proj[1] - m[1]]) {
+                for a in 0..6 {
+                    jtr[a] += j[a] * res;
+                    for b in 0..6 {
+                        jtj[a][b] += j[a] * j[b];
+                    }
+                }
+            }
+        }
+
+        let mut step = None;
+        while lambda < 1e6 {
+            let mut aug = [[0.0f64; 7]; 6];
+            for a in 0..6 {
+                aug[a][..6].copy_from_slice(&jtj[a]);
+                aug[a][a] += lambda * jtj[a][a];
+                aug[a][6] = -jtr[a];
+            }
+            if forward_eliminate::<6, 7>(&mut aug, 1e-12).is_none() {
+                lambda *= 10.0;
+                continue;
+            }
+            let mut delta = [0.0f64; 6];
+            for row in (0..6).rev() {
+                let mut sum = aug[row][6];
+                for c in (row + 1)..6 {
+                    sum -= aug[row][c] * delta[c];
+                }
+                delta[row] = sum / aug[row][row];
+            }
+            let next_r = rotation_exp(Vec3::new(delta[0], delta[1], delta[2])) * r;
+            let next_t = t + Vec3::new(delta[3], delta[4], delta[5]);
+            match cost(&next_r, &next_t) {
+                Some(next) if next < current => {
+                    lambda = (lambda / 10.0).max(1e-9);
+                    step = Some((next_r, next_t, next));
+                    break;
+                }
+                _ => lambda *= 10.0,
+            }
+        }
+        let Some((next_r, next_t, next)) = step else {
+            break;
+        };
+        let converged = current - next <= 1e-12 * current;
+        (r, t, current) = (next_r, next_t, next);
+        if converged {
+            break;
+        }
+    }
+
+    let rms = (current / points.len() as f64).sqrt();
+    Some((Pose { r: r.0, t: t.0 }, rms))
+}
+
+/// Rotation by the angle `|w|` about the axis `w` (Rodrigues' formula).
+fn rotation_exp(w: Vec3) -> Mat3 {
+    let theta = w.norm();
+    if theta < 1e-12 {
+        return Mat3::IDENTITY;
+    }
+    let k = w / theta;
+    let kx = Mat3([[0.0, -k[2], k[1]], [k[2], 0.0, -k[0]], [-k[1], k[0], 0.0]]);
+    Mat3::IDENTITY + kx * theta.sin() + kx * kx * (1.0 - theta.cos())
+}
+
 /// Soft Yaw Axis Correction (SYAC) from Abbas et al. 2019.
 ///
 /// Corrects the translation vector for yaw-induced frame inconsistency.
@@ -339,6 +1154,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
 
         let s = params.tagsize / 2.0;
@@ -358,6 +1174,11 @@ mod tests {
             decision_margin: 100.0,
             corners: corners.map(Vec2::from),
             center: Vec2::new(params.cx, params.cy),
+            homography: Homography::from_quad_corners(&corners.map(Vec2::from)).unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         let (pose, err, _, _) = estimate_tag_pose(&det, &params);
@@ -375,6 +1196,62 @@ mod tests {
         assert!(err < 1e-4);
     }
 
+    #[test]
+    fn project_points_reproduces_corners() {
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 550.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let s = params.tagsize / 2.0;
+        let tag_corners_3d = [[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]];
+        let (ca, sa) = (0.4f64.cos(), 0.4f64.sin());
+        let pose = Pose {
+            r: [[ca, 0.0, sa], [0.0, 1.0, 0.0], [-sa, 0.0, ca]],
+            t: [0.05, -0.02, 1.5],
+        };
+
+        let corners = pose.project_points(&tag_corners_3d, &params);
+        let det = Detection {
+            family_id: crate::family::FamilyId::from("test"),
+            id: 0,
+            hamming: 0,
+            decision_margin: 100.0,
+            corners: [corners[0], corners[1], corners[2], corners[3]].map(Vec2::from),
+            center: Vec2::new(0.0, 0.0),
+            homography: Homography::from_quad_corners(
+                &[corners[0], corners[1], corners[2], corners[3]].map(Vec2::from),
+            )
+            .unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
+        };
+        let (est, err, _, _) = estimate_tag_pose(&det, &params);
+        assert!(err < 1e-6);
+        for (a, b) in est
+            .project_points(&tag_corners_3d, &params)
+            .iter()
+            .zip(&corners)
+        {
+            assert!((a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3);
+        }
+
+        // Z axis of a tag turned 0.4 rad about Y leans right, away from the camera
+        let [origin, _, _, z] = pose.axes_endpoints(0.1, &params);
+        assert!(z[0] > origin[0]);
+
+        let behind = Pose {
+            r: pose.r,
+            t: [0.0, 0.0, -1.0],
+        };
+        assert!(behind.project_points(&[[0.0; 3]], &params)[0][0].is_nan());
+    }
+
     #[test]
     fn pose_offset_tag() {
         let params = PoseParams {
@@ -383,6 +1260,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
 
         let s = params.tagsize / 2.0;
@@ -408,6 +1286,11 @@ mod tests {
             decision_margin: 100.0,
             corners: corners.map(Vec2::from),
             center: Vec2::new(params.cx + params.fx * tx_world / z, params.cy),
+            homography: Homography::from_quad_corners(&corners.map(Vec2::from)).unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         let (pose, err, _, _) = estimate_tag_pose(&det, &params);
@@ -424,6 +1307,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
         let det = Detection {
             family_id: crate::family::FamilyId::from("test"),
@@ -432,6 +1316,13 @@ mod tests {
             decision_margin: 100.0,
             corners: [Vec2::new(320.0, 240.0); 4],
             center: Vec2::new(320.0, 240.0),
+            homography: Homography {
+                data: Mat3::IDENTITY,
+            },
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         let (_pose, err, alt, _) = estimate_tag_pose(&det, &params);
         assert_eq!(err, f64::MAX);
@@ -446,6 +1337,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
 
         let s = params.tagsize / 2.0;
@@ -474,6 +1366,11 @@ mod tests {
             decision_margin: 100.0,
             corners: corners.map(Vec2::from),
             center: Vec2::new(params.cx, params.cy),
+            homography: Homography::from_quad_corners(&corners.map(Vec2::from)).unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         let (pose, err, alt, _) = estimate_tag_pose(&det, &params);
@@ -490,6 +1387,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
 
         let s = params.tagsize / 2.0;
@@ -550,6 +1448,15 @@ mod tests {
                             decision_margin: 100.0,
                             corners: corners.map(Vec2::from),
                             center,
+                            // Extreme angles can make the quad degenerate
+                            homography: Homography::from_quad_corners(&corners.map(Vec2::from))
+                                .unwrap_or(Homography {
+                                    data: Mat3::IDENTITY,
+                                }),
+                            mirrored: false,
+                            line_fit_mse: 0.0,
+                            refine_residual: None,
+                            corner_covariance: [[0.0; 3]; 4],
                         };
 
                         let (pose, err, _alt, _alt_err) = estimate_tag_pose(&det, &params);
@@ -571,6 +1478,7 @@ mod tests {
             fy: 500.0,
             cx: 320.0,
             cy: 240.0,
+            solver: Default::default(),
         };
 
         let s = params.tagsize / 2.0;
@@ -590,6 +1498,11 @@ mod tests {
             decision_margin: 100.0,
             corners: corners.map(Vec2::from),
             center: Vec2::new(params.cx, params.cy),
+            homography: Homography::from_quad_corners(&corners.map(Vec2::from)).unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         let (pose, _, _, _) = estimate_tag_pose(&det, &params);
@@ -658,4 +1571,560 @@ mod tests {
         assert!(alt.is_none());
         assert_eq!(err, f64::MAX);
     }
+
+    #[test]
+    fn solve_planar_pnp_recovers_board_pose() {
+        let params = PoseParams {
+            tagsize: 0.0,
+            fx: 800.0,
+            fy: 780.0,
+            cx: 640.0,
+            cy: 360.0,
+            solver: Default::default(),
+        };
+        // Two tags' corners on a board, tilted about X and Y
+        let (ca, sa) = (0.5f64.cos(), 0.5f64.sin());
+        let (cb, sb) = (0.3f64.cos(), 0.3f64.sin());
+        let truth = Pose {
+            r: [
+                [cb, 0.0, sb],
+                [sa * sb, ca, -sa * cb],
+                [-ca * sb, sa, ca * cb],
+            ],
+            t: [-0.2, 0.1, 1.8],
+        };
+        let mut object = Vec::new();
+        for x0 in [0.0, 0.3] {
+            object.extend([
+                [x0, 0.0, 0.0],
+                [x0 + 0.1, 0.0, 0.0],
+                [x0 + 0.1, 0.1, 0.0],
+                [x0, 0.1, 0.0],
+            ]);
+        }
+        let image = truth.project_points(&object, &params);
+
+        let (pose, err, _, _) = solve_planar_pnp(&object, &image, &params).unwrap();
+        assert!(err < 1e-10);
+        for i in 0..3 {
+            assert!((pose.t[i] - truth.t[i]).abs() < 1e-6);
+            for j in 0..3 {
+                assert!((pose.r[i][j] - truth.r[i][j]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_planar_pnp_matches_tag_pose_for_one_tag() {
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let s = params.tagsize / 2.0;
+        let object = [[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]];
+        let (ca, sa) = (0.6f64.cos(), 0.6f64.sin());
+        let truth = Pose {
+            r: [[ca, 0.0, sa], [0.0, 1.0, 0.0], [-sa, 0.0, ca]],
+            t: [0.1, 0.05, 1.2],
+        };
+        let image = truth.project_points(&object, &params);
+        let det = Detection {
+            family_id: crate::family::FamilyId::from("test"),
+            id: 0,
+            hamming: 0,
+            decision_margin: 100.0,
+            corners: [image[0], image[1], image[2], image[3]].map(Vec2::from),
+            center: Vec2::new(0.0, 0.0),
+            homography: Homography::from_quad_corners(
+                &[image[0], image[1], image[2], image[3]].map(Vec2::from),
+            )
+            .unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
+        };
+
+        let (tag, tag_err, _, _) = estimate_tag_pose(&det, &params);
+        let (pnp, pnp_err, alt, _) = solve_planar_pnp(&object, &image, &params).unwrap();
+        assert!(alt.is_some());
+        assert!(tag_err < 1e-10 && pnp_err < 1e-10);
+        for i in 0..3 {
+            assert!((tag.t[i] - pnp.t[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn solve_planar_pnp_rejects_bad_input() {
+        let params = PoseParams {
+            tagsize: 0.0,
+            fx: 500.0,
+            fy: 500.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let square = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let image = [
+            [300.0, 200.0],
+            [340.0, 200.0],
+            [340.0, 240.0],
+            [300.0, 240.0],
+        ];
+        assert!(solve_planar_pnp(&square, &image, &params).is_some());
+        // Mismatched lengths, too few points
+        assert!(solve_planar_pnp(&square, &image[..3], &params).is_none());
+        assert!(solve_planar_pnp(&square[..3], &image[..3], &params).is_none());
+        // Off the z = 0 plane
+        let mut lifted = square;
+        lifted[2][2] = 0.5;
+        assert!(solve_planar_pnp(&lifted, &image, &params).is_none());
+        // Collinear
+        let line = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [3.0, 0.0, 0.0],
+        ];
+        assert!(solve_planar_pnp(&line, &image, &params).is_none());
+    }
+
+    /// An oblique tag whose homography is exact but whose corners are off by
+    /// up to a pixel, as when the homography was refined from the image.
+    fn oblique_detection(params: &PoseParams) -> (Detection, Pose) {
+        let s = params.tagsize / 2.0;
+        let a = 60f64.to_radians();
+        let truth = Pose {
+            r: [
+                [a.cos(), 0.0, a.sin()],
+                [0.0, 1.0, 0.0],
+                [-a.sin(), 0.0, a.cos()],
+            ],
+            t: [0.05, -0.02, 1.5],
+        };
+        let exact = truth
+            .project_points(
+                &[[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]],
+                params,
+            )
+            .iter()
+            .map(|&p| Vec2::from(p))
+            .collect::<Vec<_>>();
+        let offsets = [[0.8, -0.5], [-0.6, 0.7], [0.9, 0.4], [-0.7, -0.9]];
+        let corners: [Vec2; 4] = std::array::from_fn(|i| {
+            Vec2::new(exact[i][0] + offsets[i][0], exact[i][1] + offsets[i][1])
+        });
+        let det = Detection {
+            family_id: crate::family::FamilyId::from("tag36h11"),
+            id: 0,
+            hamming: 0,
+            decision_margin: 100.0,
+            corners,
+            center: corners[0],
+            homography: Homography::from_quad_corners(&[exact[0], exact[1], exact[2], exact[3]])
+                .unwrap(),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
+        };
+        (det, truth)
+    }
+
+    #[test]
+    fn refine_tag_pose_fits_the_homography() {
+        let family = crate::family::builtin_family("tag36h11").unwrap();
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let (det, truth) = oblique_detection(&params);
+
+        let (initial, ..) = estimate_tag_pose(&det, &params);
+        let (refined, rms) = refine_tag_pose(&det, &family, &params, &initial).unwrap();
+
+        let depth_error = |p: &Pose| (p.t[2] - truth.t[2]).abs();
+        assert!(depth_error(&initial) > 1e-3, "{}", depth_error(&initial));
+        assert!(depth_error(&refined) < 1e-6, "{}", depth_error(&refined));
+        assert!(rms < 1e-6, "{rms}");
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((refined.r[i][j] - truth.r[i][j]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn refine_tag_pose_rejects_unusable_input() {
+        let family = crate::family::builtin_family("tag36h11").unwrap();
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let (mut det, truth) = oblique_detection(&params);
+
+        let behind = Pose {
+            r: truth.r,
+            t: [truth.t[0], truth.t[1], -truth.t[2]],
+        };
+        assert!(refine_tag_pose(&det, &family, &params, &behind).is_none());
+
+        det.mirrored = true;
+        assert!(refine_tag_pose(&det, &family, &params, &truth).is_none());
+    }
+
+    fn detection_from_corners(corners: [Vec2; 4]) -> Detection {
+        Detection {
+            family_id: crate::family::FamilyId::from("test"),
+            id: 0,
+            hamming: 0,
+            decision_margin: 100.0,
+            corners,
+            center: corners[0],
+            homography: Homography::from_quad_corners(&corners).unwrap_or(Homography {
+                data: Mat3::IDENTITY,
+            }),
+            mirrored: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
+        }
+    }
+
+    #[test]
+    fn ippe_recovers_exact_tag_poses() {
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 580.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: PoseSolver::Ippe,
+        };
+        let s = params.tagsize / 2.0;
+        let tag_corners_3d = [[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]];
+        for (ax, ay, t) in [
+            (0.1, 0.0, [0.0, 0.0, 1.0]),
+            (0.0, 0.7, [0.3, -0.1, 2.0]),
+            (-0.5, 0.4, [-0.2, 0.2, 1.5]),
+            (1.1, -0.3, [0.1, 0.05, 0.8]),
+        ] {
+            let (cx_, sx) = (f64::cos(ax), f64::sin(ax));
+            let (cy_, sy) = (f64::cos(ay), f64::sin(ay));
+            let truth = Pose {
+                r: [
+                    [cy_, 0.0, sy],
+                    [sx * sy, cx_, -sx * cy_],
+                    [-cx_ * sy, sx, cx_ * cy_],
+                ],
+                t,
+            };
+            let image = truth.project_points(&tag_corners_3d, &params);
+            let det = detection_from_corners(std::array::from_fn(|i| Vec2::from(image[i])));
+
+            let (pose, err, alt, alt_err) = estimate_tag_pose(&det, &params);
+            assert!(err < 1e-12, "{err}");
+            assert!(alt.is_some() && alt_err >= err);
+            for i in 0..3 {
+                assert!((pose.t[i] - truth.t[i]).abs() < 1e-6, "{:?}", pose.t);
+                for j in 0..3 {
+                    assert!((pose.r[i][j] - truth.r[i][j]).abs() < 1e-6);
+                }
+            }
+
+            // Orthogonal iteration converges to the same pose
+            let oi_params = PoseParams {
+                solver: PoseSolver::OrthogonalIteration,
+                ..params.clone()
+            };
+            let (oi, ..) = estimate_tag_pose(&det, &oi_params);
+            for i in 0..3 {
+                assert!((oi.t[i] - pose.t[i]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn ippe_solve_planar_pnp_recovers_off_center_board() {
+        let params = PoseParams {
+            tagsize: 0.0,
+            fx: 800.0,
+            fy: 780.0,
+            cx: 640.0,
+            cy: 360.0,
+            solver: PoseSolver::Ippe,
+        };
+        let (ca, sa) = (0.5f64.cos(), 0.5f64.sin());
+        let truth = Pose {
+            r: [[1.0, 0.0, 0.0], [0.0, ca, -sa], [0.0, sa, ca]],
+            t: [-0.4, 0.1, 1.8],
+        };
+        let object = [
+            [0.5, 0.2, 0.0],
+            [0.9, 0.2, 0.0],
+            [0.9, 0.5, 0.0],
+            [0.5, 0.5, 0.0],
+            [0.6, 0.3, 0.0],
+            [0.8, 0.45, 0.0],
+        ];
+        let image = truth.project_points(&object, &params);
+
+        let (pose, err, alt, _) = solve_planar_pnp(&object, &image, &params).unwrap();
+        assert!(err < 1e-10);
+        assert!(alt.is_some());
+        for i in 0..3 {
+            assert!((pose.t[i] - truth.t[i]).abs() < 1e-6);
+            for j in 0..3 {
+                assert!((pose.r[i][j] - truth.r[i][j]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn ippe_rejects_degenerate_corners() {
+        let params = PoseParams {
+            tagsize: 0.2,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: PoseSolver::Ippe,
+        };
+        let det = detection_from_corners([
+            Vec2::new(100.0, 100.0),
+            Vec2::new(200.0, 100.0),
+            Vec2::new(300.0, 100.0),
+            Vec2::new(400.0, 100.0),
+        ]);
+        let (_, err, alt, _) = estimate_tag_pose_ippe(&det, &params);
+        assert_eq!(err, f64::MAX);
+        assert!(alt.is_none());
+    }
+
+    #[test]
+    fn gravity_prior_stops_near_frontal_flips() {
+        let params = PoseParams {
+            tagsize: 0.1,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let s = params.tagsize / 2.0;
+        let tag_corners_3d = [[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]];
+        let a = 10f64.to_radians();
+        let truth = Pose {
+            r: [
+                [a.cos(), 0.0, a.sin()],
+                [0.0, 1.0, 0.0],
+                [-a.sin(), 0.0, a.cos()],
+            ],
+            t: [0.05, 0.02, 1.5],
+        };
+        // Gravity along the camera's Y axis, measured a few degrees off
+        let tag_gravity = (Mat3(truth.r).transpose() * Vec3::new(0.0, 1.0, 0.0)).0;
+        let prior = PosePrior::Direction {
+            camera: [0.05, 1.0, 0.0],
+            tag: tag_gravity,
+        };
+        let truth_prior = PosePrior::Rotation(truth.r);
+        // Whether the first pose is further from the truth than the second
+        let flipped = |(best, _, alt, _): &(Pose, f64, Option<Pose>, f64)| {
+            alt.as_ref()
+                .is_some_and(|alt| truth_prior.angle(best) > truth_prior.angle(alt))
+        };
+
+        let mut state = 1u64;
+        let mut noise = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        };
+        let exact = truth.project_points(&tag_corners_3d, &params);
+        let (mut flips, mut flips_with_prior) = (0, 0);
+        for _ in 0..200 {
+            let corners =
+                std::array::from_fn(|i| Vec2::new(exact[i][0] + noise(), exact[i][1] + noise()));
+            let result = estimate_tag_pose(&detection_from_corners(corners), &params);
+            flips += flipped(&result) as usize;
+            flips_with_prior += flipped(&prior.resolve(result, 10.0)) as usize;
+        }
+        assert!(flips > 20, "{flips}");
+        assert_eq!(flips_with_prior, 0);
+    }
+
+    #[test]
+    fn pose_prior_yields_to_clear_image_evidence() {
+        let tilted = |a: f64| Pose {
+            r: [
+                [a.cos(), 0.0, a.sin()],
+                [0.0, 1.0, 0.0],
+                [-a.sin(), 0.0, a.cos()],
+            ],
+            t: [0.0, 0.0, 1.0],
+        };
+        let prior = PosePrior::Rotation(tilted(0.5).r);
+        assert!(prior.angle(&tilted(0.5)) < 1e-7);
+        assert!((prior.angle(&tilted(0.2)) - 0.3).abs() < 1e-12);
+
+        let result = (tilted(-0.2), 1.0e-6, Some(tilted(0.2)), 1.0e-4);
+        let (pose, ..) = prior.resolve(result.clone(), 10.0);
+        assert_eq!(pose.r, tilted(-0.2).r);
+        let (pose, ..) = prior.resolve(result, f64::INFINITY);
+        assert_eq!(pose.r, tilted(0.2).r);
+
+        // Without a second pose there is nothing to choose
+        let (pose, _, alt, _) = prior.resolve((tilted(-0.2), 1.0, None, f64::MAX), 10.0);
+        assert_eq!(pose.r, tilted(-0.2).r);
+        assert!(alt.is_none());
+    }
+
+    /// A detection of `truth`'s corners, offset by `noise`, with each corner
+    /// given the covariance `sigma² I`.
+    fn noisy_detection(
+        truth: &Pose,
+        params: &PoseParams,
+        sigma: f64,
+        mut noise: impl FnMut() -> f64,
+    ) -> Detection {
+        let s = params.tagsize / 2.0;
+        let tag_corners_3d = [[-s, s, 0.0], [s, s, 0.0], [s, -s, 0.0], [-s, -s, 0.0]];
+        let exact = truth.project_points(&tag_corners_3d, params);
+        let corners = std::array::from_fn(|i| {
+            Vec2::new(exact[i][0] + sigma * noise(), exact[i][1] + sigma * noise())
+        });
+        Detection {
+            corner_covariance: [[sigma * sigma, 0.0, sigma * sigma]; 4],
+            ..detection_from_corners(corners)
+        }
+    }
+
+    /// Uniform noise with unit variance.
+    fn unit_noise(seed: u64) -> impl FnMut() -> f64 {
+        let mut state = seed;
+        move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 12f64.sqrt()
+        }
+    }
+
+    fn tilted_pose(a: f64, t: [f64; 3]) -> Pose {
+        Pose {
+            r: [
+                [a.cos(), 0.0, a.sin()],
+                [0.0, 1.0, 0.0],
+                [-a.sin(), 0.0, a.cos()],
+            ],
+            t,
+        }
+    }
+
+    #[test]
+    fn pose_covariance_matches_corner_noise() {
+        let params = PoseParams {
+            tagsize: 0.1,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let truth = tilted_pose(50f64.to_radians(), [0.05, -0.02, 0.6]);
+        let sigma = 0.3;
+        let exact = noisy_detection(&truth, &params, sigma, || 0.0);
+        let predicted = pose_uncertainty(&exact, &params, &estimate_tag_pose(&exact, &params))
+            .unwrap()
+            .covariance;
+
+        let mut noise = unit_noise(7);
+        let n = 2000;
+        let mut sum_sq = [0.0; 3];
+        for _ in 0..n {
+            let det = noisy_detection(&truth, &params, sigma, &mut noise);
+            let (pose, ..) = estimate_tag_pose(&det, &params);
+            for (sum, (t, truth)) in sum_sq.iter_mut().zip(pose.t.iter().zip(truth.t)) {
+                *sum += (t - truth).powi(2) / n as f64;
+            }
+        }
+        for i in 0..3 {
+            let ratio = (sum_sq[i] / predicted[3 + i][3 + i]).sqrt();
+            assert!((0.8..1.25).contains(&ratio), "t[{i}]: {ratio}");
+        }
+    }
+
+    #[test]
+    fn pose_uncertainty_flags_near_frontal_ambiguity() {
+        let params = PoseParams {
+            tagsize: 0.1,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: PoseSolver::Ippe,
+        };
+        let uncertainty = |degrees: f64| {
+            let truth = tilted_pose(degrees.to_radians(), [0.0, 0.0, 1.0]);
+            let det = noisy_detection(&truth, &params, 0.5, unit_noise(3));
+            pose_uncertainty(&det, &params, &estimate_tag_pose(&det, &params)).unwrap()
+        };
+        let frontal = uncertainty(5.0);
+        let oblique = uncertainty(45.0);
+        assert!(
+            oblique.ambiguity > 4.0 * frontal.ambiguity,
+            "{} vs {}",
+            oblique.ambiguity,
+            frontal.ambiguity
+        );
+        // The second pose is tilted the other way
+        let diff = oblique.rotation_difference.unwrap().to_degrees();
+        assert!((60.0..120.0).contains(&diff), "{diff}");
+        // The tilt of a frontal tag is poorly constrained
+        assert!(frontal.covariance[1][1] > 4.0 * oblique.covariance[1][1]);
+    }
+
+    #[test]
+    fn pose_uncertainty_needs_corner_covariances() {
+        let params = PoseParams {
+            tagsize: 0.1,
+            fx: 600.0,
+            fy: 600.0,
+            cx: 320.0,
+            cy: 240.0,
+            solver: Default::default(),
+        };
+        let truth = tilted_pose(0.3, [0.0, 0.0, 1.0]);
+        let mut det = noisy_detection(&truth, &params, 0.5, || 0.0);
+        let result = estimate_tag_pose(&det, &params);
+        assert!(pose_uncertainty(&det, &params, &result).is_some());
+        det.corner_covariance = [[0.0; 3]; 4];
+        assert!(pose_uncertainty(&det, &params, &result).is_none());
+
+        // Without a second pose the choice is unambiguous
+        det.corner_covariance = [[0.25, 0.0, 0.25]; 4];
+        let (pose, err, _, _) = result;
+        let single = pose_uncertainty(&det, &params, &(pose, err, None, f64::MAX)).unwrap();
+        assert_eq!(single.ambiguity, f64::INFINITY);
+        assert!(single.rotation_difference.is_none());
+    }
 }
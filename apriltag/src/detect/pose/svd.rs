@@ -152,7 +152,7 @@ pub(super) fn project_to_so3(m: &Mat3) -> Mat3 {
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used)]
+#[allow(clippy::unwrap_used, clippy::needless_range_loop)]
 mod tests {
     use super::*;
 
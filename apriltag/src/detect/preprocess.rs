@@ -1,5 +1,6 @@
 use super::image::{GrayImage, ImageU8};
 use super::par::Par;
+#[cfg(feature = "simd")]
 use wide::{i32x8, u32x8};
 
 /// Decimate an image by factor `f`, subsampling every f-th pixel.
@@ -30,6 +31,9 @@ pub fn decimate(img: &(impl GrayImage + Sync), f: u32, out: &mut ImageU8) {
     out.reshape(out_w, out_h);
 
     let owu = out_w as usize;
+    if owu == 0 {
+        return;
+    }
     Par::get().chunks_mut_for_each(&mut out.buf[..out_h as usize * owu], owu, |oy, row| {
         for ox in 0..out_w {
             row[ox as usize] = img.get(ox * f, oy as u32 * f);
@@ -37,6 +41,221 @@ pub fn decimate(img: &(impl GrayImage + Sync), f: u32, out: &mut ImageU8) {
     });
 }
 
+/// Decimate an image by factor `f`, averaging each f×f block.
+///
+/// Unlike [`decimate`], every input pixel contributes, so edges finer than
+/// the step are not aliased away. Each output pixel is the rounded mean of
+/// its block and stands for the block center, `(f - 1) / 2` input pixels
+/// right of and below the pixel [`decimate`] would keep. A partial block at
+/// the right or bottom edge is dropped. When `f <= 1`, copies `img` into
+/// `out`.
+pub fn decimate_average(img: &(impl GrayImage + Sync), f: u32, out: &mut ImageU8) {
+    if f <= 1 {
+        decimate(img, f, out);
+        return;
+    }
+
+    let out_w = img.width() / f;
+    let out_h = img.height() / f;
+    out.reshape(out_w, out_h);
+
+    let owu = out_w as usize;
+    if owu == 0 {
+        return;
+    }
+    let fu = f as usize;
+    // A block of a large factor sums past u32
+    let area = u64::from(f) * u64::from(f);
+    Par::get().chunks_mut_for_each(&mut out.buf[..out_h as usize * owu], owu, |oy, row| {
+        let y0 = oy as u32 * f;
+        for (ox, dst) in row.iter_mut().enumerate() {
+            let x0 = ox * fu;
+            let mut sum = 0u64;
+            for y in y0..y0 + f {
+                sum += img.row(y)[x0..x0 + fu]
+                    .iter()
+                    .map(|&v| u64::from(v))
+                    .sum::<u64>();
+            }
+            *dst = ((sum + area / 2) / area) as u8;
+        }
+    });
+}
+
+/// Decimate an image by 1.5, turning each 3×3 block into 2×2 pixels.
+///
+/// Each output pixel weights its nearest input corner 4, the two adjacent
+/// edge pixels 2 and the block center 1, matching the C reference
+/// implementation's `quad_decimate == 1.5` special case. Output dimensions are
+/// `width / 3 * 2` by `height / 3 * 2`; a partial block at the right or
+/// bottom edge is dropped.
+pub fn decimate_three_halves(img: &(impl GrayImage + Sync), out: &mut ImageU8) {
+    let out_w = img.width() / 3 * 2;
+    let out_h = img.height() / 3 * 2;
+    out.reshape(out_w, out_h);
+
+    let owu = out_w as usize;
+    if owu == 0 {
+        return;
+    }
+    Par::get().chunks_mut_for_each(&mut out.buf[..out_h as usize * owu], 2 * owu, |by, rows| {
+        let y = 3 * by as u32;
+        let (r0, r1, r2) = (img.row(y), img.row(y + 1), img.row(y + 2));
+        let (top, bottom) = rows.split_at_mut(owu);
+        for bx in 0..owu / 2 {
+            let x = 3 * bx;
+            let [a, b, c] = [r0[x], r0[x + 1], r0[x + 2]].map(u32::from);
+            let [d, e, f] = [r1[x], r1[x + 1], r1[x + 2]].map(u32::from);
+            let [g, h, i] = [r2[x], r2[x + 1], r2[x + 2]].map(u32::from);
+            top[2 * bx] = ((4 * a + 2 * b + 2 * d + e) / 9) as u8;
+            top[2 * bx + 1] = ((4 * c + 2 * b + 2 * f + e) / 9) as u8;
+            bottom[2 * bx] = ((4 * g + 2 * d + 2 * h + e) / 9) as u8;
+            bottom[2 * bx + 1] = ((4 * i + 2 * f + 2 * h + e) / 9) as u8;
+        }
+    });
+}
+
+/// How integer [`DetectorConfig::quad_decimate`](super::detector::DetectorConfig::quad_decimate)
+/// factors pick each working-image pixel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DecimateMode {
+    /// Keep the top-left pixel of each block, as in the C reference
+    /// implementation. Fastest, but at 3× and above it can skip the edges
+    /// of small tags.
+    #[default]
+    Point,
+    /// Average each block; see [`decimate_average`]. Keeps fine edges at
+    /// large factors for a little more work.
+    Average,
+}
+
+/// One field of an interlaced frame; see
+/// [`DetectorConfig::interlace_field`](super::detector::DetectorConfig::interlace_field).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum InterlaceField {
+    /// Rows 0, 2, 4, …
+    Even,
+    /// Rows 1, 3, 5, …
+    Odd,
+}
+
+impl InterlaceField {
+    /// The frame row holding the field's first row.
+    pub fn first_row(self) -> u32 {
+        match self {
+            Self::Even => 0,
+            Self::Odd => 1,
+        }
+    }
+
+    /// Rows of this field in a frame `height` rows tall.
+    pub fn rows(self, height: u32) -> u32 {
+        height.saturating_sub(self.first_row()).div_ceil(2)
+    }
+
+    /// Map a y coordinate in the field image to the frame. Field row `r`
+    /// is frame row `2r + first_row`, and pixel centers sit at `+0.5`.
+    pub fn to_frame_y(self, y: f64) -> f64 {
+        2.0 * y - 0.5 + self.first_row() as f64
+    }
+}
+
+/// Copy the rows of one field of `img` into `out`, reusing its allocation.
+/// The result is half as tall, so its pixels are twice as tall as they are
+/// wide.
+pub fn extract_field(img: &impl GrayImage, field: InterlaceField, out: &mut ImageU8) {
+    let w = img.width();
+    let h = field.rows(img.height());
+    out.reshape(w, h);
+    let wu = w as usize;
+    for y in 0..h {
+        let dst_off = y as usize * wu;
+        out.buf[dst_off..dst_off + wu].copy_from_slice(img.row(2 * y + field.first_row()));
+    }
+}
+
+/// How [`DetectorConfig::quad_decimate`](super::detector::DetectorConfig::quad_decimate)
+/// shrinks the image before thresholding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decimation {
+    /// Keep every n-th pixel in each direction; see [`decimate`]. A step of
+    /// 0 or 1 keeps the image as is.
+    Subsample(u32),
+    /// Average each n×n block; see [`decimate_average`].
+    Average(u32),
+    /// The 1.5× filter of [`decimate_three_halves`].
+    ThreeHalves,
+}
+
+impl Decimation {
+    /// `1.5` selects the 1.5× filter. Other factors are truncated to an
+    /// integer step, as in the C reference implementation.
+    pub fn from_factor(quad_decimate: f32) -> Self {
+        if quad_decimate == 1.5 {
+            Self::ThreeHalves
+        } else {
+            Self::Subsample(quad_decimate as u32)
+        }
+    }
+
+    /// Switch integer steps above 1 to `mode`; other decimations are
+    /// unaffected.
+    pub fn with_mode(self, mode: DecimateMode) -> Self {
+        match (self, mode) {
+            (Self::Subsample(f) | Self::Average(f), DecimateMode::Average) if f > 1 => {
+                Self::Average(f)
+            }
+            (Self::Average(f), DecimateMode::Point) => Self::Subsample(f),
+            _ => self,
+        }
+    }
+
+    /// True when the working image has the input's resolution.
+    pub fn is_identity(self) -> bool {
+        matches!(self, Self::Subsample(0 | 1) | Self::Average(0 | 1))
+    }
+
+    /// Input pixels per working-image pixel along each axis, by which quad
+    /// corners are scaled back to the input image.
+    pub fn scale(self) -> f64 {
+        match self {
+            Self::Subsample(f) | Self::Average(f) => f.max(1) as f64,
+            Self::ThreeHalves => 1.5,
+        }
+    }
+
+    /// Input pixels to add along each axis after [`scale`](Self::scale),
+    /// for filters whose pixels stand for their block center.
+    pub fn offset(self) -> f64 {
+        match self {
+            Self::Average(f) if f > 1 => (f - 1) as f64 / 2.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Working image dimensions for a `width` × `height` input.
+    pub fn output_size(self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Self::Subsample(f) | Self::Average(f) if f > 1 => (width / f, height / f),
+            Self::Subsample(_) | Self::Average(_) => (width, height),
+            Self::ThreeHalves => (width / 3 * 2, height / 3 * 2),
+        }
+    }
+
+    /// Decimate `img` into `out`, reusing its allocation.
+    pub fn apply(self, img: &(impl GrayImage + Sync), out: &mut ImageU8) {
+        match self {
+            Self::Subsample(f) => decimate(img, f, out),
+            Self::Average(f) => decimate_average(img, f, out),
+            Self::ThreeHalves => decimate_three_halves(img, out),
+        }
+    }
+}
+
 /// Build a 1D Gaussian kernel with the given sigma and kernel size.
 ///
 /// Returns fixed-point kernel values scaled so they sum to `1 << 15` (32768).
@@ -101,6 +320,7 @@ fn gaussian_blur(img: &ImageU8, sigma: f32, ksz: usize, out: &mut ImageU8, tmp:
         // Interior (SIMD — no clamping needed)
         let interior_end = (wu - halfu).max(halfu);
         let mut x = halfu;
+        #[cfg(feature = "simd")]
         while x + 8 <= interior_end {
             let mut acc = u32x8::ZERO;
             for (ki, &kv) in kernel.iter().enumerate() {
@@ -157,6 +377,7 @@ fn gaussian_blur(img: &ImageU8, sigma: f32, ksz: usize, out: &mut ImageU8, tmp:
             .collect();
 
         let mut x = 0usize;
+        #[cfg(feature = "simd")]
         while x + 8 <= wu {
             let mut acc = u32x8::ZERO;
             for (k, &kv) in kernel.iter().enumerate() {
@@ -194,30 +415,35 @@ fn gaussian_blur(img: &ImageU8, sigma: f32, ksz: usize, out: &mut ImageU8, tmp:
     });
 }
 
+/// Gaussian kernel size for `quad_sigma`, or `None` if
+/// [`apply_sigma`] would only copy the image.
+fn sigma_kernel_size(quad_sigma: f32) -> Option<usize> {
+    let mut ksz = (4.0 * quad_sigma.abs()) as usize;
+    if ksz.is_multiple_of(2) {
+        ksz += 1;
+    }
+    (ksz > 1).then_some(ksz)
+}
+
+/// Whether [`apply_sigma`] leaves the image unchanged for `quad_sigma`.
+pub fn sigma_is_identity(quad_sigma: f32) -> bool {
+    sigma_kernel_size(quad_sigma).is_none()
+}
+
 /// Apply Gaussian blur or sharpening based on `quad_sigma`.
 ///
 /// - `quad_sigma > 0` → Gaussian blur into `out`
 /// - `quad_sigma < 0` → Unsharp mask into `out` (using `tmp` as scratch)
-/// - `quad_sigma == 0` → Copy `img` into `out`
+/// - `quad_sigma == 0`, or too small for a blur kernel → Copy `img` into `out`
 ///
 /// `tmp` is used as scratch space for the blur passes.
 pub fn apply_sigma(img: &ImageU8, quad_sigma: f32, out: &mut ImageU8, tmp: &mut ImageU8) {
-    if quad_sigma == 0.0 {
+    let Some(ksz) = sigma_kernel_size(quad_sigma) else {
         out.reshape(img.width, img.height);
         out.buf.copy_from_slice(&img.buf);
         return;
-    }
-
+    };
     let sigma = quad_sigma.abs();
-    let mut ksz = (4.0 * sigma) as usize;
-    if ksz.is_multiple_of(2) {
-        ksz += 1;
-    }
-    if ksz <= 1 {
-        out.reshape(img.width, img.height);
-        out.buf.copy_from_slice(&img.buf);
-        return;
-    }
 
     if quad_sigma > 0.0 {
         gaussian_blur(img, sigma, ksz, out, tmp);
@@ -233,6 +459,7 @@ pub fn apply_sigma(img: &ImageU8, quad_sigma: f32, out: &mut ImageU8, tmp: &mut
 
             // SIMD: process 8 pixels at a time
             let mut x = 0usize;
+            #[cfg(feature = "simd")]
             while x + 8 <= wu {
                 let orig = i32x8::new([
                     orig_row[x] as i32,
@@ -278,6 +505,24 @@ pub fn apply_sigma(img: &ImageU8, quad_sigma: f32, out: &mut ImageU8, tmp: &mut
 mod tests {
     use super::*;
 
+    #[test]
+    fn extract_field_keeps_alternate_rows() {
+        // 2×5, each pixel holding its row index
+        let img = ImageU8::from_pixels(2, 5, vec![0, 0, 1, 1, 2, 2, 3, 3, 4, 4]);
+        let mut out = ImageU8::new(0, 0);
+        extract_field(&img, InterlaceField::Even, &mut out);
+        assert_eq!((out.width, out.height), (2, 3));
+        assert_eq!(out.buf, [0, 0, 2, 2, 4, 4]);
+        extract_field(&img, InterlaceField::Odd, &mut out);
+        assert_eq!((out.width, out.height), (2, 2));
+        assert_eq!(out.buf, [1, 1, 3, 3]);
+
+        // Pixel centers map back onto the rows they came from
+        assert_eq!(InterlaceField::Even.to_frame_y(1.5), 2.5);
+        assert_eq!(InterlaceField::Odd.to_frame_y(1.5), 3.5);
+        assert_eq!(InterlaceField::Odd.rows(0), 0);
+    }
+
     #[test]
     fn decimate_factor_1_with_stride() {
         // Exercise the stride != width path in decimate(f=1)
@@ -335,6 +580,114 @@ mod tests {
         assert_eq!(out.get(1, 0), 180);
     }
 
+    #[test]
+    fn decimate_three_halves_weights_block_corners() {
+        // One 3x3 block plus a partial column and row, which are dropped
+        let mut img = ImageU8::new(4, 4);
+        img.set(0, 0, 90);
+        img.set(2, 2, 180);
+        img.set(1, 1, 255);
+        img.set(3, 3, 255);
+        let mut out = ImageU8::new(0, 0);
+        decimate_three_halves(&img, &mut out);
+        assert_eq!((out.width, out.height), (2, 2));
+        // (4 * corner + 2 * edges + center) / 9
+        assert_eq!(out.get(0, 0), ((4 * 90 + 255) / 9) as u8);
+        assert_eq!(out.get(1, 0), (255 / 9) as u8);
+        assert_eq!(out.get(0, 1), (255 / 9) as u8);
+        assert_eq!(out.get(1, 1), ((4 * 180 + 255) / 9) as u8);
+
+        let flat = ImageU8::from_buf(6, 3, 6, vec![77; 18]);
+        decimate_three_halves(&flat, &mut out);
+        assert_eq!((out.width, out.height), (4, 2));
+        assert!(out.buf.iter().all(|&v| v == 77));
+
+        decimate_three_halves(&ImageU8::new(2, 5), &mut out);
+        assert_eq!((out.width, out.height), (0, 2));
+    }
+
+    #[test]
+    fn decimation_from_factor() {
+        assert_eq!(Decimation::from_factor(1.5), Decimation::ThreeHalves);
+        assert_eq!(Decimation::from_factor(2.7), Decimation::Subsample(2));
+        assert!(Decimation::from_factor(1.0).is_identity());
+        assert!(Decimation::from_factor(0.5).is_identity());
+        assert_eq!(Decimation::ThreeHalves.scale(), 1.5);
+        assert_eq!(Decimation::Subsample(0).scale(), 1.0);
+        assert_eq!(Decimation::ThreeHalves.output_size(640, 481), (426, 320));
+        assert_eq!(Decimation::Subsample(3).output_size(640, 481), (213, 160));
+    }
+
+    #[test]
+    fn decimate_average_rounds_block_means() {
+        // 7x5 with 3x3 blocks: two full blocks in one row, the rest dropped
+        let mut img = ImageU8::new(7, 5);
+        img.set(0, 0, 9);
+        img.set(4, 2, 255);
+        img.set(6, 4, 255);
+        let mut out = ImageU8::new(0, 0);
+        decimate_average(&img, 3, &mut out);
+        assert_eq!((out.width, out.height), (2, 1));
+        assert_eq!(out.get(0, 0), 1);
+        assert_eq!(out.get(1, 0), 28); // 255 / 9 = 28.33
+
+        // A one-pixel line that point sampling at 4x misses entirely
+        let mut img = ImageU8::from_buf(8, 8, 8, vec![200; 64]);
+        for y in 0..8 {
+            img.set(5, y, 0);
+        }
+        decimate(&img, 4, &mut out);
+        assert!(out.buf.iter().all(|&v| v == 200));
+        decimate_average(&img, 4, &mut out);
+        assert_eq!(out.buf, [200, 150, 200, 150]);
+
+        decimate_average(&img, 1, &mut out);
+        assert_eq!(out.buf, img.buf);
+    }
+
+    #[test]
+    fn decimate_average_handles_huge_factors() {
+        // A factor larger than the image leaves nothing, and its block
+        // area does not overflow
+        let img = ImageU8::from_buf(8, 8, 8, vec![200; 64]);
+        let mut out = ImageU8::new(0, 0);
+        decimate_average(&img, 70_000, &mut out);
+        assert_eq!((out.width, out.height), (0, 0));
+        decimate(&img, 70_000, &mut out);
+        assert_eq!((out.width, out.height), (0, 0));
+
+        // One white 4200×4200 block sums to more than u32::MAX
+        let f = 4200;
+        let img = ImageU8::from_buf(f, f, f, vec![255; (f * f) as usize]);
+        decimate_average(&img, f, &mut out);
+        assert_eq!(out.buf, [255]);
+    }
+
+    #[test]
+    fn decimation_with_mode() {
+        let avg = DecimateMode::Average;
+        assert_eq!(
+            Decimation::Subsample(3).with_mode(avg),
+            Decimation::Average(3)
+        );
+        assert_eq!(
+            Decimation::Average(3).with_mode(DecimateMode::Point),
+            Decimation::Subsample(3)
+        );
+        assert_eq!(
+            Decimation::Subsample(1).with_mode(avg),
+            Decimation::Subsample(1)
+        );
+        assert_eq!(
+            Decimation::ThreeHalves.with_mode(avg),
+            Decimation::ThreeHalves
+        );
+        assert_eq!(Decimation::Average(4).scale(), 4.0);
+        assert_eq!(Decimation::Average(4).offset(), 1.5);
+        assert_eq!(Decimation::Subsample(4).offset(), 0.0);
+        assert_eq!(Decimation::Average(3).output_size(640, 481), (213, 160));
+    }
+
     #[test]
     fn decimate_truncates_partial_blocks() {
         let img = ImageU8::new(5, 5);
@@ -579,7 +932,7 @@ mod tests {
         // sigma=0.1 produces ksz=0 which triggers the skip path
         for sigma in [0.1f32, 0.8, 1.0, 1.5, 2.0] {
             let mut ksz = (4.0 * sigma) as usize;
-            if ksz % 2 == 0 {
+            if ksz.is_multiple_of(2) {
                 ksz += 1;
             }
             if ksz <= 1 {
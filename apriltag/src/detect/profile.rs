@@ -0,0 +1,117 @@
+//! Per-stage wall-clock timings of one detection.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// `Instant::now` panics on targets without a clock.
+pub(crate) const HAS_CLOCK: bool = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+/// Wall-clock time of one pipeline stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageTime {
+    /// Stage name, as used for the `tracing` spans.
+    pub name: &'static str,
+    pub duration: Duration,
+}
+
+/// Stage timings from [`Detector::detect_with_profile`](super::detector::Detector::detect_with_profile),
+/// the counterpart of the C library's `timeprofile`.
+///
+/// Stages appear in pipeline order: `preprocess`, `threshold`,
+/// `connected_components`, `gradient_clusters`, `fit_quads`,
+/// `refine_edges`, `decode` and `deduplicate`. Stages that did not run, such
+/// as `preprocess` without decimation or blur, are omitted. With
+/// [`band_rows`](super::detector::DetectorConfig::band_rows) set, a single
+/// `banded_clusters` stage replaces the three from `threshold` to
+/// `gradient_clusters`. Durations are
+/// zero on `wasm32-unknown-unknown`, which has no clock.
+///
+/// The [`Display`](fmt::Display) impl prints a table of per-stage and
+/// cumulative times in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimeProfile {
+    pub stages: Vec<StageTime>,
+}
+
+impl TimeProfile {
+    /// Time spent in stage `name`, if it ran.
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.stages
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.duration)
+    }
+
+    /// Sum of all stage times.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|s| s.duration).sum()
+    }
+}
+
+impl fmt::Display for TimeProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cumulative = Duration::ZERO;
+        for (i, stage) in self.stages.iter().enumerate() {
+            cumulative += stage.duration;
+            writeln!(
+                f,
+                "{i:2} {:<22} {:>9.3} ms {:>9.3} ms",
+                stage.name,
+                stage.duration.as_secs_f64() * 1e3,
+                cumulative.as_secs_f64() * 1e3
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Start timing a stage, if profiling.
+pub(crate) fn start(profile: &Option<&mut TimeProfile>) -> Option<Instant> {
+    (HAS_CLOCK && profile.is_some()).then(Instant::now)
+}
+
+/// Record stage `name` as having run since `start`.
+pub(crate) fn record(
+    profile: &mut Option<&mut TimeProfile>,
+    name: &'static str,
+    start: Option<Instant>,
+) {
+    if let Some(profile) = profile {
+        profile.stages.push(StageTime {
+            name,
+            duration: start.map_or(Duration::ZERO, |s| s.elapsed()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_total_and_table() {
+        let ms = Duration::from_millis;
+        let profile = TimeProfile {
+            stages: vec![
+                StageTime {
+                    name: "threshold",
+                    duration: ms(2),
+                },
+                StageTime {
+                    name: "decode",
+                    duration: ms(3),
+                },
+            ],
+        };
+
+        assert_eq!(profile.get("threshold"), Some(ms(2)));
+        assert_eq!(profile.get("refine_edges"), None);
+        assert_eq!(profile.total(), ms(5));
+
+        let table = profile.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with(" 1 decode"));
+        assert!(lines[1].ends_with("3.000 ms     5.000 ms"));
+    }
+}
@@ -1,33 +1,47 @@
-use smallvec::SmallVec;
-
-use super::line_fitting::{fit_line, range_moments, LineFitPt};
+use super::line_fitting::{fit_line, range_moments, LineFitPts};
 use super::QuadThreshParams;
+use crate::detect::hooks::RejectReason;
+
+/// Scratch space for [`find_corners`], reused across clusters.
+///
+/// Both buffers are sized up front from the cluster length, so they grow at
+/// most once per cluster and stop growing once the largest cluster is seen.
+#[derive(Default)]
+pub(super) struct CornerBufs {
+    errors: Vec<f64>,
+    maxima: Vec<(usize, f64)>,
+}
 
 /// Find 4 corner indices that partition the sorted points into quad segments.
+///
+/// Fails with [`RejectReason::CriticalAngle`] if any combination was only
+/// rejected for a corner angle, and [`RejectReason::LineFitMse`] otherwise.
 pub(super) fn find_corners(
-    lfps: &[LineFitPt],
-    errors: &mut Vec<f64>,
-    maxima: &mut SmallVec<[(usize, f64); 10]>,
+    lfps: &LineFitPts,
+    bufs: &mut CornerBufs,
     params: &QuadThreshParams,
-) -> Option<[usize; 4]> {
+) -> Result<[usize; 4], RejectReason> {
+    let CornerBufs { errors, maxima } = bufs;
     let sz = lfps.len();
     let ksz = 20.min(sz / 12).max(1);
 
     // Compute line-fit error at each point
     errors.clear();
-    for i in 0..sz {
+    errors.reserve(sz);
+    errors.extend((0..sz).map(|i| {
         let i0 = (i + sz - ksz) % sz;
         let i1 = (i + ksz) % sz;
         let moments = range_moments(lfps, i0, i1);
-        let err = fit_line(&moments).map(|(_, mse)| mse).unwrap_or(0.0);
-        errors.push(err);
-    }
+        fit_line(&moments).map(|(_, mse)| mse).unwrap_or(0.0)
+    }));
 
     // Smooth errors with Gaussian-like filter
     smooth_errors(errors);
 
-    // Find local maxima (use >= on left to handle plateaus from synthetic images)
+    // Find local maxima (use >= on left to handle plateaus from synthetic images).
+    // Each is strictly above its successor, so at most every other point is one.
     maxima.clear();
+    maxima.reserve(sz / 2);
     for i in 0..sz {
         let prev = errors[(i + sz - 1) % sz];
         let next = errors[(i + 1) % sz];
@@ -37,7 +51,7 @@ pub(super) fn find_corners(
     }
 
     if maxima.len() < 4 {
-        return None;
+        return Err(RejectReason::NoCorners);
     }
 
     // Keep top max_nmaxima by error magnitude
@@ -51,6 +65,7 @@ pub(super) fn find_corners(
     let nm = maxima.len();
     let mut best_err = f64::MAX;
     let mut best_corners: Option<[usize; 4]> = None;
+    let mut failure = RejectReason::LineFitMse;
 
     for m0 in 0..nm {
         for m1 in (m0 + 1)..nm {
@@ -58,64 +73,67 @@ pub(super) fn find_corners(
                 for m3 in (m2 + 1)..nm {
                     let indices = [maxima[m0].0, maxima[m1].0, maxima[m2].0, maxima[m3].0];
 
-                    if let Some(err) = evaluate_quad_combination(lfps, &indices, sz, params) {
-                        if err < best_err {
+                    match evaluate_quad_combination(lfps, &indices, sz, params) {
+                        Ok(err) if err < best_err => {
                             best_err = err;
                             best_corners = Some(indices);
                         }
+                        Ok(_) => {}
+                        Err(reason) => {
+                            if reason == RejectReason::CriticalAngle {
+                                failure = reason;
+                            }
+                        }
                     }
                 }
             }
         }
     }
 
-    best_corners
+    best_corners.ok_or(failure)
 }
 
 /// Evaluate the total error for a 4-corner combination.
+///
+/// All four edges are fitted before any corner angle is checked, so a
+/// [`RejectReason::CriticalAngle`] failure means the edges themselves were
+/// straight enough.
 fn evaluate_quad_combination(
-    lfps: &[LineFitPt],
+    lfps: &LineFitPts,
     indices: &[usize; 4],
     _sz: usize,
     params: &QuadThreshParams,
-) -> Option<f64> {
+) -> Result<f64, RejectReason> {
     let mut total_err = 0.0;
-    let mut prev_line: Option<super::line_fitting::FittedLine> = None;
+    let mut lines = [None; 4];
 
-    for seg in 0..4 {
+    for (seg, slot) in lines.iter_mut().enumerate() {
         let i0 = indices[seg];
         let i1 = indices[(seg + 1) % 4];
         let moments = range_moments(lfps, i0, i1);
-        let (line, mse) = fit_line(&moments)?;
+        let (line, mse) = fit_line(&moments).ok_or(RejectReason::LineFitMse)?;
 
         if mse > params.max_line_fit_mse as f64 {
-            return None;
-        }
-
-        // Check angle between adjacent lines
-        if let Some(prev) = prev_line {
-            let dot = (prev.nx * line.nx + prev.ny * line.ny).abs();
-            if dot > params.cos_critical_rad as f64 {
-                return None;
-            }
+            return Err(RejectReason::LineFitMse);
         }
 
         total_err += mse;
-        prev_line = Some(line);
+        *slot = Some(line);
     }
 
-    // Check angle between last and first line
-    let first_moments = range_moments(lfps, indices[0], indices[1]);
-    let (first_line, _) = fit_line(&first_moments)?;
-    // prev_line is always Some here: the loop runs 4 iterations, each setting prev_line.
-    // Early returns (via `?`) would have exited the function before reaching this point.
-    let last_line = prev_line?;
-    let dot = (last_line.nx * first_line.nx + last_line.ny * first_line.ny).abs();
-    if dot > params.cos_critical_rad as f64 {
-        return None;
+    // Check the angle between each pair of adjacent lines
+    for seg in 0..4 {
+        let (Some(a), Some(b)) = (lines[seg], lines[(seg + 1) % 4]) else {
+            // COVERAGE: every slot was filled above
+            return Err(RejectReason::LineFitMse);
+        };
+        let dot = (a.nx * b.nx + a.ny * b.ny).abs();
+        if dot > params.cos_critical_rad as f64 {
+            return Err(RejectReason::CriticalAngle);
+        }
     }
 
-    Some(total_err)
+    Ok(total_err)
 }
 
 /// Smooth the error array using a simple low-pass filter.
@@ -167,12 +185,42 @@ mod tests {
                 slope: 0,
             })
             .collect();
-        let mut lfps = Vec::new();
-        build_line_fit_pts(&points, &mut lfps);
-        let mut errors = Vec::new();
-        let mut maxima = SmallVec::new();
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
+        let mut bufs = CornerBufs::default();
+        let params = QuadThreshParams::default();
+        assert!(find_corners(&lfps, &mut bufs, &params).is_err());
+    }
+
+    #[test]
+    fn find_corners_reuses_presized_buffers() {
+        let square: Vec<Pt> = (0..120)
+            .map(|i| {
+                let (side, t) = (i / 30, (i % 30) * 2);
+                let (x, y) = [(t, 0), (60, t), (60 - t, 60), (0, 60 - t)][side as usize];
+                Pt {
+                    x: 100 + x,
+                    y: 100 + y,
+                    gx: 0,
+                    gy: 0,
+                    slope: 0,
+                }
+            })
+            .collect();
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&square, 1.0, &mut lfps);
         let params = QuadThreshParams::default();
-        assert!(find_corners(&lfps, &mut errors, &mut maxima, &params).is_none());
+
+        let mut bufs = CornerBufs::default();
+        let corners = find_corners(&lfps, &mut bufs, &params);
+        assert!(bufs.errors.capacity() >= square.len());
+        assert!(bufs.maxima.capacity() >= square.len() / 2);
+
+        // A second cluster of the same size allocates nothing
+        let (errors, maxima) = (bufs.errors.as_ptr(), bufs.maxima.as_ptr());
+        assert_eq!(find_corners(&lfps, &mut bufs, &params), corners);
+        assert_eq!(bufs.errors.as_ptr(), errors);
+        assert_eq!(bufs.maxima.as_ptr(), maxima);
     }
 
     #[test]
@@ -1,35 +1,104 @@
-use super::line_fitting::{fit_line, range_moments, FittedLine, LineFitPt};
+use super::line_fitting::{fit_line, range_len, range_moments, FittedLine, LineFitPts};
 use super::QuadThreshParams;
 use crate::detect::geometry::Vec2;
 
-/// Compute quad corner positions from line intersections.
+/// Floor on an edge's residual variance and spread, in pixels²: the
+/// variance of rounding to the half-pixel grid that edge points are traced
+/// on. Keeps the covariance of perfectly straight edges nonzero.
+const MIN_EDGE_VARIANCE: f64 = 0.25 / 12.0;
+
+/// Fit statistics of one quad edge, for [`corner_covariance`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EdgeFit {
+    /// The fitted line, through the centroid of its points.
+    pub line: FittedLine,
+    /// Number of points fitted.
+    pub count: f64,
+    /// Variance of the points along the line.
+    pub spread: f64,
+    /// Mean squared distance of the points from the line.
+    pub mse: f64,
+}
+
+impl EdgeFit {
+    /// Variance of the line's offset along its normal at `p`: the centroid's
+    /// offset is known to `mse / count`, and the angle's uncertainty grows
+    /// with the distance from it.
+    fn offset_variance(&self, p: (f64, f64)) -> f64 {
+        let l = &self.line;
+        let t = (p.0 - l.px) * -l.ny + (p.1 - l.py) * l.nx;
+        let spread = self.spread.max(MIN_EDGE_VARIANCE);
+        (self.mse + MIN_EDGE_VARIANCE) / self.count.max(1.0) * (1.0 + t * t / spread)
+    }
+}
+
+/// Covariance `[xx, xy, yy]` of `corner`, the intersection of edges `a` and
+/// `b`, from the uncertainty of each edge's offset along its normal. The
+/// edges must not be parallel.
+pub(crate) fn corner_covariance(a: &EdgeFit, b: &EdgeFit, corner: (f64, f64)) -> [f64; 3] {
+    let (va, vb) = (a.offset_variance(corner), b.offset_variance(corner));
+    let (na, nb) = (&a.line, &b.line);
+    // The corner solves [na; nb] c = offsets; propagate through the inverse
+    let det = na.nx * nb.ny - na.ny * nb.nx;
+    let d2 = det * det;
+    [
+        (va * nb.ny * nb.ny + vb * na.ny * na.ny) / d2,
+        -(va * nb.nx * nb.ny + vb * na.nx * na.ny) / d2,
+        (va * nb.nx * nb.nx + vb * na.nx * na.nx) / d2,
+    ]
+}
+
+/// Transform covariance `c` for coordinates scaled by `(sx, sy)`.
+pub(crate) fn scale_covariance(c: &mut [f64; 3], sx: f64, sy: f64) {
+    c[0] *= sx * sx;
+    c[1] *= sx * sy;
+    c[2] *= sy * sy;
+}
+
+/// Compute quad corner positions from line intersections, with the largest
+/// edge fit MSE and each corner's covariance.
 pub(super) fn compute_quad_corners(
-    lfps: &[LineFitPt],
+    lfps: &LineFitPts,
     indices: &[usize; 4],
-    _sz: usize,
-) -> Option<[Vec2; 4]> {
-    let mut lines = [FittedLine {
-        px: 0.0,
-        py: 0.0,
-        nx: 0.0,
-        ny: 0.0,
+    sz: usize,
+) -> Option<([Vec2; 4], f64, [[f64; 3]; 4])> {
+    let mut edges = [EdgeFit {
+        line: FittedLine {
+            px: 0.0,
+            py: 0.0,
+            nx: 0.0,
+            ny: 0.0,
+        },
+        count: 0.0,
+        spread: 0.0,
+        mse: 0.0,
     }; 4];
+    let mut max_mse = 0.0f64;
     for seg in 0..4 {
         let i0 = indices[seg];
         let i1 = indices[(seg + 1) % 4];
         let moments = range_moments(lfps, i0, i1);
-        let (line, _) = fit_line(&moments)?;
-        lines[seg] = line;
+        let (line, mse) = fit_line(&moments)?;
+        edges[seg] = EdgeFit {
+            line,
+            count: range_len(sz, i0, i1) as f64,
+            spread: moments.spread_along(&line),
+            mse,
+        };
+        max_mse = max_mse.max(mse);
     }
 
     let mut corners = [Vec2::new(0.0, 0.0); 4];
+    let mut covariance = [[0.0; 3]; 4];
     for i in 0..4 {
         let j = (i + 1) % 4;
-        let (cx, cy) = intersect_lines(&lines[i], &lines[j])?;
-        corners[i] = Vec2::new(cx, cy);
+        let (cx, cy) = intersect_lines(&edges[i].line, &edges[j].line)?;
+        corners[i] = Vec2::new(lfps.origin.0 + cx / lfps.x_scale, lfps.origin.1 + cy);
+        covariance[i] = corner_covariance(&edges[i], &edges[j], (cx, cy));
+        scale_covariance(&mut covariance[i], 1.0 / lfps.x_scale, 1.0);
     }
 
-    Some(corners)
+    Some((corners, max_mse, covariance))
 }
 
 /// Compute intersection of two fitted lines.
@@ -55,7 +124,7 @@ pub(super) fn intersect_lines(l0: &FittedLine, l1: &FittedLine) -> Option<(f64,
 }
 
 /// Validate that the quad has correct geometry.
-pub(super) fn validate_quad(corners: &[Vec2; 4], _params: &QuadThreshParams) -> Option<()> {
+pub(crate) fn validate_quad(corners: &[Vec2; 4], _params: &QuadThreshParams) -> Option<()> {
     // Check area (> 0 for valid winding)
     let area = quad_area(corners);
     if area < 0.0 {
@@ -88,6 +157,7 @@ pub(super) fn quad_area(corners: &[Vec2; 4]) -> f64 {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -127,6 +197,27 @@ mod tests {
         assert!(intersect_lines(&l0, &l1).is_none());
     }
 
+    #[test]
+    fn corner_covariance_of_perpendicular_edges() {
+        let edge = |px, py, nx, ny, mse| EdgeFit {
+            line: FittedLine { px, py, nx, ny },
+            count: 20.0,
+            spread: 400.0 / 12.0,
+            mse,
+        };
+        // A noisy horizontal edge pins y, a clean vertical one pins x
+        let horizontal = edge(10.0, 0.0, 0.0, 1.0, 1.0);
+        let vertical = edge(0.0, 10.0, 1.0, 0.0, 0.0);
+        let [xx, xy, yy] = corner_covariance(&horizontal, &vertical, (0.0, 0.0));
+        assert!(xy.abs() < 1e-12);
+        assert!((yy - horizontal.offset_variance((0.0, 0.0))).abs() < 1e-12);
+        assert!((xx - vertical.offset_variance((0.0, 0.0))).abs() < 1e-12);
+        assert!(yy > 10.0 * xx);
+
+        // Corners further from an edge's centroid are less certain
+        assert!(horizontal.offset_variance((30.0, 0.0)) > yy);
+    }
+
     fn v(corners: [[f64; 2]; 4]) -> [Vec2; 4] {
         corners.map(Vec2::from)
     }
@@ -1,8 +1,106 @@
 use super::super::cluster::Pt;
 
-/// Cumulative weighted moments for efficient range line fitting.
-#[derive(Debug, Clone, Copy, Default)]
+/// Cumulative weighted moments at one point, relative to [`LineFitPts::origin`].
+///
+/// Stored exactly in fixed point: offsets in half pixels and weights in
+/// units of 1/[`WEIGHT_ONE`]. A range's moments are the difference of two
+/// prefix sums, which grow with the square of the cluster extent; integer
+/// sums make that difference exact however long the edge.
+///
+/// With offsets below 2^16 half pixels, weights below 2^13 and at most
+/// [`max_cluster_points`](super::max_cluster_points) (below 2^18) points, the
+/// second moments stay below 2^63. The first moments and weights can
+/// exceed 32 bits too, so each point takes 48 bytes, as six `f64` would:
+/// the fixed point buys exactness, not less memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub(super) struct LineFitPt {
+    pub mx: i64,
+    pub my: i64,
+    pub mxx: i64,
+    pub mxy: i64,
+    pub myy: i64,
+    pub w: i64,
+}
+
+impl LineFitPt {
+    #[inline]
+    fn sub(self, o: Self) -> Self {
+        Self {
+            mx: self.mx - o.mx,
+            my: self.my - o.my,
+            mxx: self.mxx - o.mxx,
+            mxy: self.mxy - o.mxy,
+            myy: self.myy - o.myy,
+            w: self.w - o.w,
+        }
+    }
+
+    #[inline]
+    fn add(self, o: Self) -> Self {
+        Self {
+            mx: self.mx + o.mx,
+            my: self.my + o.my,
+            mxx: self.mxx + o.mxx,
+            mxy: self.mxy + o.mxy,
+            myy: self.myy + o.myy,
+            w: self.w + o.w,
+        }
+    }
+}
+
+/// Cumulative moments for a cluster, relative to its first point.
+///
+/// Keeping coordinates local bounds their magnitude by the cluster extent,
+/// which keeps the fixed-point sums in range wherever the cluster is in the
+/// image.
+#[derive(Debug, Clone)]
+pub(super) struct LineFitPts {
+    /// Pixel position that moments are measured from.
+    pub origin: (f64, f64),
+    /// Factor applied to x offsets from `origin`, so that moments are in a
+    /// frame with square pixels.
+    pub x_scale: f64,
+    pub pts: Vec<LineFitPt>,
+}
+
+impl Default for LineFitPts {
+    fn default() -> Self {
+        Self {
+            origin: (0.0, 0.0),
+            x_scale: 1.0,
+            pts: Vec::new(),
+        }
+    }
+}
+
+impl LineFitPts {
+    pub fn len(&self) -> usize {
+        self.pts.len()
+    }
+
+    /// Convert fixed-point moments to pixels and weights, applying `x_scale`.
+    #[inline]
+    fn moments(&self, p: LineFitPt) -> LineMoments {
+        // Half pixels to pixels and 1/WEIGHT_ONE to weights; powers of two,
+        // so only the conversion to f64 rounds
+        const W: f64 = 1.0 / WEIGHT_ONE as f64;
+        const W1: f64 = W * 0.5;
+        const W2: f64 = W * 0.25;
+        let s = self.x_scale;
+        LineMoments {
+            mx: p.mx as f64 * (W1 * s),
+            my: p.my as f64 * W1,
+            mxx: p.mxx as f64 * (W2 * s * s),
+            mxy: p.mxy as f64 * (W2 * s),
+            myy: p.myy as f64 * W2,
+            w: p.w as f64 * W,
+        }
+    }
+}
+
+/// Weighted moments of a point range, relative to [`LineFitPts::origin`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct LineMoments {
     pub mx: f64,
     pub my: f64,
     pub mxx: f64,
@@ -11,93 +109,111 @@ pub(super) struct LineFitPt {
     pub w: f64,
 }
 
+impl LineMoments {
+    /// Weighted variance of the points along `line`, which sets how well
+    /// its angle is pinned down.
+    pub fn spread_along(&self, line: &FittedLine) -> f64 {
+        let ex = self.mx / self.w;
+        let ey = self.my / self.w;
+        let cxx = self.mxx / self.w - ex * ex;
+        let cxy = self.mxy / self.w - ex * ey;
+        let cyy = self.myy / self.w - ey * ey;
+        let (dx, dy) = (-line.ny, line.nx);
+        (cxx * dx * dx + 2.0 * cxy * dx * dy + cyy * dy * dy).max(0.0)
+    }
+}
+
 /// A fitted line parameterized by a point (px, py) and unit normal (nx, ny).
 #[derive(Debug, Clone, Copy)]
-pub(super) struct FittedLine {
+pub(crate) struct FittedLine {
     pub px: f64,
     pub py: f64,
     pub nx: f64,
     pub ny: f64,
 }
 
-/// Gradient magnitude weight lookup, indexed by `(gx != 0) << 1 | (gy != 0)`.
-/// Since `gx` and `gy` are always in `{-255, 0, 255}`, there are only 4 distinct values.
+/// Fixed-point scale of [`grad_weight`]: weights are stored in units of
+/// 1/16.
+pub(super) const WEIGHT_ONE: i64 = 16;
+
+/// Gradient magnitude weight `sqrt(gx² + gy²) + 1` in units of
+/// 1/[`WEIGHT_ONE`], indexed by `(gx != 0) << 1 | (gy != 0)`.
+/// Since `gx` and `gy` are always in `{-255, 0, 255}`, there are only 4
+/// distinct values. Only the diagonal one, sqrt(130050) + 1 ≈ 361.624, is
+/// rounded, by under 3e-6 relative.
 #[inline]
-pub(super) fn grad_weight(gx: i16, gy: i16) -> f64 {
-    // sqrt(255² + 255²) + 1 = sqrt(130050) + 1 ≈ 361.624...
-    const DIAG: f64 = 361.62445840513925;
-    const TABLE: [f64; 4] = [1.0, 256.0, 256.0, DIAG];
+pub(super) fn grad_weight(gx: i16, gy: i16) -> i64 {
+    const TABLE: [i64; 4] = [16, 4096, 4096, 5786];
     TABLE[((gx != 0) as usize) << 1 | (gy != 0) as usize]
 }
 
 /// Build cumulative weighted moments for line fitting into a reusable buffer.
-pub(super) fn build_line_fit_pts(points: &[Pt], lfps: &mut Vec<LineFitPt>) {
-    lfps.clear();
-    lfps.reserve(points.len().saturating_sub(lfps.capacity()));
-    let mut cum = LineFitPt::default();
+///
+/// X offsets are multiplied by `x_scale`, the pixel aspect ratio, when the
+/// moments are read back.
+pub(super) fn build_line_fit_pts(points: &[Pt], x_scale: f64, lfps: &mut LineFitPts) {
+    lfps.pts.clear();
+    lfps.x_scale = x_scale;
+    lfps.pts
+        .reserve(points.len().saturating_sub(lfps.pts.capacity()));
+    let Some(first) = points.first() else {
+        lfps.origin = (0.0, 0.0);
+        return;
+    };
+    lfps.origin = (first.x as f64 * 0.5 + 0.5, first.y as f64 * 0.5 + 0.5);
+
+    let mut sum = LineFitPt::default();
 
     for p in points {
-        let x = p.x as f64 * 0.5 + 0.5;
-        let y = p.y as f64 * 0.5 + 0.5;
+        let x = p.x as i64 - first.x as i64;
+        let y = p.y as i64 - first.y as i64;
         let w = grad_weight(p.gx, p.gy);
 
-        cum.mx += w * x;
-        cum.my += w * y;
-        cum.mxx += w * x * x;
-        cum.mxy += w * x * y;
-        cum.myy += w * y * y;
-        cum.w += w;
-
-        lfps.push(cum);
+        sum.mx += w * x;
+        sum.my += w * y;
+        sum.mxx += w * x * x;
+        sum.mxy += w * x * y;
+        sum.myy += w * y * y;
+        sum.w += w;
+        lfps.pts.push(sum);
     }
 }
 
 /// Compute line fit moments for a range [i0, i1] (inclusive, wrapping).
-pub(super) fn range_moments(lfps: &[LineFitPt], i0: usize, i1: usize) -> LineFitPt {
-    let sz = lfps.len();
-    let last = &lfps[sz - 1];
+///
+/// Moments are relative to [`LineFitPts::origin`].
+pub(super) fn range_moments(lfps: &LineFitPts, i0: usize, i1: usize) -> LineMoments {
+    let pts = &lfps.pts;
+    let sz = pts.len();
+    let end = pts[i1];
 
-    if i0 <= i1 {
-        let end = &lfps[i1];
+    let sum = if i0 <= i1 {
         if i0 == 0 {
-            *end
+            end
         } else {
-            let start = &lfps[i0 - 1];
-            LineFitPt {
-                mx: end.mx - start.mx,
-                my: end.my - start.my,
-                mxx: end.mxx - start.mxx,
-                mxy: end.mxy - start.mxy,
-                myy: end.myy - start.myy,
-                w: end.w - start.w,
-            }
+            end.sub(pts[i0 - 1])
         }
     } else {
         // Wrapping: [i0, sz) + [0, i1] — i0 > i1 implies i0 >= 1
         debug_assert!(i0 >= 1);
-        let start = &lfps[i0 - 1];
-        let tail = LineFitPt {
-            mx: last.mx - start.mx,
-            my: last.my - start.my,
-            mxx: last.mxx - start.mxx,
-            mxy: last.mxy - start.mxy,
-            myy: last.myy - start.myy,
-            w: last.w - start.w,
-        };
-        let head = &lfps[i1];
-        LineFitPt {
-            mx: tail.mx + head.mx,
-            my: tail.my + head.my,
-            mxx: tail.mxx + head.mxx,
-            mxy: tail.mxy + head.mxy,
-            myy: tail.myy + head.myy,
-            w: tail.w + head.w,
-        }
+        pts[sz - 1].sub(pts[i0 - 1]).add(end)
+    };
+    lfps.moments(sum)
+}
+
+/// Number of points in the range [i0, i1] (inclusive, wrapping) of `sz`.
+pub(super) fn range_len(sz: usize, i0: usize, i1: usize) -> usize {
+    if i0 <= i1 {
+        i1 - i0 + 1
+    } else {
+        sz - i0 + i1 + 1
     }
 }
 
-/// Fit a line from cumulative moments and return (line, mse).
-pub(super) fn fit_line(moments: &LineFitPt) -> Option<(FittedLine, f64)> {
+/// Fit a line from range moments and return (line, mse).
+///
+/// The fitted line point is in the same frame as the moments.
+pub(super) fn fit_line(moments: &LineMoments) -> Option<(FittedLine, f64)> {
     if moments.w < 1e-10 {
         return None;
     }
@@ -151,9 +267,14 @@ pub(super) fn fit_line(moments: &LineFitPt) -> Option<(FittedLine, f64)> {
 mod tests {
     use super::*;
 
+    /// The exact weight [`grad_weight`] approximates.
+    fn grad_weight_f64(gx: i16, gy: i16) -> f64 {
+        ((gx as f64).powi(2) + (gy as f64).powi(2)).sqrt() + 1.0
+    }
+
     #[test]
     fn fit_line_collinear_points() {
-        let mut cum = LineFitPt::default();
+        let mut cum = LineMoments::default();
         for i in 0..10 {
             let x = i as f64;
             let y = 2.0 * x;
@@ -173,13 +294,13 @@ mod tests {
 
     #[test]
     fn fit_line_zero_weight() {
-        let moments = LineFitPt::default();
+        let moments = LineMoments::default();
         assert!(fit_line(&moments).is_none());
     }
 
     #[test]
     fn fit_line_coincident_points() {
-        let moments = LineFitPt {
+        let moments = LineMoments {
             mx: 10.0,
             my: 20.0,
             mxx: 100.0,
@@ -192,7 +313,7 @@ mod tests {
 
     #[test]
     fn fit_line_axis_aligned() {
-        let mut cum = LineFitPt::default();
+        let mut cum = LineMoments::default();
         for i in 0..10 {
             let x = i as f64;
             let y = 5.0;
@@ -226,10 +347,11 @@ mod tests {
                 slope: 0,
             },
         ];
-        let mut lfps = Vec::new();
-        build_line_fit_pts(&points, &mut lfps);
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
         let m = range_moments(&lfps, 0, 1);
-        assert!((m.w - lfps[1].w).abs() < 1e-10);
+        assert_eq!(lfps.pts[1].w, 2 * 4096);
+        assert_eq!(m.w, 512.0);
     }
 
     #[test]
@@ -257,12 +379,12 @@ mod tests {
                 slope: 0,
             },
         ];
-        let mut lfps = Vec::new();
-        build_line_fit_pts(&points, &mut lfps);
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
         let m = range_moments(&lfps, 2, 0);
-        let w2 = lfps[2].w - lfps[1].w;
-        let w0 = lfps[0].w;
-        assert!((m.w - (w2 + w0)).abs() < 1e-10);
+        assert_eq!(m.w, 512.0);
+        // Points 2 and 0, at x = 2 and 0 px from the origin
+        assert_eq!(m.mx, 512.0);
     }
 
     #[test]
@@ -290,20 +412,190 @@ mod tests {
                 slope: 0,
             },
         ];
-        let mut lfps = Vec::new();
-        build_line_fit_pts(&points, &mut lfps);
-        let total = &lfps[2];
-        let py = total.my / total.w;
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
+        let total = range_moments(&lfps, 0, 2);
+        let py = lfps.origin.1 + total.my / total.w;
         assert!((py - 10.5).abs() < 1e-10);
     }
 
+    #[test]
+    fn moments_are_local() {
+        // A long horizontal edge far from the image origin: absolute f32
+        // moments would lose the sub-pixel offset entirely.
+        let points: Vec<Pt> = (0..4000u16)
+            .map(|i| Pt {
+                x: 8000 + (i % 400) * 2,
+                y: 12001,
+                gx: 0,
+                gy: 255,
+                slope: 0,
+            })
+            .collect();
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
+        assert_eq!(lfps.origin, (4000.5, 6001.0));
+
+        let m = range_moments(&lfps, 100, 3900);
+        let (line, mse) = fit_line(&m).unwrap();
+        assert!(mse < 1e-6, "straight edge should fit exactly: {mse}");
+        assert!(line.ny.abs() > 0.999_999);
+        assert!((lfps.origin.1 + line.py - 6001.0).abs() < 1e-6);
+        assert!((m.w - 3801.0 * 256.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn long_tilted_edges_match_two_pass_mse() {
+        // The boundary of a 1200 px square tilted by 0.3 rad, away from the
+        // image origin, as a staircase of half-pixel boundary points
+        let (side, angle) = (1200.0f64, 0.3f64);
+        let (c, s) = (angle.cos(), angle.sin());
+        let corner = |i: usize| {
+            let (u, v) = ([0.0, side, side, 0.0][i], [0.0, 0.0, side, side][i]);
+            (3000.0 + c * u - s * v, 2000.0 + s * u + c * v)
+        };
+        let mut points = Vec::new();
+        for edge in 0..4 {
+            let (a, b) = (corner(edge), corner((edge + 1) % 4));
+            let (gx, gy) = ([255, 0, -255, 0][edge], [0, 255, 0, -255][edge]);
+            for k in 0..side as usize {
+                let t = k as f64 / side;
+                points.push(Pt {
+                    x: (2.0 * (a.0 + t * (b.0 - a.0))).round() as u16,
+                    y: (2.0 * (a.1 + t * (b.1 - a.1))).round() as u16,
+                    gx,
+                    gy,
+                    slope: 0,
+                });
+            }
+        }
+        let mut lfps = LineFitPts::default();
+        build_line_fit_pts(&points, 1.0, &mut lfps);
+
+        let two_pass = |range: &[usize]| {
+            let pts: Vec<(f64, f64, f64)> = range
+                .iter()
+                .map(|&i| {
+                    let p = &points[i];
+                    (
+                        p.x as f64 * 0.5,
+                        p.y as f64 * 0.5,
+                        grad_weight_f64(p.gx, p.gy),
+                    )
+                })
+                .collect();
+            let w: f64 = pts.iter().map(|p| p.2).sum();
+            let ex = pts.iter().map(|p| p.2 * p.0).sum::<f64>() / w;
+            let ey = pts.iter().map(|p| p.2 * p.1).sum::<f64>() / w;
+            let cov = |f: &dyn Fn(f64, f64) -> f64| {
+                pts.iter().map(|p| p.2 * f(p.0 - ex, p.1 - ey)).sum::<f64>() / w
+            };
+            let (cxx, cxy, cyy) = (cov(&|x, _| x * x), cov(&|x, y| x * y), cov(&|_, y| y * y));
+            0.5 * (cxx + cyy - ((cxx - cyy).powi(2) + 4.0 * cxy * cxy).sqrt())
+        };
+
+        let n = points.len();
+        for (i0, i1) in [(1300, 2300), (100, 1150), (4700, 200)] {
+            let range: Vec<usize> = if i0 <= i1 {
+                (i0..=i1).collect()
+            } else {
+                (i0..n).chain(0..=i1).collect()
+            };
+            let expected = two_pass(&range);
+            let (_, mse) = fit_line(&range_moments(&lfps, i0, i1)).unwrap();
+            assert!(
+                (mse - expected).abs() < 1e-6 * expected.max(1.0),
+                "window {i0}..={i1}: {mse} vs {expected}"
+            );
+        }
+        // A straight staircase stays well under a pixel²
+        let (_, mse) = fit_line(&range_moments(&lfps, 1300, 2300)).unwrap();
+        assert!(mse < 0.1, "{mse}");
+    }
+
+    #[test]
+    fn fixed_point_moments_match_f64() {
+        // Scattered points with all four weights, some far apart
+        let mut state = 7u32;
+        let mut next = |n: u32| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) % n
+        };
+        let grads = [0i16, 255, -255];
+        let points: Vec<Pt> = (0..3000)
+            .map(|_| Pt {
+                x: 20_000 + next(3000) as u16,
+                y: 9_000 + next(3000) as u16,
+                gx: grads[next(3) as usize],
+                gy: grads[next(3) as usize],
+                slope: 0,
+            })
+            .collect();
+
+        for x_scale in [1.0, 0.75] {
+            let mut lfps = LineFitPts::default();
+            build_line_fit_pts(&points, x_scale, &mut lfps);
+
+            // Direct f64 sums over the range, relative to the same origin
+            let f64_moments = |range: &[usize]| {
+                let mut m = LineMoments::default();
+                for &i in range {
+                    let p = &points[i];
+                    let x = (p.x as f64 * 0.5 + 0.5 - lfps.origin.0) * x_scale;
+                    let y = p.y as f64 * 0.5 + 0.5 - lfps.origin.1;
+                    let w = grad_weight_f64(p.gx, p.gy);
+                    m.mx += w * x;
+                    m.my += w * y;
+                    m.mxx += w * x * x;
+                    m.mxy += w * x * y;
+                    m.myy += w * y * y;
+                    m.w += w;
+                }
+                m
+            };
+
+            let n = points.len();
+            for (i0, i1) in [(0, n - 1), (17, 2400), (2900, 40), (1500, 1510)] {
+                let range: Vec<usize> = if i0 <= i1 {
+                    (i0..=i1).collect()
+                } else {
+                    (i0..n).chain(0..=i1).collect()
+                };
+                let fixed = range_moments(&lfps, i0, i1);
+                let expected = f64_moments(&range);
+                let close = |a: f64, b: f64, scale: f64| (a - b).abs() <= 1e-5 * scale;
+                let w = expected.w;
+                let r = 1500.0 * x_scale.max(1.0);
+                assert!(close(fixed.w, w, w), "w {i0}..={i1}");
+                for (a, b) in [(fixed.mx, expected.mx), (fixed.my, expected.my)] {
+                    assert!(close(a, b, w * r), "first moment {i0}..={i1}: {a} vs {b}");
+                }
+                for (a, b) in [
+                    (fixed.mxx, expected.mxx),
+                    (fixed.mxy, expected.mxy),
+                    (fixed.myy, expected.myy),
+                ] {
+                    assert!(
+                        close(a, b, w * r * r),
+                        "second moment {i0}..={i1}: {a} vs {b}"
+                    );
+                }
+                let (fixed_line, fixed_mse) = fit_line(&fixed).unwrap();
+                let (line, mse) = fit_line(&expected).unwrap();
+                assert!((fixed_mse - mse).abs() < 1e-5 * mse);
+                assert!((fixed_line.px - line.px).abs() < 1e-4);
+                assert!((fixed_line.py - line.py).abs() < 1e-4);
+            }
+        }
+    }
+
     #[test]
     fn grad_weight_matches_sqrt_for_all_valid_inputs() {
         for &gx in &[-255i16, 0, 255] {
             for &gy in &[-255i16, 0, 255] {
                 let expected = ((gx as f64).powi(2) + (gy as f64).powi(2)).sqrt() + 1.0;
-                let actual = grad_weight(gx, gy);
-                assert!((actual - expected).abs() < 1e-10);
+                let actual = grad_weight(gx, gy) as f64 / WEIGHT_ONE as f64;
+                assert!((actual - expected).abs() < 3e-6 * expected);
             }
         }
     }
@@ -2,14 +2,18 @@ mod corners;
 mod geometry;
 mod line_fitting;
 
-use corners::find_corners;
-use geometry::{compute_quad_corners, validate_quad};
+use corners::{find_corners, CornerBufs};
+use geometry::compute_quad_corners;
+pub(crate) use geometry::{corner_covariance, scale_covariance, validate_quad, EdgeFit};
 use line_fitting::build_line_fit_pts;
-use smallvec::SmallVec;
+pub(crate) use line_fitting::FittedLine;
 
 use super::cluster::{Cluster, Pt};
 use super::geometry::Vec2;
+use super::hooks::RejectReason;
 use super::par::Par;
+use super::preprocess::Decimation;
+use super::threshold::DEFAULT_TILE_SIZE;
 
 /// A detected quadrilateral with four corners in pixel coordinates.
 #[derive(Debug, Clone)]
@@ -18,17 +22,52 @@ pub struct Quad {
     pub corners: [Vec2; 4],
     /// Whether the black border is inside the white border (reversed).
     pub reversed_border: bool,
+    /// Largest mean squared error of the four edge line fits, in pixels².
+    /// Curved or motion-blurred edges fit worse.
+    pub line_fit_mse: f32,
+    /// Largest mean squared distance of the refined edge points from their
+    /// fitted lines, in pixels², or `None` if edges were not refined.
+    pub refine_residual: Option<f32>,
+    /// Covariance of each corner as `[xx, xy, yy]`, in pixels², from the
+    /// residuals of the two edge fits meeting there (the refined fits when
+    /// edges were refined).
+    pub corner_covariance: [[f64; 3]; 4],
 }
 
 /// Quad detection parameters.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct QuadThreshParams {
     pub min_cluster_pixels: i32,
     pub max_nmaxima: i32,
     pub cos_critical_rad: f32,
     pub max_line_fit_mse: f32,
     pub min_white_black_diff: i32,
+    pub min_edge_gradient: u8,
     pub deglitch: bool,
+    /// Side of the adaptive-threshold tiles, in working-resolution pixels.
+    pub tile_size: u32,
+    /// Pixel width divided by pixel height; x coordinates are multiplied by
+    /// this before line fitting.
+    pub pixel_aspect_ratio: f64,
+    /// Shortest bounding-box diagonal a cluster may have, in working pixels
+    /// (default: 0, no limit). A tag's border edges are no longer than the
+    /// diagonal of its bounding box, so a cluster shorter than the smallest
+    /// family's border width has fewer pixels than cells along some edge
+    /// and cannot decode. The detector sets this from its families; such
+    /// clusters are rejected before the sort and line fits.
+    pub min_tag_width: f64,
+}
+
+impl QuadThreshParams {
+    /// Set [`Self::min_tag_width`] for tags at least `width` working pixels
+    /// across. With non-square pixels a tag spans `width *
+    /// pixel_aspect_ratio` pixels along the other axis, so the bound is the
+    /// narrower of the two.
+    pub(crate) fn limit_tag_width(&mut self, width: f64) {
+        self.min_tag_width = width.min(width * self.pixel_aspect_ratio);
+    }
 }
 
 impl Default for QuadThreshParams {
@@ -39,7 +78,113 @@ impl Default for QuadThreshParams {
             cos_critical_rad: (10.0f32.to_radians()).cos(),
             max_line_fit_mse: 10.0,
             min_white_black_diff: 5,
+            min_edge_gradient: 0,
+            deglitch: false,
+            tile_size: DEFAULT_TILE_SIZE,
+            pixel_aspect_ratio: 1.0,
+            min_tag_width: 0.0,
+        }
+    }
+}
+
+/// User-facing quad detection parameters with explicit units.
+///
+/// Unlike [`QuadThreshParams`], which is expressed at the decimated working
+/// resolution, sizes here are in full-resolution pixels and angles in degrees,
+/// so the same values behave consistently for any `quad_decimate`. Converted
+/// with [`QuadParams::thresh_params`] at detect time.
+///
+/// The size limits and the threshold tile size default to `None`, which keeps
+/// the reference detector's working-resolution values (5 pixels, 10 pixels²
+/// and 4-pixel tiles) at every decimation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct QuadParams {
+    /// Minimum cluster size, in full-resolution boundary pixels (default:
+    /// `None`, 5 working-resolution pixels).
+    pub min_cluster_pixels: Option<u32>,
+    /// Maximum number of corner candidates considered per cluster (default: 10).
+    pub max_nmaxima: u32,
+    /// Reject quads with a corner angle within this many degrees of 0° or
+    /// 180° (default: 10.0).
+    pub critical_angle_deg: f32,
+    /// Maximum mean-squared line-fit error, in full-resolution pixels²
+    /// (default: `None`, 10 working-resolution pixels²). Matches the units of
+    /// [`Detection::line_fit_mse`](crate::Detection::line_fit_mse).
+    pub max_line_fit_mse: Option<f32>,
+    /// Minimum local white-black intensity difference (default: 5). Tiles
+    /// with less contrast than this are left unclassified.
+    pub min_white_black_diff: u8,
+    /// Minimum intensity step across a boundary for its pixels to enter a
+    /// cluster (default: 0, disabled). Speeds up noisy images at some cost in
+    /// recall on blurred or low-contrast tags.
+    pub min_edge_gradient: u8,
+    /// Enable deglitching of the thresholded image (default: false): a
+    /// morphological close and open that removes isolated pixels from the
+    /// binarized image, at some cost in speed.
+    pub deglitch: bool,
+    /// Side of the square tiles whose local extrema set the adaptive
+    /// threshold, in full-resolution pixels (default: `None`, 4
+    /// working-resolution pixels). Larger tiles tolerate larger uniform
+    /// regions inside a tag but follow uneven lighting less closely.
+    pub tile_size: Option<u32>,
+    /// Width of a pixel divided by its height (default: 1.0). Anamorphic
+    /// optics and unevenly binned sensors have non-square pixels, which skew
+    /// a tag's image; quad edges are fitted and their corner angles checked
+    /// as if pixels were square, while corners are still reported in pixel
+    /// coordinates. Must be positive.
+    pub pixel_aspect_ratio: f32,
+}
+
+impl Default for QuadParams {
+    fn default() -> Self {
+        Self {
+            min_cluster_pixels: None,
+            max_nmaxima: 10,
+            critical_angle_deg: 10.0,
+            max_line_fit_mse: None,
+            min_white_black_diff: 5,
+            min_edge_gradient: 0,
             deglitch: false,
+            tile_size: None,
+            pixel_aspect_ratio: 1.0,
+        }
+    }
+}
+
+impl QuadParams {
+    /// Convert to working-resolution parameters for the given decimation.
+    ///
+    /// Cluster sizes count boundary pixels, so they scale linearly with the
+    /// decimation factor; the result is rounded up and never below 1. Line-fit
+    /// errors are squared distances and scale with its square. The tile size
+    /// scales linearly, rounded to the nearest pixel and never below 1. Unset
+    /// values keep the [`QuadThreshParams`] defaults.
+    pub fn thresh_params(&self, quad_decimate: f32) -> QuadThreshParams {
+        let scale = Decimation::from_factor(quad_decimate).scale();
+        let defaults = QuadThreshParams::default();
+        QuadThreshParams {
+            min_cluster_pixels: self
+                .min_cluster_pixels
+                .map_or(defaults.min_cluster_pixels, |n| {
+                    (n as f64 / scale).ceil().max(1.0) as i32
+                }),
+            max_nmaxima: self.max_nmaxima as i32,
+            cos_critical_rad: self.critical_angle_deg.to_radians().cos(),
+            max_line_fit_mse: self
+                .max_line_fit_mse
+                .map_or(defaults.max_line_fit_mse, |mse| {
+                    (mse as f64 / (scale * scale)) as f32
+                }),
+            min_white_black_diff: self.min_white_black_diff as i32,
+            min_edge_gradient: self.min_edge_gradient,
+            deglitch: self.deglitch,
+            tile_size: self.tile_size.map_or(defaults.tile_size, |n| {
+                (n as f64 / scale).round().max(1.0) as u32
+            }),
+            pixel_aspect_ratio: self.pixel_aspect_ratio as f64,
+            min_tag_width: defaults.min_tag_width,
         }
     }
 }
@@ -47,9 +192,8 @@ impl Default for QuadThreshParams {
 /// Reusable scratch buffers for quad fitting, avoiding per-cluster allocation.
 #[derive(Default)]
 pub struct QuadFitBufs {
-    lfps: Vec<line_fitting::LineFitPt>,
-    errors: Vec<f64>,
-    maxima: SmallVec<[(usize, f64); 10]>,
+    lfps: line_fitting::LineFitPts,
+    corners: CornerBufs,
 }
 
 impl QuadFitBufs {
@@ -68,24 +212,132 @@ pub fn fit_quads(
     reversed_border: bool,
     out: &mut Vec<Quad>,
 ) {
-    // C reference: 2*(2*w + 2*h) = 4*(w+h). Each edge point is typically added
-    // twice (two unique neighbors), so the limit is 2× the geometric perimeter.
-    // See apriltag_quad_thresh.c:1090.
-    let max_perimeter = 4 * (image_width + image_height) as usize;
+    fit_quads_reporting(
+        clusters,
+        image_width,
+        image_height,
+        params,
+        normal_border,
+        reversed_border,
+        None,
+        out,
+    );
+}
+
+/// Like [`fit_quads`], but also pass each rejected cluster to `on_reject`,
+/// as its fitted quad if it got that far and as its bounding box otherwise.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn fit_quads_reporting(
+    clusters: &mut [Cluster],
+    image_width: u32,
+    image_height: u32,
+    params: &QuadThreshParams,
+    normal_border: bool,
+    reversed_border: bool,
+    on_reject: Option<&(dyn Fn(Quad, RejectReason) + Sync)>,
+    out: &mut Vec<Quad>,
+) {
+    let max_perimeter = max_cluster_points(image_width, image_height);
+
+    // Rejections are reported from worker threads, which do not inherit the
+    // current span.
+    #[cfg(feature = "tracing")]
+    let span = tracing::Span::current();
 
     *out = Par::get().map_init_collect(clusters, QuadFitBufs::new, |bufs, cluster| {
-        fit_quad(
+        let result = fit_quad(
             cluster,
             params,
             max_perimeter,
             normal_border,
             reversed_border,
             bufs,
-        )
+        );
+        match result {
+            Ok(quad) => Some(quad),
+            Err((reason, quad)) => {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    parent: &span,
+                    reason = reason.name(),
+                    points = cluster.points.len(),
+                    "cluster rejected"
+                );
+                if let Some(on_reject) = on_reject {
+                    on_reject(quad.map_or_else(|| bounding_quad(cluster), |q| *q), reason);
+                }
+                None
+            }
+        }
     });
 }
 
+/// Most boundary points a cluster may have and still be fitted, for a
+/// working image of the given size.
+pub(crate) fn max_cluster_points(image_width: u32, image_height: u32) -> usize {
+    // C reference: 2*(2*w + 2*h) = 4*(w+h). Each edge point is typically added
+    // twice (two unique neighbors), so the limit is 2× the geometric perimeter.
+    // See apriltag_quad_thresh.c:1090.
+    4 * (image_width as usize + image_height as usize)
+}
+
+/// Axis-aligned bounds of a cluster's points, in their doubled coordinates.
+#[derive(Debug, Clone, Copy)]
+struct BoundingBox {
+    x0: u16,
+    y0: u16,
+    x1: u16,
+    y1: u16,
+}
+
+impl BoundingBox {
+    /// The bounds of `points`; inverted (empty) if there are none.
+    fn of(points: &[Pt]) -> Self {
+        let mut bbox = Self {
+            x0: u16::MAX,
+            y0: u16::MAX,
+            x1: 0,
+            y1: 0,
+        };
+        for p in points {
+            bbox.x0 = bbox.x0.min(p.x);
+            bbox.y0 = bbox.y0.min(p.y);
+            bbox.x1 = bbox.x1.max(p.x);
+            bbox.y1 = bbox.y1.max(p.y);
+        }
+        bbox
+    }
+
+    /// Length of the diagonal, in working pixels.
+    fn diagonal(self) -> f64 {
+        let w = self.x1.saturating_sub(self.x0) as f64 * 0.5;
+        let h = self.y1.saturating_sub(self.y0) as f64 * 0.5;
+        w.hypot(h)
+    }
+}
+
+/// The axis-aligned bounding box of a cluster, in pixel coordinates, wound
+/// like a fitted quad.
+pub(crate) fn bounding_quad(cluster: &Cluster) -> Quad {
+    let BoundingBox { x0, y0, x1, y1 } = BoundingBox::of(&cluster.points);
+    let px = |v: u16| v as f64 * 0.5 + 0.5;
+    Quad {
+        corners: [
+            Vec2::new(px(x0), px(y0)),
+            Vec2::new(px(x1), px(y0)),
+            Vec2::new(px(x1), px(y1)),
+            Vec2::new(px(x0), px(y1)),
+        ],
+        reversed_border: false,
+        line_fit_mse: 0.0,
+        refine_residual: None,
+        corner_covariance: [[0.0; 3]; 4],
+    }
+}
+
 /// Try to fit a single quad from a cluster of edge points.
+///
+/// On failure, also returns the rejected quad if corners were fitted.
 fn fit_quad(
     cluster: &mut Cluster,
     params: &QuadThreshParams,
@@ -93,48 +345,61 @@ fn fit_quad(
     normal_border: bool,
     reversed_border: bool,
     bufs: &mut QuadFitBufs,
-) -> Option<Quad> {
+) -> Result<Quad, (RejectReason, Option<Box<Quad>>)> {
     let sz = cluster.points.len();
 
     // Size filtering
     if (sz as i32) < params.min_cluster_pixels || sz < 24 {
-        return None;
+        return Err((RejectReason::ClusterTooSmall, None));
     }
     if sz > max_perimeter {
-        return None;
+        return Err((RejectReason::ClusterTooLarge, None));
+    }
+    let bbox = BoundingBox::of(&cluster.points);
+    if bbox.diagonal() < params.min_tag_width {
+        return Err((RejectReason::ClusterTooSmall, None));
     }
 
     // Border direction check
     let (is_reversed, dot) = check_border_direction(&cluster.points);
     if dot.abs() < f64::EPSILON {
-        return None;
+        return Err((RejectReason::BorderDirection, None));
     }
     if is_reversed && !reversed_border {
-        return None;
+        return Err((RejectReason::BorderDirection, None));
     }
     if !is_reversed && !normal_border {
-        return None;
+        return Err((RejectReason::BorderDirection, None));
     }
 
     // Angular sorting
-    sort_by_angle(&mut cluster.points);
+    sort_by_angle(&mut cluster.points, bbox);
 
     // Build cumulative moments
-    build_line_fit_pts(&cluster.points, &mut bufs.lfps);
+    build_line_fit_pts(&cluster.points, params.pixel_aspect_ratio, &mut bufs.lfps);
 
     // Corner detection
-    let corners_idx = find_corners(&bufs.lfps, &mut bufs.errors, &mut bufs.maxima, params)?;
+    let corners_idx =
+        find_corners(&bufs.lfps, &mut bufs.corners, params).map_err(|reason| (reason, None))?;
 
     // Fit lines through each segment and compute corners
-    let quad_corners = compute_quad_corners(&bufs.lfps, &corners_idx, sz)?;
-
-    // Validate quad
-    validate_quad(&quad_corners, params)?;
+    let (quad_corners, line_fit_mse, corner_covariance) =
+        compute_quad_corners(&bufs.lfps, &corners_idx, sz)
+            .ok_or((RejectReason::NoIntersection, None))?;
 
-    Some(Quad {
+    let quad = Quad {
         corners: quad_corners,
         reversed_border: is_reversed,
-    })
+        line_fit_mse: line_fit_mse as f32,
+        refine_residual: None,
+        corner_covariance,
+    };
+
+    // Validate quad
+    if validate_quad(&quad.corners, params).is_none() {
+        return Err((RejectReason::Area, Some(Box::new(quad))));
+    }
+    Ok(quad)
 }
 
 /// Compute the dot product of each point's position (relative to centroid) with
@@ -157,17 +422,14 @@ fn check_border_direction(points: &[Pt]) -> (bool, f64) {
 }
 
 /// Sort points by angle around the cluster centroid using a fast slope proxy.
-fn sort_by_angle(points: &mut [Pt]) {
-    let (Some(xmin), Some(xmax), Some(ymin), Some(ymax)) = (
-        points.iter().map(|p| p.x).min(),
-        points.iter().map(|p| p.x).max(),
-        points.iter().map(|p| p.y).min(),
-        points.iter().map(|p| p.y).max(),
-    ) else {
-        // COVERAGE: empty points slice — callers always filter empty clusters
-        return;
-    };
-    let (xmin, xmax, ymin, ymax) = (xmin as f64, xmax as f64, ymin as f64, ymax as f64);
+/// `bbox` is the bounds of `points`.
+fn sort_by_angle(points: &mut [Pt], bbox: BoundingBox) {
+    let (xmin, xmax, ymin, ymax) = (
+        bbox.x0 as f64,
+        bbox.x1 as f64,
+        bbox.y0 as f64,
+        bbox.y1 as f64,
+    );
 
     let cx = (xmin + xmax) / 2.0 + 0.05118;
     let cy = (ymin + ymax) / 2.0 - 0.028581;
@@ -224,7 +486,7 @@ mod tests {
             let dy = angle.sin();
             let s = slope_key(dx, dy);
             let f = f32::from_bits(s);
-            assert!(f >= 0.0 && f < 4.0, "slope out of range: {f}");
+            assert!((0.0..4.0).contains(&f), "slope out of range: {f}");
             if i > 0 {
                 assert!(s > prev, "not monotonic at i={i}: {prev} -> {s}");
             }
@@ -284,6 +546,202 @@ mod tests {
         assert!((p.max_line_fit_mse - 10.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn quad_params_default_matches_thresh_params_at_default_decimate() {
+        let p = QuadParams::default().thresh_params(2.0);
+        let q = QuadThreshParams::default();
+        assert_eq!(p.min_cluster_pixels, q.min_cluster_pixels);
+        assert_eq!(p.max_nmaxima, q.max_nmaxima);
+        assert!((p.cos_critical_rad - q.cos_critical_rad).abs() < 1e-6);
+        assert_eq!(p.min_white_black_diff, q.min_white_black_diff);
+    }
+
+    #[test]
+    fn quad_params_default_keeps_reference_limits_at_any_decimation() {
+        for decimate in [1.0, 1.5, 2.0, 4.0] {
+            let p = QuadParams::default().thresh_params(decimate);
+            assert_eq!(p.min_cluster_pixels, 5);
+            assert!((p.max_line_fit_mse - 10.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn quad_params_scale_line_fit_mse_with_decimation() {
+        let p = QuadParams {
+            max_line_fit_mse: Some(40.0),
+            ..QuadParams::default()
+        };
+        assert!((p.thresh_params(1.0).max_line_fit_mse - 40.0).abs() < 1e-6);
+        assert!((p.thresh_params(2.0).max_line_fit_mse - 10.0).abs() < 1e-6);
+        assert!((p.thresh_params(4.0).max_line_fit_mse - 2.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn quad_params_scale_cluster_size_with_decimation() {
+        let p = QuadParams {
+            min_cluster_pixels: Some(10),
+            ..QuadParams::default()
+        };
+        assert_eq!(p.thresh_params(1.0).min_cluster_pixels, 10);
+        assert_eq!(p.thresh_params(1.5).min_cluster_pixels, 7);
+        assert_eq!(p.thresh_params(3.0).min_cluster_pixels, 4);
+        assert_eq!(p.thresh_params(0.5).min_cluster_pixels, 10);
+        let zero = QuadParams {
+            min_cluster_pixels: Some(0),
+            ..QuadParams::default()
+        };
+        assert_eq!(zero.thresh_params(4.0).min_cluster_pixels, 1);
+    }
+
+    #[test]
+    fn quad_params_scale_tile_size_with_decimation() {
+        for decimate in [1.0, 2.0, 4.0] {
+            let p = QuadParams::default().thresh_params(decimate);
+            assert_eq!(p.tile_size, DEFAULT_TILE_SIZE);
+        }
+        let p = QuadParams {
+            tile_size: Some(16),
+            ..QuadParams::default()
+        };
+        assert_eq!(p.thresh_params(1.0).tile_size, 16);
+        assert_eq!(p.thresh_params(3.0).tile_size, 5);
+        assert_eq!(p.thresh_params(4.0).tile_size, 4);
+        let tiny = QuadParams {
+            tile_size: Some(1),
+            ..QuadParams::default()
+        };
+        assert_eq!(tiny.thresh_params(4.0).tile_size, 1);
+    }
+
+    /// Boundary points along the edges of a quad with vertices given
+    /// clockwise (in image coordinates) at twice pixel resolution, with
+    /// gradients pointing outward.
+    fn polygon_cluster(vertices: [(i32, i32); 4]) -> Cluster {
+        let mut points = Vec::new();
+        for i in 0..4 {
+            let (px, py) = vertices[i];
+            let (qx, qy) = vertices[(i + 1) % 4];
+            let (dx, dy) = (qx - px, qy - py);
+            let len = ((dx * dx + dy * dy) as f64).sqrt();
+            let n = dx.abs().max(dy.abs()) / 2;
+            for t in 0..n {
+                points.push(Pt {
+                    x: (px + dx * t / n) as u16,
+                    y: (py + dy * t / n) as u16,
+                    gx: (255.0 * dy as f64 / len) as i16,
+                    gy: (-255.0 * dx as f64 / len) as i16,
+                    slope: 0,
+                });
+            }
+        }
+        Cluster { points }
+    }
+
+    #[test]
+    fn fit_quad_reports_line_fit_and_angle_failures() {
+        let square = [(140, 140), (260, 140), (260, 260), (140, 260)];
+        // Corners of 71.6° and 108.4°
+        let slanted = [(140, 140), (260, 140), (300, 260), (180, 260)];
+        let mut bufs = QuadFitBufs::new();
+        let mut fit = |vertices, params: &QuadThreshParams| {
+            fit_quad(
+                &mut polygon_cluster(vertices),
+                params,
+                1600,
+                true,
+                true,
+                &mut bufs,
+            )
+            .map_err(|(reason, _)| reason)
+        };
+
+        let params = QuadThreshParams::default();
+        assert!(fit(square, &params).is_ok());
+        assert!(fit(slanted, &params).is_ok());
+
+        let strict_fit = QuadThreshParams {
+            max_line_fit_mse: -1.0,
+            ..QuadThreshParams::default()
+        };
+        assert_eq!(
+            fit(square, &strict_fit).unwrap_err(),
+            RejectReason::LineFitMse
+        );
+
+        let strict_angle = QuadThreshParams {
+            cos_critical_rad: 75.0f32.to_radians().cos(),
+            ..QuadThreshParams::default()
+        };
+        assert!(fit(square, &strict_angle).is_ok());
+        assert_eq!(
+            fit(slanted, &strict_angle).unwrap_err(),
+            RejectReason::CriticalAngle
+        );
+
+        let tiny = [(140, 140), (150, 140), (150, 150), (140, 150)];
+        assert_eq!(
+            fit(tiny, &params).unwrap_err(),
+            RejectReason::ClusterTooSmall
+        );
+    }
+
+    #[test]
+    fn fit_quad_rejects_clusters_narrower_than_a_tag() {
+        // 60 working pixels wide, so its bounding box is 84.9 across
+        let square = [(140, 140), (260, 140), (260, 260), (140, 260)];
+        let fit = |min_tag_width| {
+            let params = QuadThreshParams {
+                min_tag_width,
+                ..QuadThreshParams::default()
+            };
+            fit_quad(
+                &mut polygon_cluster(square),
+                &params,
+                1600,
+                true,
+                true,
+                &mut QuadFitBufs::new(),
+            )
+            .map_err(|(reason, quad)| (reason, quad.is_some()))
+        };
+
+        assert!(fit(84.0).is_ok());
+        assert_eq!(
+            fit(86.0).unwrap_err(),
+            (RejectReason::ClusterTooSmall, false)
+        );
+    }
+
+    #[test]
+    fn tag_width_limit_follows_the_narrower_axis() {
+        // A field of an interlaced frame: 60 pixels wide, 30 rows tall,
+        // so its bounding box is 67.1 across
+        let field = [(140, 140), (260, 140), (260, 200), (140, 200)];
+        let fit = |width| {
+            let mut params = QuadThreshParams {
+                pixel_aspect_ratio: 0.5,
+                ..QuadThreshParams::default()
+            };
+            params.limit_tag_width(width);
+            fit_quad(
+                &mut polygon_cluster(field),
+                &params,
+                1600,
+                true,
+                true,
+                &mut QuadFitBufs::new(),
+            )
+            .map_err(|(reason, quad)| (reason, quad.is_some()))
+        };
+
+        // Tags 134 pixels across are 67 rows tall
+        assert!(fit(134.0).is_ok());
+        assert_eq!(
+            fit(136.0).unwrap_err(),
+            (RejectReason::ClusterTooSmall, false)
+        );
+    }
+
     #[test]
     fn fit_quad_synthetic_rectangle() {
         let mut points = Vec::new();
@@ -335,6 +793,65 @@ mod tests {
         assert!(!quads.is_empty());
     }
 
+    #[test]
+    fn fit_quad_in_square_pixel_frame() {
+        // A square tag rotated 45° and imaged with pixels 4× wider than tall:
+        // a narrow diamond whose top and bottom angles (28°) fall below the
+        // critical angle unless the aspect ratio is accounted for.
+        let (cx, cy, hw, hh) = (100.0f64, 100.0f64, 24.0f64, 96.0f64);
+        let vertices = [(cx + hw, cy), (cx, cy + hh), (cx - hw, cy), (cx, cy - hh)];
+        let mut points = Vec::new();
+        for i in 0..4 {
+            let (ax, ay) = vertices[i];
+            let (bx, by) = vertices[(i + 1) % 4];
+            let (nx, ny) = (by - ay, ax - bx);
+            let steps = 200;
+            for k in 0..steps {
+                let t = k as f64 / steps as f64;
+                let (x, y) = (ax + (bx - ax) * t, ay + (by - ay) * t);
+                points.push(Pt {
+                    x: (2.0 * x - 1.0).round() as u16,
+                    y: (2.0 * y - 1.0).round() as u16,
+                    gx: (255.0 * nx.signum()) as i16,
+                    gy: (255.0 * ny.signum()) as i16,
+                    slope: 0,
+                });
+            }
+        }
+
+        let mut quad_params = QuadParams {
+            critical_angle_deg: 30.0,
+            ..QuadParams::default()
+        };
+        let mut quads = Vec::new();
+        let cluster = Cluster {
+            points: points.clone(),
+        };
+        let params = quad_params.thresh_params(1.0);
+        fit_quads(&mut [cluster], 200, 200, &params, true, true, &mut quads);
+        assert!(quads.is_empty());
+
+        quad_params.pixel_aspect_ratio = 4.0;
+        let params = quad_params.thresh_params(1.0);
+        fit_quads(
+            &mut [Cluster { points }],
+            200,
+            200,
+            &params,
+            true,
+            true,
+            &mut quads,
+        );
+        assert_eq!(quads.len(), 1);
+        for c in &quads[0].corners {
+            let nearest = vertices
+                .iter()
+                .map(|&(vx, vy)| (c[0] - vx).hypot(c[1] - vy))
+                .fold(f64::INFINITY, f64::min);
+            assert!(nearest < 0.5, "corner {c:?} off by {nearest}");
+        }
+    }
+
     #[test]
     fn slope_key_zero_displacement_is_nan() {
         let s = slope_key(0.0, 0.0);
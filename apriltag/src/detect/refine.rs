@@ -1,12 +1,12 @@
 use super::geometry::Vec2;
 use super::image::GrayImage;
-use super::quad::Quad;
+use super::quad::{corner_covariance, EdgeFit, FittedLine, Quad};
 
 /// Refine quad edges by snapping to strong gradients in the original image.
 ///
 /// For each quad edge, samples along the edge and searches perpendicular to it
 /// to find the strongest gradient, then re-fits the edge line and recomputes
-/// corner intersections.
+/// corner intersections and their covariance.
 pub fn refine_edges(
     quad: &mut Quad,
     img: &impl GrayImage,
@@ -16,6 +16,8 @@ pub fn refine_edges(
     let range = quad_decimate as f64 + 1.0;
 
     let mut lines = [[0.0f64; 4]; 4]; // [px, py, nx, ny]
+    let mut fits: [Option<EdgeFit>; 4] = [None; 4];
+    let mut residual: Option<f64> = None;
 
     let steps = (2.0 * range * 4.0) as usize;
     // Precomputed interpolation values: offsets from (n_min - 1) to (n_max + 1) in 0.25 steps.
@@ -135,13 +137,33 @@ pub fn refine_edges(
 
         let theta = 0.5 * (-2.0 * cxy).atan2(cyy - cxx);
         lines[edge] = [ex, ey, theta.cos(), theta.sin()];
+
+        // Mean squared distance from the line: the smaller covariance eigenvalue
+        let eig_small = 0.5 * (cxx + cyy - ((cxx - cyy).powi(2) + 4.0 * cxy * cxy).sqrt());
+        residual = Some(residual.unwrap_or(0.0).max(eig_small.max(0.0)));
+        fits[edge] = Some(EdgeFit {
+            line: FittedLine {
+                px: ex,
+                py: ey,
+                nx: theta.cos(),
+                ny: theta.sin(),
+            },
+            count: n_total,
+            spread: cxx + cyy - eig_small,
+            mse: eig_small.max(0.0),
+        });
     }
+    quad.refine_residual = residual.map(|r| r as f32);
 
     // Recompute corners from refined lines
     for i in 0..4 {
         let j = (i + 1) % 4;
         if let Some((cx, cy)) = intersect_lines_raw(&lines[i], &lines[j]) {
             quad.corners[j] = Vec2::new(cx, cy);
+            // An edge that kept its unrefined line keeps its corners' covariance
+            if let (Some(a), Some(b)) = (&fits[i], &fits[j]) {
+                quad.corner_covariance[j] = corner_covariance(a, b, (cx, cy));
+            }
         }
     }
 }
@@ -201,6 +223,9 @@ mod tests {
         let mut quad = Quad {
             corners: vc([[20.0, 20.0], [80.0, 20.0], [80.0, 80.0], [20.0, 80.0]]),
             reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         refine_edges(&mut quad, &img, 2.0, &mut Vec::new());
         // Should not crash; corners may change slightly
@@ -223,6 +248,9 @@ mod tests {
         let mut quad = Quad {
             corners: vc([[45.0, 20.0], [55.0, 20.0], [55.0, 80.0], [45.0, 80.0]]),
             reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         refine_edges(&mut quad, &img, 2.0, &mut Vec::new());
 
@@ -242,7 +270,7 @@ mod tests {
         // Fill white
         for y in 0..h {
             for x in 0..w {
-                img.set(x as u32, y as u32, 255);
+                img.set(x, y, 255);
             }
         }
         // Draw black rectangle from (30,20) to (90,80) — width=60, height=60
@@ -271,6 +299,9 @@ mod tests {
                 [rx0 as f64 + 1.0, ry0 as f64 + 1.0],
             ]),
             reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
 
         refine_edges(&mut quad, &img, 1.0, &mut Vec::new());
@@ -302,6 +333,9 @@ mod tests {
         let mut quad = Quad {
             corners: vc([[0.0, 1.0], [48.0, 1.0], [48.0, 48.0], [0.0, 48.0]]),
             reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         refine_edges(&mut quad, &img, 2.0, &mut Vec::new());
         for c in &quad.corners {
@@ -332,6 +366,9 @@ mod tests {
         let mut quad_fast = Quad {
             corners: vc([[90.0, 50.0], [110.0, 50.0], [110.0, 150.0], [90.0, 150.0]]),
             reversed_border: false,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         refine_edges(&mut quad_fast, &img, 2.0, &mut Vec::new());
 
@@ -370,6 +407,9 @@ mod tests {
         let mut quad = Quad {
             corners: vc([[20.0, 20.0], [80.0, 20.0], [80.0, 80.0], [20.0, 80.0]]),
             reversed_border: true,
+            line_fit_mse: 0.0,
+            refine_residual: None,
+            corner_covariance: [[0.0; 3]; 4],
         };
         refine_edges(&mut quad, &img, 1.0, &mut Vec::new());
         for c in &quad.corners {
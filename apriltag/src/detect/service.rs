@@ -0,0 +1,269 @@
+//! A detector whose configuration can be swapped while frames are in flight.
+//!
+//! Long-running services retune detection (families, thresholds, decimation)
+//! without restarting. Building a [`Detector`] means building its families'
+//! lookup tables, which can take a while for large families, so
+//! [`DetectorService`] builds the replacement off the detecting threads and
+//! swaps it in when it is ready. Each frame runs on a snapshot of the
+//! detector taken when it starts, so a swap never interrupts or drops one.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread::JoinHandle;
+
+use super::detector::{Detection, Detector, DetectorBuffers, DetectorBuilder};
+use super::image::GrayImage;
+use crate::error::BuildError;
+
+/// A shared [`Detector`] that can be replaced atomically.
+///
+/// Share it between threads with an [`Arc`]; each detecting thread keeps its
+/// own [`DetectorBuffers`].
+///
+/// ```
+/// use std::sync::Arc;
+/// use apriltag::detect::service::DetectorService;
+/// use apriltag::{family, Detector, DetectorBuffers, ImageU8};
+///
+/// let service = Arc::new(DetectorService::new(
+///     Detector::builder().add_family(family::tag16h5(), 0).build(),
+/// ));
+/// let mut buffers = DetectorBuffers::new();
+/// service.detect(&ImageU8::new(64, 64), &mut buffers);
+///
+/// // Retune: build off-thread, swap in when done
+/// let reload = service.reload(Detector::builder()
+///     .quad_decimate(1.0)
+///     .add_family(family::tag36h11(), 2));
+/// let generation = reload.join().unwrap().expect("valid configuration");
+/// assert_eq!(generation, Some(service.generation()));
+/// assert_eq!(service.detector().config.quad_decimate, 1.0);
+/// ```
+pub struct DetectorService {
+    current: RwLock<Arc<Detector>>,
+    generation: AtomicU64,
+    /// Sequence number of the latest reload or replacement requested.
+    requested: AtomicU64,
+}
+
+/// A reload requested with [`DetectorService::reload_job`], to be built on
+/// any thread with [`run`](Self::run).
+#[must_use = "the detector is only built and swapped in by `run`"]
+pub struct ReloadJob {
+    service: Arc<DetectorService>,
+    builder: DetectorBuilder,
+    sequence: u64,
+}
+
+impl ReloadJob {
+    /// Build the detector and swap it in, returning the new generation, or
+    /// `None` if a newer reload or replacement was requested meanwhile and
+    /// this one is stale.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`BuildError`] of
+    /// [`DetectorBuilder::try_build`] if the builder names an unknown family
+    /// or holds an invalid configuration; the current detector keeps
+    /// serving.
+    pub fn run(self) -> Result<Option<u64>, BuildError> {
+        let detector = self.builder.try_build()?;
+        let mut current = self
+            .service
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.service.requested.load(Ordering::Acquire) != self.sequence {
+            return Ok(None);
+        }
+        Ok(Some(self.service.swap(&mut current, detector)))
+    }
+}
+
+impl DetectorService {
+    /// Serve `detector`, as generation 0.
+    pub fn new(detector: Detector) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(detector)),
+            generation: AtomicU64::new(0),
+            requested: AtomicU64::new(0),
+        }
+    }
+
+    /// The current detector. Later swaps do not affect the returned one, so
+    /// hold it for the duration of a frame.
+    pub fn detector(&self) -> Arc<Detector> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// How many times the detector has been replaced.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Detect tags with the current detector; see [`Detector::detect`].
+    pub fn detect(
+        &self,
+        img: &(impl GrayImage + Sync),
+        buffers: &mut DetectorBuffers,
+    ) -> Vec<Detection> {
+        self.detector().detect(img, buffers)
+    }
+
+    /// Swap in `detector` and return the new generation. Frames already
+    /// running finish on the detector they started with, and reloads
+    /// requested before this call are dropped when they finish.
+    pub fn replace(&self, detector: Detector) -> u64 {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        self.requested.fetch_add(1, Ordering::AcqRel);
+        self.swap(&mut current, detector)
+    }
+
+    /// Request a reload with `builder`, to be built by running the returned
+    /// job on a thread of the caller's choosing, such as a thread pool or a
+    /// web worker.
+    ///
+    /// Detection continues on the current detector meanwhile. Only the
+    /// latest request takes effect: a job that finishes after a newer reload
+    /// or replacement was requested is dropped, so an older configuration
+    /// never overwrites a newer one.
+    pub fn reload_job(self: &Arc<Self>, builder: DetectorBuilder) -> ReloadJob {
+        ReloadJob {
+            service: Arc::clone(self),
+            builder,
+            sequence: self.requested.fetch_add(1, Ordering::AcqRel) + 1,
+        }
+    }
+
+    /// Build `builder` on a new thread and swap the result in when it is
+    /// done, as [`reload_job`](Self::reload_job) does. Joining the handle
+    /// gives the result of [`ReloadJob::run`]: the new generation, `None` if
+    /// a newer request superseded this one, or the [`BuildError`] if the
+    /// builder was invalid.
+    ///
+    /// Not available on `wasm32`, which has no threads to spawn; run a
+    /// [`ReloadJob`] in a worker instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(
+        self: &Arc<Self>,
+        builder: DetectorBuilder,
+    ) -> JoinHandle<Result<Option<u64>, BuildError>> {
+        let job = self.reload_job(builder);
+        std::thread::spawn(move || job.run())
+    }
+
+    /// Install `detector` under the held write lock and bump the generation.
+    fn swap(&self, current: &mut Arc<Detector>, detector: Detector) -> u64 {
+        *current = Arc::new(detector);
+        // Bumped under the lock, so generations follow the swap order
+        self.generation.fetch_add(1, Ordering::AcqRel) + 1
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "family-tag16h5", feature = "family-tag36h11"))]
+    fn frames_keep_their_snapshot_across_a_reload() {
+        let service = Arc::new(DetectorService::new(
+            Detector::builder()
+                .add_family(crate::family::tag16h5(), 0)
+                .build(),
+        ));
+        let in_flight = service.detector();
+        let old_tables = in_flight.decode_table_bytes();
+        assert_eq!(service.generation(), 0);
+
+        let generation = service
+            .reload(Detector::builder().add_family(crate::family::tag36h11(), 2))
+            .join()
+            .unwrap();
+        assert_eq!(generation, Ok(Some(1)));
+        assert_eq!(service.generation(), 1);
+
+        // The frame started before the reload still sees the old families
+        assert_eq!(in_flight.decode_table_bytes(), old_tables);
+        assert!(service.detector().decode_table_bytes() > old_tables);
+
+        let mut buffers = DetectorBuffers::new();
+        assert!(service
+            .detect(&crate::ImageU8::new(32, 32), &mut buffers)
+            .is_empty());
+    }
+
+    #[test]
+    fn overlapping_reloads_keep_the_newest_request() {
+        let service = Arc::new(DetectorService::new(Detector::builder().build()));
+        let older = service.reload_job(Detector::builder().quad_decimate(3.0));
+        let newer = service.reload_job(Detector::builder().quad_decimate(1.0));
+
+        // The newer build finishes first; the older one must not undo it
+        assert_eq!(newer.run(), Ok(Some(1)));
+        assert_eq!(older.run(), Ok(None));
+        assert_eq!(service.generation(), 1);
+        assert_eq!(service.detector().config.quad_decimate, 1.0);
+
+        // Finishing in request order, the older build is stale all the same
+        let older = service.reload_job(Detector::builder().quad_decimate(4.0));
+        let newer = service.reload_job(Detector::builder().quad_decimate(2.0));
+        assert_eq!(older.run(), Ok(None));
+        assert_eq!(newer.run(), Ok(Some(2)));
+        assert_eq!(service.detector().config.quad_decimate, 2.0);
+
+        // A direct replacement supersedes reloads still building
+        let pending = service.reload_job(Detector::builder().quad_decimate(4.0));
+        assert_eq!(
+            service.replace(Detector::builder().quad_decimate(1.0).build()),
+            3
+        );
+        assert_eq!(pending.run(), Ok(None));
+        assert_eq!(service.detector().config.quad_decimate, 1.0);
+    }
+
+    #[test]
+    fn invalid_reloads_report_the_error_and_keep_serving() {
+        let service = Arc::new(DetectorService::new(
+            Detector::builder().quad_decimate(3.0).build(),
+        ));
+        let result = service
+            .reload(Detector::builder().family("tag99h1", 2))
+            .join()
+            .unwrap();
+        assert_eq!(result, Err(BuildError::UnknownFamily("tag99h1".into())));
+
+        let result = service
+            .reload_job(Detector::builder().quad_decimate(0.0))
+            .run();
+        assert!(matches!(
+            result,
+            Err(BuildError::InvalidConfig {
+                field: "quad_decimate",
+                ..
+            })
+        ));
+        assert_eq!(service.generation(), 0);
+        assert_eq!(service.detector().config.quad_decimate, 3.0);
+
+        // The failed requests do not block the next one
+        let job = service.reload_job(Detector::builder().quad_decimate(1.0));
+        assert_eq!(job.run(), Ok(Some(1)));
+    }
+
+    #[test]
+    fn replacements_count_generations() {
+        let service = DetectorService::new(Detector::builder().build());
+        assert_eq!(service.replace(Detector::builder().build()), 1);
+        assert_eq!(
+            service.replace(Detector::builder().quad_decimate(3.0).build()),
+            2
+        );
+        assert_eq!(service.detector().config.quad_decimate, 3.0);
+    }
+}
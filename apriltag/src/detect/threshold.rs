@@ -1,7 +1,9 @@
-use super::image::ImageU8;
+use super::image::{GrayImage, ImageU8};
 use super::par::Par;
 
-const TILESZ: u32 = 4;
+/// Side of the square tiles whose extrema set the local threshold, in
+/// working-image pixels, as in the C reference implementation.
+pub const DEFAULT_TILE_SIZE: u32 = 4;
 
 /// Binarize a rectangular block of pixels using a single tile's lo/hi values.
 ///
@@ -85,7 +87,8 @@ impl ThresholdBuffers {
 /// Uses `Par::get()` for optional parallelism: splits tile rows into chunks
 /// of an interleaved `[min, max]` buffer, then scatters into padded arrays.
 fn compute_tile_minmax(
-    img: &ImageU8,
+    img: &impl GrayImage,
+    tilesz: usize,
     tw: u32,
     th: u32,
     padded_w: u32,
@@ -97,13 +100,16 @@ fn compute_tile_minmax(
     let row_stride = tw_usize * 2;
     let mut minmax = vec![0u8; (th as usize) * row_stride];
 
-    let img_buf = &img.buf;
-    let img_stride = img.stride as usize;
-    let tilesz = TILESZ as usize;
+    let img_buf = img.buf();
+    let img_stride = img.stride() as usize;
 
     Par::get().chunks_mut_for_each(&mut minmax, row_stride, |ty, chunk| {
         let base_y = ty * tilesz;
-        for tx in 0..tw_usize {
+        #[cfg(feature = "simd")]
+        let first = simd::tile_row_minmax(img_buf, img_stride, tilesz, base_y, tw_usize, chunk);
+        #[cfg(not(feature = "simd"))]
+        let first = 0;
+        for tx in first..tw_usize {
             let base_x = tx * tilesz;
             let mut lo = 255u8;
             let mut hi = 0u8;
@@ -131,27 +137,103 @@ fn compute_tile_minmax(
     }
 }
 
+/// Mark the pixels of `threshed` where `region` is below 128 as unknown, so
+/// that no component or boundary forms there. Row `y` of `threshed` is row
+/// `y0 + y` of `region`.
+pub fn exclude_region(threshed: &mut ImageU8, region: &impl GrayImage, y0: u32) {
+    debug_assert_eq!(threshed.width, region.width());
+    let (w, stride) = (threshed.width as usize, threshed.stride as usize);
+    for y in 0..threshed.height {
+        let row = &mut threshed.buf[y as usize * stride..][..w];
+        for (t, &m) in row.iter_mut().zip(region.row(y0 + y)) {
+            if m < 128 {
+                *t = 127;
+            }
+        }
+    }
+}
+
 /// Produce a ternary threshold image: 0 (black), 255 (white), or 127 (unknown).
 ///
 /// Uses tile-based adaptive thresholding with min/max dilation to handle
-/// spatially varying illumination.
+/// spatially varying illumination, with [`DEFAULT_TILE_SIZE`] tiles.
 ///
 /// Writes the result into `out`, reusing its allocation.
 pub fn threshold(
-    img: &ImageU8,
+    img: &impl GrayImage,
     min_white_black_diff: i32,
     deglitch: bool,
     out: &mut ImageU8,
     tile_bufs: &mut ThresholdBuffers,
 ) {
-    let w = img.width;
-    let h = img.height;
-    let tw = w / TILESZ;
-    let th = h / TILESZ;
+    threshold_tiled(
+        img,
+        DEFAULT_TILE_SIZE,
+        min_white_black_diff,
+        deglitch,
+        out,
+        tile_bufs,
+    );
+}
 
-    if tw == 0 || th == 0 {
+/// Like [`threshold`], with `tile_size` × `tile_size` pixel tiles. Larger
+/// tiles follow the illumination more coarsely but leave fewer unknown
+/// pixels inside large uniform regions. A `tile_size` of 0 is treated as 1.
+pub fn threshold_tiled(
+    img: &impl GrayImage,
+    tile_size: u32,
+    min_white_black_diff: i32,
+    deglitch: bool,
+    out: &mut ImageU8,
+    tile_bufs: &mut ThresholdBuffers,
+) {
+    let w = img.width();
+    let h = img.height();
+    let tile_size = tile_size.max(1);
+    let Some((tw, th)) = tile_extrema(img, tile_size, tile_bufs) else {
         out.reshape(w, h);
         return;
+    };
+
+    // Binarize each pixel, processing tile-by-tile to load lo/hi once per tile.
+    // Remainder pixels (beyond tile-aligned region) use the last tile's values.
+    out.reshape(w, h);
+
+    binarize_tiles(
+        img.buf(),
+        img.stride() as usize,
+        &mut out.buf,
+        w as usize,
+        h as usize,
+        tile_size as usize,
+        tw,
+        th,
+        &tile_bufs.eroded_min,
+        &tile_bufs.dilated_max,
+        min_white_black_diff,
+    );
+
+    if deglitch {
+        deglitch_image(out, &mut tile_bufs.morph_a, &mut tile_bufs.morph_b);
+    }
+}
+
+/// Compute the eroded tile minima and dilated tile maxima of `img` for
+/// `tile_size` pixel tiles into `tile_bufs.eroded_min` and
+/// `tile_bufs.dilated_max`.
+///
+/// Returns the tile grid's width and height, or `None` when the image is
+/// smaller than one tile.
+pub(super) fn tile_extrema(
+    img: &impl GrayImage,
+    tile_size: u32,
+    tile_bufs: &mut ThresholdBuffers,
+) -> Option<(usize, usize)> {
+    let tw = img.width() / tile_size;
+    let th = img.height() / tile_size;
+
+    if tw == 0 || th == 0 {
+        return None;
     }
 
     // Compute per-tile min/max with 1-element padding border.
@@ -168,7 +250,15 @@ pub fn threshold(
     let tile_min = &mut tile_bufs.tile_min;
     let tile_max = &mut tile_bufs.tile_max;
 
-    compute_tile_minmax(img, tw, th, padded_w, tile_min, tile_max);
+    compute_tile_minmax(
+        img,
+        tile_size as usize,
+        tw,
+        th,
+        padded_w,
+        tile_min,
+        tile_max,
+    );
 
     // Dilate max, erode min using 3x3 tile neighborhood (no bounds checks needed)
     let tile_len = (tw * th) as usize;
@@ -196,39 +286,66 @@ pub fn threshold(
         }
     }
 
-    // Binarize each pixel, processing tile-by-tile to load lo/hi once per tile.
-    // Remainder pixels (beyond tile-aligned region) use the last tile's values.
-    out.reshape(w, h);
-
-    binarize_tiles(
-        &img.buf,
-        img.stride as usize,
-        &mut out.buf,
-        w as usize,
-        h as usize,
-        tw as usize,
-        th as usize,
-        eroded_min,
-        dilated_max,
-        min_white_black_diff,
-    );
+    Some((tw as usize, th as usize))
+}
 
-    if deglitch {
-        deglitch_image(out, &mut tile_bufs.morph_a, &mut tile_bufs.morph_b);
+/// Binarize rows `y0..y1` of `img` into `out`, using the tile extrema left
+/// in `tile_bufs` by [`tile_extrema`] for a `tiles` grid of `tile_size`
+/// pixel tiles.
+///
+/// The rows match the same rows of [`threshold_tiled`] without deglitching.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn binarize_rows(
+    img: &impl GrayImage,
+    y0: u32,
+    y1: u32,
+    tile_size: u32,
+    tiles: (usize, usize),
+    min_white_black_diff: i32,
+    tile_bufs: &ThresholdBuffers,
+    out: &mut ImageU8,
+) {
+    let (tw, th) = tiles;
+    let w = img.width() as usize;
+    let img_buf = img.buf();
+    let img_stride = img.stride() as usize;
+    out.reshape(w as u32, y1 - y0);
+    if w == 0 {
+        return;
     }
+    Par::get().chunks_mut_for_each(&mut out.buf, w, |i, row| {
+        let y = y0 as usize + i;
+        let ty = (y / tile_size as usize).min(th - 1);
+        binarize_tile_row(
+            img_buf,
+            img_stride,
+            row,
+            w,
+            tile_size as usize,
+            tw,
+            w,
+            1,
+            y,
+            &tile_bufs.eroded_min,
+            &tile_bufs.dilated_max,
+            ty * tw,
+            min_white_black_diff,
+        );
+    });
 }
 
 /// Binarize a single tile row (all tiles at row `ty`) into `out_chunk`.
 ///
 /// `out_chunk` is a sub-slice of the output buffer covering the pixel rows for
-/// this tile row.  `n_rows` is the number of pixel rows in the chunk (TILESZ
-/// for interior rows, possibly less for the last remainder row).
+/// this tile row.  `n_rows` is the number of pixel rows in the chunk
+/// (`tilesz` for interior rows, possibly less for the last remainder row).
 #[allow(clippy::too_many_arguments)]
 fn binarize_tile_row(
     img_buf: &[u8],
     img_stride: usize,
     out_chunk: &mut [u8],
     out_w: usize,
+    tilesz: usize,
     tw: usize,
     w: usize,
     n_rows: usize,
@@ -238,8 +355,23 @@ fn binarize_tile_row(
     tile_row_base: usize,
     min_white_black_diff: i32,
 ) {
-    let tilesz = TILESZ as usize;
-    for tx in 0..tw {
+    #[cfg(feature = "simd")]
+    let first = simd::binarize_tile_row(
+        img_buf,
+        img_stride,
+        out_chunk,
+        out_w,
+        tilesz,
+        tw,
+        n_rows,
+        img_y_base,
+        &eroded_min[tile_row_base..tile_row_base + tw],
+        &dilated_max[tile_row_base..tile_row_base + tw],
+        min_white_black_diff,
+    );
+    #[cfg(not(feature = "simd"))]
+    let first = 0;
+    for tx in first..tw {
         let idx = tile_row_base + tx;
         let x_start = tx * tilesz;
         binarize_block(
@@ -286,13 +418,13 @@ fn binarize_tiles(
     out_buf: &mut [u8],
     w: usize,
     h: usize,
+    tilesz: usize,
     tw: usize,
     th: usize,
     eroded_min: &[u8],
     dilated_max: &[u8],
     min_white_black_diff: i32,
 ) {
-    let tilesz = TILESZ as usize;
     // Each group covers `tilesz` rows, except the last may be shorter.
     // Split out_buf into groups of `tilesz * w` bytes each.
     // The last chunk may be shorter if there's a remainder.
@@ -311,6 +443,7 @@ fn binarize_tiles(
             img_stride,
             chunk,
             w,
+            tilesz,
             tw,
             w,
             n_rows,
@@ -328,7 +461,7 @@ fn binarize_tiles(
 }
 
 /// Morphological close (dilate then erode) with 3x3 structuring element.
-fn deglitch_image(img: &mut ImageU8, buf_a: &mut Vec<u8>, buf_b: &mut Vec<u8>) {
+pub(super) fn deglitch_image(img: &mut ImageU8, buf_a: &mut Vec<u8>, buf_b: &mut Vec<u8>) {
     let dilated = morph_op(img, true, std::mem::take(buf_a));
     let eroded = morph_op(&dilated, false, std::mem::take(buf_b));
     *buf_a = dilated.into_buf();
@@ -366,6 +499,109 @@ fn morph_op(img: &ImageU8, dilate: bool, buf: Vec<u8>) -> ImageU8 {
     out
 }
 
+/// Vectorized tile min/max and binarization, 16 pixels (four default-size
+/// tiles) at a time. Each function handles whole groups of tiles and returns
+/// the number of tiles done; the scalar loops finish the rest, and do all of
+/// the work for tile sizes that do not divide 16.
+#[cfg(feature = "simd")]
+mod simd {
+    use wide::u8x16;
+
+    /// Tiles per vector, if a vector holds a whole number of tiles.
+    fn group(tilesz: usize) -> Option<usize> {
+        (16 % tilesz == 0).then_some(16 / tilesz)
+    }
+
+    #[inline(always)]
+    fn load(buf: &[u8], off: usize) -> u8x16 {
+        let mut a = [0u8; 16];
+        a.copy_from_slice(&buf[off..off + 16]);
+        u8x16::new(a)
+    }
+
+    /// Write interleaved `[min, max]` pairs for the tiles of the tile row
+    /// starting at image row `base_y` into `minmax`.
+    pub(super) fn tile_row_minmax(
+        img_buf: &[u8],
+        img_stride: usize,
+        tilesz: usize,
+        base_y: usize,
+        tw: usize,
+        minmax: &mut [u8],
+    ) -> usize {
+        let Some(group) = group(tilesz) else {
+            return 0;
+        };
+        let groups = tw / group;
+        for g in 0..groups {
+            let x = g * 16;
+            let row = |dy: usize| load(img_buf, (base_y + dy) * img_stride + x);
+            let (mut lo, mut hi) = (row(0), row(0));
+            for dy in 1..tilesz {
+                lo = lo.min(row(dy));
+                hi = hi.max(row(dy));
+            }
+            // Column extremes down the tile row; reduce across each tile
+            let (lo, hi) = (lo.to_array(), hi.to_array());
+            for t in 0..group {
+                let cols = t * tilesz..(t + 1) * tilesz;
+                let tx = g * group + t;
+                minmax[tx * 2] = lo[cols.clone()].iter().copied().fold(255, u8::min);
+                minmax[tx * 2 + 1] = hi[cols].iter().copied().fold(0, u8::max);
+            }
+        }
+        groups * group
+    }
+
+    /// Binarize the tile-aligned columns of one tile row, given that row's
+    /// per-tile `lo` and `hi` values.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn binarize_tile_row(
+        img_buf: &[u8],
+        img_stride: usize,
+        out_chunk: &mut [u8],
+        out_w: usize,
+        tilesz: usize,
+        tw: usize,
+        n_rows: usize,
+        img_y_base: usize,
+        lo: &[u8],
+        hi: &[u8],
+        min_white_black_diff: i32,
+    ) -> usize {
+        let Some(group) = group(tilesz) else {
+            return 0;
+        };
+        let groups = tw / group;
+        for g in 0..groups {
+            // Per-pixel threshold and contrast mask for these tiles
+            let mut thresh = [0u8; 16];
+            let mut valid = [0u8; 16];
+            for t in 0..group {
+                let (l, h) = (lo[g * group + t], hi[g * group + t]);
+                let px = t * tilesz..(t + 1) * tilesz;
+                if (h as i32 - l as i32) >= min_white_black_diff {
+                    thresh[px.clone()].fill(l + (h - l) / 2);
+                    valid[px].fill(255);
+                }
+            }
+            let (thresh, valid) = (u8x16::new(thresh), u8x16::new(valid));
+
+            let x = g * 16;
+            for dy in 0..n_rows {
+                let v = load(img_buf, (img_y_base + dy) * img_stride + x);
+                // v <= thresh exactly where the saturating difference is zero
+                let dark = v.saturating_sub(thresh).cmp_eq(u8x16::ZERO);
+                let binary = dark.blend(u8x16::ZERO, u8x16::splat(255));
+                let out = valid.blend(binary, u8x16::splat(127));
+                let off = dy * out_w + x;
+                out_chunk[off..off + 16].copy_from_slice(out.as_array_ref());
+            }
+        }
+        groups * group
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -466,6 +702,38 @@ mod tests {
         assert_eq!(out.get(8, 0), 255);
     }
 
+    #[test]
+    fn larger_tiles_reach_further_into_uniform_regions() {
+        // Left half black, right half white
+        let mut img = ImageU8::new(32, 32);
+        for y in 0..32 {
+            for x in 16..32 {
+                img.set(x, y, 255);
+            }
+        }
+        let mut bufs = ThresholdBuffers::new();
+        let mut out = ImageU8::new(0, 0);
+        let mut tiled = ImageU8::new(0, 0);
+        threshold(&img, 5, false, &mut out, &mut bufs);
+        threshold_tiled(&img, DEFAULT_TILE_SIZE, 5, false, &mut tiled, &mut bufs);
+        assert_eq!(tiled.buf, out.buf);
+        // 4px tiles only see the edge from the tiles next to it
+        assert_eq!(out.get(0, 0), 127);
+        assert_eq!(out.get(10, 0), 127);
+        assert_eq!(out.get(12, 0), 0);
+
+        threshold_tiled(&img, 16, 5, false, &mut out, &mut bufs);
+        assert_eq!(out.get(0, 0), 0);
+        assert_eq!(out.get(31, 31), 255);
+
+        // Odd sizes and sizes of 0 go through the scalar path
+        threshold_tiled(&img, 3, 5, false, &mut out, &mut bufs);
+        assert_eq!(out.get(13, 0), 0);
+        assert_eq!(out.get(20, 0), 255);
+        threshold_tiled(&img, 0, 5, false, &mut out, &mut bufs);
+        assert_eq!((out.width, out.height), (32, 32));
+    }
+
     #[test]
     fn morph_dilate_expands_bright() {
         let mut img = ImageU8::new(5, 5);
@@ -502,4 +770,95 @@ mod tests {
         let bufs = ThresholdBuffers::default();
         assert!(bufs.tile_min.is_empty());
     }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn simd_matches_scalar() {
+        // Noise with a stride, over widths that leave scalar tiles and
+        // remainder columns, for each tile size a vector holds whole
+        let mut state = 0x9e37_79b9u32;
+        for tilesz in [2usize, 4, 8] {
+            for w in [16usize, 37, 64, 70] {
+                let (stride, h) = (w + 3, 3 * tilesz);
+                let img: Vec<u8> = (0..stride * h)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 17;
+                        state ^= state << 5;
+                        (state >> 8) as u8
+                    })
+                    .collect();
+                let tw = w / tilesz;
+                let group = 16 / tilesz;
+                let done = tw / group * group;
+
+                let mut minmax = vec![0u8; tw * 2];
+                assert_eq!(
+                    simd::tile_row_minmax(&img, stride, tilesz, tilesz, tw, &mut minmax),
+                    done
+                );
+                for tx in 0..done {
+                    let px = (tilesz..2 * tilesz).flat_map(|y| (0..tilesz).map(move |x| (x, y)));
+                    let vals: Vec<u8> =
+                        px.map(|(x, y)| img[y * stride + tx * tilesz + x]).collect();
+                    assert_eq!(minmax[tx * 2], *vals.iter().min().unwrap());
+                    assert_eq!(minmax[tx * 2 + 1], *vals.iter().max().unwrap());
+                }
+
+                // Per-tile lo/hi with some tiles below the contrast limit
+                let lo: Vec<u8> = (0..tw).map(|t| (t * 37 % 200) as u8).collect();
+                let hi: Vec<u8> = lo
+                    .iter()
+                    .enumerate()
+                    .map(|(t, &l)| l + (t % 3) as u8 * 20)
+                    .collect();
+                let mut simd_out = vec![0u8; 3 * w];
+                let mut scalar_out = vec![0u8; 3 * w];
+                let n = simd::binarize_tile_row(
+                    &img,
+                    stride,
+                    &mut simd_out,
+                    w,
+                    tilesz,
+                    tw,
+                    3,
+                    2,
+                    &lo,
+                    &hi,
+                    5,
+                );
+                assert_eq!(n, done);
+                for tx in 0..done {
+                    let x = tx * tilesz;
+                    binarize_block(
+                        &img,
+                        stride,
+                        &mut scalar_out,
+                        w,
+                        lo[tx],
+                        hi[tx],
+                        5,
+                        x,
+                        x + tilesz,
+                        0,
+                        3,
+                        2,
+                    );
+                }
+                for y in 0..3 {
+                    let row = y * w..y * w + done * tilesz;
+                    assert_eq!(
+                        simd_out[row.clone()],
+                        scalar_out[row],
+                        "tilesz={tilesz} w={w} y={y}"
+                    );
+                }
+            }
+        }
+        // Tiles that straddle vectors are left to the scalar loops
+        assert_eq!(
+            simd::tile_row_minmax(&[0; 64], 64, 3, 0, 5, &mut [0; 10]),
+            0
+        );
+    }
 }
@@ -0,0 +1,17 @@
+/// Evaluate `$body` inside a `debug`-level span named `$name`, recording its
+/// duration in `$profile` (an `Option<&mut TimeProfile>`) if set.
+///
+/// Without the `tracing` feature and without a profile this is just the bare
+/// expression.
+macro_rules! stage {
+    ($profile:ident, $name:literal, $body:expr) => {{
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name).entered();
+        let start = $crate::detect::profile::start(&$profile);
+        let out = $body;
+        $crate::detect::profile::record(&mut $profile, $name, start);
+        out
+    }};
+}
+
+pub(crate) use stage;
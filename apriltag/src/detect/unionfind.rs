@@ -1,6 +1,3 @@
-#[cfg(feature = "parallel")]
-use core::sync::atomic::{AtomicU64, Ordering};
-
 /// Packed parent (low 32 bits) + size (high 32 bits) in a single u64.
 /// Sharing a cache line eliminates one memory access per find/union step.
 #[inline(always)]
@@ -18,51 +15,6 @@ fn unpack_size(v: u64) -> u32 {
     (v >> 32) as u32
 }
 
-// Type alias: AtomicU64 when parallel, plain u64 otherwise.
-#[cfg(feature = "parallel")]
-type Cell = AtomicU64;
-#[cfg(not(feature = "parallel"))]
-type Cell = u64;
-
-/// Read the raw u64 value from a Cell.
-#[cfg(feature = "parallel")]
-#[inline(always)]
-fn cell_read(c: &Cell) -> u64 {
-    c.load(Ordering::Relaxed)
-}
-
-#[cfg(not(feature = "parallel"))]
-#[inline(always)]
-fn cell_read(c: &Cell) -> u64 {
-    *c
-}
-
-/// Get a mutable reference to the inner u64 (zero-cost for AtomicU64 via get_mut).
-#[cfg(feature = "parallel")]
-#[inline(always)]
-fn cell_get_mut(c: &mut Cell) -> &mut u64 {
-    c.get_mut()
-}
-
-#[cfg(not(feature = "parallel"))]
-#[inline(always)]
-fn cell_get_mut(c: &mut Cell) -> &mut u64 {
-    c
-}
-
-/// Create a Cell from a u64 value.
-#[cfg(feature = "parallel")]
-#[inline(always)]
-fn cell_new(v: u64) -> Cell {
-    AtomicU64::new(v)
-}
-
-#[cfg(not(feature = "parallel"))]
-#[inline(always)]
-fn cell_new(v: u64) -> Cell {
-    v
-}
-
 /// Weighted union-find (disjoint-set) with path splitting.
 ///
 /// Parent and size are interleaved in a single `Vec<u64>` so that both
@@ -72,19 +24,20 @@ fn cell_new(v: u64) -> Cell {
 /// Elements are eagerly initialized: each element starts as its own
 /// representative with size 0, eliminating a branch from `find()`.
 ///
-/// When the `parallel` feature is enabled, internal storage uses `AtomicU64`
-/// to support lock-free concurrent `find_shared`/`union_shared`. Exclusive
-/// (`&mut self`) methods use `get_mut()` for zero-overhead non-atomic access.
+/// Parallel builds split the elements into [`tiles`](Self::tiles) that are
+/// unioned independently, then read the result concurrently through
+/// [`find_flat`](Self::find_flat) after [`flatten`](Self::flatten), so no
+/// atomics are needed.
 pub struct UnionFind {
     /// Packed entries: low 32 bits = parent, high 32 bits = size.
-    data: Vec<Cell>,
+    data: Vec<u64>,
 }
 
 /// Initialize `data` so that each element is its own representative.
-fn init_data(data: &mut Vec<Cell>, n: usize) {
+fn init_data(data: &mut Vec<u64>, n: usize) {
     data.clear();
     data.reserve(n.saturating_sub(data.capacity()));
-    data.extend((0..n as u32).map(|i| cell_new(pack(i, 0))));
+    data.extend((0..n as u32).map(|i| pack(i, 0)));
 }
 
 impl UnionFind {
@@ -116,19 +69,9 @@ impl UnionFind {
     /// every node on the path, compressing more aggressively per traversal
     /// than path halving.
     #[inline]
-    pub fn find(&mut self, mut id: u32) -> u32 {
+    pub fn find(&mut self, id: u32) -> u32 {
         assert!((id as usize) < self.data.len());
-        loop {
-            let entry = *cell_get_mut(&mut self.data[id as usize]);
-            let parent = unpack_parent(entry);
-            if parent == id {
-                return id;
-            }
-            let grandparent = unpack_parent(*cell_get_mut(&mut self.data[parent as usize]));
-            // Path splitting: point to grandparent, advance to old parent
-            *cell_get_mut(&mut self.data[id as usize]) = pack(grandparent, unpack_size(entry));
-            id = parent;
-        }
+        find_in(&mut self.data, 0, id)
     }
 
     /// Union the sets containing `a` and `b`. Returns the new representative.
@@ -136,36 +79,35 @@ impl UnionFind {
     /// Uses weighted union (larger tree becomes root).
     #[inline]
     pub fn union(&mut self, a: u32, b: u32) -> u32 {
-        let ra = self.find(a);
-        let rb = self.find(b);
-        if ra == rb {
-            return ra;
-        }
-        let sa = unpack_size(*cell_get_mut(&mut self.data[ra as usize])) + 1;
-        let sb = unpack_size(*cell_get_mut(&mut self.data[rb as usize])) + 1;
-        if sa > sb {
-            *cell_get_mut(&mut self.data[rb as usize]) =
-                pack(ra, unpack_size(*cell_get_mut(&mut self.data[rb as usize])));
-            *cell_get_mut(&mut self.data[ra as usize]) = pack(
-                ra,
-                unpack_size(*cell_get_mut(&mut self.data[ra as usize])) + sb,
-            );
-            ra
-        } else {
-            *cell_get_mut(&mut self.data[ra as usize]) =
-                pack(rb, unpack_size(*cell_get_mut(&mut self.data[ra as usize])));
-            *cell_get_mut(&mut self.data[rb as usize]) = pack(
-                rb,
-                unpack_size(*cell_get_mut(&mut self.data[rb as usize])) + sa,
-            );
-            rb
+        union_in(&mut self.data, 0, a, b)
+    }
+
+    /// Split the elements into contiguous tiles ending at each of `ends`,
+    /// which must be increasing and end at the element count.
+    ///
+    /// Tiles are independent union-finds over disjoint element ranges, so
+    /// they can be built on separate threads without atomics; unions that
+    /// cross tile boundaries are made on `self` afterwards. This is the
+    /// C reference's `do_unionfind_task2` decomposition.
+    pub fn tiles(&mut self, ends: &[u32]) -> Vec<UnionFindTile<'_>> {
+        let mut tiles = Vec::with_capacity(ends.len());
+        let mut rest = &mut self.data[..];
+        let mut base = 0;
+        for &end in ends {
+            assert!(end >= base, "tile ends must be increasing");
+            let (data, tail) = rest.split_at_mut((end - base) as usize);
+            tiles.push(UnionFindTile { data, base });
+            rest = tail;
+            base = end;
         }
+        assert!(rest.is_empty(), "tiles must cover every element");
+        tiles
     }
 
     /// Get the size of the set containing `id` (including `id` itself).
     pub fn set_size(&mut self, id: u32) -> u32 {
         let r = self.find(id);
-        unpack_size(*cell_get_mut(&mut self.data[r as usize])) + 1
+        unpack_size(self.data[r as usize]) + 1
     }
 
     /// Get the size of a set given its root representative directly.
@@ -174,7 +116,7 @@ impl UnionFind {
     /// This avoids a redundant `find()` call when the root is already known.
     #[inline(always)]
     pub fn root_size(&self, root: u32) -> u32 {
-        unpack_size(cell_read(&self.data[root as usize])) + 1
+        unpack_size(self.data[root as usize]) + 1
     }
 
     /// Flatten all paths so every element points directly to its root.
@@ -185,8 +127,8 @@ impl UnionFind {
     pub fn flatten(&mut self) {
         for i in 0..self.data.len() as u32 {
             let root = self.find(i);
-            let entry = *cell_get_mut(&mut self.data[i as usize]);
-            *cell_get_mut(&mut self.data[i as usize]) = pack(root, unpack_size(entry));
+            let entry = self.data[i as usize];
+            self.data[i as usize] = pack(root, unpack_size(entry));
         }
     }
 
@@ -197,94 +139,13 @@ impl UnionFind {
     /// concurrently from multiple threads.
     #[inline(always)]
     pub fn find_flat(&self, id: u32) -> u32 {
-        unpack_parent(cell_read(&self.data[id as usize]))
+        unpack_parent(self.data[id as usize])
     }
 
-    /// Find the representative of `id` using lock-free path splitting.
-    ///
-    /// Uses `Relaxed` atomic loads/stores. Stale reads may cause extra
-    /// traversal but cannot produce incorrect final results — rayon's
-    /// join/collect barrier ensures all writes are visible before the
-    /// serial stitching phase reads them.
-    #[cfg(feature = "parallel")]
-    #[inline]
-    pub fn find_shared(&self, mut id: u32) -> u32 {
-        loop {
-            let entry = self.data[id as usize].load(Ordering::Relaxed);
-            let parent = unpack_parent(entry);
-            if parent == id {
-                return id;
-            }
-            let gp_entry = self.data[parent as usize].load(Ordering::Relaxed);
-            let grandparent = unpack_parent(gp_entry);
-            // Best-effort path splitting: benign race if another thread
-            // also updates this node — either update is valid.
-            self.data[id as usize].store(pack(grandparent, unpack_size(entry)), Ordering::Relaxed);
-            id = parent;
-        }
-    }
-
-    /// Union the sets containing `a` and `b` using lock-free CAS.
-    ///
-    /// Returns the new representative. Uses `Relaxed` ordering —
-    /// correctness does not depend on observing the latest root, only on
-    /// the CAS loop eventually succeeding for each union.
-    #[cfg(feature = "parallel")]
-    pub fn union_shared(&self, a: u32, b: u32) -> u32 {
-        loop {
-            let mut ra = self.find_shared(a);
-            let mut rb = self.find_shared(b);
-            if ra == rb {
-                return ra;
-            }
-            // Canonical ordering: smaller id becomes child to reduce contention
-            let sa = unpack_size(self.data[ra as usize].load(Ordering::Relaxed));
-            let sb = unpack_size(self.data[rb as usize].load(Ordering::Relaxed));
-            if sa < sb || (sa == sb && ra > rb) {
-                core::mem::swap(&mut ra, &mut rb);
-            }
-            // ra is the winner (larger set). Make rb point to ra.
-            let rb_entry = self.data[rb as usize].load(Ordering::Relaxed);
-            // COVERAGE: retry path only hit under contention from concurrent threads
-            if unpack_parent(rb_entry) != rb {
-                continue;
-            }
-            // CAS: set rb's parent to ra
-            if self.data[rb as usize]
-                .compare_exchange_weak(
-                    rb_entry,
-                    pack(ra, unpack_size(rb_entry)),
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                )
-                .is_ok()
-            {
-                // Best-effort size update on winner (only affects heuristic)
-                let ra_entry = self.data[ra as usize].load(Ordering::Relaxed);
-                let new_size = unpack_size(ra_entry) + unpack_size(rb_entry) + 1;
-                let _ = self.data[ra as usize].compare_exchange(
-                    ra_entry,
-                    pack(ra, new_size),
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
-                return ra;
-            }
-            // CAS failed — retry
-        }
-    }
-
-    /// Compare data contents for testing (AtomicU64 doesn't impl PartialEq).
+    /// Compare data contents for testing.
     #[cfg(test)]
     fn data_eq(&self, other: &Self) -> bool {
-        // COVERAGE: length mismatch branch not exercised by current tests
-        if self.data.len() != other.data.len() {
-            return false;
-        }
-        self.data
-            .iter()
-            .zip(other.data.iter())
-            .all(|(a, b)| cell_read(a) == cell_read(b))
+        self.data == other.data
     }
 
     /// Number of elements.
@@ -300,6 +161,67 @@ impl UnionFind {
     }
 }
 
+/// A contiguous range of a [`UnionFind`]'s elements, from
+/// [`UnionFind::tiles`]. Takes the same element ids as the full union-find,
+/// but only those inside the tile.
+pub struct UnionFindTile<'a> {
+    data: &'a mut [u64],
+    base: u32,
+}
+
+impl UnionFindTile<'_> {
+    /// Like [`UnionFind::find`], within this tile.
+    #[inline]
+    pub fn find(&mut self, id: u32) -> u32 {
+        assert!(id >= self.base && ((id - self.base) as usize) < self.data.len());
+        find_in(self.data, self.base, id)
+    }
+
+    /// Like [`UnionFind::union`], within this tile.
+    #[inline]
+    pub fn union(&mut self, a: u32, b: u32) -> u32 {
+        union_in(self.data, self.base, a, b)
+    }
+}
+
+/// Path-splitting find over `data`, which holds elements from `base` on.
+#[inline]
+fn find_in(data: &mut [u64], base: u32, mut id: u32) -> u32 {
+    loop {
+        let entry = data[(id - base) as usize];
+        let parent = unpack_parent(entry);
+        if parent == id {
+            return id;
+        }
+        let grandparent = unpack_parent(data[(parent - base) as usize]);
+        // Path splitting: point to grandparent, advance to old parent
+        data[(id - base) as usize] = pack(grandparent, unpack_size(entry));
+        id = parent;
+    }
+}
+
+/// Weighted union over `data`, which holds elements from `base` on.
+#[inline]
+fn union_in(data: &mut [u64], base: u32, a: u32, b: u32) -> u32 {
+    let ra = find_in(data, base, a);
+    let rb = find_in(data, base, b);
+    if ra == rb {
+        return ra;
+    }
+    let (ia, ib) = ((ra - base) as usize, (rb - base) as usize);
+    let sa = unpack_size(data[ia]) + 1;
+    let sb = unpack_size(data[ib]) + 1;
+    if sa > sb {
+        data[ib] = pack(ra, unpack_size(data[ib]));
+        data[ia] = pack(ra, unpack_size(data[ia]) + sb);
+        ra
+    } else {
+        data[ia] = pack(rb, unpack_size(data[ia]));
+        data[ib] = pack(rb, unpack_size(data[ib]) + sa);
+        rb
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -467,70 +389,29 @@ mod tests {
         assert_eq!(uf.root_size(root), size_before);
     }
 
-    #[cfg(feature = "parallel")]
-    mod shared {
-        use super::*;
-
-        #[test]
-        fn find_shared_returns_self_for_fresh() {
-            let uf = UnionFind::new(5);
-            assert_eq!(uf.find_shared(0), 0);
-            assert_eq!(uf.find_shared(4), 4);
-        }
-
-        #[test]
-        fn union_shared_merges_and_find_shared_agrees() {
-            let uf = UnionFind::new(5);
-            uf.union_shared(0, 1);
-            assert_eq!(uf.find_shared(0), uf.find_shared(1));
+    #[test]
+    fn tiles_union_independently_then_merge() {
+        let mut uf = UnionFind::new(9);
+        {
+            let mut tiles = uf.tiles(&[3, 3, 9]);
+            assert_eq!(tiles.len(), 3);
+            tiles[0].union(0, 2);
+            tiles[2].union(4, 8);
+            tiles[2].union(5, 4);
+            assert_eq!(tiles[2].find(8), tiles[2].find(5));
         }
+        assert_eq!(uf.find(0), uf.find(2));
+        assert_eq!(uf.set_size(4), 3);
+        assert_ne!(uf.find(2), uf.find(4));
 
-        #[test]
-        fn union_shared_weighted_larger_wins() {
-            let uf = UnionFind::new(10);
-            uf.union_shared(0, 1);
-            uf.union_shared(0, 2);
-            let r_large = uf.find_shared(0);
-            let root = uf.union_shared(0, 5);
-            assert_eq!(root, r_large);
-            assert_eq!(uf.find_shared(5), r_large);
-        }
+        // Merge across the tile boundary on the full union-find
+        uf.union(2, 5);
+        assert_eq!(uf.set_size(0), 5);
+    }
 
-        #[test]
-        fn concurrent_union_shared_all_merges_apply() {
-            use std::sync::Arc;
-            let n = 100u32;
-            let uf = Arc::new(UnionFind::new(n as usize));
-            // Spawn threads that each union a range of elements into element 0
-            let nthreads = 4u32;
-            let mut handles = Vec::new();
-            for t in 0..nthreads {
-                let uf = Arc::clone(&uf);
-                let start = t * (n / nthreads) + 1;
-                let end = if t == nthreads - 1 {
-                    n
-                } else {
-                    (t + 1) * (n / nthreads) + 1
-                };
-                handles.push(std::thread::spawn(move || {
-                    for i in start..end {
-                        uf.union_shared(0, i);
-                    }
-                }));
-            }
-            for h in handles {
-                h.join().unwrap();
-            }
-            // After all threads join, every element should share a root with 0
-            let root = uf.find_shared(0);
-            for i in 1..n {
-                // COVERAGE: assertion message only reached on failure
-                assert_eq!(
-                    uf.find_shared(i),
-                    root,
-                    "element {i} not merged with root {root}"
-                );
-            }
-        }
+    #[test]
+    #[should_panic(expected = "cover every element")]
+    fn tiles_must_cover_all_elements() {
+        UnionFind::new(4).tiles(&[2]);
     }
 }
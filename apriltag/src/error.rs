@@ -42,6 +42,128 @@ impl fmt::Display for LayoutError {
 
 impl std::error::Error for LayoutError {}
 
+/// Errors produced when an image cannot be processed by the detector.
+///
+/// ```
+/// use apriltag::error::DetectError;
+/// use apriltag::{Detector, DetectorBuffers, ImageU8};
+///
+/// let det = Detector::builder().max_image_dimension(64).build();
+/// let err = det
+///     .try_detect(&ImageU8::new(100, 50), &mut DetectorBuffers::new())
+///     .unwrap_err();
+/// assert!(matches!(err, DetectError::ExceedsMaxDimension { max: 64, .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectError {
+    /// The input is larger than the configured
+    /// [`max_image_dimension`](crate::DetectorConfig::max_image_dimension).
+    ExceedsMaxDimension { width: u32, height: u32, max: u32 },
+    /// The image after decimation is wider or taller than
+    /// [`MAX_WORKING_DIMENSION`](crate::detect::detector::MAX_WORKING_DIMENSION).
+    WorkingImageTooLarge { width: u32, height: u32 },
+    /// A pixel buffer is too short for the image it should hold, or its
+    /// stride is narrower than a row; see
+    /// [`ImageRef::try_new`](crate::ImageRef::try_new).
+    InvalidImage {
+        width: u32,
+        height: u32,
+        stride: u32,
+        len: usize,
+    },
+    /// A region mask is not the size of the image it masks; see
+    /// [`Detector::detect_masked`](crate::Detector::detect_masked).
+    MaskSizeMismatch {
+        width: u32,
+        height: u32,
+        mask_width: u32,
+        mask_height: u32,
+    },
+}
+
+impl fmt::Display for DetectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExceedsMaxDimension { width, height, max } => write!(
+                f,
+                "image {width}x{height} exceeds the maximum dimension {max}"
+            ),
+            Self::WorkingImageTooLarge { width, height } => write!(
+                f,
+                "decimated image {width}x{height} is too large to process; \
+                 increase quad_decimate or downscale the input"
+            ),
+            Self::InvalidImage {
+                width,
+                height,
+                stride,
+                len,
+            } => write!(
+                f,
+                "{len}-byte buffer cannot hold a {width}x{height} image with stride {stride}"
+            ),
+            Self::MaskSizeMismatch {
+                width,
+                height,
+                mask_width,
+                mask_height,
+            } => write!(
+                f,
+                "mask {mask_width}x{mask_height} does not match the {width}x{height} image"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DetectError {}
+
+/// Errors produced when a [`DetectorBuilder`](crate::DetectorBuilder)
+/// cannot build a detector.
+///
+/// ```
+/// use apriltag::error::BuildError;
+/// use apriltag::Detector;
+///
+/// let err = Detector::builder().family("tag99h1", 2).try_build().err();
+/// assert!(matches!(err, Some(BuildError::UnknownFamily(_))));
+///
+/// let err = Detector::builder().quad_decimate(0.5).try_build().err();
+/// assert!(matches!(
+///     err,
+///     Some(BuildError::InvalidConfig { field: "quad_decimate", .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// No built-in family has this name.
+    UnknownFamily(String),
+    /// The family has no codes to match, e.g. a generated family loaded
+    /// without its codes file.
+    EmptyFamily(String),
+    /// A [`DetectorConfig`](crate::DetectorConfig) field is out of range.
+    InvalidConfig {
+        field: &'static str,
+        reason: &'static str,
+    },
+    /// The detector's own thread pool for
+    /// [`DetectorConfig::num_threads`](crate::DetectorConfig::num_threads)
+    /// could not be built.
+    ThreadPool(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFamily(name) => write!(f, "unknown tag family: {name}"),
+            Self::EmptyFamily(name) => write!(f, "tag family {name} has no codes"),
+            Self::InvalidConfig { field, reason } => write!(f, "invalid {field}: {reason}"),
+            Self::ThreadPool(err) => write!(f, "cannot build thread pool: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -86,4 +208,67 @@ mod tests {
         let err = LayoutError::InvalidBorder("bad width".to_string());
         assert_eq!(err.to_string(), "invalid border: bad width");
     }
+
+    #[test]
+    fn display_detect_errors() {
+        let err = DetectError::ExceedsMaxDimension {
+            width: 100,
+            height: 50,
+            max: 64,
+        };
+        assert_eq!(
+            err.to_string(),
+            "image 100x50 exceeds the maximum dimension 64"
+        );
+        let err = DetectError::WorkingImageTooLarge {
+            width: 40000,
+            height: 5000,
+        };
+        assert_eq!(
+            err.to_string(),
+            "decimated image 40000x5000 is too large to process; \
+             increase quad_decimate or downscale the input"
+        );
+        let err = DetectError::InvalidImage {
+            width: 4,
+            height: 4,
+            stride: 4,
+            len: 10,
+        };
+        assert_eq!(
+            err.to_string(),
+            "10-byte buffer cannot hold a 4x4 image with stride 4"
+        );
+        let err = DetectError::MaskSizeMismatch {
+            width: 640,
+            height: 480,
+            mask_width: 320,
+            mask_height: 240,
+        };
+        assert_eq!(
+            err.to_string(),
+            "mask 320x240 does not match the 640x480 image"
+        );
+    }
+
+    #[test]
+    fn display_build_errors() {
+        assert_eq!(
+            BuildError::UnknownFamily("tag99h1".to_string()).to_string(),
+            "unknown tag family: tag99h1"
+        );
+        assert_eq!(
+            BuildError::EmptyFamily("custom".to_string()).to_string(),
+            "tag family custom has no codes"
+        );
+        let err = BuildError::InvalidConfig {
+            field: "quad_decimate",
+            reason: "must be at least 1",
+        };
+        assert_eq!(err.to_string(), "invalid quad_decimate: must be at least 1");
+        assert_eq!(
+            BuildError::ThreadPool("out of threads".to_string()).to_string(),
+            "cannot build thread pool: out of threads"
+        );
+    }
 }
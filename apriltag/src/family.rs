@@ -34,7 +34,7 @@ impl FamilyId {
 
 impl fmt::Display for FamilyId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.0)
+        f.pad(&self.0)
     }
 }
 
@@ -164,17 +164,97 @@ impl TagFamily {
     /// assert_eq!(tag.grid_size, 8);
     /// assert_eq!(tag.pixel(0, 0), Pixel::White); // outer border
     /// ```
+    ///
+    /// The handle panics on use if `index` is out of range; see
+    /// [`get_tag`](Self::get_tag).
     pub fn tag(&self, index: usize) -> crate::tag::Tag<'_> {
         crate::tag::Tag::new(self, index)
     }
 
+    /// The tag at `index`, or `None` if the family has fewer codes.
+    pub fn get_tag(&self, index: usize) -> Option<crate::tag::Tag<'_>> {
+        (index < self.codes.len()).then(|| crate::tag::Tag::new(self, index))
+    }
+
     /// Parse a TOML config string and binary code data into a TagFamily.
+    ///
+    /// `bin_data` is either a bare array of codes or the
+    /// [versioned format](Self::to_bin), whose header must agree with the
+    /// config's bit count and minimum Hamming distance and whose checksum
+    /// must match. Codes of either format must fit in the layout's bits.
     #[cfg(feature = "serde")]
     pub fn from_toml_and_bin(toml_str: &str, bin_data: &[u8]) -> Result<TagFamily, FamilyError> {
         let config: FamilyConfig =
             toml::from_str(toml_str).map_err(|e| FamilyError::Config(e.to_string()))?;
-        let codes = parse_bin_codes(bin_data)?;
-        TagFamily::from_config_and_codes(config, codes).map_err(FamilyError::Layout)
+        let (header, codes) = parse_bin(bin_data)?;
+        let family =
+            TagFamily::from_config_and_codes(config, codes).map_err(FamilyError::Layout)?;
+        if let Some(header) = header {
+            header.check(&family)?;
+        }
+        let nbits = family.layout.nbits;
+        if let Some(index) = family
+            .codes
+            .iter()
+            .position(|&code| nbits < 64 && code >> nbits != 0)
+        {
+            return Err(FamilyError::InvalidBin(format!(
+                "code {index} has more than the layout's {nbits} bits"
+            )));
+        }
+        Ok(family)
+    }
+
+    /// Load a family from a TOML config file and the `.bin` file of the same
+    /// name next to it, as written by `apriltag-gen generate`. Without a
+    /// `.bin` file the family has no codes.
+    ///
+    /// ```
+    /// use apriltag::family::TagFamily;
+    ///
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/families/tag16h5.toml");
+    /// let family = TagFamily::from_toml_file(path).unwrap();
+    /// assert_eq!(family.codes.len(), 30);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_toml_file(path: impl AsRef<std::path::Path>) -> Result<TagFamily, FamilyError> {
+        let read_error =
+            |path: &std::path::Path, e| FamilyError::Io(format!("{}: {e}", path.display()));
+        let toml_path = path.as_ref();
+        let toml_str = std::fs::read_to_string(toml_path).map_err(|e| read_error(toml_path, e))?;
+        let bin_path = toml_path.with_extension("bin");
+        let bin_data = match std::fs::read(&bin_path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(read_error(&bin_path, e)),
+        };
+        Self::from_toml_and_bin(&toml_str, &bin_data)
+    }
+
+    /// Encode the codes in the versioned binary format: a header holding
+    /// the bit count, minimum Hamming distance, code count and a CRC-32 of
+    /// the codes, followed by the codes as little-endian `u64`s.
+    ///
+    /// [`from_toml_and_bin`](Self::from_toml_and_bin) checks the header
+    /// against the config, so a `.bin` file paired with the wrong `.toml`
+    /// fails to load instead of decoding as the wrong family.
+    ///
+    /// ```
+    /// let family = apriltag::family::tag16h5();
+    /// let bin = family.to_bin();
+    /// assert_eq!(bin.len(), 24 + 8 * family.codes.len());
+    /// ```
+    pub fn to_bin(&self) -> Vec<u8> {
+        let codes = codes_to_le_bytes(&self.codes);
+        let mut out = Vec::with_capacity(BIN_HEADER_LEN + codes.len());
+        out.extend_from_slice(&BIN_MAGIC);
+        out.extend_from_slice(&(self.layout.nbits as u16).to_le_bytes());
+        out.extend_from_slice(&(self.config.min_hamming as u16).to_le_bytes());
+        out.extend_from_slice(&(self.codes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&crc32(&codes).to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&codes);
+        out
     }
 }
 
@@ -188,6 +268,103 @@ fn build_layout(config: &LayoutConfig) -> Result<Layout, LayoutError> {
     }
 }
 
+/// Start of a versioned `.bin` file. Read as a little-endian `u64` its top
+/// bit is set, so no code of a family up to 63 bits can be mistaken for it.
+const BIN_MAGIC: [u8; 8] = *b"ATFAM\x00\x02\xff";
+
+/// Bytes before the codes in a versioned `.bin` file.
+const BIN_HEADER_LEN: usize = 24;
+
+/// Header of a versioned `.bin` file; see [`TagFamily::to_bin`].
+#[cfg(feature = "serde")]
+struct BinHeader {
+    nbits: u16,
+    min_hamming: u16,
+    count: u32,
+    crc: u32,
+}
+
+#[cfg(feature = "serde")]
+impl BinHeader {
+    /// Check the header against the family built from the config.
+    fn check(&self, family: &TagFamily) -> Result<(), FamilyError> {
+        let mismatch = |what: &str, header: usize, config: usize| {
+            Err(FamilyError::InvalidBin(format!(
+                "header says {what} {header} but the config has {config}; \
+                 is this the .bin file of another family?"
+            )))
+        };
+        if self.nbits as usize != family.layout.nbits {
+            return mismatch("bits", self.nbits as usize, family.layout.nbits);
+        }
+        if self.min_hamming as u32 != family.config.min_hamming {
+            return mismatch(
+                "min_hamming",
+                self.min_hamming as usize,
+                family.config.min_hamming as usize,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `.bin` file in either format: a bare array of little-endian
+/// `u64` codes, or a [`BinHeader`] followed by one. The code count and
+/// checksum of a header are verified here.
+#[cfg(feature = "serde")]
+fn parse_bin(data: &[u8]) -> Result<(Option<BinHeader>, Vec<u64>), FamilyError> {
+    if !data.starts_with(&BIN_MAGIC) {
+        return Ok((None, parse_bin_codes(data)?));
+    }
+    if data.len() < BIN_HEADER_LEN {
+        return Err(FamilyError::InvalidBin(format!(
+            "header is truncated at {} bytes",
+            data.len()
+        )));
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let header = BinHeader {
+        nbits: u16_at(8),
+        min_hamming: u16_at(10),
+        count: u32_at(12),
+        crc: u32_at(16),
+    };
+    let body = &data[BIN_HEADER_LEN..];
+    let codes = parse_bin_codes(body)?;
+    if codes.len() != header.count as usize {
+        return Err(FamilyError::InvalidBin(format!(
+            "header says {} codes but the file holds {}",
+            header.count,
+            codes.len()
+        )));
+    }
+    let crc = crc32(body);
+    if crc != header.crc {
+        return Err(FamilyError::InvalidBin(format!(
+            "checksum {crc:08x} does not match the header's {:08x}",
+            header.crc
+        )));
+    }
+    Ok((Some(header), codes))
+}
+
+fn codes_to_le_bytes(codes: &[u64]) -> Vec<u8> {
+    codes.iter().flat_map(|code| code.to_le_bytes()).collect()
+}
+
+/// CRC-32 (IEEE 802.3, as in zlib and PNG).
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
 /// Parse a binary code file (flat array of little-endian u64).
 fn parse_bin_codes(data: &[u8]) -> Result<Vec<u64>, FamilyError> {
     if !data.len().is_multiple_of(8) {
@@ -206,6 +383,8 @@ pub enum FamilyError {
     Config(String),
     Layout(LayoutError),
     InvalidBin(String),
+    /// A family file could not be read.
+    Io(String),
 }
 
 impl fmt::Display for FamilyError {
@@ -215,6 +394,7 @@ impl fmt::Display for FamilyError {
             Self::Config(msg) => write!(f, "config error: {msg}"),
             Self::Layout(err) => write!(f, "layout error: {err}"),
             Self::InvalidBin(msg) => write!(f, "invalid binary data: {msg}"),
+            Self::Io(msg) => write!(f, "cannot read {msg}"),
         }
     }
 }
@@ -480,6 +660,14 @@ mod tests {
         assert!(builtin_family("nonexistent").is_none());
     }
 
+    #[test]
+    #[cfg(feature = "family-tag16h5")]
+    fn get_tag_checks_the_index() {
+        let f = tag16h5();
+        assert_eq!(f.get_tag(29).map(|t| t.code()), Some(f.codes[29]));
+        assert!(f.get_tag(30).is_none());
+    }
+
     #[test]
     fn parse_bin_codes_not_multiple_of_8() {
         let bad_data = &[0u8; 7]; // 7 bytes, not a multiple of 8
@@ -487,6 +675,13 @@ mod tests {
         assert!(matches!(result, Err(FamilyError::InvalidBin(_))));
     }
 
+    #[test]
+    fn family_id_display_honors_width() {
+        let id = FamilyId::new("tag16h5");
+        assert_eq!(format!("[{id:<10}]"), "[tag16h5   ]");
+        assert_eq!(format!("[{id:>9}]"), "[  tag16h5]");
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn from_toml_and_bin_invalid_toml() {
@@ -576,6 +771,63 @@ grid_size = 8
         assert_eq!(family.codes.len(), 2);
     }
 
+    #[cfg(all(feature = "serde", feature = "family-tag16h5"))]
+    #[test]
+    fn from_toml_file_reads_the_bin_alongside() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("families");
+        let family = TagFamily::from_toml_file(dir.join("tag16h5.toml")).unwrap();
+        assert_eq!(family.config.name, "tag16h5");
+        assert_eq!(family.codes, tag16h5().codes);
+
+        let err = TagFamily::from_toml_file(dir.join("missing.toml")).unwrap_err();
+        assert!(matches!(err, FamilyError::Io(_)), "{err}");
+        assert!(err.to_string().contains("missing.toml"), "{err}");
+    }
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[cfg(all(
+        feature = "serde",
+        feature = "family-tag16h5",
+        feature = "family-tag25h9"
+    ))]
+    #[test]
+    fn versioned_bin_round_trips_and_rejects_mismatches() {
+        let toml_16h5 = r#"
+name = "tag16h5"
+min_hamming = 5
+
+[layout]
+type = "classic"
+grid_size = 8
+"#;
+        let bin = tag16h5().to_bin();
+        let family = TagFamily::from_toml_and_bin(toml_16h5, &bin).unwrap();
+        assert_eq!(family.codes, tag16h5().codes);
+
+        // The bin of another family
+        let err = TagFamily::from_toml_and_bin(toml_16h5, &tag25h9().to_bin()).unwrap_err();
+        assert!(err.to_string().contains("header says bits 25"), "{err}");
+
+        // A flipped bit in the codes
+        let mut corrupt = bin.clone();
+        corrupt[BIN_HEADER_LEN] ^= 1;
+        let err = TagFamily::from_toml_and_bin(toml_16h5, &corrupt).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{err}");
+
+        // A truncated file
+        let err = TagFamily::from_toml_and_bin(toml_16h5, &bin[..bin.len() - 8]).unwrap_err();
+        assert!(err.to_string().contains("30 codes"), "{err}");
+
+        // Headerless codes wider than the layout
+        let wide = codes_to_le_bytes(&tag25h9().codes);
+        let err = TagFamily::from_toml_and_bin(toml_16h5, &wide).unwrap_err();
+        assert!(err.to_string().contains("16 bits"), "{err}");
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn from_toml_and_bin_invalid_bin() {
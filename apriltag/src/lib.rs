@@ -12,5 +12,12 @@ pub mod tag;
 pub mod types;
 
 // Re-export commonly used types at the crate root for ergonomic imports.
-pub use detect::detector::{Detection, Detector, DetectorBuffers, DetectorBuilder, DetectorConfig};
-pub use detect::image::{GrayImage, ImageRef, ImageU8};
+pub use detect::ablation::Ablation;
+pub use detect::decode::IdFilter;
+pub use detect::detector::{
+    DetectInfo, Detection, Detector, DetectorBuffers, DetectorBuilder, DetectorConfig,
+    PixelConvention, Polarity, SkipReason,
+};
+pub use detect::image::{GrayImage, ImageRef, ImageU16, ImageU8};
+pub use detect::preprocess::{DecimateMode, InterlaceField};
+pub use detect::quad::Quad;